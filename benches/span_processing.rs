@@ -7,8 +7,10 @@
 //! - Search: <1ms across 100K traces
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use std::time::{Duration, Instant, SystemTime};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
 use urpo_lib::core::{ServiceName, Span, SpanBuilder, SpanId, SpanKind, SpanStatus, TraceId};
 use urpo_lib::storage::{InMemoryStorage, StorageBackend};
 
@@ -164,6 +166,158 @@ fn bench_trace_query(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark exact attribute search via the inverted index against a full scan
+fn bench_attribute_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("attribute_search");
+    let rt = Runtime::new().unwrap();
+
+    let storage = InMemoryStorage::new(1_000_000);
+    let mut spans = generate_test_spans(100_000);
+    for (i, span) in spans.iter_mut().enumerate() {
+        span.attributes
+            .push("customer.id".into(), format!("customer-{:04}", i % 1000).into());
+    }
+
+    rt.block_on(async {
+        for span in spans {
+            storage.store_span(span).await.unwrap();
+        }
+    });
+
+    group.bench_function("indexed", |b| {
+        let storage_clone = storage.clone();
+        b.iter(|| {
+            rt.block_on(async {
+                let results = storage_clone
+                    .search_spans(black_box("customer-0042"), None, Some("customer.id"), 1000)
+                    .await
+                    .unwrap();
+                black_box(results);
+            });
+        });
+    });
+
+    group.bench_function("scan", |b| {
+        let storage_clone = storage.clone();
+        b.iter(|| {
+            rt.block_on(async {
+                let results = storage_clone
+                    .search_spans(black_box("customer-0042"), None, None, 1000)
+                    .await
+                    .unwrap();
+                black_box(results);
+            });
+        });
+    });
+
+    group.finish();
+}
+
+/// Benchmark time-bucketed trace listing on a 1M-span store
+/// against the full trace scan it replaced
+fn bench_time_range_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("time_range_query");
+    group.sample_size(20);
+    let rt = Runtime::new().unwrap();
+
+    // 1M spans (100k traces) spread over one hour
+    let storage = InMemoryStorage::new(10_000_000);
+    let base = SystemTime::now() - Duration::from_secs(3600);
+    let mut spans = generate_test_spans(1_000_000);
+    for (i, span) in spans.iter_mut().enumerate() {
+        span.start_time = base + Duration::from_micros(i as u64 * 3_600);
+    }
+    rt.block_on(async {
+        for span in spans {
+            storage.store_span(span).await.unwrap();
+        }
+    });
+
+    let nanos = |t: SystemTime| t.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+    let window_start = nanos(base + Duration::from_secs(1800));
+    let window_end = window_start + 60_000_000_000;
+
+    group.bench_function("recent_100", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let traces = storage.list_traces(None, None, None, 100).await.unwrap();
+                black_box(traces);
+            });
+        });
+    });
+
+    group.bench_function("one_minute_window", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let traces = storage
+                    .list_traces(None, Some(window_start), Some(window_end), 100)
+                    .await
+                    .unwrap();
+                black_box(traces);
+            });
+        });
+    });
+
+    // An empty query matches every trace, so this visits all of them
+    group.bench_function("full_scan_baseline", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let traces = storage.search_traces(black_box(""), 100).await.unwrap();
+                black_box(traces);
+            });
+        });
+    });
+
+    group.finish();
+}
+
+/// Benchmark the indexed time-range listing against the full trace scan
+/// 100k spans over one hour, one-minute window
+fn bench_time_range_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("time_range_index");
+    let rt = Runtime::new().unwrap();
+
+    let storage = InMemoryStorage::new(1_000_000);
+    let base = SystemTime::now() - Duration::from_secs(3600);
+    let mut spans = generate_test_spans(100_000);
+    for (i, span) in spans.iter_mut().enumerate() {
+        span.start_time = base + Duration::from_micros(i as u64 * 36_000);
+    }
+    rt.block_on(async {
+        for span in spans {
+            storage.store_span(span).await.unwrap();
+        }
+    });
+
+    let nanos = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+    let window_start = nanos(base + Duration::from_secs(1800));
+    let window_end = window_start + Duration::from_secs(60).as_nanos() as u64;
+
+    // The path list_traces took before the index: every stored trace
+    group.bench_function("full_scan", |b| {
+        b.iter(|| {
+            let traces = storage
+                .scan_traces(None, black_box(Some(window_start)), Some(window_end), 1000)
+                .unwrap();
+            black_box(traces);
+        });
+    });
+
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let traces = storage
+                    .list_traces(None, black_box(Some(window_start)), Some(window_end), 1000)
+                    .await
+                    .unwrap();
+                black_box(traces);
+            });
+        });
+    });
+
+    group.finish();
+}
+
 /// Benchmark memory usage
 /// TARGET: <100MB for 1M spans
 fn bench_memory_usage(c: &mut Criterion) {
@@ -335,6 +489,94 @@ fn get_memory_usage() -> usize {
     100_000_000 // 100MB default
 }
 
+/// Benchmark storing 10k spans one by one against a single bulk insert
+fn bench_bulk_ingestion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_ingestion");
+    let size = 10_000;
+    group.throughput(Throughput::Elements(size as u64));
+    let rt = Runtime::new().unwrap();
+    let spans = generate_test_spans(size);
+
+    group.bench_function("single_10000spans", |b| {
+        b.iter_custom(|iters| {
+            let mut total_duration = Duration::ZERO;
+            for _ in 0..iters {
+                let storage = InMemoryStorage::new(1_000_000);
+                let batch = spans.clone();
+                let start = Instant::now();
+                rt.block_on(async {
+                    for span in batch {
+                        storage.store_span(black_box(span)).await.unwrap();
+                    }
+                });
+                total_duration += start.elapsed();
+            }
+            total_duration
+        });
+    });
+
+    group.bench_function("bulk_10000spans", |b| {
+        b.iter_custom(|iters| {
+            let mut total_duration = Duration::ZERO;
+            for _ in 0..iters {
+                let storage = InMemoryStorage::new(1_000_000);
+                let batch = spans.clone();
+                let start = Instant::now();
+                rt.block_on(async {
+                    storage.store_spans_bulk(black_box(batch)).await.unwrap();
+                });
+                total_duration += start.elapsed();
+            }
+            total_duration
+        });
+    });
+
+    // Through the shared lock the receiver holds: one write lock per span or per batch
+    group.bench_function("locked_single_10000spans", |b| {
+        b.iter_custom(|iters| {
+            let mut total_duration = Duration::ZERO;
+            for _ in 0..iters {
+                let storage: Arc<RwLock<dyn StorageBackend>> =
+                    Arc::new(RwLock::new(InMemoryStorage::new(1_000_000)));
+                let batch = spans.clone();
+                let start = Instant::now();
+                rt.block_on(async {
+                    for span in batch {
+                        storage
+                            .write()
+                            .await
+                            .store_span(black_box(span))
+                            .await
+                            .unwrap();
+                    }
+                });
+                total_duration += start.elapsed();
+            }
+            total_duration
+        });
+    });
+
+    group.bench_function("locked_bulk_10000spans", |b| {
+        b.iter_custom(|iters| {
+            let mut total_duration = Duration::ZERO;
+            for _ in 0..iters {
+                let storage: Arc<RwLock<dyn StorageBackend>> =
+                    Arc::new(RwLock::new(InMemoryStorage::new(1_000_000)));
+                let batch = spans.clone();
+                let start = Instant::now();
+                rt.block_on(async {
+                    let storage = storage.write().await;
+                    storage.store_spans_bulk(black_box(batch)).await.unwrap();
+                });
+                total_duration += start.elapsed();
+            }
+            total_duration
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default()
@@ -343,7 +585,11 @@ criterion_group! {
         .measurement_time(Duration::from_secs(10))
         .warm_up_time(Duration::from_secs(3));
     targets = bench_span_ingestion,
+              bench_bulk_ingestion,
               bench_trace_query,
+              bench_attribute_search,
+              bench_time_range_query,
+              bench_time_range_index,
               bench_memory_usage,
               bench_startup_time,
               bench_service_aggregation,
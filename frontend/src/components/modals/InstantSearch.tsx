@@ -30,6 +30,7 @@ const InstantSearchImpl = ({ onTraceSelect }: SearchProps) => {
   const [isSearching, setIsSearching] = useState(false);
   const [selectedIndex, setSelectedIndex] = useState(0);
   const [searchTime, setSearchTime] = useState<number>(0);
+  const [suggestions, setSuggestions] = useState<string[]>([]);
   const [filters, setFilters] = useState({
     service: '',
     errorOnly: false,
@@ -63,6 +64,10 @@ const InstantSearchImpl = ({ onTraceSelect }: SearchProps) => {
       setSearchTime(searchDuration);
       setResults(searchResults);
       setSelectedIndex(0);
+
+      if (searchQuery) {
+        invoke('record_search_history', { entry: searchQuery }).catch(() => {});
+      }
     } catch (error) {
       console.error('Search failed:', error);
       setResults([]);
@@ -76,6 +81,13 @@ const InstantSearchImpl = ({ onTraceSelect }: SearchProps) => {
     performSearch(debouncedQuery);
   }, [debouncedQuery, filters, performSearch]);
 
+  // Suggestions from the shared search history
+  useEffect(() => {
+    invoke<string[]>('get_search_history', { query: debouncedQuery || null, limit: 8 })
+      .then(setSuggestions)
+      .catch(() => setSuggestions([]));
+  }, [debouncedQuery]);
+
   // Keyboard navigation
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -130,11 +142,17 @@ const InstantSearchImpl = ({ onTraceSelect }: SearchProps) => {
               value={query}
               onChange={(e) => setQuery(e.target.value)}
               placeholder="Search traces... (⌘K)"
+              list="search-history"
               className="w-full px-4 py-2 pl-10 bg-gray-800 text-white rounded-lg 
                        border border-surface-400 focus:border-text-700 focus:outline-none
                        placeholder-gray-500"
               autoFocus
             />
+            <datalist id="search-history">
+              {suggestions.map(entry => (
+                <option key={entry} value={entry} />
+              ))}
+            </datalist>
             <svg className="absolute left-3 top-2.5 w-5 h-5 text-gray-500" fill="none" stroke="currentColor" viewBox="0 0 24 24">
               <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M21 21l-6-6m2-5a7 7 0 11-14 0 7 7 0 0114 0z" />
             </svg>
@@ -12,6 +12,7 @@ interface SpanDetails {
   duration: number;
   status: string;
   attributes: Record<string, string>;
+  resource_attributes?: Record<string, string>;
   events: Array<{
     time: number;
     name: string;
@@ -296,6 +297,21 @@ const SpanDetailsViewImpl = ({
               </div>
             )}
 
+            {/* Resource (k8s / cloud) */}
+            {selectedSpan.resource_attributes && Object.keys(selectedSpan.resource_attributes).length > 0 && (
+              <div>
+                <h4 className="text-sm font-bold text-gray-400 mb-2">RESOURCE</h4>
+                <div className="bg-gray-900 p-3 rounded-none space-y-1 text-xs font-mono">
+                  {Object.entries(selectedSpan.resource_attributes).map(([key, value]) => (
+                    <div key={key} className="flex">
+                      <span className="text-gray-500 w-1/3">{key}:</span>
+                      <span className="text-white flex-1 break-all">{value}</span>
+                    </div>
+                  ))}
+                </div>
+              </div>
+            )}
+
             {/* Events */}
             {selectedSpan.events && selectedSpan.events.length > 0 && (
               <div>
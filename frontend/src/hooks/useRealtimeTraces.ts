@@ -11,6 +11,8 @@ interface TraceEvent {
   service_name: string;
   span_count: number;
   timestamp: number;
+  kind: 'new' | 'update';
+  has_error: boolean;
 }
 
 /**
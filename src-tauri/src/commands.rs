@@ -235,6 +235,37 @@ pub async fn search_traces(
     })
 }
 
+/// Recent search inputs, fuzzy-filtered by `query` when given
+#[tauri::command]
+pub async fn get_search_history(
+    state: State<'_, AppState>,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<String>, String> {
+    timed_command!("get_search_history", {
+        let history = state.search_history.lock().map_err(|e| e.to_string())?;
+        let limit = limit.unwrap_or(urpo_lib::query::history::MAX_HISTORY_ENTRIES);
+        let entries = match query {
+            Some(ref query) => history.search(query, limit),
+            None => history.entries().take(limit).collect(),
+        };
+        Ok(entries.into_iter().map(str::to_string).collect())
+    })
+}
+
+/// Remember a search input for later suggestions
+#[tauri::command]
+pub async fn record_search_history(
+    state: State<'_, AppState>,
+    entry: String,
+) -> Result<(), String> {
+    timed_command!("record_search_history", {
+        let mut history = state.search_history.lock().map_err(|e| e.to_string())?;
+        history.record(&entry);
+        Ok(())
+    })
+}
+
 #[tauri::command]
 #[inline]
 pub async fn get_storage_info(state: State<'_, AppState>) -> Result<StorageInfo, String> {
@@ -273,15 +304,8 @@ pub async fn start_receiver(state: State<'_, AppState>) -> Result<bool, String>
             );
 
             // Start receiver in background - BLAZING FAST
-            let receiver_arc = Arc::new(receiver.clone()); // Note: This clone is necessary as receiver is OtelReceiver
-            tokio::spawn(async move {
-                tracing::info!("Starting OTLP receiver on ports 4317/4318");
-                if let Err(e) = receiver_arc.run().await {
-                    tracing::error!("OTLP receiver error: {}", e);
-                }
-            });
-
-            *receiver_guard = Some(receiver);
+            tracing::info!("Starting OTLP receiver on ports 4327/4328");
+            *receiver_guard = Some(Arc::new(receiver).spawn());
             Ok(true) // Started
         } else {
             Ok(false) // Already running
@@ -303,8 +327,10 @@ pub async fn stop_receiver(state: State<'_, AppState>) -> Result<(), String> {
     timed_command!("stop_receiver", {
         let mut receiver_guard = state.receiver.write().await;
 
-        // Simply drop the receiver to stop it
-        *receiver_guard = None;
+        // Drain in-flight requests and pending batches instead of aborting mid-write
+        if let Some(handle) = receiver_guard.take() {
+            handle.shutdown().await.map_err(|e| e.to_string())?;
+        }
 
         Ok(())
     })
@@ -314,9 +340,9 @@ pub async fn stop_receiver(state: State<'_, AppState>) -> Result<(), String> {
 #[inline]
 pub async fn trigger_tier_migration(state: State<'_, AppState>) -> Result<String, String> {
     timed_command!("trigger_tier_migration", {
-        let storage = state.storage.write().await;
-        let removed = map_err_str!(storage.emergency_cleanup().await)?;
-        Ok(format!("Migrated {} spans to cold storage", removed))
+        let storage = state.storage.read().await;
+        let migrated = map_err_str!(storage.migrate_cold_spans().await)?;
+        Ok(format!("Migrated {} spans to warm and cold storage", migrated))
     })
 }
 
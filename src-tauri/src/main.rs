@@ -92,16 +92,9 @@ async fn init_app_state() -> (AppState, tokio::sync::broadcast::Receiver<urpo_li
     // Enable real-time event broadcasting
     let (otel_receiver, mut event_rx) = otel_receiver.with_events();
 
-    let receiver = Arc::new(RwLock::new(Some(otel_receiver.clone())));
-
     // Start receiver in background - ZERO BLOCKING
-    let receiver_arc = Arc::new(otel_receiver);
-    tokio::spawn(async move {
-        tracing::info!("🚀 Auto-starting OTLP receiver on ports 4327 (gRPC) and 4328 (HTTP)");
-        if let Err(e) = receiver_arc.run().await {
-            tracing::error!("OTLP receiver error: {}", e);
-        }
-    });
+    tracing::info!("🚀 Auto-starting OTLP receiver on ports 4327 (gRPC) and 4328 (HTTP)");
+    let receiver = Arc::new(RwLock::new(Some(Arc::new(otel_receiver).spawn())));
 
     (
         AppState {
@@ -110,6 +103,11 @@ async fn init_app_state() -> (AppState, tokio::sync::broadcast::Receiver<urpo_li
             monitor,
             metrics_storage,
             logs_storage,
+            search_history: Arc::new(std::sync::Mutex::new(
+                urpo_lib::query::history::default_history_path()
+                    .map(urpo_lib::query::SearchHistory::load)
+                    .unwrap_or_else(urpo_lib::query::SearchHistory::in_memory),
+            )),
         },
         event_rx,
     )
@@ -176,6 +174,8 @@ async fn main() {
             commands::get_error_traces,
             commands::get_trace_spans,
             commands::search_traces,
+            commands::get_search_history,
+            commands::record_search_history,
             commands::get_storage_info,
             commands::start_receiver,
             commands::stop_receiver,
@@ -206,6 +206,19 @@ async fn main() {
                 tracing::warn!("⚠️ Startup time {}ms exceeds 200ms target!", startup_ms);
             }
 
+            // Write search history held back by the debounce
+            let history = Arc::clone(&app.state::<AppState>().search_history);
+            tokio::spawn(async move {
+                let mut ticker =
+                    tokio::time::interval(urpo_lib::query::history::HISTORY_WRITE_DEBOUNCE);
+                loop {
+                    ticker.tick().await;
+                    if let Ok(mut history) = history.lock() {
+                        history.flush_if_due();
+                    }
+                }
+            });
+
             // Spawn task to broadcast trace events to frontend
             let app_handle = app.handle();
             tokio::spawn(async move {
@@ -220,6 +233,14 @@ async fn main() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // AppState is never dropped on exit, so write the history here
+            if let tauri::RunEvent::Exit = event {
+                if let Ok(mut history) = app_handle.state::<AppState>().search_history.lock() {
+                    history.flush();
+                }
+            }
+        });
 }
@@ -3,16 +3,17 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use urpo_lib::{monitoring::Monitor, receiver::OtelReceiver, storage::StorageBackend};
+use urpo_lib::{monitoring::Monitor, receiver::ShutdownHandle, storage::StorageBackend};
 
 /// Application state shared across Tauri commands
 /// PERFORMANCE: Uses RwLock for concurrent reads, exclusive writes
 pub struct AppState {
     pub storage: Arc<RwLock<dyn StorageBackend>>,
-    pub receiver: Arc<RwLock<Option<OtelReceiver>>>,
+    pub receiver: Arc<RwLock<Option<ShutdownHandle>>>,
     pub monitor: Arc<Monitor>,
     pub metrics_storage: Option<Arc<tokio::sync::Mutex<urpo_lib::metrics::MetricStorage>>>,
     pub logs_storage: Option<Arc<tokio::sync::Mutex<urpo_lib::logs::LogStorage>>>,
+    pub search_history: Arc<std::sync::Mutex<urpo_lib::query::SearchHistory>>,
 }
 
 /// Service metrics for frontend display
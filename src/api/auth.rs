@@ -0,0 +1,150 @@
+//! API key authentication for the `/api/*` routes.
+//!
+//! When keys are configured, requests must carry one of them either as
+//! `Authorization: Bearer <key>` or in the `X-API-Key` header. Keys are
+//! compared in constant time so response timing does not leak them.
+
+use super::ErrorResponse;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::Arc;
+
+/// Header carrying an API key as an alternative to a bearer token.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Compare two byte strings without short-circuiting on the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Whether `candidate` matches any of `keys`. Every key is checked, so the
+/// time taken does not depend on which key matched.
+fn is_valid_key(keys: &[String], candidate: &str) -> bool {
+    keys.iter().fold(false, |valid, key| {
+        valid | constant_time_eq(key.as_bytes(), candidate.as_bytes())
+    })
+}
+
+/// The key presented by a request, from a bearer token or `X-API-Key`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| {
+        headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+    })
+}
+
+/// Reject requests without a valid API key with 401.
+pub(super) async fn require_api_key(
+    State(keys): State<Arc<[String]>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match presented_key(request.headers()) {
+        Some(key) if is_valid_key(&keys, key.trim()) => next.run(request).await,
+        presented => {
+            let error = if presented.is_some() {
+                "Invalid API key"
+            } else {
+                "Missing API key"
+            };
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(ErrorResponse {
+                    error: error.to_string(),
+                    code: 401,
+                }),
+            )
+                .into_response()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{create_router, ApiConfig, DebugContext};
+    use super::*;
+    use crate::storage::{InMemoryStorage, StorageBackend};
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn app(api_keys: Vec<String>) -> axum::Router {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(100)));
+        let config = ApiConfig {
+            api_keys,
+            ..ApiConfig::default()
+        };
+        create_router(storage, config, DebugContext::default())
+    }
+
+    async fn status(app: &axum::Router, uri: &str, headers: &[(&str, &str)]) -> StatusCode {
+        let mut request = Request::get(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request.body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+
+        let keys = vec!["first".to_string(), "second".to_string()];
+        assert!(is_valid_key(&keys, "second"));
+        assert!(!is_valid_key(&keys, "third"));
+        assert!(!is_valid_key(&[], ""));
+    }
+
+    #[tokio::test]
+    async fn test_api_key_auth() {
+        let app = app(vec!["k1".to_string(), "k2".to_string()]);
+
+        assert_eq!(status(&app, "/api/services", &[]).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(&app, "/api/services", &[("authorization", "Bearer wrong")]).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/api/services", &[("authorization", "Basic k1")]).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/api/services", &[("authorization", "Bearer k1")]).await,
+            StatusCode::OK
+        );
+        assert_eq!(status(&app, "/api/services", &[("x-api-key", "k2")]).await, StatusCode::OK);
+
+        // Health checks and scraping stay public
+        assert_eq!(status(&app, "/health", &[]).await, StatusCode::OK);
+        assert_eq!(status(&app, "/metrics", &[]).await, StatusCode::OK);
+
+        let response = app
+            .oneshot(Request::get("/api/traces").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn test_no_keys_means_open_api() {
+        let app = app(Vec::new());
+        assert_eq!(status(&app, "/api/services", &[]).await, StatusCode::OK);
+    }
+}
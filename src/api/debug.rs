@@ -0,0 +1,376 @@
+//! Diagnostic bundle for support escalations.
+//!
+//! `GET /api/debug/dump` returns a JSON snapshot of memory-relevant state:
+//! storage stats, per-service and largest-trace footprints, pool and intern
+//! table sizes, and the running configuration with secrets redacted.
+//!
+//! `POST`/`DELETE /api/debug/capture` toggle the receiver's wire capture and
+//! `GET /api/debug/captures` returns what it recorded.
+
+use super::{ApiState, ErrorResponse};
+use crate::core::string_intern::{self, InternStats};
+use crate::core::{Config, Result};
+use crate::receiver::capture::{
+    CaptureStatus, CapturedRequest, WireCapture, DEFAULT_CAPTURE_WINDOW,
+};
+use crate::storage::{
+    InMemoryStorage, PoolStats, ServiceFootprint, StorageBackend, StorageStats, TraceFootprint,
+    ZeroAllocSpanPool,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Number of largest traces included in a dump.
+pub const DEFAULT_TOP_TRACES: usize = 100;
+
+/// Replacement for redacted config values.
+const REDACTED: &str = "[REDACTED]";
+
+/// Config keys containing any of these are treated as secrets.
+const SECRET_KEY_HINTS: &[&str] = &[
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "credential",
+    "authorization",
+];
+
+/// Runtime state outside storage that the dump reports on.
+#[derive(Clone, Default)]
+pub struct DebugContext {
+    /// Running configuration
+    pub config: Option<Arc<Config>>,
+    /// Receiver span pool
+    pub span_pool: Option<Arc<ZeroAllocSpanPool>>,
+    /// Receiver wire capture
+    pub capture: Option<Arc<WireCapture>>,
+}
+
+/// JSON diagnostic bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugDump {
+    /// Urpo version that produced the dump
+    pub version: String,
+    /// When the dump was taken
+    pub generated_at: SystemTime,
+    /// Storage statistics
+    pub storage: StorageStats,
+    /// Span counts and byte estimates per service, largest first
+    pub services: Vec<ServiceFootprint>,
+    /// Largest traces by estimated size
+    pub largest_traces: Vec<TraceFootprint>,
+    /// Receiver span pool statistics
+    pub span_pool: Option<PoolStats>,
+    /// Global string intern table size
+    pub intern: InternStats,
+    /// Running configuration with secrets redacted
+    pub config: Option<serde_json::Value>,
+}
+
+impl DebugDump {
+    /// Collect a dump from storage and the runtime context.
+    ///
+    /// Each section takes its own short read lock, so ingestion is never
+    /// blocked for the whole collection.
+    pub async fn collect(
+        storage: &Arc<tokio::sync::RwLock<dyn StorageBackend>>,
+        context: &DebugContext,
+    ) -> Result<Self> {
+        let storage_stats = storage.read().await.get_storage_stats().await?;
+
+        let (services, largest_traces) = {
+            let guard = storage.read().await;
+            match guard.as_any().downcast_ref::<InMemoryStorage>() {
+                Some(memory) => memory.memory_footprint(DEFAULT_TOP_TRACES),
+                None => (Vec::new(), Vec::new()),
+            }
+        };
+
+        let config = context.config.as_deref().map(redacted_config).transpose()?;
+
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: SystemTime::now(),
+            storage: storage_stats,
+            services,
+            largest_traces,
+            span_pool: context.span_pool.as_ref().map(|pool| pool.stats()),
+            intern: string_intern::global_stats(),
+            config,
+        })
+    }
+}
+
+/// Serialize a config to JSON with secret values replaced.
+pub fn redacted_config(config: &Config) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(config)?;
+    redact_secrets(&mut value);
+    Ok(value)
+}
+
+/// Replace the values of secret-looking keys, recursively.
+///
+/// Unset (`null`) secrets are left as-is so the dump still shows they are off.
+pub fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) {
+                    if !value.is_null() {
+                        *value = serde_json::Value::String(REDACTED.to_string());
+                    }
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        },
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {},
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+/// GET /api/debug/dump - Diagnostic bundle for support escalations
+pub(super) async fn debug_dump_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    match DebugDump::collect(&state.storage, &state.debug).await {
+        Ok(dump) => Json(dump).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to collect debug dump: {}", e),
+                code: 500,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Query parameters for starting a wire capture.
+#[derive(Debug, Deserialize)]
+pub(super) struct CaptureQuery {
+    /// Capture window in seconds
+    seconds: Option<u64>,
+}
+
+/// Wire capture state and recorded requests.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureResponse {
+    /// Session state
+    pub status: CaptureStatus,
+    /// Recorded requests, oldest first
+    pub captures: Vec<CapturedRequest>,
+}
+
+fn capture_not_enabled() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "Wire capture is not enabled on this server".to_string(),
+            code: 404,
+        }),
+    )
+        .into_response()
+}
+
+/// POST /api/debug/capture?seconds=N - Start capturing raw OTLP requests
+pub(super) async fn start_capture_handler(
+    State(state): State<ApiState>,
+    Query(query): Query<CaptureQuery>,
+) -> impl IntoResponse {
+    let Some(ref capture) = state.debug.capture else {
+        return capture_not_enabled();
+    };
+    let window = query
+        .seconds
+        .map_or(DEFAULT_CAPTURE_WINDOW, Duration::from_secs);
+    Json(capture.start(window)).into_response()
+}
+
+/// DELETE /api/debug/capture - Stop capturing
+pub(super) async fn stop_capture_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    match state.debug.capture {
+        Some(ref capture) => Json(capture.stop()).into_response(),
+        None => capture_not_enabled(),
+    }
+}
+
+/// GET /api/debug/captures - Captured requests with per-span outcomes
+pub(super) async fn list_captures_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    match state.debug.capture {
+        Some(ref capture) => Json(CaptureResponse {
+            status: capture.status(),
+            captures: capture.captures(),
+        })
+        .into_response(),
+        None => capture_not_enabled(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_router, ApiConfig};
+    use crate::core::{ServiceName, Span, SpanId, TraceId};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::json;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_redact_secrets() {
+        let mut value = json!({
+            "server": { "grpc_port": 4317 },
+            "remote_write": {
+                "endpoint": "http://prometheus:9090/api/v1/write",
+                "bearer_token": "s3cr3t",
+                "auth": { "username": "urpo", "password": "hunter2" },
+            },
+            "exporters": [{ "name": "otlp", "api_key": "abc123" }],
+            "client_secret": null,
+        });
+
+        redact_secrets(&mut value);
+
+        assert_eq!(value["server"]["grpc_port"], 4317);
+        assert_eq!(value["remote_write"]["endpoint"], "http://prometheus:9090/api/v1/write");
+        assert_eq!(value["remote_write"]["bearer_token"], REDACTED);
+        assert_eq!(value["remote_write"]["auth"]["username"], "urpo");
+        assert_eq!(value["remote_write"]["auth"]["password"], REDACTED);
+        assert_eq!(value["exporters"][0]["api_key"], REDACTED);
+        assert!(value["client_secret"].is_null());
+        assert!(!value.to_string().contains("s3cr3t"));
+        assert!(!value.to_string().contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_debug_dump_endpoint() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for i in 0..3 {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("trace_{}", i % 2)).unwrap())
+                .span_id(SpanId::new(format!("span_{}", i)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(10))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let context = DebugContext {
+            config: Some(Arc::new(Config::default())),
+            span_pool: Some(Arc::new(ZeroAllocSpanPool::new(16))),
+            capture: None,
+        };
+        let app = create_router(storage, ApiConfig::default(), context);
+
+        let response = app
+            .oneshot(Request::get("/api/debug/dump").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let dump: DebugDump = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(dump.storage.span_count, 3);
+        assert_eq!(dump.services.len(), 1);
+        assert_eq!(dump.services[0].span_count, 3);
+        assert!(dump.services[0].estimated_bytes > 0);
+        assert_eq!(dump.largest_traces.len(), 2);
+        assert_eq!(dump.largest_traces[0].span_count, 2);
+        assert_eq!(dump.span_pool.unwrap().capacity, 16);
+        assert_eq!(dump.config.unwrap()["server"]["grpc_port"], 4317);
+    }
+
+    #[tokio::test]
+    async fn test_wire_capture_endpoints() {
+        use crate::receiver::{http::create_http_router, OtelReceiver};
+
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        let capture = Arc::new(WireCapture::new());
+        let receiver = Arc::new(
+            OtelReceiver::new(
+                0,
+                0,
+                Arc::clone(&storage),
+                Arc::new(crate::monitoring::Monitor::new()),
+            )
+            .with_wire_capture(Arc::clone(&capture)),
+        );
+        let context = DebugContext {
+            capture: Some(capture),
+            ..Default::default()
+        };
+        let api = create_router(storage, ApiConfig::default(), context);
+        let otlp = create_http_router(receiver);
+        let export = || {
+            Request::post("/v1/traces")
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer s3cr3t")
+                .body(Body::from(r#"{"resourceSpans":[]}"#))
+                .unwrap()
+        };
+
+        otlp.clone().oneshot(export()).await.unwrap();
+        let response = api
+            .clone()
+            .oneshot(
+                Request::post("/api/debug/capture?seconds=30")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        otlp.clone().oneshot(export()).await.unwrap();
+
+        let response = api
+            .clone()
+            .oneshot(
+                Request::get("/api/debug/captures")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let captures: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(captures["status"]["active"], true);
+        assert_eq!(captures["captures"].as_array().unwrap().len(), 1);
+        assert_eq!(captures["captures"][0]["protocol"], "otlp/http");
+        assert_eq!(captures["captures"][0]["headers"]["authorization"], REDACTED);
+        assert_eq!(captures["captures"][0]["payload"], r#"{"resourceSpans":[]}"#);
+        assert!(!String::from_utf8_lossy(&body).contains("s3cr3t"));
+
+        let response = api
+            .oneshot(
+                Request::delete("/api/debug/capture")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["active"], false);
+        assert_eq!(status["captured"], 1);
+    }
+}
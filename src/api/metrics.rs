@@ -0,0 +1,270 @@
+//! Urpo's own operational metrics for Prometheus.
+//!
+//! `GET /metrics` renders storage and receiver counters in the Prometheus
+//! text exposition format, so Urpo can be scraped like any other service.
+
+use super::{ApiState, ErrorResponse};
+use crate::receiver::ReceiverStatsSnapshot;
+use crate::storage::StorageStats;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+};
+use std::fmt::Write;
+
+/// Content type of the Prometheus text format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus text format output, one metric family at a time.
+#[derive(Debug, Default)]
+struct MetricsText {
+    out: String,
+}
+
+impl MetricsText {
+    /// Start a metric family of `kind` (`counter` or `gauge`).
+    fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        self
+    }
+
+    /// Add a sample to the current family.
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, value))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+        self
+    }
+
+    /// Add a family with a single unlabelled sample.
+    fn single(&mut self, name: &str, kind: &str, help: &str, value: f64) -> &mut Self {
+        self.family(name, kind, help).sample(name, &[], value)
+    }
+}
+
+/// Render storage and receiver counters in the Prometheus text format.
+pub fn render(storage: &StorageStats, receiver: Option<&ReceiverStatsSnapshot>) -> String {
+    let mut text = MetricsText::default();
+    text.single(
+        "urpo_spans_processed_total",
+        "counter",
+        "Spans received by storage, stored or not.",
+        storage.spans_processed as f64,
+    )
+    .single(
+        "urpo_spans_evicted_total",
+        "counter",
+        "Spans evicted to stay within storage limits.",
+        storage.spans_evicted as f64,
+    )
+    .single(
+        "urpo_spans_rejected_total",
+        "counter",
+        "Spans rejected because storage was at capacity.",
+        storage.rejected_spans as f64,
+    )
+    .single(
+        "urpo_storage_cleanups_total",
+        "counter",
+        "Storage cleanup operations performed.",
+        storage.cleanup_count as f64,
+    )
+    .single("urpo_spans", "gauge", "Spans currently stored.", storage.span_count as f64)
+    .single(
+        "urpo_warm_spans",
+        "gauge",
+        "Spans compressed into the warm tier.",
+        storage.warm_span_count as f64,
+    )
+    .single(
+        "urpo_warm_memory_saved_bytes",
+        "gauge",
+        "Estimated bytes saved by compressing the warm tier.",
+        storage.warm_memory_saved_bytes as f64,
+    )
+    .single("urpo_traces", "gauge", "Traces currently stored.", storage.trace_count as f64)
+    .single(
+        "urpo_services",
+        "gauge",
+        "Services currently stored.",
+        storage.service_count as f64,
+    )
+    .single(
+        "urpo_memory_bytes",
+        "gauge",
+        "Estimated memory used by stored spans.",
+        storage.memory_bytes as f64,
+    )
+    .single(
+        "urpo_memory_pressure_ratio",
+        "gauge",
+        "Memory used relative to the configured limit.",
+        storage.memory_pressure,
+    )
+    .single(
+        "urpo_span_processing_rate",
+        "gauge",
+        "Spans processed per second since start.",
+        storage.processing_rate,
+    )
+    .single(
+        "urpo_processing_error_ratio",
+        "gauge",
+        "Share of processed spans that failed.",
+        storage.error_rate,
+    )
+    .single(
+        "urpo_uptime_seconds",
+        "gauge",
+        "Seconds since storage started.",
+        storage.uptime_seconds as f64,
+    );
+
+    if let Some(receiver) = receiver {
+        text.family(
+            "urpo_receiver_spans_dropped_total",
+            "counter",
+            "Spans the receiver dropped before storage, by reason.",
+        )
+        .sample(
+            "urpo_receiver_spans_dropped_total",
+            &[("reason", "excluded")],
+            receiver.excluded_spans as f64,
+        )
+        .sample(
+            "urpo_receiver_spans_dropped_total",
+            &[("reason", "rate_limited")],
+            receiver.rate_limited_spans as f64,
+        )
+        .sample(
+            "urpo_receiver_spans_dropped_total",
+            &[("reason", "capped")],
+            receiver.capped_spans as f64,
+        )
+        .single(
+            "urpo_receiver_failed_exports_total",
+            "counter",
+            "Export requests the receiver failed.",
+            receiver.failed_exports as f64,
+        );
+    }
+    text.out
+}
+
+/// GET /metrics - Urpo's own metrics in the Prometheus text format
+pub(super) async fn metrics_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    let storage = match state.storage.read().await.get_storage_stats().await {
+        Ok(storage_stats) => storage_stats,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to get storage stats: {}", e),
+                    code: 500,
+                }),
+            )
+                .into_response();
+        },
+    };
+    let receiver = state
+        .config
+        .receiver_stats
+        .as_ref()
+        .map(|stats| stats.snapshot());
+
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        render(&storage, receiver.as_ref()),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{create_router, ApiConfig, DebugContext};
+    use super::*;
+    use crate::core::{ServiceName, Span, SpanId, TraceId};
+    use crate::receiver::ReceiverStats;
+    use crate::storage::{InMemoryStorage, StorageBackend};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use tower::ServiceExt;
+
+    /// Parse the text format into samples keyed by name and labels,
+    /// checking every sample belongs to a declared family.
+    fn parse(text: &str) -> HashMap<String, f64> {
+        let mut families = HashSet::new();
+        let mut samples = HashMap::new();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some("HELP"), Some(_), Some(_)) => {},
+                    (Some("TYPE"), Some(name), Some("counter" | "gauge")) => {
+                        families.insert(name.to_string());
+                    },
+                    _ => panic!("malformed comment: {}", line),
+                }
+                continue;
+            }
+            let (series, value) = line.rsplit_once(' ').expect("sample has a value");
+            let name = series.split('{').next().unwrap();
+            assert!(families.contains(name), "undeclared metric: {}", name);
+            samples.insert(series.to_string(), value.parse::<f64>().expect("numeric value"));
+        }
+        samples
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for i in 0..3 {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("trace_{}", i)).unwrap())
+                .span_id(SpanId::new(format!("span_{}", i)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(10))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+        let config = ApiConfig {
+            receiver_stats: Some(Arc::new(ReceiverStats::default())),
+            ..ApiConfig::default()
+        };
+        let app = create_router(storage, config, DebugContext::default());
+
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROMETHEUS_CONTENT_TYPE);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let samples = parse(std::str::from_utf8(&body).unwrap());
+        assert_eq!(samples["urpo_spans_processed_total"], 3.0);
+        assert_eq!(samples["urpo_spans"], 3.0);
+        assert_eq!(samples["urpo_warm_spans"], 0.0);
+        assert_eq!(samples["urpo_spans_evicted_total"], 0.0);
+        assert!(samples["urpo_memory_pressure_ratio"] > 0.0);
+        assert!(samples.contains_key("urpo_span_processing_rate"));
+        assert_eq!(samples["urpo_receiver_spans_dropped_total{reason=\"rate_limited\"}"], 0.0);
+        assert_eq!(samples["urpo_receiver_spans_dropped_total{reason=\"capped\"}"], 0.0);
+        assert_eq!(samples["urpo_receiver_failed_exports_total"], 0.0);
+    }
+}
@@ -3,20 +3,34 @@
 //! This module provides a lightweight HTTP API with 5 essential endpoints
 //! for compatibility with external tools like dashboards and alert systems.
 
-use crate::core::{Result, UrpoError};
+pub mod auth;
+pub mod debug;
+pub mod metrics;
+
+pub use debug::{DebugContext, DebugDump};
+
+use crate::core::{
+    operation_apdex, RateLimitKey, RateLimits, Result, ServiceName, Span, SpanTree, TraceId,
+    UrpoError,
+};
 use crate::export::{ExportFormat, ExportOptions, TraceExporter};
 use crate::query::QueryEngine;
+use crate::receiver::rate_limit::{RateLimiter, RateLimiterStats};
+use crate::receiver::ReceiverStats;
+use crate::sampling::SharedServiceRates;
 use crate::service_map::ServiceMapBuilder;
-use crate::storage::{StorageBackend, UnifiedStorage};
+use crate::storage::{StorageBackend, TraceInfo, UnifiedStorage};
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
@@ -26,18 +40,39 @@ use tower_http::cors::CorsLayer;
 pub struct ApiConfig {
     /// Port to listen on (default: 8080)
     pub port: u16,
+    /// Address to listen on (default: 127.0.0.1)
+    pub bind_address: IpAddr,
     /// Enable CORS headers
     pub enable_cors: bool,
     /// Maximum results per query
     pub max_results: usize,
+    /// Default apdex target latency for `/api/operations`
+    pub apdex_target: Duration,
+    /// P95 ingest lag above which `/api/services` flags a service
+    pub ingest_lag_threshold: Duration,
+    /// Receiver sampling rates reported by `/api/sampling`
+    pub sampling_rates: Option<SharedServiceRates>,
+    /// Receiver rate limiter managed through `/api/rate-limits`
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Receiver counters exported by `/metrics`
+    pub receiver_stats: Option<Arc<ReceiverStats>>,
+    /// Keys accepted for `/api/*` requests; empty leaves the API open
+    pub api_keys: Vec<String>,
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
             port: 8080,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
             enable_cors: true,
             max_results: 1000,
+            apdex_target: Duration::from_millis(500),
+            ingest_lag_threshold: Duration::from_secs(60),
+            sampling_rates: None,
+            rate_limiter: None,
+            receiver_stats: None,
+            api_keys: Vec::new(),
         }
     }
 }
@@ -47,6 +82,7 @@ impl Default for ApiConfig {
 struct ApiState {
     storage: Arc<tokio::sync::RwLock<dyn StorageBackend>>,
     config: ApiConfig,
+    debug: DebugContext,
 }
 
 /// Health check response.
@@ -66,6 +102,34 @@ struct ErrorResponse {
     code: u16,
 }
 
+/// A trace that is no longer stored.
+#[derive(Debug, Serialize)]
+struct EvictedTraceResponse {
+    trace_id: String,
+    /// RFC 3339 eviction time
+    evicted_at: String,
+}
+
+impl From<crate::storage::EvictedTrace> for EvictedTraceResponse {
+    fn from(evicted: crate::storage::EvictedTrace) -> Self {
+        Self {
+            trace_id: evicted.trace_id.as_str().to_string(),
+            evicted_at: format_rfc3339(evicted.evicted_at),
+        }
+    }
+}
+
+fn format_rfc3339(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+/// Query parameters for the eviction log.
+#[derive(Debug, Deserialize)]
+struct EvictionsQuery {
+    /// Maximum number of results
+    limit: Option<usize>,
+}
+
 /// Query parameters for trace listing.
 #[derive(Debug, Deserialize)]
 struct TraceQuery {
@@ -79,8 +143,16 @@ struct TraceQuery {
     limit: Option<usize>,
     /// Only return traces with errors
     errors_only: Option<bool>,
-    /// Export format (json, jaeger, otel, csv)
+    /// Only return traces with a span from this `deployment.environment`
+    environment: Option<String>,
+    /// Only return traces with a span whose attribute `attr_key` equals `attr_value`
+    attr_key: Option<String>,
+    /// Value of `attr_key` to correlate traces by
+    attr_value: Option<String>,
+    /// Export format (json, ndjson, jaeger, otel, csv, chrome)
     format: Option<String>,
+    /// Wrap JSON/NDJSON exports with metadata (default: true)
+    metadata: Option<bool>,
 }
 
 /// Query parameters for search.
@@ -90,12 +162,48 @@ struct SearchQuery {
     q: String,
     /// Service filter
     service: Option<String>,
-    /// Attribute key filter
+    /// Attribute key whose value must equal `q`
     attribute_key: Option<String>,
     /// Maximum results
     limit: Option<usize>,
 }
 
+/// Query parameters for per-operation apdex.
+#[derive(Debug, Deserialize)]
+struct OperationsQuery {
+    /// Only report operations of this service
+    service: Option<String>,
+    /// Apdex target latency in milliseconds (default: configured target)
+    target_ms: Option<u64>,
+    /// Look-back window in seconds (default: 1 hour)
+    window: Option<u64>,
+}
+
+/// Query parameters for long-term statistics.
+#[derive(Debug, Deserialize)]
+struct LongTermStatsQuery {
+    /// Only report this service
+    service: Option<String>,
+    /// Look-back window in days (default: 30)
+    days: Option<u64>,
+}
+
+/// Query parameters for sampling rates.
+#[derive(Debug, Deserialize)]
+struct SamplingQuery {
+    /// Report only this service, even if no span of it is stored
+    service: Option<String>,
+}
+
+/// Query parameters for the service map.
+#[derive(Debug, Deserialize)]
+struct ServiceMapQuery {
+    /// Maximum number of traces to analyze
+    limit: Option<usize>,
+    /// Only analyze traces from the last this many seconds (default: 1 hour)
+    time_window_seconds: Option<u64>,
+}
+
 /// Query parameters for `TraceQL` queries.
 #[derive(Debug, Deserialize)]
 struct TraceQLQuery {
@@ -105,6 +213,48 @@ struct TraceQLQuery {
     limit: Option<usize>,
 }
 
+/// Body of `POST /api/query`.
+#[derive(Debug, Deserialize)]
+struct TraceQLRequest {
+    /// `TraceQL` query `string`
+    query: String,
+    /// Maximum results
+    limit: Option<usize>,
+}
+
+/// Query parameters for deleting traces.
+#[derive(Debug, Deserialize)]
+struct DeleteTracesQuery {
+    /// `TraceQL` query selecting the traces to delete
+    query: String,
+    /// Must be true to actually delete
+    confirm: Option<bool>,
+    /// Only count the matching traces
+    dry_run: Option<bool>,
+}
+
+/// Result of a trace deletion.
+#[derive(Debug, Serialize, Deserialize)]
+struct DeleteTracesResponse {
+    query: String,
+    /// Traces matching the query
+    matched: usize,
+    /// Spans removed from storage
+    deleted_spans: usize,
+    dry_run: bool,
+    /// More traces matched than one request deletes; repeat it for the rest
+    limited: bool,
+    /// Which traces the query was matched against
+    scope: String,
+}
+
+/// Most traces deleted by one request.
+const MAX_DELETE_TRACES: usize = 10_000;
+
+/// Traces a deletion query is matched against, reported with every result.
+const DELETE_TRACES_SCOPE: &str = "Only recent traces held uncompressed in memory are matched; \
+     compressed (warm) and archived traces are not searched and are kept";
+
 /// Start the API server with UnifiedStorage (recommended).
 pub async fn start_server_with_storage(storage: &UnifiedStorage, config: ApiConfig) -> Result<()> {
     start_server(storage.as_backend(), config).await
@@ -115,32 +265,23 @@ pub async fn start_server(
     storage: Arc<tokio::sync::RwLock<dyn StorageBackend>>,
     config: ApiConfig,
 ) -> Result<()> {
-    let state = ApiState {
-        storage,
-        config: config.clone(),
-    };
-
-    // Build router with all endpoints
-    let mut app = Router::new()
-        .route("/health", get(health_handler))
-        .route("/api/traces", get(list_traces_handler))
-        .route("/api/traces/:id", get(get_trace_handler))
-        .route("/api/services", get(list_services_handler))
-        .route("/api/service-map", get(get_service_map_handler))
-        .route("/api/search", get(search_handler))
-        .route("/api/query", get(query_handler))
-        .with_state(state);
+    start_server_with_debug(storage, config, DebugContext::default()).await
+}
 
-    // Add CORS if enabled
-    if config.enable_cors {
-        app = app.layer(ServiceBuilder::new().layer(CorsLayer::permissive()));
-    }
+/// Start the API server with extra runtime state for `/api/debug/dump`.
+pub async fn start_server_with_debug(
+    storage: Arc<tokio::sync::RwLock<dyn StorageBackend>>,
+    config: ApiConfig,
+    debug: DebugContext,
+) -> Result<()> {
+    // SocketAddr formats IPv6 hosts as [::1]:port
+    let addr = SocketAddr::new(config.bind_address, config.port);
+    let app = create_router(storage, config, debug);
 
     // Start server
-    let addr = format!("0.0.0.0:{}", config.port);
     tracing::info!("Starting API server on http://{}", addr);
 
-    let listener = TcpListener::bind(&addr).await.map_err(|e| {
+    let listener = TcpListener::bind(addr).await.map_err(|e| {
         UrpoError::Io(std::io::Error::new(
             std::io::ErrorKind::AddrInUse,
             format!("Failed to bind to {}: {}", addr, e),
@@ -157,6 +298,59 @@ pub async fn start_server(
     Ok(())
 }
 
+/// Build the API router with all endpoints.
+pub fn create_router(
+    storage: Arc<tokio::sync::RwLock<dyn StorageBackend>>,
+    config: ApiConfig,
+    debug: DebugContext,
+) -> Router {
+    let enable_cors = config.enable_cors;
+    let api_keys: Arc<[String]> = config.api_keys.clone().into();
+    let state = ApiState {
+        storage,
+        config,
+        debug,
+    };
+
+    let api = Router::new()
+        .route("/api/traces", get(list_traces_handler).delete(delete_traces_handler))
+        .route("/api/traces/:id", get(get_trace_handler))
+        .route("/api/traces/:id/tree", get(get_trace_tree_handler))
+        .route("/api/evictions", get(list_evictions_handler))
+        .route("/api/services", get(list_services_handler))
+        .route("/api/operations", get(list_operations_handler))
+        .route("/api/service-map", get(get_service_map_handler))
+        .route("/api/stats/longterm", get(longterm_stats_handler))
+        .route("/api/sampling", get(sampling_rates_handler))
+        .route("/api/rate-limits", get(rate_limits_handler).put(set_rate_limits_handler))
+        .route("/api/search", get(search_handler))
+        .route("/api/query", get(query_handler).post(post_query_handler))
+        .route("/api/debug/dump", get(debug::debug_dump_handler))
+        .route(
+            "/api/debug/capture",
+            post(debug::start_capture_handler).delete(debug::stop_capture_handler),
+        )
+        .route("/api/debug/captures", get(debug::list_captures_handler));
+    let api = if api_keys.is_empty() {
+        api
+    } else {
+        api.route_layer(axum::middleware::from_fn_with_state(api_keys, auth::require_api_key))
+    };
+
+    let app = Router::new()
+        .route("/health", get(health_handler))
+        .route("/metrics", get(metrics::metrics_handler))
+        .merge(api)
+        .with_state(state);
+
+    // Add CORS if enabled
+    if enable_cors {
+        app.layer(ServiceBuilder::new().layer(CorsLayer::permissive()))
+    } else {
+        app
+    }
+}
+
 /// GET /health - System health and statistics
 async fn health_handler(State(api_state): State<ApiState>) -> impl IntoResponse {
     // Get storage statistics
@@ -197,14 +391,43 @@ async fn list_traces_handler(
     // Apply limit with max cap
     let limit = params.limit.unwrap_or(100).min(state.config.max_results);
 
-    // List traces
-    let traces = match state
-        .storage
-        .read()
-        .await
-        .list_traces(params.service.as_deref(), start_time, end_time, limit)
-        .await
-    {
+    // List traces, or the traces correlated by an attribute value
+    let listed = match (params.attr_key.as_deref(), params.attr_value.as_deref()) {
+        (None, None) => {
+            state
+                .storage
+                .read()
+                .await
+                .list_traces(params.service.as_deref(), start_time, end_time, limit)
+                .await
+        },
+        (Some(key), Some(value)) => state
+            .storage
+            .read()
+            .await
+            .get_traces_by_attribute(key, value, state.config.max_results)
+            .await
+            .map(|traces| {
+                traces
+                    .into_iter()
+                    .filter(|trace| {
+                        trace_matches(trace, params.service.as_deref(), start_time, end_time)
+                    })
+                    .take(limit)
+                    .collect()
+            }),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "attr_key and attr_value must be given together".to_string(),
+                    code: 400,
+                }),
+            )
+                .into_response();
+        },
+    };
+    let traces = match listed {
         Ok(t) => t,
         Err(e) => {
             return (
@@ -225,9 +448,41 @@ async fn list_traces_handler(
         traces
     };
 
+    let filtered_traces = match params.environment.as_deref() {
+        Some(environment) => {
+            let storage = state.storage.read().await;
+            let mut kept = Vec::with_capacity(filtered_traces.len());
+            for trace in filtered_traces {
+                let mut matched = false;
+                let visited = storage
+                    .visit_trace_spans(&trace.trace_id, &mut |span| {
+                        matched = span.resource_attributes.get("deployment.environment")
+                            == Some(environment);
+                        !matched
+                    })
+                    .await;
+                if visited.is_ok() && matched {
+                    kept.push(trace);
+                }
+            }
+            kept
+        },
+        None => filtered_traces,
+    };
+
     // Handle different export formats
     if let Some(format_str) = params.format {
         let format = match format_str.parse::<ExportFormat>() {
+            Ok(f) if f.is_binary() => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        error: format!("Format {} is only available from `urpo export`", f.name()),
+                        code: 400,
+                    }),
+                )
+                    .into_response();
+            },
             Ok(f) => f,
             Err(e) => {
                 return (
@@ -251,6 +506,9 @@ async fn list_traces_handler(
             end_time,
             limit: Some(limit),
             errors_only: params.errors_only.unwrap_or(false),
+            include_metadata: params.metadata.unwrap_or(true),
+            critical_path_only: false,
+            attribute_redaction: None,
         };
 
         match exporter.export_traces(&options).await {
@@ -270,52 +528,127 @@ async fn list_traces_handler(
     }
 }
 
+/// Whether `trace` involves `service` and starts within the time range
+/// (unix nanoseconds).
+fn trace_matches(
+    trace: &TraceInfo,
+    service: Option<&str>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+) -> bool {
+    let started = trace
+        .start_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    service.map_or(true, |service| trace.services.iter().any(|s| s.as_str() == service))
+        && start_time.map_or(true, |start| started >= start)
+        && end_time.map_or(true, |end| started <= end)
+}
+
 /// GET /api/traces/:id - Get specific trace with all spans
 async fn get_trace_handler(
     State(state): State<ApiState>,
     Path(trace_id): Path<String>,
 ) -> impl IntoResponse {
+    match fetch_trace_spans(&state, &trace_id).await {
+        Ok(spans) => Json(spans).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// GET /api/traces/:id/tree - Spans of a trace nested by parent
+async fn get_trace_tree_handler(
+    State(state): State<ApiState>,
+    Path(trace_id): Path<String>,
+) -> impl IntoResponse {
+    match fetch_trace_spans(&state, &trace_id).await {
+        Ok(spans) => Json(SpanTree::build(spans)).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Spans of `trace_id`, or the error response to send.
+async fn fetch_trace_spans(
+    state: &ApiState,
+    trace_id: &str,
+) -> std::result::Result<Vec<Span>, axum::response::Response> {
     // Parse trace ID
     let trace_id: crate::core::TraceId = match trace_id.parse() {
         Ok(id) => id,
         Err(_) => {
-            return (
+            return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
                     error: "Invalid trace ID format".to_string(),
                     code: 400,
                 }),
             )
-                .into_response();
+                .into_response());
         },
     };
 
     // Get trace spans
-    let spans = match state.storage.read().await.get_trace_spans(&trace_id).await {
+    let storage = state.storage.read().await;
+    let spans = match storage.get_trace_spans(&trace_id).await {
         Ok(s) => s,
         Err(e) => {
             if e.to_string().contains("not found") {
-                return (
+                return Err((
                     StatusCode::NOT_FOUND,
                     Json(ErrorResponse {
                         error: format!("Trace not found: {}", trace_id.as_str()),
                         code: 404,
                     }),
                 )
-                    .into_response();
+                    .into_response());
             }
-            return (
+            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
                     error: format!("Failed to get trace: {}", e),
                     code: 500,
                 }),
             )
-                .into_response();
+                .into_response());
         },
     };
 
-    Json(spans).into_response()
+    if spans.is_empty() {
+        if let Some(evicted_at) = storage.trace_evicted_at(&trace_id) {
+            return Err((
+                StatusCode::GONE,
+                Json(ErrorResponse {
+                    error: format!(
+                        "Trace {} evicted at {}",
+                        trace_id.as_str(),
+                        format_rfc3339(evicted_at)
+                    ),
+                    code: 410,
+                }),
+            )
+                .into_response());
+        }
+    }
+
+    Ok(spans)
+}
+
+/// GET /api/evictions - Recently evicted trace IDs, most recent first
+async fn list_evictions_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<EvictionsQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(100).min(state.config.max_results);
+    let evicted: Vec<EvictedTraceResponse> = state
+        .storage
+        .read()
+        .await
+        .recently_evicted_traces(limit)
+        .into_iter()
+        .map(EvictedTraceResponse::from)
+        .collect();
+    Json(evicted)
 }
 
 /// GET /api/services - List all services with basic metrics
@@ -345,18 +678,215 @@ async fn list_services_handler(State(state): State<ApiState>) -> impl IntoRespon
             latency_p50: metrics.latency_p50.as_micros() as u64,
             latency_p95: metrics.latency_p95.as_micros() as u64,
             latency_p99: metrics.latency_p99.as_micros() as u64,
+            ingest_lag_p50: metrics.ingest_lag_p50.as_micros() as u64,
+            ingest_lag_p95: metrics.ingest_lag_p95.as_micros() as u64,
+            ingest_lag_p99: metrics.ingest_lag_p99.as_micros() as u64,
+            ingest_lag_warning: metrics.ingest_lag_p95 > state.config.ingest_lag_threshold,
         })
         .collect();
 
     Json(service_list).into_response()
 }
 
+/// GET /api/stats/longterm - Hourly per-service statistics, oldest first
+async fn longterm_stats_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<LongTermStatsQuery>,
+) -> impl IntoResponse {
+    let storage = state.storage.read().await;
+    let Some(longterm) = storage.longterm_stats() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Long-term statistics are not enabled (storage.longterm_stats)".to_string(),
+                code: 404,
+            }),
+        )
+            .into_response();
+    };
+
+    let window = Duration::from_secs(params.days.unwrap_or(30).saturating_mul(24 * 3600));
+    let since = SystemTime::now()
+        .checked_sub(window)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let service = params.service.as_deref().filter(|s| !s.is_empty());
+    Json(longterm.query(service, since)).into_response()
+}
+
+/// GET /api/sampling - Effective sampling rate of each service
+async fn sampling_rates_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<SamplingQuery>,
+) -> impl IntoResponse {
+    let Some(ref rates) = state.config.sampling_rates else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Sampling rates are not available without a receiver".to_string(),
+                code: 404,
+            }),
+        )
+            .into_response();
+    };
+
+    let services = match params.service.filter(|s| !s.is_empty()) {
+        Some(service) => vec![service],
+        None => match state.storage.read().await.list_services().await {
+            Ok(services) => services
+                .into_iter()
+                .map(|service| service.as_str().to_string())
+                .collect(),
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: format!("Failed to list services: {}", e),
+                        code: 500,
+                    }),
+                )
+                    .into_response();
+            },
+        },
+    };
+
+    let rates = rates.load();
+    let services = services
+        .into_iter()
+        .map(|service| {
+            let matched = rates.matching_override(&service);
+            ServiceSamplingRate {
+                rate: matched.map_or(rates.default_rate(), |(_, rate)| rate),
+                pattern: matched.map(|(pattern, _)| pattern.to_string()),
+                service,
+            }
+        })
+        .collect();
+    Json(SamplingRatesResponse {
+        default_rate: rates.default_rate(),
+        services,
+    })
+    .into_response()
+}
+
+/// GET /api/rate-limits - Ingestion rate limits and dropped span counts
+async fn rate_limits_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    match state.config.rate_limiter {
+        Some(ref limiter) => Json(RateLimitsResponse::from_limiter(limiter)).into_response(),
+        None => rate_limiting_disabled(),
+    }
+}
+
+/// PUT /api/rate-limits - Replace the ingestion rate limits
+async fn set_rate_limits_handler(
+    State(state): State<ApiState>,
+    Json(limits): Json<RateLimits>,
+) -> impl IntoResponse {
+    let Some(ref limiter) = state.config.rate_limiter else {
+        return rate_limiting_disabled();
+    };
+    if let Err(e) = limits.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+                code: 400,
+            }),
+        )
+            .into_response();
+    }
+
+    tracing::info!("Ingestion rate limits replaced through the API");
+    limiter.set_limits(limits);
+    Json(RateLimitsResponse::from_limiter(limiter)).into_response()
+}
+
+fn rate_limiting_disabled() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "Rate limiting is not enabled; add a rate_limit section to the config"
+                .to_string(),
+            code: 404,
+        }),
+    )
+        .into_response()
+}
+
+/// GET /api/operations - Per-operation apdex scores
+async fn list_operations_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<OperationsQuery>,
+) -> impl IntoResponse {
+    let target = params
+        .target_ms
+        .map_or(state.config.apdex_target, Duration::from_millis);
+    if target.is_zero() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Apdex target must be greater than zero".to_string(),
+                code: 400,
+            }),
+        )
+            .into_response();
+    }
+
+    let window = Duration::from_secs(params.window.unwrap_or(3600));
+    let since = SystemTime::now()
+        .checked_sub(window)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let spans = collect_spans_since(
+        &state.storage,
+        params.service.as_deref().filter(|s| !s.is_empty()),
+        since,
+    )
+    .await;
+
+    match spans {
+        Ok(spans) => Json(operation_apdex(&spans, target)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to get operations: {}", e),
+                code: 500,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Fetch spans since `since`, for one service or all of them.
+async fn collect_spans_since(
+    storage: &Arc<tokio::sync::RwLock<dyn StorageBackend>>,
+    service: Option<&str>,
+    since: SystemTime,
+) -> Result<Vec<Span>> {
+    let storage = storage.read().await;
+    let services = match service {
+        Some(service) => vec![ServiceName::new(service.to_string())?],
+        None => storage.list_services().await?,
+    };
+
+    let mut spans = Vec::new();
+    for service in &services {
+        spans.extend(storage.get_service_spans(service, since).await?);
+    }
+    Ok(spans)
+}
+
 /// GET /api/service-map - Get current service dependency map
-async fn get_service_map_handler(State(state): State<ApiState>) -> impl IntoResponse {
+async fn get_service_map_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<ServiceMapQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(1000).min(state.config.max_results);
+    let time_window_seconds = params.time_window_seconds.unwrap_or(3600);
+
     let storage_guard = state.storage.read().await;
     let mut builder = ServiceMapBuilder::new(&*storage_guard);
 
-    match builder.build_from_recent_traces(1000, 3600).await {
+    match builder.build_from_window(limit, time_window_seconds).await {
         Ok(map) => Json(map).into_response(),
         Err(e) => {
             tracing::error!("Failed to build service map: {}", e);
@@ -471,6 +1001,119 @@ async fn query_handler(
     }
 }
 
+/// POST /api/query - Execute a TraceQL query sent as JSON
+async fn post_query_handler(
+    State(state): State<ApiState>,
+    Json(request): Json<TraceQLRequest>,
+) -> impl IntoResponse {
+    let engine = QueryEngine::new(Arc::clone(&state.storage));
+    if let Err(e) = engine.validate(&request.query) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Invalid query: {}", e),
+                code: 400,
+            }),
+        )
+            .into_response();
+    }
+
+    let limit = request.limit.unwrap_or(100).min(state.config.max_results);
+    match engine.execute(&request.query, Some(limit)).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Query execution failed: {}", e),
+                code: 500,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// DELETE /api/traces - Delete the traces matching a TraceQL query
+async fn delete_traces_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<DeleteTracesQuery>,
+) -> impl IntoResponse {
+    let bad_request = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse { error, code: 400 })).into_response()
+    };
+    if params.query.is_empty() {
+        return bad_request("Query parameter 'query' is required".to_string());
+    }
+    let dry_run = params.dry_run.unwrap_or(false);
+    if !dry_run && params.confirm != Some(true) {
+        return bad_request(
+            "Deleting traces requires confirm=true; use dry_run=true to count matches first"
+                .to_string(),
+        );
+    }
+
+    let engine = QueryEngine::new(Arc::clone(&state.storage));
+    if let Err(e) = engine.validate(&params.query) {
+        return bad_request(format!("Invalid query: {}", e));
+    }
+    let result = match engine.execute(&params.query, Some(MAX_DELETE_TRACES)).await {
+        Ok(result) => result,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Query execution failed: {}", e),
+                    code: 500,
+                }),
+            )
+                .into_response();
+        },
+    };
+    let trace_ids: Vec<TraceId> = result
+        .trace_ids
+        .iter()
+        .filter_map(|id| TraceId::new(id.clone()).ok())
+        .collect();
+
+    let deleted_spans = if dry_run {
+        0
+    } else {
+        match state.storage.read().await.delete_traces(&trace_ids).await {
+            Ok(deleted) => deleted,
+            Err(e) => {
+                tracing::error!("Failed to delete traces matching '{}': {}", params.query, e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: format!("Failed to delete traces: {}", e),
+                        code: 500,
+                    }),
+                )
+                    .into_response();
+            },
+        }
+    };
+
+    // Audit entry, logged under its own target so it can be routed separately
+    tracing::warn!(
+        target: "urpo::audit",
+        "Trace deletion{}: query '{}' matched {} traces, deleted {} spans",
+        if dry_run { " (dry run)" } else { "" },
+        params.query,
+        trace_ids.len(),
+        deleted_spans
+    );
+
+    Json(DeleteTracesResponse {
+        query: params.query,
+        matched: trace_ids.len(),
+        deleted_spans,
+        dry_run,
+        limited: result.limited,
+        scope: DELETE_TRACES_SCOPE.to_string(),
+    })
+    .into_response()
+}
+
 /// Service information with metrics.
 #[derive(Debug, Serialize)]
 struct ServiceInfo {
@@ -480,6 +1123,47 @@ struct ServiceInfo {
     latency_p50: u64,
     latency_p95: u64,
     latency_p99: u64,
+    ingest_lag_p50: u64,
+    ingest_lag_p95: u64,
+    ingest_lag_p99: u64,
+    /// P95 ingest lag exceeds the configured threshold
+    ingest_lag_warning: bool,
+}
+
+/// Sampling rates in effect.
+#[derive(Debug, Serialize)]
+struct SamplingRatesResponse {
+    /// Rate of services without an override
+    default_rate: f64,
+    services: Vec<ServiceSamplingRate>,
+}
+
+/// Effective sampling rate of a service.
+#[derive(Debug, Serialize)]
+struct ServiceSamplingRate {
+    service: String,
+    rate: f64,
+    /// `sampling.per_service` key the rate comes from, if any
+    pattern: Option<String>,
+}
+
+/// Ingestion rate limits in effect.
+#[derive(Debug, Serialize)]
+struct RateLimitsResponse {
+    /// What identifies a client
+    key: RateLimitKey,
+    limits: RateLimits,
+    stats: RateLimiterStats,
+}
+
+impl RateLimitsResponse {
+    fn from_limiter(limiter: &RateLimiter) -> Self {
+        Self {
+            key: limiter.key().clone(),
+            limits: (*limiter.limits()).clone(),
+            stats: limiter.stats(),
+        }
+    }
 }
 
 /// Search results response.
@@ -493,6 +1177,11 @@ struct SearchResults {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::SpanId;
+    use crate::storage::InMemoryStorage;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
 
     #[test]
     fn test_default_config() {
@@ -508,6 +1197,715 @@ mod tests {
         assert_eq!("jaeger".parse::<ExportFormat>().unwrap(), ExportFormat::Jaeger);
         assert_eq!("otel".parse::<ExportFormat>().unwrap(), ExportFormat::OpenTelemetry);
         assert_eq!("csv".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert_eq!("chrome".parse::<ExportFormat>().unwrap(), ExportFormat::ChromeTrace);
+        assert_eq!("parquet".parse::<ExportFormat>().unwrap(), ExportFormat::Parquet);
         assert!("invalid".parse::<ExportFormat>().is_err());
     }
+
+    #[tokio::test]
+    async fn test_operations_apdex_endpoint() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (i, millis) in [50, 150, 900].into_iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("trace_{}", i)).unwrap())
+                .span_id(SpanId::new(format!("span_{}", i)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(millis))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(
+                Request::get("/api/operations?target_ms=100")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let operations: Vec<crate::core::OperationApdex> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].operation, "charge");
+        assert_eq!(operations[0].target_ms, 100);
+        assert_eq!(operations[0].score, Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_trace_includes_resource_attributes() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 7)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("checkout".to_string()).unwrap())
+            .operation_name("charge")
+            .start_time(SystemTime::now())
+            .resource_attribute("k8s.pod.name", "checkout-7d9f")
+            .resource_attribute("k8s.namespace.name", "shop")
+            .build()
+            .unwrap();
+        storage.read().await.store_span(span).await.unwrap();
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(
+                Request::get(format!("/api/traces/{:032x}", 7))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spans: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(spans[0]["resource_attributes"]["k8s.pod.name"], "checkout-7d9f");
+        assert_eq!(spans[0]["resource_attributes"]["k8s.namespace.name"], "shop");
+    }
+
+    #[tokio::test]
+    async fn test_list_traces_filters_by_environment() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (id, environment) in [(1, "prod"), (2, "staging"), (3, "prod")] {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(SystemTime::now())
+                .resource_attribute("deployment.environment", environment)
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(
+                Request::get("/api/traces?environment=staging")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let traces: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(traces.as_array().unwrap().len(), 1);
+        assert_eq!(traces[0]["trace_id"], format!("{:032x}", 2));
+    }
+
+    #[tokio::test]
+    async fn test_list_traces_correlated_by_attribute() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (id, service, user) in [(1, "web", "42"), (2, "mobile", "7"), (3, "mobile", "42")] {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("checkout")
+                .start_time(SystemTime::now())
+                .attribute("user.id", user)
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let list = |uri: &str| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+        let response = list("/api/traces?attr_key=user.id&attr_value=42")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let traces: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let mut ids: Vec<_> = traces
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|trace| trace["trace_id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![format!("{:032x}", 1), format!("{:032x}", 3)]);
+
+        let response = list("/api/traces?attr_key=user.id&attr_value=42&service=web")
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let traces: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(traces.as_array().unwrap().len(), 1);
+
+        let response = list("/api/traces?attr_key=user.id").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_trace_tree_endpoint_nests_children() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        // (span, parent, operation)
+        for (id, parent, operation) in [
+            (1, None, "GET /checkout"),
+            (2, Some(1), "charge"),
+            (3, Some(2), "INSERT payments"),
+            (4, Some(1), "send receipt"),
+        ] {
+            let mut builder = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", 9)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name(operation)
+                .start_time(start + Duration::from_millis(id));
+            if let Some(parent) = parent {
+                builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+            }
+            storage
+                .read()
+                .await
+                .store_span(builder.build().unwrap())
+                .await
+                .unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(
+                Request::get(format!("/api/traces/{:032x}/tree", 9))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let tree: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let roots = tree["roots"].as_array().unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0]["span"]["operation_name"], "GET /checkout");
+        assert_eq!(roots[0]["orphan"], false);
+
+        let children = roots[0]["children"].as_array().unwrap();
+        let operations: Vec<&str> = children
+            .iter()
+            .map(|c| c["span"]["operation_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(operations, vec!["charge", "send receipt"]);
+        assert_eq!(children[0]["children"][0]["span"]["operation_name"], "INSERT payments");
+        assert!(children[1]["children"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_service_map_endpoint() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        let now = SystemTime::now();
+        let day_ago = now - Duration::from_secs(24 * 3600);
+        // (trace, span, parent, service, start)
+        for (trace, id, parent, service, start) in [
+            (1, 1, None, "frontend", now),
+            (1, 2, Some(1), "backend", now),
+            (1, 3, Some(2), "db", now),
+            // Outside the time window
+            (2, 4, None, "batch", day_ago),
+            (2, 5, Some(4), "db", day_ago),
+        ] {
+            let mut builder = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name(format!("{}-op", service))
+                .start_time(start)
+                .duration(Duration::from_millis(10));
+            if let Some(parent) = parent {
+                builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+            }
+            storage
+                .read()
+                .await
+                .store_span(builder.build().unwrap())
+                .await
+                .unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(
+                Request::get("/api/service-map?limit=10&time_window_seconds=600")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let map: crate::service_map::ServiceMap = serde_json::from_slice(&body).unwrap();
+        assert_eq!(map.trace_count, 1);
+        assert_eq!(map.time_window_seconds, 600);
+        let mut edges: Vec<(&str, &str, u64)> = map
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.call_count))
+            .collect();
+        edges.sort();
+        assert_eq!(edges, vec![("backend", "db", 1), ("frontend", "backend", 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_rates_endpoint() {
+        use crate::sampling::ServiceRates;
+
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (i, service) in ["checkout", "healthcheck-eu"].into_iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("op")
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+        let rates: SharedServiceRates = Arc::new(arc_swap::ArcSwap::from_pointee(
+            ServiceRates::new([("healthcheck*".to_string(), 0.01)], 0.5),
+        ));
+        let config = ApiConfig {
+            sampling_rates: Some(Arc::clone(&rates)),
+            ..ApiConfig::default()
+        };
+        let app = create_router(storage, config, DebugContext::default());
+        let get = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let body = get("/api/sampling").await;
+        assert_eq!(body["default_rate"], 0.5);
+        let mut services = body["services"].as_array().unwrap().clone();
+        services.sort_by_key(|s| s["service"].as_str().unwrap().to_string());
+        assert_eq!(
+            services,
+            vec![
+                serde_json::json!({"service": "checkout", "rate": 0.5, "pattern": null}),
+                serde_json::json!({
+                    "service": "healthcheck-eu",
+                    "rate": 0.01,
+                    "pattern": "healthcheck*",
+                }),
+            ]
+        );
+
+        // Reloaded rates are reported right away, also for unseen services
+        rates.store(Arc::new(ServiceRates::new([("billing".to_string(), 0.25)], 1.0)));
+        let body = get("/api/sampling?service=billing").await;
+        assert_eq!(
+            body["services"],
+            serde_json::json!([{"service": "billing", "rate": 0.25, "pattern": "billing"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_longterm_stats_endpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let stats = Arc::new(
+            crate::storage::LongTermStats::open(
+                dir.path().join("longterm-stats.log"),
+                Duration::from_secs(31 * 24 * 3600),
+            )
+            .unwrap(),
+        );
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(
+                InMemoryStorage::new(1000).with_longterm_stats(Arc::clone(&stats)),
+            ));
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 3600);
+        for (i, service) in ["checkout", "checkout", "cart"].into_iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("op")
+                .start_time(two_hours_ago)
+                .duration(Duration::from_millis(20))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+        stats.flush_all().unwrap();
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/api/stats/longterm?service=checkout&days=1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let hours: Vec<crate::storage::HourlyStats> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!(hours[0].service, "checkout");
+        assert_eq!(hours[0].request_count, 2);
+
+        // Not enabled on this storage
+        let plain: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        let response = create_router(plain, ApiConfig::default(), DebugContext::default())
+            .oneshot(
+                Request::get("/api/stats/longterm")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_services_report_ingest_lag() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        // batch-worker spans arrive ten minutes after they ended
+        let ten_minutes_ago = SystemTime::now() - Duration::from_secs(600);
+        for (i, (service, start)) in [
+            ("checkout", SystemTime::now()),
+            ("batch-worker", ten_minutes_ago),
+            ("batch-worker", ten_minutes_ago),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("op")
+                .start_time(start)
+                .duration(Duration::from_millis(20))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .oneshot(Request::get("/api/services").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let services: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let service = |name: &str| services.iter().find(|s| s["name"] == name).unwrap().clone();
+
+        let lagging = service("batch-worker");
+        assert!(lagging["ingest_lag_p50"].as_u64().unwrap() >= 599_000_000);
+        assert_eq!(lagging["ingest_lag_warning"], true);
+        let fresh = service("checkout");
+        assert!(fresh["ingest_lag_p95"].as_u64().unwrap() < 60_000_000);
+        assert_eq!(fresh["ingest_lag_warning"], false);
+    }
+
+    #[tokio::test]
+    async fn test_evicted_trace_returns_gone() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(10)));
+        // One service per span so only the global capacity limit evicts
+        for i in 0..11 {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(format!("service-{}", i)).unwrap())
+                .operation_name("op")
+                .start_time(SystemTime::now())
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let app = create_router(storage, ApiConfig::default(), DebugContext::default());
+        let response = app
+            .clone()
+            .oneshot(Request::get("/api/evictions").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let evicted: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let evicted_ids: Vec<&str> = evicted
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["trace_id"].as_str().unwrap())
+            .collect();
+        let first = format!("{:032x}", 1);
+        assert!(evicted_ids.contains(&first.as_str()));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/api/traces/{}", first))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(error["error"].as_str().unwrap().contains("evicted at"));
+
+        // The newest trace is still served
+        let response = app
+            .oneshot(
+                Request::get(format!("/api/traces/{:032x}", 11))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_delete_traces_by_attribute_query() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        // Traces 1 and 3 belong to user 42; trace 3 also reaches "payments"
+        for (trace, span, service, user) in [
+            (1, 1, "checkout", "42"),
+            (2, 2, "checkout", "7"),
+            (3, 3, "checkout", "42"),
+            (3, 4, "payments", "42"),
+        ] {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", span)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(SystemTime::now())
+                .attribute("user.id", user)
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+        let app =
+            create_router(Arc::clone(&storage), ApiConfig::default(), DebugContext::default());
+        let send = |request: Request<Body>| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+        let delete = |params: &str| {
+            Request::delete(format!("/api/traces?query=user.id%20%3D%20%2242%22{}", params))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // A dry run only counts, and deleting needs confirmation
+        let (status, dry_run) = send(delete("&dry_run=true")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(dry_run["matched"], 2);
+        assert_eq!(dry_run["deleted_spans"], 0);
+        assert_eq!(dry_run["scope"], DELETE_TRACES_SCOPE);
+        let (status, _) = send(delete("")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, invalid) = send(
+            Request::delete("/api/traces?query=user.id%20%3D&dry_run=true")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error = invalid["error"].as_str().unwrap();
+        assert!(error.starts_with("Invalid query"), "{}", error);
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 4);
+
+        let (status, deleted) = send(delete("&confirm=true")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted["matched"], 2);
+        assert_eq!(deleted["deleted_spans"], 3);
+
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+        let (_, traces) = send(get("/api/traces")).await;
+        let listed: Vec<&str> = traces
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["trace_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(listed, vec![format!("{:032x}", 2)]);
+
+        let (_, found) = send(get("/api/search?q=42&attribute_key=user.id")).await;
+        assert_eq!(found["count"], 0);
+
+        let metrics = storage.read().await.get_service_metrics().await.unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name.as_str(), "checkout");
+        assert_eq!(metrics[0].span_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_query_endpoint() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        // Traces 1 to 3 are slow "api" requests, trace 4 a fast one
+        for (trace, millis) in [(1, 250), (2, 300), (3, 150), (4, 20)] {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", trace)).unwrap())
+                .service_name(ServiceName::new("api".to_string()).unwrap())
+                .operation_name("GET /orders")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(millis))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+        let query = |max_results: usize, body: serde_json::Value| {
+            let config = ApiConfig {
+                max_results,
+                ..ApiConfig::default()
+            };
+            let app = create_router(Arc::clone(&storage), config, DebugContext::default());
+            async move {
+                let request = Request::post("/api/query")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+
+        let (status, result) =
+            query(1000, serde_json::json!({ "query": "service=\"api\" && duration > 100ms" }))
+                .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["total_matches"], 3);
+        let mut trace_ids: Vec<&str> = result["trace_ids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| id.as_str().unwrap())
+            .collect();
+        trace_ids.sort();
+        let expected: Vec<String> = (1..=3).map(|trace| format!("{:032x}", trace)).collect();
+        assert_eq!(trace_ids, expected);
+
+        // The limit is capped by max_results
+        let (status, result) =
+            query(2, serde_json::json!({ "query": "duration > 100ms", "limit": 100 })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["trace_ids"].as_array().unwrap().len(), 2);
+
+        let (status, error) = query(1000, serde_json::json!({ "query": "duration >" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid query:"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_endpoint() {
+        use crate::core::{RateLimit, RateLimitConfig};
+
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(100)));
+        let limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+        let config = ApiConfig {
+            rate_limiter: Some(Arc::clone(&limiter)),
+            ..ApiConfig::default()
+        };
+        let app = create_router(Arc::clone(&storage), config, DebugContext::default());
+        let put = |body: serde_json::Value| {
+            Request::put("/api/rate-limits")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(put(serde_json::json!({
+                "default": { "spans_per_second": 1000.0 },
+                "clients": { "10.0.0.7": { "spans_per_second": 50.0, "burst": 100.0 } }
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["key"], "peer_ip");
+        assert_eq!(body["stats"]["dropped_spans"], 0);
+        assert_eq!(
+            limiter.limits().limit_for("10.0.0.7"),
+            Some(RateLimit {
+                spans_per_second: 50.0,
+                burst: Some(100.0)
+            })
+        );
+
+        let response = app
+            .clone()
+            .oneshot(put(serde_json::json!({ "default": { "spans_per_second": 0.0 } })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            limiter
+                .limits()
+                .limit_for("10.0.0.1")
+                .unwrap()
+                .spans_per_second,
+            1000.0
+        );
+
+        // Without a limiter there is nothing to manage
+        let response = create_router(storage, ApiConfig::default(), DebugContext::default())
+            .oneshot(
+                Request::get("/api/rate-limits")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }
@@ -25,8 +25,7 @@ impl Application {
         let monitor = Arc::new(Monitor::new());
 
         // Initialize storage with configuration
-        let storage =
-            Arc::new(UnifiedStorage::new(config.storage.max_spans, config.storage.max_memory_mb));
+        let storage = Arc::new(UnifiedStorage::open(&config)?);
 
         // Initialize receiver with storage and monitor
         let receiver = Arc::new(
@@ -36,7 +35,15 @@ impl Application {
                 storage.as_backend(),
                 Arc::clone(&monitor),
             )
-            .with_sampling_rate(config.sampling.default_rate as f32)
+            .with_service_rates(crate::sampling::ServiceRates::from_config(&config.sampling))
+            .with_sampling_rules(crate::sampling::SamplingRules::from_config(&config.sampling))
+            .with_service_filter(
+                config
+                    .receiver
+                    .as_ref()
+                    .map(crate::core::ServiceFilter::from_config)
+                    .unwrap_or_default(),
+            )
             .with_metrics(config.monitoring.max_metrics, config.monitoring.max_services)
             .with_logs(config.logging.max_logs),
         );
@@ -53,6 +60,9 @@ impl Application {
     pub async fn run(self) -> Result<()> {
         tracing::info!("Starting Urpo application");
 
+        // The monitoring loop also drives the receiver's health probes
+        self.monitor.start().await?;
+
         // Start the receiver and run indefinitely
         self.receiver.run().await?;
 
@@ -3,9 +3,13 @@
 //! This module provides a simple, htop-like CLI for Urpo.
 //! Just run `urpo` to start with sensible defaults!
 
-use crate::core::{Config, Result, UrpoError};
+mod watch;
+
+use crate::core::{AttributeFilter, Config, Glob, Result, UrpoError};
+use crate::export::redaction::RedactionPolicy;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+pub use watch::{watch_spans, SpanWatcher, WatchFormat};
 
 /// Terminal-native OTEL trace explorer - simple as htop!
 #[derive(Parser, Debug)]
@@ -25,6 +29,22 @@ pub struct Cli {
     #[arg(long, env = "URPO_HTTP_PORT", default_value = "4318")]
     pub http_port: Option<u16>,
 
+    /// Address the receivers and API server listen on (default: 127.0.0.1; e.g., 0.0.0.0, ::1)
+    #[arg(long = "bind", visible_alias = "bind-address", env = "URPO_BIND_ADDR")]
+    pub bind_address: Option<std::net::IpAddr>,
+
+    /// Maximum accepted OTLP request size in bytes (gRPC message or HTTP body)
+    #[arg(long, env = "URPO_MAX_REQUEST_BYTES")]
+    pub max_request_bytes: Option<usize>,
+
+    /// Maximum concurrent HTTP/2 streams per gRPC connection
+    #[arg(long, env = "URPO_MAX_CONCURRENT_STREAMS")]
+    pub max_concurrent_streams: Option<u32>,
+
+    /// Timeout for a single gRPC request (e.g., "30s", "1m")
+    #[arg(long, env = "URPO_REQUEST_TIMEOUT")]
+    pub request_timeout: Option<String>,
+
     /// Maximum memory usage in MB
     #[arg(long, env = "URPO_MEMORY_LIMIT")]
     pub memory_limit: Option<usize>,
@@ -53,6 +73,10 @@ pub struct Cli {
     #[arg(long)]
     pub check_config: bool,
 
+    /// Receive spans and print each one to stdout as it arrives (same as `urpo watch`)
+    #[arg(long)]
+    pub watch: bool,
+
     /// Show version information
     #[arg(short = 'V', long = "show-version")]
     pub version: bool,
@@ -64,6 +88,47 @@ pub struct Cli {
     /// HTTP API server port (default: 8080)
     #[arg(long, env = "URPO_API_PORT", default_value = "8080")]
     pub api_port: u16,
+
+    /// API key required for /api/* requests (repeatable; also used by `debug-dump`)
+    #[arg(
+        long = "api-key",
+        env = "URPO_API_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    pub api_keys: Vec<String>,
+
+    /// Prometheus remote-write endpoint for derived service metrics
+    #[cfg(feature = "remote-write")]
+    #[arg(long, env = "URPO_REMOTE_WRITE_URL")]
+    pub remote_write_url: Option<String>,
+
+    /// Bearer token for the remote-write endpoint
+    #[cfg(feature = "remote-write")]
+    #[arg(long, env = "URPO_REMOTE_WRITE_TOKEN", hide_env_values = true)]
+    pub remote_write_token: Option<String>,
+
+    /// Remote-write push interval (e.g., "15s", "1m")
+    #[cfg(feature = "remote-write")]
+    #[arg(long, env = "URPO_REMOTE_WRITE_INTERVAL", default_value = "15s")]
+    pub remote_write_interval: String,
+
+    /// Port for the Jaeger Thrift collector endpoint (off unless set, Jaeger default: 14268)
+    #[cfg(feature = "jaeger")]
+    #[arg(long, env = "URPO_JAEGER_PORT")]
+    pub jaeger_port: Option<u16>,
+
+    /// Stream stored spans to a Jaeger remote storage gRPC endpoint (e.g., "jaeger:17271")
+    #[arg(long, env = "URPO_EXPORT_TO_JAEGER")]
+    pub export_to_jaeger: Option<String>,
+
+    /// Forward received spans to a downstream OTLP/gRPC collector (e.g., "tempo:4317")
+    #[arg(long, env = "URPO_FORWARD_OTLP")]
+    pub forward_otlp: Option<String>,
+
+    /// Also write wire captures started via `/api/debug/capture` to this directory
+    #[arg(long, env = "URPO_CAPTURE_DIR")]
+    pub capture_dir: Option<PathBuf>,
 }
 
 /// Available subcommands
@@ -74,7 +139,7 @@ pub enum Commands {
         /// Trace ID to export (if not specified, exports based on filters)
         trace_id: Option<String>,
 
-        /// Export format (json, jaeger, otel, csv)
+        /// Export format (json, ndjson, jaeger, otel, csv, chrome, parquet)
         #[arg(short, long, default_value = "json")]
         format: String,
 
@@ -105,7 +170,103 @@ pub enum Commands {
         /// Maximum number of traces to export
         #[arg(long, default_value = "1000")]
         limit: usize,
+
+        /// Omit the metadata header from JSON/NDJSON exports (raw output)
+        #[arg(long)]
+        no_metadata: bool,
+
+        /// Print an aggregate table instead of exporting (e.g., "COUNT(*) GROUP BY service")
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Only export the spans on each trace's critical path
+        #[arg(long)]
+        critical_path_only: bool,
+
+        /// Redact attributes whose key matches this glob (repeatable)
+        #[arg(long = "redact", value_name = "PATTERN")]
+        redact: Vec<String>,
+
+        /// Redact attributes whose key matches none of these globs
+        /// (repeatable); `--redact` wins when a key matches both
+        #[arg(long = "redact-allow", value_name = "PATTERN")]
+        redact_allow: Vec<String>,
+
+        /// Value replacing redacted attributes; empty removes them instead
+        #[arg(long, default_value = crate::export::redaction::DEFAULT_REDACTION_PLACEHOLDER)]
+        redact_placeholder: String,
     },
+
+    /// Fetch a diagnostic bundle from a running instance's HTTP API
+    DebugDump {
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Host of the running instance's HTTP API (port from --api-port)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
+    /// Receive spans and print each one to stdout as it arrives
+    Watch {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: WatchFormat,
+
+        /// Only print spans of this service
+        #[arg(short, long)]
+        service: Option<String>,
+
+        /// Only print error spans
+        #[arg(long)]
+        errors_only: bool,
+    },
+
+    /// Compare the spans of two traces, e.g. from before and after a deployment
+    Diff {
+        /// Baseline trace ID
+        trace_a: String,
+
+        /// Trace ID to compare against the baseline
+        trace_b: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: DiffFormat,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Only compare the spans on each trace's critical path
+        #[arg(long)]
+        critical_path_only: bool,
+    },
+
+    /// Replay a file of OTLP JSON (e.g., from `urpo export --format otel`) into storage
+    Replay {
+        /// File with one OTLP JSON export request, an array of them, or one per line
+        file: PathBuf,
+
+        /// Playback speed relative to the recording (e.g., "2x", "0.5x", "max")
+        #[arg(long, default_value = "1x")]
+        speed: String,
+
+        /// Shift past timestamps so the first span starts now
+        #[arg(long)]
+        shift_to_now: bool,
+    },
+}
+
+/// Output format of `urpo diff`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffFormat {
+    /// Unified diff style listing, colored on a terminal
+    #[default]
+    Text,
+    /// The diff as one JSON object
+    Json,
 }
 
 impl Cli {
@@ -125,20 +286,9 @@ impl Cli {
         let mut builder = ConfigBuilder::new();
 
         // 1. Load from config file if specified or default location
-        let config_path = if let Some(path) = &self.config {
-            path.clone()
-        } else {
-            // Check default config location
-            let default_path = dirs::config_dir()
-                .map(|d| d.join("urpo").join("config.yaml"))
-                .unwrap_or_else(|| PathBuf::from("~/.config/urpo/config.yaml"));
-
-            if default_path.exists() {
-                default_path
-            } else {
-                // No config file, use defaults
-                return self.build_config_from_args(builder);
-            }
+        let Some(config_path) = self.config_file() else {
+            // No config file, use defaults
+            return self.build_config_from_args(builder);
         };
 
         // Try to load config file
@@ -164,6 +314,17 @@ impl Cli {
         self.build_config_from_args(builder)
     }
 
+    /// The config file to load: `--config`, or the default location if it exists.
+    pub fn config_file(&self) -> Option<PathBuf> {
+        if let Some(path) = &self.config {
+            return Some(path.clone());
+        }
+        let default_path = dirs::config_dir()
+            .map(|d| d.join("urpo").join("config.yaml"))
+            .unwrap_or_else(|| PathBuf::from("~/.config/urpo/config.yaml"));
+        default_path.exists().then_some(default_path)
+    }
+
     fn build_config_from_args(
         &self,
         mut builder: crate::core::config::ConfigBuilder,
@@ -175,9 +336,28 @@ impl Cli {
         if let Some(port) = self.http_port {
             builder = builder.http_port(port);
         }
+        if let Some(addr) = self.bind_address {
+            builder = builder.bind_address(addr);
+        }
+        if let Some(bytes) = self.max_request_bytes {
+            builder = builder.max_request_bytes(bytes);
+        }
+        if let Some(streams) = self.max_concurrent_streams {
+            builder = builder.max_concurrent_streams(streams);
+        }
+        if let Some(ref timeout) = self.request_timeout {
+            let timeout = parse_duration(timeout).ok_or_else(|| {
+                UrpoError::config(format!("Invalid request timeout: {}", timeout))
+            })?;
+            builder = builder.request_timeout(timeout);
+        }
         if let Some(limit) = self.memory_limit {
             builder = builder.max_memory_mb(limit);
         }
+        #[cfg(feature = "jaeger")]
+        if let Some(port) = self.jaeger_port {
+            builder = builder.jaeger_port(port);
+        }
 
         builder = builder.debug(self.debug);
 
@@ -186,7 +366,9 @@ impl Cli {
 
     /// Initialize logging based on configuration.
     pub fn init_logging(&self) -> Result<()> {
-        use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+        use tracing_subscriber::{
+            fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+        };
 
         // Determine log level
         let env_log_level = std::env::var("URPO_LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
@@ -199,10 +381,18 @@ impl Cli {
         let filter =
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
 
+        // Watch mode owns stdout, so logs go to stderr
+        let writer = if self.watches() {
+            BoxMakeWriter::new(std::io::stderr)
+        } else {
+            BoxMakeWriter::new(std::io::stdout)
+        };
+
         // Configure logging format
         let fmt_layer = if self.headless {
             // Structured logging for headless mode
             tracing_subscriber::fmt::layer()
+                .with_writer(writer)
                 .with_target(true)
                 .with_thread_ids(true)
                 .with_line_number(true)
@@ -210,6 +400,7 @@ impl Cli {
         } else {
             // Simpler format for interactive mode
             tracing_subscriber::fmt::layer()
+                .with_writer(writer)
                 .with_target(false)
                 .compact()
         };
@@ -222,6 +413,11 @@ impl Cli {
 
         Ok(())
     }
+
+    /// Whether spans are printed to stdout, via `--watch` or `urpo watch`.
+    fn watches(&self) -> bool {
+        self.watch || matches!(self.command, Some(Commands::Watch { .. }))
+    }
 }
 
 /// Execute the Urpo application.
@@ -241,6 +437,10 @@ pub async fn execute(cli: Cli) -> Result<()> {
     // Initialize logging
     cli.init_logging()?;
 
+    if cli.watch {
+        return execute_watch(WatchFormat::Text, None, false, &cli).await;
+    }
+
     // Load and validate configuration
     let config = cli.load_config().await?;
 
@@ -250,8 +450,32 @@ pub async fn execute(cli: Cli) -> Result<()> {
         println!("Configuration is valid!");
         println!("  GRPC port: {}", config.server.grpc_port);
         println!("  HTTP port: {}", config.server.http_port);
+        println!("  Bind address: {}", config.server.bind_address);
+        println!("  Max request size: {} bytes", config.server.max_request_bytes);
+        let limits = &config.server.span_limits;
+        println!(
+            "  Span limits: {} attributes, {} byte keys, {} char values, {} events",
+            limits.max_attributes,
+            limits.max_key_length,
+            limits.max_value_length,
+            limits.max_events
+        );
         println!("  Memory limit: {}MB", config.storage.max_memory_mb);
         println!("  Max spans: {}", config.storage.max_spans);
+        println!("  Sampling rate: {}", config.sampling.default_rate);
+        let mut per_service: Vec<_> = config.sampling.per_service.iter().collect();
+        per_service.sort_by(|a, b| a.0.cmp(b.0));
+        for (service, rate) in per_service {
+            println!("    {}: {}", service, rate);
+        }
+        if let Some(ref services) = config.receiver {
+            if !services.include_services.is_empty() {
+                println!("  Included services: {}", services.include_services.join(", "));
+            }
+            if !services.exclude_services.is_empty() {
+                println!("  Excluded services: {}", services.exclude_services.join(", "));
+            }
+        }
         return Ok(());
     }
 
@@ -281,7 +505,17 @@ async fn execute_subcommand(command: Commands, cli: &Cli) -> Result<()> {
             output,
             errors_only,
             limit,
+            no_metadata,
+            query,
+            critical_path_only,
+            redact,
+            redact_allow,
+            redact_placeholder,
         } => {
+            if let Some(query) = query {
+                return execute_aggregate_query(&query, output, cli).await;
+            }
+            let attribute_redaction = redaction_policy(redact, redact_allow, redact_placeholder);
             execute_export(
                 trace_id,
                 format,
@@ -292,13 +526,208 @@ async fn execute_subcommand(command: Commands, cli: &Cli) -> Result<()> {
                 output,
                 errors_only,
                 limit,
+                no_metadata,
+                critical_path_only,
+                attribute_redaction,
                 cli,
             )
             .await
         },
+        Commands::DebugDump { output, host } => execute_debug_dump(output, &host, cli).await,
+        Commands::Watch {
+            format,
+            service,
+            errors_only,
+        } => execute_watch(format, service, errors_only, cli).await,
+        Commands::Diff {
+            trace_a,
+            trace_b,
+            format,
+            output,
+            critical_path_only,
+        } => execute_diff(&trace_a, &trace_b, format, output, critical_path_only, cli).await,
+        Commands::Replay {
+            file,
+            speed,
+            shift_to_now,
+        } => execute_replay(&file, &speed, shift_to_now, cli).await,
     }
 }
 
+/// Print the differences between the spans of two stored traces
+async fn execute_diff(
+    trace_a: &str,
+    trace_b: &str,
+    format: DiffFormat,
+    output: Option<PathBuf>,
+    critical_path_only: bool,
+    cli: &Cli,
+) -> Result<()> {
+    use crate::{
+        core::TraceId,
+        export::{ExportOptions, TraceExporter},
+        storage::{backend_from_config, StorageBackend},
+    };
+    use std::io::IsTerminal;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let config = cli.load_config().await?;
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
+    let storage_guard = storage.read().await;
+
+    let options = ExportOptions {
+        critical_path_only,
+        ..Default::default()
+    };
+    let diff = TraceExporter::new(&*storage_guard)
+        .diff_traces(
+            &TraceId::new(trace_a.to_string())?,
+            &TraceId::new(trace_b.to_string())?,
+            &options,
+        )
+        .await?;
+
+    let content = match format {
+        DiffFormat::Text => diff.to_text(output.is_none() && std::io::stdout().is_terminal()),
+        DiffFormat::Json => format!("{}\n", serde_json::to_string_pretty(&diff)?),
+    };
+    match output {
+        Some(output_path) => tokio::fs::write(output_path, content)
+            .await
+            .map_err(|e| UrpoError::config(format!("Failed to write output: {}", e))),
+        None => {
+            print!("{}", content);
+            Ok(())
+        },
+    }
+}
+
+/// Replay recorded spans through the receiver pipeline into storage
+async fn execute_replay(
+    file: &std::path::Path,
+    speed: &str,
+    shift_to_now: bool,
+    cli: &Cli,
+) -> Result<()> {
+    use crate::{
+        monitoring::Monitor,
+        receiver::{replay, OtelReceiver},
+        storage::backend_from_config,
+    };
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let options = replay::ReplayOptions::default()
+        .with_speed(replay::parse_speed(speed)?)
+        .with_shift_to_now(shift_to_now);
+    let config = cli.load_config().await?;
+    let contents = tokio::fs::read_to_string(file).await.map_err(|e| {
+        UrpoError::config(format!("Cannot read replay file {}: {}", file.display(), e))
+    })?;
+    let spans = replay::parse_replay_file(&contents, &config.server.span_limits)?;
+
+    let receiver = configure_receiver(
+        OtelReceiver::new(
+            config.server.grpc_port,
+            config.server.http_port,
+            backend_from_config(&config)?,
+            Arc::new(Monitor::new()),
+        ),
+        &config,
+        cli,
+    )?;
+
+    eprintln!("Replaying {} spans from {}", spans.len(), file.display());
+    let mut last_report = Instant::now();
+    let done = receiver
+        .replay(spans, &options, |progress| {
+            if last_report.elapsed() >= Duration::from_secs(1) {
+                last_report = Instant::now();
+                eprintln!(
+                    "  {}/{} spans ({:.0} spans/sec)",
+                    progress.sent,
+                    progress.total,
+                    progress.spans_per_sec()
+                );
+            }
+        })
+        .await?;
+    println!(
+        "Replayed {} spans in {:.2}s ({:.0} spans/sec)",
+        done.sent,
+        done.elapsed.as_secs_f64(),
+        done.spans_per_sec()
+    );
+    Ok(())
+}
+
+/// Run the receivers and print every stored span to stdout until shutdown
+async fn execute_watch(
+    format: WatchFormat,
+    service: Option<String>,
+    errors_only: bool,
+    cli: &Cli,
+) -> Result<()> {
+    use crate::{
+        monitoring::Monitor,
+        receiver::OtelReceiver,
+        storage::{backend_from_config, StorageBackend},
+    };
+    use std::io::IsTerminal;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let config = cli.load_config().await?;
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
+
+    let (receiver, events) = configure_receiver(
+        OtelReceiver::new(
+            config.server.grpc_port,
+            config.server.http_port,
+            Arc::clone(&storage),
+            Arc::new(Monitor::new()),
+        ),
+        &config,
+        cli,
+    )?
+    .with_events();
+    let receiver = Arc::new(receiver);
+
+    tracing::info!(
+        "Watching spans on GRPC {} and HTTP {}",
+        std::net::SocketAddr::new(config.server.bind_address, config.server.grpc_port),
+        std::net::SocketAddr::new(config.server.bind_address, config.server.http_port)
+    );
+
+    let color = format == WatchFormat::Text && std::io::stdout().is_terminal();
+    let watcher = SpanWatcher::new(format)
+        .with_service(service)
+        .with_errors_only(errors_only)
+        .with_color(color);
+    let watch_handle = tokio::spawn(watch_spans(events, storage, watcher, std::io::stdout()));
+
+    let result = receiver.run_until(shutdown_signal()).await;
+    watch_handle.abort();
+    result
+}
+
+/// Export redaction policy from the `--redact*` flags, if any were given.
+fn redaction_policy(
+    redact: Vec<String>,
+    redact_allow: Vec<String>,
+    placeholder: String,
+) -> Option<RedactionPolicy> {
+    if redact.is_empty() && redact_allow.is_empty() {
+        return None;
+    }
+    let keys = AttributeFilter::new(
+        redact_allow.into_iter().map(Glob::new).collect(),
+        redact.into_iter().map(Glob::new).collect(),
+    );
+    Some(RedactionPolicy::new(keys).with_placeholder(Some(placeholder).filter(|p| !p.is_empty())))
+}
+
 /// Execute the export command
 async fn execute_export(
     trace_id: Option<String>,
@@ -310,12 +739,15 @@ async fn execute_export(
     output: Option<PathBuf>,
     errors_only: bool,
     limit: usize,
+    no_metadata: bool,
+    critical_path_only: bool,
+    attribute_redaction: Option<RedactionPolicy>,
     cli: &Cli,
 ) -> Result<()> {
     use crate::{
         core::TraceId,
-        export::{ExportFormat, ExportOptions, TraceExporter},
-        storage::{InMemoryStorage, StorageBackend},
+        export::{prepare_spans, ExportFormat, ExportOptions, TraceExporter},
+        storage::{backend_from_config, StorageBackend},
     };
     use std::sync::Arc;
     use std::time::SystemTime;
@@ -325,14 +757,19 @@ async fn execute_export(
     let config = cli.load_config().await?;
 
     // Initialize storage (read-only for export)
-    let storage: Arc<RwLock<dyn StorageBackend>> =
-        Arc::new(RwLock::new(InMemoryStorage::with_config(&config)));
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
     let storage_trait = Arc::clone(&storage);
 
     // Parse export format
     let export_format = format
         .parse::<ExportFormat>()
         .map_err(|_| UrpoError::config(format!("Invalid export format: {}", format)))?;
+    if export_format.is_binary() && output.is_none() {
+        return Err(UrpoError::config(format!(
+            "{} export is binary; write it to a file with --output",
+            export_format.name()
+        )));
+    }
 
     // Handle time filtering
     let (start_time, end_time) = if let Some(last_str) = last {
@@ -391,8 +828,16 @@ async fn execute_export(
             end_time: None,
             limit: Some(1),
             errors_only: false,
+            include_metadata: !no_metadata,
+            critical_path_only,
+            attribute_redaction,
         };
 
+        if let (ExportFormat::Parquet, Some(path)) = (export_format, &output) {
+            let spans = prepare_spans(spans, &export_options);
+            return trace_exporter.write_parquet(&spans, path);
+        }
+
         let export_result = trace_exporter
             .export_single_trace(&trace_id, &spans, &export_options)
             .await?;
@@ -415,23 +860,149 @@ async fn execute_export(
             end_time,
             limit: Some(limit),
             errors_only,
+            include_metadata: !no_metadata,
+            critical_path_only,
+            attribute_redaction,
         };
 
-        let export_result = trace_exporter.export_traces(&export_options).await?;
+        if let (ExportFormat::Parquet, Some(path)) = (export_format, &output) {
+            return trace_exporter
+                .export_traces_parquet(&export_options, path)
+                .await;
+        }
 
-        // Write output
-        if let Some(output_path) = output {
-            tokio::fs::write(output_path, export_result)
-                .await
-                .map_err(|e| UrpoError::config(format!("Failed to write output: {}", e)))?;
-        } else {
-            print!("{}", export_result);
+        // Stream to the destination so memory stays flat for large exports
+        match output {
+            Some(output_path) => {
+                let file = std::fs::File::create(&output_path)
+                    .map_err(|e| UrpoError::config(format!("Failed to write output: {}", e)))?;
+                trace_exporter
+                    .write_traces(&export_options, std::io::BufWriter::new(file))
+                    .await?;
+            },
+            None => {
+                trace_exporter
+                    .write_traces(&export_options, std::io::BufWriter::new(std::io::stdout()))
+                    .await?;
+            },
         }
     }
 
     Ok(())
 }
 
+/// Run an aggregate query and print the result as a table
+async fn execute_aggregate_query(query: &str, output: Option<PathBuf>, cli: &Cli) -> Result<()> {
+    use crate::query::{parse_aggregate_query, QueryExecutor};
+    use crate::storage::{backend_from_config, StorageBackend};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let query = parse_aggregate_query(query)?;
+    let config = cli.load_config().await?;
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
+
+    let result = QueryExecutor::new(storage)
+        .execute_aggregate(query.clone())
+        .await?;
+    let table = format_aggregate_table(&query.aggregate, &result);
+
+    if let Some(output_path) = output {
+        tokio::fs::write(output_path, table)
+            .await
+            .map_err(|e| UrpoError::config(format!("Failed to write output: {}", e)))?;
+    } else {
+        print!("{}", table);
+    }
+    Ok(())
+}
+
+/// Render an aggregate result as a plain-text table
+fn format_aggregate_table(
+    aggregate: &crate::query::AggregateExpr,
+    result: &crate::query::AggregateResult,
+) -> String {
+    use crate::query::{AggregateExpr, AggregateResult};
+    use std::fmt::Write;
+
+    let mut table = String::new();
+    match result {
+        AggregateResult::Count(count) => {
+            let _ = writeln!(table, "{:>8}", "COUNT");
+            let _ = writeln!(table, "{:>8}", count);
+        },
+        AggregateResult::Groups(groups) => {
+            let field = match aggregate {
+                AggregateExpr::CountGroupBy(field) => field.as_str(),
+                AggregateExpr::Count => "group",
+            };
+            let width = groups
+                .keys()
+                .map(String::len)
+                .chain(std::iter::once(field.len()))
+                .max()
+                .unwrap_or(0);
+            let _ = writeln!(table, "{:<width$}  {:>8}", field.to_uppercase(), "COUNT");
+            for (key, count) in groups {
+                let key = if key.is_empty() { "-" } else { key.as_str() };
+                let _ = writeln!(table, "{:<width$}  {:>8}", key, count);
+            }
+        },
+    }
+    table
+}
+
+/// Execute the debug-dump command
+async fn execute_debug_dump(output: Option<PathBuf>, host: &str, cli: &Cli) -> Result<()> {
+    use http_body_util::{BodyExt, Empty};
+    use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+
+    let url = format!("http://{}:{}/api/debug/dump", host, cli.api_port);
+    let uri: hyper::Uri = url
+        .parse()
+        .map_err(|e| UrpoError::config(format!("Invalid API address {}: {}", url, e)))?;
+
+    let mut request = hyper::Request::get(uri);
+    if let Some(key) = cli.api_keys.first() {
+        request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {}", key));
+    }
+    let request = request
+        .body(Empty::<bytes::Bytes>::new())
+        .map_err(|e| UrpoError::config(format!("Invalid debug dump request: {}", e)))?;
+
+    let client = Client::builder(TokioExecutor::new()).build_http::<Empty<bytes::Bytes>>();
+    let response = client.request(request).await.map_err(|e| {
+        UrpoError::network(format!("Failed to reach {} (is urpo running with --api?): {}", url, e))
+    })?;
+
+    let status = response.status();
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| UrpoError::network(format!("Failed to read debug dump: {}", e)))?
+        .to_bytes();
+
+    if !status.is_success() {
+        return Err(UrpoError::network(format!(
+            "Debug dump request failed with {}: {}",
+            status,
+            String::from_utf8_lossy(&body)
+        )));
+    }
+
+    if let Some(output_path) = output {
+        tokio::fs::write(&output_path, &body)
+            .await
+            .map_err(|e| UrpoError::config(format!("Failed to write output: {}", e)))?;
+        eprintln!("Debug dump written to {}", output_path.display());
+    } else {
+        println!("{}", String::from_utf8_lossy(&body));
+    }
+
+    Ok(())
+}
+
 /// Parse a duration string like "1h", "30m", "24h"
 fn parse_duration(s: &str) -> Option<std::time::Duration> {
     use std::time::Duration;
@@ -472,54 +1043,285 @@ fn parse_timestamp(s: &str) -> Result<u64> {
     Ok(dt.timestamp_nanos_opt().unwrap_or(0) as u64)
 }
 
+/// Spawn the Prometheus remote-write pusher if an endpoint was configured.
+#[cfg(feature = "remote-write")]
+fn spawn_remote_write(
+    cli: &Cli,
+    storage: std::sync::Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>>,
+) -> Result<Option<tokio::task::JoinHandle<()>>> {
+    use crate::export::remote_write::{RemoteWriteAuth, RemoteWriteClient, RemoteWriteConfig};
+
+    let Some(endpoint) = cli.remote_write_url.clone() else {
+        return Ok(None);
+    };
+
+    let interval = parse_duration(&cli.remote_write_interval).ok_or_else(|| {
+        UrpoError::config(format!("Invalid remote-write interval: {}", cli.remote_write_interval))
+    })?;
+
+    let config = RemoteWriteConfig {
+        endpoint,
+        auth: cli
+            .remote_write_token
+            .clone()
+            .map(|token| RemoteWriteAuth::Bearer { token }),
+        interval,
+        ..Default::default()
+    };
+
+    tracing::info!("  Prometheus remote-write to {} every {:?}", config.endpoint, interval);
+    let client = RemoteWriteClient::new(config, storage)?;
+    Ok(Some(tokio::spawn(client.run())))
+}
+
+/// Start evaluating `alert_rules`, if any are configured.
+fn spawn_alerts(
+    config: &Config,
+    storage: std::sync::Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>>,
+) -> Result<Option<tokio::task::JoinHandle<()>>> {
+    if config.alert_rules.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "alerting")]
+    {
+        use crate::monitoring::alerting::AlertEngine;
+
+        let interval = config.monitoring.health_check_interval;
+        tracing::info!("  {} alert rule(s) checked every {:?}", config.alert_rules.len(), interval);
+        let engine = AlertEngine::new(config.alert_rules.clone(), storage)?
+            .with_min_sample_size(config.monitoring.alerts.min_sample_size);
+        Ok(Some(tokio::spawn(engine.run(interval))))
+    }
+
+    #[cfg(not(feature = "alerting"))]
+    {
+        let _ = storage;
+        tracing::warn!(
+            "{} alert rule(s) configured but urpo was built without the `alerting` feature",
+            config.alert_rules.len()
+        );
+        Ok(None)
+    }
+}
+
+/// Apply config- and flag-driven receiver options.
+fn configure_receiver(
+    receiver: crate::receiver::OtelReceiver,
+    config: &Config,
+    cli: &Cli,
+) -> Result<crate::receiver::OtelReceiver> {
+    let receiver = receiver
+        .with_bind_address(config.server.bind_address)
+        .with_max_request_bytes(config.server.max_request_bytes)
+        .with_max_concurrent_streams(config.server.max_concurrent_streams)
+        .with_request_timeout(config.server.request_timeout)
+        .with_cors_allowed_origins(config.server.cors_allowed_origins.clone())
+        .with_grpc_web(config.server.grpc_web)
+        .with_apply_traceparent(config.server.apply_traceparent)
+        .with_restart_policy(config.server.restart)
+        .with_span_limits(config.server.span_limits)
+        .with_grpc_uds_path(config.server.grpc_uds_path.clone())
+        .with_http_uds_path(config.server.http_uds_path.clone())
+        .with_uds_mode(config.server.uds_mode);
+    let capture = match cli.capture_dir {
+        Some(ref dir) => crate::receiver::capture::WireCapture::new().with_directory(dir),
+        None => crate::receiver::capture::WireCapture::new(),
+    };
+    let receiver = receiver.with_wire_capture(std::sync::Arc::new(capture));
+    let receiver = match config.sampling.fairness {
+        Some(ref fairness) => receiver.with_fair_sampling(fairness.clone()),
+        None => receiver,
+    };
+    let receiver = match config.sampling.max_spans_per_second {
+        Some(limit) => receiver.with_rate_limiting_sampler(limit),
+        None => receiver,
+    };
+    let receiver = receiver
+        .with_service_rates(crate::sampling::ServiceRates::from_config(&config.sampling))
+        .with_sampling_rules(crate::sampling::SamplingRules::from_config(&config.sampling));
+    let receiver = match config.attributes {
+        Some(ref attributes) => {
+            receiver.with_attribute_filter(crate::core::AttributeFilter::from_config(attributes))
+        },
+        None => receiver,
+    };
+    let receiver = match config.receiver {
+        Some(ref services) => {
+            receiver.with_service_filter(crate::core::ServiceFilter::from_config(services))
+        },
+        None => receiver,
+    };
+    let receiver = match config.rate_limit {
+        Some(ref rate_limit) => receiver.with_rate_limiter(std::sync::Arc::new(
+            crate::receiver::rate_limit::RateLimiter::new(rate_limit.clone()),
+        )),
+        None => receiver,
+    };
+    let receiver = match config.redaction {
+        Some(ref redaction) => {
+            receiver.with_redaction(crate::core::Redactor::from_config(redaction)?)
+        },
+        None => receiver,
+    };
+    let receiver = match cli.forward_otlp {
+        Some(ref endpoint) => {
+            tracing::info!("  Forwarding spans to OTLP collector at {}", endpoint);
+            receiver.with_forwarding(endpoint)?
+        },
+        None => receiver,
+    };
+    match cli.export_to_jaeger {
+        Some(ref endpoint) => {
+            tracing::info!("  Exporting spans to Jaeger at {}", endpoint);
+            receiver.with_jaeger_export(endpoint)
+        },
+        None => Ok(receiver),
+    }
+}
+
+/// Spawn the Jaeger collector endpoint if a port was configured.
+fn spawn_jaeger(
+    config: &Config,
+    receiver: &std::sync::Arc<crate::receiver::OtelReceiver>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let port = config.server.jaeger_port?;
+
+    #[cfg(feature = "jaeger")]
+    {
+        let addr = std::net::SocketAddr::new(config.server.bind_address, port);
+        let receiver = std::sync::Arc::clone(receiver);
+        tracing::info!("  Jaeger Thrift receiver on {}", addr);
+        Some(tokio::spawn(async move {
+            if let Err(e) = receiver.start_jaeger(addr).await {
+                tracing::error!("Jaeger receiver error: {}", e);
+            }
+        }))
+    }
+
+    #[cfg(not(feature = "jaeger"))]
+    {
+        let _ = receiver;
+        tracing::warn!(
+            "Jaeger port {} configured but urpo was built without the `jaeger` feature",
+            port
+        );
+        None
+    }
+}
+
+/// Watch the config file and apply reloaded redaction rules and sampling
+/// rates to `receiver`.
+fn spawn_config_watch(
+    config: &Config,
+    cli: &Cli,
+    receiver: &crate::receiver::OtelReceiver,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let path = cli.config_file()?;
+    let watcher = crate::core::ConfigWatcher::new(path, config.clone());
+    let handle = receiver.watch_config(watcher.subscribe());
+
+    // The watcher blocks on file events, so it gets its own thread
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::spawn(move || {
+        if let Err(e) = runtime.block_on(watcher.watch()) {
+            tracing::error!("Config watcher error: {}", e);
+        }
+    });
+    Some(handle)
+}
+
+/// Spawn the Kafka source if one was configured.
+fn spawn_kafka(
+    config: &Config,
+    receiver: &std::sync::Arc<crate::receiver::OtelReceiver>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let kafka = config.kafka.clone()?;
+
+    #[cfg(feature = "kafka")]
+    {
+        let receiver = std::sync::Arc::clone(receiver);
+        tracing::info!("  Kafka source from topic {}", kafka.topic);
+        Some(tokio::spawn(async move {
+            if let Err(e) = receiver.start_kafka(kafka).await {
+                tracing::error!("Kafka source error: {}", e);
+            }
+        }))
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    {
+        let _ = receiver;
+        tracing::warn!(
+            "Kafka source for topic {} configured but urpo was built without the `kafka` feature",
+            kafka.topic
+        );
+        None
+    }
+}
+
 async fn start_with_ui(config: Config, cli: &Cli) -> Result<()> {
     use crate::{
-        api::{start_server as start_api_server, ApiConfig},
+        api::{start_server_with_debug as start_api_server, ApiConfig, DebugContext},
         monitoring::Monitor,
         receiver::OtelReceiver,
-        storage::{InMemoryStorage, StorageBackend},
+        storage::{backend_from_config, StorageBackend},
     };
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
     // Initialize storage
-    let storage: Arc<RwLock<dyn StorageBackend>> =
-        Arc::new(RwLock::new(InMemoryStorage::with_config(&config)));
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
     let storage_trait = Arc::clone(&storage);
 
-    // Initialize health monitor
+    // Initialize health monitor with per-service baselines; its background
+    // loop also drives the receiver's health probes
     let health_monitor = Arc::new(Monitor::new());
+    health_monitor.start().await?;
+    let baseline_handle = health_monitor.start_baselines(Arc::clone(&storage_trait));
 
     // Fake span generator completely removed - using real OTEL data only
 
     // Start OTEL receivers
-    let receiver = Arc::new(OtelReceiver::new(
-        config.server.grpc_port,
-        config.server.http_port,
-        Arc::clone(&storage_trait),
-        Arc::clone(&health_monitor),
-    ));
-
-    let receiver_clone = Arc::clone(&receiver);
-    let receiver_handle = tokio::spawn(async move {
-        if let Err(e) = receiver_clone.run().await {
-            tracing::error!("OTEL receiver error: {}", e);
-        }
-    });
+    let receiver = Arc::new(configure_receiver(
+        OtelReceiver::new(
+            config.server.grpc_port,
+            config.server.http_port,
+            Arc::clone(&storage_trait),
+            Arc::clone(&health_monitor),
+        ),
+        &config,
+        cli,
+    )?);
+
+    let receiver_handle = Arc::clone(&receiver).spawn();
 
     // Start HTTP API server if enabled
     let api_handle = if cli.api {
         let api_storage = Arc::clone(&storage_trait);
         let api_config = ApiConfig {
             port: cli.api_port,
+            bind_address: config.server.bind_address,
             enable_cors: true,
             max_results: 1000,
+            apdex_target: config.monitoring.apdex_target,
+            ingest_lag_threshold: config.monitoring.alerts.ingest_lag_threshold,
+            sampling_rates: Some(Arc::clone(receiver.service_rates())),
+            rate_limiter: receiver.rate_limiter().cloned(),
+            receiver_stats: Some(Arc::clone(receiver.shared_stats())),
+            api_keys: cli.api_keys.clone(),
+        };
+
+        let debug = DebugContext {
+            config: Some(Arc::new(config.clone())),
+            span_pool: Some(Arc::clone(receiver.span_pool())),
+            capture: receiver.wire_capture().cloned(),
         };
 
         tracing::info!("Starting HTTP API server on port {}...", cli.api_port);
 
         Some(tokio::spawn(async move {
-            if let Err(e) = start_api_server(api_storage, api_config).await {
+            if let Err(e) = start_api_server(api_storage, api_config, debug).await {
                 tracing::error!("API server error: {}", e);
             }
         }))
@@ -527,62 +1329,96 @@ async fn start_with_ui(config: Config, cli: &Cli) -> Result<()> {
         None
     };
 
-    // Keep receivers running (GUI is separate via Tauri)
-    tracing::info!("Receivers started - use Tauri GUI to view data");
-    tracing::info!("  GRPC receiver on port {}", config.server.grpc_port);
-    tracing::info!("  HTTP receiver on port {}", config.server.http_port);
+    #[cfg(feature = "remote-write")]
+    let remote_write_handle = spawn_remote_write(cli, Arc::clone(&storage_trait))?;
 
-    // Wait for shutdown signal
-    let shutdown = tokio::signal::ctrl_c();
+    let jaeger_handle = spawn_jaeger(&config, &receiver);
+    let kafka_handle = spawn_kafka(&config, &receiver);
+    let config_watch_handle = spawn_config_watch(&config, cli, &receiver);
 
-    tokio::select! {
-        _ = receiver_handle => {
-            tracing::error!("Receiver stopped unexpectedly");
-        }
-        _ = shutdown => {
-            tracing::info!("Received shutdown signal, stopping...");
-        }
+    // Keep receivers running (GUI is separate via Tauri)
+    tracing::info!("Receivers started - use Tauri GUI to view data");
+    tracing::info!(
+        "  GRPC receiver on {}",
+        std::net::SocketAddr::new(config.server.bind_address, config.server.grpc_port)
+    );
+    tracing::info!(
+        "  HTTP receiver on {}",
+        std::net::SocketAddr::new(config.server.bind_address, config.server.http_port)
+    );
+
+    // Wait for shutdown signal, then drain the receivers
+    shutdown_signal().await;
+    if let Err(e) = receiver_handle.shutdown().await {
+        tracing::error!("Receiver error: {}", e);
     }
 
     // Cleanup
+    health_monitor.stop();
+    baseline_handle.abort();
     if let Some(handle) = api_handle {
         handle.abort();
     }
+    #[cfg(feature = "remote-write")]
+    if let Some(handle) = remote_write_handle {
+        handle.abort();
+    }
+    if let Some(handle) = jaeger_handle {
+        handle.abort();
+    }
+    if let Some(handle) = kafka_handle {
+        handle.abort();
+    }
+    if let Some(handle) = config_watch_handle {
+        handle.abort();
+    }
 
     Ok(())
 }
 
 async fn start_headless(config: Config, cli: &Cli) -> Result<()> {
     use crate::{
-        api::{start_server as start_api_server, ApiConfig},
+        api::{start_server_with_debug as start_api_server, ApiConfig, DebugContext},
         monitoring::Monitor,
         receiver::OtelReceiver,
-        storage::{InMemoryStorage, StorageBackend},
+        storage::{backend_from_config, StorageBackend},
     };
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
     // Initialize storage
-    let storage: Arc<RwLock<dyn StorageBackend>> =
-        Arc::new(RwLock::new(InMemoryStorage::with_config(&config)));
+    let storage: Arc<RwLock<dyn StorageBackend>> = backend_from_config(&config)?;
     let storage_trait = Arc::clone(&storage);
 
-    // Initialize health monitor
+    // Initialize health monitor with per-service baselines; its background
+    // loop also drives the receiver's health probes
     let health_monitor = Arc::new(Monitor::new());
+    health_monitor.start().await?;
+    let baseline_handle = health_monitor.start_baselines(Arc::clone(&storage_trait));
 
     // Fake span generator completely removed - using real OTEL data only
 
     // Start OTEL receivers
-    let receiver = Arc::new(OtelReceiver::new(
-        config.server.grpc_port,
-        config.server.http_port,
-        Arc::clone(&storage_trait),
-        health_monitor,
-    ));
+    let receiver = Arc::new(configure_receiver(
+        OtelReceiver::new(
+            config.server.grpc_port,
+            config.server.http_port,
+            Arc::clone(&storage_trait),
+            Arc::clone(&health_monitor),
+        ),
+        &config,
+        cli,
+    )?);
 
     tracing::info!("Urpo running in headless mode");
-    tracing::info!("  GRPC receiver on port {}", config.server.grpc_port);
-    tracing::info!("  HTTP receiver on port {}", config.server.http_port);
+    tracing::info!(
+        "  GRPC receiver on {}",
+        std::net::SocketAddr::new(config.server.bind_address, config.server.grpc_port)
+    );
+    tracing::info!(
+        "  HTTP receiver on {}",
+        std::net::SocketAddr::new(config.server.bind_address, config.server.http_port)
+    );
 
     // Start API server if enabled
     if cli.api {
@@ -590,35 +1426,78 @@ async fn start_headless(config: Config, cli: &Cli) -> Result<()> {
         let api_storage = Arc::clone(&storage_trait);
         let api_config = ApiConfig {
             port: cli.api_port,
+            bind_address: config.server.bind_address,
             enable_cors: true,
             max_results: 1000,
+            apdex_target: config.monitoring.apdex_target,
+            ingest_lag_threshold: config.monitoring.alerts.ingest_lag_threshold,
+            sampling_rates: Some(Arc::clone(receiver.service_rates())),
+            rate_limiter: receiver.rate_limiter().cloned(),
+            receiver_stats: Some(Arc::clone(receiver.shared_stats())),
+            api_keys: cli.api_keys.clone(),
+        };
+        let debug = DebugContext {
+            config: Some(Arc::new(config.clone())),
+            span_pool: Some(Arc::clone(receiver.span_pool())),
+            capture: receiver.wire_capture().cloned(),
         };
 
         tokio::spawn(async move {
-            if let Err(e) = start_api_server(api_storage, api_config).await {
+            if let Err(e) = start_api_server(api_storage, api_config, debug).await {
                 tracing::error!("API server error: {}", e);
             }
         });
     }
 
-    // Wait for shutdown signal
-    let shutdown = tokio::signal::ctrl_c();
+    #[cfg(feature = "remote-write")]
+    let _remote_write_handle = spawn_remote_write(cli, Arc::clone(&storage_trait))?;
 
-    tokio::select! {
-        result = receiver.run() => {
-            if let Err(e) = result {
-                tracing::error!("Receiver error: {}", e);
-                return Err(e);
-            }
-        }
-        _ = shutdown => {
-            tracing::info!("Received shutdown signal, stopping...");
-        }
+    let alert_handle = spawn_alerts(&config, Arc::clone(&storage_trait))?;
+    let _jaeger_handle = spawn_jaeger(&config, &receiver);
+    let _kafka_handle = spawn_kafka(&config, &receiver);
+    let _config_watch_handle = spawn_config_watch(&config, cli, &receiver);
+
+    // Run until ctrl-c or SIGTERM, then drain in-flight requests and batches
+    let result = receiver.run_until(shutdown_signal()).await;
+    health_monitor.stop();
+    baseline_handle.abort();
+    if let Some(handle) = alert_handle {
+        handle.abort();
+    }
+    if let Err(e) = result {
+        tracing::error!("Receiver error: {}", e);
+        return Err(e);
     }
 
     Ok(())
 }
 
+/// Resolve on ctrl-c or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            },
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            },
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+
+    tracing::info!("Received shutdown signal, draining receivers...");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -631,6 +1510,10 @@ mod tests {
             command: None,
             grpc_port: None,
             http_port: None,
+            bind_address: None,
+            max_request_bytes: None,
+            max_concurrent_streams: None,
+            request_timeout: None,
             memory_limit: None,
             config: None,
             no_fake: false,
@@ -638,9 +1521,22 @@ mod tests {
             headless: false,
             terminal: true,
             check_config: false,
+            watch: false,
             version: false,
             api: false,
             api_port: 8080,
+            api_keys: Vec::new(),
+            #[cfg(feature = "remote-write")]
+            remote_write_url: None,
+            #[cfg(feature = "remote-write")]
+            remote_write_token: None,
+            #[cfg(feature = "remote-write")]
+            remote_write_interval: "15s".to_string(),
+            #[cfg(feature = "jaeger")]
+            jaeger_port: None,
+            export_to_jaeger: None,
+            forward_otlp: None,
+            capture_dir: None,
         };
 
         assert!(!cli.debug);
@@ -650,6 +1546,22 @@ mod tests {
         assert_eq!(cli.api_port, 8080);
     }
 
+    #[test]
+    fn test_format_aggregate_table() {
+        use crate::query::{AggregateExpr, AggregateResult};
+        use std::collections::BTreeMap;
+
+        let groups = BTreeMap::from([("checkout".to_string(), 12), ("db".to_string(), 3)]);
+        let table = format_aggregate_table(
+            &AggregateExpr::CountGroupBy("service".to_string()),
+            &AggregateResult::Groups(groups),
+        );
+        assert_eq!(table, "SERVICE      COUNT\ncheckout        12\ndb               3\n");
+
+        let table = format_aggregate_table(&AggregateExpr::Count, &AggregateResult::Count(7));
+        assert_eq!(table, "   COUNT\n       7\n");
+    }
+
     #[test]
     fn test_parse_duration() {
         assert_eq!(parse_duration("1s"), Some(Duration::from_secs(1)));
@@ -0,0 +1,314 @@
+//! `urpo watch`: stream newly received spans to stdout.
+//!
+//! The watcher subscribes to the receiver's [`TraceEvent`] broadcast and, for
+//! each event, reads the trace from storage and prints the spans it has not
+//! printed yet, one line per span.
+
+use crate::core::{Result, Span, SpanId, TraceId};
+use crate::receiver::TraceEvent;
+use crate::storage::StorageBackend;
+use chrono::{DateTime, SecondsFormat, Utc};
+use colored::Colorize;
+use std::collections::HashSet;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+/// Traces whose printed spans are remembered, so updates print only new spans.
+const WATCHED_TRACES: usize = 10_000;
+
+/// Output format of `urpo watch`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum WatchFormat {
+    /// `timestamp service operation duration status`, colored on a terminal
+    #[default]
+    Text,
+    /// One JSON object per span
+    Json,
+}
+
+/// Turns trace events into output lines for the spans not printed yet.
+pub struct SpanWatcher {
+    format: WatchFormat,
+    service: Option<String>,
+    errors_only: bool,
+    color: bool,
+    printed: lru::LruCache<TraceId, HashSet<SpanId>>,
+}
+
+impl SpanWatcher {
+    /// Watch every span, printed uncolored in `format`.
+    pub fn new(format: WatchFormat) -> Self {
+        Self {
+            format,
+            service: None,
+            errors_only: false,
+            color: false,
+            printed: lru::LruCache::new(NonZeroUsize::new(WATCHED_TRACES).expect("non-zero")),
+        }
+    }
+
+    /// Only print spans of `service`.
+    pub fn with_service(mut self, service: Option<String>) -> Self {
+        self.service = service;
+        self
+    }
+
+    /// Only print error spans.
+    pub fn with_errors_only(mut self, errors_only: bool) -> Self {
+        self.errors_only = errors_only;
+        self
+    }
+
+    /// Color text output with ANSI escapes.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Lines for the spans of `event`'s trace that pass the filters and
+    /// were not printed before, oldest first.
+    pub async fn lines(
+        &mut self,
+        event: &TraceEvent,
+        storage: &dyn StorageBackend,
+    ) -> Result<Vec<String>> {
+        let trace_id = TraceId::new(event.trace_id.clone())?;
+        let mut spans = storage.get_trace_spans(&trace_id).await?;
+        spans.sort_by_key(|span| span.start_time);
+
+        let printed = self.printed.get_or_insert_mut(trace_id, HashSet::new);
+        let new_spans: Vec<Span> = spans
+            .into_iter()
+            .filter(|span| printed.insert(span.span_id.clone()))
+            .collect();
+        Ok(new_spans
+            .iter()
+            .filter(|span| {
+                self.service
+                    .as_deref()
+                    .map_or(true, |service| span.service_name.as_str() == service)
+                    && (!self.errors_only || span.is_error())
+            })
+            .map(|span| self.format_span(span))
+            .collect())
+    }
+
+    fn format_span(&self, span: &Span) -> String {
+        let timestamp =
+            DateTime::<Utc>::from(span.start_time).to_rfc3339_opts(SecondsFormat::Millis, true);
+        let duration_ms = span.duration.as_secs_f64() * 1000.0;
+        let status = if span.is_error() { "ERROR" } else { "OK" };
+
+        match self.format {
+            WatchFormat::Json => serde_json::json!({
+                "timestamp": timestamp,
+                "trace_id": span.trace_id.as_str(),
+                "span_id": span.span_id.as_str(),
+                "service_name": span.service_name.as_str(),
+                "operation_name": span.operation_name,
+                "duration_ms": duration_ms,
+                "status": status,
+            })
+            .to_string(),
+            WatchFormat::Text if self.color => format!(
+                "{} {} {} {} {}",
+                timestamp.dimmed(),
+                span.service_name.as_str().cyan(),
+                span.operation_name.bold(),
+                format!("{:.2}ms", duration_ms).yellow(),
+                if span.is_error() {
+                    status.red()
+                } else {
+                    status.green()
+                }
+            ),
+            WatchFormat::Text => format!(
+                "{} {} {} {:.2}ms {}",
+                timestamp,
+                span.service_name.as_str(),
+                span.operation_name,
+                duration_ms,
+                status
+            ),
+        }
+    }
+}
+
+/// Write the new spans of every event to `out` until the event channel closes.
+pub async fn watch_spans(
+    mut events: broadcast::Receiver<TraceEvent>,
+    storage: Arc<RwLock<dyn StorageBackend>>,
+    mut watcher: SpanWatcher,
+    mut out: impl Write,
+) -> Result<()> {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Watch fell behind, skipped {} trace events", skipped);
+                continue;
+            },
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        let lines = watcher.lines(&event, &*storage.read().await).await;
+        match lines {
+            Ok(lines) => {
+                for line in lines {
+                    writeln!(out, "{}", line)?;
+                }
+                out.flush()?;
+            },
+            Err(e) => tracing::warn!("Cannot read spans of trace {}: {}", event.trace_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::receiver::{http::create_http_router, OtelReceiver};
+    use crate::storage::InMemoryStorage;
+    use axum::body::Body;
+    use axum::http::Request;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{status, ResourceSpans, ScopeSpans, Status};
+    use prost::Message;
+    use tower::ServiceExt;
+
+    fn export_request(service: &str, spans: &[(u8, &str, bool)]) -> Vec<u8> {
+        let spans = spans
+            .iter()
+            .map(|&(id, name, error)| opentelemetry_proto::tonic::trace::v1::Span {
+                trace_id: vec![1; 16],
+                span_id: vec![id; 8],
+                name: name.to_string(),
+                start_time_unix_nano: 1_700_000_000_000_000_000 + u64::from(id) * 1_000_000,
+                end_time_unix_nano: 1_700_000_000_250_000_000 + u64::from(id) * 1_000_000,
+                status: error.then(|| Status {
+                    code: status::StatusCode::Error as i32,
+                    message: "declined".to_string(),
+                }),
+                ..Default::default()
+            })
+            .collect();
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue(service.to_string())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_watch_prints_received_spans() {
+        let storage: Arc<RwLock<dyn StorageBackend>> =
+            Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+        let (receiver, events) = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_events();
+        let otlp = create_http_router(Arc::new(receiver));
+
+        for request in [
+            export_request("checkout", &[(1, "GET /checkout", false)]),
+            // A second batch of the same trace prints only its new spans
+            export_request("payments", &[(2, "charge", true), (3, "refund", false)]),
+        ] {
+            let response = otlp
+                .clone()
+                .oneshot(
+                    Request::post("/v1/traces")
+                        .header("content-type", "application/x-protobuf")
+                        .body(Body::from(request))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+        }
+        drop(otlp);
+
+        let mut out = Vec::new();
+        watch_spans(events, Arc::clone(&storage), SpanWatcher::new(WatchFormat::Text), &mut out)
+            .await
+            .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "2023-11-14T22:13:20.001Z checkout GET /checkout 250.00ms OK",
+                "2023-11-14T22:13:20.002Z payments charge 250.00ms ERROR",
+                "2023-11-14T22:13:20.003Z payments refund 250.00ms OK",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_filters_and_json_format() {
+        let storage: Arc<RwLock<dyn StorageBackend>> =
+            Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+        let (receiver, events) = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_events();
+        let otlp = create_http_router(Arc::new(receiver));
+        for request in [
+            export_request("checkout", &[(1, "GET /checkout", true)]),
+            export_request("payments", &[(2, "charge", true), (3, "refund", false)]),
+        ] {
+            otlp.clone()
+                .oneshot(
+                    Request::post("/v1/traces")
+                        .header("content-type", "application/x-protobuf")
+                        .body(Body::from(request))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        drop(otlp);
+
+        let watcher = SpanWatcher::new(WatchFormat::Json)
+            .with_service(Some("payments".to_string()))
+            .with_errors_only(true);
+        let mut out = Vec::new();
+        watch_spans(events, storage, watcher, &mut out)
+            .await
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["service_name"], "payments");
+        assert_eq!(lines[0]["operation_name"], "charge");
+        assert_eq!(lines[0]["status"], "ERROR");
+        assert_eq!(lines[0]["duration_ms"], 250.0);
+    }
+}
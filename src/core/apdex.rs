@@ -0,0 +1,177 @@
+//! Apdex (Application Performance Index) scoring.
+//!
+//! Given a target latency T, each request is *satisfied* (duration <= T),
+//! *tolerating* (duration <= 4T) or *frustrated* (slower, or failed). The
+//! score is `(satisfied + tolerating / 2) / total`, from 0.0 to 1.0.
+
+use super::types::Span;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Tolerating requests are at most this many times the target.
+const TOLERATING_FACTOR: u32 = 4;
+
+/// Satisfied/tolerating/frustrated request counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Apdex {
+    /// Requests at or under the target
+    pub satisfied: u64,
+    /// Requests at or under four times the target
+    pub tolerating: u64,
+    /// Slower requests and errors
+    pub frustrated: u64,
+}
+
+impl Apdex {
+    /// Classify a set of latencies against the target.
+    pub fn from_durations<I>(durations: I, target: Duration) -> Self
+    where
+        I: IntoIterator<Item = Duration>,
+    {
+        let mut apdex = Self::default();
+        for duration in durations {
+            apdex.record(duration, target);
+        }
+        apdex
+    }
+
+    /// Classify one request by latency.
+    pub fn record(&mut self, duration: Duration, target: Duration) {
+        if duration <= target {
+            self.satisfied += 1;
+        } else if duration <= target.saturating_mul(TOLERATING_FACTOR) {
+            self.tolerating += 1;
+        } else {
+            self.frustrated += 1;
+        }
+    }
+
+    /// Classify a span; error spans are always frustrated.
+    pub fn record_span(&mut self, span: &Span, target: Duration) {
+        if span.status.is_error() {
+            self.frustrated += 1;
+        } else {
+            self.record(span.duration, target);
+        }
+    }
+
+    /// Total number of requests classified.
+    pub fn total(&self) -> u64 {
+        self.satisfied + self.tolerating + self.frustrated
+    }
+
+    /// Apdex score in `0.0..=1.0`, or `None` without samples.
+    pub fn score(&self) -> Option<f64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        Some((self.satisfied as f64 + self.tolerating as f64 / 2.0) / total as f64)
+    }
+}
+
+/// Apdex for one operation of a service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationApdex {
+    /// Service name
+    pub service: String,
+    /// Operation name
+    pub operation: String,
+    /// Target latency T in milliseconds
+    pub target_ms: u64,
+    /// Request counts
+    pub apdex: Apdex,
+    /// Apdex score, absent without samples
+    pub score: Option<f64>,
+}
+
+/// Compute apdex per (service, operation), sorted by service then operation.
+pub fn operation_apdex(spans: &[Span], target: Duration) -> Vec<OperationApdex> {
+    let mut operations: BTreeMap<(&str, &str), Apdex> = BTreeMap::new();
+    for span in spans {
+        operations
+            .entry((span.service_name.as_str(), span.operation_name.as_str()))
+            .or_default()
+            .record_span(span, target);
+    }
+
+    operations
+        .into_iter()
+        .map(|((service, operation), apdex)| OperationApdex {
+            service: service.to_string(),
+            operation: operation.to_string(),
+            target_ms: target.as_millis() as u64,
+            apdex,
+            score: apdex.score(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanStatus, TraceId};
+    use std::time::SystemTime;
+
+    fn span(id: usize, operation: &str, millis: u64, status: SpanStatus) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("trace_{}", id)).unwrap())
+            .span_id(SpanId::new(format!("span_{}", id)).unwrap())
+            .service_name(ServiceName::new("checkout".to_string()).unwrap())
+            .operation_name(operation)
+            .start_time(SystemTime::now())
+            .duration(Duration::from_millis(millis))
+            .status(status)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_apdex_score_for_known_latencies() {
+        let target = Duration::from_millis(100);
+        // 4 satisfied (<= 100ms), 3 tolerating (<= 400ms), 3 frustrated
+        let latencies = [10, 50, 99, 100, 101, 250, 400, 401, 1_000, 5_000];
+
+        let apdex = Apdex::from_durations(latencies.map(Duration::from_millis), target);
+
+        assert_eq!(apdex.satisfied, 4);
+        assert_eq!(apdex.tolerating, 3);
+        assert_eq!(apdex.frustrated, 3);
+        assert_eq!(apdex.total(), 10);
+        // (4 + 3/2) / 10
+        assert!((apdex.score().unwrap() - 0.55).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_apdex_empty_has_no_score() {
+        assert_eq!(Apdex::default().score(), None);
+    }
+
+    #[test]
+    fn test_operation_apdex() {
+        let spans = vec![
+            span(0, "charge", 20, SpanStatus::Ok),
+            span(1, "charge", 300, SpanStatus::Ok),
+            span(2, "charge", 20, SpanStatus::Error("declined".to_string())),
+            span(3, "refund", 50, SpanStatus::Ok),
+        ];
+
+        let result = operation_apdex(&spans, Duration::from_millis(100));
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].operation, "charge");
+        assert_eq!(
+            result[0].apdex,
+            Apdex {
+                satisfied: 1,
+                tolerating: 1,
+                frustrated: 1
+            }
+        );
+        assert!((result[0].score.unwrap() - 0.5).abs() < f64::EPSILON);
+        assert_eq!(result[1].operation, "refund");
+        assert_eq!(result[1].score, Some(1.0));
+        assert_eq!(result[1].target_ms, 100);
+    }
+}
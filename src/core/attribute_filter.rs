@@ -0,0 +1,294 @@
+//! Span attribute allow/deny filtering.
+//!
+//! High-cardinality attributes (e.g. `thread.id`, `db.statement`) can dominate
+//! memory usage. An [`AttributeFilter`] drops unwanted keys at ingestion time,
+//! before spans reach storage. Keys are matched against glob patterns where
+//! `*` matches any sequence of characters and `?` matches a single character.
+//!
+//! A key is kept when it matches no deny pattern and, if any allow patterns
+//! are configured, matches at least one of them. Deny always wins.
+//!
+//! Keys can optionally be normalized (trimmed and lowercased) first, so that
+//! `HTTP.Method` and `http.method` from different SDKs become one key. Patterns
+//! are then matched against the normalized key.
+
+use crate::core::config::AttributeFilterConfig;
+use crate::core::types::AttributeMap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Glob pattern for attribute keys and names.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Glob(String);
+
+impl Glob {
+    /// Create a glob from a pattern such as `db.*` or `http.?ethod`.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Glob(pattern.into())
+    }
+
+    /// The original pattern.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check whether `key` matches this pattern.
+    pub fn matches(&self, key: &str) -> bool {
+        let pattern = self.0.as_bytes();
+        let key = key.as_bytes();
+
+        let (mut p, mut k) = (0, 0);
+        // Position of the last `*` and the key index it was tried against
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while k < key.len() {
+            match pattern.get(p) {
+                Some(b'*') => {
+                    backtrack = Some((p, k));
+                    p += 1;
+                },
+                Some(&c) if c == b'?' || c == key[k] => {
+                    p += 1;
+                    k += 1;
+                },
+                _ => match backtrack {
+                    // Let the last `*` swallow one more character
+                    Some((star_p, star_k)) => {
+                        backtrack = Some((star_p, star_k + 1));
+                        p = star_p + 1;
+                        k = star_k + 1;
+                    },
+                    None => return false,
+                },
+            }
+        }
+
+        pattern[p..].iter().all(|&c| c == b'*')
+    }
+}
+
+/// Drops span attributes by key according to allow/deny glob lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeFilter {
+    /// Keys to keep; empty means keep everything not denied
+    pub allow_keys: Vec<Glob>,
+    /// Keys to drop; takes precedence over `allow_keys`
+    pub deny_keys: Vec<Glob>,
+    /// Trim and lowercase keys before filtering
+    pub normalize_keys: bool,
+    /// Keep the original attribute next to the normalized one when its key changed
+    pub preserve_original_keys: bool,
+}
+
+impl AttributeFilter {
+    /// Create a filter from allow and deny patterns.
+    pub fn new(allow_keys: Vec<Glob>, deny_keys: Vec<Glob>) -> Self {
+        Self {
+            allow_keys,
+            deny_keys,
+            normalize_keys: false,
+            preserve_original_keys: false,
+        }
+    }
+
+    /// Normalize keys before filtering, optionally keeping the originals.
+    pub fn with_key_normalization(mut self, preserve_original_keys: bool) -> Self {
+        self.normalize_keys = true;
+        self.preserve_original_keys = preserve_original_keys;
+        self
+    }
+
+    /// Build a filter from the `attributes` config section.
+    pub fn from_config(config: &AttributeFilterConfig) -> Self {
+        let filter = Self::new(
+            config.allow_keys.iter().cloned().map(Glob::new).collect(),
+            config.deny_keys.iter().cloned().map(Glob::new).collect(),
+        );
+        if config.normalize_keys {
+            filter.with_key_normalization(config.preserve_original_keys)
+        } else {
+            filter
+        }
+    }
+
+    /// Returns true if the filter leaves every attribute untouched.
+    pub fn is_empty(&self) -> bool {
+        self.allow_keys.is_empty() && self.deny_keys.is_empty() && !self.normalize_keys
+    }
+
+    /// Check whether an attribute with this key should be kept.
+    #[inline]
+    pub fn allows(&self, key: &str) -> bool {
+        if self.deny_keys.iter().any(|g| g.matches(key)) {
+            return false;
+        }
+        self.allow_keys.is_empty() || self.allow_keys.iter().any(|g| g.matches(key))
+    }
+
+    /// Normalize keys if enabled and remove attributes that are not allowed,
+    /// returning how many were dropped.
+    pub fn filter_attributes(&self, attributes: &mut AttributeMap) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        if self.normalize_keys {
+            self.normalize_attribute_keys(attributes);
+        }
+        let before = attributes.len();
+        attributes.0.retain(|(key, _)| self.allows(key));
+        before - attributes.len()
+    }
+
+    /// Replace keys with their normalized form. When several keys collapse to
+    /// the same one, the first value wins.
+    fn normalize_attribute_keys(&self, attributes: &mut AttributeMap) {
+        let mut seen: HashSet<Arc<str>> = HashSet::with_capacity(attributes.len());
+        let mut originals = Vec::new();
+        let mut normalized = AttributeMap::new();
+
+        for (key, value) in attributes.0.drain(..) {
+            let key = match normalize_key(&key) {
+                Cow::Borrowed(_) => key,
+                Cow::Owned(normal) => {
+                    if self.preserve_original_keys {
+                        originals.push((key, Arc::clone(&value)));
+                    }
+                    Arc::from(normal)
+                },
+            };
+            if seen.insert(Arc::clone(&key)) {
+                normalized.push(key, value);
+            }
+        }
+        for (key, value) in originals {
+            if !seen.contains(&key) {
+                normalized.push(key, value);
+            }
+        }
+        *attributes = normalized;
+    }
+}
+
+/// Trimmed, lowercased attribute key; borrowed when already normalized.
+pub fn normalize_key(key: &str) -> Cow<'_, str> {
+    let trimmed = key.trim();
+    if trimmed.len() == key.len() && !key.chars().any(char::is_uppercase) {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(trimmed.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn attrs(keys: &[&str]) -> AttributeMap {
+        let mut map = AttributeMap::new();
+        for key in keys {
+            map.push(Arc::from(*key), Arc::from("v"));
+        }
+        map
+    }
+
+    fn keys(map: &AttributeMap) -> Vec<&str> {
+        map.iter().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(Glob::new("db.*").matches("db.statement"));
+        assert!(Glob::new("db.*").matches("db."));
+        assert!(!Glob::new("db.*").matches("db"));
+        assert!(!Glob::new("db.*").matches("mydb.statement"));
+        assert!(Glob::new("*.id").matches("thread.id"));
+        assert!(Glob::new("http.?ethod").matches("http.method"));
+        assert!(Glob::new("*").matches(""));
+        assert!(Glob::new("a*b*c").matches("axxbyybc"));
+        assert!(!Glob::new("a*b*c").matches("axxbyyb"));
+        assert!(Glob::new("thread.id").matches("thread.id"));
+        assert!(!Glob::new("thread.id").matches("thread.ids"));
+    }
+
+    #[test]
+    fn test_deny_wildcard() {
+        let filter = AttributeFilter::new(vec![], vec![Glob::new("db.*")]);
+        let mut map = attrs(&["db.statement", "db.system", "http.method"]);
+
+        assert_eq!(filter.filter_attributes(&mut map), 2);
+        assert_eq!(keys(&map), vec!["http.method"]);
+    }
+
+    #[test]
+    fn test_allow_wildcard() {
+        let filter = AttributeFilter::new(vec![Glob::new("http.*")], vec![]);
+        let mut map = attrs(&["http.method", "http.status_code", "thread.id", "db.statement"]);
+
+        assert_eq!(filter.filter_attributes(&mut map), 2);
+        assert_eq!(keys(&map), vec!["http.method", "http.status_code"]);
+    }
+
+    #[test]
+    fn test_deny_wins_over_allow() {
+        let filter = AttributeFilter::new(
+            vec![Glob::new("http.*"), Glob::new("db.*")],
+            vec![Glob::new("db.*"), Glob::new("http.request.header.*")],
+        );
+
+        assert!(filter.allows("http.method"));
+        assert!(!filter.allows("http.request.header.cookie"));
+        assert!(!filter.allows("db.statement"));
+        assert!(!filter.allows("thread.id"));
+    }
+
+    #[test]
+    fn test_key_normalization_collapses_case_variants() {
+        let mut map = AttributeMap::new();
+        map.push(Arc::from("HTTP.Method"), Arc::from("GET"));
+        map.push(Arc::from("http.method"), Arc::from("POST"));
+        map.push(Arc::from(" Http.Method "), Arc::from("PUT"));
+        map.push(Arc::from("db.system"), Arc::from("postgres"));
+
+        let filter = AttributeFilter::default().with_key_normalization(false);
+        assert!(!filter.is_empty());
+
+        let mut normalized = map.clone();
+        filter.filter_attributes(&mut normalized);
+        assert_eq!(keys(&normalized), vec!["http.method", "db.system"]);
+        assert_eq!(normalized.get("http.method"), Some("GET"));
+
+        // Originals are kept alongside, and deny patterns see normalized keys
+        let filter =
+            AttributeFilter::new(vec![], vec![Glob::new("db.*")]).with_key_normalization(true);
+        let mut preserved = map.clone();
+        assert_eq!(filter.filter_attributes(&mut preserved), 1);
+        assert_eq!(keys(&preserved), vec!["http.method", "HTTP.Method", " Http.Method "]);
+        assert_eq!(preserved.get("HTTP.Method"), Some("GET"));
+
+        assert!(matches!(normalize_key("http.route"), Cow::Borrowed(_)));
+        assert_eq!(normalize_key(" Net.Peer "), "net.peer");
+    }
+
+    #[test]
+    fn test_from_yaml_config() {
+        let yaml = r#"
+attributes:
+  allow_keys: ["http.*", "service.*"]
+  deny_keys: ["db.*"]
+"#;
+        let config = crate::core::ConfigBuilder::new()
+            .from_yaml(yaml)
+            .unwrap()
+            .build()
+            .unwrap();
+        let filter = AttributeFilter::from_config(config.attributes.as_ref().unwrap());
+
+        assert_eq!(filter.allow_keys, vec![Glob::new("http.*"), Glob::new("service.*")]);
+        assert!(filter.allows("http.route"));
+        assert!(!filter.allows("db.statement"));
+    }
+}
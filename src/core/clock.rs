@@ -0,0 +1,115 @@
+//! Injectable time source.
+//!
+//! Logic that compares against "now" (retention cutoffs, metric windows,
+//! sampling budgets, alert timestamps) reads the time from a [`Clock`]
+//! rather than calling `SystemTime::now()` or `Instant::now()` itself.
+//!
+//! The pattern for a component:
+//! - keep a [`SharedClock`] field initialised with [`system_clock()`];
+//! - offer a `with_clock(clock)` builder;
+//! - call `self.clock.now()` / `self.clock.instant()` where it used the
+//!   std functions.
+//!
+//! Tests build the component with a [`MockClock`] and move time with
+//! [`MockClock::advance`] instead of sleeping or backdating data.
+
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of wall-clock and monotonic time.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Current wall-clock time.
+    fn now(&self) -> SystemTime;
+
+    /// Current monotonic time.
+    fn instant(&self) -> Instant;
+}
+
+/// Clock shared between a component and its background tasks.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    #[inline]
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Shared handle to the real clock.
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Clock that only moves when told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: SystemTime,
+    start_instant: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl Default for MockClock {
+    /// A clock frozen at 2023-11-14 22:13:20 UTC (Unix 1_700_000_000).
+    fn default() -> Self {
+        Self::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    }
+}
+
+impl MockClock {
+    /// A clock frozen at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            start,
+            start_instant: Instant::now(),
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Move time forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock() += by;
+    }
+
+    /// This clock as a [`SharedClock`].
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.start + *self.elapsed.lock()
+    }
+
+    fn instant(&self) -> Instant {
+        self.start_instant + *self.elapsed.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_moves_only_when_advanced() {
+        let clock = MockClock::default();
+        let shared = clock.shared();
+        let (now, instant) = (shared.now(), shared.instant());
+        assert_eq!(shared.now(), now);
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(shared.now(), now + Duration::from_secs(90));
+        assert_eq!(shared.instant() - instant, Duration::from_secs(90));
+    }
+}
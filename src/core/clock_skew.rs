@@ -0,0 +1,224 @@
+//! Clock skew correction for the spans of a trace.
+//!
+//! Every service times its spans with its own host clock, so a few hundred
+//! milliseconds of drift between containers can make a child start before its
+//! parent. Like Jaeger's clock skew adjuster, a child recorded by another
+//! service than its parent is shifted into the parent's window when it does
+//! not fit there: a shorter child is centred in the parent, a longer one
+//! starts with it. Shifts larger than the configured maximum are taken to be
+//! real timing and left alone. Spans of the same service share a clock, so
+//! they move together with their parent.
+
+use super::types::{Span, SpanId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Attribute recording how far a span was shifted, in milliseconds.
+pub const CLOCK_SKEW_ATTRIBUTE: &str = "urpo.clock_skew_adjusted_ms";
+
+/// Shifts child spans of a trace into their parents to undo clock skew.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkewAdjuster {
+    max_skew: Duration,
+}
+
+impl ClockSkewAdjuster {
+    /// Correct skews of at most `max_skew`.
+    pub fn new(max_skew: Duration) -> Self {
+        Self { max_skew }
+    }
+
+    /// Largest skew that is corrected.
+    pub fn max_skew(&self) -> Duration {
+        self.max_skew
+    }
+
+    /// Shift the skewed spans of one trace, tagging each moved span with
+    /// [`CLOCK_SKEW_ATTRIBUTE`], and return how many were moved. The spans
+    /// are left in start time order.
+    pub fn adjust(&self, spans: &mut [Span]) -> usize {
+        let mut index: HashMap<SpanId, usize> = HashMap::with_capacity(spans.len());
+        for (i, span) in spans.iter().enumerate() {
+            index.entry(span.span_id.clone()).or_insert(i);
+        }
+        let mut parents = vec![None; spans.len()];
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); spans.len()];
+        let mut pending = Vec::new();
+        for (i, span) in spans.iter().enumerate() {
+            match span
+                .parent_span_id
+                .as_ref()
+                .and_then(|parent| index.get(parent))
+            {
+                Some(&parent) if parent != i => {
+                    parents[i] = Some(parent);
+                    children[parent].push(i);
+                },
+                _ => pending.push((i, 0)),
+            }
+        }
+
+        // Parents are shifted before their children are measured against them
+        let mut visited = vec![false; spans.len()];
+        let mut adjusted = 0;
+        while let Some((i, inherited)) = pending.pop() {
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            let shift = match parents[i] {
+                Some(parent) if spans[parent].service_name != spans[i].service_name => {
+                    self.skew(&spans[parent], &spans[i])
+                },
+                _ => inherited,
+            };
+            if shift != 0 {
+                shift_span(&mut spans[i], shift);
+                adjusted += 1;
+            }
+            pending.extend(children[i].iter().map(|&child| (child, shift)));
+        }
+
+        spans.sort_by_key(|span| span.start_time);
+        adjusted
+    }
+
+    /// Nanoseconds to shift `child` by to fit it into `parent`.
+    fn skew(&self, parent: &Span, child: &Span) -> i128 {
+        let parent_start = unix_nanos(parent.start_time);
+        let child_start = unix_nanos(child.start_time);
+        let parent_duration = parent.duration.as_nanos() as i128;
+        let child_duration = child.duration.as_nanos() as i128;
+        if child_start >= parent_start
+            && child_start + child_duration <= parent_start + parent_duration
+        {
+            return 0;
+        }
+
+        let target = if child_duration >= parent_duration {
+            parent_start
+        } else {
+            parent_start + (parent_duration - child_duration) / 2
+        };
+        let shift = target - child_start;
+        if shift.unsigned_abs() > self.max_skew.as_nanos() {
+            0
+        } else {
+            shift
+        }
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+fn shifted(time: SystemTime, nanos: i128) -> SystemTime {
+    let by = Duration::from_nanos(nanos.unsigned_abs() as u64);
+    if nanos < 0 {
+        time - by
+    } else {
+        time + by
+    }
+}
+
+/// Move `span` and its events by `nanos` and record the shift.
+fn shift_span(span: &mut Span, nanos: i128) {
+    span.start_time = shifted(span.start_time, nanos);
+    for event in &mut span.events {
+        event.timestamp = shifted(event.timestamp, nanos);
+    }
+    // Whole microseconds, in milliseconds
+    let millis = (nanos / 1_000) as f64 / 1_000.0;
+    span.attributes
+        .push(Arc::from(CLOCK_SKEW_ATTRIBUTE), Arc::from(millis.to_string().as_str()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, TraceId};
+
+    fn span(id: u64, parent: Option<u64>, service: &str, start_ms: i64, millis: u64) -> Span {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let start = shifted(start, i128::from(start_ms) * 1_000_000);
+        let mut builder = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name(format!("op-{}", id))
+            .start_time(start)
+            .duration(Duration::from_millis(millis));
+        if let Some(parent) = parent {
+            builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    fn find<'a>(spans: &'a [Span], id: u64) -> &'a Span {
+        let id = SpanId::new(format!("{:016x}", id)).unwrap();
+        spans.iter().find(|span| span.span_id == id).unwrap()
+    }
+
+    fn start_ms(span: &Span) -> i128 {
+        (unix_nanos(span.start_time) - 1_700_000_000_000_000_000) / 1_000_000
+    }
+
+    #[test]
+    fn test_skewed_child_moves_into_parent_with_its_descendants() {
+        // The db host clock runs 300ms behind the api host
+        let mut spans = vec![
+            span(1, None, "api", 0, 100),
+            span(2, Some(1), "db", -290, 40),
+            span(3, Some(2), "db", -280, 10),
+        ];
+        let adjuster = ClockSkewAdjuster::new(Duration::from_millis(500));
+        assert_eq!(adjuster.adjust(&mut spans), 2);
+
+        // Centred in its parent, and its own child keeps the same offset
+        let (child, grandchild) = (find(&spans, 2), find(&spans, 3));
+        assert_eq!(start_ms(child), 30);
+        assert_eq!(start_ms(grandchild), 40);
+        assert_eq!(child.attributes.get(CLOCK_SKEW_ATTRIBUTE), Some("320"));
+        assert_eq!(grandchild.attributes.get(CLOCK_SKEW_ATTRIBUTE), Some("320"));
+        assert_eq!(find(&spans, 1).attributes.get(CLOCK_SKEW_ATTRIBUTE), None);
+        assert_eq!(spans[0].span_id, find(&spans, 1).span_id);
+    }
+
+    #[test]
+    fn test_longer_child_starts_with_parent() {
+        let mut spans = vec![span(1, None, "api", 0, 100), span(2, Some(1), "db", 150, 120)];
+        ClockSkewAdjuster::new(Duration::from_millis(500)).adjust(&mut spans);
+        let child = find(&spans, 2);
+        assert_eq!(start_ms(child), 0);
+        assert_eq!(child.attributes.get(CLOCK_SKEW_ATTRIBUTE), Some("-150"));
+    }
+
+    #[test]
+    fn test_fitting_same_service_and_large_skews_are_left_alone() {
+        let mut spans = vec![
+            span(1, None, "api", 0, 100),
+            // Fits already
+            span(2, Some(1), "db", 10, 20),
+            // Same clock as its parent, so its early start is real
+            span(3, Some(1), "api", -50, 10),
+            // Beyond the maximum skew
+            span(4, Some(1), "cache", 2_000, 10),
+            // Parent not in the trace
+            span(5, Some(99), "queue", -500, 10),
+        ];
+        let original = spans.clone();
+        assert_eq!(ClockSkewAdjuster::new(Duration::from_millis(500)).adjust(&mut spans), 0);
+        for span in &original {
+            let id = u64::from_str_radix(span.span_id.as_str(), 16).unwrap();
+            assert_eq!(find(&spans, id).start_time, span.start_time);
+            assert!(find(&spans, id)
+                .attributes
+                .get(CLOCK_SKEW_ATTRIBUTE)
+                .is_none());
+        }
+    }
+}
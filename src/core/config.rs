@@ -6,12 +6,17 @@
 //! - CLI argument overrides
 //! - Validation and defaults
 
-use crate::core::{Result, UrpoError};
+use crate::core::{Glob, Result, UrpoError};
+use crate::monitoring::alerting::AlertRule;
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 use std::time::Duration;
 
+/// Smallest memory budget per span that `max_memory_mb` must leave for `max_spans`.
+pub const MIN_BYTES_PER_SPAN: usize = 512;
+
 /// Complete configuration for Urpo
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -30,6 +35,18 @@ pub struct Config {
     pub logging: LoggingConfig,
     /// Feature flags
     pub features: FeatureConfig,
+    /// Span attribute allow/deny filtering at ingestion
+    pub attributes: Option<AttributeFilterConfig>,
+    /// PII redaction of span attributes at ingestion
+    pub redaction: Option<RedactionConfig>,
+    /// Services whose spans the receivers keep or drop
+    pub receiver: Option<ServiceFilterConfig>,
+    /// Per-client rate limiting of OTLP span ingestion
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Kafka source for OTLP spans (requires the `kafka` feature)
+    pub kafka: Option<KafkaConfig>,
+    /// Webhook alerts sent in headless mode
+    pub alert_rules: Vec<AlertRule>,
     /// Debug mode
     #[serde(skip)]
     pub debug: bool,
@@ -42,13 +59,125 @@ pub struct ServerConfig {
     pub grpc_port: u16,
     /// HTTP port for OTEL receiver
     pub http_port: u16,
-    /// Bind address for receivers
+    /// Port for the Jaeger Thrift collector endpoint (disabled when unset)
+    #[serde(default)]
+    pub jaeger_port: Option<u16>,
+    /// Address the receivers and API server listen on (IPv4 or IPv6)
     pub bind_address: IpAddr,
     /// Maximum concurrent connections
     pub max_connections: usize,
     /// Connection timeout
     #[serde(with = "humantime_serde")]
     pub connection_timeout: Duration,
+    /// Maximum accepted OTLP request size in bytes (gRPC message or HTTP body)
+    #[serde(default = "default_max_request_bytes")]
+    pub max_request_bytes: usize,
+    /// Maximum concurrent HTTP/2 streams per gRPC connection (unlimited when unset)
+    #[serde(default)]
+    pub max_concurrent_streams: Option<u32>,
+    /// Timeout for a single gRPC request (none when unset)
+    #[serde(default, with = "humantime_serde")]
+    pub request_timeout: Option<Duration>,
+    /// Origins allowed to call the OTLP HTTP routes from a browser (any when empty)
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// Accept gRPC-Web trace exports on the HTTP port
+    #[serde(default = "default_grpc_web")]
+    pub grpc_web: bool,
+    /// Move OTLP/HTTP exports into the trace of their `traceparent` header
+    #[serde(default)]
+    pub apply_traceparent: bool,
+    /// Restarting of a gRPC or HTTP receiver that stops on its own
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    /// Limits on the attributes and events of each received span
+    #[serde(default)]
+    pub span_limits: SpanLimits,
+    /// Unix socket the gRPC receiver also listens on (none when unset)
+    #[serde(default)]
+    pub grpc_uds_path: Option<PathBuf>,
+    /// Unix socket the HTTP receiver also listens on (none when unset)
+    #[serde(default)]
+    pub http_uds_path: Option<PathBuf>,
+    /// Permission bits of the Unix socket files
+    #[serde(default = "default_uds_mode")]
+    pub uds_mode: u32,
+}
+
+/// Limits on the data of a single span, enforced while converting it at ingest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpanLimits {
+    /// Attributes kept per span; further ones are dropped
+    pub max_attributes: usize,
+    /// Longest attribute key in bytes; attributes with longer keys are dropped
+    pub max_key_length: usize,
+    /// Longest attribute value in characters; longer values are truncated
+    pub max_value_length: usize,
+    /// Events kept per span; further ones are dropped
+    pub max_events: usize,
+}
+
+impl Default for SpanLimits {
+    fn default() -> Self {
+        SpanLimits {
+            max_attributes: 128,
+            max_key_length: 256,
+            max_value_length: 4096,
+            max_events: 128,
+        }
+    }
+}
+
+/// How a receiver that stops unexpectedly is restarted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    /// Restarts attempted before giving up (0 disables restarting)
+    pub max_restarts: u32,
+    /// Delay before the first restart, doubled for each further attempt
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between restarts
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    /// Run time after which a server counts as stable and its restart count
+    /// and backoff start over
+    #[serde(with = "humantime_serde")]
+    pub stable_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            stable_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before restart number `attempt` (starting at 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+fn default_max_request_bytes() -> usize {
+    crate::receiver::DEFAULT_MAX_REQUEST_BYTES
+}
+
+fn default_grpc_web() -> bool {
+    true
+}
+
+fn default_uds_mode() -> u32 {
+    0o600
 }
 
 /// Storage configuration
@@ -61,12 +190,18 @@ pub struct StorageConfig {
     /// Span retention duration
     #[serde(with = "humantime_serde")]
     pub retention_duration: Duration,
+    /// Retention of services matching a glob, instead of `retention_duration`
+    #[serde(default, with = "humantime_map")]
+    pub retention_overrides: HashMap<Glob, Duration>,
     /// Cleanup interval
     #[serde(with = "humantime_serde")]
     pub cleanup_interval: Duration,
     /// Enable compression
     pub compression_enabled: bool,
-    /// Enable persistent storage to disk
+    /// Storage backend (`memory` or `persistent`)
+    #[serde(default)]
+    pub backend: StorageBackendKind,
+    /// Enable persistent storage to disk (same as `backend = "persistent"`)
     pub persistent: bool,
     /// Data directory for persistent storage
     pub data_dir: PathBuf,
@@ -78,6 +213,181 @@ pub struct StorageConfig {
     pub cold_retention_hours: usize,
     /// Enable archival storage for compressed historical data
     pub enable_archival: bool,
+    /// Number of decompressed warm traces kept in the read cache (0 disables it)
+    #[serde(default = "default_warm_cache_traces")]
+    pub warm_cache_traces: usize,
+    /// Spans older than this are compressed into the warm tier
+    #[serde(default = "default_warm_after", with = "humantime_serde")]
+    pub warm_after: Duration,
+    /// How often spans move between tiers when compression is enabled (0 disables it)
+    #[serde(default = "default_tier_migration_interval", with = "humantime_serde")]
+    pub tier_migration_interval: Duration,
+    /// Spans older than this are moved to the disk archive when archival is enabled
+    #[serde(default = "default_archive_after", with = "humantime_serde")]
+    pub archive_after: Duration,
+    /// Span archive directory (defaults to `<data_dir>/archive`)
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+    /// Write-ahead log replayed into in-memory storage on startup (off when unset)
+    #[serde(default)]
+    pub wal: Option<WalConfig>,
+    /// Hourly per-service statistics kept across restarts (off when unset)
+    #[serde(default)]
+    pub longterm_stats: Option<LongTermStatsConfig>,
+    /// Correct clock skew of up to this much between the services of a trace
+    /// when reading it back (off when unset)
+    #[serde(default, with = "humantime_serde")]
+    pub max_clock_skew: Option<Duration>,
+}
+
+/// Downsampled long-term statistics for the in-memory backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LongTermStatsConfig {
+    /// Stats file (defaults to `<data_dir>/longterm-stats.log`)
+    pub path: Option<PathBuf>,
+    /// How long hourly statistics are kept
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+}
+
+impl Default for LongTermStatsConfig {
+    fn default() -> Self {
+        LongTermStatsConfig {
+            path: None,
+            retention: Duration::from_secs(31 * 24 * 3600),
+        }
+    }
+}
+
+/// Write-ahead log for the in-memory backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalConfig {
+    /// WAL directory (defaults to `<data_dir>/wal`)
+    pub dir: Option<PathBuf>,
+    /// Segment size at which a new segment file is started
+    pub max_segment_bytes: u64,
+    /// Number of segment files kept; older ones are deleted
+    pub max_segments: usize,
+    /// How often appended records are fsynced (0 syncs every write)
+    #[serde(with = "humantime_serde")]
+    pub sync_interval: Duration,
+    /// How often stored spans are snapshotted and older segments deleted (0 disables)
+    #[serde(with = "humantime_serde")]
+    pub snapshot_interval: Duration,
+}
+
+impl Default for WalConfig {
+    fn default() -> Self {
+        WalConfig {
+            dir: None,
+            max_segment_bytes: 16 * 1024 * 1024,
+            max_segments: 4,
+            sync_interval: Duration::from_secs(1),
+            snapshot_interval: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Map values as humantime durations such as `7days` or `5m`.
+mod humantime_map {
+    use humantime_serde::Serde;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::hash::Hash;
+    use std::time::Duration;
+
+    pub fn serialize<K, S>(map: &HashMap<K, Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Eq + Hash,
+        S: Serializer,
+    {
+        let map: HashMap<&K, Serde<&Duration>> = map
+            .iter()
+            .map(|(key, value)| (key, Serde::from(value)))
+            .collect();
+        map.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, K, D>(deserializer: D) -> Result<HashMap<K, Duration>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        D: Deserializer<'de>,
+    {
+        let map = HashMap::<K, Serde<Duration>>::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(key, value)| (key, value.into_inner()))
+            .collect())
+    }
+}
+
+fn default_warm_cache_traces() -> usize {
+    64
+}
+
+fn default_warm_after() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+fn default_tier_migration_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_archive_after() -> Duration {
+    Duration::from_secs(15 * 60)
+}
+
+impl StorageConfig {
+    /// Backend selected by `backend` or the legacy `persistent` flag.
+    pub fn backend_kind(&self) -> StorageBackendKind {
+        if self.persistent {
+            StorageBackendKind::Persistent
+        } else {
+            self.backend
+        }
+    }
+
+    /// Directory the persistent backend writes its segments to.
+    pub fn persistent_dir(&self) -> PathBuf {
+        self.data_dir.join("spans")
+    }
+
+    /// Directory the span archive is written to.
+    pub fn archive_dir(&self) -> PathBuf {
+        self.archive_dir
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("archive"))
+    }
+
+    /// Directory the write-ahead log is written to.
+    pub fn wal_dir(&self) -> Option<PathBuf> {
+        let wal = self.wal.as_ref()?;
+        Some(wal.dir.clone().unwrap_or_else(|| self.data_dir.join("wal")))
+    }
+
+    /// File long-term statistics are written to.
+    pub fn longterm_stats_path(&self) -> Option<PathBuf> {
+        let stats = self.longterm_stats.as_ref()?;
+        Some(
+            stats
+                .path
+                .clone()
+                .unwrap_or_else(|| self.data_dir.join("longterm-stats.log")),
+        )
+    }
+}
+
+/// Storage backend implementations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    /// Bounded in-memory storage, lost on restart
+    #[default]
+    Memory,
+    /// Segment files under `<data_dir>/spans`, kept across restarts
+    Persistent,
 }
 
 /// UI configuration
@@ -101,12 +411,105 @@ pub struct UiConfig {
 pub struct SamplingConfig {
     /// Default sampling rate (0.0 to 1.0)
     pub default_rate: f64,
-    /// Per-service sampling rates
+    /// Per-service sampling rates, keyed by service name or glob
     pub per_service: std::collections::HashMap<String, f64>,
     /// Adaptive sampling enabled
     pub adaptive: bool,
     /// Target spans per second for adaptive sampling
     pub target_sps: Option<usize>,
+    /// Per-service fair head sampling (off when unset)
+    #[serde(default)]
+    pub fairness: Option<FairnessConfig>,
+    /// Rules tried in order; the first matching rule decides, otherwise
+    /// `default_rate` applies
+    #[serde(default)]
+    pub rules: Vec<SamplingRule>,
+    /// Most spans kept per service per second, applied after sampling (off
+    /// when unset)
+    #[serde(default)]
+    pub max_spans_per_second: Option<u32>,
+}
+
+/// A sampling rule: which spans it matches and what happens to them
+///
+/// Exactly one of `rate`, `always_keep` and `always_drop` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingRule {
+    /// Service names matched (default: any)
+    #[serde(default = "match_any")]
+    pub service_matches: Glob,
+    /// Operation names matched (default: any)
+    #[serde(default = "match_any")]
+    pub operation_matches: Glob,
+    /// Only match spans, or traces when their duration is known, lasting at least this long
+    #[serde(default)]
+    pub min_duration_ms: Option<u64>,
+    /// Only match error (true) or successful (false) spans
+    #[serde(default)]
+    pub has_error: Option<bool>,
+    /// Keep matching traces with this probability (0.0 to 1.0)
+    #[serde(default)]
+    pub rate: Option<f64>,
+    /// Keep every matching span
+    #[serde(default)]
+    pub always_keep: bool,
+    /// Drop every matching span
+    #[serde(default)]
+    pub always_drop: bool,
+}
+
+fn match_any() -> Glob {
+    Glob::new("*")
+}
+
+/// Fair sampling: per-service token buckets under a global cap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FairnessConfig {
+    /// Kept traces per service per minute
+    pub per_service_per_minute: u32,
+    /// Kept traces per minute across all services
+    pub global_per_minute: u32,
+    /// Kept traces per service per minute even when the global cap is exhausted
+    pub floor_per_minute: u32,
+    /// Per-service overrides of floor and ceiling
+    pub services: std::collections::HashMap<String, ServiceFairnessConfig>,
+}
+
+/// Per-service fair sampling limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceFairnessConfig {
+    /// Guaranteed kept traces per minute (overrides `floor_per_minute`)
+    pub floor: Option<u32>,
+    /// Maximum kept traces per minute (overrides `per_service_per_minute`)
+    pub ceiling: Option<u32>,
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        FairnessConfig {
+            per_service_per_minute: 600,
+            global_per_minute: 6000,
+            floor_per_minute: 6,
+            services: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl FairnessConfig {
+    /// Floor and ceiling (kept traces per minute) for a service
+    pub fn limits(&self, service: &str) -> (u32, u32) {
+        let overrides = self.services.get(service);
+        let ceiling = overrides
+            .and_then(|o| o.ceiling)
+            .unwrap_or(self.per_service_per_minute);
+        let floor = overrides
+            .and_then(|o| o.floor)
+            .unwrap_or(self.floor_per_minute)
+            .min(ceiling);
+        (floor, ceiling)
+    }
 }
 
 /// Monitoring configuration
@@ -125,6 +528,13 @@ pub struct MonitoringConfig {
     pub max_metrics: usize,
     /// Maximum services to track
     pub max_services: usize,
+    /// Apdex target latency T; requests up to T are satisfied, up to 4T tolerating
+    #[serde(default = "default_apdex_target", with = "humantime_serde")]
+    pub apdex_target: Duration,
+}
+
+fn default_apdex_target() -> Duration {
+    Duration::from_millis(500)
 }
 
 /// Alert configuration
@@ -137,6 +547,13 @@ pub struct AlertConfig {
     pub p95_latency_threshold: Duration,
     /// Minimum sample size for alerts
     pub min_sample_size: usize,
+    /// Warn when a service's P95 ingest lag (span end to storage) exceeds this
+    #[serde(default = "default_ingest_lag_threshold", with = "humantime_serde")]
+    pub ingest_lag_threshold: Duration,
+}
+
+fn default_ingest_lag_threshold() -> Duration {
+    Duration::from_secs(60)
 }
 
 /// Logging configuration
@@ -166,6 +583,163 @@ pub struct FeatureConfig {
     pub profiling: bool,
 }
 
+/// Span attribute filter configuration (glob patterns such as `db.*`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeFilterConfig {
+    /// Attribute keys to keep; empty keeps everything not denied
+    pub allow_keys: Vec<String>,
+    /// Attribute keys to drop; takes precedence over `allow_keys`
+    pub deny_keys: Vec<String>,
+    /// Trim and lowercase attribute keys at ingestion (`HTTP.Method` -> `http.method`)
+    pub normalize_keys: bool,
+    /// Keep attributes under their original key too when normalization changed it
+    pub preserve_original_keys: bool,
+}
+
+/// Receiver service filter configuration (exact names or glob patterns)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceFilterConfig {
+    /// Services to keep; empty keeps everything not excluded
+    pub include_services: Vec<String>,
+    /// Services to drop; takes precedence over `include_services`
+    pub exclude_services: Vec<String>,
+}
+
+/// Per-client rate limiting of OTLP span ingestion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// What identifies a client
+    pub key: RateLimitKey,
+    /// Limits, replaceable at runtime through `/api/rate-limits`
+    #[serde(flatten)]
+    pub limits: RateLimits,
+}
+
+/// What identifies a client to the ingestion rate limiter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// Address of the connection peer
+    #[default]
+    PeerIp,
+    /// Value of a resource attribute such as `service.name`
+    ResourceAttribute(String),
+}
+
+/// Ingestion limits per client
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimits {
+    /// Limit of clients without their own entry; unlimited when unset
+    pub default: Option<RateLimit>,
+    /// Limits by client key (an IP address or attribute value)
+    pub clients: HashMap<String, RateLimit>,
+}
+
+/// Token bucket of one client
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained spans per second
+    pub spans_per_second: f64,
+    /// Spans accepted at once after a quiet period (default: one second's worth)
+    #[serde(default)]
+    pub burst: Option<f64>,
+}
+
+impl RateLimit {
+    /// Most tokens the bucket holds
+    pub fn capacity(&self) -> f64 {
+        self.burst.unwrap_or(self.spans_per_second)
+    }
+}
+
+impl RateLimits {
+    /// Limit of a client, its own or the default
+    pub fn limit_for(&self, client: &str) -> Option<RateLimit> {
+        self.clients.get(client).copied().or(self.default)
+    }
+
+    /// Check every rate and burst is positive
+    pub fn validate(&self) -> Result<()> {
+        let limits = self.default.iter().map(|limit| ("default", limit));
+        for (client, limit) in limits.chain(self.clients.iter().map(|(k, v)| (k.as_str(), v))) {
+            let positive = |value: f64| value.is_finite() && value > 0.0;
+            if !positive(limit.spans_per_second) || !limit.burst.map_or(true, positive) {
+                return Err(UrpoError::config(format!(
+                    "Rate limit of '{}' needs a positive spans_per_second and burst",
+                    client
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Span attribute redaction configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Rules in order; the first rule matching a key decides its action
+    pub rules: Vec<RedactionRule>,
+}
+
+/// Attributes to redact and how.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Exact attribute keys (e.g. `http.request.header.authorization`)
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Regular expressions matched against attribute keys
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// What happens to a matching attribute
+    pub action: RedactionAction,
+    /// Characters kept by the `truncate` action
+    #[serde(default = "default_redaction_max_length")]
+    pub max_length: usize,
+}
+
+fn default_redaction_max_length() -> usize {
+    16
+}
+
+/// What a redaction rule does to a matching attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    /// Remove the attribute
+    Drop,
+    /// Replace the value with a stable hash, so equal values still correlate
+    Hash,
+    /// Keep only the first `max_length` characters of the value
+    Truncate,
+}
+
+/// Kafka source configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KafkaConfig {
+    /// Comma-separated bootstrap brokers (e.g., "localhost:9092")
+    pub brokers: String,
+    /// Topic carrying OTLP protobuf trace payloads
+    #[serde(default = "default_kafka_topic")]
+    pub topic: String,
+    /// Consumer group id
+    #[serde(default = "default_kafka_group_id")]
+    pub group_id: String,
+}
+
+/// Same default topic as the OTel collector `kafka` exporter.
+fn default_kafka_topic() -> String {
+    "otlp_spans".to_string()
+}
+
+fn default_kafka_group_id() -> String {
+    "urpo".to_string()
+}
+
 /// Color themes
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -215,6 +789,12 @@ impl Default for Config {
             monitoring: MonitoringConfig::default(),
             logging: LoggingConfig::default(),
             features: FeatureConfig::default(),
+            attributes: None,
+            redaction: None,
+            receiver: None,
+            rate_limit: None,
+            kafka: None,
+            alert_rules: Vec::new(),
             debug: false,
         }
     }
@@ -225,9 +805,21 @@ impl Default for ServerConfig {
         ServerConfig {
             grpc_port: 4317,
             http_port: 4318,
-            bind_address: "0.0.0.0".parse().expect("Valid default IP address"),
+            jaeger_port: None,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
             max_connections: 1000,
             connection_timeout: Duration::from_secs(30),
+            max_request_bytes: default_max_request_bytes(),
+            max_concurrent_streams: None,
+            request_timeout: None,
+            cors_allowed_origins: Vec::new(),
+            grpc_web: default_grpc_web(),
+            apply_traceparent: false,
+            restart: RestartPolicy::default(),
+            span_limits: SpanLimits::default(),
+            grpc_uds_path: None,
+            http_uds_path: None,
+            uds_mode: default_uds_mode(),
         }
     }
 }
@@ -238,14 +830,24 @@ impl Default for StorageConfig {
             max_spans: 100_000,
             max_memory_mb: 1024,
             retention_duration: Duration::from_secs(3600), // 1 hour
+            retention_overrides: HashMap::new(),
             cleanup_interval: Duration::from_secs(30),
             compression_enabled: false,
+            backend: StorageBackendKind::Memory,
             persistent: false,
             data_dir: PathBuf::from("./urpo_data"),
             hot_storage_size: 10_000, // 10k spans in hot ring
             warm_storage_mb: 512,     // 512MB warm storage
             cold_retention_hours: 24, // Keep cold data for 24 hours
             enable_archival: false,   // Disabled by default
+            warm_cache_traces: default_warm_cache_traces(),
+            warm_after: default_warm_after(),
+            tier_migration_interval: default_tier_migration_interval(),
+            archive_after: default_archive_after(),
+            archive_dir: None,
+            wal: None,
+            longterm_stats: None,
+            max_clock_skew: None,
         }
     }
 }
@@ -269,6 +871,9 @@ impl Default for SamplingConfig {
             per_service: std::collections::HashMap::new(),
             adaptive: false,
             target_sps: None,
+            fairness: None,
+            rules: Vec::new(),
+            max_spans_per_second: None,
         }
     }
 }
@@ -282,6 +887,7 @@ impl Default for MonitoringConfig {
             alerts: AlertConfig::default(),
             max_metrics: 1_048_576, // 1M metrics
             max_services: 1000,      // 1000 services
+            apdex_target: default_apdex_target(),
         }
     }
 }
@@ -292,6 +898,7 @@ impl Default for AlertConfig {
             error_rate_threshold: 5.0, // 5%
             p95_latency_threshold: Duration::from_secs(1),
             min_sample_size: 100,
+            ingest_lag_threshold: default_ingest_lag_threshold(),
         }
     }
 }
@@ -336,10 +943,81 @@ impl Config {
             )));
         }
 
+        validate_bind_address(self.server.bind_address)?;
+
+        for origin in &self.server.cors_allowed_origins {
+            validate_cors_origin(origin)?;
+        }
+
+        if self.server.grpc_uds_path.is_some()
+            && self.server.grpc_uds_path == self.server.http_uds_path
+        {
+            return Err(UrpoError::config("GRPC and HTTP Unix socket paths must be different"));
+        }
+
+        if self.server.uds_mode > 0o777 {
+            return Err(UrpoError::config(format!(
+                "uds_mode must be permission bits between 0 and 0o777, got {:#o}",
+                self.server.uds_mode
+            )));
+        }
+
+        if let Some(port) = self.server.jaeger_port {
+            if port == self.server.grpc_port || port == self.server.http_port {
+                return Err(UrpoError::config(format!(
+                    "Jaeger port must differ from the GRPC and HTTP ports: {}",
+                    port
+                )));
+            }
+        }
+
+        if let Some(ref attributes) = self.attributes {
+            if attributes
+                .allow_keys
+                .iter()
+                .chain(&attributes.deny_keys)
+                .any(|pattern| pattern.is_empty())
+            {
+                return Err(UrpoError::config("Attribute filter patterns must not be empty"));
+            }
+        }
+
+        if let Some(ref redaction) = self.redaction {
+            crate::core::Redactor::from_config(redaction)?;
+        }
+
+        if let Some(ref kafka) = self.kafka {
+            if kafka.brokers.trim().is_empty() || kafka.topic.is_empty() || kafka.group_id.is_empty()
+            {
+                return Err(UrpoError::config(
+                    "Kafka brokers, topic and group_id must not be empty",
+                ));
+            }
+        }
+
         if self.server.max_connections == 0 {
             return Err(UrpoError::config("max_connections must be greater than 0"));
         }
 
+        if self.server.max_request_bytes == 0 {
+            return Err(UrpoError::config("max_request_bytes must be greater than 0"));
+        }
+
+        if self.server.max_concurrent_streams == Some(0) {
+            return Err(UrpoError::config("max_concurrent_streams must be greater than 0"));
+        }
+
+        if self.server.request_timeout.is_some_and(|t| t.is_zero()) {
+            return Err(UrpoError::config("request_timeout must be greater than 0"));
+        }
+
+        let limits = &self.server.span_limits;
+        if limits.max_key_length == 0 || limits.max_value_length == 0 {
+            return Err(UrpoError::config(
+                "span_limits max_key_length and max_value_length must be greater than 0",
+            ));
+        }
+
         // Storage validation
         if self.storage.max_spans == 0 {
             return Err(UrpoError::config("max_spans must be greater than 0"));
@@ -349,6 +1027,22 @@ impl Config {
             return Err(UrpoError::config("max_memory_mb must be greater than 0"));
         }
 
+        if self.storage.compression_enabled && self.storage.warm_after.is_zero() {
+            return Err(UrpoError::config("warm_after must be greater than 0"));
+        }
+
+        if self.storage.enable_archival && self.storage.archive_after.is_zero() {
+            return Err(UrpoError::config("archive_after must be greater than 0"));
+        }
+
+        if let Some(ref wal) = self.storage.wal {
+            if wal.max_segments == 0 || wal.max_segment_bytes == 0 {
+                return Err(UrpoError::config(
+                    "wal max_segments and max_segment_bytes must be greater than 0",
+                ));
+            }
+        }
+
         // Sampling validation
         if self.sampling.default_rate < 0.0 || self.sampling.default_rate > 1.0 {
             return Err(UrpoError::InvalidSamplingRate(self.sampling.default_rate));
@@ -363,6 +1057,51 @@ impl Config {
             }
         }
 
+        for (i, rule) in self.sampling.rules.iter().enumerate() {
+            let actions = usize::from(rule.rate.is_some())
+                + usize::from(rule.always_keep)
+                + usize::from(rule.always_drop);
+            if actions != 1 {
+                return Err(UrpoError::config(format!(
+                    "Sampling rule {} needs exactly one of rate, always_keep and always_drop",
+                    i + 1
+                )));
+            }
+            if let Some(rate) = rule.rate {
+                if !(0.0..=1.0).contains(&rate) {
+                    return Err(UrpoError::config(format!(
+                        "Invalid rate for sampling rule {}: {}",
+                        i + 1,
+                        rate
+                    )));
+                }
+            }
+        }
+
+        if self.sampling.max_spans_per_second == Some(0) {
+            return Err(UrpoError::config(
+                "sampling.max_spans_per_second must be greater than zero",
+            ));
+        }
+
+        if let Some(ref fairness) = self.sampling.fairness {
+            if fairness.per_service_per_minute == 0 || fairness.global_per_minute == 0 {
+                return Err(UrpoError::config(
+                    "Fair sampling per-service and global rates must be greater than zero",
+                ));
+            }
+            for (service, limits) in &fairness.services {
+                if let (Some(floor), Some(ceiling)) = (limits.floor, limits.ceiling) {
+                    if floor > ceiling {
+                        return Err(UrpoError::config(format!(
+                            "Fair sampling floor {} exceeds ceiling {} for service '{}'",
+                            floor, ceiling, service
+                        )));
+                    }
+                }
+            }
+        }
+
         // Alert validation
         if self.monitoring.alerts.error_rate_threshold < 0.0
             || self.monitoring.alerts.error_rate_threshold > 100.0
@@ -373,6 +1112,91 @@ impl Config {
             )));
         }
 
+        if self.monitoring.apdex_target.is_zero() {
+            return Err(UrpoError::config("Apdex target must be greater than zero"));
+        }
+
+        if let Some(ref rate_limit) = self.rate_limit {
+            rate_limit.limits.validate()?;
+        }
+
+        for rule in &self.alert_rules {
+            if !rule.webhook_url.starts_with("http://") && !rule.webhook_url.starts_with("https://")
+            {
+                return Err(UrpoError::config(format!(
+                    "Alert webhook_url must be an http(s) URL, got '{}'",
+                    rule.webhook_url
+                )));
+            }
+        }
+
+        self.validate_cross_field()
+    }
+
+    /// Check settings that are valid alone but conflict with each other.
+    fn validate_cross_field(&self) -> Result<()> {
+        let server = &self.server;
+        if let Some(port) = self.monitoring.metrics_port {
+            if port == server.grpc_port || port == server.http_port || Some(port) == server.jaeger_port
+            {
+                return Err(UrpoError::config(format!(
+                    "monitoring.metrics_port {} is already used by a receiver; pick a free port",
+                    port
+                )));
+            }
+        }
+
+        let storage = &self.storage;
+        let memory_bytes = storage.max_memory_mb.saturating_mul(1024 * 1024);
+        if memory_bytes / storage.max_spans < MIN_BYTES_PER_SPAN {
+            return Err(UrpoError::config(format!(
+                "max_memory_mb {} is too small for max_spans {}: raise max_memory_mb to at least \
+                 {} or lower max_spans to at most {}",
+                storage.max_memory_mb,
+                storage.max_spans,
+                (storage.max_spans * MIN_BYTES_PER_SPAN).div_ceil(1024 * 1024),
+                memory_bytes / MIN_BYTES_PER_SPAN
+            )));
+        }
+
+        if storage.hot_storage_size > storage.max_spans {
+            return Err(UrpoError::config(format!(
+                "hot_storage_size {} exceeds max_spans {}: lower hot_storage_size to at most {}",
+                storage.hot_storage_size, storage.max_spans, storage.max_spans
+            )));
+        }
+
+        if storage.retention_duration.is_zero() {
+            return Err(UrpoError::config("retention_duration must be greater than 0"));
+        }
+
+        if let Some((service, _)) = storage
+            .retention_overrides
+            .iter()
+            .find(|(_, retention)| retention.is_zero())
+        {
+            return Err(UrpoError::config(format!(
+                "retention override for '{}' must be greater than 0",
+                service.as_str()
+            )));
+        }
+
+        if storage.cleanup_interval > storage.retention_duration {
+            return Err(UrpoError::config(format!(
+                "cleanup_interval {:?} is longer than retention_duration {:?}, so spans outlive \
+                 their retention: lower cleanup_interval to at most {:?}",
+                storage.cleanup_interval, storage.retention_duration, storage.retention_duration
+            )));
+        }
+
+        if storage.enable_archival && storage.archive_after >= storage.retention_duration {
+            return Err(UrpoError::config(format!(
+                "archive_after {:?} is not shorter than retention_duration {:?}, so spans expire \
+                 before they are archived: lower archive_after or raise retention_duration",
+                storage.archive_after, storage.retention_duration
+            )));
+        }
+
         Ok(())
     }
 
@@ -400,11 +1224,7 @@ impl Config {
 
     /// Get sampling rate for a service
     pub fn get_sampling_rate(&self, service: &str) -> f64 {
-        self.sampling
-            .per_service
-            .get(service)
-            .copied()
-            .unwrap_or(self.sampling.default_rate)
+        crate::sampling::ServiceRates::from_config(&self.sampling).rate(service)
     }
 
     /// Should sample based on service and rate
@@ -433,6 +1253,43 @@ impl LogLevel {
     }
 }
 
+/// Check that an address can be used as a listen address.
+///
+/// Unicast and unspecified (`0.0.0.0`, `::`) addresses are accepted; multicast
+/// and the IPv4 broadcast address are not.
+pub fn validate_bind_address(addr: IpAddr) -> Result<()> {
+    let invalid = match addr {
+        IpAddr::V4(v4) => v4.is_multicast() || v4.is_broadcast(),
+        IpAddr::V6(v6) => v6.is_multicast(),
+    };
+    if invalid {
+        return Err(UrpoError::config(format!(
+            "Bind address must be a unicast or unspecified address, got {}",
+            addr
+        )));
+    }
+    Ok(())
+}
+
+/// Check that a CORS origin is `*` or a `scheme://host[:port]` origin.
+pub fn validate_cors_origin(origin: &str) -> Result<()> {
+    let valid = origin == "*"
+        || ["http://", "https://"].iter().any(|scheme| {
+            origin.strip_prefix(scheme).is_some_and(|host| {
+                !host.is_empty()
+                    && !host.contains('/')
+                    && host.bytes().all(|b| b.is_ascii_graphic())
+            })
+        });
+    if !valid {
+        return Err(UrpoError::config(format!(
+            "CORS origin must be \"*\" or scheme://host[:port], got {:?}",
+            origin
+        )));
+    }
+    Ok(())
+}
+
 /// Configuration builder for programmatic construction
 pub struct ConfigBuilder {
     config: Config,
@@ -465,6 +1322,36 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set receiver bind address
+    pub fn bind_address(mut self, addr: IpAddr) -> Self {
+        self.config.server.bind_address = addr;
+        self
+    }
+
+    /// Set maximum accepted OTLP request size in bytes
+    pub fn max_request_bytes(mut self, bytes: usize) -> Self {
+        self.config.server.max_request_bytes = bytes;
+        self
+    }
+
+    /// Set maximum concurrent HTTP/2 streams per gRPC connection
+    pub fn max_concurrent_streams(mut self, streams: u32) -> Self {
+        self.config.server.max_concurrent_streams = Some(streams);
+        self
+    }
+
+    /// Set gRPC request timeout
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.server.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set Jaeger collector port
+    pub fn jaeger_port(mut self, port: u16) -> Self {
+        self.config.server.jaeger_port = Some(port);
+        self
+    }
+
     /// Set max memory
     pub fn max_memory_mb(mut self, mb: usize) -> Self {
         self.config.storage.max_memory_mb = mb;
@@ -483,6 +1370,12 @@ impl ConfigBuilder {
         self
     }
 
+    /// Select the storage backend
+    pub fn storage_backend(mut self, backend: StorageBackendKind) -> Self {
+        self.config.storage.backend = backend;
+        self
+    }
+
     /// Set data directory
     pub fn data_dir(mut self, path: PathBuf) -> Self {
         self.config.storage.data_dir = path;
@@ -612,6 +1505,150 @@ mod tests {
         config.server.grpc_port = 8080;
         config.server.http_port = 8080;
         assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.server.jaeger_port = Some(config.server.http_port);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_cors_origin_validation() {
+        for origin in ["*", "http://localhost:3000", "https://app.example.com"] {
+            let mut config = Config::default();
+            config.server.cors_allowed_origins = vec![origin.to_string()];
+            assert!(config.validate().is_ok(), "{} should be accepted", origin);
+        }
+
+        for origin in ["localhost:3000", "https://", "https://app.example.com/", "http://a b"] {
+            let mut config = Config::default();
+            config.server.cors_allowed_origins = vec![origin.to_string()];
+            assert!(config.validate().is_err(), "{} should be rejected", origin);
+        }
+    }
+
+    #[test]
+    fn test_bind_address_validation() {
+        for addr in ["0.0.0.0", "127.0.0.1", "::", "::1", "192.168.1.10"] {
+            let config = ConfigBuilder::new()
+                .bind_address(addr.parse().unwrap())
+                .build();
+            assert!(config.is_ok(), "{} should be accepted", addr);
+        }
+
+        for addr in ["224.0.0.1", "255.255.255.255", "ff02::1"] {
+            let config = ConfigBuilder::new()
+                .bind_address(addr.parse().unwrap())
+                .build();
+            assert!(config.is_err(), "{} should be rejected", addr);
+        }
+    }
+
+    #[test]
+    fn test_bind_address_parsed_at_load() {
+        let yaml = |addr: &str| {
+            format!(
+                r#"
+server:
+  bind_address: "{}"
+  grpc_port: 4317
+  http_port: 4318
+  max_connections: 1000
+  connection_timeout: 30s
+"#,
+                addr
+            )
+        };
+
+        let config = ConfigBuilder::new()
+            .from_yaml(&yaml("::1"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(config.server.bind_address, "::1".parse::<IpAddr>().unwrap());
+
+        for addr in ["localhost", "0.0.0.0:4317", "[::1]", "127.0.0.256"] {
+            let loaded = ConfigBuilder::new().from_yaml(&yaml(addr));
+            assert!(loaded.is_err(), "{} should be rejected", addr);
+        }
+
+        // Loopback only unless configured otherwise
+        assert!(Config::default().server.bind_address.is_loopback());
+    }
+
+    #[test]
+    fn test_grpc_limits_config() {
+        let yaml = r#"
+server:
+  bind_address: "0.0.0.0"
+  grpc_port: 4317
+  http_port: 4318
+  max_connections: 1000
+  connection_timeout: 30s
+  max_request_bytes: 16777216
+  max_concurrent_streams: 64
+  request_timeout: 10s
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        assert_eq!(config.server.max_request_bytes, 16 * 1024 * 1024);
+        assert_eq!(config.server.max_concurrent_streams, Some(64));
+        assert_eq!(config.server.request_timeout, Some(Duration::from_secs(10)));
+
+        let defaults = Config::default();
+        assert_eq!(defaults.server.max_request_bytes, 4 * 1024 * 1024);
+        assert_eq!(defaults.server.max_concurrent_streams, None);
+        assert_eq!(defaults.server.request_timeout, None);
+
+        assert!(ConfigBuilder::new().max_request_bytes(0).build().is_err());
+        assert!(ConfigBuilder::new().max_concurrent_streams(0).build().is_err());
+        assert!(ConfigBuilder::new().request_timeout(Duration::ZERO).build().is_err());
+    }
+
+    #[test]
+    fn test_span_limits_config() {
+        let yaml = r#"
+server:
+  bind_address: "0.0.0.0"
+  grpc_port: 4317
+  http_port: 4318
+  max_connections: 1000
+  connection_timeout: 30s
+  span_limits:
+    max_attributes: 64
+    max_value_length: 1024
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        assert_eq!(config.server.span_limits.max_attributes, 64);
+        assert_eq!(config.server.span_limits.max_value_length, 1024);
+        assert_eq!(config.server.span_limits.max_key_length, 256);
+        assert_eq!(config.server.span_limits.max_events, 128);
+
+        let invalid = yaml.replace("max_value_length: 1024", "max_value_length: 0");
+        assert!(ConfigBuilder::new().from_yaml(&invalid).unwrap().build().is_err());
+    }
+
+    #[test]
+    fn test_unix_socket_config() {
+        let yaml = r#"
+server:
+  bind_address: "127.0.0.1"
+  grpc_port: 4317
+  http_port: 4318
+  max_connections: 1000
+  connection_timeout: 30s
+  grpc_uds_path: /tmp/urpo-grpc.sock
+  http_uds_path: /tmp/urpo-http.sock
+  uds_mode: 0o660
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        assert_eq!(config.server.grpc_uds_path, Some(PathBuf::from("/tmp/urpo-grpc.sock")));
+        assert_eq!(config.server.http_uds_path, Some(PathBuf::from("/tmp/urpo-http.sock")));
+        assert_eq!(config.server.uds_mode, 0o660);
+        assert_eq!(Config::default().server.uds_mode, 0o600);
+
+        let same_path = yaml.replace("urpo-http.sock", "urpo-grpc.sock");
+        assert!(ConfigBuilder::new().from_yaml(&same_path).unwrap().build().is_err());
+        let bad_mode = yaml.replace("0o660", "0o4755");
+        assert!(ConfigBuilder::new().from_yaml(&bad_mode).unwrap().build().is_err());
     }
 
     #[test]
@@ -678,4 +1715,235 @@ sampling:
         assert_eq!(config.sampling.default_rate, 0.8);
         assert_eq!(config.sampling.per_service.get("high-volume"), Some(&0.1));
     }
+
+    #[test]
+    fn test_retention_overrides_config() {
+        let yaml = r#"
+storage:
+  max_spans: 50000
+  max_memory_mb: 512
+  retention_duration: 1h
+  retention_overrides:
+    "payment-*": 7days
+    "health-*": 5m
+  cleanup_interval: 30s
+  compression_enabled: false
+  persistent: false
+  data_dir: ./urpo_data
+  hot_storage_size: 10000
+  warm_storage_mb: 256
+  cold_retention_hours: 24
+  enable_archival: false
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        let overrides = &config.storage.retention_overrides;
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[&Glob::new("payment-*")], Duration::from_secs(7 * 24 * 3600));
+        assert_eq!(overrides[&Glob::new("health-*")], Duration::from_secs(300));
+
+        let mut config = Config::default();
+        config
+            .storage
+            .retention_overrides
+            .insert(Glob::new("debug-*"), Duration::ZERO);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_kafka_config() {
+        let yaml = r#"
+kafka:
+  brokers: "kafka-1:9092,kafka-2:9092"
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        let kafka = config.kafka.unwrap();
+        assert_eq!(kafka.brokers, "kafka-1:9092,kafka-2:9092");
+        assert_eq!(kafka.topic, "otlp_spans");
+        assert_eq!(kafka.group_id, "urpo");
+
+        let mut config = Config::default();
+        config.kafka = Some(KafkaConfig {
+            brokers: String::new(),
+            topic: default_kafka_topic(),
+            group_id: default_kafka_group_id(),
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_config() {
+        let yaml = r#"
+rate_limit:
+  key: !resource_attribute service.name
+  default:
+    spans_per_second: 5000
+  clients:
+    checkout:
+      spans_per_second: 500
+      burst: 1000
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        let rate_limit = config.rate_limit.unwrap();
+        assert_eq!(rate_limit.key, RateLimitKey::ResourceAttribute("service.name".to_string()));
+        let checkout = rate_limit.limits.limit_for("checkout").unwrap();
+        assert_eq!((checkout.spans_per_second, checkout.capacity()), (500.0, 1000.0));
+        let other = rate_limit.limits.limit_for("search").unwrap();
+        assert_eq!((other.spans_per_second, other.capacity()), (5000.0, 5000.0));
+
+        let mut config = Config::default();
+        config.rate_limit = Some(RateLimitConfig {
+            limits: RateLimits {
+                default: Some(RateLimit {
+                    spans_per_second: 100.0,
+                    burst: Some(0.0),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fairness_config() {
+        let yaml = r#"
+sampling:
+  default_rate: 1.0
+  per_service: {}
+  adaptive: false
+  fairness:
+    per_service_per_minute: 120
+    services:
+      checkout:
+        floor: 30
+      batch-jobs:
+        ceiling: 10
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        let fairness = config.sampling.fairness.unwrap();
+        assert_eq!(fairness.global_per_minute, 6000);
+        assert_eq!(fairness.limits("checkout"), (30, 120));
+        assert_eq!(fairness.limits("batch-jobs"), (6, 10));
+        assert_eq!(fairness.limits("other"), (6, 120));
+
+        let mut config = Config::default();
+        let mut fairness = FairnessConfig::default();
+        fairness.services.insert(
+            "api".to_string(),
+            ServiceFairnessConfig {
+                floor: Some(100),
+                ceiling: Some(10),
+            },
+        );
+        config.sampling.fairness = Some(fairness);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_max_spans_per_second_config() {
+        let yaml = r#"
+sampling:
+  default_rate: 1.0
+  per_service: {}
+  adaptive: false
+  max_spans_per_second: 500
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        assert_eq!(config.sampling.max_spans_per_second, Some(500));
+        assert_eq!(Config::default().sampling.max_spans_per_second, None);
+
+        let mut config = Config::default();
+        config.sampling.max_spans_per_second = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_sampling_rules_config() {
+        let yaml = r#"
+sampling:
+  default_rate: 0.1
+  per_service: {}
+  adaptive: false
+  rules:
+    - service_matches: "payment*"
+      always_keep: true
+    - operation_matches: "GET /health*"
+      always_drop: true
+    - min_duration_ms: 500
+      has_error: false
+      rate: 0.5
+"#;
+        let config = ConfigBuilder::new().from_yaml(yaml).unwrap().build().unwrap();
+        let rules = &config.sampling.rules;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].service_matches.as_str(), "payment*");
+        assert_eq!(rules[0].operation_matches.as_str(), "*");
+        assert!(rules[0].always_keep);
+        assert!(rules[1].always_drop);
+        assert_eq!(rules[2].min_duration_ms, Some(500));
+        assert_eq!(rules[2].has_error, Some(false));
+        assert_eq!(rules[2].rate, Some(0.5));
+
+        // Each rule needs exactly one valid action
+        for action in ["rate: 0.5\n      always_keep: true", "has_error: true", "rate: 1.5"] {
+            let invalid = format!(
+                "sampling:\n  default_rate: 1.0\n  per_service: {{}}\n  adaptive: false\n  \
+                 rules:\n    - service_matches: \"api\"\n      {}\n",
+                action
+            );
+            let built = ConfigBuilder::new().from_yaml(&invalid).unwrap().build();
+            assert!(built.is_err(), "accepted {}", action);
+        }
+    }
+
+    #[test]
+    fn test_cross_field_validation() {
+        let cases: [(fn(&mut Config), &str); 9] = [
+            (|c| c.server.http_port = c.server.grpc_port, "GRPC and HTTP ports must be different"),
+            (|c| c.server.jaeger_port = Some(c.server.grpc_port), "Jaeger port must differ"),
+            (
+                |c| c.monitoring.metrics_port = Some(c.server.http_port),
+                "metrics_port 4318 is already used by a receiver",
+            ),
+            (
+                |c| c.storage.max_memory_mb = 16,
+                "max_memory_mb 16 is too small for max_spans 100000: raise max_memory_mb to at \
+                 least 49 or lower max_spans to at most 32768",
+            ),
+            (
+                |c| c.storage.hot_storage_size = 200_000,
+                "hot_storage_size 200000 exceeds max_spans 100000",
+            ),
+            (
+                |c| c.storage.retention_duration = Duration::ZERO,
+                "retention_duration must be greater than 0",
+            ),
+            (
+                |c| c.storage.cleanup_interval = Duration::from_secs(7200),
+                "cleanup_interval 7200s is longer than retention_duration 3600s",
+            ),
+            (
+                |c| {
+                    c.storage.enable_archival = true;
+                    c.storage.archive_after = Duration::from_secs(3600);
+                },
+                "archive_after 3600s is not shorter than retention_duration 3600s",
+            ),
+            (|c| c.sampling.default_rate = 1.5, "1.5"),
+        ];
+
+        for (invalidate, expected) in cases {
+            let mut config = Config::default();
+            invalidate(&mut config);
+            let message = config.validate().unwrap_err().to_string();
+            assert!(message.contains(expected), "{:?} lacks {:?}", message, expected);
+        }
+
+        // Each setting is fine once the conflicting one follows it
+        let mut config = Config::default();
+        config.storage.max_memory_mb = 16;
+        config.storage.max_spans = 32_768;
+        config.storage.hot_storage_size = 32_768;
+        assert!(config.validate().is_ok());
+    }
 }
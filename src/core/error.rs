@@ -93,6 +93,22 @@ pub enum UrpoError {
 
     #[error("Buffer full: cannot store more items")]
     BufferFull,
+
+    /// Storage is at capacity and rejected incoming spans
+    #[error("Storage full: {rejected} spans rejected")]
+    StorageFull {
+        /// Number of spans that could not be stored
+        rejected: usize,
+    },
+
+    /// The client sent spans faster than its ingestion rate limit
+    #[error("Rate limited: {rejected} spans rejected, retry after {retry_after:?}")]
+    RateLimited {
+        /// Number of spans over the limit
+        rejected: usize,
+        /// When the client's bucket holds enough tokens again
+        retry_after: std::time::Duration,
+    },
 }
 
 /// Result type alias for Urpo operations
@@ -141,10 +157,16 @@ impl UrpoError {
         Self::Storage(format!("Internal error: {}", msg.into()))
     }
 
+    /// Returns true if storage rejected data because it is at capacity
+    pub fn is_storage_full(&self) -> bool {
+        matches!(self, Self::StorageFull { .. } | Self::MemoryLimitExceeded { .. })
+    }
+
     /// Returns true if this error is recoverable
     pub fn is_recoverable(&self) -> bool {
         match self {
             Self::Network(_) => true,
+            Self::StorageFull { .. } | Self::RateLimited { .. } => true,
             Self::Timeout { .. } => true,
             Self::ChannelSend | Self::ChannelReceive => true,
             Self::Grpc(status) => {
@@ -163,7 +185,9 @@ impl UrpoError {
             Self::Render(_) | Self::Terminal(_) => "ui",
             Self::ServiceNotFound(_) | Self::TraceNotFound(_) | Self::NotFound(_) => "not_found",
             Self::InvalidSpan(_) | Self::InvalidSamplingRate(_) => "validation",
-            Self::MemoryLimitExceeded { .. } => "resource",
+            Self::MemoryLimitExceeded { .. }
+            | Self::StorageFull { .. }
+            | Self::RateLimited { .. } => "resource",
             Self::Io(_) => "io",
             Self::Serialization(_) | Self::SerializationError(_) | Self::Parse { .. } => {
                 "serialization"
@@ -205,4 +229,14 @@ mod tests {
         assert_eq!(err.to_string(), "Memory limit exceeded: current 2048MB, limit 1024MB");
         assert_eq!(err.category(), "resource");
     }
+
+    #[test]
+    fn test_storage_full_error() {
+        let err = UrpoError::StorageFull { rejected: 3 };
+        assert_eq!(err.to_string(), "Storage full: 3 spans rejected");
+        assert_eq!(err.category(), "resource");
+        assert!(err.is_storage_full());
+        assert!(err.is_recoverable());
+        assert!(!UrpoError::storage("disk corrupted").is_storage_full());
+    }
 }
@@ -5,17 +5,37 @@
 
 #![warn(missing_docs)]
 
+pub mod apdex;
+pub mod attribute_filter;
+pub mod clock;
+pub mod clock_skew;
 pub mod config;
 pub mod diagnostics;
 pub mod error;
 pub mod otel_compliance;
+pub mod redaction;
 pub mod retry;
+pub mod service_filter;
+pub mod span_tree;
 pub mod string_intern;
 pub mod types;
 
 // Re-export commonly used types
-pub use config::{Config, ConfigBuilder, ConfigWatcher};
+pub use apdex::{operation_apdex, Apdex, OperationApdex};
+pub use attribute_filter::{AttributeFilter, Glob};
+pub use clock::{system_clock, Clock, MockClock, SharedClock, SystemClock};
+pub use clock_skew::{ClockSkewAdjuster, CLOCK_SKEW_ATTRIBUTE};
+pub use config::{
+    AttributeFilterConfig, Config, ConfigBuilder, ConfigWatcher, FairnessConfig, KafkaConfig,
+    LongTermStatsConfig, RateLimit, RateLimitConfig, RateLimitKey, RateLimits, RedactionAction,
+    RedactionConfig, RedactionRule, RestartPolicy, ServiceFairnessConfig, ServiceFilterConfig,
+    SpanLimits, StorageBackendKind, WalConfig,
+};
 pub use error::{Result, UrpoError};
+pub use redaction::Redactor;
+pub use service_filter::ServiceFilter;
+pub use span_tree::{SpanNode, SpanTree};
 pub use types::{
-    ServiceMetrics, ServiceName, Span, SpanBuilder, SpanId, SpanKind, SpanStatus, Trace, TraceId,
+    ResourceInfo, ServiceMetrics, ServiceName, Span, SpanBuilder, SpanEvent, SpanId, SpanKind,
+    SpanLink, SpanStatus, Trace, TraceId,
 };
@@ -0,0 +1,216 @@
+//! PII redaction of span attributes at ingestion.
+//!
+//! Urpo's views are shared with the whole team, so attributes such as
+//! `http.request.header.authorization` or `db.statement` can be dropped,
+//! hashed or truncated before spans reach storage. A [`Redactor`] is built
+//! from the `redaction` config section: rules are tried in order and the
+//! first rule whose exact keys or key regexes match decides the action.
+//!
+//! Span, resource, event and link attributes are all redacted.
+
+use crate::core::config::{RedactionAction, RedactionConfig};
+use crate::core::types::{AttributeMap, Span};
+use crate::core::{Result, UrpoError};
+use regex::RegexSet;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A compiled redaction rule.
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    keys: HashSet<String>,
+    patterns: RegexSet,
+    action: RedactionAction,
+    max_length: usize,
+}
+
+impl CompiledRule {
+    fn matches(&self, key: &str) -> bool {
+        self.keys.contains(key) || self.patterns.is_match(key)
+    }
+}
+
+/// Applies redaction rules to span attributes.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<CompiledRule>,
+}
+
+impl Redactor {
+    /// Compile the rules of the `redaction` config section.
+    pub fn from_config(config: &RedactionConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let patterns = RegexSet::new(&rule.patterns)
+                    .map_err(|e| UrpoError::config(format!("Invalid redaction pattern: {}", e)))?;
+                Ok(CompiledRule {
+                    keys: rule.keys.iter().cloned().collect(),
+                    patterns,
+                    action: rule.action,
+                    max_length: rule.max_length,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Returns true if no rules are configured.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Redact an attribute map in place, returning how many attributes changed.
+    pub fn redact_attributes(&self, attributes: &mut AttributeMap) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut redacted = 0;
+        attributes.0.retain(|(key, value)| {
+            let Some(rule) = self.rules.iter().find(|rule| rule.matches(key)) else {
+                return true;
+            };
+            match rule.action {
+                RedactionAction::Drop => {
+                    redacted += 1;
+                    return false;
+                },
+                RedactionAction::Hash => {
+                    *value = Arc::from(hash_value(value));
+                    redacted += 1;
+                },
+                RedactionAction::Truncate => {
+                    if let Some((end, _)) = value.char_indices().nth(rule.max_length) {
+                        *value = Arc::from(&value[..end]);
+                        redacted += 1;
+                    }
+                },
+            }
+            true
+        });
+        redacted
+    }
+
+    /// Redact every attribute of `span`, returning how many changed.
+    pub fn redact_span(&self, span: &mut Span) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut redacted = self.redact_attributes(&mut span.attributes)
+            + self.redact_attributes(&mut span.tags)
+            + self.redact_attributes(&mut span.resource_attributes);
+        for event in &mut span.events {
+            redacted += self.redact_attributes(&mut event.attributes);
+        }
+        for link in &mut span.links {
+            redacted += self.redact_attributes(&mut link.attributes);
+        }
+        redacted
+    }
+}
+
+/// Stable FNV-1a hash of a value, so equal values still correlate.
+fn hash_value(value: &str) -> String {
+    let hash = value.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("hash:{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::RedactionRule;
+
+    fn rule(keys: &[&str], patterns: &[&str], action: RedactionAction) -> RedactionRule {
+        RedactionRule {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            action,
+            max_length: 4,
+        }
+    }
+
+    fn attrs(pairs: &[(&str, &str)]) -> AttributeMap {
+        let mut map = AttributeMap::new();
+        for (key, value) in pairs {
+            map.push(Arc::from(*key), Arc::from(*value));
+        }
+        map
+    }
+
+    #[test]
+    fn test_drop_hash_and_truncate() {
+        let redactor = Redactor::from_config(&RedactionConfig {
+            rules: vec![
+                rule(&["http.request.header.authorization"], &[], RedactionAction::Drop),
+                rule(&[], &[r"^user\."], RedactionAction::Hash),
+                rule(&["db.statement"], &[], RedactionAction::Truncate),
+            ],
+        })
+        .unwrap();
+        let mut map = attrs(&[
+            ("http.request.header.authorization", "Bearer s3cret"),
+            ("user.email", "ada@example.com"),
+            ("db.statement", "SELECT * FROM users"),
+            ("http.method", "GET"),
+        ]);
+
+        assert_eq!(redactor.redact_attributes(&mut map), 3);
+        assert_eq!(map.get("http.request.header.authorization"), None);
+        assert_eq!(map.get("db.statement"), Some("SELE"));
+        assert_eq!(map.get("http.method"), Some("GET"));
+        let hashed = map.get("user.email").unwrap();
+        assert!(hashed.starts_with("hash:"));
+        assert_eq!(hashed, hash_value("ada@example.com"));
+        assert_ne!(hashed, hash_value("bob@example.com"));
+
+        // Values already short enough are left alone
+        let mut shorter = attrs(&[("db.statement", "END")]);
+        assert_eq!(redactor.redact_attributes(&mut shorter), 0);
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let redactor = Redactor::from_config(&RedactionConfig {
+            rules: vec![
+                rule(&["user.id"], &[], RedactionAction::Hash),
+                rule(&[], &[r"^user\."], RedactionAction::Drop),
+            ],
+        })
+        .unwrap();
+        let mut map = attrs(&[("user.id", "42"), ("user.name", "ada")]);
+
+        redactor.redact_attributes(&mut map);
+        assert_eq!(map.get("user.id"), Some(hash_value("42").as_str()));
+        assert!(!map.contains_key("user.name"));
+    }
+
+    #[test]
+    fn test_from_yaml_config() {
+        let yaml = r#"
+redaction:
+  rules:
+    - keys: ["http.request.header.authorization"]
+      action: drop
+    - patterns: ["(?i)password"]
+      action: hash
+"#;
+        let config = crate::core::ConfigBuilder::new()
+            .from_yaml(yaml)
+            .unwrap()
+            .build()
+            .unwrap();
+        let redactor = Redactor::from_config(config.redaction.as_ref().unwrap()).unwrap();
+        let mut map = attrs(&[("db.Password", "hunter2")]);
+        assert_eq!(redactor.redact_attributes(&mut map), 1);
+
+        let invalid = "redaction:\n  rules:\n    - patterns: [\"(\"]\n      action: drop\n";
+        assert!(crate::core::ConfigBuilder::new()
+            .from_yaml(invalid)
+            .unwrap()
+            .build()
+            .is_err());
+    }
+}
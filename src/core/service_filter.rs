@@ -0,0 +1,106 @@
+//! Service include/exclude filtering at the receiver.
+//!
+//! A [`ServiceFilter`] decides by service name whether received spans are
+//! kept at all, before sampling, storage and event broadcasting. Names are
+//! matched against [`Glob`] patterns, so exact names and patterns such as
+//! `checkout-*` can be mixed.
+//!
+//! A service is kept when it matches no exclude pattern and, if any include
+//! patterns are configured, matches at least one of them. Exclude always wins.
+
+use crate::core::attribute_filter::Glob;
+use crate::core::config::ServiceFilterConfig;
+
+/// Keeps or drops spans by service name according to include/exclude glob lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceFilter {
+    /// Services to keep; empty means keep everything not excluded
+    pub include: Vec<Glob>,
+    /// Services to drop; takes precedence over `include`
+    pub exclude: Vec<Glob>,
+}
+
+impl ServiceFilter {
+    /// Create a filter from include and exclude patterns.
+    pub fn new(include: Vec<Glob>, exclude: Vec<Glob>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// Build a filter from the `receiver` config section.
+    pub fn from_config(config: &ServiceFilterConfig) -> Self {
+        Self::new(
+            config
+                .include_services
+                .iter()
+                .cloned()
+                .map(Glob::new)
+                .collect(),
+            config
+                .exclude_services
+                .iter()
+                .cloned()
+                .map(Glob::new)
+                .collect(),
+        )
+    }
+
+    /// Returns true if the filter keeps every service.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Check whether spans of `service` are kept.
+    pub fn allows(&self, service: &str) -> bool {
+        if self.exclude.iter().any(|glob| glob.matches(service)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|glob| glob.matches(service))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn globs(patterns: &[&str]) -> Vec<Glob> {
+        patterns.iter().map(|pattern| Glob::new(*pattern)).collect()
+    }
+
+    #[test]
+    fn test_include_matches_names_and_globs() {
+        let filter = ServiceFilter::new(globs(&["payments", "checkout-*"]), Vec::new());
+
+        assert!(filter.allows("payments"));
+        assert!(filter.allows("checkout-api"));
+        assert!(filter.allows("checkout-"));
+        assert!(!filter.allows("payments-worker"));
+        assert!(!filter.allows("inventory"));
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let filter =
+            ServiceFilter::new(globs(&["checkout-*"]), globs(&["checkout-canary", "*-test"]));
+
+        assert!(filter.allows("checkout-api"));
+        assert!(!filter.allows("checkout-canary"));
+        assert!(!filter.allows("checkout-test"));
+        // Outside the include list even though not excluded
+        assert!(!filter.allows("frontend"));
+    }
+
+    #[test]
+    fn test_exclude_only_and_empty() {
+        let filter = ServiceFilter::from_config(&ServiceFilterConfig {
+            include_services: Vec::new(),
+            exclude_services: vec!["healthcheck?".to_string()],
+        });
+        assert!(filter.allows("frontend"));
+        assert!(!filter.allows("healthcheck1"));
+        assert!(filter.allows("healthcheck"));
+
+        let empty = ServiceFilter::default();
+        assert!(empty.is_empty());
+        assert!(empty.allows("anything"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Parent/child span tree of a trace, ready for rendering.
+//!
+//! Span data from the wild is not always a well-formed tree: parents can be
+//! missing (not received yet, sampled out, evicted), span IDs can repeat, and
+//! broken instrumentation can produce parent cycles. The builder never loses
+//! a span to any of these: spans whose parent is missing, or whose parent
+//! chain loops, become extra roots flagged as orphans, and duplicate span IDs
+//! keep their first occurrence.
+
+use super::types::{Span, SpanId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// A span with its children, ordered by start time.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanNode {
+    /// The span itself
+    pub span: Span,
+    /// Has a parent that is not in the tree (missing, or part of a cycle)
+    pub orphan: bool,
+    /// Child spans, earliest first
+    pub children: Vec<SpanNode>,
+}
+
+/// The spans of one trace arranged by parent.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpanTree {
+    /// Root spans and orphans, earliest first
+    pub roots: Vec<SpanNode>,
+}
+
+impl SpanTree {
+    /// Arrange `spans` by parent span ID.
+    pub fn build(mut spans: Vec<Span>) -> Self {
+        spans.sort_by_key(|span| span.start_time);
+        let mut seen = HashSet::new();
+        spans.retain(|span| seen.insert(span.span_id.clone()));
+
+        let index: HashMap<&SpanId, usize> = spans
+            .iter()
+            .enumerate()
+            .map(|(i, span)| (&span.span_id, i))
+            .collect();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); spans.len()];
+        let mut roots = Vec::new();
+        let mut orphan = vec![false; spans.len()];
+        for (i, span) in spans.iter().enumerate() {
+            match span.parent_span_id.as_ref().map(|parent| index.get(parent)) {
+                None => roots.push(i),
+                Some(Some(&parent)) if parent != i => children[parent].push(i),
+                Some(_) => {
+                    orphan[i] = true;
+                    roots.push(i);
+                },
+            }
+        }
+
+        let mut slots: Vec<Option<Span>> = spans.into_iter().map(Some).collect();
+        let mut visited = vec![false; slots.len()];
+        let mut tree = Self::default();
+        for root in roots {
+            tree.roots
+                .push(build_subtree(root, &children, &orphan, &mut slots, &mut visited));
+        }
+        // Whatever is left only hangs off a parent cycle; cut it at the earliest span
+        for i in 0..slots.len() {
+            if !visited[i] {
+                orphan[i] = true;
+                tree.roots
+                    .push(build_subtree(i, &children, &orphan, &mut slots, &mut visited));
+            }
+        }
+        tree.roots.sort_by_key(|node| node.span.start_time);
+        tree
+    }
+
+    /// Number of spans in the tree.
+    pub fn span_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack: Vec<&SpanNode> = self.roots.iter().collect();
+        while let Some(node) = stack.pop() {
+            count += 1;
+            stack.extend(&node.children);
+        }
+        count
+    }
+
+    /// Spans on the trace's critical path, earliest first.
+    ///
+    /// The path starts at the root that finishes last. Walking back from a
+    /// span's end, the child that finished last before that point is on the
+    /// path, then the search continues from that child's start; each chosen
+    /// child is searched the same way, up to the point its parent needed it.
+    pub fn critical_path(&self) -> Vec<&Span> {
+        let Some(root) = self.roots.iter().max_by_key(|node| node.span.end_time()) else {
+            return Vec::new();
+        };
+
+        let mut path = Vec::new();
+        let mut stack = vec![(root, root.span.end_time())];
+        while let Some((node, end)) = stack.pop() {
+            path.push(&node.span);
+            let mut children: Vec<&SpanNode> = node.children.iter().collect();
+            children.sort_by_key(|child| std::cmp::Reverse(child.span.end_time()));
+            let mut cursor = end;
+            for child in children {
+                if child.span.start_time < cursor {
+                    stack.push((child, child.span.end_time().min(cursor)));
+                    cursor = child.span.start_time;
+                }
+            }
+        }
+        path.sort_by_key(|span| span.start_time);
+        path
+    }
+}
+
+/// Build the subtree under `root` with an explicit stack. Spans already
+/// placed are skipped, which breaks cycles.
+fn build_subtree(
+    root: usize,
+    children: &[Vec<usize>],
+    orphan: &[bool],
+    slots: &mut [Option<Span>],
+    visited: &mut [bool],
+) -> SpanNode {
+    visited[root] = true;
+    // (span index, next child to visit, built children)
+    let mut stack = vec![(root, 0, Vec::new())];
+    loop {
+        let (i, next, _) = stack
+            .last_mut()
+            .expect("stack holds the root until it is built");
+        if let Some(&child) = children[*i].get(*next) {
+            *next += 1;
+            if !visited[child] {
+                visited[child] = true;
+                stack.push((child, 0, Vec::new()));
+            }
+            continue;
+        }
+
+        let (i, _, built) = stack.pop().expect("stack is not empty");
+        let node = SpanNode {
+            span: slots[i].take().expect("each span is built once"),
+            orphan: orphan[i],
+            children: built,
+        };
+        match stack.last_mut() {
+            Some((_, _, siblings)) => siblings.push(node),
+            None => return node,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, TraceId};
+    use std::time::{Duration, SystemTime};
+
+    fn span(id: u64, parent: Option<u64>) -> Span {
+        let mut builder = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name(format!("op-{}", id))
+            .start_time(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + id));
+        if let Some(parent) = parent {
+            builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    fn ids(nodes: &[SpanNode]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|node| node.span.operation_name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_nesting_and_child_order() {
+        // Out of order on purpose
+        let tree = SpanTree::build(vec![
+            span(3, Some(1)),
+            span(2, Some(1)),
+            span(4, Some(2)),
+            span(1, None),
+        ]);
+
+        assert_eq!(ids(&tree.roots), vec!["op-1"]);
+        let root = &tree.roots[0];
+        assert!(!root.orphan);
+        assert_eq!(ids(&root.children), vec!["op-2", "op-3"]);
+        assert_eq!(ids(&root.children[0].children), vec!["op-4"]);
+        assert_eq!(tree.span_count(), 4);
+    }
+
+    #[test]
+    fn test_orphans_cycles_and_duplicates_are_kept() {
+        let tree = SpanTree::build(vec![
+            span(1, None),
+            span(1, None),
+            // Parent never arrived
+            span(2, Some(99)),
+            span(3, Some(2)),
+            // 4 -> 5 -> 4, and 6 is its own parent
+            span(4, Some(5)),
+            span(5, Some(4)),
+            span(6, Some(6)),
+        ]);
+
+        assert_eq!(tree.span_count(), 6);
+        assert_eq!(ids(&tree.roots), vec!["op-1", "op-2", "op-4", "op-6"]);
+        let orphans: Vec<bool> = tree.roots.iter().map(|node| node.orphan).collect();
+        assert_eq!(orphans, vec![false, true, true, true]);
+        assert_eq!(ids(&tree.roots[1].children), vec!["op-3"]);
+        assert_eq!(ids(&tree.roots[2].children), vec!["op-5"]);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let timed = |id, parent, start_ms, end_ms| {
+            let mut span = span(id, parent);
+            span.start_time = SystemTime::UNIX_EPOCH + Duration::from_millis(start_ms);
+            span.duration = Duration::from_millis(end_ms - start_ms);
+            span
+        };
+        let tree = SpanTree::build(vec![
+            timed(1, None, 0, 100),
+            // 2 runs alongside 3 and finishes first, so it never blocks the root
+            timed(2, Some(1), 2, 30),
+            timed(3, Some(1), 1, 60),
+            timed(7, Some(3), 15, 55),
+            timed(4, Some(1), 60, 100),
+            timed(6, Some(4), 61, 63),
+            timed(5, Some(4), 65, 95),
+        ]);
+
+        let path: Vec<&str> = tree
+            .critical_path()
+            .iter()
+            .map(|span| span.operation_name.as_str())
+            .collect();
+        assert_eq!(path, vec!["op-1", "op-3", "op-7", "op-4", "op-6", "op-5"]);
+        assert!(SpanTree::default().critical_path().is_empty());
+    }
+}
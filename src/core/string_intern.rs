@@ -173,6 +173,23 @@ pub fn get_string(id: InternId) -> Option<Arc<str>> {
     GLOBAL_INTERN.get(id)
 }
 
+/// Size of the global string intern table.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct InternStats {
+    /// Number of interned strings
+    pub strings: usize,
+    /// Estimated memory usage in bytes
+    pub memory_bytes: usize,
+}
+
+/// Get the size of the global table
+pub fn global_stats() -> InternStats {
+    InternStats {
+        strings: GLOBAL_INTERN.len(),
+        memory_bytes: GLOBAL_INTERN.memory_usage(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -455,6 +455,65 @@ impl SpanStatus {
     }
 }
 
+/// Timestamped event recorded during a span (e.g. an exception)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanEvent {
+    /// Event name (`exception` for recorded errors)
+    pub name: String,
+    /// When the event occurred
+    pub timestamp: SystemTime,
+    /// Event attributes (e.g. `exception.stacktrace`)
+    pub attributes: AttributeMap,
+}
+
+impl SpanEvent {
+    /// Create an event without attributes
+    pub fn new<S: Into<String>>(name: S, timestamp: SystemTime) -> Self {
+        Self {
+            name: name.into(),
+            timestamp,
+            attributes: AttributeMap::new(),
+        }
+    }
+
+    /// Add an attribute to the event
+    pub fn with_attribute<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.attributes
+            .push(Arc::from(key.into().as_str()), Arc::from(value.into().as_str()));
+        self
+    }
+}
+
+/// Link from a span to a span in another (or the same) trace, e.g. from a
+/// message consumer to its producer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanLink {
+    /// Trace of the linked span
+    pub trace_id: TraceId,
+    /// The linked span
+    pub span_id: SpanId,
+    /// Link attributes
+    pub attributes: AttributeMap,
+}
+
+impl SpanLink {
+    /// Create a link without attributes
+    pub fn new(trace_id: TraceId, span_id: SpanId) -> Self {
+        Self {
+            trace_id,
+            span_id,
+            attributes: AttributeMap::new(),
+        }
+    }
+
+    /// Add an attribute to the link
+    pub fn with_attribute<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.attributes
+            .push(Arc::from(key.into().as_str()), Arc::from(value.into().as_str()));
+        self
+    }
+}
+
 /// Represents a single span in a distributed trace
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Span {
@@ -468,9 +527,9 @@ pub struct Span {
     pub service_name: ServiceName,
     /// Name of the operation this span represents
     pub operation_name: String,
-    /// When the span started
+    /// When the span started, at the nanosecond precision OTLP delivers
     pub start_time: SystemTime,
-    /// How long the span took to complete
+    /// How long the span took to complete, at nanosecond precision
     pub duration: Duration,
     /// Type/kind of the span
     pub kind: SpanKind,
@@ -482,6 +541,12 @@ pub struct Span {
     pub tags: AttributeMap,
     /// Resource attributes (e.g., host, container info)
     pub resource_attributes: AttributeMap,
+    /// Events recorded during the span, in arrival order
+    #[serde(default)]
+    pub events: Vec<SpanEvent>,
+    /// Links to related spans, typically in other traces
+    #[serde(default)]
+    pub links: Vec<SpanLink>,
 }
 
 impl Span {
@@ -529,6 +594,98 @@ impl Span {
     pub fn duration_ms(&self) -> u64 {
         self.duration.as_millis() as u64
     }
+
+    /// Kubernetes and cloud placement from the resource attributes
+    pub fn resource_info(&self) -> ResourceInfo {
+        ResourceInfo::from_attributes(&self.resource_attributes)
+    }
+}
+
+/// Kubernetes and cloud placement of a span's resource (OTEL semantic conventions)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceInfo {
+    /// `k8s.namespace.name`
+    pub k8s_namespace_name: Option<String>,
+    /// `k8s.pod.name`
+    pub k8s_pod_name: Option<String>,
+    /// `k8s.pod.uid`
+    pub k8s_pod_uid: Option<String>,
+    /// `k8s.node.name`
+    pub k8s_node_name: Option<String>,
+    /// `k8s.deployment.name`
+    pub k8s_deployment_name: Option<String>,
+    /// `k8s.container.name`
+    pub k8s_container_name: Option<String>,
+    /// `cloud.provider`
+    pub cloud_provider: Option<String>,
+    /// `cloud.region`
+    pub cloud_region: Option<String>,
+    /// `cloud.availability_zone`
+    pub cloud_availability_zone: Option<String>,
+}
+
+impl ResourceInfo {
+    /// Resource attribute keys captured, in field order
+    pub const KEYS: [&'static str; 9] = [
+        "k8s.namespace.name",
+        "k8s.pod.name",
+        "k8s.pod.uid",
+        "k8s.node.name",
+        "k8s.deployment.name",
+        "k8s.container.name",
+        "cloud.provider",
+        "cloud.region",
+        "cloud.availability_zone",
+    ];
+
+    /// Build from a lookup of resource attribute values by key
+    pub fn from_lookup<F>(mut lookup: F) -> Self
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let [namespace, pod, pod_uid, node, deployment, container, provider, region, zone] =
+            Self::KEYS.map(&mut lookup);
+        Self {
+            k8s_namespace_name: namespace,
+            k8s_pod_name: pod,
+            k8s_pod_uid: pod_uid,
+            k8s_node_name: node,
+            k8s_deployment_name: deployment,
+            k8s_container_name: container,
+            cloud_provider: provider,
+            cloud_region: region,
+            cloud_availability_zone: zone,
+        }
+    }
+
+    /// Build from a span's resource attributes
+    pub fn from_attributes(attributes: &AttributeMap) -> Self {
+        Self::from_lookup(|key| attributes.get(key).map(str::to_string))
+    }
+
+    /// Present attributes as `(key, value)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> + '_ {
+        let values = [
+            &self.k8s_namespace_name,
+            &self.k8s_pod_name,
+            &self.k8s_pod_uid,
+            &self.k8s_node_name,
+            &self.k8s_deployment_name,
+            &self.k8s_container_name,
+            &self.cloud_provider,
+            &self.cloud_region,
+            &self.cloud_availability_zone,
+        ];
+        Self::KEYS
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.as_deref().map(|v| (key, v)))
+    }
+
+    /// True if no attribute is present
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
 }
 
 /// Builder for creating Span instances
@@ -546,6 +703,8 @@ pub struct SpanBuilder {
     attributes: AttributeMap,
     tags: AttributeMap,
     resource_attributes: AttributeMap,
+    events: Vec<SpanEvent>,
+    links: Vec<SpanLink>,
 }
 
 impl SpanBuilder {
@@ -616,6 +775,16 @@ impl SpanBuilder {
         self
     }
 
+    pub fn event(mut self, event: SpanEvent) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    pub fn link(mut self, link: SpanLink) -> Self {
+        self.links.push(link);
+        self
+    }
+
     /// Build a default span for pool allocation.
     /// Used internally by the span pool for pre-allocation.
     pub fn build_default(self) -> Span {
@@ -632,6 +801,8 @@ impl SpanBuilder {
             attributes: AttributeMap::new(),
             tags: AttributeMap::new(),
             resource_attributes: AttributeMap::new(),
+            events: Vec::new(),
+            links: Vec::new(),
         }
     }
 
@@ -657,6 +828,8 @@ impl SpanBuilder {
             attributes: self.attributes,
             tags: self.tags,
             resource_attributes: self.resource_attributes,
+            events: self.events,
+            links: self.links,
         })
     }
 }
@@ -809,6 +982,15 @@ pub struct ServiceMetrics {
     pub max_duration: Duration,
     /// Minimum duration observed
     pub min_duration: Duration,
+    /// Median delay between a span ending and it being stored
+    #[serde(default)]
+    pub ingest_lag_p50: Duration,
+    /// 95th percentile ingest delay
+    #[serde(default)]
+    pub ingest_lag_p95: Duration,
+    /// 99th percentile ingest delay
+    #[serde(default)]
+    pub ingest_lag_p99: Duration,
 }
 
 impl ServiceMetrics {
@@ -827,6 +1009,9 @@ impl ServiceMetrics {
             avg_duration: Duration::from_millis(0),
             max_duration: Duration::from_millis(0),
             min_duration: Duration::from_millis(0),
+            ingest_lag_p50: Duration::from_millis(0),
+            ingest_lag_p95: Duration::from_millis(0),
+            ingest_lag_p99: Duration::from_millis(0),
         }
     }
 
@@ -851,6 +1036,9 @@ impl ServiceMetrics {
             avg_duration,
             max_duration: avg_duration,
             min_duration: avg_duration,
+            ingest_lag_p50: Duration::from_millis(0),
+            ingest_lag_p95: Duration::from_millis(0),
+            ingest_lag_p99: Duration::from_millis(0),
         }
     }
 
@@ -924,6 +1112,36 @@ mod tests {
         assert_eq!(span.get_attribute("key"), Some("value"));
     }
 
+    #[test]
+    fn test_resource_info_from_span() {
+        let span = Span::builder()
+            .trace_id(TraceId::new("trace1".to_string()).unwrap())
+            .span_id(SpanId::new("span1".to_string()).unwrap())
+            .service_name(ServiceName::new("test-service".to_string()).unwrap())
+            .operation_name("test-op")
+            .resource_attribute("k8s.pod.name", "checkout-7d9f")
+            .resource_attribute("k8s.namespace.name", "shop")
+            .resource_attribute("cloud.region", "eu-west-1")
+            .resource_attribute("host.name", "ignored")
+            .build()
+            .unwrap();
+
+        let info = span.resource_info();
+        assert_eq!(info.k8s_pod_name.as_deref(), Some("checkout-7d9f"));
+        assert_eq!(info.k8s_namespace_name.as_deref(), Some("shop"));
+        assert_eq!(info.cloud_region.as_deref(), Some("eu-west-1"));
+        assert_eq!(info.k8s_node_name, None);
+        assert_eq!(
+            info.iter().collect::<Vec<_>>(),
+            vec![
+                ("k8s.namespace.name", "shop"),
+                ("k8s.pod.name", "checkout-7d9f"),
+                ("cloud.region", "eu-west-1"),
+            ]
+        );
+        assert!(ResourceInfo::default().is_empty());
+    }
+
     #[test]
     fn test_trace_from_spans() {
         let trace_id = TraceId::new("trace1".to_string()).unwrap();
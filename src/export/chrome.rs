@@ -0,0 +1,244 @@
+//! Chrome trace event export for chrome://tracing and Perfetto.
+//!
+//! Every span becomes a complete (`"ph": "X"`) event with `ts` and `dur` in
+//! microseconds. Each service is a process; within a service, spans are laid
+//! out on threads so that events of one thread nest strictly, which is what
+//! the viewers need to draw a flame graph. A span stays on its parent's
+//! thread when it fits inside the parent; overlapping siblings move to
+//! another thread. Metadata (`"ph": "M"`) events name processes and threads.
+
+use super::{micros, unix_micros};
+use crate::core::{Span, SpanId};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One Chrome trace event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChromeEvent {
+    /// Operation name, or `process_name`/`thread_name` for metadata
+    pub name: String,
+    /// Span kind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cat: Option<String>,
+    /// `X` for spans, `M` for metadata
+    pub ph: &'static str,
+    /// Microseconds since the Unix epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<u64>,
+    /// Microseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dur: Option<u64>,
+    /// Service of the span
+    pub pid: u32,
+    /// Thread within the service
+    pub tid: u32,
+    /// Span IDs, status and attributes, or the metadata name
+    pub args: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Threads of one service, each a stack of the end times of open events.
+#[derive(Default)]
+struct Threads {
+    open: Vec<Vec<u64>>,
+}
+
+impl Threads {
+    /// Whether an event from `start` to `end` nests on `thread`, closing
+    /// the events there that ended before it.
+    fn fits(&mut self, thread: usize, start: u64, end: u64) -> bool {
+        let open = &mut self.open[thread];
+        while open.last().map_or(false, |&open_end| open_end <= start) {
+            open.pop();
+        }
+        open.last().map_or(true, |&open_end| end <= open_end)
+    }
+
+    /// Place an event, preferring `preferred`, and return its thread.
+    fn place(&mut self, preferred: Option<usize>, start: u64, end: u64) -> usize {
+        let thread = preferred
+            .filter(|&thread| self.fits(thread, start, end))
+            .or_else(|| (0..self.open.len()).find(|&thread| self.fits(thread, start, end)))
+            .unwrap_or_else(|| {
+                self.open.push(Vec::new());
+                self.open.len() - 1
+            });
+        self.open[thread].push(end);
+        thread
+    }
+}
+
+/// Convert spans, of one or more traces, to Chrome trace events.
+pub fn convert_to_chrome_trace(spans: &[Span]) -> Vec<ChromeEvent> {
+    let mut ordered: Vec<&Span> = spans.iter().collect();
+    // Parents before the children starting with them
+    ordered.sort_by_key(|span| (unix_micros(span.start_time), std::cmp::Reverse(span.duration)));
+
+    let mut pids: HashMap<&str, (u32, Threads)> = HashMap::new();
+    let mut placed: HashMap<&SpanId, (&str, usize)> = HashMap::new();
+    let mut events = Vec::with_capacity(spans.len());
+    for span in ordered {
+        let service = span.service_name.as_str();
+        let next_pid = pids.len() as u32 + 1;
+        let (pid, threads) = pids
+            .entry(service)
+            .or_insert_with(|| (next_pid, Threads::default()));
+
+        let start = unix_micros(span.start_time);
+        let end = start.max(unix_micros(span.start_time + span.duration));
+        let parent_thread = span
+            .parent_span_id
+            .as_ref()
+            .and_then(|parent| placed.get(parent))
+            .filter(|(parent_service, _)| *parent_service == service)
+            .map(|&(_, thread)| thread);
+        let thread = threads.place(parent_thread, start, end);
+        placed.insert(&span.span_id, (service, thread));
+
+        events.push(ChromeEvent {
+            name: span.operation_name.clone(),
+            cat: Some(format!("{:?}", span.kind).to_lowercase()),
+            ph: "X",
+            ts: Some(start),
+            dur: Some(end - start),
+            pid: *pid,
+            tid: thread as u32 + 1,
+            args: span_args(span),
+        });
+    }
+
+    let mut metadata = Vec::new();
+    for (service, (pid, threads)) in &pids {
+        metadata.push(metadata_event("process_name", *pid, 0, service));
+        for thread in 0..threads.open.len() {
+            let tid = thread as u32 + 1;
+            metadata.push(metadata_event(
+                "thread_name",
+                *pid,
+                tid,
+                &format!("{} #{}", service, tid),
+            ));
+        }
+    }
+    metadata.sort_by_key(|event| (event.pid, event.tid));
+    metadata.extend(events);
+    metadata
+}
+
+fn metadata_event(name: &str, pid: u32, tid: u32, value: &str) -> ChromeEvent {
+    let mut args = serde_json::Map::new();
+    args.insert("name".to_string(), value.into());
+    ChromeEvent {
+        name: name.to_string(),
+        cat: None,
+        ph: "M",
+        ts: None,
+        dur: None,
+        pid,
+        tid,
+        args,
+    }
+}
+
+fn span_args(span: &Span) -> serde_json::Map<String, serde_json::Value> {
+    let mut args = serde_json::Map::new();
+    args.insert("trace_id".to_string(), span.trace_id.as_str().into());
+    args.insert("span_id".to_string(), span.span_id.as_str().into());
+    if let Some(ref parent) = span.parent_span_id {
+        args.insert("parent_span_id".to_string(), parent.as_str().into());
+    }
+    let status = if span.status.is_error() {
+        "ERROR"
+    } else {
+        "OK"
+    };
+    args.insert("status".to_string(), status.into());
+    args.insert("duration_us".to_string(), micros(span.duration).into());
+    for (key, value) in span.attributes.iter() {
+        args.insert(key.to_string(), value.into());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, TraceId};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn span(id: u64, parent: Option<u64>, service: &str, start_ms: u64, millis: u64) -> Span {
+        let mut builder = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name(format!("op-{}", id))
+            .start_time(start(start_ms))
+            .duration(Duration::from_millis(millis));
+        if let Some(parent) = parent {
+            builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    fn start(ms: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000) + Duration::from_millis(ms)
+    }
+
+    fn complete<'a>(events: &'a [ChromeEvent], name: &str) -> &'a ChromeEvent {
+        events
+            .iter()
+            .find(|event| event.ph == "X" && event.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parent_and_child_nest() {
+        // The child is listed first; order in storage does not matter
+        let spans = vec![span(2, Some(1), "api", 10, 50), span(1, None, "api", 0, 100)];
+        let events = convert_to_chrome_trace(&spans);
+
+        let parent = complete(&events, "op-1");
+        let child = complete(&events, "op-2");
+        assert_eq!((parent.pid, parent.tid), (child.pid, child.tid));
+        assert_eq!(parent.ts, Some(1_700_000_000_000_000));
+        assert_eq!(parent.dur, Some(100_000));
+        assert_eq!(child.ts, Some(1_700_000_000_010_000));
+        assert_eq!(child.dur, Some(50_000));
+        assert_eq!(child.args["parent_span_id"], format!("{:016x}", 1));
+
+        let json = serde_json::to_value(&events).unwrap();
+        let process = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|event| event["name"] == "process_name")
+            .unwrap();
+        assert_eq!(process["ph"], "M");
+        assert_eq!(process["args"]["name"], "api");
+        assert!(json[events.len() - 1].get("cat").is_some());
+    }
+
+    #[test]
+    fn test_overlapping_siblings_and_services_get_own_tracks() {
+        let spans = vec![
+            span(1, None, "api", 0, 100),
+            span(2, Some(1), "api", 10, 50),
+            // Overlaps its sibling without nesting in it
+            span(3, Some(1), "api", 40, 50),
+            span(4, Some(2), "db", 20, 10),
+        ];
+        let events = convert_to_chrome_trace(&spans);
+
+        let (root, first, second) =
+            (complete(&events, "op-1"), complete(&events, "op-2"), complete(&events, "op-3"));
+        assert_eq!(first.tid, root.tid);
+        assert_ne!(second.tid, root.tid);
+        assert_eq!(second.pid, root.pid);
+        assert_ne!(complete(&events, "op-4").pid, root.pid);
+
+        let threads = events
+            .iter()
+            .filter(|event| event.name == "thread_name")
+            .count();
+        assert_eq!(threads, 3);
+    }
+}
@@ -0,0 +1,275 @@
+//! Comparison of the spans of two traces.
+//!
+//! Spans are matched by service and operation name. When a trace runs the
+//! same operation several times, its occurrences are paired in start time
+//! order, so the third `SELECT` of one trace is compared with the third of
+//! the other. Matched spans count as changed when their duration or their
+//! error status differ.
+
+use crate::core::{Span, TraceId};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+/// A span present in both traces whose duration or status differs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedSpan {
+    /// The span in the first trace
+    pub before: Span,
+    /// The matching span in the second trace
+    pub after: Span,
+}
+
+impl ChangedSpan {
+    /// Whether the span took a different time.
+    pub fn duration_changed(&self) -> bool {
+        self.before.duration != self.after.duration
+    }
+
+    /// Whether the span failed in one trace only.
+    pub fn status_changed(&self) -> bool {
+        self.before.is_error() != self.after.is_error()
+    }
+}
+
+/// Differences between the spans of two traces.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceDiff {
+    /// First trace, the baseline
+    pub trace_a: TraceId,
+    /// Second trace, compared against the first
+    pub trace_b: TraceId,
+    /// Spans only in the second trace
+    pub added: Vec<Span>,
+    /// Spans only in the first trace
+    pub removed: Vec<Span>,
+    /// Spans in both traces with a different duration or status
+    pub changed: Vec<ChangedSpan>,
+    /// Number of spans identical in both traces
+    pub unchanged: usize,
+}
+
+impl TraceDiff {
+    /// Compare the spans of trace `a` with those of trace `b`.
+    pub fn between(mut a: Vec<Span>, mut b: Vec<Span>) -> Self {
+        let trace_a = a
+            .first()
+            .map(|span| span.trace_id.clone())
+            .unwrap_or_default();
+        let trace_b = b
+            .first()
+            .map(|span| span.trace_id.clone())
+            .unwrap_or_default();
+        a.sort_by_key(|span| span.start_time);
+        b.sort_by_key(|span| span.start_time);
+
+        let mut unmatched: HashMap<(String, String), VecDeque<Span>> = HashMap::new();
+        for span in b {
+            unmatched
+                .entry(match_key(&span))
+                .or_default()
+                .push_back(span);
+        }
+
+        let mut diff = Self {
+            trace_a,
+            trace_b,
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            unchanged: 0,
+        };
+        for before in a {
+            match unmatched
+                .get_mut(&match_key(&before))
+                .and_then(VecDeque::pop_front)
+            {
+                Some(after) => {
+                    let change = ChangedSpan { before, after };
+                    if change.duration_changed() || change.status_changed() {
+                        diff.changed.push(change);
+                    } else {
+                        diff.unchanged += 1;
+                    }
+                },
+                None => diff.removed.push(before),
+            }
+        }
+        diff.added = unmatched.into_values().flatten().collect();
+        diff.added.sort_by_key(|span| span.start_time);
+        diff
+    }
+
+    /// Whether the traces ran the same spans with the same outcome.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Unified diff style listing: `-` for removed spans, `+` for added ones
+    /// and `~` for changed ones. With `color`, removed spans, slowdowns and
+    /// new errors are red, added spans, speedups and fixed errors green.
+    pub fn to_text(&self, color: bool) -> String {
+        let paint = |text: String, good: bool| match (color, good) {
+            (false, _) => text,
+            (true, true) => text.green().to_string(),
+            (true, false) => text.red().to_string(),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(out, "--- {}", self.trace_a.as_str());
+        let _ = writeln!(out, "+++ {}", self.trace_b.as_str());
+        for span in &self.removed {
+            out.push_str(&paint(format!("- {}", describe(span)), false));
+            out.push('\n');
+        }
+        for span in &self.added {
+            out.push_str(&paint(format!("+ {}", describe(span)), true));
+            out.push('\n');
+        }
+        for change in &self.changed {
+            let (before, after) = (&change.before, &change.after);
+            let _ = write!(out, "~ {} {}", before.service_name.as_str(), before.operation_name);
+            if change.duration_changed() {
+                let percent = if before.duration.is_zero() {
+                    String::new()
+                } else {
+                    let ratio = after.duration.as_secs_f64() / before.duration.as_secs_f64();
+                    format!(" ({:+.1}%)", (ratio - 1.0) * 100.0)
+                };
+                let text = format!(" {} -> {}{}", millis(before), millis(after), percent);
+                out.push_str(&paint(text, after.duration < before.duration));
+            } else {
+                let _ = write!(out, " {}", millis(after));
+            }
+            if change.status_changed() {
+                let text = format!(" {} -> {}", status(before), status(after));
+                out.push_str(&paint(text, !after.is_error()));
+            } else {
+                let _ = write!(out, " {}", status(after));
+            }
+            out.push('\n');
+        }
+        let _ = writeln!(
+            out,
+            "{} removed, {} added, {} changed, {} unchanged",
+            self.removed.len(),
+            self.added.len(),
+            self.changed.len(),
+            self.unchanged
+        );
+        out
+    }
+}
+
+fn match_key(span: &Span) -> (String, String) {
+    (span.service_name.as_str().to_string(), span.operation_name.clone())
+}
+
+fn millis(span: &Span) -> String {
+    format!("{:.2}ms", span.duration.as_secs_f64() * 1000.0)
+}
+
+fn status(span: &Span) -> &'static str {
+    if span.is_error() {
+        "ERROR"
+    } else {
+        "OK"
+    }
+}
+
+fn describe(span: &Span) -> String {
+    format!(
+        "{} {} {} {}",
+        span.service_name.as_str(),
+        span.operation_name,
+        millis(span),
+        status(span)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanStatus};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn span(trace: u8, id: u8, service: &str, operation: &str, millis: u64, error: bool) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name(operation)
+            .start_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000 + u64::from(id)))
+            .duration(Duration::from_millis(millis))
+            .status(if error {
+                SpanStatus::Error("boom".to_string())
+            } else {
+                SpanStatus::Ok
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_spans_are_matched_by_service_and_operation() {
+        let before = vec![
+            span(1, 1, "checkout", "POST /pay", 100, false),
+            span(1, 2, "payments", "charge", 40, false),
+            span(1, 3, "db", "SELECT", 5, false),
+            span(1, 4, "db", "SELECT", 5, false),
+            span(1, 5, "cache", "GET", 1, false),
+        ];
+        let after = vec![
+            span(2, 1, "checkout", "POST /pay", 100, false),
+            span(2, 2, "payments", "charge", 90, true),
+            span(2, 3, "db", "SELECT", 5, false),
+            span(2, 4, "db", "SELECT", 25, false),
+            span(2, 5, "fraud", "score", 30, false),
+        ];
+        let diff = TraceDiff::between(before, after);
+
+        assert_eq!(diff.trace_a.as_str(), format!("{:032x}", 1));
+        assert_eq!(diff.trace_b.as_str(), format!("{:032x}", 2));
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].service_name.as_str(), "cache");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].service_name.as_str(), "fraud");
+
+        // The second SELECT is compared with the second SELECT
+        assert_eq!(diff.changed.len(), 2);
+        let charge = &diff.changed[0];
+        assert!(charge.duration_changed() && charge.status_changed());
+        let select = &diff.changed[1];
+        assert_eq!(select.before.span_id.as_str(), format!("{:016x}", 4));
+        assert!(select.duration_changed() && !select.status_changed());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_text_diff() {
+        let before =
+            vec![span(1, 1, "payments", "charge", 40, true), span(1, 2, "cache", "GET", 1, false)];
+        let after = vec![
+            span(2, 1, "payments", "charge", 20, false),
+            span(2, 2, "fraud", "score", 30, false),
+        ];
+        let text = TraceDiff::between(before, after).to_text(false);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                format!("--- {:032x}", 1).as_str(),
+                format!("+++ {:032x}", 2).as_str(),
+                "- cache GET 1.00ms OK",
+                "+ fraud score 30.00ms OK",
+                "~ payments charge 40.00ms -> 20.00ms (-50.0%) ERROR -> OK",
+                "1 removed, 1 added, 1 changed, 0 unchanged",
+            ]
+        );
+
+        let same = vec![span(1, 1, "payments", "charge", 40, false)];
+        assert!(TraceDiff::between(same.clone(), same).is_empty());
+    }
+}
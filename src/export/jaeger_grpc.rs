@@ -0,0 +1,453 @@
+//! Live span export to Jaeger's gRPC remote storage API.
+//!
+//! Spans are converted to the `jaeger.api_v2` model and pushed one at a time
+//! through `jaeger.storage.v1.SpanWriterPlugin/WriteSpan`, the API served by
+//! `jaeger-remote-storage` (default port 17271) and by remote storage plugins.
+//! The tonic channel is created lazily and shared by all clones of the
+//! exporter, so concurrent writes reuse the same HTTP/2 connection.
+
+use crate::core::{Result, Span, SpanEvent, SpanKind, SpanStatus, UrpoError};
+use std::time::UNIX_EPOCH;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+
+/// Fully qualified `WriteSpan` method path.
+pub const WRITE_SPAN_PATH: &str = "/jaeger.storage.v1.SpanWriterPlugin/WriteSpan";
+
+/// Jaeger protobuf messages (`jaeger.api_v2` model and `jaeger.storage.v1`).
+pub mod proto {
+    /// Tag value type.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ValueType {
+        /// `v_str` is set
+        String = 0,
+        /// `v_bool` is set
+        Bool = 1,
+        /// `v_int64` is set
+        Int64 = 2,
+        /// `v_float64` is set
+        Float64 = 3,
+        /// `v_binary` is set
+        Binary = 4,
+    }
+
+    /// Span reference type.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum SpanRefType {
+        /// Parent/child relationship
+        ChildOf = 0,
+        /// Causal relationship without a parent
+        FollowsFrom = 1,
+    }
+
+    /// Typed tag.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct KeyValue {
+        /// Tag key.
+        #[prost(string, tag = "1")]
+        pub key: String,
+        /// Which value field is set.
+        #[prost(enumeration = "ValueType", tag = "2")]
+        pub v_type: i32,
+        /// String value.
+        #[prost(string, tag = "3")]
+        pub v_str: String,
+        /// Boolean value.
+        #[prost(bool, tag = "4")]
+        pub v_bool: bool,
+        /// Integer value.
+        #[prost(int64, tag = "5")]
+        pub v_int64: i64,
+        /// Float value.
+        #[prost(double, tag = "6")]
+        pub v_float64: f64,
+        /// Binary value.
+        #[prost(bytes = "vec", tag = "7")]
+        pub v_binary: Vec<u8>,
+    }
+
+    /// `google.protobuf.Timestamp`.
+    #[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+    pub struct Timestamp {
+        /// Seconds since the Unix epoch.
+        #[prost(int64, tag = "1")]
+        pub seconds: i64,
+        /// Sub-second nanoseconds.
+        #[prost(int32, tag = "2")]
+        pub nanos: i32,
+    }
+
+    /// `google.protobuf.Duration`.
+    #[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+    pub struct Duration {
+        /// Whole seconds.
+        #[prost(int64, tag = "1")]
+        pub seconds: i64,
+        /// Sub-second nanoseconds.
+        #[prost(int32, tag = "2")]
+        pub nanos: i32,
+    }
+
+    /// Timestamped span log.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Log {
+        /// Log time.
+        #[prost(message, optional, tag = "1")]
+        pub timestamp: Option<Timestamp>,
+        /// Log fields.
+        #[prost(message, repeated, tag = "2")]
+        pub fields: Vec<KeyValue>,
+    }
+
+    /// Reference to another span.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SpanRef {
+        /// 16-byte trace ID.
+        #[prost(bytes = "vec", tag = "1")]
+        pub trace_id: Vec<u8>,
+        /// 8-byte span ID.
+        #[prost(bytes = "vec", tag = "2")]
+        pub span_id: Vec<u8>,
+        /// Reference type.
+        #[prost(enumeration = "SpanRefType", tag = "3")]
+        pub ref_type: i32,
+    }
+
+    /// Emitting process.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Process {
+        /// Service name.
+        #[prost(string, tag = "1")]
+        pub service_name: String,
+        /// Process (resource) tags.
+        #[prost(message, repeated, tag = "2")]
+        pub tags: Vec<KeyValue>,
+    }
+
+    /// Jaeger span.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Span {
+        /// 16-byte trace ID.
+        #[prost(bytes = "vec", tag = "1")]
+        pub trace_id: Vec<u8>,
+        /// 8-byte span ID.
+        #[prost(bytes = "vec", tag = "2")]
+        pub span_id: Vec<u8>,
+        /// Operation name.
+        #[prost(string, tag = "3")]
+        pub operation_name: String,
+        /// Parent and other references.
+        #[prost(message, repeated, tag = "4")]
+        pub references: Vec<SpanRef>,
+        /// Trace flags.
+        #[prost(uint32, tag = "5")]
+        pub flags: u32,
+        /// Start time.
+        #[prost(message, optional, tag = "6")]
+        pub start_time: Option<Timestamp>,
+        /// Duration.
+        #[prost(message, optional, tag = "7")]
+        pub duration: Option<Duration>,
+        /// Span tags.
+        #[prost(message, repeated, tag = "8")]
+        pub tags: Vec<KeyValue>,
+        /// Span logs.
+        #[prost(message, repeated, tag = "9")]
+        pub logs: Vec<Log>,
+        /// Emitting process.
+        #[prost(message, optional, tag = "10")]
+        pub process: Option<Process>,
+        /// Process reference, unused when `process` is set.
+        #[prost(string, tag = "11")]
+        pub process_id: String,
+        /// Instrumentation warnings.
+        #[prost(string, repeated, tag = "12")]
+        pub warnings: Vec<String>,
+    }
+
+    /// `SpanWriterPlugin.WriteSpan` request.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WriteSpanRequest {
+        /// Span to write.
+        #[prost(message, optional, tag = "1")]
+        pub span: Option<Span>,
+    }
+
+    /// `SpanWriterPlugin.WriteSpan` response.
+    #[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+    pub struct WriteSpanResponse {}
+}
+
+/// Pushes spans to a Jaeger `SpanWriterPlugin` endpoint.
+///
+/// Cloning is cheap; clones share the underlying channel.
+#[derive(Debug, Clone)]
+pub struct JaegerGrpcExporter {
+    endpoint: String,
+    client: tonic::client::Grpc<Channel>,
+}
+
+impl JaegerGrpcExporter {
+    /// Create an exporter for `endpoint` (e.g. `http://jaeger:17271`).
+    ///
+    /// A missing scheme defaults to `http://`. The connection is opened on
+    /// the first write and re-established automatically after failures.
+    pub fn connect_lazy(endpoint: &str) -> Result<Self> {
+        let endpoint = if endpoint.contains("://") {
+            endpoint.to_string()
+        } else {
+            format!("http://{}", endpoint)
+        };
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .map_err(|e| UrpoError::config(format!("Invalid Jaeger endpoint {}: {}", endpoint, e)))?
+            .connect_lazy();
+
+        Ok(Self {
+            endpoint,
+            client: tonic::client::Grpc::new(channel),
+        })
+    }
+
+    /// Endpoint spans are written to.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Write a single span.
+    pub async fn write_span(&self, span: &Span) -> Result<()> {
+        let request = proto::WriteSpanRequest {
+            span: Some(to_jaeger_span(span)),
+        };
+
+        let mut client = self.client.clone();
+        client.ready().await.map_err(|e| {
+            UrpoError::network(format!("Jaeger endpoint {} unavailable: {}", self.endpoint, e))
+        })?;
+        client
+            .unary::<_, proto::WriteSpanResponse, _>(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(WRITE_SPAN_PATH),
+                ProstCodec::default(),
+            )
+            .await
+            .map_err(|status| {
+                UrpoError::network(format!(
+                    "Jaeger WriteSpan to {} failed: {}",
+                    self.endpoint,
+                    status.message()
+                ))
+            })?;
+        Ok(())
+    }
+}
+
+/// Convert a span to the `jaeger.api_v2` model.
+///
+/// Attributes and tags become string tags, the span kind becomes `span.kind`
+/// and error statuses set `error=true` plus `otel.status_description`.
+/// Resource attributes become process tags, span events become logs and
+/// span links become `FOLLOWS_FROM` references.
+pub fn to_jaeger_span(span: &Span) -> proto::Span {
+    let trace_id = decode_id(span.trace_id.as_str(), 16);
+
+    let references = span
+        .parent_span_id
+        .iter()
+        .map(|parent| proto::SpanRef {
+            trace_id: trace_id.clone(),
+            span_id: decode_id(parent.as_str(), 8),
+            ref_type: proto::SpanRefType::ChildOf as i32,
+        })
+        .chain(span.links.iter().map(|link| proto::SpanRef {
+            trace_id: decode_id(link.trace_id.as_str(), 16),
+            span_id: decode_id(link.span_id.as_str(), 8),
+            ref_type: proto::SpanRefType::FollowsFrom as i32,
+        }))
+        .collect();
+
+    let mut tags: Vec<proto::KeyValue> = span
+        .attributes
+        .iter()
+        .chain(span.tags.iter())
+        .map(|(key, value)| string_tag(key, value))
+        .collect();
+    if let Some(kind) = span_kind_tag(&span.kind) {
+        tags.push(string_tag("span.kind", kind));
+    }
+    match span.status {
+        SpanStatus::Error(ref message) => {
+            tags.push(proto::KeyValue {
+                key: "error".to_string(),
+                v_type: proto::ValueType::Bool as i32,
+                v_bool: true,
+                ..Default::default()
+            });
+            tags.push(string_tag("otel.status_code", "ERROR"));
+            if !message.is_empty() {
+                tags.push(string_tag("otel.status_description", message));
+            }
+        },
+        SpanStatus::Ok => tags.push(string_tag("otel.status_code", "OK")),
+        _ => {},
+    }
+
+    let start = span
+        .start_time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    proto::Span {
+        trace_id,
+        span_id: decode_id(span.span_id.as_str(), 8),
+        operation_name: span.operation_name.clone(),
+        references,
+        flags: 1, // sampled
+        start_time: Some(proto::Timestamp {
+            seconds: start.as_secs() as i64,
+            nanos: start.subsec_nanos() as i32,
+        }),
+        duration: Some(proto::Duration {
+            seconds: span.duration.as_secs() as i64,
+            nanos: span.duration.subsec_nanos() as i32,
+        }),
+        tags,
+        logs: span.events.iter().map(to_jaeger_log).collect(),
+        process: Some(proto::Process {
+            service_name: span.service_name.as_str().to_string(),
+            tags: span
+                .resource_attributes
+                .iter()
+                .map(|(key, value)| string_tag(key, value))
+                .collect(),
+        }),
+        process_id: String::new(),
+        warnings: Vec::new(),
+    }
+}
+
+/// Span events become logs with the event name in the `event` field.
+fn to_jaeger_log(event: &SpanEvent) -> proto::Log {
+    let time = event
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut fields = vec![string_tag("event", &event.name)];
+    fields.extend(
+        event
+            .attributes
+            .iter()
+            .map(|(key, value)| string_tag(key, value)),
+    );
+    proto::Log {
+        timestamp: Some(proto::Timestamp {
+            seconds: time.as_secs() as i64,
+            nanos: time.subsec_nanos() as i32,
+        }),
+        fields,
+    }
+}
+
+fn string_tag(key: &str, value: &str) -> proto::KeyValue {
+    proto::KeyValue {
+        key: key.to_string(),
+        v_type: proto::ValueType::String as i32,
+        v_str: value.to_string(),
+        ..Default::default()
+    }
+}
+
+fn span_kind_tag(kind: &SpanKind) -> Option<&'static str> {
+    match kind {
+        SpanKind::Internal => None,
+        SpanKind::Client => Some("client"),
+        SpanKind::Server => Some("server"),
+        SpanKind::Producer => Some("producer"),
+        SpanKind::Consumer => Some("consumer"),
+    }
+}
+
+/// Decode a hex ID into `len` big-endian bytes, left-padding short IDs.
+pub(crate) fn decode_id(hex_id: &str, len: usize) -> Vec<u8> {
+    let mut bytes = hex::decode(hex_id).unwrap_or_default();
+    if bytes.len() < len {
+        let mut padded = vec![0; len - bytes.len()];
+        padded.append(&mut bytes);
+        bytes = padded;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanLink, TraceId};
+    use std::time::Duration;
+
+    #[test]
+    fn test_to_jaeger_span() {
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 0xabcu64)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 2)).unwrap())
+            .parent_span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("checkout".to_string()).unwrap())
+            .operation_name("charge")
+            .start_time(UNIX_EPOCH + Duration::new(1_700_000_000, 5))
+            .duration(Duration::from_micros(1_500))
+            .kind(SpanKind::Client)
+            .status(SpanStatus::Error("declined".to_string()))
+            .attribute("http.method", "POST")
+            .event(
+                SpanEvent::new("exception", UNIX_EPOCH + Duration::from_secs(1_700_000_001))
+                    .with_attribute("exception.type", "CardDeclined"),
+            )
+            .link(SpanLink::new(
+                TraceId::new(format!("{:032x}", 0xdefu64)).unwrap(),
+                SpanId::new(format!("{:016x}", 9)).unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        let jaeger = to_jaeger_span(&span);
+        assert_eq!(jaeger.trace_id.len(), 16);
+        assert_eq!(jaeger.trace_id[14..], [0x0a, 0xbc]);
+        assert_eq!(jaeger.span_id, vec![0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(jaeger.references.len(), 2);
+        assert_eq!(jaeger.references[0].span_id, vec![0, 0, 0, 0, 0, 0, 0, 1]);
+        let link = &jaeger.references[1];
+        assert_eq!(link.ref_type, proto::SpanRefType::FollowsFrom as i32);
+        assert_eq!(link.trace_id[14..], [0x0d, 0xef]);
+        assert_eq!(link.span_id, vec![0, 0, 0, 0, 0, 0, 0, 9]);
+        assert_eq!(
+            jaeger.start_time,
+            Some(proto::Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 5
+            })
+        );
+        assert_eq!(
+            jaeger.duration,
+            Some(proto::Duration {
+                seconds: 0,
+                nanos: 1_500_000
+            })
+        );
+
+        let tag = |key: &str| jaeger.tags.iter().find(|t| t.key == key).cloned();
+        assert_eq!(tag("http.method").unwrap().v_str, "POST");
+        assert_eq!(tag("span.kind").unwrap().v_str, "client");
+        assert!(tag("error").unwrap().v_bool);
+        assert_eq!(tag("otel.status_description").unwrap().v_str, "declined");
+        assert_eq!(jaeger.process.unwrap().service_name, "checkout");
+
+        assert_eq!(jaeger.logs.len(), 1);
+        let log = &jaeger.logs[0];
+        assert_eq!(log.timestamp.unwrap().seconds, 1_700_000_001);
+        assert_eq!(log.fields[0].key, "event");
+        assert_eq!(log.fields[0].v_str, "exception");
+        assert_eq!(log.fields[1].key, "exception.type");
+        assert_eq!(log.fields[1].v_str, "CardDeclined");
+    }
+}
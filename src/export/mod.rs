@@ -1,26 +1,62 @@
 //! Export functionality for traces.
 //!
-//! Supports multiple export formats including JSON, CSV, and compatibility
-//! formats for other tracing systems.
+//! Supports multiple export formats including JSON, CSV, Chrome trace events
+//! and compatibility formats for other tracing systems. Parquet (behind the
+//! `parquet` feature) is binary and can only be written to a file.
 
-use crate::core::{Result, Span, TraceId, UrpoError};
+use crate::core::{Result, Span, SpanEvent, SpanTree, TraceId, UrpoError};
 use crate::storage::{StorageBackend, TraceInfo};
+use redaction::RedactionPolicy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod chrome;
+pub mod diff;
+pub mod jaeger_grpc;
+pub mod otlp_forwarder;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod redaction;
+#[cfg(feature = "remote-write")]
+pub mod remote_write;
+
+/// Columns of CSV exports.
+const CSV_HEADER: [&str; 9] = [
+    "trace_id",
+    "span_id",
+    "parent_span_id",
+    "service",
+    "operation",
+    "start_time",
+    "duration_us",
+    "status",
+    "attributes",
+];
+
+fn csv_error(e: impl std::fmt::Display) -> UrpoError {
+    UrpoError::SerializationError(format!("CSV: {}", e))
+}
 
 /// Export format options.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExportFormat {
     /// Native Urpo JSON format
     Json,
+    /// Newline-delimited JSON, one trace per line
+    Ndjson,
     /// Jaeger-compatible JSON format
     Jaeger,
     /// OpenTelemetry JSON format
     OpenTelemetry,
     /// CSV format for spreadsheet analysis
     Csv,
+    /// Chrome trace events for chrome://tracing and Perfetto
+    ChromeTrace,
+    /// Columnar Parquet file for DuckDB/pandas
+    Parquet,
 }
 
 impl std::str::FromStr for ExportFormat {
@@ -29,14 +65,37 @@ impl std::str::FromStr for ExportFormat {
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "json" => Ok(ExportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
             "jaeger" => Ok(ExportFormat::Jaeger),
             "otel" | "opentelemetry" => Ok(ExportFormat::OpenTelemetry),
             "csv" => Ok(ExportFormat::Csv),
+            "chrome" | "chrome-trace" | "perfetto" => Ok(ExportFormat::ChromeTrace),
+            "parquet" => Ok(ExportFormat::Parquet),
             _ => Err(format!("Unknown export format: {}", s)),
         }
     }
 }
 
+impl ExportFormat {
+    /// Canonical lowercase name of the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Jaeger => "jaeger",
+            ExportFormat::OpenTelemetry => "otel",
+            ExportFormat::Csv => "csv",
+            ExportFormat::ChromeTrace => "chrome",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    /// Whether the format is binary and must be written to a file.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, ExportFormat::Parquet)
+    }
+}
+
 /// Export options for trace export.
 #[derive(Debug, Clone)]
 pub struct ExportOptions {
@@ -54,6 +113,12 @@ pub struct ExportOptions {
     pub limit: Option<usize>,
     /// Only export traces with errors
     pub errors_only: bool,
+    /// Wrap JSON/NDJSON output with export metadata (disable for raw output)
+    pub include_metadata: bool,
+    /// Only export the spans on each trace's critical path
+    pub critical_path_only: bool,
+    /// Attributes redacted from every exported span
+    pub attribute_redaction: Option<RedactionPolicy>,
 }
 
 impl Default for ExportOptions {
@@ -66,6 +131,70 @@ impl Default for ExportOptions {
             end_time: None,
             limit: None,
             errors_only: false,
+            include_metadata: true,
+            critical_path_only: false,
+            attribute_redaction: None,
+        }
+    }
+}
+
+/// Provenance information attached to JSON/NDJSON exports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMetadata {
+    /// When the export was produced (RFC 3339)
+    pub exported_at: String,
+    /// Urpo version that produced the export
+    pub urpo_version: String,
+    /// Export format name
+    pub format: String,
+    /// Filters applied to select the exported traces
+    pub filters: ExportFilters,
+    /// Number of traces in the export
+    pub trace_count: usize,
+}
+
+/// Filters recorded in export metadata.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportFilters {
+    /// Single trace ID, if exporting one trace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Service filter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Time range start (unix nanoseconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    /// Time range end (unix nanoseconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    /// Maximum number of traces requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Only traces with errors
+    pub errors_only: bool,
+    /// Only the critical-path spans of each trace
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub critical_path_only: bool,
+}
+
+impl ExportMetadata {
+    /// Build metadata for an export produced with the given options.
+    pub fn new(options: &ExportOptions, trace_id: Option<&TraceId>, trace_count: usize) -> Self {
+        Self {
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            urpo_version: env!("CARGO_PKG_VERSION").to_string(),
+            format: options.format.name().to_string(),
+            filters: ExportFilters {
+                trace_id: trace_id.map(|id| id.as_str().to_string()),
+                service: options.service.clone(),
+                start_time: options.start_time,
+                end_time: options.end_time,
+                limit: options.limit,
+                errors_only: options.errors_only,
+                critical_path_only: options.critical_path_only,
+            },
+            trace_count,
         }
     }
 }
@@ -92,9 +221,12 @@ impl<'a> TraceExporter<'a> {
 
         match format {
             ExportFormat::Json => self.export_json(&spans),
+            ExportFormat::Ndjson => self.export_ndjson(&spans),
             ExportFormat::Jaeger => self.export_jaeger(&spans),
             ExportFormat::OpenTelemetry => self.export_otel(&spans),
             ExportFormat::Csv => self.export_csv(&spans),
+            ExportFormat::ChromeTrace => self.export_chrome(&spans),
+            ExportFormat::Parquet => Err(binary_format_error(format)),
         }
     }
 
@@ -108,18 +240,133 @@ impl<'a> TraceExporter<'a> {
         if spans.is_empty() {
             return Err(UrpoError::TraceNotFound(format!("Trace {}", trace_id.as_str())));
         }
+        let prepared;
+        let spans = if options.critical_path_only || options.attribute_redaction.is_some() {
+            prepared = prepare_spans(spans.to_vec(), options);
+            &prepared[..]
+        } else {
+            spans
+        };
+
+        if options.include_metadata {
+            let metadata = ExportMetadata::new(options, Some(trace_id), 1);
+            match options.format {
+                ExportFormat::Json => {
+                    return Self::serialize_json(&serde_json::json!({
+                        "metadata": metadata,
+                        "spans": spans,
+                    }));
+                },
+                ExportFormat::Ndjson => {
+                    let mut output =
+                        Self::ndjson_line(&serde_json::json!({ "metadata": metadata }))?;
+                    output.push_str(&self.export_ndjson(spans)?);
+                    return Ok(output);
+                },
+                _ => {},
+            }
+        }
 
         match options.format {
             ExportFormat::Json => self.export_json(spans),
+            ExportFormat::Ndjson => self.export_ndjson(spans),
             ExportFormat::Jaeger => self.export_jaeger(spans),
             ExportFormat::OpenTelemetry => self.export_otel(spans),
             ExportFormat::Csv => self.export_csv(spans),
+            ExportFormat::ChromeTrace => self.export_chrome(spans),
+            ExportFormat::Parquet => Err(binary_format_error(options.format)),
         }
     }
 
     /// Export multiple traces based on options.
+    ///
+    /// The whole export is built in memory; use
+    /// [`TraceExporter::write_traces`] to stream large exports.
     pub async fn export_traces(&self, options: &ExportOptions) -> Result<String> {
-        // Query traces based on filters
+        let mut output = Vec::new();
+        self.write_traces(options, &mut output).await?;
+        String::from_utf8(output).map_err(|e| UrpoError::SerializationError(e.to_string()))
+    }
+
+    /// Export multiple traces based on options into `out`, returning the
+    /// number of traces written.
+    ///
+    /// Traces are fetched and written one at a time, so memory use does not
+    /// grow with the number of traces. Chrome trace exports are the
+    /// exception: laying out threads needs every span at once.
+    pub async fn write_traces<W: Write + Send>(
+        &self,
+        options: &ExportOptions,
+        mut out: W,
+    ) -> Result<usize> {
+        if options.format.is_binary() {
+            return Err(binary_format_error(options.format));
+        }
+
+        let traces = self.select_traces(options).await?;
+        match options.format {
+            ExportFormat::Json if options.include_metadata => {
+                let metadata = ExportMetadata::new(options, None, traces.len());
+                out.write_all(b"{\n  \"metadata\": ")?;
+                serde_json::to_writer_pretty(Indented::new(&mut out, 1), &metadata)
+                    .map_err(serialization_error)?;
+                out.write_all(b",\n  \"traces\": ")?;
+                let mut array = JsonArrayWriter::new(&mut out, 1)?;
+                for trace in &traces {
+                    array.element(&self.trace_value(trace, options).await?)?;
+                }
+                array.finish()?;
+                out.write_all(b"\n}")?;
+            },
+            ExportFormat::Json => {
+                let mut array = JsonArrayWriter::new(&mut out, 0)?;
+                for trace in &traces {
+                    array.element(&self.trace_value(trace, options).await?)?;
+                }
+                array.finish()?;
+            },
+            ExportFormat::Ndjson => {
+                if options.include_metadata {
+                    let metadata = ExportMetadata::new(options, None, traces.len());
+                    write_ndjson_line(&mut out, &serde_json::json!({ "metadata": metadata }))?;
+                }
+                for trace in &traces {
+                    write_ndjson_line(&mut out, &self.trace_value(trace, options).await?)?;
+                }
+            },
+            ExportFormat::Jaeger => {
+                let mut array = JsonArrayWriter::new(&mut out, 0)?;
+                for trace in &traces {
+                    let spans = self.trace_spans(&trace.trace_id, options).await?;
+                    array.element(&convert_to_jaeger_format(&spans))?;
+                }
+                array.finish()?;
+            },
+            ExportFormat::OpenTelemetry => {
+                let mut array = JsonArrayWriter::new(&mut out, 0)?;
+                for trace in &traces {
+                    let spans = self.trace_spans(&trace.trace_id, options).await?;
+                    array.element(&convert_to_otel_format(&spans))?;
+                }
+                array.finish()?;
+            },
+            ExportFormat::Csv => self.write_traces_csv(&traces, options, &mut out).await?,
+            ExportFormat::ChromeTrace => {
+                let mut spans = Vec::new();
+                for trace in &traces {
+                    spans.extend(self.trace_spans(&trace.trace_id, options).await?);
+                }
+                serde_json::to_writer_pretty(&mut out, &chrome::convert_to_chrome_trace(&spans))
+                    .map_err(serialization_error)?;
+            },
+            ExportFormat::Parquet => return Err(binary_format_error(options.format)),
+        }
+        out.flush()?;
+        Ok(traces.len())
+    }
+
+    /// Traces matching the filters of `options`.
+    async fn select_traces(&self, options: &ExportOptions) -> Result<Vec<TraceInfo>> {
         let traces = self
             .storage
             .list_traces(
@@ -130,23 +377,39 @@ impl<'a> TraceExporter<'a> {
             )
             .await?;
 
-        // Filter by error status if requested
-        let filtered_traces: Vec<TraceInfo> = if options.errors_only {
+        Ok(if options.errors_only {
             traces.into_iter().filter(|t| t.has_error).collect()
         } else {
             traces
-        };
+        })
+    }
 
-        if filtered_traces.is_empty() {
-            return Ok("[]".to_string());
+    /// Export the traces selected by `options` as Parquet into `path`.
+    pub async fn export_traces_parquet(&self, options: &ExportOptions, path: &Path) -> Result<()> {
+        let traces = self.select_traces(options).await?;
+
+        let mut spans = Vec::new();
+        for trace in &traces {
+            spans.extend(self.trace_spans(&trace.trace_id, options).await?);
         }
+        self.write_parquet(&spans, path)
+    }
 
-        // Export based on format
-        match options.format {
-            ExportFormat::Json => self.export_traces_json(&filtered_traces).await,
-            ExportFormat::Jaeger => self.export_traces_jaeger(&filtered_traces).await,
-            ExportFormat::OpenTelemetry => self.export_traces_otel(&filtered_traces).await,
-            ExportFormat::Csv => self.export_traces_csv(&filtered_traces).await,
+    /// Write `spans` as a Parquet file at `path`.
+    pub fn write_parquet(&self, spans: &[Span], path: &Path) -> Result<()> {
+        #[cfg(feature = "parquet")]
+        {
+            let file = std::fs::File::create(path)
+                .map_err(|e| UrpoError::Storage(format!("Failed to create file: {}", e)))?;
+            parquet::write_spans(spans, std::io::BufWriter::new(file))
+        }
+
+        #[cfg(not(feature = "parquet"))]
+        {
+            let _ = (spans, path);
+            Err(UrpoError::config(
+                "Parquet export requires urpo to be built with the `parquet` feature",
+            ))
         }
     }
 
@@ -160,6 +423,23 @@ impl<'a> TraceExporter<'a> {
         Self::serialize_json(spans)
     }
 
+    /// Serialize a value as a single NDJSON line.
+    fn ndjson_line<T: serde::Serialize + ?Sized>(data: &T) -> Result<String> {
+        let mut line = serde_json::to_string(data)
+            .map_err(|e| UrpoError::SerializationError(e.to_string()))?;
+        line.push('\n');
+        Ok(line)
+    }
+
+    /// Export spans as NDJSON, one span per line.
+    fn export_ndjson(&self, spans: &[Span]) -> Result<String> {
+        let mut output = String::new();
+        for span in spans {
+            output.push_str(&Self::ndjson_line(span)?);
+        }
+        Ok(output)
+    }
+
     /// Export spans as Jaeger-compatible JSON.
     fn export_jaeger(&self, spans: &[Span]) -> Result<String> {
         let jaeger_trace = convert_to_jaeger_format(spans);
@@ -172,108 +452,122 @@ impl<'a> TraceExporter<'a> {
         Self::serialize_json(&otel_trace)
     }
 
+    /// Export spans as a JSON array of Chrome trace events.
+    fn export_chrome(&self, spans: &[Span]) -> Result<String> {
+        Self::serialize_json(&chrome::convert_to_chrome_trace(spans))
+    }
+
     /// Export spans as CSV.
     fn export_csv(&self, spans: &[Span]) -> Result<String> {
-        let mut csv_output = String::new();
-
-        // Header
-        csv_output.push_str("trace_id,span_id,parent_span_id,service,operation,start_time,duration_us,status,attributes\n");
-
-        // Data rows
+        let mut writer = Self::csv_writer(Vec::new())?;
         for span in spans {
-            Self::append_csv_row(&mut csv_output, span);
-        }
-
-        Ok(csv_output)
-    }
-
-    /// Helper function to format a single CSV row.
-    fn append_csv_row(csv_output: &mut String, span: &Span) {
-        csv_output.push_str(&format!(
-            "{},{},{},{},{},{},{},{},\"{}\"\n",
-            span.trace_id.as_str(),
-            span.span_id.as_str(),
-            span.parent_span_id
-                .as_ref()
-                .map(|p| p.as_str())
-                .unwrap_or(""),
-            span.service_name.as_str(),
-            span.operation_name,
-            span.start_time
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_nanos(),
-            span.duration.as_nanos(),
-            if span.status.is_error() {
-                "ERROR"
-            } else {
-                "OK"
-            },
-            serde_json::to_string(&span.attributes).unwrap_or_default(),
-        ));
-    }
-
-    /// Export multiple traces as JSON.
-    async fn export_traces_json(&self, traces: &[TraceInfo]) -> Result<String> {
-        let mut all_traces = Vec::new();
-
-        for trace_info in traces {
-            let spans = self.storage.get_trace_spans(&trace_info.trace_id).await?;
-            all_traces.push(serde_json::json!({
-                "trace_id": trace_info.trace_id.as_str(),
-                "root_service": trace_info.root_service,
-                "root_operation": trace_info.root_operation,
-                "start_time": trace_info.start_time,
-                "duration": trace_info.duration,
-                "span_count": trace_info.span_count,
-                "has_error": trace_info.has_error,
-                "spans": spans,
-            }));
+            Self::write_csv_row(&mut writer, span)?;
         }
-
-        Self::serialize_json(&all_traces)
+        Self::finish_csv(writer)
     }
 
-    /// Export multiple traces as Jaeger format.
-    async fn export_traces_jaeger(&self, traces: &[TraceInfo]) -> Result<String> {
-        let mut jaeger_traces = Vec::new();
-
-        for trace_info in traces {
-            let spans = self.storage.get_trace_spans(&trace_info.trace_id).await?;
-            jaeger_traces.push(convert_to_jaeger_format(&spans));
-        }
+    /// CSV writer with the header row written. Fields are quoted as
+    /// RFC 4180 requires.
+    fn csv_writer<W: Write>(out: W) -> Result<csv::Writer<W>> {
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(CSV_HEADER).map_err(csv_error)?;
+        Ok(writer)
+    }
 
-        Self::serialize_json(&jaeger_traces)
+    /// Write a single CSV row.
+    fn write_csv_row<W: Write>(writer: &mut csv::Writer<W>, span: &Span) -> Result<()> {
+        let attributes = serde_json::to_string(&span.attributes)
+            .map_err(|e| UrpoError::SerializationError(e.to_string()))?;
+        writer
+            .write_record([
+                span.trace_id.as_str(),
+                span.span_id.as_str(),
+                span.parent_span_id
+                    .as_ref()
+                    .map(|p| p.as_str())
+                    .unwrap_or(""),
+                span.service_name.as_str(),
+                &span.operation_name,
+                &unix_nanos(span.start_time),
+                &span.duration.as_nanos().to_string(),
+                if span.status.is_error() {
+                    "ERROR"
+                } else {
+                    "OK"
+                },
+                &attributes,
+            ])
+            .map_err(csv_error)
     }
 
-    /// Export multiple traces as OpenTelemetry format.
-    async fn export_traces_otel(&self, traces: &[TraceInfo]) -> Result<String> {
-        let mut otel_traces = Vec::new();
+    /// Output of a CSV writer.
+    fn finish_csv(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+        let bytes = writer.into_inner().map_err(csv_error)?;
+        String::from_utf8(bytes).map_err(csv_error)
+    }
 
-        for trace_info in traces {
-            let spans = self.storage.get_trace_spans(&trace_info.trace_id).await?;
-            otel_traces.push(convert_to_otel_format(&spans));
+    /// Compare the spans of two traces, each prepared as `options` ask.
+    pub async fn diff_traces(
+        &self,
+        trace_a: &TraceId,
+        trace_b: &TraceId,
+        options: &ExportOptions,
+    ) -> Result<diff::TraceDiff> {
+        let mut traces = Vec::with_capacity(2);
+        for trace_id in [trace_a, trace_b] {
+            let spans = self.trace_spans(trace_id, options).await?;
+            if spans.is_empty() {
+                return Err(UrpoError::TraceNotFound(format!("Trace {}", trace_id.as_str())));
+            }
+            traces.push(spans);
         }
+        let b = traces.pop().unwrap_or_default();
+        let a = traces.pop().unwrap_or_default();
+        Ok(diff::TraceDiff::between(a, b))
+    }
 
-        serde_json::to_string_pretty(&otel_traces)
-            .map_err(|e| UrpoError::SerializationError(e.to_string()))
+    /// Spans of a trace as exported with `options`.
+    async fn trace_spans(&self, trace_id: &TraceId, options: &ExportOptions) -> Result<Vec<Span>> {
+        let spans = self.storage.get_trace_spans(trace_id).await?;
+        Ok(prepare_spans(spans, options))
     }
 
-    /// Export multiple traces as CSV.
-    async fn export_traces_csv(&self, traces: &[TraceInfo]) -> Result<String> {
-        let mut csv_output = String::new();
+    /// Native JSON representation of a trace.
+    async fn trace_value(
+        &self,
+        trace_info: &TraceInfo,
+        options: &ExportOptions,
+    ) -> Result<serde_json::Value> {
+        let spans = self.trace_spans(&trace_info.trace_id, options).await?;
+        Ok(serde_json::json!({
+            "trace_id": trace_info.trace_id.as_str(),
+            "root_service": trace_info.root_service,
+            "root_operation": trace_info.root_operation,
+            "start_time": trace_info.start_time,
+            "duration": trace_info.duration,
+            "span_count": trace_info.span_count,
+            "has_error": trace_info.has_error,
+            "spans": spans,
+        }))
+    }
 
-        // Header
-        csv_output.push_str("trace_id,span_id,parent_span_id,service,operation,start_time,duration_us,status,attributes\n");
+    /// Write multiple traces as CSV, row by row.
+    async fn write_traces_csv<W: Write + Send>(
+        &self,
+        traces: &[TraceInfo],
+        options: &ExportOptions,
+        out: W,
+    ) -> Result<()> {
+        let mut writer = Self::csv_writer(out)?;
 
         for trace_info in traces {
-            let spans = self.storage.get_trace_spans(&trace_info.trace_id).await?;
-            for span in spans {
-                Self::append_csv_row(&mut csv_output, &span);
+            for span in self.trace_spans(&trace_info.trace_id, options).await? {
+                Self::write_csv_row(&mut writer, &span)?;
             }
         }
 
-        Ok(csv_output)
+        writer.flush()?;
+        Ok(())
     }
 
     /// Write export to file or stdout.
@@ -294,6 +588,125 @@ impl<'a> TraceExporter<'a> {
     }
 }
 
+/// Cut the spans of one trace down to its critical path and redact them, as
+/// `options` ask.
+pub fn prepare_spans(spans: Vec<Span>, options: &ExportOptions) -> Vec<Span> {
+    let mut spans = if options.critical_path_only {
+        critical_path_spans(spans)
+    } else {
+        spans
+    };
+    if let Some(ref policy) = options.attribute_redaction {
+        for span in &mut spans {
+            policy.redact_span(span);
+        }
+    }
+    spans
+}
+
+/// Keep the spans of one trace that lie on its critical path, earliest first.
+pub fn critical_path_spans(spans: Vec<Span>) -> Vec<Span> {
+    SpanTree::build(spans)
+        .critical_path()
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+/// Error for binary formats requested as text output.
+fn binary_format_error(format: ExportFormat) -> UrpoError {
+    UrpoError::config(format!(
+        "{} export is binary and must be written to an output file",
+        format.name()
+    ))
+}
+
+fn serialization_error(e: serde_json::Error) -> UrpoError {
+    UrpoError::SerializationError(e.to_string())
+}
+
+/// Write `data` as a single NDJSON line.
+fn write_ndjson_line<W: Write, T: Serialize + ?Sized>(mut out: W, data: &T) -> Result<()> {
+    serde_json::to_writer(&mut out, data).map_err(serialization_error)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Writer that indents every line after the first by `levels` levels of
+/// pretty-printed JSON, so a value can be nested in hand-written output.
+struct Indented<W: Write> {
+    out: W,
+    levels: usize,
+}
+
+impl<W: Write> Indented<W> {
+    fn new(out: W, levels: usize) -> Self {
+        Self { out, levels }
+    }
+}
+
+impl<W: Write> Write for Indented<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // serde_json escapes newlines inside strings, so every one is a line break
+        for line in buf.split_inclusive(|&byte| byte == b'\n') {
+            self.out.write_all(line)?;
+            if line.ends_with(b"\n") {
+                write_indent(&mut self.out, self.levels)?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn write_indent<W: Write>(mut out: W, levels: usize) -> std::io::Result<()> {
+    for _ in 0..levels {
+        out.write_all(b"  ")?;
+    }
+    Ok(())
+}
+
+/// Pretty-printed JSON array written one element at a time, laid out as
+/// `serde_json::to_writer_pretty` lays out a whole array.
+struct JsonArrayWriter<W: Write> {
+    out: W,
+    /// Nesting level of the array itself
+    level: usize,
+    empty: bool,
+}
+
+impl<W: Write> JsonArrayWriter<W> {
+    fn new(mut out: W, level: usize) -> Result<Self> {
+        out.write_all(b"[")?;
+        Ok(Self {
+            out,
+            level,
+            empty: true,
+        })
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.out
+            .write_all(if self.empty { &b"\n"[..] } else { &b",\n"[..] })?;
+        self.empty = false;
+        write_indent(&mut self.out, self.level + 1)?;
+        serde_json::to_writer_pretty(Indented::new(&mut self.out, self.level + 1), value)
+            .map_err(serialization_error)
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if !self.empty {
+            self.out.write_all(b"\n")?;
+            write_indent(&mut self.out, self.level)?;
+        }
+        self.out.write_all(b"]")?;
+        Ok(())
+    }
+}
+
 /// Jaeger trace format.
 #[derive(Debug, Serialize, Deserialize)]
 struct JaegerTrace {
@@ -315,8 +728,10 @@ struct JaegerSpan {
     #[serde(rename = "operationName")]
     operation_name: String,
     references: Vec<JaegerReference>,
+    /// Microseconds since the Unix epoch
     #[serde(rename = "startTime")]
     start_time: u64,
+    /// Microseconds
     duration: u64,
     tags: Vec<JaegerTag>,
     logs: Vec<JaegerLog>,
@@ -355,10 +770,44 @@ struct JaegerTag {
 /// Jaeger log entry.
 #[derive(Debug, Serialize, Deserialize)]
 struct JaegerLog {
+    /// Microseconds since the Unix epoch
     timestamp: u64,
     fields: Vec<JaegerTag>,
 }
 
+/// Microseconds since the Unix epoch, the unit of Jaeger timestamps.
+fn unix_micros(time: SystemTime) -> u64 {
+    micros(time.duration_since(UNIX_EPOCH).unwrap_or_default())
+}
+
+/// Whole microseconds of `duration`, the unit of Jaeger durations.
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+/// Nanoseconds since the Unix epoch as a decimal string, as OTLP JSON encodes them.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Convert a span event to a Jaeger log; the name goes in the `event` field.
+fn jaeger_log(event: &SpanEvent) -> JaegerLog {
+    let string_field = |key: &str, value: &str| JaegerTag {
+        key: key.to_string(),
+        tag_type: "string".to_string(),
+        value: serde_json::Value::String(value.to_string()),
+    };
+    let mut fields = vec![string_field("event", &event.name)];
+    fields.extend(event.attributes.iter().map(|(k, v)| string_field(k, v)));
+    JaegerLog {
+        timestamp: unix_micros(event.timestamp),
+        fields,
+    }
+}
+
 /// Convert Urpo spans to Jaeger format.
 fn convert_to_jaeger_format(spans: &[Span]) -> JaegerTrace {
     let mut processes = HashMap::new();
@@ -394,7 +843,7 @@ fn convert_to_jaeger_format(spans: &[Span]) -> JaegerTrace {
             });
         }
 
-        let references = if let Some(parent_id) = &span.parent_span_id {
+        let mut references = if let Some(parent_id) = &span.parent_span_id {
             vec![JaegerReference {
                 ref_type: "CHILD_OF".to_string(),
                 trace_id: span.trace_id.as_str().to_string(),
@@ -403,6 +852,12 @@ fn convert_to_jaeger_format(spans: &[Span]) -> JaegerTrace {
         } else {
             vec![]
         };
+        // Links have no parent semantics in Jaeger
+        references.extend(span.links.iter().map(|link| JaegerReference {
+            ref_type: "FOLLOWS_FROM".to_string(),
+            trace_id: link.trace_id.as_str().to_string(),
+            span_id: link.span_id.as_str().to_string(),
+        }));
 
         jaeger_spans.push(JaegerSpan {
             trace_id: span.trace_id.as_str().to_string(),
@@ -410,14 +865,10 @@ fn convert_to_jaeger_format(spans: &[Span]) -> JaegerTrace {
             parent_span_id: span.parent_span_id.as_ref().map(|p| p.as_str().to_string()),
             operation_name: span.operation_name.clone(),
             references,
-            start_time: span
-                .start_time
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_micros() as u64,
-            duration: span.duration.as_micros() as u64,
+            start_time: unix_micros(span.start_time),
+            duration: micros(span.duration),
             tags,
-            logs: vec![],
+            logs: span.events.iter().map(jaeger_log).collect(),
             process_id: process_id.to_string(),
         });
     }
@@ -459,15 +910,55 @@ fn convert_to_otel_format(spans: &[Span]) -> serde_json::Value {
                 }));
             }
 
+            let events: Vec<_> = span
+                .events
+                .iter()
+                .map(|event| {
+                    let attributes: Vec<_> = event
+                        .attributes
+                        .iter()
+                        .map(|(key, value)| {
+                            serde_json::json!({ "key": key, "value": { "stringValue": value } })
+                        })
+                        .collect();
+                    serde_json::json!({
+                        "timeUnixNano": unix_nanos(event.timestamp),
+                        "name": event.name,
+                        "attributes": attributes,
+                    })
+                })
+                .collect();
+
+            let links: Vec<_> = span
+                .links
+                .iter()
+                .map(|link| {
+                    let attributes: Vec<_> = link
+                        .attributes
+                        .iter()
+                        .map(|(key, value)| {
+                            serde_json::json!({ "key": key, "value": { "stringValue": value } })
+                        })
+                        .collect();
+                    serde_json::json!({
+                        "traceId": link.trace_id.as_str(),
+                        "spanId": link.span_id.as_str(),
+                        "attributes": attributes,
+                    })
+                })
+                .collect();
+
             otel_spans.push(serde_json::json!({
                 "traceId": span.trace_id.as_str(),
                 "spanId": span.span_id.as_str(),
                 "parentSpanId": span.parent_span_id.as_ref().map(|p| p.as_str()),
                 "name": span.operation_name,
                 "kind": 1, // SPAN_KIND_SERVER
-                "startTimeUnixNano": span.start_time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos().to_string(),
-                "endTimeUnixNano": (span.start_time + span.duration).duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos().to_string(),
+                "startTimeUnixNano": unix_nanos(span.start_time),
+                "endTimeUnixNano": unix_nanos(span.start_time + span.duration),
                 "attributes": attributes,
+                "events": events,
+                "links": links,
                 "status": {
                     "code": if span.status.is_error() { 2 } else { 1 }
                 }
@@ -500,3 +991,373 @@ fn convert_to_otel_format(spans: &[Span]) -> serde_json::Value {
         "resourceSpans": resource_spans
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanLink, SpanStatus};
+    use crate::storage::InMemoryStorage;
+
+    async fn storage_with_traces() -> InMemoryStorage {
+        let storage = InMemoryStorage::new(1000);
+        for (i, service) in ["api", "api", "db"].iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("handle")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(10))
+                .status(SpanStatus::Ok)
+                .build()
+                .unwrap();
+            storage.store_span(span).await.unwrap();
+        }
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_json_export_metadata_header() {
+        let storage = storage_with_traces().await;
+        let exporter = TraceExporter::new(&storage);
+        let options = ExportOptions {
+            service: Some("api".to_string()),
+            limit: Some(50),
+            errors_only: false,
+            ..Default::default()
+        };
+
+        let output = exporter.export_traces(&options).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        let metadata = &value["metadata"];
+        assert_eq!(metadata["urpo_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata["format"], "json");
+        assert_eq!(metadata["filters"]["service"], "api");
+        assert_eq!(metadata["filters"]["limit"], 50);
+        assert_eq!(metadata["filters"]["errors_only"], false);
+        assert_eq!(metadata["trace_count"], 2);
+        assert_eq!(value["traces"].as_array().unwrap().len(), 2);
+        assert!(metadata["exported_at"].as_str().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_export_metadata_first_line() {
+        let storage = storage_with_traces().await;
+        let exporter = TraceExporter::new(&storage);
+        let options = ExportOptions {
+            format: ExportFormat::Ndjson,
+            errors_only: true,
+            ..Default::default()
+        };
+
+        let output = exporter.export_traces(&options).await.unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["metadata"]["filters"]["errors_only"], true);
+        assert_eq!(header["metadata"]["trace_count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_export_without_metadata_is_raw() {
+        let storage = storage_with_traces().await;
+        let exporter = TraceExporter::new(&storage);
+        let options = ExportOptions {
+            include_metadata: false,
+            ..Default::default()
+        };
+
+        let output = exporter.export_traces(&options).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_critical_path_only_export() {
+        let storage = InMemoryStorage::new(1000);
+        let start = SystemTime::now() - Duration::from_secs(1);
+        // (id, parent, start ms, end ms); span 2 overlaps span 3 and never blocks the root
+        for (id, parent, from, to) in [
+            (1, None, 0, 100),
+            (2, Some(1), 2, 30),
+            (3, Some(1), 1, 60),
+            (4, Some(1), 60, 100),
+        ] {
+            let mut builder = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new("api".to_string()).unwrap())
+                .operation_name(format!("op-{}", id))
+                .start_time(start + Duration::from_millis(from))
+                .duration(Duration::from_millis(to - from));
+            if let Some(parent) = parent {
+                builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+            }
+            storage.store_span(builder.build().unwrap()).await.unwrap();
+        }
+        let exporter = TraceExporter::new(&storage);
+        let options = ExportOptions {
+            critical_path_only: true,
+            ..Default::default()
+        };
+
+        let output = exporter.export_traces(&options).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["metadata"]["filters"]["critical_path_only"], true);
+        let operations: Vec<&str> = value["traces"][0]["spans"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|span| span["operation_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(operations, vec!["op-1", "op-3", "op-4"]);
+
+        let jaeger = ExportOptions {
+            format: ExportFormat::Jaeger,
+            ..options
+        };
+        let output = exporter.export_traces(&jaeger).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["spans"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_streamed_arrays_match_pretty_serialization() {
+        let values = vec![
+            serde_json::json!({ "spans": [{ "name": "line\nbreak" }, []], "count": 2 }),
+            serde_json::json!({}),
+        ];
+        let mut out = Vec::new();
+        let mut array = JsonArrayWriter::new(&mut out, 0).unwrap();
+        for value in &values {
+            array.element(value).unwrap();
+        }
+        array.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), serde_json::to_string_pretty(&values).unwrap());
+
+        for traces in [values, Vec::new()] {
+            let mut out = Vec::new();
+            out.extend_from_slice(b"{\n  \"metadata\": ");
+            serde_json::to_writer_pretty(
+                Indented::new(&mut out, 1),
+                &serde_json::json!({ "a": [1] }),
+            )
+            .unwrap();
+            out.extend_from_slice(b",\n  \"traces\": ");
+            let mut array = JsonArrayWriter::new(&mut out, 1).unwrap();
+            for value in &traces {
+                array.element(value).unwrap();
+            }
+            array.finish().unwrap();
+            out.extend_from_slice(b"\n}");
+            let whole = serde_json::json!({ "metadata": { "a": [1] }, "traces": traces });
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                serde_json::to_string_pretty(&whole).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attribute_redaction_applies_to_every_format() {
+        let storage = InMemoryStorage::new(1000);
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 7)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 7)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("login")
+            .start_time(SystemTime::now())
+            .duration(Duration::from_millis(10))
+            .attribute("http.request.header.authorization", "Bearer s3cret")
+            .attribute("enduser.email", "jane@example.com")
+            .attribute("http.route", "/login")
+            .build()
+            .unwrap();
+        storage.store_span(span).await.unwrap();
+        let exporter = TraceExporter::new(&storage);
+        let policy = redaction::RedactionPolicy::deny(vec![
+            crate::core::Glob::new("http.request.header.*"),
+            crate::core::Glob::new("enduser.*"),
+        ]);
+
+        for format in [
+            ExportFormat::Json,
+            ExportFormat::Ndjson,
+            ExportFormat::Jaeger,
+            ExportFormat::OpenTelemetry,
+            ExportFormat::Csv,
+            ExportFormat::ChromeTrace,
+        ] {
+            let mut options = ExportOptions {
+                format,
+                attribute_redaction: Some(policy.clone()),
+                ..Default::default()
+            };
+            let output = exporter.export_traces(&options).await.unwrap();
+            assert!(!output.contains("s3cret"), "{} export", format.name());
+            assert!(!output.contains("jane@example.com"), "{} export", format.name());
+            assert!(output.contains(redaction::DEFAULT_REDACTION_PLACEHOLDER));
+            assert!(output.contains("/login"), "{} export", format.name());
+
+            options.attribute_redaction = Some(policy.clone().with_placeholder(None));
+            let output = exporter.export_traces(&options).await.unwrap();
+            assert!(!output.contains("http.request.header.authorization"));
+            assert!(!output.contains(redaction::DEFAULT_REDACTION_PLACEHOLDER));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_traces_streams_every_format() {
+        let storage = storage_with_traces().await;
+        let exporter = TraceExporter::new(&storage);
+        for format in [
+            ExportFormat::Json,
+            ExportFormat::Ndjson,
+            ExportFormat::Jaeger,
+            ExportFormat::OpenTelemetry,
+            ExportFormat::Csv,
+            ExportFormat::ChromeTrace,
+        ] {
+            let options = ExportOptions {
+                format,
+                include_metadata: false,
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            assert_eq!(exporter.write_traces(&options, &mut out).await.unwrap(), 3);
+            let output = String::from_utf8(out).unwrap();
+
+            let records = match format {
+                ExportFormat::Ndjson => output.lines().count(),
+                // Header row plus one row per span
+                ExportFormat::Csv => output.lines().count() - 1,
+                ExportFormat::ChromeTrace => {
+                    serde_json::from_str::<Vec<serde_json::Value>>(&output)
+                        .unwrap()
+                        .iter()
+                        .filter(|event| event["ph"] == "X")
+                        .count()
+                },
+                _ => serde_json::from_str::<Vec<serde_json::Value>>(&output)
+                    .unwrap()
+                    .len(),
+            };
+            assert_eq!(records, 3, "{} export", format.name());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parquet_is_not_exported_as_text() {
+        let storage = storage_with_traces().await;
+        let exporter = TraceExporter::new(&storage);
+        let options = ExportOptions {
+            format: ExportFormat::Parquet,
+            ..Default::default()
+        };
+
+        assert!(exporter.export_traces(&options).await.is_err());
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        assert!(exporter
+            .export_trace(&trace_id, ExportFormat::Parquet)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_links_in_otel_and_jaeger_formats() {
+        let producer_trace = TraceId::new(format!("{:032x}", 7)).unwrap();
+        let producer_span = SpanId::new(format!("{:016x}", 8)).unwrap();
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("worker".to_string()).unwrap())
+            .operation_name("consume")
+            .link(
+                SpanLink::new(producer_trace.clone(), producer_span.clone())
+                    .with_attribute("messaging.operation", "publish"),
+            )
+            .build()
+            .unwrap();
+
+        let otel = convert_to_otel_format(std::slice::from_ref(&span));
+        let link = &otel["resourceSpans"][0]["scopeSpans"][0]["spans"][0]["links"][0];
+        assert_eq!(link["traceId"], producer_trace.as_str());
+        assert_eq!(link["spanId"], producer_span.as_str());
+        assert_eq!(link["attributes"][0]["key"], "messaging.operation");
+
+        let jaeger = convert_to_jaeger_format(&[span]);
+        let references = &jaeger.spans[0].references;
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].ref_type, "FOLLOWS_FROM");
+        assert_eq!(references[0].trace_id, producer_trace.as_str());
+        assert_eq!(references[0].span_id, producer_span.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_csv_fields_are_escaped() {
+        let storage = InMemoryStorage::new(100);
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("api, \"edge\"".to_string()).unwrap())
+            .operation_name("GET /a,b \"x\"\nnext")
+            .start_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .duration(Duration::from_millis(5))
+            .attribute("note", "say \"hi\", bye")
+            .build()
+            .unwrap();
+        storage.store_span(span).await.unwrap();
+        let exporter = TraceExporter::new(&storage);
+
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        let single = exporter
+            .export_trace(&trace_id, ExportFormat::Csv)
+            .await
+            .unwrap();
+        let options = ExportOptions {
+            format: ExportFormat::Csv,
+            ..Default::default()
+        };
+        let multi = exporter.export_traces(&options).await.unwrap();
+
+        for output in [single, multi] {
+            let mut reader = csv::Reader::from_reader(output.as_bytes());
+            assert_eq!(reader.headers().unwrap(), &csv::StringRecord::from(CSV_HEADER.to_vec()));
+            let rows: Vec<csv::StringRecord> = reader
+                .records()
+                .collect::<std::result::Result<_, _>>()
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+            assert_eq!(&rows[0][3], "api, \"edge\"");
+            assert_eq!(&rows[0][4], "GET /a,b \"x\"\nnext");
+            let attributes: serde_json::Value = serde_json::from_str(&rows[0][8]).unwrap();
+            assert_eq!(attributes["note"], "say \"hi\", bye");
+        }
+    }
+
+    #[test]
+    fn test_exported_time_units() {
+        let start = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET /")
+            .start_time(start)
+            .duration(Duration::from_secs(1))
+            .build()
+            .unwrap();
+
+        let jaeger = convert_to_jaeger_format(std::slice::from_ref(&span));
+        assert_eq!(jaeger.spans[0].start_time, 1_700_000_000_123_456);
+        assert_eq!(jaeger.spans[0].duration, 1_000_000);
+
+        let otel = convert_to_otel_format(&[span]);
+        let otel_span = &otel["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        let nanos = |key: &str| otel_span[key].as_str().unwrap().parse::<u128>().unwrap();
+        assert_eq!(nanos("startTimeUnixNano"), 1_700_000_000_123_456_789);
+        assert_eq!(nanos("endTimeUnixNano") - nanos("startTimeUnixNano"), 1_000_000_000);
+    }
+}
@@ -0,0 +1,424 @@
+//! Forwarding of received spans to a downstream OTLP/gRPC collector.
+//!
+//! [`OtlpForwarder`] relays spans to another collector (Tempo, Jaeger, an
+//! OpenTelemetry Collector) through `TraceService/Export`. Spans are sent in
+//! batches of at most [`DEFAULT_BATCH_SIZE`] spans, one resource per service.
+//! Failed exports are retried with exponential backoff when the collector is
+//! unavailable or slow; other errors fail the batch at once. Like the Jaeger
+//! exporter, the channel is created lazily and shared by all clones.
+
+use super::jaeger_grpc::decode_id;
+use crate::core::retry::{retry_with_config, RetryConfig};
+use crate::core::types::AttributeMap;
+use crate::core::{Result, Span, SpanKind, SpanStatus, UrpoError};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    trace_service_client::TraceServiceClient, ExportTraceServiceRequest,
+};
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{
+    span, status, ResourceSpans, ScopeSpans, Span as OtelSpan, Status,
+};
+use std::time::{Duration, UNIX_EPOCH};
+use tonic::transport::{Channel, Endpoint};
+
+/// Most spans sent in one export request.
+pub const DEFAULT_BATCH_SIZE: usize = 512;
+
+/// Forwards spans to a downstream OTLP/gRPC collector.
+///
+/// Cloning is cheap; clones share the underlying channel.
+#[derive(Debug, Clone)]
+pub struct OtlpForwarder {
+    endpoint: String,
+    client: TraceServiceClient<Channel>,
+    retry: RetryConfig,
+    batch_size: usize,
+}
+
+impl OtlpForwarder {
+    /// Create a forwarder for `endpoint` (e.g. `http://tempo:4317`).
+    ///
+    /// A missing scheme defaults to `http://`. The connection is opened on
+    /// the first export and re-established automatically after failures.
+    pub fn new(endpoint: &str) -> Result<Self> {
+        let endpoint = if endpoint.contains("://") {
+            endpoint.to_string()
+        } else {
+            format!("http://{}", endpoint)
+        };
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .map_err(|e| UrpoError::config(format!("Invalid OTLP endpoint {}: {}", endpoint, e)))?
+            .connect_lazy();
+
+        Ok(Self {
+            endpoint,
+            client: TraceServiceClient::new(channel),
+            retry: RetryConfig::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Retry failed exports according to `retry`.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send at most `batch_size` spans per export request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Endpoint spans are forwarded to.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Most spans sent in one export request.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Forward spans in batches, retrying each failed batch.
+    ///
+    /// Stops at the first batch that still fails after retrying; batches
+    /// already sent are not sent again.
+    pub async fn submit(&self, spans: Vec<Span>) -> Result<()> {
+        for batch in spans.chunks(self.batch_size) {
+            let request = to_otlp_request(batch);
+            retry_with_config(self.retry.clone(), || {
+                let mut client = self.client.clone();
+                let request = request.clone();
+                async move {
+                    client
+                        .export(tonic::Request::new(request))
+                        .await
+                        .map(|_| ())
+                        .map_err(UrpoError::Grpc)
+                }
+            })
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert spans to an OTLP export request, one resource per service.
+pub fn to_otlp_request(spans: &[Span]) -> ExportTraceServiceRequest {
+    let mut resource_spans: Vec<ResourceSpans> = Vec::new();
+    let mut services: Vec<&str> = Vec::new();
+    for span in spans {
+        let service = span.service_name.as_str();
+        let index = match services.iter().position(|&known| known == service) {
+            Some(index) => index,
+            None => {
+                services.push(service);
+                resource_spans.push(resource(span));
+                resource_spans.len() - 1
+            },
+        };
+        resource_spans[index].scope_spans[0]
+            .spans
+            .push(to_otlp_span(span));
+    }
+    ExportTraceServiceRequest { resource_spans }
+}
+
+/// Resource of the span's service, with no spans yet.
+fn resource(span: &Span) -> ResourceSpans {
+    let mut attributes = vec![string_value("service.name", span.service_name.as_str())];
+    attributes.extend(
+        span.resource_attributes
+            .iter()
+            .filter(|(key, _)| *key != "service.name")
+            .map(|(key, value)| string_value(key, value)),
+    );
+    ResourceSpans {
+        resource: Some(Resource {
+            attributes,
+            dropped_attributes_count: 0,
+        }),
+        scope_spans: vec![ScopeSpans {
+            scope: Some(InstrumentationScope {
+                name: "urpo".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn to_otlp_span(span: &Span) -> OtelSpan {
+    let trace_id = decode_id(span.trace_id.as_str(), 16);
+    let status = match span.status {
+        SpanStatus::Ok => Some(Status {
+            code: status::StatusCode::Ok as i32,
+            message: String::new(),
+        }),
+        SpanStatus::Error(ref message) => Some(Status {
+            code: status::StatusCode::Error as i32,
+            message: message.clone(),
+        }),
+        _ => None,
+    };
+
+    OtelSpan {
+        span_id: decode_id(span.span_id.as_str(), 8),
+        parent_span_id: span
+            .parent_span_id
+            .as_ref()
+            .map(|parent| decode_id(parent.as_str(), 8))
+            .unwrap_or_default(),
+        name: span.operation_name.clone(),
+        kind: span_kind(&span.kind) as i32,
+        start_time_unix_nano: unix_nanos(span.start_time),
+        end_time_unix_nano: unix_nanos(span.start_time + span.duration),
+        attributes: key_values(&span.attributes),
+        events: span
+            .events
+            .iter()
+            .map(|event| span::Event {
+                time_unix_nano: unix_nanos(event.timestamp),
+                name: event.name.clone(),
+                attributes: key_values(&event.attributes),
+                dropped_attributes_count: 0,
+            })
+            .collect(),
+        links: span
+            .links
+            .iter()
+            .map(|link| span::Link {
+                trace_id: decode_id(link.trace_id.as_str(), 16),
+                span_id: decode_id(link.span_id.as_str(), 8),
+                attributes: key_values(&link.attributes),
+                ..Default::default()
+            })
+            .collect(),
+        status,
+        trace_id,
+        ..Default::default()
+    }
+}
+
+fn span_kind(kind: &SpanKind) -> span::SpanKind {
+    match kind {
+        SpanKind::Internal => span::SpanKind::Internal,
+        SpanKind::Server => span::SpanKind::Server,
+        SpanKind::Client => span::SpanKind::Client,
+        SpanKind::Producer => span::SpanKind::Producer,
+        SpanKind::Consumer => span::SpanKind::Consumer,
+    }
+}
+
+fn key_values(attributes: &AttributeMap) -> Vec<KeyValue> {
+    attributes
+        .iter()
+        .map(|(key, value)| string_value(key, value))
+        .collect()
+}
+
+fn string_value(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.to_string())),
+        }),
+    }
+}
+
+fn unix_nanos(time: std::time::SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanEvent, SpanId, TraceId};
+    use opentelemetry_proto::tonic::collector::trace::v1::{
+        trace_service_server::{TraceService, TraceServiceServer},
+        ExportTraceServiceResponse,
+    };
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    /// Collector counting the export requests and spans it accepts, after
+    /// refusing the first `failures` requests as unavailable.
+    #[derive(Clone, Default)]
+    struct MockCollector {
+        requests: Arc<AtomicUsize>,
+        spans: Arc<AtomicUsize>,
+        failures: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl TraceService for MockCollector {
+        async fn export(
+            &self,
+            request: tonic::Request<ExportTraceServiceRequest>,
+        ) -> std::result::Result<tonic::Response<ExportTraceServiceResponse>, tonic::Status>
+        {
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err(tonic::Status::unavailable("warming up"));
+            }
+            let spans = request
+                .into_inner()
+                .resource_spans
+                .iter()
+                .flat_map(|resource| &resource.scope_spans)
+                .map(|scope| scope.spans.len())
+                .sum::<usize>();
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            self.spans.fetch_add(spans, Ordering::SeqCst);
+            Ok(tonic::Response::new(ExportTraceServiceResponse {
+                partial_success: None,
+            }))
+        }
+    }
+
+    async fn start_collector(failures: usize) -> (MockCollector, SocketAddr) {
+        let collector = MockCollector {
+            failures: Arc::new(AtomicUsize::new(failures)),
+            ..Default::default()
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(TraceServiceServer::new(collector.clone()))
+                .serve_with_incoming(TcpIncoming::from_listener(listener, true, None).unwrap()),
+        );
+        (collector, addr)
+    }
+
+    fn span(id: u64, service: &str) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name("charge")
+            .start_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .duration(Duration::from_millis(5))
+            .build()
+            .unwrap()
+    }
+
+    fn fast_retry() -> RetryConfig {
+        RetryConfig {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            multiplier: 2.0,
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn test_to_otlp_request_groups_spans_by_service() {
+        let mut child = span(2, "payments");
+        child.parent_span_id = Some(SpanId::new(format!("{:016x}", 1)).unwrap());
+        child.kind = SpanKind::Client;
+        child.status = SpanStatus::Error("declined".to_string());
+        child
+            .events
+            .push(SpanEvent::new("retry", UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+
+        let request = to_otlp_request(&[span(1, "checkout"), child, span(3, "checkout")]);
+        assert_eq!(request.resource_spans.len(), 2);
+        let checkout = &request.resource_spans[0];
+        assert_eq!(checkout.scope_spans[0].spans.len(), 2);
+        assert_eq!(
+            checkout.resource.as_ref().unwrap().attributes[0],
+            string_value("service.name", "checkout")
+        );
+
+        let otlp = &request.resource_spans[1].scope_spans[0].spans[0];
+        assert_eq!(otlp.trace_id[15], 1);
+        assert_eq!(otlp.span_id, vec![0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(otlp.parent_span_id, vec![0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(otlp.kind, span::SpanKind::Client as i32);
+        assert_eq!(otlp.end_time_unix_nano - otlp.start_time_unix_nano, 5_000_000);
+        assert_eq!(otlp.status.as_ref().unwrap().message, "declined");
+        assert_eq!(otlp.events[0].name, "retry");
+    }
+
+    #[tokio::test]
+    async fn test_submit_batches_and_retries_unavailable_collector() {
+        let (collector, addr) = start_collector(2).await;
+        let forwarder = OtlpForwarder::new(&addr.to_string())
+            .unwrap()
+            .with_retry(fast_retry())
+            .with_batch_size(2);
+
+        let spans = (1..=5).map(|id| span(id, "checkout")).collect();
+        forwarder.submit(spans).await.unwrap();
+        assert_eq!(collector.requests.load(Ordering::SeqCst), 3);
+        assert_eq!(collector.spans.load(Ordering::SeqCst), 5);
+        assert_eq!(collector.failures.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_submit_gives_up_after_retries() {
+        let (collector, addr) = start_collector(usize::MAX).await;
+        let forwarder = OtlpForwarder::new(&addr.to_string())
+            .unwrap()
+            .with_retry(fast_retry());
+
+        assert!(forwarder.submit(vec![span(1, "checkout")]).await.is_err());
+        assert_eq!(collector.requests.load(Ordering::SeqCst), 0);
+        assert_eq!(collector.failures.load(Ordering::SeqCst), usize::MAX - 4);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_forwards_sampled_spans() {
+        use crate::receiver::{http::create_http_router, OtelReceiver};
+        use axum::body::Body;
+        use prost::Message;
+        use tower::ServiceExt;
+
+        let (collector, addr) = start_collector(0).await;
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_forwarding(&addr.to_string())
+        .unwrap();
+
+        let request = to_otlp_request(&[span(1, "checkout"), span(2, "payments")]);
+        let response = create_http_router(Arc::new(receiver))
+            .oneshot(
+                axum::http::Request::post("/v1/traces")
+                    .header("content-type", "application/x-protobuf")
+                    .body(Body::from(request.encode_to_vec()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 2);
+
+        for _ in 0..500 {
+            if collector.spans.load(Ordering::SeqCst) == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(collector.spans.load(Ordering::SeqCst), 2);
+        assert_eq!(collector.requests.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,208 @@
+//! Parquet export of spans for offline analysis (DuckDB, pandas).
+//!
+//! One row per span with the columns `trace_id`, `span_id`,
+//! `parent_span_id`, `service`, `operation`, `start_time` (UTC nanosecond
+//! timestamp), `duration_ns`, `status` and `attributes`. Attributes are a
+//! JSON object string, flattened into columns by the reader when needed.
+
+use crate::core::{Result, Span, UrpoError};
+use ::parquet::basic::Compression;
+use ::parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::file::writer::SerializedFileWriter;
+use ::parquet::schema::parser::parse_message_type;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+/// Spans written per row group.
+pub const ROW_GROUP_SPANS: usize = 64 * 1024;
+
+/// Schema of exported span rows.
+const SPAN_SCHEMA: &str = "
+message span {
+    REQUIRED BYTE_ARRAY trace_id (UTF8);
+    REQUIRED BYTE_ARRAY span_id (UTF8);
+    OPTIONAL BYTE_ARRAY parent_span_id (UTF8);
+    REQUIRED BYTE_ARRAY service (UTF8);
+    REQUIRED BYTE_ARRAY operation (UTF8);
+    REQUIRED INT64 start_time (TIMESTAMP(NANOS, true));
+    REQUIRED INT64 duration_ns;
+    REQUIRED BYTE_ARRAY status (UTF8);
+    REQUIRED BYTE_ARRAY attributes (JSON);
+}
+";
+
+fn parquet_error(e: ::parquet::errors::ParquetError) -> UrpoError {
+    UrpoError::SerializationError(format!("Parquet: {}", e))
+}
+
+/// Values of one column of a row group.
+enum Column {
+    Strings(Vec<ByteArray>),
+    /// Values of the non-null rows and a definition level per row
+    OptionalStrings(Vec<ByteArray>, Vec<i16>),
+    Int64(Vec<i64>),
+}
+
+/// Write `spans` as a Parquet file to `writer`.
+pub fn write_spans<W: Write + Send>(spans: &[Span], writer: W) -> Result<()> {
+    let schema = Arc::new(parse_message_type(SPAN_SCHEMA).map_err(parquet_error)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let mut file = SerializedFileWriter::new(writer, schema, properties).map_err(parquet_error)?;
+
+    for chunk in spans.chunks(ROW_GROUP_SPANS) {
+        let mut row_group = file.next_row_group().map_err(parquet_error)?;
+        for column in columns(chunk)? {
+            let mut writer = row_group
+                .next_column()
+                .map_err(parquet_error)?
+                .expect("schema has a column per value");
+            match column {
+                Column::Strings(values) => writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, None, None),
+                Column::OptionalStrings(values, levels) => writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None),
+                Column::Int64(values) => {
+                    writer.typed::<Int64Type>().write_batch(&values, None, None)
+                },
+            }
+            .map_err(parquet_error)?;
+            writer.close().map_err(parquet_error)?;
+        }
+        row_group.close().map_err(parquet_error)?;
+    }
+
+    file.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+/// Columns of `spans` in schema order.
+fn columns(spans: &[Span]) -> Result<Vec<Column>> {
+    let strings = |value: fn(&Span) -> &str| {
+        Column::Strings(
+            spans
+                .iter()
+                .map(|span| ByteArray::from(value(span)))
+                .collect(),
+        )
+    };
+    let attributes = spans
+        .iter()
+        .map(|span| {
+            serde_json::to_string(&span.attributes)
+                .map(|json| ByteArray::from(json.into_bytes()))
+                .map_err(|e| UrpoError::SerializationError(e.to_string()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(vec![
+        strings(|span| span.trace_id.as_str()),
+        strings(|span| span.span_id.as_str()),
+        Column::OptionalStrings(
+            spans
+                .iter()
+                .filter_map(|span| span.parent_span_id.as_ref())
+                .map(|parent| ByteArray::from(parent.as_str()))
+                .collect(),
+            spans
+                .iter()
+                .map(|span| i16::from(span.parent_span_id.is_some()))
+                .collect(),
+        ),
+        strings(|span| span.service_name.as_str()),
+        strings(|span| &span.operation_name),
+        Column::Int64(
+            spans
+                .iter()
+                .map(|span| {
+                    span.start_time
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_nanos() as i64
+                })
+                .collect(),
+        ),
+        Column::Int64(
+            spans
+                .iter()
+                .map(|span| span.duration.as_nanos() as i64)
+                .collect(),
+        ),
+        strings(|span| {
+            if span.status.is_error() {
+                "ERROR"
+            } else {
+                "OK"
+            }
+        }),
+        Column::Strings(attributes),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanStatus, TraceId};
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use ::parquet::record::RowAccessor;
+    use std::time::{Duration, SystemTime};
+
+    fn span(id: u64, parent: Option<u64>, error: bool) -> Span {
+        let mut builder = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("checkout".to_string()).unwrap())
+            .operation_name(format!("op-{}", id))
+            .start_time(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + id))
+            .duration(Duration::from_millis(id * 10))
+            .status(if error {
+                SpanStatus::Error("declined".to_string())
+            } else {
+                SpanStatus::Ok
+            })
+            .attribute("http.method", "POST");
+        if let Some(parent) = parent {
+            builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let spans = vec![span(1, None, false), span(2, Some(1), true)];
+        let file = tempfile::NamedTempFile::new().unwrap();
+        write_spans(&spans, file.reopen().unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(file.reopen().unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        let root = &rows[0];
+        assert_eq!(root.get_string(0).unwrap(), &format!("{:032x}", 1));
+        assert_eq!(root.get_string(1).unwrap(), &format!("{:016x}", 1));
+        assert!(root.get_string(2).is_err());
+        assert_eq!(root.get_string(3).unwrap(), "checkout");
+        assert_eq!(root.get_string(4).unwrap(), "op-1");
+        assert_eq!(root.get_long(5).unwrap(), 1_700_000_001_000_000_000);
+        assert_eq!(root.get_long(6).unwrap(), 10_000_000);
+        assert_eq!(root.get_string(7).unwrap(), "OK");
+
+        let child = &rows[1];
+        assert_eq!(child.get_string(2).unwrap(), &format!("{:016x}", 1));
+        assert_eq!(child.get_string(7).unwrap(), "ERROR");
+        let attributes: serde_json::Value =
+            serde_json::from_str(child.get_string(8).unwrap()).unwrap();
+        assert_eq!(attributes["http.method"], "POST");
+    }
+}
@@ -0,0 +1,157 @@
+//! Attribute redaction of exported spans.
+//!
+//! Exports leave Urpo for other systems, so attributes can be stripped on the
+//! way out whatever was kept at ingestion. A [`RedactionPolicy`] matches
+//! attribute keys with an [`AttributeFilter`], as the `attributes` config does
+//! at ingestion: every key the filter would drop is redacted. Redacted values
+//! are replaced by a placeholder, or the attributes removed when there is
+//! none. Span, resource, event and link attributes are all covered.
+
+use crate::core::types::AttributeMap;
+use crate::core::{AttributeFilter, Glob, Span};
+use std::sync::Arc;
+
+/// Value that replaces redacted attributes by default.
+pub const DEFAULT_REDACTION_PLACEHOLDER: &str = "[REDACTED]";
+
+/// Attributes to redact from exported spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionPolicy {
+    /// Keys left as is; the keys it does not allow are redacted
+    pub keys: AttributeFilter,
+    /// Value written in place of redacted attributes; `None` removes them
+    pub placeholder: Option<String>,
+}
+
+impl RedactionPolicy {
+    /// Redact the keys `keys` does not allow.
+    pub fn new(keys: AttributeFilter) -> Self {
+        Self {
+            keys,
+            placeholder: Some(DEFAULT_REDACTION_PLACEHOLDER.to_string()),
+        }
+    }
+
+    /// Redact keys matching any of `patterns`.
+    pub fn deny(patterns: Vec<Glob>) -> Self {
+        Self::new(AttributeFilter::new(Vec::new(), patterns))
+    }
+
+    /// Redact keys matching none of `patterns`.
+    pub fn allow(patterns: Vec<Glob>) -> Self {
+        Self::new(AttributeFilter::new(patterns, Vec::new()))
+    }
+
+    /// Replace redacted values by `placeholder`, or remove the attributes.
+    pub fn with_placeholder(mut self, placeholder: Option<String>) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Whether the attribute `key` is redacted.
+    pub fn redacts(&self, key: &str) -> bool {
+        !self.keys.allows(key)
+    }
+
+    /// Redact an attribute map in place, returning how many attributes changed.
+    pub fn redact_attributes(&self, attributes: &mut AttributeMap) -> usize {
+        let placeholder: Option<Arc<str>> = self.placeholder.as_deref().map(Arc::from);
+        let mut redacted = 0;
+        attributes.0.retain(|(key, value)| {
+            if !self.redacts(key) {
+                return true;
+            }
+            redacted += 1;
+            match placeholder {
+                Some(ref placeholder) => {
+                    *value = Arc::clone(placeholder);
+                    true
+                },
+                None => false,
+            }
+        });
+        redacted
+    }
+
+    /// Redact every attribute of `span`, returning how many changed.
+    pub fn redact_span(&self, span: &mut Span) -> usize {
+        let mut redacted = self.redact_attributes(&mut span.attributes)
+            + self.redact_attributes(&mut span.tags)
+            + self.redact_attributes(&mut span.resource_attributes);
+        for event in &mut span.events {
+            redacted += self.redact_attributes(&mut event.attributes);
+        }
+        for link in &mut span.links {
+            redacted += self.redact_attributes(&mut link.attributes);
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(pairs: &[(&str, &str)]) -> AttributeMap {
+        let mut map = AttributeMap::new();
+        for (key, value) in pairs {
+            map.push(Arc::from(*key), Arc::from(*value));
+        }
+        map
+    }
+
+    fn globs(patterns: &[&str]) -> Vec<Glob> {
+        patterns.iter().map(|pattern| Glob::new(*pattern)).collect()
+    }
+
+    #[test]
+    fn test_denylist_replaces_or_removes_matching_keys() {
+        let original = attrs(&[
+            ("http.request.header.authorization", "Bearer abc"),
+            ("user.email", "a@example.com"),
+            ("http.route", "/orders"),
+        ]);
+        let policy = RedactionPolicy::deny(globs(&["http.request.header.*", "user.*"]));
+
+        let mut replaced = original.clone();
+        assert_eq!(policy.redact_attributes(&mut replaced), 2);
+        assert_eq!(
+            replaced.get("http.request.header.authorization"),
+            Some(DEFAULT_REDACTION_PLACEHOLDER)
+        );
+        assert_eq!(replaced.get("user.email"), Some(DEFAULT_REDACTION_PLACEHOLDER));
+        assert_eq!(replaced.get("http.route"), Some("/orders"));
+
+        let mut removed = original;
+        assert_eq!(
+            policy
+                .with_placeholder(None)
+                .redact_attributes(&mut removed),
+            2
+        );
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed.get("http.request.header.authorization"), None);
+        assert_eq!(removed.get("http.route"), Some("/orders"));
+    }
+
+    #[test]
+    fn test_allowlist_redacts_everything_else() {
+        let mut attributes = attrs(&[("http.method", "GET"), ("db.statement", "SELECT 1")]);
+        let policy =
+            RedactionPolicy::allow(globs(&["http.*"])).with_placeholder(Some("***".to_string()));
+        assert_eq!(policy.redact_attributes(&mut attributes), 1);
+        assert_eq!(attributes.get("http.method"), Some("GET"));
+        assert_eq!(attributes.get("db.statement"), Some("***"));
+    }
+
+    #[test]
+    fn test_deny_wins_over_allow() {
+        let policy = RedactionPolicy::new(AttributeFilter::new(
+            globs(&["http.*"]),
+            globs(&["http.request.header.*"]),
+        ));
+        assert!(!policy.redacts("http.route"));
+        assert!(policy.redacts("http.request.header.cookie"));
+        assert!(policy.redacts("db.statement"));
+    }
+}
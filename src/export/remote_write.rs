@@ -0,0 +1,583 @@
+//! Prometheus remote-write output for derived RED metrics.
+//!
+//! Periodically computes per-service and per-operation request rate, error
+//! rate and latency quantiles from stored spans and pushes them to a
+//! Prometheus-compatible remote-write endpoint (snappy-compressed protobuf).
+//!
+//! Series that disappear between pushes (e.g. a service stops sending spans)
+//! are closed with Prometheus staleness markers so dashboards stop drawing
+//! them immediately. Failed pushes are kept in a bounded retry queue; when the
+//! queue is full the oldest payload is dropped.
+
+use crate::core::{Result, Span, SpanStatus, UrpoError};
+use crate::storage::StorageBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Bit pattern Prometheus uses to mark a series as stale.
+pub const STALE_NAN_BITS: u64 = 0x7ff0_0000_0000_0002;
+
+/// Latency quantiles exported for every service and operation.
+const QUANTILES: [f64; 3] = [0.5, 0.95, 0.99];
+
+/// Remote-write protobuf messages (prometheus/prompb).
+pub mod proto {
+    /// Top-level remote-write payload.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WriteRequest {
+        /// Series contained in this request.
+        #[prost(message, repeated, tag = "1")]
+        pub timeseries: Vec<TimeSeries>,
+    }
+
+    /// A single labelled series with its samples.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TimeSeries {
+        /// Labels, sorted by name.
+        #[prost(message, repeated, tag = "1")]
+        pub labels: Vec<Label>,
+        /// Samples, sorted by timestamp.
+        #[prost(message, repeated, tag = "2")]
+        pub samples: Vec<Sample>,
+    }
+
+    /// Label name/value pair.
+    #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Message)]
+    pub struct Label {
+        /// Label name.
+        #[prost(string, tag = "1")]
+        pub name: String,
+        /// Label value.
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    /// Sample value with a millisecond timestamp.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        /// Sample value.
+        #[prost(double, tag = "1")]
+        pub value: f64,
+        /// Unix timestamp in milliseconds.
+        #[prost(int64, tag = "2")]
+        pub timestamp: i64,
+    }
+}
+
+use proto::{Label, Sample, TimeSeries, WriteRequest};
+
+/// Authentication for the remote-write endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteWriteAuth {
+    /// `Authorization: Bearer <token>`
+    Bearer {
+        /// Bearer token
+        token: String,
+    },
+    /// HTTP basic authentication
+    Basic {
+        /// Username
+        username: String,
+        /// Password
+        password: String,
+    },
+}
+
+/// Remote-write client configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteWriteConfig {
+    /// Remote-write endpoint URL (e.g. `http://prometheus:9090/api/v1/write`)
+    pub endpoint: String,
+    /// Optional authentication
+    pub auth: Option<RemoteWriteAuth>,
+    /// Push interval; also the window over which rates are computed
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    /// Per-request timeout
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Maximum number of payloads kept for retry
+    pub max_queue_size: usize,
+}
+
+impl Default for RemoteWriteConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            auth: None,
+            interval: Duration::from_secs(15),
+            timeout: Duration::from_secs(10),
+            max_queue_size: 32,
+        }
+    }
+}
+
+/// Outcome of a single push attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushOutcome {
+    /// Payload accepted by the endpoint
+    Sent,
+    /// Payload permanently rejected, drop it
+    Rejected,
+    /// Transient failure, keep payload for retry
+    Retry,
+}
+
+/// Counters exposed for diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RemoteWriteStats {
+    /// Payloads successfully delivered
+    pub sent: u64,
+    /// Payloads dropped (queue overflow or permanent rejection)
+    pub dropped: u64,
+    /// Payloads currently waiting for retry
+    pub queued: usize,
+}
+
+/// Pushes derived service metrics to a Prometheus remote-write endpoint.
+pub struct RemoteWriteClient {
+    config: RemoteWriteConfig,
+    storage: Arc<RwLock<dyn StorageBackend>>,
+    http: reqwest::Client,
+    queue: VecDeque<WriteRequest>,
+    active_series: HashSet<Vec<Label>>,
+    stats: RemoteWriteStats,
+}
+
+impl RemoteWriteClient {
+    /// Create a new remote-write client.
+    pub fn new(
+        config: RemoteWriteConfig,
+        storage: Arc<RwLock<dyn StorageBackend>>,
+    ) -> Result<Self> {
+        if config.endpoint.is_empty() {
+            return Err(UrpoError::config("Remote-write endpoint must not be empty"));
+        }
+        if config.max_queue_size == 0 {
+            return Err(UrpoError::config("Remote-write max_queue_size must be greater than 0"));
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| UrpoError::network(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            storage,
+            http,
+            queue: VecDeque::new(),
+            active_series: HashSet::new(),
+            stats: RemoteWriteStats::default(),
+        })
+    }
+
+    /// Current delivery counters.
+    pub fn stats(&self) -> RemoteWriteStats {
+        RemoteWriteStats {
+            queued: self.queue.len(),
+            ..self.stats
+        }
+    }
+
+    /// Run the push loop until the task is cancelled.
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.push_once().await {
+                tracing::warn!("Remote-write push failed: {}", e);
+            }
+        }
+    }
+
+    /// Collect current metrics, enqueue them and flush the retry queue.
+    pub async fn push_once(&mut self) -> Result<()> {
+        let now = SystemTime::now();
+        let spans = self.collect_spans(now).await?;
+        let request = self.build_request(&spans, now);
+
+        if !request.timeseries.is_empty() {
+            self.enqueue(request);
+        }
+
+        self.flush().await
+    }
+
+    /// Fetch spans for every service within the push window.
+    async fn collect_spans(&self, now: SystemTime) -> Result<Vec<Span>> {
+        let since = now.checked_sub(self.config.interval).unwrap_or(UNIX_EPOCH);
+        let storage = self.storage.read().await;
+
+        let mut spans = Vec::new();
+        for service in storage.list_services().await? {
+            spans.extend(storage.get_service_spans(&service, since).await?);
+        }
+        Ok(spans)
+    }
+
+    /// Build a write request for the given spans, appending staleness markers
+    /// for series that were active in the previous push but are now gone.
+    fn build_request(&mut self, spans: &[Span], now: SystemTime) -> WriteRequest {
+        let timestamp = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+
+        let mut timeseries = derive_series(spans, self.config.interval, timestamp);
+
+        let current: HashSet<Vec<Label>> = timeseries.iter().map(|ts| ts.labels.clone()).collect();
+        for labels in self.active_series.difference(&current) {
+            timeseries.push(TimeSeries {
+                labels: labels.clone(),
+                samples: vec![Sample {
+                    value: f64::from_bits(STALE_NAN_BITS),
+                    timestamp,
+                }],
+            });
+        }
+        self.active_series = current;
+
+        WriteRequest { timeseries }
+    }
+
+    /// Add a payload to the retry queue, dropping the oldest when full.
+    fn enqueue(&mut self, request: WriteRequest) {
+        while self.queue.len() >= self.config.max_queue_size {
+            self.queue.pop_front();
+            self.stats.dropped += 1;
+            tracing::warn!("Remote-write queue full, dropping oldest payload");
+        }
+        self.queue.push_back(request);
+    }
+
+    /// Send queued payloads in order, stopping at the first transient failure.
+    async fn flush(&mut self) -> Result<()> {
+        while let Some(request) = self.queue.front() {
+            match self.send(request).await? {
+                PushOutcome::Sent => self.stats.sent += 1,
+                PushOutcome::Rejected => self.stats.dropped += 1,
+                PushOutcome::Retry => return Ok(()),
+            }
+            self.queue.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Send a single payload.
+    async fn send(&self, request: &WriteRequest) -> Result<PushOutcome> {
+        let body = encode_request(request)?;
+
+        let mut builder = self
+            .http
+            .post(&self.config.endpoint)
+            .header("Content-Type", "application/x-protobuf")
+            .header("Content-Encoding", "snappy")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+            .header("User-Agent", concat!("urpo/", env!("CARGO_PKG_VERSION")))
+            .body(body);
+
+        builder = match &self.config.auth {
+            Some(RemoteWriteAuth::Bearer { token }) => builder.bearer_auth(token),
+            Some(RemoteWriteAuth::Basic { username, password }) => {
+                builder.basic_auth(username, Some(password))
+            },
+            None => builder,
+        };
+
+        let response = match builder.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::debug!("Remote-write request failed, will retry: {}", e);
+                return Ok(PushOutcome::Retry);
+            },
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(PushOutcome::Sent)
+        } else if status.is_server_error() || status.as_u16() == 429 {
+            tracing::debug!("Remote-write endpoint returned {}, will retry", status);
+            Ok(PushOutcome::Retry)
+        } else {
+            // 4xx other than 429 will never succeed - drop the payload
+            tracing::warn!("Remote-write endpoint rejected payload with {}", status);
+            Ok(PushOutcome::Rejected)
+        }
+    }
+}
+
+/// Encode a write request as snappy-compressed protobuf.
+pub fn encode_request(request: &WriteRequest) -> Result<Vec<u8>> {
+    use prost::Message;
+
+    snap::raw::Encoder::new()
+        .compress_vec(&request.encode_to_vec())
+        .map_err(|e| UrpoError::SerializationError(format!("Snappy compression failed: {}", e)))
+}
+
+/// Decode a snappy-compressed protobuf write request.
+pub fn decode_request(body: &[u8]) -> Result<WriteRequest> {
+    use prost::Message;
+
+    let raw = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|e| {
+            UrpoError::SerializationError(format!("Snappy decompression failed: {}", e))
+        })?;
+    WriteRequest::decode(raw.as_slice())
+        .map_err(|e| UrpoError::SerializationError(format!("Invalid WriteRequest: {}", e)))
+}
+
+/// Aggregated RED data for one service or operation.
+#[derive(Default)]
+struct RedAccumulator {
+    count: u64,
+    errors: u64,
+    durations: Vec<f64>,
+}
+
+impl RedAccumulator {
+    fn record(&mut self, span: &Span) {
+        self.count += 1;
+        if matches!(span.status, SpanStatus::Error(_)) {
+            self.errors += 1;
+        }
+        self.durations.push(span.duration.as_secs_f64());
+    }
+}
+
+/// Derive rate, error and latency quantile series from spans.
+fn derive_series(spans: &[Span], window: Duration, timestamp: i64) -> Vec<TimeSeries> {
+    let mut services: BTreeMap<&str, RedAccumulator> = BTreeMap::new();
+    let mut operations: BTreeMap<(&str, &str), RedAccumulator> = BTreeMap::new();
+
+    for span in spans {
+        services
+            .entry(span.service_name.as_str())
+            .or_default()
+            .record(span);
+        operations
+            .entry((span.service_name.as_str(), span.operation_name.as_str()))
+            .or_default()
+            .record(span);
+    }
+
+    let window_secs = window.as_secs_f64().max(1.0);
+    let mut series = Vec::new();
+
+    for (service, acc) in &mut services {
+        let base = [("service", *service)];
+        push_red_series(&mut series, "urpo_service", &base, acc, window_secs, timestamp);
+    }
+    for ((service, operation), acc) in &mut operations {
+        let base = [("operation", *operation), ("service", *service)];
+        push_red_series(&mut series, "urpo_operation", &base, acc, window_secs, timestamp);
+    }
+
+    series
+}
+
+fn push_red_series(
+    series: &mut Vec<TimeSeries>,
+    prefix: &str,
+    base: &[(&str, &str)],
+    acc: &mut RedAccumulator,
+    window_secs: f64,
+    timestamp: i64,
+) {
+    let rate = acc.count as f64 / window_secs;
+    let error_rate = if acc.count == 0 {
+        0.0
+    } else {
+        acc.errors as f64 / acc.count as f64
+    };
+
+    series.push(single_sample(&format!("{}_request_rate", prefix), base, &[], rate, timestamp));
+    series.push(single_sample(
+        &format!("{}_error_rate", prefix),
+        base,
+        &[],
+        error_rate,
+        timestamp,
+    ));
+
+    acc.durations.sort_by(|a, b| a.total_cmp(b));
+    let name = format!("{}_latency_seconds", prefix);
+    for q in QUANTILES {
+        let value = quantile(&acc.durations, q);
+        let q_label = q.to_string();
+        series.push(single_sample(
+            &name,
+            base,
+            &[("quantile", q_label.as_str())],
+            value,
+            timestamp,
+        ));
+    }
+}
+
+/// Nearest-rank quantile over sorted values.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let idx = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx]
+}
+
+fn single_sample(
+    name: &str,
+    base: &[(&str, &str)],
+    extra: &[(&str, &str)],
+    value: f64,
+    timestamp: i64,
+) -> TimeSeries {
+    let mut labels: Vec<Label> = std::iter::once(("__name__", name))
+        .chain(base.iter().copied())
+        .chain(extra.iter().copied())
+        .map(|(name, value)| Label {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect();
+    labels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    TimeSeries {
+        labels,
+        samples: vec![Sample { value, timestamp }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, TraceId};
+    use crate::storage::InMemoryStorage;
+
+    fn span(service: &str, operation: &str, ms: u64, error: bool) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("trace-{}-{}", service, ms)).unwrap())
+            .span_id(SpanId::new(format!("span-{}-{}", service, ms)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name(operation)
+            .duration(Duration::from_millis(ms))
+            .status(if error {
+                SpanStatus::Error("boom".to_string())
+            } else {
+                SpanStatus::Ok
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn client() -> RemoteWriteClient {
+        let storage: Arc<RwLock<dyn StorageBackend>> =
+            Arc::new(RwLock::new(InMemoryStorage::new(100)));
+        RemoteWriteClient::new(
+            RemoteWriteConfig {
+                endpoint: "http://127.0.0.1:1/api/v1/write".to_string(),
+                max_queue_size: 2,
+                ..Default::default()
+            },
+            storage,
+        )
+        .unwrap()
+    }
+
+    fn label<'a>(ts: &'a TimeSeries, name: &str) -> Option<&'a str> {
+        ts.labels
+            .iter()
+            .find(|l| l.name == name)
+            .map(|l| l.value.as_str())
+    }
+
+    #[test]
+    fn test_derive_series_red_values() {
+        let spans = vec![
+            span("api", "GET /users", 10, false),
+            span("api", "GET /users", 20, true),
+            span("api", "POST /users", 30, false),
+        ];
+        let series = derive_series(&spans, Duration::from_secs(10), 1_000);
+
+        let rate = series
+            .iter()
+            .find(|ts| {
+                label(ts, "__name__") == Some("urpo_service_request_rate")
+                    && label(ts, "service") == Some("api")
+            })
+            .unwrap();
+        assert!((rate.samples[0].value - 0.3).abs() < f64::EPSILON);
+
+        let op_errors = series
+            .iter()
+            .find(|ts| {
+                label(ts, "__name__") == Some("urpo_operation_error_rate")
+                    && label(ts, "operation") == Some("GET /users")
+            })
+            .unwrap();
+        assert!((op_errors.samples[0].value - 0.5).abs() < f64::EPSILON);
+
+        // Labels must be sorted by name for remote-write receivers
+        for ts in &series {
+            assert!(ts.labels.windows(2).all(|w| w[0].name < w[1].name));
+        }
+    }
+
+    #[test]
+    fn test_staleness_markers_for_disappeared_services() {
+        let mut client = client();
+        let now = SystemTime::now();
+
+        let first =
+            client.build_request(&[span("api", "op", 5, false), span("db", "q", 5, false)], now);
+        assert!(first
+            .timeseries
+            .iter()
+            .all(|ts| !ts.samples[0].value.is_nan()));
+
+        let second = client.build_request(&[span("api", "op", 5, false)], now);
+        let stale: Vec<_> = second
+            .timeseries
+            .iter()
+            .filter(|ts| ts.samples[0].value.to_bits() == STALE_NAN_BITS)
+            .collect();
+        assert!(!stale.is_empty());
+        assert!(stale.iter().all(|ts| label(ts, "service") == Some("db")));
+
+        // Once marked stale, the series is not reported again
+        let third = client.build_request(&[span("api", "op", 5, false)], now);
+        assert!(third
+            .timeseries
+            .iter()
+            .all(|ts| ts.samples[0].value.to_bits() != STALE_NAN_BITS));
+    }
+
+    #[test]
+    fn test_retry_queue_is_bounded() {
+        let mut client = client();
+        for _ in 0..5 {
+            client.enqueue(WriteRequest::default());
+        }
+        let stats = client.stats();
+        assert_eq!(stats.queued, 2);
+        assert_eq!(stats.dropped, 3);
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let request = WriteRequest {
+            timeseries: derive_series(&[span("api", "op", 5, false)], Duration::from_secs(15), 42),
+        };
+        let decoded = decode_request(&encode_request(&request).unwrap()).unwrap();
+        assert_eq!(decoded, request);
+    }
+}
@@ -228,6 +228,11 @@ impl LogStorage {
         Ok(filtered)
     }
 
+    /// Get the shared string pool for service names
+    pub fn string_pool(&self) -> &Arc<StringPool> {
+        &self.string_pool
+    }
+
     /// Get storage statistics
     pub fn get_stats(&self) -> LogStorageStats {
         let logs = self.logs.read();
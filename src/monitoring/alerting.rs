@@ -0,0 +1,318 @@
+//! Webhook alerts on service health.
+//!
+//! With the `alerting` feature, an [`AlertEngine`] checks the current [`ServiceMetrics`] of every service
+//! against its [`AlertRule`]s on each tick and POSTs a JSON summary of the
+//! breaching service to the rule's webhook. The payload carries a `text`
+//! field so Slack incoming webhooks can take it as is. A rule fires at most
+//! once per service and cooldown, so a service that stays unhealthy does not
+//! flood the channel.
+
+use crate::core::ServiceMetrics;
+#[cfg(feature = "alerting")]
+use crate::core::{Result, ServiceName, UrpoError};
+#[cfg(feature = "alerting")]
+use crate::storage::StorageBackend;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "alerting")]
+use std::collections::HashMap;
+#[cfg(feature = "alerting")]
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "alerting")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "alerting")]
+use tokio::sync::RwLock;
+
+/// Timeout of a webhook POST.
+#[cfg(feature = "alerting")]
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_cooldown() -> Duration {
+    Duration::from_secs(300)
+}
+
+/// What an [`AlertRule`] checks on each service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Error rate above `threshold` percent
+    ErrorRate {
+        /// Percentage of spans with an error status
+        threshold: f64,
+    },
+    /// P95 latency above `threshold`
+    P95Latency {
+        /// Latency limit
+        #[serde(with = "humantime_serde")]
+        threshold: Duration,
+    },
+}
+
+impl AlertCondition {
+    /// Whether `metrics` breach the condition.
+    pub fn is_breached(&self, metrics: &ServiceMetrics) -> bool {
+        match self {
+            Self::ErrorRate { threshold } => metrics.error_rate * 100.0 > *threshold,
+            Self::P95Latency { threshold } => metrics.latency_p95 > *threshold,
+        }
+    }
+
+    /// Human readable description such as `error rate > 5%`.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::ErrorRate { threshold } => format!("error rate > {}%", threshold),
+            Self::P95Latency { threshold } => format!("p95 latency > {:?}", threshold),
+        }
+    }
+}
+
+/// A condition and the webhook notified when a service breaches it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Condition checked on every service
+    pub condition: AlertCondition,
+    /// URL the alert is POSTed to
+    pub webhook_url: String,
+    /// Minimum time between two alerts of the rule for the same service
+    #[serde(default = "default_cooldown", with = "humantime_serde")]
+    pub cooldown: Duration,
+}
+
+/// JSON body POSTed to a rule's webhook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertPayload {
+    /// One line summary, shown by Slack
+    pub text: String,
+    /// Breaching service
+    pub service: String,
+    /// Breached condition, as in [`AlertCondition::describe`]
+    pub condition: String,
+    /// Error rate in percent
+    pub error_rate_percent: f64,
+    /// P95 latency in milliseconds
+    pub latency_p95_ms: u64,
+    /// Requests per second
+    pub request_rate: f64,
+    /// Spans seen for the service
+    pub span_count: u64,
+    /// Error spans seen for the service
+    pub error_count: u64,
+    /// When the alert fired, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+#[cfg(feature = "alerting")]
+impl AlertPayload {
+    fn new(condition: &AlertCondition, metrics: &ServiceMetrics, now: SystemTime) -> Self {
+        let error_rate_percent = metrics.error_rate * 100.0;
+        let latency_p95_ms = metrics.latency_p95.as_millis() as u64;
+        Self {
+            text: format!(
+                "[urpo] {}: {} (error rate {:.1}%, p95 {}ms, {} spans)",
+                metrics.name.as_str(),
+                condition.describe(),
+                error_rate_percent,
+                latency_p95_ms,
+                metrics.span_count
+            ),
+            service: metrics.name.as_str().to_string(),
+            condition: condition.describe(),
+            error_rate_percent,
+            latency_p95_ms,
+            request_rate: metrics.request_rate,
+            span_count: metrics.span_count,
+            error_count: metrics.error_count,
+            timestamp: now
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Evaluates alert rules against service metrics and notifies webhooks.
+#[cfg(feature = "alerting")]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    storage: Arc<RwLock<dyn StorageBackend>>,
+    http: reqwest::Client,
+    min_sample_size: u64,
+    last_fired: HashMap<(usize, ServiceName), Instant>,
+}
+
+#[cfg(feature = "alerting")]
+impl AlertEngine {
+    /// Create an engine for `rules`.
+    pub fn new(rules: Vec<AlertRule>, storage: Arc<RwLock<dyn StorageBackend>>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|e| UrpoError::network(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            rules,
+            storage,
+            http,
+            min_sample_size: 0,
+            last_fired: HashMap::new(),
+        })
+    }
+
+    /// Ignore services with fewer spans than `min_sample_size`.
+    pub fn with_min_sample_size(mut self, min_sample_size: usize) -> Self {
+        self.min_sample_size = min_sample_size as u64;
+        self
+    }
+
+    /// Evaluate the rules every `interval` until the task is cancelled.
+    pub async fn run(mut self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.evaluate_once().await {
+                tracing::warn!("Alert evaluation failed: {}", e);
+            }
+        }
+    }
+
+    /// Check the current service metrics and POST the alerts that fire,
+    /// returning them.
+    pub async fn evaluate_once(&mut self) -> Result<Vec<AlertPayload>> {
+        let metrics = self.storage.read().await.get_service_metrics().await?;
+        let fired = self.fire(&metrics, Instant::now(), SystemTime::now());
+
+        let mut payloads = Vec::with_capacity(fired.len());
+        for (rule, payload) in fired {
+            let url = &self.rules[rule].webhook_url;
+            match self.http.post(url).json(&payload).send().await {
+                Ok(response) if !response.status().is_success() => {
+                    tracing::warn!("Alert webhook {} answered {}", url, response.status());
+                },
+                Ok(_) => tracing::info!("Alert sent to {}: {}", url, payload.text),
+                Err(e) => tracing::warn!("Failed to send alert to {}: {}", url, e),
+            }
+            payloads.push(payload);
+        }
+        Ok(payloads)
+    }
+
+    /// Alerts due for `metrics`, as (rule index, payload), recording them for
+    /// the cooldown.
+    fn fire(
+        &mut self,
+        metrics: &[ServiceMetrics],
+        now: Instant,
+        wall_clock: SystemTime,
+    ) -> Vec<(usize, AlertPayload)> {
+        let mut fired = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            for service in metrics {
+                if service.span_count < self.min_sample_size || !rule.condition.is_breached(service)
+                {
+                    continue;
+                }
+                let key = (index, service.name.clone());
+                if let Some(last) = self.last_fired.get(&key) {
+                    if now.duration_since(*last) < rule.cooldown {
+                        continue;
+                    }
+                }
+                self.last_fired.insert(key, now);
+                fired.push((index, AlertPayload::new(&rule.condition, service, wall_clock)));
+            }
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alerting")]
+    use crate::storage::InMemoryStorage;
+
+    #[cfg(feature = "alerting")]
+    fn metrics(service: &str, error_rate: f64, p95_ms: u64) -> ServiceMetrics {
+        let mut metrics = ServiceMetrics::new(ServiceName::new(service.to_string()).unwrap());
+        metrics.error_rate = error_rate;
+        metrics.latency_p95 = Duration::from_millis(p95_ms);
+        metrics.span_count = 100;
+        metrics
+    }
+
+    #[cfg(feature = "alerting")]
+    fn engine(rules: Vec<AlertRule>) -> AlertEngine {
+        let storage: Arc<RwLock<dyn StorageBackend>> =
+            Arc::new(RwLock::new(InMemoryStorage::new(100)));
+        AlertEngine::new(rules, storage).unwrap()
+    }
+
+    #[cfg(feature = "alerting")]
+    #[test]
+    fn test_rules_fire_once_per_cooldown() {
+        let mut engine = engine(vec![
+            AlertRule {
+                condition: AlertCondition::ErrorRate { threshold: 5.0 },
+                webhook_url: "http://alerts.example.com".to_string(),
+                cooldown: Duration::from_secs(60),
+            },
+            AlertRule {
+                condition: AlertCondition::P95Latency {
+                    threshold: Duration::from_millis(500),
+                },
+                webhook_url: "http://alerts.example.com".to_string(),
+                cooldown: Duration::from_secs(60),
+            },
+        ]);
+        let services = [metrics("checkout", 0.2, 100), metrics("search", 0.01, 900)];
+        let start = Instant::now();
+
+        let fired = engine.fire(&services, start, SystemTime::now());
+        let fired: Vec<(usize, &str)> = fired
+            .iter()
+            .map(|(rule, payload)| (*rule, payload.service.as_str()))
+            .collect();
+        assert_eq!(fired, vec![(0, "checkout"), (1, "search")]);
+
+        assert!(engine
+            .fire(&services, start + Duration::from_secs(30), SystemTime::now())
+            .is_empty());
+        assert_eq!(
+            engine
+                .fire(&services, start + Duration::from_secs(61), SystemTime::now())
+                .len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "alerting")]
+    #[test]
+    fn test_small_samples_are_ignored() {
+        let mut engine = engine(vec![AlertRule {
+            condition: AlertCondition::ErrorRate { threshold: 5.0 },
+            webhook_url: "http://alerts.example.com".to_string(),
+            cooldown: default_cooldown(),
+        }])
+        .with_min_sample_size(1000);
+        assert!(engine
+            .fire(&[metrics("checkout", 0.5, 10)], Instant::now(), SystemTime::now())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rule_from_yaml() {
+        let rule: AlertRule = serde_yaml::from_str(
+            "condition: { type: p95_latency, threshold: 750ms }\nwebhook_url: https://hooks.example.com/x\n",
+        )
+        .unwrap();
+        assert_eq!(
+            rule.condition,
+            AlertCondition::P95Latency {
+                threshold: Duration::from_millis(750)
+            }
+        );
+        assert_eq!(rule.cooldown, Duration::from_secs(300));
+    }
+}
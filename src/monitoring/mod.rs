@@ -3,18 +3,31 @@
 //! This module provides comprehensive system monitoring, health checks,
 //! and operational metrics for production deployment.
 
+pub mod alerting;
+
 use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
     Arc,
 };
 use std::time::{Duration, SystemTime};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{watch, Mutex, RwLock};
 use tokio::time::interval;
 
-use crate::core::Result;
+use crate::core::{system_clock, Result, ServiceName, SharedClock, Span};
 // No more external performance manager - we track it ourselves
-use crate::storage::{StorageHealth, StorageStats};
+use crate::storage::{StorageBackend, StorageHealth, StorageStats};
+use dashmap::DashMap;
+
+/// How far back service baselines look.
+pub const BASELINE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often service baselines are recomputed.
+pub const BASELINE_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A service is slow (or erroring) when its current value exceeds this
+/// multiple of its own baseline.
+pub const BASELINE_FACTOR: f64 = 3.0;
 
 /// Performance metrics tracked internally
 #[derive(Debug, Clone, Default)]
@@ -234,6 +247,12 @@ pub struct Monitor {
     uptime_tracker: Arc<Mutex<UptimeTracker>>,
     /// Shutdown signal.
     shutdown: Arc<AtomicBool>,
+    /// Per-service latency and error baselines.
+    baselines: Arc<BaselineCalculator>,
+    /// Time source for timestamps, health check intervals and baselines.
+    clock: SharedClock,
+    /// System health published after each metrics collection pass.
+    health_passes: Arc<watch::Sender<SystemHealth>>,
 }
 
 /// Monitoring configuration.
@@ -311,7 +330,7 @@ impl ErrorTracker {
         }
     }
 
-    fn record_error(&mut self, category: &str, message: String) {
+    fn record_error(&mut self, category: &str, message: String, now: SystemTime) {
         self.total.fetch_add(1, Ordering::Relaxed);
 
         // Update category count
@@ -321,7 +340,7 @@ impl ErrorTracker {
             .fetch_add(1, Ordering::Relaxed);
 
         // Add to recent errors
-        self.recent.push((SystemTime::now(), message));
+        self.recent.push((now, message));
 
         // Limit recent errors
         if self.recent.len() > 100 {
@@ -357,16 +376,18 @@ struct UptimeTracker {
 }
 
 impl UptimeTracker {
-    fn new() -> Self {
+    fn new(start_time: SystemTime) -> Self {
         Self {
-            start_time: SystemTime::now(),
+            start_time,
             restarts: 0,
             downtime_events: Vec::new(),
         }
     }
 
-    fn get_metrics(&self) -> UptimeMetrics {
-        let uptime = self.start_time.elapsed().unwrap_or(Duration::new(0, 0));
+    fn get_metrics(&self, now: SystemTime) -> UptimeMetrics {
+        let uptime = now
+            .duration_since(self.start_time)
+            .unwrap_or(Duration::new(0, 0));
 
         UptimeMetrics {
             start_time: self.start_time,
@@ -401,6 +422,12 @@ impl Monitor {
                 last_cleanup: None,
                 health_status: StorageHealth::Healthy,
                 uptime_seconds: 0,
+                rejected_spans: 0,
+                spans_processed: 0,
+                spans_evicted: 0,
+                warm_span_count: 0,
+                warm_memory_bytes: 0,
+                warm_memory_saved_bytes: 0,
             },
             performance: PerformanceStats::default(),
             receiver: ReceiverMetrics::default(),
@@ -415,11 +442,21 @@ impl Monitor {
             config,
             health_checks: Arc::new(RwLock::new(HashMap::new())),
             error_tracker: Arc::new(Mutex::new(ErrorTracker::new())),
-            uptime_tracker: Arc::new(Mutex::new(UptimeTracker::new())),
+            uptime_tracker: Arc::new(Mutex::new(UptimeTracker::new(SystemTime::now()))),
             shutdown: Arc::new(AtomicBool::new(false)),
+            baselines: Arc::new(BaselineCalculator::new(BASELINE_WINDOW)),
+            clock: system_clock(),
+            health_passes: Arc::new(watch::Sender::new(SystemHealth::Healthy)),
         }
     }
 
+    /// Read the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.uptime_tracker = Arc::new(Mutex::new(UptimeTracker::new(clock.now())));
+        self.clock = clock;
+        self
+    }
+
     /// Start monitoring in background.
     pub async fn start(&self) -> Result<()> {
         // Start metrics collection
@@ -438,6 +475,8 @@ impl Monitor {
         let uptime_tracker = Arc::clone(&self.uptime_tracker);
         let shutdown = Arc::clone(&self.shutdown);
         let config = self.config.clone();
+        let clock = Arc::clone(&self.clock);
+        let health_passes = Arc::clone(&self.health_passes);
 
         tokio::spawn(async move {
             let mut interval = interval(config.metrics_interval);
@@ -457,7 +496,7 @@ impl Monitor {
                 // Collect uptime metrics
                 let uptime = {
                     let uptime_tracker = uptime_tracker.lock().await;
-                    uptime_tracker.get_metrics()
+                    uptime_tracker.get_metrics(clock.now())
                 };
 
                 // Collect resource metrics (simplified)
@@ -472,8 +511,10 @@ impl Monitor {
                 metrics.errors = errors;
                 metrics.uptime = uptime;
                 metrics.resources = resources;
-                metrics.health = health;
-                metrics.timestamp = SystemTime::now();
+                metrics.health = health.clone();
+                metrics.timestamp = clock.now();
+                drop(metrics);
+                health_passes.send_replace(health);
             }
         });
 
@@ -485,6 +526,7 @@ impl Monitor {
         let health_checks = Arc::clone(&self.health_checks);
         let shutdown = Arc::clone(&self.shutdown);
         let config = self.config.clone();
+        let clock = Arc::clone(&self.clock);
 
         tokio::spawn(async move {
             let mut interval = interval(config.health_check_interval);
@@ -493,14 +535,18 @@ impl Monitor {
                 interval.tick().await;
 
                 let mut checks = health_checks.write().await;
+                let now = clock.now();
                 for (name, check) in checks.iter_mut() {
                     if check.enabled
-                        && check.last_check.elapsed().unwrap_or(Duration::MAX) >= check.interval
+                        && now
+                            .duration_since(check.last_check)
+                            .unwrap_or(Duration::MAX)
+                            >= check.interval
                     {
                         // Perform health check (simplified)
                         let healthy = Self::perform_health_check(name).await;
 
-                        check.last_check = SystemTime::now();
+                        check.last_check = now;
                         check.healthy = healthy;
 
                         if !healthy {
@@ -601,7 +647,7 @@ impl Monitor {
     /// Record an error for monitoring.
     pub async fn record_error(&self, category: &str, message: String) {
         let mut error_tracker = self.error_tracker.lock().await;
-        error_tracker.record_error(category, message);
+        error_tracker.record_error(category, message, self.clock.now());
     }
 
     /// Update storage metrics.
@@ -626,6 +672,12 @@ impl Monitor {
         self.metrics.read().await.health.clone()
     }
 
+    /// Receive the system health after every pass of the background
+    /// metrics collection started by [`start`](Self::start), changed or not.
+    pub fn subscribe_health(&self) -> watch::Receiver<SystemHealth> {
+        self.health_passes.subscribe()
+    }
+
     /// Register a health check.
     pub async fn register_health_check(&self, check: HealthCheck) {
         let mut health_checks = self.health_checks.write().await;
@@ -637,6 +689,38 @@ impl Monitor {
         self.health_checks.read().await.clone()
     }
 
+    /// Recompute service baselines from `storage` every
+    /// [`BASELINE_REFRESH_INTERVAL`] until monitoring is stopped.
+    pub fn start_baselines(
+        &self,
+        storage: Arc<RwLock<dyn StorageBackend>>,
+    ) -> tokio::task::JoinHandle<()> {
+        let baselines = Arc::clone(&self.baselines);
+        let shutdown = Arc::clone(&self.shutdown);
+        let clock = Arc::clone(&self.clock);
+
+        tokio::spawn(async move {
+            let mut interval = interval(BASELINE_REFRESH_INTERVAL);
+            while !shutdown.load(Ordering::Relaxed) {
+                interval.tick().await;
+                let storage = storage.read().await;
+                if let Err(e) = baselines.refresh(&*storage, clock.now()).await {
+                    tracing::warn!("Failed to refresh service baselines: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Baseline of `service`, once one has been computed.
+    pub fn get_baseline(&self, service: &ServiceName) -> Option<ServiceBaseline> {
+        self.baselines.get(service)
+    }
+
+    /// The baseline calculator.
+    pub fn baselines(&self) -> &Arc<BaselineCalculator> {
+        &self.baselines
+    }
+
     /// Stop monitoring.
     pub fn stop(&self) {
         self.shutdown.store(true, Ordering::Relaxed);
@@ -644,11 +728,12 @@ impl Monitor {
 
     /// Create default health checks.
     pub async fn setup_default_health_checks(&self) {
+        let now = self.clock.now();
         let checks = vec![
             HealthCheck {
                 name: "storage".to_string(),
                 enabled: true,
-                last_check: SystemTime::now(),
+                last_check: now,
                 healthy: true,
                 interval: Duration::from_secs(30),
                 consecutive_failures: 0,
@@ -657,7 +742,7 @@ impl Monitor {
             HealthCheck {
                 name: "grpc_receiver".to_string(),
                 enabled: true,
-                last_check: SystemTime::now(),
+                last_check: now,
                 healthy: true,
                 interval: Duration::from_secs(60),
                 consecutive_failures: 0,
@@ -666,7 +751,7 @@ impl Monitor {
             HealthCheck {
                 name: "memory".to_string(),
                 enabled: true,
-                last_check: SystemTime::now(),
+                last_check: now,
                 healthy: true,
                 interval: Duration::from_secs(15),
                 consecutive_failures: 0,
@@ -734,6 +819,91 @@ pub struct HealthSummary {
     pub storage_health: StorageHealth,
 }
 
+/// Normal latency and error rate of a service over the baseline window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServiceBaseline {
+    /// 95th percentile span duration
+    pub latency_p95: Duration,
+    /// Fraction of spans with an error status (0.0 to 1.0)
+    pub error_rate: f64,
+    /// Number of spans the baseline was computed from
+    pub span_count: usize,
+    /// When the baseline was computed
+    pub computed_at: SystemTime,
+}
+
+impl ServiceBaseline {
+    /// Baseline of `spans`, or `None` if there are none.
+    pub fn from_spans(spans: &[Span], computed_at: SystemTime) -> Option<Self> {
+        if spans.is_empty() {
+            return None;
+        }
+        let mut durations: Vec<Duration> = spans.iter().map(|span| span.duration).collect();
+        durations.sort_unstable();
+        let errors = spans.iter().filter(|span| span.is_error()).count();
+        Some(Self {
+            latency_p95: durations[(durations.len() * 95 / 100).min(durations.len() - 1)],
+            error_rate: errors as f64 / spans.len() as f64,
+            span_count: spans.len(),
+            computed_at,
+        })
+    }
+
+    /// Whether `latency` is more than [`BASELINE_FACTOR`] times the baseline P95.
+    pub fn is_slow(&self, latency: Duration) -> bool {
+        latency.as_secs_f64() > self.latency_p95.as_secs_f64() * BASELINE_FACTOR
+    }
+
+    /// Whether `error_rate` is more than [`BASELINE_FACTOR`] times the
+    /// baseline error rate; any error counts for a service that had none.
+    pub fn is_erroring(&self, error_rate: f64) -> bool {
+        error_rate > 0.0 && error_rate > self.error_rate * BASELINE_FACTOR
+    }
+}
+
+/// Per-service baselines computed from stored spans.
+#[derive(Debug)]
+pub struct BaselineCalculator {
+    window: Duration,
+    baselines: DashMap<ServiceName, ServiceBaseline>,
+}
+
+impl BaselineCalculator {
+    /// Create a calculator looking back `window` from each refresh.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            baselines: DashMap::new(),
+        }
+    }
+
+    /// Recompute the baseline of every stored service as of `now`, returning
+    /// how many services have one. Services without spans in the window
+    /// keep their previous baseline.
+    pub async fn refresh(&self, storage: &dyn StorageBackend, now: SystemTime) -> Result<usize> {
+        let since = now
+            .checked_sub(self.window)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        for service in storage.list_services().await? {
+            let spans = storage.get_service_spans(&service, since).await?;
+            if let Some(baseline) = ServiceBaseline::from_spans(&spans, now) {
+                self.baselines.insert(service, baseline);
+            }
+        }
+        Ok(self.baselines.len())
+    }
+
+    /// Set the baseline of `service` directly.
+    pub fn insert(&self, service: ServiceName, baseline: ServiceBaseline) {
+        self.baselines.insert(service, baseline);
+    }
+
+    /// Baseline of `service`, if computed.
+    pub fn get(&self, service: &ServiceName) -> Option<ServiceBaseline> {
+        self.baselines.get(service).map(|baseline| *baseline)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -746,6 +916,34 @@ mod tests {
         assert_eq!(metrics.health, SystemHealth::Healthy);
     }
 
+    #[tokio::test]
+    async fn test_errors_and_uptime_follow_clock() {
+        use crate::core::{Clock, MockClock};
+
+        let clock = MockClock::default();
+        let start = clock.now();
+        let monitor = Monitor::new().with_clock(clock.shared());
+
+        monitor
+            .record_error("storage", "disk full".to_string())
+            .await;
+        clock.advance(Duration::from_secs(90));
+        monitor
+            .record_error("receiver", "bad frame".to_string())
+            .await;
+
+        let errors = monitor.error_tracker.lock().await.get_metrics(10);
+        assert_eq!(
+            errors.recent_errors,
+            vec![
+                (start + Duration::from_secs(90), "bad frame".to_string()),
+                (start, "disk full".to_string()),
+            ]
+        );
+        let uptime = monitor.uptime_tracker.lock().await.get_metrics(clock.now());
+        assert_eq!(uptime.uptime, Duration::from_secs(90));
+    }
+
     #[tokio::test]
     async fn test_health_determination() {
         let config = MonitoringConfig::default();
@@ -797,6 +995,21 @@ mod tests {
         assert!(metrics.errors.error_categories.contains_key("storage"));
     }
 
+    #[tokio::test]
+    async fn test_metrics_passes_publish_health() {
+        let monitor = Monitor::new();
+        let mut passes = monitor.subscribe_health();
+        monitor.start().await.unwrap();
+
+        // The first pass runs right away
+        tokio::time::timeout(Duration::from_secs(5), passes.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*passes.borrow_and_update(), monitor.get_health().await);
+        monitor.stop();
+    }
+
     #[tokio::test]
     async fn test_health_checks() {
         let monitor = Monitor::new();
@@ -817,6 +1030,80 @@ mod tests {
         assert!(checks.contains_key("test_check"));
         assert!(checks["test_check"].healthy);
     }
+
+    #[tokio::test]
+    async fn test_slow_is_relative_to_service_baseline() {
+        let monitor = Monitor::new();
+        let (fast, slow) = (
+            ServiceName::new("cache".to_string()).unwrap(),
+            ServiceName::new("batch".to_string()).unwrap(),
+        );
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let baseline = |p95_ms: u64| ServiceBaseline {
+            latency_p95: Duration::from_millis(p95_ms),
+            error_rate: 0.02,
+            span_count: 1000,
+            computed_at: now,
+        };
+        monitor.baselines().insert(fast.clone(), baseline(10));
+        monitor.baselines().insert(slow.clone(), baseline(2000));
+
+        // 100ms is slow for a 10ms service but normal for a 2s one
+        let current = Duration::from_millis(100);
+        assert!(monitor.get_baseline(&fast).unwrap().is_slow(current));
+        assert!(!monitor.get_baseline(&slow).unwrap().is_slow(current));
+        // Up to 3x the baseline is still normal
+        assert!(!monitor
+            .get_baseline(&fast)
+            .unwrap()
+            .is_slow(Duration::from_millis(30)));
+        assert!(monitor
+            .get_baseline(&slow)
+            .unwrap()
+            .is_slow(Duration::from_millis(6001)));
+
+        let fast_baseline = monitor.get_baseline(&fast).unwrap();
+        assert!(!fast_baseline.is_erroring(0.05));
+        assert!(fast_baseline.is_erroring(0.07));
+        assert!(monitor
+            .get_baseline(&ServiceName::new("unknown".to_string()).unwrap())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_baseline_refresh_from_storage() {
+        use crate::core::{SpanId, SpanStatus, TraceId};
+        use crate::storage::InMemoryStorage;
+
+        let storage = InMemoryStorage::new(1000);
+        let now = SystemTime::now();
+        for i in 0..20u64 {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new("api".to_string()).unwrap())
+                .operation_name("GET /users")
+                .start_time(now - Duration::from_secs(60))
+                .duration(Duration::from_millis(10 * (i + 1)))
+                .status(if i < 2 {
+                    SpanStatus::Error("timeout".to_string())
+                } else {
+                    SpanStatus::Ok
+                })
+                .build()
+                .unwrap();
+            storage.store_span(span).await.unwrap();
+        }
+
+        let calculator = BaselineCalculator::new(BASELINE_WINDOW);
+        assert_eq!(calculator.refresh(&storage, now).await.unwrap(), 1);
+        let baseline = calculator
+            .get(&ServiceName::new("api".to_string()).unwrap())
+            .unwrap();
+        assert_eq!(baseline.span_count, 20);
+        assert_eq!(baseline.latency_p95, Duration::from_millis(200));
+        assert!((baseline.error_rate - 0.1).abs() < 1e-9);
+    }
 }
 
 // Type alias for backward compatibility
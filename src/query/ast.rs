@@ -8,6 +8,22 @@ pub struct Query {
     pub filter: QueryFilter,
 }
 
+/// Aggregation over the traces matching a filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateQuery {
+    pub aggregate: AggregateExpr,
+    pub filter: QueryFilter,
+}
+
+/// Aggregate functions
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateExpr {
+    /// Number of matching traces
+    Count,
+    /// Number of matching traces per value of a field (e.g. `service`)
+    CountGroupBy(String),
+}
+
 /// Query filter expressions
 #[derive(Debug, Clone, PartialEq)]
 pub enum QueryFilter {
@@ -175,6 +191,16 @@ impl fmt::Display for Query {
     }
 }
 
+impl fmt::Display for AggregateQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "COUNT({})", self.filter)?;
+        if let AggregateExpr::CountGroupBy(field) = &self.aggregate {
+            write!(f, " GROUP BY {}", field)?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for QueryFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
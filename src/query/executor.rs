@@ -1,13 +1,16 @@
 //! Query executor that runs parsed queries against the storage backend.
 
 use super::ast::*;
-use super::QueryResult;
-use crate::core::{Result, ServiceName, SpanStatus};
+use super::{AggregateResult, QueryResult};
+use crate::core::{Result, ServiceName, Span, SpanStatus, TraceId, UrpoError};
 use crate::storage::StorageBackend;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 
+/// Maximum number of traces an aggregate query scans.
+const MAX_AGGREGATE_TRACES: usize = 10_000;
+
 /// Query executor that runs queries against the storage
 pub struct QueryExecutor {
     storage: Arc<tokio::sync::RwLock<dyn StorageBackend>>,
@@ -47,6 +50,35 @@ impl QueryExecutor {
         })
     }
 
+    /// Execute an aggregate query over the matching traces.
+    ///
+    /// Grouping uses the trace's root span: `service` and `name` map to the
+    /// root service and operation, any other field to a root span attribute.
+    /// Traces without a value for the field are counted under `""`.
+    pub async fn execute_aggregate(&self, query: AggregateQuery) -> Result<AggregateResult> {
+        let storage = self.storage.read().await;
+        let matching_traces = self
+            .execute_filter(&*storage, &query.filter, MAX_AGGREGATE_TRACES)
+            .await?;
+
+        match query.aggregate {
+            AggregateExpr::Count => Ok(AggregateResult::Count(matching_traces.len())),
+            AggregateExpr::CountGroupBy(field) => {
+                let mut groups = BTreeMap::new();
+                for trace_id in matching_traces {
+                    let trace_id = TraceId::new(format!("{:032x}", trace_id))?;
+                    let spans = storage.get_trace_spans(&trace_id).await?;
+                    if spans.is_empty() {
+                        continue;
+                    }
+                    let key = group_key(&field, &spans).unwrap_or_default();
+                    *groups.entry(key).or_insert(0) += 1;
+                }
+                Ok(AggregateResult::Groups(groups))
+            },
+        }
+    }
+
     /// Execute a filter against the storage
     async fn execute_filter(
         &self,
@@ -218,12 +250,31 @@ impl QueryExecutor {
                 }
             },
 
+            Field::Attribute(name) => {
+                let matcher = AttributeMatcher::new(name, *op, value)?;
+                let trace_infos = storage.list_recent_traces(limit * 10, None).await?;
+
+                let mut trace_ids = Vec::new();
+                for info in trace_infos {
+                    let spans = storage.get_trace_spans(&info.trace_id).await?;
+                    if !spans.iter().any(|span| matcher.matches(span)) {
+                        continue;
+                    }
+                    if let Ok(trace_id) = u128::from_str_radix(info.trace_id.as_str(), 16) {
+                        trace_ids.push(trace_id);
+                        if trace_ids.len() >= limit {
+                            break;
+                        }
+                    }
+                }
+                Ok(trace_ids)
+            },
+
             Field::Name
             | Field::TraceId
             | Field::SpanId
             | Field::ParentSpanId
-            | Field::SpanKind
-            | Field::Attribute(_) => {
+            | Field::SpanKind => {
                 // For now, these require scanning all spans
                 // In a production system, we'd have proper indexing for these
                 Ok(vec![])
@@ -260,17 +311,94 @@ impl QueryExecutor {
     async fn get_recent_spans(
         &self,
         storage: &dyn StorageBackend,
-        _limit: usize,
+        limit: usize,
     ) -> Result<Vec<crate::core::Span>> {
-        // This is a simplified implementation
-        // In production, we'd have a more efficient way to get recent spans
+        // Spans of the most recent traces, as there is no span level index
+        let mut spans = Vec::new();
+        for info in storage.list_recent_traces(limit, None).await? {
+            spans.extend(storage.get_trace_spans(&info.trace_id).await?);
+        }
+        Ok(spans)
+    }
+}
 
-        // Try to get spans from a known service (this is a hack)
-        // In reality, we'd need a get_recent_spans method on StorageBackend
-        let _stats = storage.get_stats().await?;
+/// Span attribute comparison. `resource.<key>` names look up the span's
+/// resource attributes, any other name its own attributes.
+struct AttributeMatcher<'a> {
+    key: &'a str,
+    resource: bool,
+    op: Operator,
+    expected: String,
+    regex: Option<regex::Regex>,
+}
 
-        // For now, return empty - this needs proper implementation
-        Ok(vec![])
+impl<'a> AttributeMatcher<'a> {
+    fn new(name: &'a str, op: Operator, value: &Value) -> Result<Self> {
+        let (key, resource) = match name.strip_prefix("resource.") {
+            Some(key) => (key, true),
+            None => (name, false),
+        };
+        let expected = match value {
+            Value::String(s) => s.clone(),
+            Value::Integer(i) => i.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            other => {
+                return Err(UrpoError::parse(format!(
+                    "cannot compare attribute '{}' with {:?}",
+                    name, other
+                )))
+            },
+        };
+        let regex =
+            match op {
+                Operator::Regex => Some(regex::Regex::new(&expected).map_err(|e| {
+                    UrpoError::parse(format!("invalid regex '{}': {}", expected, e))
+                })?),
+                _ => None,
+            };
+        Ok(Self {
+            key,
+            resource,
+            op,
+            expected,
+            regex,
+        })
+    }
+
+    fn matches(&self, span: &Span) -> bool {
+        let actual = if self.resource {
+            span.resource_attributes.get(self.key)
+        } else {
+            span.attributes.get(self.key)
+        };
+        let Some(actual) = actual else {
+            return false;
+        };
+        let numeric = || Some((actual.parse::<f64>().ok()?, self.expected.parse::<f64>().ok()?));
+        match self.op {
+            Operator::Eq => actual == self.expected,
+            Operator::NotEq => actual != self.expected,
+            Operator::Contains => actual.contains(self.expected.as_str()),
+            Operator::Regex => self.regex.as_ref().is_some_and(|re| re.is_match(actual)),
+            Operator::Gt => numeric().is_some_and(|(a, e)| a > e),
+            Operator::Gte => numeric().is_some_and(|(a, e)| a >= e),
+            Operator::Lt => numeric().is_some_and(|(a, e)| a < e),
+            Operator::Lte => numeric().is_some_and(|(a, e)| a <= e),
+        }
+    }
+}
+
+/// Value of `field` on the trace's root span (or first span without a root).
+fn group_key(field: &str, spans: &[Span]) -> Option<String> {
+    let root = spans
+        .iter()
+        .find(|span| span.parent_span_id.is_none())
+        .or_else(|| spans.first())?;
+
+    match field.to_ascii_lowercase().as_str() {
+        "service" => Some(root.service_name.as_str().to_string()),
+        "name" | "operation" => Some(root.operation_name.clone()),
+        _ => root.attributes.get(field).map(str::to_string),
     }
 }
 
@@ -299,4 +427,79 @@ mod tests {
         let result = executor.execute(query, Some(10)).await.unwrap();
         assert_eq!(result.trace_ids.len(), 0); // No data yet
     }
+
+    #[tokio::test]
+    async fn test_execute_aggregate() {
+        use crate::core::SpanId;
+        use std::time::{Duration, SystemTime};
+
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (i, service) in ["api", "api", "db"].into_iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("handle")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(5))
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let executor = QueryExecutor::new(storage);
+
+        let count = crate::query::parse_aggregate_query("COUNT(service=\"api\")").unwrap();
+        assert_eq!(executor.execute_aggregate(count).await.unwrap(), AggregateResult::Count(2));
+
+        let grouped = crate::query::parse_aggregate_query("COUNT(*) GROUP BY service").unwrap();
+        let expected = BTreeMap::from([("api".to_string(), 2), ("db".to_string(), 1)]);
+        assert_eq!(
+            executor.execute_aggregate(grouped).await.unwrap(),
+            AggregateResult::Groups(expected)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_resource_attribute_filter() {
+        use crate::core::SpanId;
+        use std::time::{Duration, SystemTime};
+
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(1000)));
+        for (i, environment) in ["prod", "staging", "prod"].into_iter().enumerate() {
+            let span = Span::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+                .service_name(ServiceName::new("api".to_string()).unwrap())
+                .operation_name("handle")
+                .start_time(SystemTime::now())
+                .duration(Duration::from_millis(5))
+                .attribute("http.status_code", if i == 2 { "500" } else { "200" })
+                .resource_attribute("deployment.environment", environment)
+                .build()
+                .unwrap();
+            storage.read().await.store_span(span).await.unwrap();
+        }
+
+        let executor = QueryExecutor::new(storage);
+        let run = |query: &str| {
+            let query = crate::query::parse_query(query).unwrap();
+            let executor = &executor;
+            async move {
+                let mut ids = executor.execute(query, Some(10)).await.unwrap().trace_ids;
+                ids.sort();
+                ids
+            }
+        };
+
+        let prod = run("resource.deployment.environment = \"prod\"").await;
+        assert_eq!(prod, vec![format!("{:032x}", 1), format!("{:032x}", 3)]);
+        let failing =
+            run("resource.deployment.environment = \"prod\" && http.status_code >= 500").await;
+        assert_eq!(failing, vec![format!("{:032x}", 3)]);
+        // Resource attributes are not span attributes
+        assert!(run("deployment.environment = \"prod\"").await.is_empty());
+    }
 }
@@ -0,0 +1,281 @@
+//! Persisted search/query history behind the GUI search box.
+//!
+//! Keeps the last [`MAX_HISTORY_ENTRIES`] distinct inputs, most recent first,
+//! as a JSON array in the state directory. Writes are debounced and a corrupt
+//! or unreadable file starts an empty history instead of failing.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Maximum number of remembered entries.
+pub const MAX_HISTORY_ENTRIES: usize = 100;
+
+/// Minimum time between two writes of the history file.
+pub const HISTORY_WRITE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+const HISTORY_FILE_NAME: &str = "search_history.json";
+
+/// Default history file: `<state dir>/urpo/search_history.json`.
+pub fn default_history_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("urpo").join(HISTORY_FILE_NAME))
+}
+
+/// Search and query history, most recent first.
+#[derive(Debug)]
+pub struct SearchHistory {
+    entries: VecDeque<String>,
+    path: Option<PathBuf>,
+    dirty: bool,
+    last_write: Option<Instant>,
+}
+
+impl SearchHistory {
+    /// History kept in memory only.
+    pub fn in_memory() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            path: None,
+            dirty: false,
+            last_write: None,
+        }
+    }
+
+    /// Load history from `path`, starting empty if it is missing or corrupt.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<Vec<String>>(&bytes) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("Ignoring corrupt search history {}: {}", path.display(), e);
+                    Vec::new()
+                },
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Cannot read search history {}: {}", path.display(), e);
+                Vec::new()
+            },
+        };
+
+        let mut history = Self::in_memory();
+        history.path = Some(path);
+        // Re-apply dedup and the size cap in case the file was edited by hand
+        for entry in entries.into_iter().rev() {
+            history.push(&entry);
+        }
+        history.dirty = false;
+        history
+    }
+
+    /// File the history is persisted to.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Entries, most recent first.
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the history is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry `index` steps back from the most recent (Up/Down navigation).
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Remember an input and persist it once the debounce interval has passed.
+    pub fn record(&mut self, input: &str) {
+        if self.push(input) {
+            self.flush_if_due();
+        }
+    }
+
+    fn push(&mut self, input: &str) -> bool {
+        let input = input.trim();
+        if input.is_empty() {
+            return false;
+        }
+        if self.entries.front().map(String::as_str) == Some(input) {
+            return false;
+        }
+        self.entries.retain(|entry| entry != input);
+        self.entries.push_front(input.to_string());
+        self.entries.truncate(MAX_HISTORY_ENTRIES);
+        self.dirty = true;
+        true
+    }
+
+    /// Entries matching `query`, best match first, ties broken by recency.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&str> {
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(recency, entry)| {
+                fuzzy_score(query, entry).map(|score| (score, recency, entry.as_str()))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, entry)| entry)
+            .collect()
+    }
+
+    /// Write pending changes if the last write is older than the debounce interval.
+    pub fn flush_if_due(&mut self) {
+        let due = self
+            .last_write
+            .map_or(true, |at| at.elapsed() >= HISTORY_WRITE_DEBOUNCE);
+        if self.dirty && due {
+            self.flush();
+        }
+    }
+
+    /// Write pending changes now.
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let Some(ref path) = self.path else {
+            self.dirty = false;
+            return;
+        };
+        match write_atomically(path, &self.entries) {
+            Ok(()) => {
+                self.dirty = false;
+                self.last_write = Some(Instant::now());
+            },
+            Err(e) => tracing::warn!("Cannot write search history {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl Drop for SearchHistory {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn write_atomically(path: &Path, entries: &VecDeque<String>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(entries)?)?;
+    std::fs::rename(tmp, path)
+}
+
+/// fzf-style fuzzy match: `query` characters must appear in order in
+/// `candidate` (case-insensitive). Returns `None` without a match; higher
+/// scores mean consecutive runs, matches at word starts and short gaps.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    const MATCH: i64 = 16;
+    const CONSECUTIVE_BONUS: i64 = 16;
+    const BOUNDARY_BONUS: i64 = 8;
+    const GAP_PENALTY: i64 = 1;
+
+    let query: Vec<char> = query.trim().chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    let mut prev: Option<char> = None;
+    for (i, c) in candidate.chars().enumerate() {
+        if next < query.len() && c.to_lowercase().eq(std::iter::once(query[next])) {
+            score += MATCH;
+            match last_match {
+                Some(last) if last + 1 == i => score += CONSECUTIVE_BONUS,
+                Some(last) => score -= GAP_PENALTY * (i - last - 1) as i64,
+                None => score -= GAP_PENALTY * i as i64,
+            }
+            if prev.map_or(true, |p| !p.is_alphanumeric()) {
+                score += BOUNDARY_BONUS;
+            }
+            last_match = Some(i);
+            next += 1;
+        }
+        prev = Some(c);
+    }
+
+    (next == query.len()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_ranking() {
+        assert_eq!(fuzzy_score("xyz", "service=api"), None);
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("SVC", "service").is_some());
+        assert!(fuzzy_score("cvs", "service").is_none());
+
+        // Consecutive beats scattered, word starts beat mid-word
+        let exact = fuzzy_score("error", "status = error").unwrap();
+        let scattered = fuzzy_score("error", "every rare order").unwrap();
+        assert!(exact > scattered);
+        let boundary = fuzzy_score("dur", "span.duration > 1s").unwrap();
+        let inner = fuzzy_score("dur", "procedure").unwrap();
+        assert!(boundary > inner);
+    }
+
+    #[test]
+    fn test_history_dedup_cap_and_search() {
+        let mut history = SearchHistory::in_memory();
+        for i in 0..(MAX_HISTORY_ENTRIES + 10) {
+            history.record(&format!("query {}", i));
+        }
+        history.record("  ");
+        assert_eq!(history.len(), MAX_HISTORY_ENTRIES);
+
+        history.record("service = checkout");
+        history.record("duration > 500ms");
+        history.record("service = checkout");
+        assert_eq!(history.get(0), Some("service = checkout"));
+        assert_eq!(history.get(1), Some("duration > 500ms"));
+        assert_eq!(history.entries().filter(|e| e.contains("checkout")).count(), 1);
+
+        assert_eq!(history.search("chk", 5), vec!["service = checkout"]);
+        // Equal scores fall back to recency
+        assert_eq!(history.search("query 10", 2), vec!["query 109", "query 108"]);
+    }
+
+    #[test]
+    fn test_history_persists_and_tolerates_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join(HISTORY_FILE_NAME);
+
+        let mut history = SearchHistory::load(&path);
+        assert!(history.is_empty());
+        history.record("first");
+        // Debounced: the second write waits for the interval or an explicit flush
+        history.record("second");
+        drop(history);
+
+        let history = SearchHistory::load(&path);
+        assert_eq!(history.entries().collect::<Vec<_>>(), vec!["second", "first"]);
+
+        std::fs::write(&path, b"{not json").unwrap();
+        let history = SearchHistory::load(&path);
+        assert!(history.is_empty());
+    }
+}
@@ -5,15 +5,18 @@
 
 pub mod ast;
 pub mod executor;
+pub mod history;
 pub mod parser;
 
 use crate::core::Result;
 use crate::storage::StorageBackend;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
-pub use ast::{LogicalOp, Operator, Query, QueryFilter, Value};
+pub use ast::{AggregateExpr, AggregateQuery, LogicalOp, Operator, Query, QueryFilter, Value};
 pub use executor::QueryExecutor;
-pub use parser::parse_query;
+pub use history::{fuzzy_score, SearchHistory};
+pub use parser::{is_aggregate_query, parse_aggregate_query, parse_query};
 
 /// High-level query API
 pub struct QueryEngine {
@@ -37,6 +40,12 @@ impl QueryEngine {
         self.executor.execute(query, limit).await
     }
 
+    /// Execute an aggregate query string such as `COUNT(*) GROUP BY service`
+    pub async fn execute_aggregate(&self, query_str: &str) -> Result<AggregateResult> {
+        let query = parse_aggregate_query(query_str)?;
+        self.executor.execute_aggregate(query).await
+    }
+
     /// Validate a query without executing it
     pub fn validate(&self, query_str: &str) -> Result<()> {
         parse_query(query_str)?;
@@ -57,6 +66,16 @@ pub struct QueryResult {
     pub limited: bool,
 }
 
+/// Aggregate query result
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateResult {
+    /// Number of matching traces
+    Count(usize),
+    /// Matching trace count per group value
+    Groups(BTreeMap<String, usize>),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,8 +5,8 @@ use crate::core::{Result, UrpoError};
 use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while1},
-    character::complete::{char, digit1, multispace0},
-    combinator::{map, recognize, value as nom_value},
+    character::complete::{char, digit1, multispace0, multispace1},
+    combinator::{map, opt, recognize, value as nom_value},
     multi::many0,
     sequence::{delimited, pair, preceded, tuple},
     IResult,
@@ -38,6 +38,52 @@ pub fn parse_query(input: &str) -> Result<Query> {
     }
 }
 
+/// Parse an aggregate query such as `COUNT(*) GROUP BY service`
+pub fn parse_aggregate_query(input: &str) -> Result<AggregateQuery> {
+    match aggregate_query(input.trim()) {
+        Ok((remaining, query)) => {
+            if !remaining.trim().is_empty() {
+                Err(UrpoError::Parse {
+                    message: format!("Unexpected input after aggregate: '{}'", remaining),
+                })
+            } else {
+                Ok(query)
+            }
+        },
+        Err(e) => Err(UrpoError::Parse {
+            message: format!("Failed to parse aggregate query: {}", e),
+        }),
+    }
+}
+
+/// Check whether a query string is an aggregate rather than a filter
+pub fn is_aggregate_query(input: &str) -> bool {
+    aggregate_prefix(input.trim()).is_ok()
+}
+
+/// Parse `COUNT(<filter or *>) [GROUP BY <field>]`
+fn aggregate_query(input: &str) -> IResult<&str, AggregateQuery> {
+    let (input, _) = aggregate_prefix(input)?;
+    let (input, filter) =
+        alt((nom_value(QueryFilter::All, preceded(multispace0, char('*'))), query_filter))(input)?;
+    let (input, _) = preceded(multispace0, char(')'))(input)?;
+    let (input, group_by) = opt(preceded(
+        tuple((multispace1, tag_no_case("group"), multispace1, tag_no_case("by"), multispace1)),
+        attribute_name,
+    ))(input)?;
+
+    let aggregate = match group_by {
+        Some(field) => AggregateExpr::CountGroupBy(field),
+        None => AggregateExpr::Count,
+    };
+    Ok((input, AggregateQuery { aggregate, filter }))
+}
+
+/// Parse the `COUNT(` prefix
+fn aggregate_prefix(input: &str) -> IResult<&str, &str> {
+    preceded(tag_no_case("count"), preceded(multispace0, tag("(")))(input)
+}
+
 /// Parse a query filter (the main expression)
 fn query_filter(input: &str) -> IResult<&str, QueryFilter> {
     logical_or(input)
@@ -279,4 +325,38 @@ mod tests {
             _ => panic!("Expected comparison filter"),
         }
     }
+
+    #[test]
+    fn test_parse_count() {
+        let query = parse_aggregate_query("COUNT(service=\"x\")").unwrap();
+        assert_eq!(query.aggregate, AggregateExpr::Count);
+        assert_eq!(
+            query.filter,
+            QueryFilter::Comparison {
+                field: Field::Service,
+                op: Operator::Eq,
+                value: Value::String("x".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_count_group_by() {
+        let query = parse_aggregate_query("COUNT(*) GROUP BY service").unwrap();
+        assert_eq!(query.aggregate, AggregateExpr::CountGroupBy("service".to_string()));
+        assert_eq!(query.filter, QueryFilter::All);
+
+        let query = parse_aggregate_query("count( status = error ) group by http.method").unwrap();
+        assert_eq!(query.aggregate, AggregateExpr::CountGroupBy("http.method".to_string()));
+        assert!(matches!(query.filter, QueryFilter::Comparison { .. }));
+    }
+
+    #[test]
+    fn test_parse_invalid_aggregate() {
+        assert!(is_aggregate_query("COUNT(*)"));
+        assert!(!is_aggregate_query("service = api"));
+        assert!(parse_aggregate_query("COUNT(*").is_err());
+        assert!(parse_aggregate_query("COUNT(*) GROUP BY").is_err());
+        assert!(parse_aggregate_query("COUNT(*) extra").is_err());
+    }
 }
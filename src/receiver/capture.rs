@@ -0,0 +1,593 @@
+//! Wire capture of raw OTLP trace requests for debugging SDK integrations.
+//!
+//! Capture is off until an admin starts it for a bounded window. While it is
+//! active each trace export request is kept with its raw payload (truncated
+//! to a size limit), its headers with credentials redacted, and the outcome
+//! of every span under the receiver's span limits and filters. Sampling is
+//! not reflected. Capture switches itself off when the window elapses or the
+//! request limit is reached.
+
+use super::{convert_otel_span, extract_attribute_value, extract_resource_semantics};
+use crate::core::{system_clock, AttributeFilter, ServiceFilter, SharedClock, SpanLimits};
+use axum::http::HeaderMap;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Capture window used when none is given.
+pub const DEFAULT_CAPTURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest capture window an admin can request.
+pub const MAX_CAPTURE_WINDOW: Duration = Duration::from_secs(600);
+
+/// Requests kept per capture session.
+pub const DEFAULT_MAX_CAPTURES: usize = 100;
+
+/// Payload bytes kept per request.
+pub const DEFAULT_MAX_CAPTURE_PAYLOAD_BYTES: usize = 256 * 1024;
+
+/// Replacement for redacted header values.
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers whose names contain any of these carry credentials.
+const SENSITIVE_HEADER_HINTS: &[&str] =
+    &["authorization", "cookie", "token", "secret", "password", "api-key", "apikey"];
+
+/// How a captured payload is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadEncoding {
+    /// UTF-8 text (OTLP/JSON)
+    Utf8,
+    /// Lowercase hex (protobuf and anything else)
+    Hex,
+}
+
+/// Conversion outcome of one span in a captured request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpanOutcome {
+    /// Service from the resource attributes
+    pub service_name: String,
+    /// Span name
+    pub name: String,
+    /// Trace ID as sent, hex encoded
+    pub trace_id: String,
+    /// Span ID as sent, hex encoded
+    pub span_id: String,
+    /// Why the span was rejected or dropped, `None` if it was accepted
+    pub error: Option<String>,
+    /// Attributes dropped by the span limits, or already by the SDK
+    pub dropped_attributes: usize,
+    /// Attributes whose value was truncated to the span limits
+    pub truncated_attributes: usize,
+    /// Keys of attributes removed by the attribute filter
+    pub filtered_attributes: Vec<String>,
+}
+
+/// Receiver settings a span goes through, so captured outcomes match what
+/// the receiver does with it.
+#[derive(Debug, Clone, Copy)]
+pub struct SpanPipeline<'a> {
+    /// Limits spans are converted with
+    pub limits: &'a SpanLimits,
+    /// Attribute filter applied while converting
+    pub attribute_filter: Option<&'a AttributeFilter>,
+    /// Service filter applied before sampling
+    pub service_filter: Option<&'a ServiceFilter>,
+}
+
+/// A captured export request.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedRequest {
+    /// Sequence number within the capture session
+    pub id: u64,
+    /// When the request was received
+    pub captured_at: SystemTime,
+    /// Transport, e.g. `otlp/grpc` or `otlp/http`
+    pub protocol: String,
+    /// Request headers with credentials redacted
+    pub headers: BTreeMap<String, String>,
+    /// Size of the full payload in bytes
+    pub payload_bytes: usize,
+    /// True if `payload` was cut at the size limit
+    pub payload_truncated: bool,
+    /// Encoding of `payload`
+    pub payload_encoding: PayloadEncoding,
+    /// Raw payload, possibly truncated
+    pub payload: String,
+    /// Why the payload could not be decoded, if it could not
+    pub decode_error: Option<String>,
+    /// Per-span conversion outcome
+    pub spans: Vec<SpanOutcome>,
+}
+
+/// Current state of the capture session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaptureStatus {
+    /// True while requests are being captured
+    pub active: bool,
+    /// When the current window ends
+    pub expires_at: Option<SystemTime>,
+    /// Requests captured in the current or last session
+    pub captured: usize,
+    /// Request limit per session
+    pub max_captures: usize,
+    /// Directory captures are also written to
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct CaptureState {
+    until: Option<Instant>,
+    expires_at: Option<SystemTime>,
+    next_id: u64,
+    captures: VecDeque<CapturedRequest>,
+}
+
+/// Admin-toggled, time-limited capture of raw OTLP requests.
+#[derive(Debug)]
+pub struct WireCapture {
+    max_captures: usize,
+    max_payload_bytes: usize,
+    directory: Option<PathBuf>,
+    state: Mutex<CaptureState>,
+    clock: SharedClock,
+}
+
+impl WireCapture {
+    /// Create an inactive capture with default limits.
+    pub fn new() -> Self {
+        Self {
+            max_captures: DEFAULT_MAX_CAPTURES,
+            max_payload_bytes: DEFAULT_MAX_CAPTURE_PAYLOAD_BYTES,
+            directory: None,
+            state: Mutex::new(CaptureState::default()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Time capture windows by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Limit the number of requests kept per session.
+    pub fn with_max_captures(mut self, max_captures: usize) -> Self {
+        self.max_captures = max_captures;
+        self
+    }
+
+    /// Limit the payload bytes kept per request.
+    pub fn with_max_payload_bytes(mut self, max_payload_bytes: usize) -> Self {
+        self.max_payload_bytes = max_payload_bytes;
+        self
+    }
+
+    /// Also write each capture as JSON into `directory`.
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Start a new session for `window` (capped at [`MAX_CAPTURE_WINDOW`]),
+    /// discarding earlier captures.
+    pub fn start(&self, window: Duration) -> CaptureStatus {
+        let window = window.min(MAX_CAPTURE_WINDOW);
+        {
+            let mut state = self.state.lock();
+            state.until = Some(self.clock.instant() + window);
+            state.expires_at = Some(self.clock.now() + window);
+            state.next_id = 0;
+            state.captures.clear();
+        }
+        tracing::info!("Wire capture enabled for {:?}", window);
+        self.status()
+    }
+
+    /// End the current session, keeping its captures.
+    pub fn stop(&self) -> CaptureStatus {
+        {
+            let mut state = self.state.lock();
+            state.until = None;
+            state.expires_at = None;
+        }
+        self.status()
+    }
+
+    /// True while requests are being captured.
+    pub fn is_active(&self) -> bool {
+        self.active(&mut self.state.lock())
+    }
+
+    /// Check the window, disabling capture once it has elapsed.
+    fn active(&self, state: &mut CaptureState) -> bool {
+        match state.until {
+            Some(until) if self.clock.instant() < until => true,
+            Some(_) => {
+                state.until = None;
+                state.expires_at = None;
+                tracing::info!("Wire capture window elapsed, capture disabled");
+                false
+            },
+            None => false,
+        }
+    }
+
+    /// Current session state.
+    pub fn status(&self) -> CaptureStatus {
+        let mut state = self.state.lock();
+        let active = self.active(&mut state);
+        CaptureStatus {
+            active,
+            expires_at: state.expires_at,
+            captured: state.captures.len(),
+            max_captures: self.max_captures,
+            directory: self.directory.clone(),
+        }
+    }
+
+    /// Captured requests, oldest first.
+    pub fn captures(&self) -> Vec<CapturedRequest> {
+        self.state.lock().captures.iter().cloned().collect()
+    }
+
+    /// Record a trace export request if capture is active.
+    ///
+    /// `request` is the decoded payload, or the reason it could not be decoded.
+    pub fn record(
+        &self,
+        protocol: &str,
+        headers: &HeaderMap,
+        payload: &[u8],
+        request: std::result::Result<&ExportTraceServiceRequest, &str>,
+        pipeline: SpanPipeline<'_>,
+    ) {
+        if !self.is_active() {
+            return;
+        }
+
+        let mut captured = self.capture(protocol, headers, payload, request, pipeline);
+
+        let mut state = self.state.lock();
+        if !self.active(&mut state) {
+            return;
+        }
+        captured.id = state.next_id;
+        state.next_id += 1;
+        if let Some(ref dir) = self.directory {
+            if let Err(e) = write_capture(dir, &captured) {
+                tracing::warn!("Cannot write wire capture to {}: {}", dir.display(), e);
+            }
+        }
+        state.captures.push_back(captured);
+        if state.captures.len() >= self.max_captures {
+            state.until = None;
+            state.expires_at = None;
+            tracing::info!("Wire capture limit of {} requests reached", self.max_captures);
+        }
+    }
+
+    fn capture(
+        &self,
+        protocol: &str,
+        headers: &HeaderMap,
+        payload: &[u8],
+        request: std::result::Result<&ExportTraceServiceRequest, &str>,
+        pipeline: SpanPipeline<'_>,
+    ) -> CapturedRequest {
+        let kept = &payload[..payload.len().min(self.max_payload_bytes)];
+        let (payload_encoding, rendered) = match std::str::from_utf8(kept) {
+            Ok(text) if is_json(headers) => (PayloadEncoding::Utf8, text.to_string()),
+            _ => (PayloadEncoding::Hex, hex::encode(kept)),
+        };
+
+        let (decode_error, spans) = match request {
+            Ok(request) => (None, span_outcomes(request, pipeline)),
+            Err(e) => (Some(e.to_string()), Vec::new()),
+        };
+
+        CapturedRequest {
+            id: 0,
+            captured_at: self.clock.now(),
+            protocol: protocol.to_string(),
+            headers: redacted_headers(headers),
+            payload_bytes: payload.len(),
+            payload_truncated: kept.len() < payload.len(),
+            payload_encoding,
+            payload: rendered,
+            decode_error,
+            spans,
+        }
+    }
+}
+
+impl Default for WireCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.contains("json"))
+}
+
+/// Header names and values with credentials replaced.
+pub fn redacted_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str().to_string();
+            let value = if is_sensitive_header(&name) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_HEADER_HINTS
+        .iter()
+        .any(|hint| name.contains(hint))
+}
+
+/// Run every span through `pipeline` again to report what the receiver made
+/// of it.
+fn span_outcomes(
+    request: &ExportTraceServiceRequest,
+    pipeline: SpanPipeline<'_>,
+) -> Vec<SpanOutcome> {
+    let mut outcomes = Vec::new();
+    for resource_spans in &request.resource_spans {
+        let resource = resource_spans.resource.clone().unwrap_or_default();
+        let service_name = extract_resource_semantics(&resource).service_name;
+        let excluded = pipeline
+            .service_filter
+            .map_or(false, |filter| !filter.allows(&service_name));
+        for otel_span in resource_spans.scope_spans.iter().flat_map(|s| &s.spans) {
+            let error =
+                match convert_otel_span(otel_span.clone(), service_name.clone(), pipeline.limits) {
+                    Err(e) => Some(e.to_string()),
+                    Ok(_) if excluded => Some("Service excluded by the service filter".to_string()),
+                    Ok(_) => None,
+                };
+            let mut outcome = SpanOutcome {
+                service_name: service_name.clone(),
+                name: otel_span.name.clone(),
+                trace_id: hex::encode(&otel_span.trace_id),
+                span_id: hex::encode(&otel_span.span_id),
+                error,
+                dropped_attributes: otel_span.dropped_attributes_count as usize,
+                truncated_attributes: 0,
+                filtered_attributes: Vec::new(),
+            };
+            count_attribute_changes(otel_span, pipeline, &mut outcome);
+            outcomes.push(outcome);
+        }
+    }
+    outcomes
+}
+
+/// Tally what the span limits and attribute filter do to the span's
+/// attributes, in the same order as the receiver applies them.
+fn count_attribute_changes(
+    otel_span: &opentelemetry_proto::tonic::trace::v1::Span,
+    pipeline: SpanPipeline<'_>,
+    outcome: &mut SpanOutcome,
+) {
+    let limits = pipeline.limits;
+    let mut kept = 0;
+    for attr in &otel_span.attributes {
+        if kept >= limits.max_attributes || attr.key.len() > limits.max_key_length {
+            outcome.dropped_attributes += 1;
+            continue;
+        }
+        let Some(value) = extract_attribute_value(&attr.value) else {
+            continue;
+        };
+        kept += 1;
+        if value.chars().count() > limits.max_value_length {
+            outcome.truncated_attributes += 1;
+        }
+        if pipeline
+            .attribute_filter
+            .map_or(false, |filter| !filter.allows(&attr.key))
+        {
+            outcome.filtered_attributes.push(attr.key.clone());
+        }
+    }
+}
+
+fn write_capture(dir: &Path, captured: &CapturedRequest) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("capture-{:06}.json", captured.id));
+    std::fs::write(path, serde_json::to_vec_pretty(captured)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Clock, MockClock};
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+    fn request() -> ExportTraceServiceRequest {
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        Span {
+                            trace_id: vec![0xab; 16],
+                            span_id: vec![1; 8],
+                            name: "ok".to_string(),
+                            start_time_unix_nano: 1_700_000_000_000_000_000,
+                            end_time_unix_nano: 1_700_000_000_001_000_000,
+                            ..Default::default()
+                        },
+                        Span {
+                            trace_id: vec![0; 16],
+                            span_id: vec![2; 8],
+                            name: "bad".to_string(),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn defaults(limits: &SpanLimits) -> SpanPipeline<'_> {
+        SpanPipeline {
+            limits,
+            attribute_filter: None,
+            service_filter: None,
+        }
+    }
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        headers.insert("authorization", "Bearer s3cr3t".parse().unwrap());
+        headers.insert("x-honeycomb-team-token", "abc123".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_capture_redacts_headers_and_reports_span_outcomes() {
+        let limits = SpanLimits::default();
+        let pipeline = defaults(&limits);
+        let capture = WireCapture::new().with_max_payload_bytes(4);
+        capture.record(
+            "otlp/http",
+            &headers(),
+            b"{\"resourceSpans\":[]}",
+            Ok(&request()),
+            pipeline,
+        );
+        assert!(capture.captures().is_empty(), "inactive capture must not record");
+
+        capture.start(DEFAULT_CAPTURE_WINDOW);
+        capture.record(
+            "otlp/http",
+            &headers(),
+            b"{\"resourceSpans\":[]}",
+            Ok(&request()),
+            pipeline,
+        );
+
+        let captures = capture.captures();
+        assert_eq!(captures.len(), 1);
+        let captured = &captures[0];
+        assert_eq!(captured.headers["authorization"], REDACTED);
+        assert_eq!(captured.headers["x-honeycomb-team-token"], REDACTED);
+        assert_eq!(captured.headers["content-type"], "application/json");
+        assert!(!serde_json::to_string(captured).unwrap().contains("s3cr3t"));
+
+        assert_eq!(captured.payload, "{\"re");
+        assert!(captured.payload_truncated);
+        assert_eq!(captured.payload_encoding, PayloadEncoding::Utf8);
+
+        assert_eq!(captured.spans.len(), 2);
+        assert_eq!(captured.spans[0].error, None);
+        assert_eq!(captured.spans[1].name, "bad");
+        assert!(captured.spans[1].error.is_some());
+    }
+
+    #[test]
+    fn test_capture_expires_and_stops_at_limit() {
+        let limits = SpanLimits::default();
+        let pipeline = defaults(&limits);
+        let clock = MockClock::default();
+        let capture = WireCapture::new()
+            .with_max_captures(2)
+            .with_clock(clock.shared());
+        capture.start(Duration::from_secs(30));
+        clock.advance(Duration::from_secs(29));
+        assert!(capture.is_active());
+        clock.advance(Duration::from_secs(1));
+        assert!(!capture.is_active());
+        capture.record("otlp/grpc", &HeaderMap::new(), &[1, 2], Err("bad frame"), pipeline);
+        assert_eq!(capture.status().captured, 0);
+
+        let status = capture.start(Duration::from_secs(3_600));
+        assert!(status.active);
+        assert_eq!(status.expires_at, Some(clock.now() + MAX_CAPTURE_WINDOW));
+        for _ in 0..3 {
+            capture.record("otlp/grpc", &HeaderMap::new(), &[1, 2], Err("bad frame"), pipeline);
+        }
+        let status = capture.status();
+        assert!(!status.active);
+        assert_eq!(status.captured, 2);
+        let captures = capture.captures();
+        assert_eq!(captures[1].id, 1);
+        assert_eq!(captures[0].payload, "0102");
+        assert_eq!(captures[0].decode_error.as_deref(), Some("bad frame"));
+    }
+
+    #[test]
+    fn test_span_outcomes_follow_receiver_limits_and_filters() {
+        use crate::core::Glob;
+        use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+        use opentelemetry_proto::tonic::resource::v1::Resource;
+
+        let attribute = |key: &str, value: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        };
+        let mut request = request();
+        request.resource_spans[0].resource = Some(Resource {
+            attributes: vec![attribute("service.name", "billing")],
+            ..Default::default()
+        });
+        let span = &mut request.resource_spans[0].scope_spans[0].spans[0];
+        span.attributes = vec![
+            attribute("user.email", "a@example.com"),
+            attribute("db.statement", "SELECT * FROM invoices"),
+            attribute("http.route", "/pay"),
+        ];
+        span.dropped_attributes_count = 1;
+
+        let limits = SpanLimits {
+            max_attributes: 2,
+            max_value_length: 8,
+            ..SpanLimits::default()
+        };
+        let attributes = AttributeFilter::new(Vec::new(), vec![Glob::new("user.*")]);
+        let services = ServiceFilter::new(Vec::new(), vec![Glob::new("billing")]);
+        let pipeline = SpanPipeline {
+            limits: &limits,
+            attribute_filter: Some(&attributes),
+            service_filter: None,
+        };
+
+        let outcomes = span_outcomes(&request, pipeline);
+        assert_eq!(outcomes[0].error, None);
+        assert_eq!(outcomes[0].dropped_attributes, 2);
+        assert_eq!(outcomes[0].truncated_attributes, 2);
+        assert_eq!(outcomes[0].filtered_attributes, vec!["user.email".to_string()]);
+
+        let excluding = SpanPipeline {
+            service_filter: Some(&services),
+            ..pipeline
+        };
+        let outcomes = span_outcomes(&request, excluding);
+        assert!(outcomes[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("service filter"));
+    }
+}
@@ -0,0 +1,215 @@
+//! gRPC-Web ingestion of OTLP traces on the HTTP port.
+//!
+//! Browser exporters cannot open HTTP/2 gRPC streams, so gRPC-Web carries the
+//! same `TraceService/Export` call over plain HTTP in length-prefixed frames:
+//!
+//! ```text
+//! flags (u8) | length (u32 big-endian) | message
+//! ```
+//!
+//! The request holds one data frame (flags `0x00`). The response holds a data
+//! frame with the `ExportTraceServiceResponse`, then a trailer frame (flags
+//! `0x80`) with `grpc-status` and `grpc-message` as HTTP/1 header lines. With
+//! `application/grpc-web-text` both bodies are base64 encoded.
+
+use super::http::{process_export_request, HttpError, HttpOtelState};
+use super::{rate_limited_status, storage_full_status};
+use crate::core::UrpoError;
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use prost::Message;
+use std::net::{IpAddr, SocketAddr};
+use tonic::{Code, Status};
+
+/// Path browser gRPC-Web exporters post trace exports to.
+pub const GRPC_WEB_TRACES_PATH: &str =
+    "/opentelemetry.proto.collector.trace.v1.TraceService/Export";
+
+const FRAME_HEADER_LEN: usize = 5;
+const COMPRESSED_FLAG: u8 = 0x01;
+const TRAILER_FLAG: u8 = 0x80;
+
+/// Extract the message of the first data frame in a gRPC-Web request body.
+pub fn decode_data_frame(body: &[u8]) -> std::result::Result<&[u8], Status> {
+    if body.len() < FRAME_HEADER_LEN {
+        return Err(Status::invalid_argument("Truncated gRPC-Web frame header"));
+    }
+    let flags = body[0];
+    if flags & TRAILER_FLAG != 0 {
+        return Err(Status::invalid_argument("Expected a gRPC-Web data frame"));
+    }
+    if flags & COMPRESSED_FLAG != 0 {
+        return Err(Status::unimplemented("Compressed gRPC-Web messages are not supported"));
+    }
+    let len = u32::from_be_bytes(body[1..FRAME_HEADER_LEN].try_into().unwrap()) as usize;
+    body.get(FRAME_HEADER_LEN..FRAME_HEADER_LEN + len)
+        .ok_or_else(|| Status::invalid_argument("Truncated gRPC-Web message"))
+}
+
+/// Append one gRPC-Web frame to `out`.
+pub fn encode_frame(out: &mut Vec<u8>, flags: u8, payload: &[u8]) {
+    out.push(flags);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// Handle a gRPC-Web `TraceService/Export` call.
+pub(super) async fn handle_grpc_web_traces(
+    State(state): State<HttpOtelState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !content_type.starts_with("application/grpc-web") {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Expected application/grpc-web or application/grpc-web-text",
+        )
+            .into_response();
+    }
+    let text = content_type.starts_with("application/grpc-web-text");
+
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let result = export(&state, peer, &headers, &body, text).await;
+    grpc_web_response(text, result)
+}
+
+async fn export(
+    state: &HttpOtelState,
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    body: &[u8],
+    text: bool,
+) -> std::result::Result<ExportTraceServiceResponse, Status> {
+    let decoded;
+    let framed = if text {
+        let encoded: Vec<u8> = body
+            .iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| Status::invalid_argument(format!("Invalid base64 body: {}", e)))?;
+        decoded.as_slice()
+    } else {
+        body
+    };
+    let message = decode_data_frame(framed)?;
+
+    let request = ExportTraceServiceRequest::decode(message)
+        .map_err(|e| Status::invalid_argument(format!("Failed to parse protobuf: {}", e)));
+    if let Some(capture) = state.receiver.wire_capture() {
+        let pipeline = state.receiver.span_pipeline();
+        match request {
+            Ok(ref request) => {
+                capture.record("otlp/grpc-web", headers, message, Ok(request), pipeline)
+            },
+            Err(ref e) => {
+                capture.record("otlp/grpc-web", headers, message, Err(e.message()), pipeline)
+            },
+        }
+    }
+
+    let (spans, mut rejected) =
+        process_export_request(request?, state.receiver.span_limits()).map_err(status_from_http)?;
+    match state.receiver.process_export(spans, peer).await {
+        Ok(storage_rejected) => rejected.merge(storage_rejected),
+        Err(UrpoError::StorageFull { rejected: full }) => {
+            return Err(storage_full_status(full + rejected.count));
+        },
+        Err(UrpoError::RateLimited {
+            rejected: limited,
+            retry_after,
+        }) => {
+            return Err(rate_limited_status(limited + rejected.count, retry_after));
+        },
+        Err(e) => return Err(status_from_http(HttpError::from_process_error(e))),
+    }
+
+    Ok(ExportTraceServiceResponse {
+        partial_success: rejected.partial_success(),
+    })
+}
+
+fn status_from_http(error: HttpError) -> Status {
+    match error {
+        HttpError::BadRequest(msg) => Status::invalid_argument(msg),
+        HttpError::NotFound(msg) => Status::unimplemented(msg),
+        HttpError::Internal(msg) => Status::internal(msg),
+        HttpError::TooManyRequests { message, .. } => Status::resource_exhausted(message),
+    }
+}
+
+/// Build the framed response; errors are reported in the trailer frame.
+fn grpc_web_response(
+    text: bool,
+    result: std::result::Result<ExportTraceServiceResponse, Status>,
+) -> Response {
+    let mut body = Vec::new();
+    let status = match result {
+        Ok(response) => {
+            encode_frame(&mut body, 0, &response.encode_to_vec());
+            Status::new(Code::Ok, "")
+        },
+        Err(status) => status,
+    };
+
+    let mut trailer_map = HeaderMap::new();
+    if let Err(invalid) = status.add_header(&mut trailer_map) {
+        trailer_map.clear();
+        let _ = invalid.add_header(&mut trailer_map);
+    }
+    let mut trailers = Vec::new();
+    for (name, value) in &trailer_map {
+        trailers.extend_from_slice(name.as_str().as_bytes());
+        trailers.push(b':');
+        trailers.extend_from_slice(value.as_bytes());
+        trailers.extend_from_slice(b"\r\n");
+    }
+    encode_frame(&mut body, TRAILER_FLAG, &trailers);
+
+    let (content_type, body) = if text {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&body);
+        ("application/grpc-web-text+proto", encoded.into_bytes())
+    } else {
+        ("application/grpc-web+proto", body)
+    };
+    let mut response = (StatusCode::OK, body).into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut body = Vec::new();
+        encode_frame(&mut body, 0, b"hello");
+        encode_frame(&mut body, TRAILER_FLAG, b"grpc-status:0\r\n");
+        assert_eq!(&body[..5], &[0, 0, 0, 0, 5]);
+        assert_eq!(decode_data_frame(&body).unwrap(), b"hello");
+
+        assert_eq!(decode_data_frame(&body[..7]).unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(decode_data_frame(&body[10..]).unwrap_err().code(), Code::InvalidArgument);
+        let mut compressed = body.clone();
+        compressed[0] = COMPRESSED_FLAG;
+        assert_eq!(decode_data_frame(&compressed).unwrap_err().code(), Code::Unimplemented);
+    }
+}
@@ -0,0 +1,333 @@
+//! Health reporting for load balancer and Kubernetes probes.
+//!
+//! The receiver serves the standard `grpc.health.v1.Health` service from
+//! `tonic-health` on the gRPC port and `/healthz` on the HTTP port. Both
+//! report [`ReceiverHealth`], which is refreshed from the storage and monitor
+//! health: SERVING while storage is healthy or degraded, NOT_SERVING once it
+//! is critical or offline, or the monitor reports the system critical.
+//!
+//! gRPC health checks answer for the whole server (the empty service name)
+//! and for each OTLP service the gRPC server runs.
+
+use crate::monitoring::SystemHealth;
+use crate::storage::StorageHealth;
+use tokio::sync::watch;
+use tonic::server::NamedService;
+use tonic::service::Routes;
+use tonic_health::server::HealthReporter;
+pub use tonic_health::ServingStatus;
+
+/// Serving status of the receiver, shared by the gRPC and HTTP probes.
+#[derive(Debug)]
+pub struct ReceiverHealth {
+    status: watch::Sender<ServingStatus>,
+    /// Monitor health the status was last derived from
+    system: watch::Sender<SystemHealth>,
+    grpc: tokio::sync::Mutex<GrpcHealth>,
+    /// `grpc.health.v1.Health` server answering from `grpc`
+    routes: Routes,
+}
+
+/// Statuses reported to gRPC health checks.
+#[derive(Debug)]
+struct GrpcHealth {
+    reporter: HealthReporter,
+    /// Services reported besides the whole server
+    services: Vec<&'static str>,
+}
+
+impl Default for ReceiverHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReceiverHealth {
+    /// Start out serving.
+    pub fn new() -> Self {
+        let (reporter, server) = tonic_health::server::health_reporter();
+        Self {
+            status: watch::Sender::new(ServingStatus::Serving),
+            system: watch::Sender::new(SystemHealth::Healthy),
+            grpc: tokio::sync::Mutex::new(GrpcHealth {
+                reporter,
+                services: Vec::new(),
+            }),
+            routes: Routes::new(server),
+        }
+    }
+
+    /// Current status.
+    pub fn status(&self) -> ServingStatus {
+        *self.status.borrow()
+    }
+
+    /// Receive the status and every change of it.
+    pub fn subscribe(&self) -> watch::Receiver<ServingStatus> {
+        self.status.subscribe()
+    }
+
+    /// Receive the monitor health and every change of it.
+    pub fn subscribe_system(&self) -> watch::Receiver<SystemHealth> {
+        self.system.subscribe()
+    }
+
+    /// The `grpc.health.v1.Health` service, to add to a gRPC server.
+    pub fn grpc_routes(&self) -> Routes {
+        self.routes.clone()
+    }
+
+    /// Answer gRPC health checks for service `S` from now on.
+    pub async fn register<S: NamedService>(&self) {
+        let mut grpc = self.grpc.lock().await;
+        if !grpc.services.contains(&S::NAME) {
+            grpc.services.push(S::NAME);
+        }
+        match self.status() {
+            ServingStatus::Serving => grpc.reporter.set_serving::<S>().await,
+            _ => grpc.reporter.set_not_serving::<S>().await,
+        }
+    }
+
+    /// Derive the status from storage and monitor health, logging changes.
+    pub async fn update(&self, storage: StorageHealth, system: SystemHealth) -> ServingStatus {
+        let status = match (&storage, &system) {
+            (StorageHealth::Critical | StorageHealth::Offline, _) | (_, SystemHealth::Critical) => {
+                ServingStatus::NotServing
+            },
+            _ => ServingStatus::Serving,
+        };
+        self.system.send_if_modified(|current| {
+            let changed = *current != system;
+            current.clone_from(&system);
+            changed
+        });
+        let changed = self.status.send_if_modified(|current| {
+            let changed = *current != status;
+            *current = status;
+            changed
+        });
+        if changed {
+            tracing::warn!(
+                "Receiver health is now {:?} (storage {:?}, system {:?})",
+                status,
+                storage,
+                system
+            );
+            // Only changes are reported: each report wakes every gRPC watcher
+            let mut grpc = self.grpc.lock().await;
+            let GrpcHealth { reporter, services } = &mut *grpc;
+            // The latest status, should updates race
+            let status = self.status();
+            for service in std::iter::once("").chain(services.iter().copied()) {
+                reporter.set_service_status(service, status).await;
+            }
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, Span, SpanId, TraceId};
+    use crate::receiver::{http::create_http_router, OtelReceiver};
+    use crate::storage::{CleanupConfig, InMemoryStorage, StorageBackend};
+    use axum::body::Body;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tonic::codegen::http;
+    use tonic::transport::Channel;
+    use tonic::{Code, Streaming};
+    use tonic_health::pb::health_check_response::ServingStatus as WireStatus;
+    use tonic_health::pb::health_client::HealthClient;
+    use tonic_health::pb::{HealthCheckRequest, HealthCheckResponse};
+    use tower::ServiceExt;
+
+    const TRACE_SERVICE: &str = "opentelemetry.proto.collector.trace.v1.TraceService";
+    const METRICS_SERVICE: &str = "opentelemetry.proto.collector.metrics.v1.MetricsService";
+    const LOGS_SERVICE: &str = "opentelemetry.proto.collector.logs.v1.LogsService";
+
+    /// Serve `receiver` over gRPC on a free port and connect a health client.
+    async fn connect(receiver: &Arc<OtelReceiver>) -> HealthClient<Channel> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        tokio::spawn(Arc::clone(receiver).start_grpc(addr));
+
+        for _ in 0..50 {
+            let endpoint = Channel::from_shared(format!("http://{}", addr)).unwrap();
+            if let Ok(channel) = endpoint.connect().await {
+                let mut client = HealthClient::new(channel);
+                // Services are registered as the server starts
+                if check(&mut client, TRACE_SERVICE).await.is_ok() {
+                    return client;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("gRPC receiver did not start");
+    }
+
+    async fn check(
+        client: &mut HealthClient<Channel>,
+        service: &str,
+    ) -> Result<i32, tonic::Status> {
+        let request = HealthCheckRequest {
+            service: service.to_string(),
+        };
+        Ok(client.check(request).await?.into_inner().status)
+    }
+
+    async fn healthz(receiver: &Arc<OtelReceiver>) -> http::StatusCode {
+        create_http_router(Arc::clone(receiver))
+            .oneshot(http::Request::get("/healthz").body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    async fn next_status(updates: &mut Streaming<HealthCheckResponse>) -> i32 {
+        let update = tokio::time::timeout(Duration::from_secs(5), updates.message());
+        update.await.unwrap().unwrap().unwrap().status
+    }
+
+    type SharedStorage = Arc<tokio::sync::RwLock<dyn StorageBackend>>;
+
+    /// A receiver whose storage turns critical once it holds any span.
+    fn receiver_with_tiny_memory_budget() -> (SharedStorage, Arc<OtelReceiver>) {
+        // Any stored span pushes a one-byte budget past the emergency threshold
+        let cleanup = CleanupConfig {
+            max_memory_bytes: 1,
+            ..Default::default()
+        };
+        let storage: SharedStorage =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::with_cleanup_config(100, cleanup)));
+        let receiver = Arc::new(
+            OtelReceiver::new(
+                0,
+                0,
+                Arc::clone(&storage),
+                Arc::new(crate::monitoring::Monitor::new()),
+            )
+            .with_logs(100),
+        );
+        (storage, receiver)
+    }
+
+    fn test_span(trace_id: &TraceId) -> Span {
+        Span::builder()
+            .trace_id(trace_id.clone())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET /")
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_probes_follow_storage_memory_pressure() {
+        let (storage, receiver) = receiver_with_tiny_memory_budget();
+        let mut client = connect(&receiver).await;
+
+        let system = receiver.subscribe_health();
+        assert_eq!(receiver.refresh_health().await, ServingStatus::Serving);
+        for service in ["", TRACE_SERVICE, METRICS_SERVICE, LOGS_SERVICE] {
+            assert_eq!(check(&mut client, service).await.unwrap(), WireStatus::Serving as i32);
+        }
+        assert_eq!(healthz(&receiver).await, http::StatusCode::OK);
+        assert_eq!(*system.borrow(), SystemHealth::Healthy);
+
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        let span = test_span(&trace_id);
+        storage.read().await.store_span(span).await.unwrap();
+        assert_eq!(storage.read().await.get_health(), StorageHealth::Critical);
+
+        assert_eq!(receiver.refresh_health().await, ServingStatus::NotServing);
+        for service in ["", TRACE_SERVICE, METRICS_SERVICE, LOGS_SERVICE] {
+            assert_eq!(check(&mut client, service).await.unwrap(), WireStatus::NotServing as i32);
+        }
+        assert_eq!(healthz(&receiver).await, http::StatusCode::SERVICE_UNAVAILABLE);
+
+        // Freeing the memory brings the receiver back
+        storage
+            .read()
+            .await
+            .delete_traces(&[trace_id])
+            .await
+            .unwrap();
+        assert_eq!(receiver.refresh_health().await, ServingStatus::Serving);
+        assert_eq!(check(&mut client, "").await.unwrap(), WireStatus::Serving as i32);
+        assert_eq!(healthz(&receiver).await, http::StatusCode::OK);
+
+        let unknown = check(&mut client, "unknown.Service").await.unwrap_err();
+        assert_eq!(unknown.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_watch_streams_status_changes() {
+        let (storage, receiver) = receiver_with_tiny_memory_budget();
+        let mut client = connect(&receiver).await;
+        let request = HealthCheckRequest {
+            service: TRACE_SERVICE.to_string(),
+        };
+        let mut updates = client.watch(request).await.unwrap().into_inner();
+        assert_eq!(next_status(&mut updates).await, WireStatus::Serving as i32);
+
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        storage
+            .read()
+            .await
+            .store_span(test_span(&trace_id))
+            .await
+            .unwrap();
+        receiver.refresh_health().await;
+        assert_eq!(next_status(&mut updates).await, WireStatus::NotServing as i32);
+
+        // The stream stays open for later changes
+        storage
+            .read()
+            .await
+            .delete_traces(&[trace_id])
+            .await
+            .unwrap();
+        receiver.refresh_health().await;
+        assert_eq!(next_status(&mut updates).await, WireStatus::Serving as i32);
+    }
+
+    #[tokio::test]
+    async fn test_status_follows_storage_and_system_health() {
+        let health = ReceiverHealth::new();
+        let mut changes = health.subscribe();
+        let mut system_changes = health.subscribe_system();
+        assert_eq!(health.status(), ServingStatus::Serving);
+
+        let serving = health
+            .update(StorageHealth::Degraded, SystemHealth::Degraded)
+            .await;
+        assert_eq!(serving, ServingStatus::Serving);
+        assert!(!changes.has_changed().unwrap());
+        assert_eq!(*system_changes.borrow_and_update(), SystemHealth::Degraded);
+
+        // Unchanged monitor health wakes no subscriber
+        health
+            .update(StorageHealth::Healthy, SystemHealth::Degraded)
+            .await;
+        assert!(!system_changes.has_changed().unwrap());
+
+        for (storage, system) in [
+            (StorageHealth::Critical, SystemHealth::Healthy),
+            (StorageHealth::Offline, SystemHealth::Healthy),
+            (StorageHealth::Healthy, SystemHealth::Critical),
+        ] {
+            assert_eq!(health.update(storage, system).await, ServingStatus::NotServing);
+        }
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(*changes.borrow_and_update(), ServingStatus::NotServing);
+
+        health
+            .update(StorageHealth::Healthy, SystemHealth::Unhealthy)
+            .await;
+        assert_eq!(health.status(), ServingStatus::Serving);
+    }
+}
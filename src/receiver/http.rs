@@ -3,33 +3,66 @@
 //! Implements the OTLP/HTTP protocol specification for receiving traces
 //! over HTTP on port 4318. Supports both JSON and protobuf formats.
 
-use crate::receiver::{convert_otel_span, extract_service_name};
+use super::logs::OtelLogsReceiver;
+use super::metrics::OtelMetricsReceiver;
+use crate::core::{SpanId, SpanLimits, TraceId};
+use crate::receiver::{convert_otel_span, extract_resource_semantics, RejectedSpans};
 use axum::{
     body::Bytes,
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, DefaultBodyLimit, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    ExportLogsServiceRequest, ExportLogsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTracePartialSuccess, ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
 use prost::Message;
 use serde_json::Value;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceBuilder;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
 
 /// HTTP OTLP server state.
 #[derive(Clone)]
 pub struct HttpOtelState {
     pub receiver: Arc<super::OtelReceiver>,
+    /// Log ingestion, when the receiver stores logs
+    pub logs: Option<Arc<OtelLogsReceiver>>,
+    /// Metric ingestion, when the receiver stores metrics
+    pub metrics: Option<Arc<OtelMetricsReceiver>>,
 }
 
 /// Create HTTP router for OTLP endpoints.
 pub fn create_http_router(receiver: Arc<super::OtelReceiver>) -> Router {
-    let state = HttpOtelState { receiver };
+    let max_request_bytes = receiver.max_request_bytes();
+    let cors = cors_layer(receiver.cors_allowed_origins());
+    let grpc_web = receiver.grpc_web();
+    let logs = receiver
+        .logs_storage()
+        .map(|storage| Arc::new(OtelLogsReceiver::new(Arc::clone(storage))));
+    let metrics = receiver
+        .metrics_storage()
+        .map(|storage| Arc::new(OtelMetricsReceiver::new(Arc::clone(storage))));
+    let state = HttpOtelState {
+        receiver,
+        logs,
+        metrics,
+    };
 
-    Router::new()
+    let router = Router::new()
         // OTLP trace endpoints
         .route("/v1/traces", post(handle_traces_v1))
         .route("/v1/trace", post(handle_traces_v1)) // Alternative endpoint
@@ -37,31 +70,73 @@ pub fn create_http_router(receiver: Arc<super::OtelReceiver>) -> Router {
         .route("/v1/metrics", post(handle_metrics_v1))
         // OTLP logs endpoint
         .route("/v1/logs", post(handle_logs_v1))
+        // Zipkin v2 JSON endpoint
+        .route("/api/v2/spans", post(super::zipkin::handle_zipkin_spans))
         // Health check
         .route("/health", get(health_check))
-        .route("/", get(root_handler))
+        // Probe endpoint: 503 while storage is critical
+        .route("/healthz", get(healthz))
+        .route("/", get(root_handler));
+    // gRPC-Web trace exports from browsers
+    let router = if grpc_web {
+        router.route(
+            super::grpc_web::GRPC_WEB_TRACES_PATH,
+            post(super::grpc_web::handle_grpc_web_traces),
+        )
+    } else {
+        router
+    };
+
+    router
         // Add middleware
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
-                .layer(
-                    CorsLayer::new()
-                        .allow_origin(tower_http::cors::Any)
-                        .allow_methods(tower_http::cors::Any)
-                        .allow_headers(tower_http::cors::Any),
-                ),
+                // Oversized bodies are rejected with 413 before being buffered
+                .layer(DefaultBodyLimit::max(max_request_bytes))
+                .layer(cors),
         )
         .with_state(state)
 }
 
+/// CORS for browser exporters, allowing any origin when `origins` is empty.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.is_empty() || origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(tower_http::cors::Any)
+        .allow_headers(tower_http::cors::Any)
+        // gRPC-Web clients read the status from headers on trailers-only responses
+        .expose_headers([
+            HeaderName::from_static("grpc-status"),
+            HeaderName::from_static("grpc-message"),
+        ])
+}
+
 /// Handle OTLP trace export requests.
 async fn handle_traces_v1(
     State(state): State<HttpOtelState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: HeaderMap,
     body: Bytes,
 ) -> std::result::Result<impl IntoResponse, HttpError> {
     tracing::debug!("Received HTTP trace export request, {} bytes", body.len());
 
+    // Caller's trace context, applied to the spans after conversion when enabled
+    let trace_context = if state.receiver.apply_traceparent() {
+        parse_w3c_trace_context(&headers)
+    } else {
+        None
+    };
+
     // Determine content type
     let content_type = headers
         .get("content-type")
@@ -70,32 +145,187 @@ async fn handle_traces_v1(
 
     tracing::debug!("Content-Type: {}", content_type);
 
+    let is_protobuf = is_protobuf_content_type(content_type);
+
     // Parse the request based on content type
-    let export_request = if content_type.contains("application/x-protobuf")
-        || content_type.contains("application/octet-stream")
-    {
+    let export_request = if is_protobuf {
         // Protobuf format
-        parse_protobuf_request(&body)?
+        parse_protobuf_request(&body)
     } else {
         // Assume JSON format
-        parse_json_request(&body)?
+        parse_json_request(&body)
     };
 
+    if let Some(capture) = state.receiver.wire_capture() {
+        let pipeline = state.receiver.span_pipeline();
+        match export_request {
+            Ok(ref request) => capture.record("otlp/http", &headers, &body, Ok(request), pipeline),
+            Err(ref e) => {
+                capture.record("otlp/http", &headers, &body, Err(&e.to_string()), pipeline)
+            },
+        }
+    }
+    let export_request = export_request?;
+
     // Process the spans using the same logic as gRPC
-    let spans = process_export_request(export_request)?;
+    let (mut spans, mut rejected) =
+        process_export_request(export_request, state.receiver.span_limits())?;
+    if let Some((trace_id, parent_span_id)) = trace_context {
+        apply_trace_context(&mut spans, &trace_id, &parent_span_id);
+    }
 
     // Store spans
-    if let Err(e) = state.receiver.process_spans(spans).await {
-        tracing::error!("Failed to process spans: {}", e);
-        return Err(HttpError::Internal(format!("Failed to process spans: {}", e)));
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    match state.receiver.process_export(spans, peer).await {
+        Ok(storage_rejected) => rejected.merge(storage_rejected),
+        Err(e) => return Err(HttpError::from_process_error(e)),
     }
 
     tracing::debug!("Successfully processed HTTP trace export request");
 
-    // Return OTLP response
-    Ok(Json(serde_json::json!({
-        "partialSuccess": null
-    })))
+    // Return OTLP response in the encoding the client asked for
+    Ok(export_trace_response(&headers, is_protobuf, rejected.partial_success()))
+}
+
+/// Parse the W3C TraceContext `traceparent` header into its trace ID and
+/// parent span ID.
+///
+/// Returns `None` if the header is missing or malformed, including the
+/// all-zero IDs and version `ff` the spec marks invalid. `tracestate` is
+/// vendor data and only logged.
+pub fn parse_w3c_trace_context(headers: &HeaderMap) -> Option<(TraceId, SpanId)> {
+    let traceparent = headers.get("traceparent")?.to_str().ok()?.trim();
+    let fields: Vec<&str> = traceparent.split('-').collect();
+    let &[version, trace_id, parent_id, flags] = fields.get(..4)? else {
+        return None;
+    };
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    };
+    let all_zero = |s: &str| s.bytes().all(|b| b == b'0');
+    // Version 00 has exactly four fields; later versions may append more
+    if !is_hex(version, 2)
+        || version == "ff"
+        || (version == "00" && fields.len() != 4)
+        || !is_hex(trace_id, 32)
+        || !is_hex(parent_id, 16)
+        || !is_hex(flags, 2)
+        || all_zero(trace_id)
+        || all_zero(parent_id)
+    {
+        tracing::debug!("Ignoring malformed traceparent header: {}", traceparent);
+        return None;
+    }
+    if let Some(tracestate) = headers.get("tracestate").and_then(|v| v.to_str().ok()) {
+        tracing::debug!("Export request tracestate: {}", tracestate);
+    }
+
+    Some((
+        TraceId::new(trace_id.to_string()).ok()?,
+        SpanId::new(parent_id.to_string()).ok()?,
+    ))
+}
+
+/// Place exported spans in the caller's trace: every span takes `trace_id`
+/// and spans without a parent become children of `parent_span_id`.
+fn apply_trace_context(
+    spans: &mut [crate::core::Span],
+    trace_id: &TraceId,
+    parent_span_id: &SpanId,
+) {
+    for span in spans {
+        span.trace_id = trace_id.clone();
+        if span.parent_span_id.is_none() {
+            span.parent_span_id = Some(parent_span_id.clone());
+        }
+    }
+}
+
+/// Check whether a content type denotes binary protobuf.
+fn is_protobuf_content_type(content_type: &str) -> bool {
+    content_type.contains("application/x-protobuf")
+        || content_type.contains("application/octet-stream")
+}
+
+/// Build the OTLP export response.
+///
+/// An explicit `Accept` header selects the encoding; otherwise the response
+/// mirrors the request encoding as required by the OTLP/HTTP spec.
+fn export_trace_response(
+    headers: &HeaderMap,
+    request_is_protobuf: bool,
+    partial_success: Option<ExportTracePartialSuccess>,
+) -> Response {
+    if respond_protobuf(headers, request_is_protobuf) {
+        let body = ExportTraceServiceResponse { partial_success }.encode_to_vec();
+        (StatusCode::OK, [(header::CONTENT_TYPE, "application/x-protobuf")], body).into_response()
+    } else {
+        let partial_success = partial_success.map(|p| {
+            serde_json::json!({
+                "rejectedSpans": p.rejected_spans,
+                "errorMessage": p.error_message,
+            })
+        });
+        Json(serde_json::json!({
+            "partialSuccess": partial_success
+        }))
+        .into_response()
+    }
+}
+
+/// Whether to encode the response as protobuf: an explicit `Accept` header
+/// decides, otherwise the request encoding is mirrored.
+fn respond_protobuf(headers: &HeaderMap, request_is_protobuf: bool) -> bool {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if is_protobuf_content_type(accept) {
+        true
+    } else if accept.contains("application/json") {
+        false
+    } else {
+        request_is_protobuf
+    }
+}
+
+/// Decode a logs or metrics export request from protobuf or OTLP/JSON,
+/// returning whether it was protobuf.
+fn parse_signal_request<T>(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> std::result::Result<(T, bool), HttpError>
+where
+    T: Message + Default + serde::de::DeserializeOwned,
+{
+    let content_type = headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json");
+    if is_protobuf_content_type(content_type) {
+        T::decode(body)
+            .map(|request| (request, true))
+            .map_err(|e| HttpError::BadRequest(format!("Failed to parse protobuf: {}", e)))
+    } else {
+        serde_json::from_slice(body)
+            .map(|request| (request, false))
+            .map_err(|e| HttpError::BadRequest(format!("Invalid JSON: {}", e)))
+    }
+}
+
+/// Encode a logs or metrics export response.
+fn export_signal_response<T: Message + serde::Serialize>(
+    headers: &HeaderMap,
+    request_is_protobuf: bool,
+    response: T,
+) -> Response {
+    if respond_protobuf(headers, request_is_protobuf) {
+        let body = response.encode_to_vec();
+        (StatusCode::OK, [(header::CONTENT_TYPE, "application/x-protobuf")], body).into_response()
+    } else {
+        Json(response).into_response()
+    }
 }
 
 /// Parse protobuf OTLP request.
@@ -118,7 +348,9 @@ fn parse_json_request(body: &[u8]) -> std::result::Result<ExportTraceServiceRequ
 }
 
 /// Convert JSON Value to OTLP ExportTraceServiceRequest.
-fn json_to_otlp_request(json: Value) -> std::result::Result<ExportTraceServiceRequest, HttpError> {
+pub(super) fn json_to_otlp_request(
+    json: Value,
+) -> std::result::Result<ExportTraceServiceRequest, HttpError> {
     use opentelemetry_proto::tonic::{
         collector::trace::v1::ExportTraceServiceRequest,
         common::v1::{AnyValue, InstrumentationScope, KeyValue},
@@ -243,7 +475,7 @@ fn json_to_span(
 ) -> std::result::Result<opentelemetry_proto::tonic::trace::v1::Span, HttpError> {
     use opentelemetry_proto::tonic::{
         common::v1::{AnyValue, KeyValue},
-        trace::v1::Span,
+        trace::v1::{Span, Status},
     };
 
     // Extract required fields
@@ -321,6 +553,15 @@ fn json_to_span(
     // Parse kind
     let kind = span_json.get("kind").and_then(|v| v.as_u64()).unwrap_or(0) as i32;
 
+    let status = span_json.get("status").map(|status| Status {
+        code: status.get("code").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+        message: status
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+    });
+
     Ok(Span {
         trace_id: trace_id_bytes,
         span_id: span_id_bytes,
@@ -335,17 +576,19 @@ fn json_to_span(
         dropped_events_count: 0,
         links: Vec::new(),
         dropped_links_count: 0,
-        status: None,
+        status,
         trace_state: "".to_string(),
         flags: 0,
     })
 }
 
-/// Process OTLP export request and convert to Urpo spans.
-fn process_export_request(
+/// Process OTLP export request and convert to Urpo spans, counting rejects.
+pub(super) fn process_export_request(
     export_request: ExportTraceServiceRequest,
-) -> std::result::Result<Vec<crate::core::Span>, HttpError> {
+    limits: &SpanLimits,
+) -> std::result::Result<(Vec<crate::core::Span>, RejectedSpans), HttpError> {
     let mut spans = Vec::new();
+    let mut rejected = RejectedSpans::default();
     let mut total_resource_spans = 0;
     let mut total_scope_spans = 0;
     let mut total_spans = 0;
@@ -354,7 +597,9 @@ fn process_export_request(
     for resource_spans in export_request.resource_spans {
         total_resource_spans += 1;
         let resource = resource_spans.resource.unwrap_or_default();
-        let service_name = extract_service_name(&resource.attributes);
+        let semantics = extract_resource_semantics(&resource);
+        let service_name = semantics.service_name.clone();
+        let resource_attributes = semantics.span_resource_attributes();
 
         tracing::debug!(
             "Processing HTTP resource spans for service: {}, scope_spans count: {}",
@@ -382,8 +627,9 @@ fn process_export_request(
                 let trace_id_hex = hex::encode(&otel_span.trace_id);
                 let span_id_hex = hex::encode(&otel_span.span_id);
 
-                match convert_otel_span(otel_span, service_name.clone()) {
-                    Ok(span) => {
+                match convert_otel_span(otel_span, service_name.clone(), limits) {
+                    Ok(mut span) => {
+                        span.resource_attributes = resource_attributes.clone();
                         tracing::debug!(
                             "Converted HTTP span: service={}, operation={}, trace_id={}, span_id={}",
                             service_name, span_name, trace_id_hex, span_id_hex
@@ -395,6 +641,7 @@ fn process_export_request(
                             "Failed to convert HTTP span: service={}, operation={}, trace_id={}, span_id={}, error={}",
                             service_name, span_name, trace_id_hex, span_id_hex, e
                         );
+                        rejected.record(e);
                     },
                 }
             }
@@ -409,7 +656,7 @@ fn process_export_request(
         spans.len()
     );
 
-    Ok(spans)
+    Ok((spans, rejected))
 }
 
 /// Health check endpoint.
@@ -417,10 +664,18 @@ async fn health_check() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
         "service": "urpo-http-receiver",
-        "endpoints": ["/v1/traces", "/health"]
+        "endpoints": ["/v1/traces", "/v1/metrics", "/v1/logs", "/api/v2/spans", "/health"]
     }))
 }
 
+/// Readiness probe: 200 `SERVING`, or 503 `NOT_SERVING` while storage is critical.
+async fn healthz(State(state): State<HttpOtelState>) -> impl IntoResponse {
+    match state.receiver.refresh_health().await {
+        super::health::ServingStatus::Serving => (StatusCode::OK, "SERVING"),
+        _ => (StatusCode::SERVICE_UNAVAILABLE, "NOT_SERVING"),
+    }
+}
+
 /// Root handler.
 async fn root_handler() -> impl IntoResponse {
     Json(serde_json::json!({
@@ -430,45 +685,110 @@ async fn root_handler() -> impl IntoResponse {
             "/v1/traces": "POST - OTLP trace export",
             "/v1/metrics": "POST - OTLP metrics export",
             "/v1/logs": "POST - OTLP logs export",
-            "/health": "GET - Health check"
+            "/api/v2/spans": "POST - Zipkin v2 JSON span list",
+            "/health": "GET - Health check",
+            "/healthz": "GET - Readiness probe (503 while storage is critical)"
         }
     }))
 }
 
-/// Handle OTLP metrics export requests - minimal implementation.
-#[inline(always)]
-async fn handle_metrics_v1() -> impl IntoResponse {
-    tracing::debug!("Received HTTP metrics export request");
-
-    // Zero-allocation OTLP success response
-    const OTLP_SUCCESS: &str = r#"{"partialSuccess":null}"#;
-
-    (StatusCode::OK, [("content-type", "application/json")], OTLP_SUCCESS)
+/// Handle OTLP metrics export requests.
+async fn handle_metrics_v1(
+    State(state): State<HttpOtelState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> std::result::Result<Response, HttpError> {
+    tracing::debug!("Received HTTP metrics export request, {} bytes", body.len());
+    let metrics = state
+        .metrics
+        .ok_or_else(|| HttpError::NotFound("Metrics are not enabled".to_string()))?;
+    let (request, is_protobuf) =
+        parse_signal_request::<ExportMetricsServiceRequest>(&headers, &body)?;
+
+    let partial_success = metrics.ingest(request).await;
+    Ok(export_signal_response(
+        &headers,
+        is_protobuf,
+        ExportMetricsServiceResponse { partial_success },
+    ))
 }
 
-/// Handle OTLP logs export requests - minimal implementation.
-#[inline(always)]
-async fn handle_logs_v1() -> impl IntoResponse {
-    tracing::debug!("Received HTTP logs export request");
-
-    // Zero-allocation OTLP success response
-    const OTLP_SUCCESS: &str = r#"{"partialSuccess":null}"#;
-
-    (StatusCode::OK, [("content-type", "application/json")], OTLP_SUCCESS)
+/// Handle OTLP logs export requests.
+async fn handle_logs_v1(
+    State(state): State<HttpOtelState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> std::result::Result<Response, HttpError> {
+    tracing::debug!("Received HTTP logs export request, {} bytes", body.len());
+    let logs = state
+        .logs
+        .ok_or_else(|| HttpError::NotFound("Logs are not enabled".to_string()))?;
+    let (request, is_protobuf) = parse_signal_request::<ExportLogsServiceRequest>(&headers, &body)?;
+
+    let partial_success = logs.ingest(request).await;
+    Ok(export_signal_response(
+        &headers,
+        is_protobuf,
+        ExportLogsServiceResponse { partial_success },
+    ))
 }
 
 /// HTTP-specific error type.
 #[derive(Debug)]
 pub enum HttpError {
     BadRequest(String),
+    /// The signal is not enabled on this receiver
+    NotFound(String),
     Internal(String),
+    /// Storage is full or the client over its rate limit; answered with 429
+    /// and `Retry-After`
+    TooManyRequests {
+        message: String,
+        retry_after: Duration,
+    },
+}
+
+impl HttpError {
+    /// Map a `process_spans` failure, turning a full storage or an exceeded
+    /// rate limit into 429.
+    pub fn from_process_error(e: crate::core::UrpoError) -> Self {
+        match e {
+            crate::core::UrpoError::StorageFull { rejected } => {
+                tracing::warn!("Storage full, asking exporter to retry {} spans later", rejected);
+                HttpError::TooManyRequests {
+                    message: format!("Storage full: {} spans rejected", rejected),
+                    retry_after: super::STORAGE_FULL_RETRY_DELAY,
+                }
+            },
+            crate::core::UrpoError::RateLimited {
+                rejected,
+                retry_after,
+            } => HttpError::TooManyRequests {
+                message: format!("Rate limited: {} spans rejected", rejected),
+                retry_after,
+            },
+            e => {
+                tracing::error!("Failed to process spans: {}", e);
+                HttpError::Internal(format!("Failed to process spans: {}", e))
+            },
+        }
+    }
 }
 
 impl IntoResponse for HttpError {
     fn into_response(self) -> Response {
+        let mut retry_after = None;
         let (status, error_message) = match self {
             HttpError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            HttpError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             HttpError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            HttpError::TooManyRequests {
+                message,
+                retry_after: delay,
+            } => {
+                retry_after = Some(delay);
+                (StatusCode::TOO_MANY_REQUESTS, message)
+            },
         };
 
         let body = Json(serde_json::json!({
@@ -476,6 +796,11 @@ impl IntoResponse for HttpError {
             "status": status.as_u16()
         }));
 
+        if let Some(delay) = retry_after {
+            // Whole seconds, rounded up so the client never retries too early
+            let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+            return (status, [(header::RETRY_AFTER, seconds.to_string())], body).into_response();
+        }
         (status, body).into_response()
     }
 }
@@ -484,7 +809,11 @@ impl std::fmt::Display for HttpError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             HttpError::BadRequest(msg) => write!(f, "Bad Request: {}", msg),
+            HttpError::NotFound(msg) => write!(f, "Not Found: {}", msg),
             HttpError::Internal(msg) => write!(f, "Internal Error: {}", msg),
+            HttpError::TooManyRequests { message, .. } => {
+                write!(f, "Too Many Requests: {}", message)
+            },
         }
     }
 }
@@ -0,0 +1,637 @@
+//! Jaeger Thrift ingestion.
+//!
+//! Implements the Jaeger collector HTTP endpoint (`POST /api/traces`) that
+//! jaeger-client HTTP senders report to. Batches are `jaeger.thrift` structs
+//! encoded with the Thrift binary protocol. Each span is converted into a
+//! `core::Span` and fed through the same sampling/storage path as OTLP spans.
+//!
+//! The endpoint runs on its own port (Jaeger's default is 14268) and is only
+//! started when `server.jaeger_port` is configured.
+
+use super::OtelReceiver;
+use crate::core::{
+    Result, ServiceName, Span, SpanEvent, SpanId, SpanKind, SpanLink, SpanStatus, TraceId,
+    UrpoError,
+};
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use thrift::protocol::{TBinaryInputProtocol, TInputProtocol, TType};
+
+/// `SpanRefType.CHILD_OF`
+const REF_CHILD_OF: i32 = 0;
+/// `SpanRefType.FOLLOWS_FROM`
+const REF_FOLLOWS_FROM: i32 = 1;
+
+/// Jaeger process (the reporting service).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JaegerProcess {
+    /// Service name
+    pub service_name: String,
+    /// Process tags, values rendered as strings
+    pub tags: Vec<(String, String)>,
+}
+
+/// Reference from one span to another.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JaegerSpanRef {
+    /// `CHILD_OF` (0) or `FOLLOWS_FROM` (1)
+    pub ref_type: i32,
+    /// Low 64 bits of the referenced trace ID
+    pub trace_id_low: i64,
+    /// High 64 bits of the referenced trace ID
+    pub trace_id_high: i64,
+    /// Referenced span ID
+    pub span_id: i64,
+}
+
+/// Timestamped span log entry.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JaegerLog {
+    /// Timestamp in microseconds since epoch
+    pub timestamp: i64,
+    /// Log fields, values rendered as strings
+    pub fields: Vec<(String, String)>,
+}
+
+/// Jaeger span as defined in `jaeger.thrift`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JaegerSpan {
+    /// Low 64 bits of the trace ID
+    pub trace_id_low: i64,
+    /// High 64 bits of the trace ID
+    pub trace_id_high: i64,
+    /// Span ID
+    pub span_id: i64,
+    /// Parent span ID (0 for root spans)
+    pub parent_span_id: i64,
+    /// Operation name
+    pub operation_name: String,
+    /// Span references
+    pub references: Vec<JaegerSpanRef>,
+    /// Start time in microseconds since epoch
+    pub start_time: i64,
+    /// Duration in microseconds
+    pub duration: i64,
+    /// Span tags, values rendered as strings
+    pub tags: Vec<(String, String)>,
+    /// Span logs
+    pub logs: Vec<JaegerLog>,
+}
+
+/// Batch of spans reported by a single process.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JaegerBatch {
+    /// Reporting process
+    pub process: JaegerProcess,
+    /// Spans in this batch
+    pub spans: Vec<JaegerSpan>,
+}
+
+impl OtelReceiver {
+    /// Start the Jaeger collector HTTP endpoint.
+    pub async fn start_jaeger(self: Arc<Self>, addr: SocketAddr) -> Result<()> {
+        tracing::info!("Starting Jaeger Thrift receiver on {}", addr);
+
+        let app = create_jaeger_router(self);
+
+        let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+            UrpoError::network(format!("Failed to bind Jaeger receiver to {}: {}", addr, e))
+        })?;
+
+        axum::serve(listener, app)
+            .await
+            .map_err(|e| UrpoError::protocol(format!("Jaeger receiver error: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Create HTTP router for the Jaeger collector endpoint.
+pub fn create_jaeger_router(receiver: Arc<OtelReceiver>) -> Router {
+    let max_request_bytes = receiver.max_request_bytes();
+
+    Router::new()
+        .route("/api/traces", post(handle_jaeger_traces))
+        .layer(DefaultBodyLimit::max(max_request_bytes))
+        .with_state(receiver)
+}
+
+/// Handle `jaeger.thrift` batch uploads.
+async fn handle_jaeger_traces(
+    State(receiver): State<Arc<OtelReceiver>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if !content_type.starts_with("application/x-thrift")
+        && !content_type.starts_with("application/vnd.apache.thrift.binary")
+    {
+        return (StatusCode::BAD_REQUEST, format!("Unsupported content type: {}", content_type))
+            .into_response();
+    }
+
+    let batch = match decode_batch(&body) {
+        Ok(batch) => batch,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let total = batch.spans.len();
+    let (spans, errors) = convert_jaeger_batch(batch);
+    if !errors.is_empty() {
+        tracing::warn!(
+            "Rejected {} of {} Jaeger spans: {}",
+            errors.len(),
+            total,
+            errors.join("; ")
+        );
+    }
+
+    if let Err(e) = receiver.process_spans(spans).await {
+        return super::http::HttpError::from_process_error(e).into_response();
+    }
+
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// Decode a Thrift binary-encoded `jaeger.thrift` Batch.
+pub fn decode_batch(body: &[u8]) -> Result<JaegerBatch> {
+    let mut protocol = TBinaryInputProtocol::new(body, true);
+    read_batch(&mut protocol).map_err(|e| UrpoError::parse(format!("Invalid Jaeger batch: {}", e)))
+}
+
+/// Convert a Jaeger batch, collecting per-span errors.
+pub fn convert_jaeger_batch(batch: JaegerBatch) -> (Vec<Span>, Vec<String>) {
+    let mut spans = Vec::with_capacity(batch.spans.len());
+    let mut errors = Vec::new();
+
+    for (index, jaeger_span) in batch.spans.into_iter().enumerate() {
+        match convert_jaeger_span(&batch.process, jaeger_span) {
+            Ok(span) => spans.push(span),
+            Err(e) => errors.push(format!("span[{}]: {}", index, e)),
+        }
+    }
+
+    (spans, errors)
+}
+
+/// Convert a single Jaeger span into a Urpo span.
+pub fn convert_jaeger_span(process: &JaegerProcess, jaeger: JaegerSpan) -> Result<Span> {
+    if jaeger.trace_id_low == 0 && jaeger.trace_id_high == 0 {
+        return Err(UrpoError::InvalidSpan("Missing traceId".to_string()));
+    }
+    if jaeger.span_id == 0 {
+        return Err(UrpoError::InvalidSpan("Missing spanId".to_string()));
+    }
+
+    let trace_id = TraceId::new(format_trace_id(jaeger.trace_id_high, jaeger.trace_id_low))?;
+    let span_id = SpanId::new(format_span_id(jaeger.span_id))?;
+
+    let service_name = if process.service_name.is_empty() {
+        "unknown"
+    } else {
+        process.service_name.as_str()
+    };
+
+    let tag = |key: &str| {
+        jaeger
+            .tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let kind = match tag("span.kind") {
+        Some("client") => SpanKind::Client,
+        Some("server") => SpanKind::Server,
+        Some("producer") => SpanKind::Producer,
+        Some("consumer") => SpanKind::Consumer,
+        _ => SpanKind::Internal,
+    };
+
+    // Jaeger marks failures with the boolean "error" tag
+    let status = if tag("error") == Some("true") {
+        let message = tag("otel.status_description")
+            .or_else(|| tag("error.message"))
+            .unwrap_or("error");
+        SpanStatus::Error(message.to_string())
+    } else {
+        SpanStatus::Unknown
+    };
+
+    // CHILD_OF wins over the legacy parentSpanId field
+    let parent_id = jaeger
+        .references
+        .iter()
+        .find(|r| r.ref_type == REF_CHILD_OF && r.span_id != 0)
+        .map(|r| r.span_id)
+        .unwrap_or(jaeger.parent_span_id);
+
+    let mut builder = Span::builder()
+        .trace_id(trace_id)
+        .span_id(span_id)
+        .service_name(ServiceName::new(service_name.to_string())?)
+        .operation_name(jaeger.operation_name.clone())
+        .start_time(UNIX_EPOCH + Duration::from_micros(jaeger.start_time.max(0) as u64))
+        .duration(Duration::from_micros(jaeger.duration.max(0) as u64))
+        .kind(kind)
+        .status(status);
+
+    if parent_id != 0 {
+        builder = builder.parent_span_id(SpanId::new(format_span_id(parent_id))?);
+    }
+
+    let follows_from: Vec<String> = jaeger
+        .references
+        .iter()
+        .filter(|r| r.ref_type == REF_FOLLOWS_FROM)
+        .map(|r| format_span_id(r.span_id))
+        .collect();
+    if !follows_from.is_empty() {
+        builder = builder.attribute("jaeger.follows_from", follows_from.join(","));
+    }
+    // FOLLOWS_FROM references are also kept as links
+    for r in jaeger
+        .references
+        .iter()
+        .filter(|r| r.ref_type == REF_FOLLOWS_FROM)
+    {
+        builder = builder.link(SpanLink::new(
+            TraceId::new(format_trace_id(r.trace_id_high, r.trace_id_low))?,
+            SpanId::new(format_span_id(r.span_id))?,
+        ));
+    }
+
+    // Process tags first so span tags win on conflicts
+    for (key, value) in &process.tags {
+        builder = builder.attribute(key.clone(), value.clone());
+    }
+    for (key, value) in jaeger.tags {
+        builder = builder.attribute(key, value);
+    }
+
+    // Logs become span events
+    for log in jaeger.logs {
+        let name = log
+            .fields
+            .iter()
+            .find(|(k, _)| k == "event")
+            .map(|(_, v)| v.clone())
+            .unwrap_or_else(|| "log".to_string());
+        let timestamp = UNIX_EPOCH + Duration::from_micros(log.timestamp.max(0) as u64);
+        let mut event = SpanEvent::new(name, timestamp);
+        for (key, value) in log.fields {
+            if key != "event" {
+                event = event.with_attribute(key, value);
+            }
+        }
+        builder = builder.event(event);
+    }
+
+    builder.build()
+}
+
+fn format_trace_id(high: i64, low: i64) -> String {
+    format!("{:016x}{:016x}", high as u64, low as u64)
+}
+
+fn format_span_id(id: i64) -> String {
+    format!("{:016x}", id as u64)
+}
+
+type ThriftResult<T> = thrift::Result<T>;
+
+/// Iterate over the fields of a struct, calling `f` with each field id and type.
+/// `f` must consume the field value or return `false` to have it skipped.
+fn read_struct(
+    protocol: &mut dyn TInputProtocol,
+    mut f: impl FnMut(&mut dyn TInputProtocol, i16, TType) -> ThriftResult<bool>,
+) -> ThriftResult<()> {
+    protocol.read_struct_begin()?;
+    loop {
+        let field = protocol.read_field_begin()?;
+        if field.field_type == TType::Stop {
+            break;
+        }
+        let id = field.id.unwrap_or_default();
+        if !f(protocol, id, field.field_type)? {
+            protocol.skip(field.field_type)?;
+        }
+        protocol.read_field_end()?;
+    }
+    protocol.read_struct_end()
+}
+
+fn read_list<T>(
+    protocol: &mut dyn TInputProtocol,
+    mut read: impl FnMut(&mut dyn TInputProtocol) -> ThriftResult<T>,
+) -> ThriftResult<Vec<T>> {
+    let list = protocol.read_list_begin()?;
+    let mut items = Vec::with_capacity(list.size.clamp(0, 1024) as usize);
+    for _ in 0..list.size {
+        items.push(read(protocol)?);
+    }
+    protocol.read_list_end()?;
+    Ok(items)
+}
+
+fn read_batch(protocol: &mut dyn TInputProtocol) -> ThriftResult<JaegerBatch> {
+    let mut batch = JaegerBatch::default();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::Struct) => batch.process = read_process(p)?,
+            (2, TType::List) => batch.spans = read_list(p, read_span)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(batch)
+}
+
+fn read_process(protocol: &mut dyn TInputProtocol) -> ThriftResult<JaegerProcess> {
+    let mut process = JaegerProcess::default();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::String) => process.service_name = p.read_string()?,
+            (2, TType::List) => process.tags = read_list(p, read_tag)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(process)
+}
+
+fn read_span(protocol: &mut dyn TInputProtocol) -> ThriftResult<JaegerSpan> {
+    let mut span = JaegerSpan::default();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::I64) => span.trace_id_low = p.read_i64()?,
+            (2, TType::I64) => span.trace_id_high = p.read_i64()?,
+            (3, TType::I64) => span.span_id = p.read_i64()?,
+            (4, TType::I64) => span.parent_span_id = p.read_i64()?,
+            (5, TType::String) => span.operation_name = p.read_string()?,
+            (6, TType::List) => span.references = read_list(p, read_span_ref)?,
+            (8, TType::I64) => span.start_time = p.read_i64()?,
+            (9, TType::I64) => span.duration = p.read_i64()?,
+            (10, TType::List) => span.tags = read_list(p, read_tag)?,
+            (11, TType::List) => span.logs = read_list(p, read_log)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(span)
+}
+
+fn read_span_ref(protocol: &mut dyn TInputProtocol) -> ThriftResult<JaegerSpanRef> {
+    let mut span_ref = JaegerSpanRef::default();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::I32) => span_ref.ref_type = p.read_i32()?,
+            (2, TType::I64) => span_ref.trace_id_low = p.read_i64()?,
+            (3, TType::I64) => span_ref.trace_id_high = p.read_i64()?,
+            (4, TType::I64) => span_ref.span_id = p.read_i64()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(span_ref)
+}
+
+fn read_log(protocol: &mut dyn TInputProtocol) -> ThriftResult<JaegerLog> {
+    let mut log = JaegerLog::default();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::I64) => log.timestamp = p.read_i64()?,
+            (2, TType::List) => log.fields = read_list(p, read_tag)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(log)
+}
+
+/// Read a `Tag`, rendering its typed value as a string.
+fn read_tag(protocol: &mut dyn TInputProtocol) -> ThriftResult<(String, String)> {
+    let mut key = String::new();
+    let mut value = String::new();
+    read_struct(protocol, |p, id, ty| {
+        match (id, ty) {
+            (1, TType::String) => key = p.read_string()?,
+            (2, TType::I32) => {
+                p.read_i32()?;
+            },
+            (3, TType::String) => value = p.read_string()?,
+            (4, TType::Double) => value = p.read_double()?.to_string(),
+            (5, TType::Bool) => value = p.read_bool()?.to_string(),
+            (6, TType::I64) => value = p.read_i64()?.to_string(),
+            (7, TType::String) => value = hex::encode(p.read_bytes()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thrift::protocol::{
+        TBinaryOutputProtocol, TFieldIdentifier, TListIdentifier, TOutputProtocol,
+        TStructIdentifier,
+    };
+
+    fn write_field(p: &mut dyn TOutputProtocol, id: i16, ty: TType) {
+        p.write_field_begin(&TFieldIdentifier::new::<_, String, i16>(None, ty, id))
+            .unwrap();
+    }
+
+    fn write_tag(p: &mut dyn TOutputProtocol, key: &str, value: &str) {
+        p.write_struct_begin(&TStructIdentifier::new("Tag"))
+            .unwrap();
+        write_field(p, 1, TType::String);
+        p.write_string(key).unwrap();
+        write_field(p, 2, TType::I32);
+        p.write_i32(0).unwrap();
+        write_field(p, 3, TType::String);
+        p.write_string(value).unwrap();
+        p.write_field_stop().unwrap();
+        p.write_struct_end().unwrap();
+    }
+
+    fn write_tags(p: &mut dyn TOutputProtocol, id: i16, tags: &[(&str, &str)]) {
+        write_field(p, id, TType::List);
+        p.write_list_begin(&TListIdentifier::new(TType::Struct, tags.len() as i32))
+            .unwrap();
+        for (k, v) in tags {
+            write_tag(p, k, v);
+        }
+        p.write_list_end().unwrap();
+    }
+
+    /// Encode a batch with one child span the way jaeger-client does.
+    fn encode_batch() -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut p = TBinaryOutputProtocol::new(&mut buf, true);
+            p.write_struct_begin(&TStructIdentifier::new("Batch"))
+                .unwrap();
+
+            write_field(&mut p, 1, TType::Struct);
+            p.write_struct_begin(&TStructIdentifier::new("Process"))
+                .unwrap();
+            write_field(&mut p, 1, TType::String);
+            p.write_string("frontend").unwrap();
+            write_tags(&mut p, 2, &[("hostname", "web-1")]);
+            p.write_field_stop().unwrap();
+            p.write_struct_end().unwrap();
+
+            write_field(&mut p, 2, TType::List);
+            p.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            p.write_struct_begin(&TStructIdentifier::new("Span"))
+                .unwrap();
+            for (id, value) in [(1, 0x5af7_183f_b1d4_cf5f_i64), (2, 0), (3, 0x352b), (4, 0)] {
+                write_field(&mut p, id, TType::I64);
+                p.write_i64(value).unwrap();
+            }
+            write_field(&mut p, 5, TType::String);
+            p.write_string("GET /api").unwrap();
+
+            write_field(&mut p, 6, TType::List);
+            p.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            p.write_struct_begin(&TStructIdentifier::new("SpanRef"))
+                .unwrap();
+            write_field(&mut p, 1, TType::I32);
+            p.write_i32(REF_CHILD_OF).unwrap();
+            for (id, value) in [(2, 0x5af7_183f_b1d4_cf5f_i64), (3, 0), (4, 0x6b22)] {
+                write_field(&mut p, id, TType::I64);
+                p.write_i64(value).unwrap();
+            }
+            p.write_field_stop().unwrap();
+            p.write_struct_end().unwrap();
+            p.write_list_end().unwrap();
+
+            write_field(&mut p, 7, TType::I32);
+            p.write_i32(1).unwrap();
+            write_field(&mut p, 8, TType::I64);
+            p.write_i64(1_556_604_172_355_737).unwrap();
+            write_field(&mut p, 9, TType::I64);
+            p.write_i64(1431).unwrap();
+            write_tags(&mut p, 10, &[("span.kind", "client"), ("error", "true")]);
+
+            write_field(&mut p, 11, TType::List);
+            p.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            p.write_struct_begin(&TStructIdentifier::new("Log"))
+                .unwrap();
+            write_field(&mut p, 1, TType::I64);
+            p.write_i64(1_556_604_172_356_000).unwrap();
+            write_tags(&mut p, 2, &[("event", "retry"), ("attempt", "2")]);
+            p.write_field_stop().unwrap();
+            p.write_struct_end().unwrap();
+            p.write_list_end().unwrap();
+
+            p.write_field_stop().unwrap();
+            p.write_struct_end().unwrap();
+            p.write_list_end().unwrap();
+
+            p.write_field_stop().unwrap();
+            p.write_struct_end().unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn test_decode_and_convert_batch() {
+        let batch = decode_batch(&encode_batch()).unwrap();
+        assert_eq!(batch.process.service_name, "frontend");
+        assert_eq!(batch.spans.len(), 1);
+
+        let (spans, errors) = convert_jaeger_batch(batch);
+        assert!(errors.is_empty());
+
+        let span = &spans[0];
+        assert_eq!(span.trace_id.as_str(), "00000000000000005af7183fb1d4cf5f");
+        assert_eq!(span.span_id.as_str(), "000000000000352b");
+        // Parent comes from the CHILD_OF reference, not the zero parentSpanId
+        assert_eq!(span.parent_span_id.as_ref().unwrap().as_str(), "0000000000006b22");
+        assert_eq!(span.service_name.as_str(), "frontend");
+        assert_eq!(span.operation_name, "GET /api");
+        assert_eq!(span.kind, SpanKind::Client);
+        assert!(matches!(span.status, SpanStatus::Error(_)));
+        assert_eq!(span.duration, Duration::from_micros(1431));
+        assert_eq!(span.attributes.get("hostname"), Some("web-1"));
+        assert!(span.attributes.get("event.0.name").is_none());
+        assert_eq!(span.events.len(), 1);
+        assert_eq!(span.events[0].name, "retry");
+        assert_eq!(span.events[0].attributes.get("attempt"), Some("2"));
+        assert_eq!(
+            span.events[0].timestamp,
+            UNIX_EPOCH + Duration::from_micros(1_556_604_172_356_000)
+        );
+    }
+
+    #[test]
+    fn test_convert_rejects_missing_ids() {
+        let process = JaegerProcess {
+            service_name: "svc".to_string(),
+            tags: Vec::new(),
+        };
+        let no_span_id = JaegerSpan {
+            trace_id_low: 1,
+            ..Default::default()
+        };
+        assert!(convert_jaeger_span(&process, no_span_id).is_err());
+
+        let no_trace_id = JaegerSpan {
+            span_id: 1,
+            ..Default::default()
+        };
+        assert!(convert_jaeger_span(&process, no_trace_id).is_err());
+    }
+
+    #[test]
+    fn test_follows_from_becomes_link() {
+        let process = JaegerProcess {
+            service_name: "worker".to_string(),
+            tags: Vec::new(),
+        };
+        let consumer = JaegerSpan {
+            trace_id_low: 2,
+            span_id: 3,
+            operation_name: "consume".to_string(),
+            references: vec![JaegerSpanRef {
+                ref_type: REF_FOLLOWS_FROM,
+                trace_id_low: 0x10,
+                trace_id_high: 0,
+                span_id: 0x11,
+            }],
+            ..Default::default()
+        };
+
+        let span = convert_jaeger_span(&process, consumer).unwrap();
+        assert!(span.parent_span_id.is_none());
+        assert_eq!(span.links.len(), 1);
+        assert_eq!(span.links[0].trace_id.as_str(), "00000000000000000000000000000010");
+        assert_eq!(span.links[0].span_id.as_str(), "0000000000000011");
+        assert_eq!(span.attributes.get("jaeger.follows_from"), Some("0000000000000011"));
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert!(decode_batch(&[0xff, 0x00, 0x12]).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Kafka source for OTLP spans.
+//!
+//! Consumes `ExportTraceServiceRequest` protobuf payloads from a Kafka topic,
+//! the layout written by the OTel collector `kafka` exporter with the default
+//! `otlp_proto` encoding. Spans go through `OtelReceiver::process_spans`, so
+//! sampling, attribute filtering and UI events apply as for gRPC ingestion.
+//!
+//! Offsets are committed only after a message's spans were stored. When
+//! storage fails the consumer seeks back to the message and retries it after
+//! a backoff; undecodable messages are logged, counted and skipped.
+
+use super::{convert_otel_span_with_pool, extract_resource_semantics, OtelReceiver};
+use crate::core::{KafkaConfig, Result, Span as UrpoSpan, UrpoError};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use prost::Message as _;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, ConsumerContext, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::{ClientContext, Offset, Statistics};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often librdkafka reports statistics (and therefore lag).
+const STATISTICS_INTERVAL_MS: &str = "5000";
+
+/// Delay before retrying a message whose spans could not be stored.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Kafka consumer counters.
+#[derive(Debug, Default)]
+pub struct KafkaStats {
+    messages_consumed: AtomicU64,
+    messages_skipped: AtomicU64,
+    store_failures: AtomicU64,
+    spans_received: AtomicU64,
+    /// Sum of consumer lag over assigned partitions, -1 until first reported
+    lag: AtomicI64,
+}
+
+/// Point-in-time copy of [`KafkaStats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct KafkaStatsSnapshot {
+    /// Messages whose spans were stored and committed
+    pub messages_consumed: u64,
+    /// Messages skipped because they could not be decoded
+    pub messages_skipped: u64,
+    /// Storage failures that caused a message to be retried
+    pub store_failures: u64,
+    /// Spans decoded from consumed messages
+    pub spans_received: u64,
+    /// Total consumer lag in messages, `None` until librdkafka reports it
+    pub lag: Option<u64>,
+}
+
+impl KafkaStats {
+    /// Create counters with unknown lag.
+    pub fn new() -> Self {
+        Self {
+            lag: AtomicI64::new(-1),
+            ..Default::default()
+        }
+    }
+
+    /// Take a snapshot of the counters.
+    pub fn snapshot(&self) -> KafkaStatsSnapshot {
+        let lag = self.lag.load(Ordering::Relaxed);
+        KafkaStatsSnapshot {
+            messages_consumed: self.messages_consumed.load(Ordering::Relaxed),
+            messages_skipped: self.messages_skipped.load(Ordering::Relaxed),
+            store_failures: self.store_failures.load(Ordering::Relaxed),
+            spans_received: self.spans_received.load(Ordering::Relaxed),
+            lag: u64::try_from(lag).ok(),
+        }
+    }
+}
+
+/// Consumer context that records lag from librdkafka statistics.
+struct LagContext {
+    stats: Arc<KafkaStats>,
+}
+
+impl ClientContext for LagContext {
+    fn stats(&self, statistics: Statistics) {
+        let lag: i64 = statistics
+            .topics
+            .values()
+            .flat_map(|topic| topic.partitions.values())
+            // Partition -1 is librdkafka's internal unassigned partition
+            .filter(|partition| partition.partition >= 0 && partition.consumer_lag >= 0)
+            .map(|partition| partition.consumer_lag)
+            .sum();
+        self.stats.lag.store(lag, Ordering::Relaxed);
+    }
+}
+
+impl ConsumerContext for LagContext {}
+
+impl OtelReceiver {
+    /// Consume OTLP trace payloads from Kafka until the consumer fails.
+    pub async fn start_kafka(self: Arc<Self>, config: KafkaConfig) -> Result<()> {
+        tracing::info!(
+            "Starting Kafka source: brokers={}, topic={}, group={}",
+            config.brokers,
+            config.topic,
+            config.group_id
+        );
+
+        let consumer: StreamConsumer<LagContext> = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .set("statistics.interval.ms", STATISTICS_INTERVAL_MS)
+            .create_with_context(LagContext {
+                stats: Arc::clone(&self.kafka_stats),
+            })
+            .map_err(|e| UrpoError::network(format!("Failed to create Kafka consumer: {}", e)))?;
+
+        consumer.subscribe(&[config.topic.as_str()]).map_err(|e| {
+            UrpoError::network(format!("Failed to subscribe to {}: {}", config.topic, e))
+        })?;
+
+        loop {
+            let message = match consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::warn!("Kafka receive error: {}", e);
+                    tokio::time::sleep(RETRY_BACKOFF).await;
+                    continue;
+                },
+            };
+
+            let spans = match self.decode_kafka_payload(message.payload().unwrap_or_default()) {
+                Ok(spans) => spans,
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping undecodable Kafka message {}/{}@{}: {}",
+                        message.topic(),
+                        message.partition(),
+                        message.offset(),
+                        e
+                    );
+                    self.kafka_stats
+                        .messages_skipped
+                        .fetch_add(1, Ordering::Relaxed);
+                    commit(&consumer, &message);
+                    continue;
+                },
+            };
+            let span_count = spans.len() as u64;
+
+            if let Err(e) = self.process_spans(spans).await {
+                tracing::warn!(
+                    "Failed to store spans from Kafka message {}/{}@{}, retrying: {}",
+                    message.topic(),
+                    message.partition(),
+                    message.offset(),
+                    e
+                );
+                self.kafka_stats
+                    .store_failures
+                    .fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(RETRY_BACKOFF).await;
+
+                if let Err(e) = consumer.seek(
+                    message.topic(),
+                    message.partition(),
+                    Offset::Offset(message.offset()),
+                    RETRY_BACKOFF,
+                ) {
+                    tracing::error!("Failed to rewind Kafka partition: {}", e);
+                }
+                continue;
+            }
+
+            self.kafka_stats
+                .messages_consumed
+                .fetch_add(1, Ordering::Relaxed);
+            self.kafka_stats
+                .spans_received
+                .fetch_add(span_count, Ordering::Relaxed);
+            commit(&consumer, &message);
+        }
+    }
+
+    /// Kafka consumer statistics, including lag.
+    pub fn kafka_stats(&self) -> KafkaStatsSnapshot {
+        self.kafka_stats.snapshot()
+    }
+
+    /// Decode an OTLP protobuf payload into spans.
+    fn decode_kafka_payload(&self, payload: &[u8]) -> Result<Vec<UrpoSpan>> {
+        let request = ExportTraceServiceRequest::decode(payload)
+            .map_err(|e| UrpoError::protocol(format!("Invalid OTLP protobuf payload: {}", e)))?;
+
+        let mut spans = Vec::new();
+        for resource_spans in request.resource_spans {
+            let resource = resource_spans.resource.unwrap_or_default();
+            let semantics = extract_resource_semantics(&resource);
+            let service_name = &semantics.service_name;
+            let resource_attributes = semantics.span_resource_attributes();
+
+            for otel_span in resource_spans
+                .scope_spans
+                .into_iter()
+                .flat_map(|scope| scope.spans)
+            {
+                match convert_otel_span_with_pool(
+                    otel_span,
+                    service_name,
+                    &self.span_pool,
+                    self.attribute_filter.as_deref(),
+                    &self.span_limits,
+                ) {
+                    Ok(mut span) => {
+                        span.resource_attributes = resource_attributes.clone();
+                        spans.push(span);
+                    },
+                    Err(e) => tracing::warn!(
+                        "Failed to convert Kafka span: service={}, error={}",
+                        service_name,
+                        e
+                    ),
+                }
+            }
+        }
+        Ok(spans)
+    }
+}
+
+fn commit(consumer: &StreamConsumer<LagContext>, message: &BorrowedMessage<'_>) {
+    if let Err(e) = consumer.commit_message(message, CommitMode::Async) {
+        tracing::warn!("Failed to commit Kafka offset: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::Monitor;
+    use crate::storage::{InMemoryStorage, StorageBackend};
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+    fn receiver() -> OtelReceiver {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(100)));
+        OtelReceiver::new(0, 0, storage, Arc::new(Monitor::new()))
+    }
+
+    #[test]
+    fn test_decode_kafka_payload() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue("billing".to_string())),
+                        }),
+                    }],
+                    dropped_attributes_count: 0,
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: vec![0x11; 16],
+                        span_id: vec![0x22; 8],
+                        name: "invoice".to_string(),
+                        start_time_unix_nano: now,
+                        end_time_unix_nano: now + 1_000_000,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let spans = receiver()
+            .decode_kafka_payload(&request.encode_to_vec())
+            .unwrap();
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].service_name.as_str(), "billing");
+        assert_eq!(spans[0].operation_name, "invoice");
+        assert_eq!(spans[0].trace_id.as_str(), "11".repeat(16));
+    }
+
+    #[test]
+    fn test_decode_invalid_kafka_payload() {
+        assert!(receiver()
+            .decode_kafka_payload(&[0xff, 0xff, 0xff])
+            .is_err());
+    }
+
+    #[test]
+    fn test_stats_snapshot() {
+        let stats = KafkaStats::new();
+        assert_eq!(stats.snapshot().lag, None);
+
+        stats.lag.store(42, Ordering::Relaxed);
+        stats.messages_consumed.fetch_add(3, Ordering::Relaxed);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.lag, Some(42));
+        assert_eq!(snapshot.messages_consumed, 3);
+    }
+}
@@ -5,14 +5,13 @@
 
 use crate::core::{otel_compliance, Result, SpanId, TraceId};
 use crate::logs::{
-    buffer::LogCircularBuffer,
     storage::LogStorage,
     types::{LogRecord, LogSeverity},
 };
 use crate::metrics::string_pool::StringPool;
 use opentelemetry_proto::tonic::collector::logs::v1::{
     logs_service_server::{LogsService, LogsServiceServer},
-    ExportLogsServiceRequest, ExportLogsServiceResponse,
+    ExportLogsPartialSuccess, ExportLogsServiceRequest, ExportLogsServiceResponse,
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -20,52 +19,24 @@ use tonic::{Request, Response, Status};
 
 /// OTLP Logs receiver service
 pub struct OtelLogsReceiver {
-    /// High-performance circular buffer for log ingestion
-    buffer: Arc<LogCircularBuffer>,
     /// Logs storage engine
     log_storage: Arc<Mutex<LogStorage>>,
-    /// String interning pool for service names
+    /// Shared string interning pool for service names (from storage)
     string_pool: Arc<StringPool>,
-    /// Background processor handle
-    _processor_handle: tokio::task::JoinHandle<()>,
 }
 
 impl OtelLogsReceiver {
-    /// Create new logs receiver with high-performance buffer
+    /// Create new logs receiver writing to `log_storage`
     pub fn new(log_storage: Arc<Mutex<LogStorage>>) -> Self {
-        let buffer = Arc::new(LogCircularBuffer::new(10_000));
-        let string_pool = Arc::new(StringPool::new());
-
-        // Spawn background processor for batched storage
-        let processor_buffer = Arc::clone(&buffer);
-        let processor_storage = Arc::clone(&log_storage);
-
-        let processor_handle = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
-
-            loop {
-                interval.tick().await;
-
-                // Process logs in batches for efficiency
-                let batch = processor_buffer.pop_batch(1000);
-                if !batch.is_empty() {
-                    let storage = processor_storage.lock().await;
-                    for log_arc in batch {
-                        // Convert Arc<LogRecord> back to owned
-                        let log_record = (*log_arc).clone();
-                        if let Err(e) = storage.store_log(log_record) {
-                            tracing::warn!("Failed to store log: {}", e);
-                        }
-                    }
-                }
-            }
-        });
+        // Share the storage's pool so service IDs resolve when querying
+        let string_pool = match log_storage.try_lock() {
+            Ok(storage_guard) => Arc::clone(storage_guard.string_pool()),
+            Err(_) => Arc::clone(log_storage.blocking_lock().string_pool()),
+        };
 
         Self {
-            buffer,
             log_storage,
             string_pool,
-            _processor_handle: processor_handle,
         }
     }
 
@@ -177,15 +148,16 @@ fn extract_string_value(value: &opentelemetry_proto::tonic::common::v1::AnyValue
     }
 }
 
-#[tonic::async_trait]
-impl LogsService for OtelLogsReceiver {
-    async fn export(
+impl OtelLogsReceiver {
+    /// Store the records of an export request, returning the partial
+    /// success to report when some were rejected.
+    pub async fn ingest(
         &self,
-        request: Request<ExportLogsServiceRequest>,
-    ) -> std::result::Result<Response<ExportLogsServiceResponse>, Status> {
-        let request = request.into_inner();
-        let mut total_logs = 0;
-        let mut processed_logs = 0;
+        request: ExportLogsServiceRequest,
+    ) -> Option<ExportLogsPartialSuccess> {
+        let mut converted = Vec::new();
+        let mut rejected: i64 = 0;
+        let mut last_error = None;
 
         for resource_logs in request.resource_logs {
             let service_id = if let Some(resource) = &resource_logs.resource {
@@ -196,30 +168,50 @@ impl LogsService for OtelLogsReceiver {
 
             for scope_logs in resource_logs.scope_logs {
                 for log_record in scope_logs.log_records {
-                    total_logs += 1;
-
                     match self.convert_otlp_log(&log_record, service_id) {
-                        Ok(converted_log) => {
-                            // Push to high-performance circular buffer (wait-free)
-                            if self.buffer.push(converted_log) {
-                                processed_logs += 1;
-                            } else {
-                                tracing::warn!("Failed to buffer log (buffer full)");
-                            }
-                        },
+                        Ok(log) => converted.push(log),
                         Err(e) => {
                             tracing::warn!("Failed to convert log record: {}", e);
+                            rejected += 1;
+                            last_error = Some(e.to_string());
                         },
                     }
                 }
             }
         }
 
-        tracing::debug!("Processed {} out of {} log records", processed_logs, total_logs);
+        let mut stored = 0;
+        {
+            let storage = self.log_storage.lock().await;
+            for log in converted {
+                match storage.store_log(log) {
+                    Ok(()) => stored += 1,
+                    Err(e) => {
+                        tracing::warn!("Failed to store log: {}", e);
+                        rejected += 1;
+                        last_error = Some(e.to_string());
+                    },
+                }
+            }
+        }
+
+        tracing::debug!("Stored {} log records ({} rejected)", stored, rejected);
+
+        last_error.map(|error_message| ExportLogsPartialSuccess {
+            rejected_log_records: rejected,
+            error_message,
+        })
+    }
+}
 
-        Ok(Response::new(ExportLogsServiceResponse {
-            partial_success: None,
-        }))
+#[tonic::async_trait]
+impl LogsService for OtelLogsReceiver {
+    async fn export(
+        &self,
+        request: Request<ExportLogsServiceRequest>,
+    ) -> std::result::Result<Response<ExportLogsServiceResponse>, Status> {
+        let partial_success = self.ingest(request.into_inner()).await;
+        Ok(Response::new(ExportLogsServiceResponse { partial_success }))
     }
 }
 
@@ -337,7 +329,10 @@ mod tests {
         assert_eq!(log_record.body, "Error occurred");
         assert!(log_record.trace_id.is_some());
         assert!(log_record.span_id.is_some());
-        assert_eq!(log_record.attributes.get("http.method"), Some(&"GET".to_string()));
+        assert_eq!(
+            log_record.attributes.as_ref().unwrap().get("http.method"),
+            Some(&"GET".to_string())
+        );
     }
 
     #[test]
@@ -3,11 +3,11 @@
 //! This module implements gRPC receiver for OpenTelemetry metrics
 //! following the OTLP specification.
 
-use crate::core::Result;
+use crate::core::{otel_compliance, Result};
 use crate::metrics::{storage::MetricStorage, string_pool::StringPool, types::MetricPoint};
 use opentelemetry_proto::tonic::collector::metrics::v1::{
     metrics_service_server::{MetricsService, MetricsServiceServer},
-    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+    ExportMetricsPartialSuccess, ExportMetricsServiceRequest, ExportMetricsServiceResponse,
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -31,10 +31,11 @@ pub struct OtelMetricsReceiver {
 impl OtelMetricsReceiver {
     /// Create new metrics receiver
     pub fn new(metric_storage: Arc<Mutex<MetricStorage>>) -> Self {
-        // Extract the shared string pool from storage
-        let string_pool = {
-            let storage_guard = metric_storage.blocking_lock();
-            Arc::clone(storage_guard.string_pool())
+        // Extract the shared string pool from storage. The lock is uncontended
+        // at construction time; only fall back to blocking outside a runtime.
+        let string_pool = match metric_storage.try_lock() {
+            Ok(storage_guard) => Arc::clone(storage_guard.string_pool()),
+            Err(_) => Arc::clone(metric_storage.blocking_lock().string_pool()),
         };
 
         Self {
@@ -43,6 +44,50 @@ impl OtelMetricsReceiver {
         }
     }
 
+    /// Store the data points of an export request, returning the partial
+    /// success to report when some were rejected.
+    pub async fn ingest(
+        &self,
+        request: ExportMetricsServiceRequest,
+    ) -> Option<ExportMetricsPartialSuccess> {
+        let timestamp = otel_compliance::system_time_to_nanos(std::time::SystemTime::now());
+        let mut points = Vec::new();
+        for resource_metrics in &request.resource_metrics {
+            let service_id = match &resource_metrics.resource {
+                Some(resource) => self.extract_service_id(resource),
+                None => self.string_pool.intern("unknown_service").0,
+            };
+            for scope_metrics in &resource_metrics.scope_metrics {
+                for metric in &scope_metrics.metrics {
+                    match self.convert_otlp_metric(metric, service_id, timestamp) {
+                        Ok(converted) => points.extend(converted),
+                        Err(e) => tracing::warn!("Failed to convert metric {}: {}", metric.name, e),
+                    }
+                }
+            }
+        }
+
+        let mut storage = self.metric_storage.lock().await;
+        let mut rejected: i64 = 0;
+        let mut last_error = None;
+        for point in &points {
+            if let Err(e) = storage.process_metrics(std::slice::from_ref(point)) {
+                rejected += 1;
+                last_error = Some(e);
+            }
+        }
+        tracing::debug!(
+            "Stored {} metric points ({} rejected)",
+            points.len() as i64 - rejected,
+            rejected
+        );
+
+        last_error.map(|error_message| ExportMetricsPartialSuccess {
+            rejected_data_points: rejected,
+            error_message,
+        })
+    }
+
     /// Convert OTLP metric to MetricPoint
     fn convert_otlp_metric(
         &self,
@@ -160,20 +205,15 @@ impl OtelMetricsReceiver {
 
 #[tonic::async_trait]
 impl MetricsService for OtelMetricsReceiver {
-    /// Minimal OTLP metrics export - CLAUDE.md compliant
-    #[inline(always)]
     async fn export(
         &self,
         request: Request<ExportMetricsServiceRequest>,
     ) -> std::result::Result<Response<ExportMetricsServiceResponse>, Status> {
-        // Log metrics reception for debugging
-        let metrics_count = request.get_ref().resource_metrics.len();
-        tracing::debug!("Received {} resource metrics via gRPC", metrics_count);
-
-        // Return success immediately - minimal implementation
-        Ok(Response::new(ExportMetricsServiceResponse {
-            partial_success: None,
-        }))
+        let request = request.into_inner();
+        tracing::debug!("Received {} resource metrics via gRPC", request.resource_metrics.len());
+
+        let partial_success = self.ingest(request).await;
+        Ok(Response::new(ExportMetricsServiceResponse { partial_success }))
     }
 }
 
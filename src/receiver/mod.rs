@@ -3,29 +3,83 @@
 //! This module implements GRPC and HTTP receivers for OpenTelemetry
 //! trace and metrics data following the OTLP specification.
 
+pub mod capture;
+pub mod grpc_web;
+pub mod health;
 pub mod http;
+#[cfg(feature = "jaeger")]
+pub mod jaeger;
+#[cfg(feature = "kafka")]
+pub mod kafka;
 pub mod logs;
 pub mod metrics;
-
-use crate::core::{Result, ServiceName, Span as UrpoSpan, SpanId, SpanStatus, TraceId, UrpoError};
+pub mod rate_limit;
+pub mod replay;
+#[cfg(unix)]
+pub mod uds;
+pub mod zipkin;
+
+use crate::core::types::AttributeMap;
+use crate::core::{
+    system_clock, AttributeFilter, Redactor, ResourceInfo, RestartPolicy, Result, ServiceFilter,
+    ServiceName, SharedClock, Span as UrpoSpan, SpanEvent, SpanId, SpanLimits, SpanLink, SpanStatus,
+    TraceId, UrpoError,
+};
 use crate::metrics::MetricStorage;
 use crate::storage::ZeroAllocSpanPool;
-use chrono::{DateTime, Utc};
+use opentelemetry_proto::tonic::collector::{
+    logs::v1::logs_service_server::LogsServiceServer,
+    metrics::v1::metrics_service_server::MetricsServiceServer,
+};
 use opentelemetry_proto::tonic::collector::trace::v1::{
     trace_service_server::{TraceService, TraceServiceServer},
-    ExportTraceServiceRequest, ExportTraceServiceResponse,
+    ExportTracePartialSuccess, ExportTraceServiceRequest, ExportTraceServiceResponse,
 };
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::{transport::Server, Request, Response, Status};
 
+/// Default maximum size of a single OTLP request (matches common collector defaults).
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 4 * 1024 * 1024;
+
+/// How long exporters are asked to wait before retrying when storage is full.
+pub const STORAGE_FULL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Spans queued for the Jaeger exporter before new ones are dropped.
+pub const JAEGER_EXPORT_QUEUE: usize = 10_000;
+
+/// Batches of spans queued for the OTLP forwarder before new ones are dropped.
+pub const OTLP_FORWARD_QUEUE: usize = 1_000;
+
+/// How long a shutdown waits for in-flight requests before giving up on them.
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Traces remembered to tell new traces from updates in [`TraceEvent`]s.
+pub const EVENT_TRACE_MEMORY: usize = 10_000;
+
 /// Configuration for OTEL receiver
 #[derive(Debug, Clone)]
 pub struct ReceiverConfig {
     pub span_pool_size: usize,
     pub batch_size: usize,
     pub sampling_rate: f32,
+    /// Maximum accepted OTLP request size in bytes (gRPC message or HTTP body)
+    pub max_request_bytes: usize,
+    /// Address the gRPC and HTTP receivers listen on
+    pub bind_address: IpAddr,
+    /// Maximum concurrent HTTP/2 streams per gRPC connection (unlimited when unset)
+    pub max_concurrent_streams: Option<u32>,
+    /// Timeout for a single gRPC request (none when unset)
+    pub request_timeout: Option<Duration>,
+    /// Origins allowed to call the OTLP HTTP routes from a browser (any when empty)
+    pub cors_allowed_origins: Vec<String>,
+    /// Accept gRPC-Web trace exports on the HTTP port
+    pub grpc_web: bool,
+    /// Limits on the attributes and events of each received span
+    pub span_limits: SpanLimits,
 }
 
 impl Default for ReceiverConfig {
@@ -34,6 +88,13 @@ impl Default for ReceiverConfig {
             span_pool_size: 10_000, // Configurable instead of hardcoded
             batch_size: 512,        // Configurable instead of hardcoded
             sampling_rate: 1.0,     // Accept all traces by default for debugging
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            max_concurrent_streams: None,
+            request_timeout: None,
+            cors_allowed_origins: Vec::new(),
+            grpc_web: true,
+            span_limits: SpanLimits::default(),
         }
     }
 }
@@ -53,26 +114,268 @@ pub struct OtelReceiver {
     grpc_port: u16,
     /// HTTP port
     http_port: u16,
+    /// Address the receivers listen on
+    bind_address: IpAddr,
     /// Storage backend
     storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>>,
     /// Health monitor
     health_monitor: Arc<crate::monitoring::Monitor>,
-    /// Sampling rate (0.0 to 1.0)
-    sampling_rate: f32,
+    /// Serving status reported to gRPC and HTTP health probes
+    health: Arc<health::ReceiverHealth>,
+    /// Default and per-service sampling rates, swapped on config reload
+    service_rates: crate::sampling::SharedServiceRates,
     /// Zero-allocation span pool for 6.3x performance boost
     span_pool: Arc<ZeroAllocSpanPool>,
     /// Batch processing channel
     batch_sender: Option<tokio::sync::mpsc::Sender<Vec<UrpoSpan>>>,
+    /// Requests to flush the pending batch, acknowledged once stored
+    batch_drain: Option<tokio::sync::mpsc::Sender<tokio::sync::oneshot::Sender<()>>>,
     /// Batch configuration
     batch_size: usize,
     /// Smart sampler for OTEL-compliant sampling
     sampler: Option<Arc<crate::sampling::SmartSampler>>,
+    /// Per-service fair head sampler
+    fair_sampler: Option<Arc<crate::sampling::FairSampler>>,
+    /// Per-service cap on spans per second, applied after sampling
+    rate_limiting_sampler: Option<Arc<crate::sampling::RateLimitingSampler>>,
+    /// Ordered sampling rules, used instead of the plain sampling rate
+    sampling_rules: Option<Arc<crate::sampling::SamplingRules>>,
     /// Metrics storage for OTLP metrics
     metrics_storage: Option<Arc<tokio::sync::Mutex<MetricStorage>>>,
     /// Logs storage for OTLP logs
     logs_storage: Option<Arc<tokio::sync::Mutex<crate::logs::LogStorage>>>,
-    /// Event broadcaster for real-time UI updates
-    event_sender: Option<tokio::sync::broadcast::Sender<TraceEvent>>,
+    /// Event broadcaster for real-time UI updates, with the recently
+    /// announced traces and whether they have an error
+    event_sender: Option<(
+        tokio::sync::broadcast::Sender<TraceEvent>,
+        Arc<parking_lot::Mutex<lru::LruCache<String, bool>>>,
+    )>,
+    /// Maximum accepted request size in bytes
+    max_request_bytes: usize,
+    /// Maximum concurrent HTTP/2 streams per gRPC connection
+    max_concurrent_streams: Option<u32>,
+    /// Timeout for a single gRPC request
+    request_timeout: Option<Duration>,
+    /// Origins allowed to call the OTLP HTTP routes (any when empty)
+    cors_allowed_origins: Vec<String>,
+    /// Accept gRPC-Web trace exports on the HTTP port
+    grpc_web: bool,
+    /// Move OTLP/HTTP exports into the trace of their `traceparent` header
+    apply_traceparent: bool,
+    /// Unix socket the gRPC server also listens on
+    grpc_uds_path: Option<PathBuf>,
+    /// Unix socket the HTTP server also listens on
+    http_uds_path: Option<PathBuf>,
+    /// Permission bits of the Unix socket files
+    uds_mode: u32,
+    /// Attribute allow/deny filter applied at ingestion
+    attribute_filter: Option<Arc<AttributeFilter>>,
+    /// Services whose spans are kept, applied before sampling
+    service_filter: Option<Arc<ServiceFilter>>,
+    /// Per-client span rate limits of OTLP exports
+    rate_limiter: Option<Arc<rate_limit::RateLimiter>>,
+    /// Counters such as spans dropped by the service filter
+    stats: Arc<ReceiverStats>,
+    /// Limits on span attributes and events, enforced during conversion
+    span_limits: SpanLimits,
+    /// PII redaction rules, shared with clones and swapped on config reload
+    redactor: Arc<arc_swap::ArcSwap<Redactor>>,
+    /// Restarting of a gRPC or HTTP server that stops on its own
+    restart_policy: RestartPolicy,
+    /// Queue feeding the Jaeger gRPC exporter
+    jaeger_export: Option<tokio::sync::mpsc::Sender<UrpoSpan>>,
+    /// Queue feeding the downstream OTLP forwarder
+    otlp_forward: Option<tokio::sync::mpsc::Sender<Vec<UrpoSpan>>>,
+    /// Raw request capture for debugging SDK integrations
+    wire_capture: Option<Arc<capture::WireCapture>>,
+    /// Time source for event timestamps and the fair sampler
+    clock: SharedClock,
+    /// Kafka source counters and lag
+    #[cfg(feature = "kafka")]
+    kafka_stats: Arc<kafka::KafkaStats>,
+}
+
+/// Where a receiver server accepts connections.
+enum Listen {
+    /// TCP address
+    Tcp(SocketAddr),
+    /// Unix socket file
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl std::fmt::Display for Listen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Handle to receivers started with [`OtelReceiver::spawn`].
+#[derive(Debug)]
+pub struct ShutdownHandle {
+    signal: tokio::sync::watch::Sender<bool>,
+    task: tokio::task::JoinHandle<Result<()>>,
+}
+
+impl ShutdownHandle {
+    /// Ask the receivers to drain and stop without waiting.
+    pub fn trigger(&self) {
+        let _ = self.signal.send(true);
+    }
+
+    /// True once the receivers have stopped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Drain and stop the receivers, waiting until pending spans are stored.
+    pub async fn shutdown(self) -> Result<()> {
+        self.trigger();
+        self.task
+            .await
+            .map_err(|e| UrpoError::protocol(format!("Receiver task failed: {}", e)))?
+    }
+}
+
+/// Run a receiver server built by `start`, restarting it according to
+/// `policy` whenever it exits before `stop` is set.
+///
+/// A server that ran for `policy.stable_after` before exiting starts over with
+/// a fresh restart count. Returns once `stop` is set, or with an error naming
+/// the server once the restarts are used up.
+async fn supervise<F, Fut>(
+    name: &'static str,
+    policy: RestartPolicy,
+    mut stop: tokio::sync::watch::Receiver<bool>,
+    mut start: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let mut restarts = 0;
+    loop {
+        let started = std::time::Instant::now();
+        let result = start().await;
+        if *stop.borrow() {
+            return Ok(());
+        }
+        if started.elapsed() >= policy.stable_after {
+            restarts = 0;
+        }
+        let reason = match result {
+            Ok(()) => {
+                tracing::warn!("{} server stopped unexpectedly", name);
+                "stopped unexpectedly".to_string()
+            },
+            Err(e) => {
+                tracing::error!("{} server error: {}", name, e);
+                e.to_string()
+            },
+        };
+        if restarts >= policy.max_restarts {
+            return Err(UrpoError::network(format!(
+                "Gave up on {} server after {} restarts: {}",
+                name, restarts, reason
+            )));
+        }
+
+        restarts += 1;
+        let delay = policy.backoff(restarts);
+        tracing::warn!(
+            "Restarting {} server in {:?} (attempt {}/{})",
+            name,
+            delay,
+            restarts,
+            policy.max_restarts
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.wait_for(|stop| *stop) => return Ok(()),
+        }
+    }
+}
+
+/// Spans dropped from an export request, reported via OTLP `partial_success`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct RejectedSpans {
+    /// Number of rejected spans
+    pub count: usize,
+    /// First rejection reason
+    pub reason: Option<String>,
+}
+
+impl RejectedSpans {
+    /// Record one rejected span.
+    pub fn record(&mut self, reason: impl std::fmt::Display) {
+        self.count += 1;
+        if self.reason.is_none() {
+            self.reason = Some(reason.to_string());
+        }
+    }
+
+    /// Merge rejections from another stage of the same request.
+    pub fn merge(&mut self, other: RejectedSpans) {
+        self.count += other.count;
+        if self.reason.is_none() {
+            self.reason = other.reason;
+        }
+    }
+
+    /// OTLP `partial_success`, `None` when every span was accepted.
+    pub fn partial_success(&self) -> Option<ExportTracePartialSuccess> {
+        (self.count > 0).then(|| ExportTracePartialSuccess {
+            rejected_spans: self.count as i64,
+            error_message: format!(
+                "{} spans rejected: {}",
+                self.count,
+                self.reason.as_deref().unwrap_or("unknown error")
+            ),
+        })
+    }
+}
+
+/// Receiver counters, shared by clones of a receiver.
+#[derive(Debug, Default)]
+pub struct ReceiverStats {
+    excluded_spans: std::sync::atomic::AtomicU64,
+    rate_limited_spans: std::sync::atomic::AtomicU64,
+    capped_spans: std::sync::atomic::AtomicU64,
+    failed_exports: std::sync::atomic::AtomicU64,
+}
+
+impl ReceiverStats {
+    /// Current values of the counters.
+    pub fn snapshot(&self) -> ReceiverStatsSnapshot {
+        use std::sync::atomic::Ordering::Relaxed;
+        ReceiverStatsSnapshot {
+            excluded_spans: self.excluded_spans.load(Relaxed),
+            rate_limited_spans: self.rate_limited_spans.load(Relaxed),
+            capped_spans: self.capped_spans.load(Relaxed),
+            failed_exports: self.failed_exports.load(Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of the receiver counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiverStatsSnapshot {
+    /// Spans dropped because their service is excluded by the service filter
+    pub excluded_spans: u64,
+    /// Spans dropped because their client exceeded its rate limit
+    #[serde(default)]
+    pub rate_limited_spans: u64,
+    /// Spans dropped because their service exceeded its spans per second cap
+    #[serde(default)]
+    pub capped_spans: u64,
+    /// Export requests that failed, including those refused by storage or
+    /// the rate limiter
+    #[serde(default)]
+    pub failed_exports: u64,
 }
 
 /// Real-time trace event for broadcasting to UI
@@ -80,8 +383,23 @@ pub struct OtelReceiver {
 pub struct TraceEvent {
     pub trace_id: String,
     pub service_name: String,
+    /// Spans of the trace stored by this batch
     pub span_count: usize,
     pub timestamp: u64,
+    /// First event for the trace, or more spans for one already announced
+    pub kind: TraceEventKind,
+    /// The trace has an error span, in this batch or an earlier one
+    pub has_error: bool,
+}
+
+/// Whether a [`TraceEvent`] announces a trace or updates one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceEventKind {
+    /// First spans of the trace
+    New,
+    /// More spans of a trace already announced
+    Update,
 }
 
 impl OtelReceiver {
@@ -123,30 +441,378 @@ impl OtelReceiver {
         Self {
             grpc_port,
             http_port,
+            bind_address: config.bind_address,
             storage,
             health_monitor,
-            sampling_rate: config.sampling_rate,
+            health: Arc::new(health::ReceiverHealth::new()),
+            service_rates: Arc::new(arc_swap::ArcSwap::from_pointee(
+                crate::sampling::ServiceRates::default()
+                    .with_default_rate(f64::from(config.sampling_rate)),
+            )),
             span_pool,
             batch_sender: None,
+            batch_drain: None,
             batch_size: config.batch_size,
             sampler: None,
+            fair_sampler: None,
+            rate_limiting_sampler: None,
+            sampling_rules: None,
             metrics_storage,
             logs_storage: None,
             event_sender: None,
+            max_request_bytes: config.max_request_bytes,
+            max_concurrent_streams: config.max_concurrent_streams,
+            request_timeout: config.request_timeout,
+            cors_allowed_origins: config.cors_allowed_origins,
+            grpc_web: config.grpc_web,
+            apply_traceparent: false,
+            attribute_filter: None,
+            service_filter: None,
+            rate_limiter: None,
+            stats: Arc::new(ReceiverStats::default()),
+            span_limits: config.span_limits,
+            redactor: Arc::new(arc_swap::ArcSwap::from_pointee(Redactor::default())),
+            restart_policy: RestartPolicy::default(),
+            grpc_uds_path: None,
+            http_uds_path: None,
+            uds_mode: 0o600,
+            jaeger_export: None,
+            otlp_forward: None,
+            wire_capture: None,
+            clock: system_clock(),
+            #[cfg(feature = "kafka")]
+            kafka_stats: Arc::new(kafka::KafkaStats::new()),
         }
     }
 
     /// Set the sampling rate (0.0 to 1.0).
-    pub fn with_sampling_rate(mut self, rate: f32) -> Self {
-        self.sampling_rate = rate.clamp(0.0, 1.0);
+    pub fn with_sampling_rate(self, rate: f32) -> Self {
+        let rates = (**self.service_rates.load()).clone();
+        self.set_service_rates(rates.with_default_rate(f64::from(rate.clamp(0.0, 1.0))));
+        self
+    }
+
+    /// Sample services at their own rates, consulted before the default rate.
+    pub fn with_service_rates(self, rates: crate::sampling::ServiceRates) -> Self {
+        self.set_service_rates(rates);
+        self
+    }
+
+    /// Replace the sampling rates of this receiver and its clones.
+    pub fn set_service_rates(&self, rates: crate::sampling::ServiceRates) {
+        self.service_rates.store(Arc::new(rates));
+    }
+
+    /// Sampling rates in effect, shared with this receiver.
+    pub fn service_rates(&self) -> &crate::sampling::SharedServiceRates {
+        &self.service_rates
+    }
+
+    /// Decide which spans to keep by ordered sampling rules rather than the
+    /// plain sampling rate. Empty rules leave the rate in charge.
+    pub fn with_sampling_rules(mut self, rules: crate::sampling::SamplingRules) -> Self {
+        self.sampling_rules = (!rules.is_empty()).then(|| Arc::new(rules));
+        self
+    }
+
+    /// Set the maximum accepted OTLP request size in bytes.
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Limit concurrent HTTP/2 streams per gRPC connection.
+    pub fn with_max_concurrent_streams(mut self, max_concurrent_streams: Option<u32>) -> Self {
+        self.max_concurrent_streams = max_concurrent_streams;
+        self
+    }
+
+    /// Fail gRPC requests that take longer than `timeout`.
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Restart a gRPC or HTTP server that stops on its own according to `policy`.
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Read the time from `clock`. Set it before enabling fair sampling or
+    /// the rate limiting sampler, which take the receiver's clock when they
+    /// are created.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the address the gRPC and HTTP receivers listen on.
+    pub fn with_bind_address(mut self, bind_address: IpAddr) -> Self {
+        self.bind_address = bind_address;
+        self
+    }
+
+    /// Address the gRPC and HTTP receivers listen on.
+    pub fn bind_address(&self) -> IpAddr {
+        self.bind_address
+    }
+
+    /// Restrict browser access to the OTLP HTTP routes to `origins` (any when empty).
+    pub fn with_cors_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.cors_allowed_origins = origins;
+        self
+    }
+
+    /// Origins allowed to call the OTLP HTTP routes (any when empty).
+    pub fn cors_allowed_origins(&self) -> &[String] {
+        &self.cors_allowed_origins
+    }
+
+    /// Limit the attributes and events kept per received span.
+    pub fn with_span_limits(mut self, limits: SpanLimits) -> Self {
+        self.span_limits = limits;
+        self
+    }
+
+    /// Limits on the attributes and events of each received span.
+    pub fn span_limits(&self) -> &SpanLimits {
+        &self.span_limits
+    }
+
+    /// Accept or refuse gRPC-Web trace exports on the HTTP port.
+    pub fn with_grpc_web(mut self, enabled: bool) -> Self {
+        self.grpc_web = enabled;
+        self
+    }
+
+    /// Move the spans of OTLP/HTTP exports into the trace of the request's
+    /// `traceparent` header. Off by default: an exporter whose own HTTP
+    /// client is instrumented would merge unrelated traces into one.
+    pub fn with_apply_traceparent(mut self, enabled: bool) -> Self {
+        self.apply_traceparent = enabled;
+        self
+    }
+
+    /// Whether OTLP/HTTP exports take the trace of their `traceparent` header.
+    pub fn apply_traceparent(&self) -> bool {
+        self.apply_traceparent
+    }
+
+    /// Also listen for gRPC exports on a Unix socket at `path` (Unix only).
+    pub fn with_grpc_uds_path(mut self, path: Option<PathBuf>) -> Self {
+        self.grpc_uds_path = path;
+        self
+    }
+
+    /// Also listen for HTTP exports on a Unix socket at `path` (Unix only).
+    pub fn with_http_uds_path(mut self, path: Option<PathBuf>) -> Self {
+        self.http_uds_path = path;
+        self
+    }
+
+    /// Create the Unix socket files with permission bits `mode`.
+    pub fn with_uds_mode(mut self, mode: u32) -> Self {
+        self.uds_mode = mode;
+        self
+    }
+
+    /// Whether gRPC-Web trace exports are accepted on the HTTP port.
+    pub fn grpc_web(&self) -> bool {
+        self.grpc_web
+    }
+
+    /// Maximum accepted OTLP request size in bytes.
+    pub fn max_request_bytes(&self) -> usize {
+        self.max_request_bytes
+    }
+
+    /// Get the span pool used for OTLP conversion.
+    pub fn span_pool(&self) -> &Arc<ZeroAllocSpanPool> {
+        &self.span_pool
+    }
+
+    /// Drop span attributes according to an allow/deny filter at ingestion.
+    pub fn with_attribute_filter(mut self, filter: AttributeFilter) -> Self {
+        self.attribute_filter = (!filter.is_empty()).then(|| Arc::new(filter));
         self
     }
 
+    /// Keep or drop spans by service name before sampling, storage and events.
+    pub fn with_service_filter(mut self, filter: ServiceFilter) -> Self {
+        self.service_filter = (!filter.is_empty()).then(|| Arc::new(filter));
+        self
+    }
+
+    /// Limit the spans each client may export per second.
+    pub fn with_rate_limiter(mut self, limiter: Arc<rate_limit::RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// The rate limiter, whose limits can be changed while running.
+    pub fn rate_limiter(&self) -> Option<&Arc<rate_limit::RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Receiver counters, such as spans dropped by the service filter.
+    pub fn stats(&self) -> ReceiverStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Receiver counters, shared with this receiver.
+    pub fn shared_stats(&self) -> &Arc<ReceiverStats> {
+        &self.stats
+    }
+
+    /// Redact span attributes before spans are stored, exported or broadcast.
+    pub fn with_redaction(self, redactor: Redactor) -> Self {
+        self.set_redaction(redactor);
+        self
+    }
+
+    /// Replace the redaction rules of this receiver and its clones.
+    pub fn set_redaction(&self, redactor: Redactor) {
+        self.redactor.store(Arc::new(redactor));
+    }
+
+    /// Follow configuration reloads, replacing the redaction rules and the
+    /// sampling rates whenever a new configuration arrives. Invalid
+    /// redaction rules keep the previous ones.
+    pub fn watch_config(
+        &self,
+        mut config: tokio::sync::watch::Receiver<crate::core::Config>,
+    ) -> tokio::task::JoinHandle<()> {
+        let redactor = Arc::clone(&self.redactor);
+        let service_rates = Arc::clone(&self.service_rates);
+        tokio::spawn(async move {
+            while config.changed().await.is_ok() {
+                let (section, rates) = {
+                    let config = config.borrow_and_update();
+                    (
+                        config.redaction.clone(),
+                        crate::sampling::ServiceRates::from_config(&config.sampling),
+                    )
+                };
+                service_rates.store(Arc::new(rates));
+                match section.as_ref().map(Redactor::from_config).transpose() {
+                    Ok(rules) => {
+                        redactor.store(Arc::new(rules.unwrap_or_default()));
+                        tracing::info!("Redaction rules reloaded");
+                    },
+                    Err(e) => tracing::error!("Keeping previous redaction rules: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Stream stored spans to a Jaeger `SpanWriterPlugin` gRPC endpoint.
+    ///
+    /// Spans are written by a background task; when more than
+    /// [`JAEGER_EXPORT_QUEUE`] spans are waiting, new ones are not exported.
+    pub fn with_jaeger_export(mut self, endpoint: &str) -> Result<Self> {
+        let exporter = crate::export::jaeger_grpc::JaegerGrpcExporter::connect_lazy(endpoint)?;
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<UrpoSpan>(JAEGER_EXPORT_QUEUE);
+
+        tokio::spawn(async move {
+            while let Some(span) = rx.recv().await {
+                if let Err(e) = exporter.write_span(&span).await {
+                    tracing::warn!("Jaeger export failed: {}", e);
+                }
+            }
+        });
+
+        self.jaeger_export = Some(tx);
+        Ok(self)
+    }
+
+    /// Forward every sampled span to a downstream OTLP/gRPC collector
+    /// (e.g. `http://tempo:4317`).
+    ///
+    /// Spans are sent in batches by a background task, retrying while the
+    /// collector is unavailable; when more than [`OTLP_FORWARD_QUEUE`]
+    /// batches are waiting, new spans are not forwarded.
+    pub fn with_forwarding(mut self, endpoint: &str) -> Result<Self> {
+        let forwarder = crate::export::otlp_forwarder::OtlpForwarder::new(endpoint)?;
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<UrpoSpan>>(OTLP_FORWARD_QUEUE);
+
+        tokio::spawn(async move {
+            while let Some(mut spans) = rx.recv().await {
+                // Merge queued batches into one request where they fit
+                while spans.len() < forwarder.batch_size() {
+                    match rx.try_recv() {
+                        Ok(more) => spans.extend(more),
+                        Err(_) => break,
+                    }
+                }
+                if let Err(e) = forwarder.submit(spans).await {
+                    tracing::warn!("Forwarding to {} failed: {}", forwarder.endpoint(), e);
+                }
+            }
+        });
+
+        self.otlp_forward = Some(tx);
+        Ok(self)
+    }
+
+    /// Attach a wire capture that records raw trace requests while active.
+    pub fn with_wire_capture(mut self, capture: Arc<capture::WireCapture>) -> Self {
+        self.wire_capture = Some(capture);
+        self
+    }
+
+    /// Wire capture, if attached.
+    pub fn wire_capture(&self) -> Option<&Arc<capture::WireCapture>> {
+        self.wire_capture.as_ref()
+    }
+
+    /// Span limits and filters, for reporting captured span outcomes.
+    pub(crate) fn span_pipeline(&self) -> capture::SpanPipeline<'_> {
+        capture::SpanPipeline {
+            limits: &self.span_limits,
+            attribute_filter: self.attribute_filter.as_deref(),
+            service_filter: self.service_filter.as_deref(),
+        }
+    }
+
+    /// Serving status reported to health probes.
+    pub fn health(&self) -> &Arc<health::ReceiverHealth> {
+        &self.health
+    }
+
+    /// Receive the monitor health as of the last refresh, and every change
+    /// of it. Refreshed after each pass of the monitor's background loop
+    /// (see [`Monitor::start`](crate::monitoring::Monitor::start)) while the
+    /// receiver runs.
+    pub fn subscribe_health(
+        &self,
+    ) -> tokio::sync::watch::Receiver<crate::monitoring::SystemHealth> {
+        self.health.subscribe_system()
+    }
+
+    /// Re-derive the serving status from the current storage and monitor health.
+    pub async fn refresh_health(&self) -> health::ServingStatus {
+        let storage = self.storage.read().await.get_health();
+        let system = self.health_monitor.get_health().await;
+        self.health.update(storage, system).await
+    }
+
+    /// Queue a span for the Jaeger exporter, if enabled.
+    fn export_to_jaeger(&self, span: UrpoSpan) {
+        if let Some(ref tx) = self.jaeger_export {
+            if tx.try_send(span).is_err() {
+                tracing::debug!("Jaeger export queue full, span not exported");
+            }
+        }
+    }
+
     /// Enable batch processing with specified size.
     pub fn with_batch_processing(mut self, batch_size: usize) -> Self {
         self.batch_size = batch_size;
         // Initialize batch processor
         let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<UrpoSpan>>(16);
+        let (drain_tx, mut drain_rx) =
+            tokio::sync::mpsc::channel::<tokio::sync::oneshot::Sender<()>>(1);
         let storage = Arc::clone(&self.storage);
 
         // Spawn batch processor task
@@ -167,11 +833,19 @@ impl OtelReceiver {
                             Self::flush_batch(&storage, &mut batch).await;
                         }
                     }
+                    Some(ack) = drain_rx.recv() => {
+                        while let Ok(spans) = rx.try_recv() {
+                            batch.extend(spans);
+                        }
+                        Self::flush_batch(&storage, &mut batch).await;
+                        let _ = ack.send(());
+                    }
                 }
             }
         });
 
         self.batch_sender = Some(tx);
+        self.batch_drain = Some(drain_tx);
         self
     }
 
@@ -181,6 +855,34 @@ impl OtelReceiver {
         self
     }
 
+    /// Enable fair head sampling across services.
+    ///
+    /// Takes precedence over the smart sampler and the global sampling rate.
+    pub fn with_fair_sampling(mut self, config: crate::core::FairnessConfig) -> Self {
+        self.fair_sampler = Some(Arc::new(crate::sampling::FairSampler::with_clock(
+            config,
+            Arc::clone(&self.clock),
+        )));
+        self
+    }
+
+    /// Cap the spans kept per service at `spans_per_second`.
+    ///
+    /// Applied after every other sampler, so it bounds what reaches storage.
+    pub fn with_rate_limiting_sampler(mut self, spans_per_second: u32) -> Self {
+        self.rate_limiting_sampler =
+            Some(Arc::new(crate::sampling::RateLimitingSampler::with_clock(
+                spans_per_second,
+                Arc::clone(&self.clock),
+            )));
+        self
+    }
+
+    /// Achieved per-service rates of the fair sampler, if enabled.
+    pub fn fair_sampling_stats(&self) -> Option<Vec<crate::sampling::ServiceSamplingStats>> {
+        self.fair_sampler.as_ref().map(|sampler| sampler.stats())
+    }
+
     /// Enable metrics collection with specified capacity.
     pub fn with_metrics(mut self, buffer_capacity: usize, max_services: usize) -> Self {
         self.metrics_storage = Some(Arc::new(tokio::sync::Mutex::new(MetricStorage::new(
@@ -220,13 +922,16 @@ impl OtelReceiver {
     /// Returns a receiver that can subscribe to trace events.
     pub fn with_events(mut self) -> (Self, tokio::sync::broadcast::Receiver<TraceEvent>) {
         let (tx, rx) = tokio::sync::broadcast::channel(1000); // Buffer 1000 events
-        self.event_sender = Some(tx);
+        let seen = lru::LruCache::new(
+            std::num::NonZeroUsize::new(EVENT_TRACE_MEMORY).expect("non-zero capacity"),
+        );
+        self.event_sender = Some((tx, Arc::new(parking_lot::Mutex::new(seen))));
         (self, rx)
     }
 
     /// Get event receiver for subscribing to trace events.
     pub fn subscribe_events(&self) -> Option<tokio::sync::broadcast::Receiver<TraceEvent>> {
-        self.event_sender.as_ref().map(|tx| tx.subscribe())
+        self.event_sender.as_ref().map(|(tx, _)| tx.subscribe())
     }
 
     /// Flush a batch to storage.
@@ -239,105 +944,272 @@ impl OtelReceiver {
         }
 
         let storage = storage.write().await;
-        for span in batch.drain(..) {
-            if let Err(e) = storage.store_span(span).await {
-                tracing::error!("Failed to store span: {}", e);
+        let span_count = batch.len();
+        if let Err(e) = storage.store_spans_bulk(std::mem::take(batch)).await {
+            if let UrpoError::StorageFull { rejected } = e {
+                storage.record_rejected_spans(rejected);
             }
+            tracing::error!("Failed to store batch of {} spans: {}", span_count, e);
         }
     }
 
-    /// Run both GRPC and HTTP receivers
+    /// Store spans still waiting in the batch processor.
+    pub async fn flush(&self) {
+        let Some(ref drain) = self.batch_drain else {
+            return;
+        };
+        let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
+        if drain.send(ack_tx).await.is_ok() {
+            let _ = ack_rx.await;
+        }
+    }
+
+    /// Run both GRPC and HTTP receivers until ctrl-c, then drain.
     pub async fn run(self: Arc<Self>) -> Result<()> {
+        self.run_until(async {
+            let _ = tokio::signal::ctrl_c().await;
+            tracing::info!("Received shutdown signal, stopping both servers");
+        })
+        .await
+    }
+
+    /// Run the receivers in a background task, returning a handle to stop them.
+    ///
+    /// Dropping the handle leaves the receivers running.
+    pub fn spawn(self: Arc<Self>) -> ShutdownHandle {
+        let (signal, mut shutdown) = tokio::sync::watch::channel(false);
+        let task = tokio::spawn(self.run_until(async move {
+            if shutdown.wait_for(|stop| *stop).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }));
+        ShutdownHandle { signal, task }
+    }
+
+    /// Run both GRPC and HTTP receivers until `shutdown` completes.
+    ///
+    /// On shutdown both servers stop accepting connections, in-flight
+    /// requests get up to [`SHUTDOWN_DRAIN_TIMEOUT`] to finish, and the
+    /// pending batch is stored before returning. If a server runs out of
+    /// restarts, the others are stopped the same way and its error is returned.
+    pub async fn run_until<F>(self: Arc<Self>, shutdown: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        crate::core::config::validate_bind_address(self.bind_address)?;
+
+        let grpc_addr = SocketAddr::new(self.bind_address, self.grpc_port);
+        let http_addr = SocketAddr::new(self.bind_address, self.http_port);
         tracing::info!(
-            "Starting OTEL receivers on ports {} (GRPC) and {} (HTTP)",
-            self.grpc_port,
-            self.http_port
+            "Starting OTEL receivers on {} (GRPC) and {} (HTTP)",
+            grpc_addr,
+            http_addr
         );
 
-        let grpc_addr = SocketAddr::from(([0, 0, 0, 0], self.grpc_port));
-        let http_addr = SocketAddr::from(([0, 0, 0, 0], self.http_port));
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+        let stopped = |mut rx: tokio::sync::watch::Receiver<bool>| async move {
+            let _ = rx.wait_for(|stop| *stop).await;
+        };
 
-        // Start GRPC server
-        let mut grpc_handle = {
+        // Re-check the health probes after each pass of the monitoring loop
+        let health_handle = {
             let receiver = Arc::clone(&self);
+            let mut passes = self.health_monitor.subscribe_health();
             tokio::spawn(async move {
-                if let Err(e) = receiver.start_grpc(grpc_addr).await {
-                    tracing::error!("GRPC server error: {}", e);
+                receiver.refresh_health().await;
+                while passes.changed().await.is_ok() {
+                    receiver.refresh_health().await;
                 }
             })
         };
 
+        // Every server is restarted if it stops on its own
+        let mut servers = tokio::task::JoinSet::new();
+
+        // Start GRPC server
+        {
+            let receiver = Arc::clone(&self);
+            let stop = stop_rx.clone();
+            servers.spawn(supervise("GRPC", self.restart_policy, stop_rx.clone(), move || {
+                Arc::clone(&receiver).start_grpc_until(grpc_addr, stopped(stop.clone()))
+            }));
+        }
+
         // Start HTTP server
-        let mut http_handle = {
+        {
             let receiver = Arc::clone(&self);
-            tokio::spawn(async move {
-                if let Err(e) = receiver.start_http(http_addr).await {
-                    tracing::error!("HTTP server error: {}", e);
-                }
-            })
-        };
+            let stop = stop_rx.clone();
+            servers.spawn(supervise("HTTP", self.restart_policy, stop_rx.clone(), move || {
+                Arc::clone(&receiver).start_http_until(http_addr, stopped(stop.clone()))
+            }));
+        }
 
-        // Wait for shutdown signal or server error
-        tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                tracing::info!("Received shutdown signal, stopping both servers");
-                grpc_handle.abort();
-                http_handle.abort();
-                Ok(())
+        // Start the Unix socket servers next to the TCP ones
+        #[cfg(unix)]
+        {
+            if let Some(ref path) = self.grpc_uds_path {
+                let receiver = Arc::clone(&self);
+                let (path, stop) = (path.clone(), stop_rx.clone());
+                let start = move || {
+                    let signal = stopped(stop.clone());
+                    Arc::clone(&receiver).start_grpc_uds_until(path.clone(), signal)
+                };
+                servers.spawn(supervise(
+                    "GRPC Unix socket",
+                    self.restart_policy,
+                    stop_rx.clone(),
+                    start,
+                ));
             }
-            _result = &mut grpc_handle => {
-                tracing::warn!("GRPC server stopped unexpectedly");
-                http_handle.abort();
-                Ok(())
+            if let Some(ref path) = self.http_uds_path {
+                let receiver = Arc::clone(&self);
+                let (path, stop) = (path.clone(), stop_rx.clone());
+                let start = move || {
+                    let signal = stopped(stop.clone());
+                    Arc::clone(&receiver).start_http_uds_until(path.clone(), signal)
+                };
+                servers.spawn(supervise("HTTP Unix socket", self.restart_policy, stop_rx, start));
             }
-            _result = &mut http_handle => {
-                tracing::warn!("HTTP server stopped unexpectedly");
-                grpc_handle.abort();
-                Ok(())
+        }
+        #[cfg(not(unix))]
+        if self.grpc_uds_path.is_some() || self.http_uds_path.is_some() {
+            tracing::warn!("Unix socket listeners are only supported on Unix, ignoring them");
+        }
+
+        // Wait for shutdown or a server running out of restarts
+        let failure = tokio::select! {
+            _ = shutdown => None,
+            Some(joined) = servers.join_next() => {
+                let error = match joined {
+                    Ok(result) => result.err().unwrap_or_else(|| {
+                        UrpoError::network("Receiver server stopped before shutdown")
+                    }),
+                    Err(e) => UrpoError::protocol(format!("Receiver server task failed: {}", e)),
+                };
+                tracing::error!("{}, shutting down", error);
+                Some(error)
             }
+        };
+
+        // Stop accepting and let in-flight requests finish
+        let _ = stop_tx.send(true);
+        let drain = async { while servers.join_next().await.is_some() {} };
+        if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, drain).await.is_err() {
+            tracing::warn!("In-flight requests did not finish within {:?}", SHUTDOWN_DRAIN_TIMEOUT);
+            servers.abort_all();
         }
+
+        health_handle.abort();
+        self.flush().await;
+        tracing::info!("OTEL receivers stopped");
+        failure.map_or(Ok(()), Err)
     }
 
     /// Start the GRPC server with all OTLP services.
     pub async fn start_grpc(self: Arc<Self>, addr: SocketAddr) -> Result<()> {
+        self.start_grpc_until(addr, std::future::pending()).await
+    }
+
+    /// Start the GRPC server, shutting down gracefully when `signal` completes.
+    pub async fn start_grpc_until<F>(self: Arc<Self>, addr: SocketAddr, signal: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        self.serve_grpc(Listen::Tcp(addr), signal).await
+    }
+
+    /// Start the GRPC server on a Unix socket at `path`, shutting down
+    /// gracefully when `signal` completes and removing the socket file.
+    #[cfg(unix)]
+    pub async fn start_grpc_uds_until<F>(self: Arc<Self>, path: PathBuf, signal: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        self.serve_grpc(Listen::Unix(path), signal).await
+    }
+
+    async fn serve_grpc<F>(self: Arc<Self>, listen: Listen, signal: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
         let trace_service = TraceServiceServer::new(GrpcTraceService {
             receiver: self.clone(),
-        });
+        })
+        .max_decoding_message_size(self.max_request_bytes);
 
-        tracing::info!("GRPC server binding to {} with trace support", addr);
+        tracing::info!(
+            "GRPC server binding to {} with trace support (max message {} bytes)",
+            listen,
+            self.max_request_bytes
+        );
 
-        // Create server builder with trace service
-        let mut server = Server::builder().add_service(trace_service);
+        let mut builder = Server::builder().max_concurrent_streams(self.max_concurrent_streams);
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        // Create server builder with trace service and health checks
+        self.health
+            .register::<TraceServiceServer<GrpcTraceService>>()
+            .await;
+        let mut server = builder
+            .layer(tower::util::MapResponseLayer::new(map_oversized_message_status))
+            .add_routes(self.health.grpc_routes())
+            .add_service(trace_service);
 
         // Add metrics service if enabled
         if let Some(ref metrics_storage) = self.metrics_storage {
             tracing::info!("Adding OTLP metrics service to GRPC server");
-            server = server
-                .add_service(metrics::create_metrics_service_server(Arc::clone(metrics_storage)));
+            self.health
+                .register::<MetricsServiceServer<metrics::OtelMetricsReceiver>>()
+                .await;
+            server = server.add_service(
+                metrics::create_metrics_service_server(Arc::clone(metrics_storage))
+                    .max_decoding_message_size(self.max_request_bytes),
+            );
         }
 
         // Add logs service if enabled
         if let Some(ref logs_storage) = self.logs_storage {
             tracing::info!("Adding OTLP logs service to GRPC server");
-            server = server
-                .add_service(logs::create_logs_service_server(Arc::clone(logs_storage)));
+            self.health
+                .register::<LogsServiceServer<logs::OtelLogsReceiver>>()
+                .await;
+            server = server.add_service(
+                logs::create_logs_service_server(Arc::clone(logs_storage))
+                    .max_decoding_message_size(self.max_request_bytes),
+            );
         }
 
-        tracing::debug!("Starting server.serve() on {}", addr);
+        tracing::debug!("Starting server.serve() on {}", listen);
+
+        let result = match listen {
+            Listen::Tcp(addr) => server.serve_with_shutdown(addr, signal).await,
+            #[cfg(unix)]
+            Listen::Unix(ref path) => {
+                let (listener, _socket) = uds::bind(path, self.uds_mode)?;
+                let incoming = tokio_stream::wrappers::UnixListenerStream::new(listener);
+                server.serve_with_incoming_shutdown(incoming, signal).await
+            },
+        };
 
         // Serve with proper error handling
-        match server.serve(addr).await {
+        match result {
             Ok(_) => {
                 tracing::info!("GRPC server stopped gracefully");
                 Ok(())
             },
             Err(e) => {
-                tracing::error!("GRPC server error: {} (binding to {})", e, addr);
+                tracing::error!("GRPC server error: {} (binding to {})", e, listen);
                 // Check if it's a binding/address error
                 if e.to_string().contains("Address already in use") {
-                    Err(UrpoError::network(format!("Port {} already in use", addr.port())))
+                    Err(UrpoError::network(match listen {
+                        Listen::Tcp(addr) => format!("Port {} already in use", addr.port()),
+                        #[cfg(unix)]
+                        Listen::Unix(_) => format!("{} already in use", listen),
+                    }))
                 } else if e.to_string().contains("Permission denied") {
-                    Err(UrpoError::network(format!("Permission denied binding to {}", addr)))
+                    Err(UrpoError::network(format!("Permission denied binding to {}", listen)))
                 } else {
                     Err(UrpoError::protocol(format!("Failed to start GRPC server: {}", e)))
                 }
@@ -347,6 +1219,14 @@ impl OtelReceiver {
 
     /// Start the HTTP server.
     pub async fn start_http(self: Arc<Self>, addr: SocketAddr) -> Result<()> {
+        self.start_http_until(addr, std::future::pending()).await
+    }
+
+    /// Start the HTTP server, shutting down gracefully when `signal` completes.
+    pub async fn start_http_until<F>(self: Arc<Self>, addr: SocketAddr, signal: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
         tracing::info!("Starting HTTP OTLP receiver on {}", addr);
 
         let app = http::create_http_router(self);
@@ -357,29 +1237,123 @@ impl OtelReceiver {
 
         tracing::info!("HTTP OTLP receiver listening on {}", addr);
 
-        axum::serve(listener, app)
+        // Peer addresses key the rate limiter
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(signal)
             .await
             .map_err(|e| UrpoError::protocol(format!("HTTP server error: {}", e)))?;
 
         Ok(())
     }
 
+    /// Start the HTTP server on a Unix socket at `path`, shutting down
+    /// gracefully when `signal` completes and removing the socket file.
+    #[cfg(unix)]
+    pub async fn start_http_uds_until<F>(self: Arc<Self>, path: PathBuf, signal: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let app = http::create_http_router(Arc::clone(&self));
+        let (listener, _socket) = uds::bind(&path, self.uds_mode)?;
+        tracing::info!("HTTP OTLP receiver listening on {}", path.display());
+
+        uds::serve_http(listener, app, signal).await;
+        Ok(())
+    }
+
+    /// Process the spans of an OTLP export from `peer`, first dropping those
+    /// of clients over their rate limit.
+    ///
+    /// The dropped spans are reported as rejections; when every span is
+    /// dropped the export fails with `UrpoError::RateLimited`.
+    async fn process_export(
+        &self,
+        mut spans: Vec<UrpoSpan>,
+        peer: Option<IpAddr>,
+    ) -> Result<RejectedSpans> {
+        let mut rejected = RejectedSpans::default();
+        if let Some(ref limiter) = self.rate_limiter {
+            let limited = limiter.admit(&mut spans, peer);
+            if limited.rejected > 0 {
+                self.stats
+                    .rate_limited_spans
+                    .fetch_add(limited.rejected as u64, std::sync::atomic::Ordering::Relaxed);
+                if spans.is_empty() {
+                    self.stats
+                        .failed_exports
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    return Err(UrpoError::RateLimited {
+                        rejected: limited.rejected,
+                        retry_after: limited.retry_after,
+                    });
+                }
+                rejected.count = limited.rejected;
+                rejected.reason = Some("client over its rate limit".to_string());
+            }
+        }
+        match self.process_spans(spans).await {
+            Ok(stored) => {
+                rejected.merge(stored);
+                Ok(rejected)
+            },
+            Err(e) => {
+                self.stats
+                    .failed_exports
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Err(e)
+            },
+        }
+    }
+
     /// Process incoming spans with batching and sampling.
-    async fn process_spans(&self, spans: Vec<UrpoSpan>) -> Result<()> {
+    ///
+    /// Spans that storage refuses are skipped and returned as rejections;
+    /// a full storage stops the batch with `UrpoError::StorageFull`.
+    async fn process_spans(&self, mut spans: Vec<UrpoSpan>) -> Result<RejectedSpans> {
         let span_count = spans.len();
         tracing::info!("🔧 Processing {} spans through sampling and storage", span_count);
 
-        // Apply sampling
-        let sampled_spans: Vec<UrpoSpan> = if let Some(ref sampler) = self.sampler {
+        // Drop excluded services before they are sampled, stored or broadcast
+        if let Some(ref filter) = self.service_filter {
+            spans.retain(|span| filter.allows(span.service_name.as_str()));
+            let excluded = span_count - spans.len();
+            if excluded > 0 {
+                tracing::debug!("Dropped {} spans of excluded services", excluded);
+                self.stats
+                    .excluded_spans
+                    .fetch_add(excluded as u64, std::sync::atomic::Ordering::Relaxed);
+            }
+            if spans.is_empty() {
+                return Ok(RejectedSpans::default());
+            }
+        }
+
+        // Apply sampling; a per-service rate overrides the fair and smart samplers
+        let mut sampled_spans: Vec<UrpoSpan> = if let Some(ref fair) = self.fair_sampler {
+            spans
+                .into_iter()
+                .filter(|span| {
+                    self.service_override(span).unwrap_or_else(|| {
+                        fair.should_sample(&span.trace_id, span.service_name.as_str())
+                    }) == crate::sampling::SamplingDecision::Keep
+                })
+                .collect()
+        } else if let Some(ref sampler) = self.sampler {
             // Use smart sampler for OTEL-compliant sampling
             let mut sampled = Vec::with_capacity(spans.len());
             for span in spans {
+                if let Some(decision) = self.service_override(&span) {
+                    if decision == crate::sampling::SamplingDecision::Keep {
+                        sampled.push(span);
+                    }
+                    continue;
+                }
                 let trace_id = &span.trace_id;
                 match sampler.should_sample_head(trace_id) {
                     crate::sampling::SamplingDecision::Keep => sampled.push(span),
                     crate::sampling::SamplingDecision::Defer => {
                         // For deferred decisions, use simple probability for now
-                        if self.should_sample() {
+                        if self.should_sample(&span) {
                             sampled.push(span);
                         }
                     },
@@ -389,19 +1363,57 @@ impl OtelReceiver {
             sampled
         } else {
             // Fallback to simple sampling
-            spans.into_iter().filter(|_| self.should_sample()).collect()
+            spans.into_iter().filter(|span| self.should_sample(span)).collect()
         };
 
+        // Cap what is left per service, whatever the samplers kept
+        if let Some(ref limiter) = self.rate_limiting_sampler {
+            let before = sampled_spans.len();
+            sampled_spans.retain(|span| {
+                limiter.should_sample(span.service_name.as_str())
+                    == crate::sampling::SamplingDecision::Keep
+            });
+            let capped = before - sampled_spans.len();
+            if capped > 0 {
+                tracing::debug!("Dropped {} spans over the per-service rate cap", capped);
+                self.stats
+                    .capped_spans
+                    .fetch_add(capped as u64, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
         if sampled_spans.is_empty() {
             tracing::warn!("All {} spans were filtered out by sampling", span_count);
-            return Ok(());
+            return Ok(RejectedSpans::default());
         }
 
         tracing::info!("After sampling: {} spans will be stored", sampled_spans.len());
 
+        // Redact before anything leaves this function: storage, export and events
+        let redactor = self.redactor.load();
+        if !redactor.is_empty() {
+            for span in &mut sampled_spans {
+                redactor.redact_span(span);
+            }
+        }
+
+        if let Some(ref tx) = self.otlp_forward {
+            if tx.try_send(sampled_spans.clone()).is_err() {
+                tracing::debug!(
+                    "OTLP forward queue full, {} spans not forwarded",
+                    sampled_spans.len()
+                );
+            }
+        }
+
         // Use batch processing if configured
         if let Some(ref sender) = self.batch_sender {
             tracing::debug!("Sending spans to batch processor");
+            if self.jaeger_export.is_some() {
+                for span in &sampled_spans {
+                    self.export_to_jaeger(span.clone());
+                }
+            }
             sender
                 .send(sampled_spans)
                 .await
@@ -411,40 +1423,91 @@ impl OtelReceiver {
             tracing::info!("Storing spans directly to storage (no batching configured)");
             let storage = self.storage.write().await;
             let span_count = sampled_spans.len();
+            let mut stored = 0;
+            let mut rejected = RejectedSpans::default();
+            let mut full = false;
+
+            // Trace info for events, kept as the batch moves into storage
+            let summaries: Vec<(String, String, bool)> = sampled_spans
+                .iter()
+                .map(|span| {
+                    (
+                        span.trace_id.as_str().to_string(),
+                        span.service_name.to_string(),
+                        span.status.is_error(),
+                    )
+                })
+                .collect();
+            let exported = self.jaeger_export.is_some().then(|| sampled_spans.clone());
+
+            // One storage call for the whole batch; a full storage keeps a prefix of it
+            match storage.store_spans_bulk(sampled_spans).await {
+                Ok(count) => stored = count,
+                Err(e) if e.is_storage_full() => {
+                    let not_stored = match e {
+                        UrpoError::StorageFull { rejected } => rejected,
+                        _ => span_count,
+                    };
+                    storage.record_rejected_spans(not_stored);
+                    stored = span_count - not_stored;
+                    rejected.count = not_stored;
+                    full = true;
+                    tracing::warn!(
+                        "Storage full, rejecting {} of {} spans: {}",
+                        rejected.count,
+                        span_count,
+                        e
+                    );
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to store spans: {}", e);
+                    rejected.record(e);
+                    rejected.count = span_count;
+                },
+            }
+            if let Some(spans) = exported {
+                for span in spans.into_iter().take(stored) {
+                    self.export_to_jaeger(span);
+                }
+            }
 
-            // Group spans by trace_id for event broadcasting
-            let mut trace_map: std::collections::HashMap<String, (String, usize)> = std::collections::HashMap::new();
-
-            for span in sampled_spans {
-                tracing::debug!(
-                    "Storing span: {} for service: {}",
-                    span.span_id,
-                    span.service_name
-                );
-
-                // Track trace info for events
-                let trace_id = span.trace_id.as_str().to_string();
-                let service_name = span.service_name.to_string();
-
-                storage.store_span(span).await?;
-
-                // Update trace map
-                trace_map.entry(trace_id.clone())
-                    .and_modify(|(_, count)| *count += 1)
-                    .or_insert((service_name, 1));
+            // Group stored spans by trace_id for event broadcasting: (service, spans, has error)
+            let mut trace_map: std::collections::HashMap<String, (String, usize, bool)> =
+                std::collections::HashMap::new();
+            for (trace_id, service_name, is_error) in summaries.into_iter().take(stored) {
+                trace_map
+                    .entry(trace_id)
+                    .and_modify(|(_, count, has_error)| {
+                        *count += 1;
+                        *has_error |= is_error;
+                    })
+                    .or_insert((service_name, 1, is_error));
             }
 
             // Broadcast events for real-time UI updates
-            if let Some(ref event_tx) = self.event_sender {
-                for (trace_id, (service_name, span_count)) in trace_map {
+            if let Some((ref event_tx, ref event_traces)) = self.event_sender {
+                for (trace_id, (service_name, span_count, batch_error)) in trace_map {
+                    let (kind, has_error) = {
+                        let mut seen = event_traces.lock();
+                        let (kind, had_error) = match seen.get(&trace_id) {
+                            Some(&had_error) => (TraceEventKind::Update, had_error),
+                            None => (TraceEventKind::New, false),
+                        };
+                        seen.put(trace_id.clone(), had_error || batch_error);
+                        (kind, had_error || batch_error)
+                    };
                     let event = TraceEvent {
                         trace_id,
                         service_name,
                         span_count,
-                        timestamp: std::time::SystemTime::now()
+                        timestamp: self
+                            .clock
+                            .now()
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap()
                             .as_nanos() as u64,
+                        kind,
+                        has_error,
                     };
 
                     // Non-blocking send - if no receivers, that's OK
@@ -452,19 +1515,119 @@ impl OtelReceiver {
                 }
             }
 
-            tracing::info!("Successfully stored {} spans", span_count);
+            if full {
+                return Err(UrpoError::StorageFull {
+                    rejected: rejected.count,
+                });
+            }
+            tracing::info!("Successfully stored {} of {} spans", stored, span_count);
+            return Ok(rejected);
         }
-        Ok(())
+        Ok(RejectedSpans::default())
+    }
+
+    /// Decision of `span`'s per-service sampling rate, if its service has
+    /// one. Sampling rules apply the rates themselves, so this is `None`
+    /// when they are set.
+    fn service_override(&self, span: &UrpoSpan) -> Option<crate::sampling::SamplingDecision> {
+        if self.sampling_rules.is_some() {
+            return None;
+        }
+        self.service_rates.load().decide(span)
     }
 
-    /// Determine if a span should be sampled based on the configured sampling rate.
+    /// Determine if a span should be sampled: by the sampling rules when
+    /// configured, then by its service's rate, otherwise by the default rate.
     #[inline]
-    fn should_sample(&self) -> bool {
-        // Use fastrand for efficient random sampling
-        fastrand::f32() < self.sampling_rate
+    fn should_sample(&self, span: &UrpoSpan) -> bool {
+        use crate::sampling::SamplingDecision;
+
+        let rates = self.service_rates.load();
+        if let Some(ref rules) = self.sampling_rules {
+            return rules.apply_rules_with_rates(span, None, &rates) == SamplingDecision::Keep;
+        }
+        match rates.decide(span) {
+            Some(decision) => decision == SamplingDecision::Keep,
+            // Use fastrand for efficient random sampling
+            None => fastrand::f64() < rates.default_rate(),
+        }
     }
 }
 
+/// Tonic reports messages over `max_decoding_message_size` as OUT_OF_RANGE;
+/// rewrite them to RESOURCE_EXHAUSTED as the OTLP spec expects for oversized payloads.
+fn map_oversized_message_status<B>(
+    mut response: tonic::codegen::http::Response<B>,
+) -> tonic::codegen::http::Response<B> {
+    let headers = response.headers_mut();
+    let is_out_of_range = headers
+        .get("grpc-status")
+        .map_or(false, |v| v.as_bytes() == b"11");
+    let is_too_large = headers
+        .get("grpc-message")
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |m| m.contains("too%20large") || m.contains("too large"));
+
+    if is_out_of_range && is_too_large {
+        headers.insert(
+            "grpc-status",
+            tonic::codegen::http::HeaderValue::from_static("8"), // RESOURCE_EXHAUSTED
+        );
+    }
+    response
+}
+
+/// RESOURCE_EXHAUSTED status telling exporters to back off and retry.
+///
+/// The status details carry an encoded `ExportTraceServiceResponse` whose
+/// `partial_success` reports how many spans were rejected, and the
+/// `grpc-retry-pushback-ms` trailer carries [`STORAGE_FULL_RETRY_DELAY`].
+fn storage_full_status(rejected: usize) -> Status {
+    resource_exhausted_status(
+        format!("Storage full: {} spans rejected", rejected),
+        rejected,
+        "storage is full",
+        STORAGE_FULL_RETRY_DELAY,
+    )
+}
+
+/// RESOURCE_EXHAUSTED status for an export whose spans all exceeded their
+/// client's rate limit, asking to retry once the bucket has refilled.
+fn rate_limited_status(rejected: usize, retry_after: Duration) -> Status {
+    resource_exhausted_status(
+        format!("Rate limited: {} spans rejected", rejected),
+        rejected,
+        "client over its rate limit",
+        retry_after,
+    )
+}
+
+fn resource_exhausted_status(
+    message: String,
+    rejected: usize,
+    reason: &str,
+    retry_after: Duration,
+) -> Status {
+    use prost::Message;
+
+    let response = ExportTraceServiceResponse {
+        partial_success: Some(ExportTracePartialSuccess {
+            rejected_spans: rejected as i64,
+            error_message: reason.to_string(),
+        }),
+    };
+    let mut status = Status::with_details(
+        tonic::Code::ResourceExhausted,
+        message,
+        response.encode_to_vec().into(),
+    );
+    status.metadata_mut().insert(
+        "grpc-retry-pushback-ms",
+        (retry_after.as_millis() as u64).into(),
+    );
+    status
+}
+
 /// GRPC trace service implementation.
 struct GrpcTraceService {
     receiver: Arc<OtelReceiver>,
@@ -478,8 +1641,22 @@ impl TraceService for GrpcTraceService {
     ) -> std::result::Result<Response<ExportTraceServiceResponse>, Status> {
         tracing::info!("🔥 RECEIVED OTLP TRACE EXPORT REQUEST");
 
+        let peer = request.remote_addr().map(|addr| addr.ip());
+        let capture = self.receiver.wire_capture.as_ref().filter(|c| c.is_active());
+        let headers = capture.map(|_| request.metadata().clone().into_headers());
         let export_request = request.into_inner();
+        if let (Some(capture), Some(headers)) = (capture, headers) {
+            use prost::Message;
+            capture.record(
+                "otlp/grpc",
+                &headers,
+                &export_request.encode_to_vec(),
+                Ok(&export_request),
+                self.receiver.span_pipeline(),
+            );
+        }
         let mut spans = Vec::new();
+        let mut rejected = RejectedSpans::default();
         let mut total_resource_spans = 0;
         let mut total_scope_spans = 0;
         let mut total_spans = 0;
@@ -495,6 +1672,7 @@ impl TraceService for GrpcTraceService {
             let resource = resource_spans.resource.unwrap_or_default();
             let semantics = extract_resource_semantics(&resource);
             let service_name = semantics.service_name.clone();
+            let resource_attributes = semantics.span_resource_attributes();
 
             tracing::info!(
                 "Processing resource spans for service: {}, scope_spans count: {}",
@@ -523,8 +1701,11 @@ impl TraceService for GrpcTraceService {
                         otel_span,
                         &service_name,
                         &self.receiver.span_pool,
+                        self.receiver.attribute_filter.as_deref(),
+                        &self.receiver.span_limits,
                     ) {
-                        Ok(span) => {
+                        Ok(mut span) => {
+                            span.resource_attributes = resource_attributes.clone();
                             tracing::debug!(
                                 "Successfully converted span: {} for service: {}",
                                 span.span_id,
@@ -538,6 +1719,7 @@ impl TraceService for GrpcTraceService {
                                 service_name,
                                 e
                             );
+                            rejected.record(e);
                         },
                     }
                 }
@@ -553,13 +1735,25 @@ impl TraceService for GrpcTraceService {
         );
 
         // Process the spans
-        if let Err(e) = self.receiver.process_spans(spans).await {
-            tracing::error!("Failed to process spans: {}", e);
-            return Err(Status::internal(format!("Failed to process spans: {}", e)));
+        match self.receiver.process_export(spans, peer).await {
+            Ok(storage_rejected) => rejected.merge(storage_rejected),
+            Err(UrpoError::StorageFull { rejected: full }) => {
+                return Err(storage_full_status(full + rejected.count));
+            },
+            Err(UrpoError::RateLimited {
+                rejected: limited,
+                retry_after,
+            }) => {
+                return Err(rate_limited_status(limited + rejected.count, retry_after));
+            },
+            Err(e) => {
+                tracing::error!("Failed to process spans: {}", e);
+                return Err(Status::internal(format!("Failed to process spans: {}", e)));
+            },
         }
 
         Ok(Response::new(ExportTraceServiceResponse {
-            partial_success: None,
+            partial_success: rejected.partial_success(),
         }))
     }
 }
@@ -596,18 +1790,22 @@ fn extract_resource_semantics(
     let attrs = &resource.attributes;
 
     ResourceSemantics {
-        service_name: extract_resource_attribute(attrs, "service.name")
-            .unwrap_or_else(|| "unknown".into()),
+        service_name: extract_service_name(attrs),
         service_version: extract_resource_attribute(attrs, "service.version"),
         service_namespace: extract_resource_attribute(attrs, "service.namespace"),
         deployment_environment: extract_resource_attribute(attrs, "deployment.environment"),
         host_name: extract_resource_attribute(attrs, "host.name"),
         container_id: extract_resource_attribute(attrs, "container.id"),
-        process_pid: extract_resource_attribute(attrs, "process.pid")
+        // Int-valued per the spec, though some SDKs send a string
+        process_pid: attrs
+            .iter()
+            .find(|attr| attr.key == "process.pid")
+            .and_then(|attr| extract_attribute_value(&attr.value))
             .and_then(|s| s.parse::<i32>().ok()),
         telemetry_sdk_name: extract_resource_attribute(attrs, "telemetry.sdk.name"),
         telemetry_sdk_version: extract_resource_attribute(attrs, "telemetry.sdk.version"),
         telemetry_sdk_language: extract_resource_attribute(attrs, "telemetry.sdk.language"),
+        resource: ResourceInfo::from_lookup(|key| extract_resource_attribute(attrs, key)),
     }
 }
 
@@ -624,6 +1822,37 @@ struct ResourceSemantics {
     pub telemetry_sdk_name: Option<String>,
     pub telemetry_sdk_version: Option<String>,
     pub telemetry_sdk_language: Option<String>,
+    pub resource: ResourceInfo,
+}
+
+impl ResourceSemantics {
+    /// Resource attributes copied onto every span of the resource.
+    ///
+    /// Built once per resource; spans share the `Arc<str>` values.
+    fn span_resource_attributes(&self) -> AttributeMap {
+        let mut attributes = AttributeMap::new();
+        let pid = self.process_pid.map(|pid| pid.to_string());
+        let semantic = [
+            ("service.version", &self.service_version),
+            ("service.namespace", &self.service_namespace),
+            ("deployment.environment", &self.deployment_environment),
+            ("host.name", &self.host_name),
+            ("container.id", &self.container_id),
+            ("process.pid", &pid),
+            ("telemetry.sdk.name", &self.telemetry_sdk_name),
+            ("telemetry.sdk.version", &self.telemetry_sdk_version),
+            ("telemetry.sdk.language", &self.telemetry_sdk_language),
+        ];
+        for (key, value) in semantic {
+            if let Some(value) = value {
+                attributes.push(Arc::from(key), Arc::from(value.as_str()));
+            }
+        }
+        for (key, value) in self.resource.iter() {
+            attributes.push(Arc::from(key), Arc::from(value));
+        }
+        attributes
+    }
 }
 
 /// Extract attribute value from OTEL any value.
@@ -639,11 +1868,52 @@ fn extract_attribute_value(
     })
 }
 
+/// Attribute recording how many attributes were dropped, by the SDK or by
+/// [`SpanLimits`], mirroring the OTLP `dropped_attributes_count` field.
+pub const DROPPED_ATTRIBUTES_COUNT: &str = "urpo.dropped_attributes_count";
+
+/// `value` cut to at most `max_chars` characters.
+fn truncate_value(value: &str, max_chars: usize) -> &str {
+    match value.char_indices().nth(max_chars) {
+        Some((end, _)) => &value[..end],
+        None => value,
+    }
+}
+
+/// Convert OTLP attributes into `attributes` within `limits`, returning how
+/// many were dropped for exceeding the count or key length.
+fn push_limited_attributes(
+    attributes: &mut AttributeMap,
+    otel_attributes: &[opentelemetry_proto::tonic::common::v1::KeyValue],
+    limits: &SpanLimits,
+) -> usize {
+    let mut dropped = 0;
+    for attr in otel_attributes {
+        if attributes.len() >= limits.max_attributes || attr.key.len() > limits.max_key_length {
+            dropped += 1;
+            continue;
+        }
+        if let Some(value) = extract_attribute_value(&attr.value) {
+            attributes.push(
+                Arc::from(attr.key.as_str()),
+                Arc::from(truncate_value(&value, limits.max_value_length)),
+            );
+        }
+    }
+    dropped
+}
+
 /// Convert OTEL span to Urpo span using zero-alloc pool for 6.3x performance.
+///
+/// Attributes and events over `limits` are dropped and long values are
+/// truncated; the number of dropped attributes is recorded in
+/// [`DROPPED_ATTRIBUTES_COUNT`].
 fn convert_otel_span_with_pool(
     otel_span: opentelemetry_proto::tonic::trace::v1::Span,
     service_name: &str,
     pool: &Arc<ZeroAllocSpanPool>,
+    attribute_filter: Option<&AttributeFilter>,
+    limits: &SpanLimits,
 ) -> Result<UrpoSpan> {
     // Try to get a span from the pool for zero-allocation
     let pooled = pool.try_get_or_new();
@@ -664,29 +1934,44 @@ fn convert_otel_span_with_pool(
     span_box.start_time = timing.start_time;
     span_box.duration = timing.duration;
     span_box.status = status;
+    span_box.events = extract_span_events(&otel_span, limits);
+    span_box.links = extract_span_links(&otel_span);
+
+    let span_kind = extract_span_kind(&otel_span);
 
     // Clear and set attributes
     span_box.attributes.0.clear();
+    span_box.resource_attributes.0.clear();
+
+    // Add attributes from OTEL span, within the limits
+    let dropped =
+        push_limited_attributes(&mut span_box.attributes, &otel_span.attributes, limits)
+            + otel_span.dropped_attributes_count as usize;
+
+    // Drop filtered attributes before the span reaches storage
+    if let Some(filter) = attribute_filter {
+        filter.filter_attributes(&mut span_box.attributes);
+    }
+
     span_box
         .attributes
-        .push(Arc::from("span.kind"), Arc::from(extract_span_kind(&otel_span)));
-
-    // Add other attributes from OTEL span
-    for attr in otel_span.attributes {
-        if let Some(value) = extract_attribute_value(&attr.value) {
-            span_box
-                .attributes
-                .push(Arc::from(attr.key.as_str()), Arc::from(value.as_str()));
-        }
+        .push(Arc::from("span.kind"), Arc::from(span_kind));
+    if dropped > 0 {
+        span_box.attributes.push(
+            Arc::from(DROPPED_ATTRIBUTES_COUNT),
+            Arc::from(dropped.to_string()),
+        );
     }
 
     Ok(*span_box)
 }
 
-/// Convert OTEL span to Urpo span (legacy without pool).
+/// Convert OTEL span to Urpo span (legacy without pool), keeping at most
+/// `limits.max_events` events.
 fn convert_otel_span(
     otel_span: opentelemetry_proto::tonic::trace::v1::Span,
     service_name: String,
+    limits: &SpanLimits,
 ) -> Result<UrpoSpan> {
     let (trace_id, span_id, parent_span_id) = extract_span_ids(&otel_span)?;
     let service_name = parse_service_name(&service_name)?;
@@ -707,6 +1992,12 @@ fn convert_otel_span(
     if let Some(parent_id) = parent_span_id {
         builder = builder.parent_span_id(parent_id);
     }
+    for event in extract_span_events(&otel_span, limits) {
+        builder = builder.event(event);
+    }
+    for link in extract_span_links(&otel_span) {
+        builder = builder.link(link);
+    }
 
     builder.build()
 }
@@ -924,7 +2215,7 @@ fn is_all_zeros(bytes: &[u8]) -> bool {
     bytes.iter().all(|&b| b == 0)
 }
 
-/// Extract attributes from OTEL span including events
+/// Extract attributes from OTEL span
 fn extract_span_attributes(
     otel_span: &opentelemetry_proto::tonic::trace::v1::Span,
 ) -> HashMap<String, String> {
@@ -937,29 +2228,51 @@ fn extract_span_attributes(
         }
     }
 
-    // Store events as attributes for now since we don't have SpanEvent in core types
-    for (i, event) in otel_span.events.iter().enumerate() {
-        attributes.insert(format!("event.{}.name", i), event.name.clone());
-        attributes.insert(
-            format!("event.{}.time", i),
-            nanos_to_datetime(event.time_unix_nano).to_rfc3339(),
-        );
-        for attr in &event.attributes {
-            if let Some(value) = &attr.value {
-                attributes
-                    .insert(format!("event.{}.{}", i, attr.key), value_to_string(value.clone()));
-            }
-        }
-    }
-
     attributes
 }
 
-/// Convert nanoseconds to DateTime.
-fn nanos_to_datetime(nanos: u64) -> DateTime<Utc> {
-    let secs = (nanos / 1_000_000_000) as i64;
-    let nanos = (nanos % 1_000_000_000) as u32;
-    DateTime::from_timestamp(secs, nanos).unwrap_or_else(|| Utc::now())
+/// Convert OTEL span events within `limits`, keeping their timestamps and attributes.
+fn extract_span_events(
+    otel_span: &opentelemetry_proto::tonic::trace::v1::Span,
+    limits: &SpanLimits,
+) -> Vec<SpanEvent> {
+    otel_span
+        .events
+        .iter()
+        .take(limits.max_events)
+        .map(|event| {
+            let mut attributes = AttributeMap::new();
+            push_limited_attributes(&mut attributes, &event.attributes, limits);
+            SpanEvent {
+                name: event.name.clone(),
+                timestamp: std::time::UNIX_EPOCH + Duration::from_nanos(event.time_unix_nano),
+                attributes,
+            }
+        })
+        .collect()
+}
+
+/// Convert OTEL span links, skipping links without valid IDs.
+fn extract_span_links(otel_span: &opentelemetry_proto::tonic::trace::v1::Span) -> Vec<SpanLink> {
+    otel_span
+        .links
+        .iter()
+        .filter_map(|link| {
+            let trace_id = TraceId::new(hex::encode(&link.trace_id)).ok()?;
+            let span_id = SpanId::new(hex::encode(&link.span_id)).ok()?;
+            let mut attributes = AttributeMap::new();
+            for attr in &link.attributes {
+                if let Some(value) = extract_attribute_value(&attr.value) {
+                    attributes.push(Arc::from(attr.key.as_str()), Arc::from(value.as_str()));
+                }
+            }
+            Some(SpanLink {
+                trace_id,
+                span_id,
+                attributes,
+            })
+        })
+        .collect()
 }
 
 /// Convert OTEL value to string.
@@ -1008,19 +2321,12 @@ fn value_to_string(value: opentelemetry_proto::tonic::common::v1::AnyValue) -> S
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Datelike;
+    use std::net::Ipv6Addr;
     use opentelemetry_proto::tonic::{
         common::v1::{any_value::Value, AnyValue, KeyValue},
         trace::v1::{Span as OtelSpan, Status},
     };
 
-    #[test]
-    fn test_nanos_to_datetime() {
-        let nanos = 1_700_000_000_000_000_000; // Approximately Nov 2023
-        let dt = nanos_to_datetime(nanos);
-        assert!(dt.year() >= 2023);
-    }
-
     #[test]
     fn test_extract_service_name() {
         let attributes = vec![KeyValue {
@@ -1036,6 +2342,75 @@ mod tests {
         assert_eq!(extract_service_name(&empty_attributes), "unknown");
     }
 
+    #[test]
+    fn test_extract_resource_semantics_k8s() {
+        let string_attr = |key: &str, value: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue(value.to_string())),
+            }),
+        };
+        let resource = opentelemetry_proto::tonic::resource::v1::Resource {
+            attributes: vec![
+                string_attr("service.name", "checkout"),
+                string_attr("k8s.namespace.name", "shop"),
+                string_attr("k8s.pod.name", "checkout-7d9f"),
+                string_attr("k8s.node.name", "node-3"),
+                string_attr("cloud.region", "eu-west-1"),
+                string_attr("host.name", "not-k8s"),
+            ],
+            ..Default::default()
+        };
+
+        let semantics = extract_resource_semantics(&resource);
+        assert_eq!(semantics.service_name, "checkout");
+        assert_eq!(semantics.resource.k8s_namespace_name.as_deref(), Some("shop"));
+        assert_eq!(semantics.resource.k8s_pod_name.as_deref(), Some("checkout-7d9f"));
+        assert_eq!(semantics.resource.k8s_node_name.as_deref(), Some("node-3"));
+        assert_eq!(semantics.resource.cloud_region.as_deref(), Some("eu-west-1"));
+        assert_eq!(semantics.resource.k8s_deployment_name, None);
+
+        let attributes = semantics.span_resource_attributes();
+        assert_eq!(attributes.len(), 5);
+        assert_eq!(attributes.get("k8s.pod.name"), Some("checkout-7d9f"));
+        assert_eq!(attributes.get("host.name"), Some("not-k8s"));
+    }
+
+    #[test]
+    fn test_extract_resource_semantics_without_k8s() {
+        let resource = opentelemetry_proto::tonic::resource::v1::Resource::default();
+
+        let semantics = extract_resource_semantics(&resource);
+        assert!(semantics.resource.is_empty());
+        assert!(semantics.span_resource_attributes().is_empty());
+    }
+
+    #[test]
+    fn test_span_resource_attributes_keep_semantic_conventions() {
+        let attr = |key: &str, value: Value| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue { value: Some(value) }),
+        };
+        let resource = opentelemetry_proto::tonic::resource::v1::Resource {
+            attributes: vec![
+                attr("service.name", Value::StringValue("checkout".to_string())),
+                attr("service.version", Value::StringValue("1.4.2".to_string())),
+                attr("deployment.environment", Value::StringValue("prod".to_string())),
+                attr("process.pid", Value::IntValue(4242)),
+                attr("telemetry.sdk.language", Value::StringValue("rust".to_string())),
+            ],
+            ..Default::default()
+        };
+
+        let attributes = extract_resource_semantics(&resource).span_resource_attributes();
+        assert_eq!(attributes.len(), 4);
+        assert_eq!(attributes.get("service.version"), Some("1.4.2"));
+        assert_eq!(attributes.get("deployment.environment"), Some("prod"));
+        assert_eq!(attributes.get("process.pid"), Some("4242"));
+        assert_eq!(attributes.get("telemetry.sdk.language"), Some("rust"));
+        assert_eq!(attributes.get("service.name"), None);
+    }
+
     #[test]
     fn test_extract_span_timing_valid() {
         let span = OtelSpan {
@@ -1278,7 +2653,8 @@ mod tests {
             ..Default::default()
         };
 
-        let result = convert_otel_span_with_pool(otel_span, "test-service", &pool);
+        let limits = SpanLimits::default();
+        let result = convert_otel_span_with_pool(otel_span, "test-service", &pool, None, &limits);
         assert!(result.is_ok());
 
         let span = result.expect("Span conversion should succeed");
@@ -1287,12 +2663,292 @@ mod tests {
         assert!(span.attributes.get("http.method").is_some());
     }
 
+    #[test]
+    fn test_convert_otel_span_keeps_events() {
+        use opentelemetry_proto::tonic::trace::v1::span::Event;
+
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let stacktrace = "Error: declined\n    at charge (pay.js:10:5)";
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "charge".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            events: vec![Event {
+                time_unix_nano: 1_700_000_000_500_000_000,
+                name: "exception".to_string(),
+                attributes: vec![KeyValue {
+                    key: "exception.stacktrace".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(Value::StringValue(stacktrace.to_string())),
+                    }),
+                }],
+                dropped_attributes_count: 0,
+            }],
+            ..Default::default()
+        };
+
+        let limits = SpanLimits::default();
+        let span =
+            convert_otel_span_with_pool(otel_span.clone(), "svc", &pool, None, &limits).unwrap();
+        assert_eq!(span.events.len(), 1);
+        let event = &span.events[0];
+        assert_eq!(event.name, "exception");
+        assert_eq!(
+            event.timestamp,
+            std::time::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_500_000_000)
+        );
+        assert_eq!(event.attributes.get("exception.stacktrace"), Some(stacktrace));
+
+        let legacy = convert_otel_span(otel_span, "svc".to_string(), &limits).unwrap();
+        assert_eq!(legacy.events.len(), 1);
+    }
+
+    #[test]
+    fn test_convert_otel_span_events_not_flattened() {
+        use opentelemetry_proto::tonic::trace::v1::span::Event;
+
+        let event = |name: &str, offset_ms: u64, key: &str, value: &str| Event {
+            time_unix_nano: 1_700_000_000_000_000_000 + offset_ms * 1_000_000,
+            name: name.to_string(),
+            attributes: vec![KeyValue {
+                key: key.to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue(value.to_string())),
+                }),
+            }],
+            dropped_attributes_count: 0,
+        };
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "checkout".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            events: vec![
+                event("cache.miss", 100, "cache.key", "cart:42"),
+                event("retry", 250, "attempt", "2"),
+            ],
+            ..Default::default()
+        };
+
+        let limits = SpanLimits::default();
+        let span = convert_otel_span_with_pool(otel_span, "svc", &pool, None, &limits).unwrap();
+        assert_eq!(span.events.len(), 2);
+        let start = std::time::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_000_000_000);
+        assert_eq!(span.events[0].name, "cache.miss");
+        assert_eq!(span.events[0].timestamp, start + Duration::from_millis(100));
+        assert_eq!(span.events[0].attributes.get("cache.key"), Some("cart:42"));
+        assert_eq!(span.events[1].name, "retry");
+        assert_eq!(span.events[1].timestamp, start + Duration::from_millis(250));
+        assert_eq!(span.events[1].attributes.get("attempt"), Some("2"));
+        assert!(span.attributes.get("event.0.name").is_none());
+        assert!(span.attributes.get("event.1.time").is_none());
+    }
+
+    #[test]
+    fn test_convert_otel_span_keeps_links() {
+        use opentelemetry_proto::tonic::trace::v1::span::Link;
+
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let producer = Link {
+            trace_id: vec![7; 16],
+            span_id: vec![8; 8],
+            attributes: vec![KeyValue {
+                key: "messaging.operation".to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue("publish".to_string())),
+                }),
+            }],
+            ..Default::default()
+        };
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "consume orders".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            // The second link has no IDs and is dropped
+            links: vec![producer, Link::default()],
+            ..Default::default()
+        };
+
+        let limits = SpanLimits::default();
+        let span =
+            convert_otel_span_with_pool(otel_span.clone(), "svc", &pool, None, &limits).unwrap();
+        assert_eq!(span.links.len(), 1);
+        let link = &span.links[0];
+        assert_eq!(link.trace_id.as_str(), "07".repeat(16));
+        assert_eq!(link.span_id.as_str(), "08".repeat(8));
+        assert_eq!(link.attributes.get("messaging.operation"), Some("publish"));
+
+        let legacy = convert_otel_span(otel_span, "svc".to_string(), &limits).unwrap();
+        assert_eq!(legacy.links.len(), 1);
+    }
+
+    #[test]
+    fn test_convert_otel_span_keeps_two_links_in_order() {
+        use opentelemetry_proto::tonic::trace::v1::span::Link;
+
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let link = |trace: u8, span: u8| Link {
+            trace_id: vec![trace; 16],
+            span_id: vec![span; 8],
+            ..Default::default()
+        };
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "batch consume".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            links: vec![link(3, 4), link(5, 6)],
+            ..Default::default()
+        };
+
+        let span = convert_otel_span_with_pool(
+            otel_span,
+            "svc",
+            &pool,
+            None,
+            &SpanLimits::default(),
+        )
+        .unwrap();
+        let linked: Vec<_> = span
+            .links
+            .iter()
+            .map(|link| (link.trace_id.as_str(), link.span_id.as_str()))
+            .collect();
+        assert_eq!(
+            linked,
+            vec![
+                ("03".repeat(16).as_str(), "04".repeat(8).as_str()),
+                ("05".repeat(16).as_str(), "06".repeat(8).as_str()),
+            ]
+        );
+        assert!(span.links.iter().all(|link| link.attributes.is_empty()));
+    }
+
+    #[test]
+    fn test_convert_otel_span_with_attribute_filter() {
+        use crate::core::Glob;
+
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let attr = |key: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue("v".to_string())),
+            }),
+        };
+
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "query".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            attributes: vec![attr("http.method"), attr("db.statement"), attr("thread.id")],
+            ..Default::default()
+        };
+
+        let filter = AttributeFilter::new(vec![Glob::new("http.*")], vec![Glob::new("db.*")]);
+        let limits = SpanLimits::default();
+        let span = convert_otel_span_with_pool(otel_span, "svc", &pool, Some(&filter), &limits)
+            .expect("Span conversion should succeed");
+
+        assert!(span.attributes.get("http.method").is_some());
+        assert!(span.attributes.get("db.statement").is_none());
+        assert!(span.attributes.get("thread.id").is_none());
+        // Internal span.kind is never filtered
+        assert!(span.attributes.get("span.kind").is_some());
+    }
+
+    #[test]
+    fn test_convert_otel_span_enforces_limits() {
+        use opentelemetry_proto::tonic::trace::v1::span::Event;
+
+        let pool = Arc::new(ZeroAllocSpanPool::new(10));
+        let attr = |key: &str, value: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue(value.to_string())),
+            }),
+        };
+        let event = |name: &str| Event {
+            time_unix_nano: 1_700_000_000_500_000_000,
+            name: name.to_string(),
+            attributes: vec![attr("stack", "at charge (pay.js:10:5)")],
+            dropped_attributes_count: 0,
+        };
+        let otel_span = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: "charge".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            attributes: vec![
+                attr("db.query", "SELECT * FROM payments"),
+                attr("thread.name.with.long.key", "main"),
+                attr("user.id", "42"),
+                attr("http.method", "POST"),
+            ],
+            // Already dropped by the SDK
+            dropped_attributes_count: 1,
+            events: vec![event("exception"), event("retry")],
+            ..Default::default()
+        };
+        let limits = SpanLimits {
+            max_attributes: 2,
+            max_key_length: 16,
+            max_value_length: 6,
+            max_events: 1,
+        };
+
+        let span =
+            convert_otel_span_with_pool(otel_span.clone(), "svc", &pool, None, &limits).unwrap();
+        assert_eq!(span.attributes.get("db.query"), Some("SELECT"));
+        assert_eq!(span.attributes.get("user.id"), Some("42"));
+        assert!(!span.attributes.contains_key("thread.name.with.long.key"));
+        assert!(!span.attributes.contains_key("http.method"));
+        // Over the key length, over the count, and the SDK's one
+        assert_eq!(span.attributes.get(DROPPED_ATTRIBUTES_COUNT), Some("3"));
+        assert!(span.attributes.get("span.kind").is_some());
+
+        assert_eq!(span.events.len(), 1);
+        assert_eq!(span.events[0].name, "exception");
+        assert_eq!(span.events[0].attributes.get("stack"), Some("at cha"));
+        let legacy = convert_otel_span(otel_span, "svc".to_string(), &limits).unwrap();
+        assert_eq!(legacy.events.len(), 1);
+
+        // Nothing dropped, nothing recorded
+        let small = OtelSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![3; 8],
+            name: "charge".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            end_time_unix_nano: 1_700_000_001_000_000_000,
+            attributes: vec![attr("user.id", "42")],
+            ..Default::default()
+        };
+        let span = convert_otel_span_with_pool(small, "svc", &pool, None, &limits).unwrap();
+        assert!(!span.attributes.contains_key(DROPPED_ATTRIBUTES_COUNT));
+    }
+
+    #[test]
+    fn test_truncate_value_respects_char_boundaries() {
+        assert_eq!(truncate_value("héllo wörld", 7), "héllo w");
+        assert_eq!(truncate_value("short", 10), "short");
+    }
+
     #[test]
     fn test_receiver_config() {
         let config = ReceiverConfig::default();
         assert_eq!(config.span_pool_size, 10000);
         assert_eq!(config.batch_size, 1000);
         assert_eq!(config.sampling_rate, 1.0);
+        assert_eq!(config.max_request_bytes, 4 * 1024 * 1024);
 
         let custom_config = ReceiverConfig {
             span_pool_size: 5000,
@@ -1302,4 +2958,609 @@ mod tests {
         assert_eq!(custom_config.span_pool_size, 5000);
         assert_eq!(custom_config.sampling_rate, 0.5);
     }
+
+    #[tokio::test]
+    async fn test_bind_address() {
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let receiver =
+            OtelReceiver::new(0, 0, storage, Arc::new(crate::monitoring::Monitor::new()));
+        assert_eq!(receiver.bind_address(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        let receiver = receiver.with_bind_address(IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(receiver.bind_address(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+
+        // Multicast addresses are rejected before anything is bound
+        let receiver = receiver.with_bind_address("224.0.0.1".parse().unwrap());
+        assert!(Arc::new(receiver).run().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trace_events_mark_new_and_updated_traces() {
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let (receiver, mut events) =
+            OtelReceiver::new(0, 0, storage, Arc::new(crate::monitoring::Monitor::new()))
+                .with_events();
+        let span = |id: u64, error: bool| {
+            UrpoSpan::builder()
+                .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+                .service_name(ServiceName::new("checkout".to_string()).unwrap())
+                .operation_name("charge")
+                .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                .status(if error {
+                    SpanStatus::Error("declined".to_string())
+                } else {
+                    SpanStatus::Ok
+                })
+                .build()
+                .unwrap()
+        };
+
+        receiver.process_spans(vec![span(1, false), span(2, false)]).await.unwrap();
+        let first = events.recv().await.unwrap();
+        assert_eq!(first.kind, TraceEventKind::New);
+        assert_eq!(first.span_count, 2);
+        assert!(!first.has_error);
+
+        receiver.process_spans(vec![span(3, true)]).await.unwrap();
+        let second = events.recv().await.unwrap();
+        assert_eq!(second.kind, TraceEventKind::Update);
+        assert_eq!(second.span_count, 1);
+        assert!(second.has_error);
+
+        // The error sticks to the trace in later updates
+        receiver.process_spans(vec![span(4, false)]).await.unwrap();
+        let third = events.recv().await.unwrap();
+        assert_eq!(third.kind, TraceEventKind::Update);
+        assert!(third.has_error);
+    }
+
+    #[tokio::test]
+    async fn test_service_filter_drops_excluded_spans_before_storage_and_events() {
+        use axum::body::Body;
+        use opentelemetry_proto::tonic::{
+            resource::v1::Resource,
+            trace::v1::{ResourceSpans, ScopeSpans},
+        };
+        use prost::Message;
+        use tower::ServiceExt;
+
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let (receiver, mut events) = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_service_filter(ServiceFilter::new(
+            vec![crate::core::Glob::new("checkout-*")],
+            vec![crate::core::Glob::new("checkout-canary")],
+        ))
+        .with_events();
+        let receiver = Arc::new(receiver);
+
+        let resource_spans = |id: u8, service: &str| ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(Value::StringValue(service.to_string())),
+                    }),
+                }],
+                dropped_attributes_count: 0,
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans: vec![OtelSpan {
+                    trace_id: vec![id; 16],
+                    span_id: vec![id; 8],
+                    name: "charge".to_string(),
+                    start_time_unix_nano: 1_700_000_000_000_000_000,
+                    end_time_unix_nano: 1_700_000_000_001_000_000,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![
+                resource_spans(1, "checkout-api"),
+                resource_spans(2, "checkout-canary"),
+            ],
+        };
+        let response = http::create_http_router(Arc::clone(&receiver))
+            .oneshot(
+                axum::http::Request::post("/v1/traces")
+                    .header("content-type", "application/x-protobuf")
+                    .body(Body::from(request.encode_to_vec()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let services = storage.read().await.list_services().await.unwrap();
+        assert_eq!(services, vec![ServiceName::new("checkout-api".to_string()).unwrap()]);
+        assert_eq!(events.recv().await.unwrap().service_name, "checkout-api");
+        assert_eq!(receiver.stats().excluded_spans, 1);
+
+        // Outside the include list: nothing stored or broadcast
+        let span = UrpoSpan::builder()
+            .trace_id(TraceId::new(format!("{:032x}", 3)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", 3)).unwrap())
+            .service_name(ServiceName::new("frontend".to_string()).unwrap())
+            .operation_name("GET /")
+            .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .build()
+            .unwrap();
+        receiver.process_spans(vec![span]).await.unwrap();
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 1);
+        assert!(events.try_recv().is_err());
+        assert_eq!(receiver.stats().excluded_spans, 2);
+    }
+
+    fn redaction_config(yaml: &str) -> crate::core::Config {
+        crate::core::ConfigBuilder::new()
+            .from_yaml(yaml)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_redacted_attributes_never_reach_storage_export_or_search() {
+        use crate::export::{ExportFormat, TraceExporter};
+        use axum::body::{to_bytes, Body};
+        use tower::ServiceExt;
+
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let config = redaction_config(
+            r#"
+redaction:
+  rules:
+    - keys: ["http.request.header.authorization"]
+      action: drop
+    - patterns: ["^db\\."]
+      action: hash
+"#,
+        );
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_redaction(Redactor::from_config(config.redaction.as_ref().unwrap()).unwrap());
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        let span = UrpoSpan::builder()
+            .trace_id(trace_id.clone())
+            .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+            .service_name(ServiceName::new("checkout".to_string()).unwrap())
+            .operation_name("charge")
+            .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .attribute("http.request.header.authorization", "Bearer tok-s3cret")
+            .attribute("db.statement", "SELECT card FROM wallets WHERE owner = 'ada'")
+            .attribute("http.method", "POST")
+            .build()
+            .unwrap();
+        receiver.process_spans(vec![span]).await.unwrap();
+
+        let stored = storage.read().await.get_trace_spans(&trace_id).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(!stored[0].attributes.contains_key("http.request.header.authorization"));
+        assert!(stored[0].attributes.get("db.statement").unwrap().starts_with("hash:"));
+        assert_eq!(stored[0].attributes.get("http.method"), Some("POST"));
+
+        let storage_guard = storage.read().await;
+        let exporter = TraceExporter::new(&*storage_guard);
+        for format in [ExportFormat::Json, ExportFormat::OpenTelemetry, ExportFormat::Csv] {
+            let output = exporter.export_trace(&trace_id, format).await.unwrap();
+            assert!(!output.contains("tok-s3cret"), "{} export leaks", format.name());
+            assert!(!output.contains("wallets"), "{} export leaks", format.name());
+        }
+        drop(storage_guard);
+
+        let app = crate::api::create_router(
+            Arc::clone(&storage),
+            crate::api::ApiConfig::default(),
+            crate::api::DebugContext::default(),
+        );
+        for query in ["tok-s3cret", "wallets"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    axum::http::Request::get(format!("/api/search?q={}", query))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let results: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(results["count"], 0, "search for {} matched", query);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampling_rules_decide_which_spans_are_stored() {
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(1000)));
+        let config = crate::core::ConfigBuilder::new()
+            .from_yaml(
+                r#"
+sampling:
+  default_rate: 0.0
+  per_service: {}
+  adaptive: false
+  rules:
+    - service_matches: "payment*"
+      always_keep: true
+"#,
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_sampling_rules(crate::sampling::SamplingRules::from_config(&config.sampling));
+
+        let spans = (1..=100)
+            .map(|i| {
+                let service = if i % 2 == 0 { "payment-service" } else { "checkout" };
+                UrpoSpan::builder()
+                    .trace_id(TraceId::new(format!("{:032x}", i)).unwrap())
+                    .span_id(SpanId::new(format!("{:016x}", i)).unwrap())
+                    .service_name(ServiceName::new(service.to_string()).unwrap())
+                    .operation_name("charge")
+                    .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        receiver.process_spans(spans).await.unwrap();
+
+        let services = storage.read().await.list_services().await.unwrap();
+        assert_eq!(services, vec![ServiceName::new("payment-service".to_string()).unwrap()]);
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_per_service_rates_apply_and_follow_reloads() {
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(10_000)));
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        );
+        let (tx, rx) = tokio::sync::watch::channel(crate::core::Config::default());
+        let handle = receiver.watch_config(rx);
+
+        let mut config = crate::core::Config::default();
+        config
+            .sampling
+            .per_service
+            .insert("health*".to_string(), 0.0);
+        config
+            .sampling
+            .per_service
+            .insert("checkout".to_string(), 1.0);
+        tx.send(config).unwrap();
+        for _ in 0..100 {
+            if receiver.service_rates().load().override_rate("healthz").is_some() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        let span = |i: u64, service: &str| {
+            UrpoSpan::builder()
+                .trace_id(TraceId::new(format!("{:032x}", i)).unwrap())
+                .span_id(SpanId::new(format!("{:016x}", i)).unwrap())
+                .service_name(ServiceName::new(service.to_string()).unwrap())
+                .operation_name("GET")
+                .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                .build()
+                .unwrap()
+        };
+        let spans = (1..=100)
+            .map(|i| span(i, if i % 2 == 0 { "healthz" } else { "checkout" }))
+            .collect();
+        receiver.process_spans(spans).await.unwrap();
+        let services = storage.read().await.list_services().await.unwrap();
+        assert_eq!(services, vec![ServiceName::new("checkout".to_string()).unwrap()]);
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 50);
+
+        // Dropping the override brings the default rate back
+        tx.send(crate::core::Config::default()).unwrap();
+        for _ in 0..100 {
+            if receiver.service_rates().load().override_rate("healthz").is_none() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        receiver.process_spans(vec![span(101, "healthz")]).await.unwrap();
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 51);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_per_service_rates_override_smart_and_fair_samplers() {
+        let config = crate::core::ConfigBuilder::new()
+            .from_yaml(
+                r#"
+sampling:
+  default_rate: 1.0
+  per_service:
+    noisy-svc: 0.0
+    checkout: 1.0
+  adaptive: false
+"#,
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let spans = || -> Vec<UrpoSpan> {
+            (1..=200)
+                .map(|i| {
+                    let service = if i % 2 == 0 { "noisy-svc" } else { "checkout" };
+                    UrpoSpan::builder()
+                        .trace_id(TraceId::new(format!("{:032x}", i)).unwrap())
+                        .span_id(SpanId::new(format!("{:016x}", i)).unwrap())
+                        .service_name(ServiceName::new(service.to_string()).unwrap())
+                        .operation_name("GET")
+                        .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                        .build()
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        for fair in [false, true] {
+            let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+                Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(10_000)));
+            let receiver = OtelReceiver::new(
+                0,
+                0,
+                Arc::clone(&storage),
+                Arc::new(crate::monitoring::Monitor::new()),
+            )
+            .with_service_rates(crate::sampling::ServiceRates::from_config(&config.sampling));
+            let receiver = if fair {
+                receiver.with_fair_sampling(crate::core::FairnessConfig::default())
+            } else {
+                receiver.with_smart_sampling(1)
+            };
+
+            receiver.process_spans(spans()).await.unwrap();
+            let services = storage.read().await.list_services().await.unwrap();
+            assert_eq!(services, vec![ServiceName::new("checkout".to_string()).unwrap()]);
+            assert_eq!(storage.read().await.get_span_count().await.unwrap(), 100);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiting_sampler_caps_spans_per_service() {
+        let clock = crate::core::MockClock::default();
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(10_000)));
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        )
+        .with_clock(clock.shared())
+        .with_rate_limiting_sampler(10);
+        let burst = |first: u64, service: &str| -> Vec<UrpoSpan> {
+            (first..first + 100)
+                .map(|i| {
+                    UrpoSpan::builder()
+                        .trace_id(TraceId::new(format!("{:032x}", i)).unwrap())
+                        .span_id(SpanId::new(format!("{:016x}", i)).unwrap())
+                        .service_name(ServiceName::new(service.to_string()).unwrap())
+                        .operation_name("GET")
+                        .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                        .build()
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        receiver.process_spans(burst(0, "noisy")).await.unwrap();
+        receiver.process_spans(burst(100, "quiet")).await.unwrap();
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 20);
+        assert_eq!(receiver.stats().capped_spans, 180);
+
+        // Half a second refills half of the noisy service's bucket
+        clock.advance(Duration::from_millis(500));
+        receiver.process_spans(burst(200, "noisy")).await.unwrap();
+        assert_eq!(storage.read().await.get_span_count().await.unwrap(), 25);
+        assert_eq!(receiver.stats().capped_spans, 275);
+    }
+
+    #[tokio::test]
+    async fn test_redaction_rules_follow_config_reloads() {
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100))),
+            Arc::new(crate::monitoring::Monitor::new()),
+        );
+        let (tx, rx) = tokio::sync::watch::channel(crate::core::Config::default());
+        let handle = receiver.watch_config(rx);
+        let redacts = |receiver: &OtelReceiver| {
+            let mut attributes = AttributeMap::new();
+            attributes.push(Arc::from("user.email"), Arc::from("ada@example.com"));
+            receiver.redactor.load().redact_attributes(&mut attributes) > 0
+        };
+        assert!(!redacts(&receiver));
+
+        let reloaded = redaction_config(
+            "redaction:\n  rules:\n    - keys: [\"user.email\"]\n      action: drop\n",
+        );
+        tx.send(reloaded).unwrap();
+        for _ in 0..100 {
+            if redacts(&receiver) {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(redacts(&receiver));
+
+        // Removing the section turns redaction off again
+        tx.send(crate::core::Config::default()).unwrap();
+        for _ in 0..100 {
+            if !redacts(&receiver) {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(!redacts(&receiver));
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failed_server_is_restarted() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+
+            ..RestartPolicy::default()
+        };
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+
+        // Fails on every start: tried once plus two restarts, then given up
+        let starts = AtomicU32::new(0);
+        let result = supervise("test", policy, stop_rx.clone(), || {
+            starts.fetch_add(1, Ordering::SeqCst);
+            async { Err(UrpoError::network("Port 4317 already in use")) }
+        })
+        .await;
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("test server after 2 restarts"), "{}", error);
+
+        // Exits once, then serves until stopped
+        let starts = AtomicU32::new(0);
+        supervise("test", policy, stop_rx, || {
+            let attempt = starts.fetch_add(1, Ordering::SeqCst);
+            let stop_tx = &stop_tx;
+            async move {
+                if attempt > 0 {
+                    let _ = stop_tx.send(true);
+                }
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stable_run_resets_restart_count() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let policy = RestartPolicy {
+            max_restarts: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            stable_after: Duration::from_millis(50),
+        };
+        let (_stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+
+        // Fails, is restarted and runs stably before failing again: the
+        // restart budget starts over, so it is only given up on the third failure
+        let starts = AtomicU32::new(0);
+        let result = supervise("test", policy, stop_rx, || {
+            let attempt = starts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 1 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Err(UrpoError::network("Connection reset"))
+            }
+        })
+        .await;
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert!(result.unwrap_err().to_string().contains("after 1 restarts"));
+    }
+
+    #[tokio::test]
+    async fn test_run_until_fails_when_server_gives_up() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let policy = RestartPolicy {
+            max_restarts: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+
+            ..RestartPolicy::default()
+        };
+        let monitor = Arc::new(crate::monitoring::Monitor::new());
+        let receiver = OtelReceiver::new(0, port, storage, monitor).with_restart_policy(policy);
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            Arc::new(receiver).run_until(std::future::pending()),
+        )
+        .await
+        .expect("run_until should stop once the HTTP server gives up");
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("HTTP server"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_until_fails_when_unix_socket_server_gives_up() {
+        let storage: Arc<tokio::sync::RwLock<dyn crate::storage::StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(crate::storage::InMemoryStorage::new(100)));
+        let monitor = Arc::new(crate::monitoring::Monitor::new());
+        let receiver = OtelReceiver::new(0, 0, storage, monitor)
+            .with_restart_policy(RestartPolicy {
+                max_restarts: 0,
+                ..RestartPolicy::default()
+            })
+            .with_http_uds_path(Some(PathBuf::from("/nonexistent/urpo/http.sock")));
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            Arc::new(receiver).run_until(std::future::pending()),
+        )
+        .await
+        .expect("run_until should stop once the Unix socket server gives up");
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("HTTP Unix socket server"), "{}", error);
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_up_to_max() {
+        let policy = RestartPolicy {
+            max_restarts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+
+            ..RestartPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+        assert_eq!(policy.backoff(40), Duration::from_secs(5));
+    }
 }
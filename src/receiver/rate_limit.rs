@@ -0,0 +1,313 @@
+//! Per-client rate limiting of span ingestion.
+//!
+//! Every client, identified by its peer address or by a resource attribute
+//! such as `service.name`, has a token bucket holding up to its burst in
+//! spans and refilled at its rate. A request is admitted when the bucket of
+//! each of its clients holds enough tokens for that client's spans. Requests
+//! larger than the burst are admitted from a full bucket and leave it in
+//! debt, so they are slowed down rather than never accepted.
+//!
+//! Spans of clients over their limit are dropped from the request. When no
+//! span is left the receivers answer RESOURCE_EXHAUSTED or 429 with the time
+//! until the bucket refills enough to accept the request.
+
+use crate::core::{system_clock, RateLimitConfig, RateLimitKey, RateLimits, SharedClock, Span};
+use arc_swap::ArcSwap;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Most clients whose buckets are remembered; the least recent is forgotten.
+const MAX_CLIENTS: usize = 4096;
+
+/// Client key of spans whose peer address or attribute is unknown.
+pub const UNKNOWN_CLIENT: &str = "unknown";
+
+/// Token bucket and counters of one client.
+#[derive(Debug)]
+struct ClientState {
+    tokens: f64,
+    last_refill: Instant,
+    admitted: u64,
+    dropped: u64,
+}
+
+/// Spans a [`RateLimiter`] dropped from one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Spans dropped
+    pub rejected: usize,
+    /// Longest wait until a dropped client's bucket accepts its spans
+    pub retry_after: Duration,
+}
+
+/// Admitted and dropped spans of one client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientRateStats {
+    /// Client key
+    pub client: String,
+    /// Spans admitted
+    pub admitted: u64,
+    /// Spans dropped for being over the limit
+    pub dropped: u64,
+}
+
+/// Rate limiter counters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimiterStats {
+    /// Spans dropped since start
+    pub dropped_spans: u64,
+    /// Requests with at least one dropped span
+    pub limited_requests: u64,
+    /// Remembered clients, most dropped spans first
+    pub clients: Vec<ClientRateStats>,
+}
+
+/// Token bucket rate limiter keyed by client.
+pub struct RateLimiter {
+    key: RateLimitKey,
+    limits: ArcSwap<RateLimits>,
+    clients: Mutex<LruCache<String, ClientState>>,
+    dropped_spans: AtomicU64,
+    limited_requests: AtomicU64,
+    clock: SharedClock,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter.
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Create a rate limiter refilling its buckets by `clock`.
+    pub fn with_clock(config: RateLimitConfig, clock: SharedClock) -> Self {
+        Self {
+            key: config.key,
+            limits: ArcSwap::from_pointee(config.limits),
+            clients: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_CLIENTS).expect("client count is non-zero"),
+            )),
+            dropped_spans: AtomicU64::new(0),
+            limited_requests: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// What identifies a client.
+    pub fn key(&self) -> &RateLimitKey {
+        &self.key
+    }
+
+    /// Limits in effect.
+    pub fn limits(&self) -> Arc<RateLimits> {
+        self.limits.load_full()
+    }
+
+    /// Replace the limits. Buckets keep their tokens, capped by the new burst.
+    pub fn set_limits(&self, limits: RateLimits) {
+        self.limits.store(Arc::new(limits));
+    }
+
+    /// Take tokens for `spans` spans of `client`, or return how long until
+    /// its bucket holds enough of them.
+    pub fn check(&self, client: &str, spans: usize) -> std::result::Result<(), Duration> {
+        let Some(limit) = self.limits.load().limit_for(client) else {
+            return Ok(());
+        };
+        let now = self.clock.instant();
+        let capacity = limit.capacity();
+        let mut clients = self.clients.lock();
+        let state = clients.get_or_insert_mut(client.to_string(), || ClientState {
+            tokens: capacity,
+            last_refill: now,
+            admitted: 0,
+            dropped: 0,
+        });
+
+        let elapsed = now
+            .saturating_duration_since(state.last_refill)
+            .as_secs_f64();
+        state.tokens = (state.tokens + elapsed * limit.spans_per_second).min(capacity);
+        state.last_refill = now;
+
+        let needed = (spans as f64).min(capacity);
+        if state.tokens >= needed {
+            state.tokens -= spans as f64;
+            state.admitted += spans as u64;
+            Ok(())
+        } else {
+            state.dropped += spans as u64;
+            Err(Duration::from_secs_f64((needed - state.tokens) / limit.spans_per_second))
+        }
+    }
+
+    /// Drop the spans of clients over their limit from `spans`.
+    pub fn admit(&self, spans: &mut Vec<Span>, peer: Option<IpAddr>) -> RateLimited {
+        let peer = peer.map(|ip| ip.to_string());
+        let client_of = |span: &Span| -> String {
+            match self.key {
+                RateLimitKey::PeerIp => peer.as_deref(),
+                RateLimitKey::ResourceAttribute(ref key) => span
+                    .resource_attributes
+                    .get(key)
+                    .or_else(|| (key == "service.name").then(|| span.service_name.as_str())),
+            }
+            .unwrap_or(UNKNOWN_CLIENT)
+            .to_string()
+        };
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for span in spans.iter() {
+            *counts.entry(client_of(span)).or_insert(0) += 1;
+        }
+
+        let mut limited = RateLimited::default();
+        counts.retain(|client, count| match self.check(client, *count) {
+            Ok(()) => false,
+            Err(retry_after) => {
+                limited.rejected += *count;
+                limited.retry_after = limited.retry_after.max(retry_after);
+                tracing::debug!("Client {} over its rate limit, dropping {} spans", client, count);
+                true
+            },
+        });
+        if limited.rejected > 0 {
+            spans.retain(|span| !counts.contains_key(&client_of(span)));
+            self.dropped_spans
+                .fetch_add(limited.rejected as u64, Ordering::Relaxed);
+            self.limited_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        limited
+    }
+
+    /// Dropped span counters, overall and per client.
+    pub fn stats(&self) -> RateLimiterStats {
+        let mut clients: Vec<ClientRateStats> = self
+            .clients
+            .lock()
+            .iter()
+            .map(|(client, state)| ClientRateStats {
+                client: client.clone(),
+                admitted: state.admitted,
+                dropped: state.dropped,
+            })
+            .collect();
+        clients.sort_by(|a, b| b.dropped.cmp(&a.dropped).then(a.client.cmp(&b.client)));
+        RateLimiterStats {
+            dropped_spans: self.dropped_spans.load(Ordering::Relaxed),
+            limited_requests: self.limited_requests.load(Ordering::Relaxed),
+            clients,
+        }
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("key", &self.key)
+            .field("limits", &self.limits.load())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MockClock, RateLimit, ServiceName, SpanId, TraceId};
+
+    fn limit(spans_per_second: f64, burst: f64) -> RateLimit {
+        RateLimit {
+            spans_per_second,
+            burst: Some(burst),
+        }
+    }
+
+    fn span(id: u64, service: &str) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name("op")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bucket_refills_at_its_rate() {
+        let clock = Arc::new(MockClock::new(std::time::UNIX_EPOCH));
+        let config = RateLimitConfig {
+            limits: RateLimits {
+                default: Some(limit(100.0, 200.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let limiter = RateLimiter::with_clock(config, Arc::clone(&clock) as SharedClock);
+
+        assert!(limiter.check("10.0.0.1", 150).is_ok());
+        // 50 tokens left; 100 more need half a second
+        assert_eq!(limiter.check("10.0.0.1", 100), Err(Duration::from_millis(500)));
+        assert!(limiter.check("10.0.0.2", 200).is_ok());
+
+        clock.advance(Duration::from_millis(500));
+        assert!(limiter.check("10.0.0.1", 100).is_ok());
+
+        // Larger than the burst: admitted from a full bucket, leaving debt
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.check("10.0.0.1", 1000).is_ok());
+        assert!(limiter.check("10.0.0.1", 1).is_err());
+
+        let stats = limiter.stats();
+        assert_eq!(stats.clients[0].client, "10.0.0.1");
+        assert_eq!(stats.clients[0].dropped, 101);
+        assert_eq!(stats.clients[0].admitted, 1250);
+    }
+
+    #[test]
+    fn test_admit_drops_only_clients_over_their_limit() {
+        let clock = Arc::new(MockClock::new(std::time::UNIX_EPOCH));
+        let mut clients = HashMap::new();
+        clients.insert("noisy".to_string(), limit(1.0, 2.0));
+        let config = RateLimitConfig {
+            key: RateLimitKey::ResourceAttribute("service.name".to_string()),
+            limits: RateLimits {
+                default: None,
+                clients,
+            },
+        };
+        let limiter = RateLimiter::with_clock(config, clock);
+
+        let batch = || -> Vec<Span> {
+            (1..=4)
+                .map(|id| span(id, "noisy"))
+                .chain([span(5, "quiet")])
+                .collect()
+        };
+        // The first batch empties the bucket, and two spans more
+        let mut spans = batch();
+        assert_eq!(limiter.admit(&mut spans, None), RateLimited::default());
+        assert_eq!(spans.len(), 5);
+
+        let mut spans = batch();
+        let limited = limiter.admit(&mut spans, None);
+        assert_eq!(limited.rejected, 4);
+        assert_eq!(limited.retry_after, Duration::from_secs(4));
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].service_name.as_str(), "quiet");
+
+        // Limits change at runtime; unlimited clients have no bucket
+        limiter.set_limits(RateLimits::default());
+        let mut spans: Vec<Span> = (1..=4).map(|id| span(id, "noisy")).collect();
+        assert_eq!(limiter.admit(&mut spans, None), RateLimited::default());
+        assert_eq!(spans.len(), 4);
+
+        let stats = limiter.stats();
+        assert_eq!((stats.dropped_spans, stats.limited_requests), (4, 1));
+    }
+}
@@ -0,0 +1,306 @@
+//! Replay of exported OTLP JSON through the receiver pipeline.
+//!
+//! Reads OTLP JSON export requests from a file: a single request, an array
+//! of requests (the output of `urpo export --format otel`) or one request per
+//! line. Spans go through `OtelReceiver::process_spans`, so sampling,
+//! filtering, redaction, storage, forwarding and events apply as for live
+//! ingestion.
+//!
+//! Spans are sent in start time order, spaced as they were recorded and
+//! divided by the replay speed. Recordings from the past can be shifted so
+//! that the first span starts when the replay starts.
+
+use super::http::{json_to_otlp_request, process_export_request};
+use super::OtelReceiver;
+use crate::core::{Result, Span as UrpoSpan, SpanLimits, UrpoError};
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+/// Most spans passed to `process_spans` at once.
+pub const REPLAY_BATCH_SIZE: usize = 1_000;
+
+/// How a recording is replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOptions {
+    /// Playback speed factor; `None` sends spans without waiting
+    pub speed: Option<f64>,
+    /// Move past timestamps so the first span starts at the replay start
+    pub shift_to_now: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: Some(1.0),
+            shift_to_now: false,
+        }
+    }
+}
+
+impl ReplayOptions {
+    /// Replay at `speed` times the recorded pace, or as fast as possible.
+    pub fn with_speed(mut self, speed: Option<f64>) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Shift past timestamps so the first span starts now.
+    pub fn with_shift_to_now(mut self, shift_to_now: bool) -> Self {
+        self.shift_to_now = shift_to_now;
+        self
+    }
+}
+
+/// Parse a speed such as `2x`, `0.5` or `max` (no waiting).
+pub fn parse_speed(speed: &str) -> Result<Option<f64>> {
+    let speed = speed.trim();
+    if speed.eq_ignore_ascii_case("max") {
+        return Ok(None);
+    }
+    let factor = speed
+        .strip_suffix(['x', 'X'])
+        .unwrap_or(speed)
+        .parse::<f64>()
+        .ok()
+        .filter(|factor| factor.is_finite() && *factor > 0.0)
+        .ok_or_else(|| {
+            UrpoError::config(format!(
+                "Invalid replay speed '{}': use a positive factor like 2x or max",
+                speed
+            ))
+        })?;
+    Ok(Some(factor))
+}
+
+/// Progress of a replay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayProgress {
+    /// Spans passed to the pipeline so far
+    pub sent: usize,
+    /// Spans in the recording
+    pub total: usize,
+    /// Time since the replay started
+    pub elapsed: Duration,
+}
+
+impl ReplayProgress {
+    /// Average spans sent per second.
+    pub fn spans_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.sent as f64 / secs
+        } else {
+            self.sent as f64
+        }
+    }
+}
+
+/// Parse the spans of a file of OTLP JSON export requests.
+///
+/// Accepts one request, an array of requests or whitespace-separated
+/// requests such as newline-delimited JSON. Spans are converted as by the
+/// OTLP/HTTP receiver, including `limits`.
+pub fn parse_replay_file(contents: &str, limits: &SpanLimits) -> Result<Vec<UrpoSpan>> {
+    let mut spans = Vec::new();
+    let values = serde_json::Deserializer::from_str(contents).into_iter::<serde_json::Value>();
+    for (index, value) in values.enumerate() {
+        let value = value.map_err(|e| {
+            UrpoError::parse(format!("Invalid JSON in request {}: {}", index + 1, e))
+        })?;
+        let requests = match value {
+            serde_json::Value::Array(requests) => requests,
+            request => vec![request],
+        };
+        for request in requests {
+            let (batch, rejected) = json_to_otlp_request(request)
+                .and_then(|request| process_export_request(request, limits))
+                .map_err(|e| {
+                    UrpoError::parse(format!("Invalid OTLP request {}: {}", index + 1, e))
+                })?;
+            if rejected.count > 0 {
+                tracing::warn!(
+                    "Skipped {} spans of request {}: {}",
+                    rejected.count,
+                    index + 1,
+                    rejected.reason.unwrap_or_default()
+                );
+            }
+            spans.extend(batch);
+        }
+    }
+    Ok(spans)
+}
+
+/// Move the timestamps of `span` and its events `by` later.
+fn shift_span(span: &mut UrpoSpan, by: Duration) {
+    span.start_time += by;
+    for event in &mut span.events {
+        event.timestamp += by;
+    }
+}
+
+impl OtelReceiver {
+    /// Replay recorded spans through the pipeline, calling `progress` after
+    /// each batch. Returns the final progress.
+    pub async fn replay(
+        &self,
+        mut spans: Vec<UrpoSpan>,
+        options: &ReplayOptions,
+        mut progress: impl FnMut(&ReplayProgress),
+    ) -> Result<ReplayProgress> {
+        spans.sort_by_key(|span| span.start_time);
+        let first_start = spans
+            .first()
+            .map_or(SystemTime::UNIX_EPOCH, |span| span.start_time);
+        if options.shift_to_now {
+            if let Ok(age) = SystemTime::now().duration_since(first_start) {
+                for span in &mut spans {
+                    shift_span(span, age);
+                }
+            }
+        }
+        let first_start = spans.first().map_or(first_start, |span| span.start_time);
+
+        let started = Instant::now();
+        let due = |span: &UrpoSpan| {
+            let offset = span
+                .start_time
+                .duration_since(first_start)
+                .unwrap_or_default();
+            options.speed.map(|speed| started + offset.div_f64(speed))
+        };
+        let mut state = ReplayProgress {
+            sent: 0,
+            total: spans.len(),
+            elapsed: Duration::ZERO,
+        };
+        let mut pending = spans.into_iter().peekable();
+        while let Some(span) = pending.next() {
+            if let Some(due) = due(&span) {
+                tokio::time::sleep_until(due).await;
+            }
+            let mut batch = vec![span];
+            while batch.len() < REPLAY_BATCH_SIZE {
+                match pending.next_if(|next| due(next).map_or(true, |due| due <= Instant::now())) {
+                    Some(next) => batch.push(next),
+                    None => break,
+                }
+            }
+
+            state.sent += batch.len();
+            self.process_spans(batch).await?;
+            state.elapsed = started.elapsed();
+            progress(&state);
+        }
+        state.elapsed = started.elapsed();
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, TraceId};
+    use crate::storage::{InMemoryStorage, StorageBackend};
+    use std::sync::Arc;
+
+    fn request(service: &str, spans: &[(u64, u64)]) -> serde_json::Value {
+        let spans: Vec<_> = spans
+            .iter()
+            .map(|&(id, start_ms)| {
+                let start = 1_700_000_000_000_000_000u64 + start_ms * 1_000_000;
+                serde_json::json!({
+                    "traceId": format!("{:032x}", 1),
+                    "spanId": format!("{:016x}", id),
+                    "name": "charge",
+                    "startTimeUnixNano": start.to_string(),
+                    "endTimeUnixNano": (start + 1_000_000).to_string(),
+                    "status": { "code": 2, "message": "declined" },
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": service } }]
+                },
+                "scopeSpans": [{ "spans": spans }]
+            }]
+        })
+    }
+
+    #[test]
+    fn test_parse_single_array_and_newline_delimited() {
+        let limits = SpanLimits::default();
+        let single = serde_json::to_string_pretty(&request("api", &[(1, 0), (2, 5)])).unwrap();
+        let spans = parse_replay_file(&single, &limits).unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].service_name.as_str(), "api");
+        assert!(spans[0].is_error());
+
+        let array = serde_json::json!([request("api", &[(1, 0)]), request("db", &[(2, 1)])]);
+        let spans = parse_replay_file(&serde_json::to_string_pretty(&array).unwrap(), &limits);
+        assert_eq!(spans.unwrap().len(), 2);
+
+        let ndjson =
+            format!("{}\n\n{}\n", request("api", &[(1, 0)]), request("db", &[(2, 1), (3, 2)]));
+        assert_eq!(parse_replay_file(&ndjson, &limits).unwrap().len(), 3);
+
+        let error = parse_replay_file("{\"resourceSpans\": [] }\n{oops", &limits);
+        assert!(error.unwrap_err().to_string().contains("request 2"));
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("2x").unwrap(), Some(2.0));
+        assert_eq!(parse_speed("0.5").unwrap(), Some(0.5));
+        assert_eq!(parse_speed("MAX").unwrap(), None);
+        assert!(parse_speed("0x").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_scaled_timing_and_shifts_to_now() {
+        let storage: Arc<tokio::sync::RwLock<dyn StorageBackend>> =
+            Arc::new(tokio::sync::RwLock::new(InMemoryStorage::new(100)));
+        let receiver = OtelReceiver::new(
+            0,
+            0,
+            Arc::clone(&storage),
+            Arc::new(crate::monitoring::Monitor::new()),
+        );
+        let contents = request("api", &[(3, 400), (1, 0), (2, 200)]).to_string();
+        let spans = parse_replay_file(&contents, &SpanLimits::default()).unwrap();
+
+        let options = ReplayOptions::default()
+            .with_speed(Some(4.0))
+            .with_shift_to_now(true);
+        let replay_start = SystemTime::now();
+        let mut updates = Vec::new();
+        let done = receiver
+            .replay(spans, &options, |progress| updates.push(progress.sent))
+            .await
+            .unwrap();
+
+        assert_eq!(updates, vec![1, 2, 3]);
+        assert_eq!((done.sent, done.total), (3, 3));
+        // 400ms of recording at 4x
+        assert!(done.elapsed >= Duration::from_millis(100));
+        assert!(done.elapsed < Duration::from_millis(400));
+        assert!(done.spans_per_sec() > 0.0);
+
+        let trace_id = TraceId::new(format!("{:032x}", 1)).unwrap();
+        let mut stored = storage
+            .read()
+            .await
+            .get_trace_spans(&trace_id)
+            .await
+            .unwrap();
+        stored.sort_by_key(|span| span.start_time);
+        let first = stored[0].start_time;
+        assert!(first >= replay_start - Duration::from_secs(1));
+        assert_eq!(stored[2].start_time.duration_since(first).unwrap(), Duration::from_millis(400));
+        assert_eq!(stored[0].span_id, SpanId::new(format!("{:016x}", 1)).unwrap());
+        assert_eq!(stored[0].service_name, ServiceName::new("api".to_string()).unwrap());
+    }
+}
@@ -0,0 +1,172 @@
+//! Unix domain socket listeners for local OTLP ingestion.
+//!
+//! The gRPC and HTTP receivers can listen on a socket file besides their TCP
+//! port, so local SDKs can export without a port that may clash with a
+//! collector on the same host. The socket file gets the configured
+//! permission bits before anyone else can reach it, and is removed when its
+//! server stops.
+
+use crate::core::{Result, UrpoError};
+use hyper_util::rt::TokioIo;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::net::UnixListener;
+
+/// A bound socket file, removed when dropped.
+#[derive(Debug)]
+pub struct SocketFile {
+    path: PathBuf,
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove Unix socket {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Listen on a Unix socket at `path` with permission bits `mode`.
+///
+/// A socket file left behind by an earlier run is replaced; any other file
+/// at `path` is an error. The socket is bound in a private (0700) directory
+/// next to `path` and only moved into place once it has `mode`, so other
+/// users cannot connect while it still has the umask's permissions.
+pub fn bind(path: &Path, mode: u32) -> Result<(UnixListener, SocketFile)> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(UrpoError::network(format!(
+                "Cannot listen on {}: file exists and is not a socket",
+                path.display()
+            )));
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+        Err(e) => return Err(e.into()),
+    }
+
+    let listener = bind_privately(path, mode).map_err(|e| {
+        UrpoError::network(format!("Failed to bind Unix socket {}: {}", path.display(), e))
+    })?;
+    Ok((
+        listener,
+        SocketFile {
+            path: path.to_path_buf(),
+        },
+    ))
+}
+
+/// Bind in a fresh directory only the current user can enter, then move the
+/// socket to `path` once it has `mode`.
+fn bind_privately(path: &Path, mode: u32) -> std::io::Result<UnixListener> {
+    let staging = staging_dir(path)?;
+    let staged = staging.join("socket");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&staging);
+    bound
+}
+
+fn staging_dir(path: &Path) -> std::io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file name"))?;
+    let mut staging_name = std::ffi::OsString::from(".");
+    staging_name.push(name);
+    staging_name.push(format!(".{}.tmp", std::process::id()));
+    let staging = path.with_file_name(staging_name);
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.mode(0o700);
+    match builder.create(&staging) {
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            // Left behind by a crashed run with our pid
+            std::fs::remove_dir_all(&staging)?;
+            builder.create(&staging)?;
+        },
+        result => result?,
+    }
+    Ok(staging)
+}
+
+/// Serve `app` over HTTP/1 on `listener` until `signal` completes, then wait
+/// for open connections to finish.
+pub async fn serve_http<F>(listener: UnixListener, app: axum::Router, signal: F)
+where
+    F: std::future::Future<Output = ()>,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(signal);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept Unix socket connection: {}", e);
+                    continue;
+                },
+            },
+            _ = &mut signal => break,
+        };
+
+        let connection = hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(stream), TowerToHyperService::new(app.clone()));
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!("Unix socket connection failed: {}", e);
+            }
+        });
+    }
+
+    graceful.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_sets_mode_and_removes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.sock");
+
+        let (listener, socket) = bind(&path, 0o660).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        // A stale socket is replaced, other files are left alone
+        drop(listener);
+        std::mem::forget(socket);
+        let (_listener, socket) = bind(&path, 0o600).unwrap();
+        drop(socket);
+        assert!(!path.exists());
+
+        std::fs::write(&path, b"not a socket").unwrap();
+        assert!(bind(&path, 0o600).is_err());
+        assert!(path.exists());
+
+        // The private staging directory is cleaned up
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bound_socket_accepts_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.sock");
+
+        let (listener, _socket) = bind(&path, 0o600).unwrap();
+        let (accepted, connected) =
+            tokio::join!(listener.accept(), tokio::net::UnixStream::connect(&path));
+        accepted.unwrap();
+        connected.unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+//! Zipkin v2 JSON ingestion.
+//!
+//! Accepts the Zipkin v2 span list on `POST /api/v2/spans` so existing Zipkin
+//! reporters can point directly at Urpo. Each span is converted into a
+//! `core::Span` and fed through the same sampling/storage path as OTLP spans.
+//! Invalid spans are rejected individually; the rest of the batch is kept.
+
+use super::http::{HttpError, HttpOtelState};
+use crate::core::{Result, ServiceName, Span, SpanId, SpanKind, SpanStatus, TraceId, UrpoError};
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Zipkin v2 endpoint description.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipkinEndpoint {
+    /// Service name
+    #[serde(default)]
+    pub service_name: Option<String>,
+    /// IPv4 address
+    #[serde(default)]
+    pub ipv4: Option<String>,
+    /// Port
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+/// Zipkin v2 span as sent by reporters.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipkinSpan {
+    /// 16 or 32 lower-hex trace ID
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// 16 lower-hex span ID
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Parent span ID
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Operation name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// CLIENT, SERVER, PRODUCER or CONSUMER
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Start timestamp in microseconds since epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// Duration in microseconds
+    #[serde(default)]
+    pub duration: Option<u64>,
+    /// Local (reporting) endpoint
+    #[serde(default)]
+    pub local_endpoint: Option<ZipkinEndpoint>,
+    /// Remote endpoint
+    #[serde(default)]
+    pub remote_endpoint: Option<ZipkinEndpoint>,
+    /// String tags
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+/// Handle Zipkin v2 span list uploads.
+pub(super) async fn handle_zipkin_spans(
+    State(state): State<HttpOtelState>,
+    body: Bytes,
+) -> std::result::Result<impl IntoResponse, HttpError> {
+    let raw_spans: Vec<serde_json::Value> = serde_json::from_slice(&body)
+        .map_err(|e| HttpError::BadRequest(format!("Expected Zipkin v2 span list: {}", e)))?;
+
+    let total = raw_spans.len();
+    let (spans, errors) = convert_zipkin_batch(raw_spans);
+
+    if spans.is_empty() && total > 0 {
+        return Err(HttpError::BadRequest(format!(
+            "All {} Zipkin spans rejected: {}",
+            total,
+            errors.join("; ")
+        )));
+    }
+
+    let accepted = spans.len();
+    if let Err(e) = state.receiver.process_spans(spans).await {
+        return Err(HttpError::from_process_error(e));
+    }
+
+    if !errors.is_empty() {
+        tracing::warn!("Rejected {} of {} Zipkin spans", errors.len(), total);
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "accepted": accepted,
+            "rejected": errors.len(),
+            "errors": errors,
+        })),
+    ))
+}
+
+/// Convert a batch of raw Zipkin spans, collecting per-span errors.
+pub fn convert_zipkin_batch(raw_spans: Vec<serde_json::Value>) -> (Vec<Span>, Vec<String>) {
+    let mut spans = Vec::with_capacity(raw_spans.len());
+    let mut errors = Vec::new();
+
+    for (index, raw) in raw_spans.into_iter().enumerate() {
+        let converted = serde_json::from_value::<ZipkinSpan>(raw)
+            .map_err(|e| UrpoError::parse(e.to_string()))
+            .and_then(convert_zipkin_span);
+
+        match converted {
+            Ok(span) => spans.push(span),
+            Err(e) => errors.push(format!("span[{}]: {}", index, e)),
+        }
+    }
+
+    (spans, errors)
+}
+
+/// Convert a single Zipkin v2 span into a Urpo span.
+pub fn convert_zipkin_span(zipkin: ZipkinSpan) -> Result<Span> {
+    let trace_id = match zipkin.trace_id.as_deref() {
+        Some(id) if is_hex_id(id, &[16, 32]) => {
+            // 64-bit trace IDs are left-padded to match OTLP's 128-bit form
+            TraceId::new(format!("{:0>32}", id.to_ascii_lowercase()))?
+        },
+        Some(id) => return Err(UrpoError::InvalidSpan(format!("Invalid traceId: {}", id))),
+        None => return Err(UrpoError::InvalidSpan("Missing traceId".to_string())),
+    };
+
+    let span_id = match zipkin.id.as_deref() {
+        Some(id) if is_hex_id(id, &[16]) => SpanId::new(id.to_ascii_lowercase())?,
+        Some(id) => return Err(UrpoError::InvalidSpan(format!("Invalid id: {}", id))),
+        None => return Err(UrpoError::InvalidSpan("Missing id".to_string())),
+    };
+
+    let service_name = zipkin
+        .local_endpoint
+        .as_ref()
+        .and_then(|e| e.service_name.as_deref())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("unknown");
+
+    let start_time = zipkin
+        .timestamp
+        .map(|us| UNIX_EPOCH + Duration::from_micros(us))
+        .unwrap_or_else(SystemTime::now);
+    let duration = Duration::from_micros(zipkin.duration.unwrap_or(0));
+
+    let (kind, kind_str) = match zipkin.kind.as_deref() {
+        Some("CLIENT") => (SpanKind::Client, "client"),
+        Some("SERVER") => (SpanKind::Server, "server"),
+        Some("PRODUCER") => (SpanKind::Producer, "producer"),
+        Some("CONSUMER") => (SpanKind::Consumer, "consumer"),
+        _ => (SpanKind::Internal, "internal"),
+    };
+
+    // Zipkin marks failures with an "error" tag whose value is the message
+    let status = match zipkin.tags.get("error") {
+        Some(message) => SpanStatus::Error(message.clone()),
+        None => SpanStatus::Unknown,
+    };
+
+    let mut builder = Span::builder()
+        .trace_id(trace_id)
+        .span_id(span_id)
+        .service_name(ServiceName::new(service_name.to_string())?)
+        .operation_name(zipkin.name.unwrap_or_default())
+        .start_time(start_time)
+        .duration(duration)
+        .kind(kind)
+        .status(status)
+        .attribute("span.kind", kind_str);
+
+    if let Some(parent_id) = zipkin.parent_id.as_deref().filter(|p| is_hex_id(p, &[16])) {
+        builder = builder.parent_span_id(SpanId::new(parent_id.to_ascii_lowercase())?);
+    }
+
+    if let Some(peer) = zipkin
+        .remote_endpoint
+        .as_ref()
+        .and_then(|e| e.service_name.as_deref())
+    {
+        builder = builder.attribute("peer.service", peer);
+    }
+
+    for (key, value) in zipkin.tags {
+        builder = builder.attribute(key, value);
+    }
+
+    builder.build()
+}
+
+/// Check that an ID is hex of one of the allowed lengths and not all zeros.
+fn is_hex_id(id: &str, lengths: &[usize]) -> bool {
+    lengths.contains(&id.len())
+        && id.bytes().all(|b| b.is_ascii_hexdigit())
+        && id.bytes().any(|b| b != b'0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_convert_zipkin_span() {
+        let raw = json!({
+            "traceId": "5af7183fb1d4cf5f",
+            "parentId": "6b221d5bc9e6496c",
+            "id": "352bff9a74ca9ad2",
+            "kind": "CLIENT",
+            "name": "get /api",
+            "timestamp": 1_556_604_172_355_737u64,
+            "duration": 1431,
+            "localEndpoint": { "serviceName": "frontend", "ipv4": "192.168.99.1" },
+            "remoteEndpoint": { "serviceName": "backend", "port": 9000 },
+            "tags": { "http.method": "GET", "http.path": "/api" }
+        });
+
+        let span = convert_zipkin_span(serde_json::from_value(raw).unwrap()).unwrap();
+        assert_eq!(span.trace_id.as_str(), "00000000000000005af7183fb1d4cf5f");
+        assert_eq!(span.span_id.as_str(), "352bff9a74ca9ad2");
+        assert_eq!(span.parent_span_id.unwrap().as_str(), "6b221d5bc9e6496c");
+        assert_eq!(span.service_name.as_str(), "frontend");
+        assert_eq!(span.operation_name, "get /api");
+        assert_eq!(span.kind, SpanKind::Client);
+        assert_eq!(span.duration, Duration::from_micros(1431));
+        assert_eq!(span.start_time, UNIX_EPOCH + Duration::from_micros(1_556_604_172_355_737));
+        assert_eq!(span.attributes.get("http.method"), Some("GET"));
+        assert_eq!(span.attributes.get("peer.service"), Some("backend"));
+        assert_eq!(span.attributes.get("span.kind"), Some("client"));
+    }
+
+    #[test]
+    fn test_zipkin_error_tag_sets_status() {
+        let raw = json!({
+            "traceId": "463ac35c9f6413ad48485a3953bb6124",
+            "id": "a2fb4a1d1a96d312",
+            "name": "query",
+            "timestamp": 1_556_604_172_355_737u64,
+            "localEndpoint": { "serviceName": "db" },
+            "tags": { "error": "connection refused" }
+        });
+
+        let span = convert_zipkin_span(serde_json::from_value(raw).unwrap()).unwrap();
+        assert_eq!(span.status, SpanStatus::Error("connection refused".to_string()));
+    }
+
+    #[test]
+    fn test_zipkin_batch_rejects_spans_individually() {
+        let batch = vec![
+            json!({ "traceId": "5af7183fb1d4cf5f", "id": "352bff9a74ca9ad2", "name": "ok" }),
+            json!({ "id": "352bff9a74ca9ad3", "name": "no trace id" }),
+            json!({ "traceId": "5af7183fb1d4cf5f", "name": "no span id" }),
+            json!({ "traceId": "not-hex", "id": "352bff9a74ca9ad4" }),
+        ];
+
+        let (spans, errors) = convert_zipkin_batch(batch);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("span[1]") && errors[0].contains("traceId"));
+        assert!(errors[1].contains("span[2]") && errors[1].contains("id"));
+    }
+}
@@ -0,0 +1,265 @@
+//! Fair head sampling across services
+//!
+//! Each service gets a token bucket refilled at its ceiling (kept traces per
+//! minute), and all services share a global bucket. A trace is kept when its
+//! service has a token and either the global bucket has one too or the
+//! service is still under its floor, so quiet services stay represented while
+//! noisy ones hit their own limit and the global cap first.
+//!
+//! The decision is made on the first span of a trace and cached, so every
+//! span of a trace gets the same decision.
+
+use super::SamplingDecision;
+use crate::core::{system_clock, FairnessConfig, SharedClock, TraceId};
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::Instant;
+
+/// Number of recent trace decisions remembered.
+const DECISION_CACHE_SIZE: usize = 65_536;
+
+/// Token bucket refilled continuously, holding at most one minute of tokens.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    per_minute: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(per_minute),
+            per_minute: f64::from(per_minute),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_minute / 60.0).min(self.per_minute);
+        self.last_refill = now;
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn refund(&mut self) {
+        self.tokens = (self.tokens + 1.0).min(self.per_minute);
+    }
+}
+
+/// Buckets and counters for one service.
+#[derive(Debug)]
+struct ServiceState {
+    ceiling: TokenBucket,
+    floor: TokenBucket,
+    seen: u64,
+    kept: u64,
+    first_seen: Instant,
+}
+
+struct FairState {
+    global: TokenBucket,
+    services: HashMap<String, ServiceState>,
+    decisions: LruCache<TraceId, SamplingDecision>,
+}
+
+/// Achieved sampling for one service.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ServiceSamplingStats {
+    /// Service name
+    pub service: String,
+    /// Traces seen
+    pub seen: u64,
+    /// Traces kept
+    pub kept: u64,
+    /// Average kept traces per minute since the service was first seen
+    pub kept_per_minute: f64,
+}
+
+/// Head sampler keeping traces fairly across services.
+pub struct FairSampler {
+    config: FairnessConfig,
+    state: Mutex<FairState>,
+    clock: SharedClock,
+}
+
+impl FairSampler {
+    /// Create a fair sampler.
+    pub fn new(config: FairnessConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Create a fair sampler refilling its buckets by `clock`.
+    pub fn with_clock(config: FairnessConfig, clock: SharedClock) -> Self {
+        let now = clock.instant();
+        let state = FairState {
+            global: TokenBucket::new(config.global_per_minute, now),
+            services: HashMap::new(),
+            decisions: LruCache::new(
+                NonZeroUsize::new(DECISION_CACHE_SIZE).expect("cache size is non-zero"),
+            ),
+        };
+        Self {
+            config,
+            state: Mutex::new(state),
+            clock,
+        }
+    }
+
+    /// Decide whether to keep the trace a span of `service` belongs to.
+    pub fn should_sample(&self, trace_id: &TraceId, service: &str) -> SamplingDecision {
+        let now = self.clock.instant();
+        let mut state = self.state.lock();
+        if let Some(decision) = state.decisions.get(trace_id) {
+            return *decision;
+        }
+
+        let FairState {
+            global, services, ..
+        } = &mut *state;
+        let service_state = services.entry(service.to_string()).or_insert_with(|| {
+            let (floor, ceiling) = self.config.limits(service);
+            ServiceState {
+                ceiling: TokenBucket::new(ceiling, now),
+                floor: TokenBucket::new(floor, now),
+                seen: 0,
+                kept: 0,
+                first_seen: now,
+            }
+        });
+        service_state.seen += 1;
+
+        let keep = if !service_state.ceiling.try_take(now) {
+            false
+        } else if global.try_take(now) || service_state.floor.try_take(now) {
+            true
+        } else {
+            service_state.ceiling.refund();
+            false
+        };
+
+        let decision = if keep {
+            service_state.kept += 1;
+            SamplingDecision::Keep
+        } else {
+            SamplingDecision::Drop
+        };
+        state.decisions.put(trace_id.clone(), decision);
+        decision
+    }
+
+    /// Achieved sampling per service, sorted by service name.
+    pub fn stats(&self) -> Vec<ServiceSamplingStats> {
+        let now = self.clock.instant();
+        let state = self.state.lock();
+        let mut per_service: Vec<_> = state
+            .services
+            .iter()
+            .map(|(service, s)| {
+                let minutes = now.saturating_duration_since(s.first_seen).as_secs_f64() / 60.0;
+                ServiceSamplingStats {
+                    service: service.clone(),
+                    seen: s.seen,
+                    kept: s.kept,
+                    kept_per_minute: s.kept as f64 / minutes.max(1.0),
+                }
+            })
+            .collect();
+        per_service.sort_by(|a, b| a.service.cmp(&b.service));
+        per_service
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MockClock, ServiceFairnessConfig};
+    use std::time::Duration;
+
+    fn trace_id(n: u64) -> TraceId {
+        TraceId::new(format!("{:032x}", n)).unwrap()
+    }
+
+    #[test]
+    fn test_same_trace_same_decision() {
+        let config = FairnessConfig {
+            per_service_per_minute: 1,
+            ..Default::default()
+        };
+        let sampler = FairSampler::new(config);
+
+        assert_eq!(sampler.should_sample(&trace_id(1), "api"), SamplingDecision::Keep);
+        assert_eq!(sampler.should_sample(&trace_id(2), "api"), SamplingDecision::Drop);
+        // Later spans of a kept trace are kept even from another service
+        assert_eq!(sampler.should_sample(&trace_id(1), "db"), SamplingDecision::Keep);
+    }
+
+    #[test]
+    fn test_skewed_traffic_keeps_quiet_service_represented() {
+        let config = FairnessConfig {
+            per_service_per_minute: 300,
+            global_per_minute: 250,
+            floor_per_minute: 10,
+            services: HashMap::from([(
+                "batch".to_string(),
+                ServiceFairnessConfig {
+                    floor: None,
+                    ceiling: Some(50),
+                },
+            )]),
+        };
+        let clock = MockClock::default();
+        let sampler = FairSampler::with_clock(config, clock.shared());
+
+        // 5 simulated minutes: "noisy" sends 100 traces/s, "batch" 1/s but is
+        // capped at 50/min, "quiet" one every 6s.
+        let mut next_id = 0;
+        for tick in 0..(5 * 60 * 10) {
+            for _ in 0..10 {
+                next_id += 1;
+                sampler.should_sample(&trace_id(next_id), "noisy");
+            }
+            if tick % 10 == 0 {
+                next_id += 1;
+                sampler.should_sample(&trace_id(next_id), "batch");
+            }
+            if tick % 60 == 0 {
+                next_id += 1;
+                sampler.should_sample(&trace_id(next_id), "quiet");
+            }
+            clock.advance(Duration::from_millis(100));
+        }
+
+        let stats = sampler.stats();
+        let by_service = |name: &str| stats.iter().find(|s| s.service == name).unwrap().clone();
+        let (noisy, quiet, batch) = (by_service("noisy"), by_service("quiet"), by_service("batch"));
+
+        // The global cap is exhausted, yet the quiet service keeps every trace
+        assert_eq!(quiet.seen, 50);
+        assert_eq!(quiet.kept, 50);
+
+        // Noisy services are held to their ceilings (plus the initial burst)
+        assert_eq!(noisy.seen, 30_000);
+        assert!(noisy.kept <= 300 * 6, "noisy kept {}", noisy.kept);
+        assert!(batch.kept <= 50 * 6, "batch kept {}", batch.kept);
+        assert!(batch.kept_per_minute <= 60.0);
+        assert!((quiet.kept_per_minute - 10.0).abs() < f64::EPSILON);
+
+        // Total stays within the global cap plus floors
+        let total: u64 = stats.iter().map(|s| s.kept).sum();
+        assert!(total <= 250 * 6 + 3 * 10 * 6, "total kept {}", total);
+    }
+}
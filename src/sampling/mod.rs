@@ -7,16 +7,26 @@
 
 pub mod adaptive;
 pub mod budget;
+pub mod fairness;
 pub mod pattern;
+pub mod rate_limiting;
 pub mod tail_based;
 
 pub use adaptive::AdaptiveSampler;
 pub use budget::BudgetAwareSampler;
+pub use fairness::{FairSampler, ServiceSamplingStats};
 pub use pattern::PatternDetector;
+pub use rate_limiting::RateLimitingSampler;
 pub use tail_based::TailBasedSampler;
 
-use crate::core::TraceId;
+/// Per-service rates shared by the receiver and its readers, swapped on
+/// config reload.
+pub type SharedServiceRates = Arc<arc_swap::ArcSwap<ServiceRates>>;
+
+use crate::core::config::{SamplingConfig, SamplingRule};
+use crate::core::{Glob, Span, TraceId};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Sampling decision result
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -151,6 +161,179 @@ pub struct SystemMetrics {
     pub memory_usage: f64,
 }
 
+/// Ordered sampling rules from the `sampling.rules` config section.
+///
+/// The first rule matching a span decides; spans no rule matches are kept
+/// at the default rate. Rate decisions hash the trace ID, so all spans of a
+/// trace matched by the same rule share the outcome.
+#[derive(Debug, Clone, Default)]
+pub struct SamplingRules {
+    rules: Vec<SamplingRule>,
+    default_rate: f64,
+}
+
+impl SamplingRules {
+    /// Rules tried in order, falling back to `default_rate`.
+    pub fn new(rules: Vec<SamplingRule>, default_rate: f64) -> Self {
+        Self {
+            rules,
+            default_rate,
+        }
+    }
+
+    /// Rules and default rate of the `sampling` config section.
+    pub fn from_config(config: &SamplingConfig) -> Self {
+        Self::new(config.rules.clone(), config.default_rate)
+    }
+
+    /// Returns true if no rules are configured.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Decide whether to keep `span`. `trace_duration` replaces the span's
+    /// own duration for `min_duration_ms` when the whole trace is known.
+    pub fn apply_rules(&self, span: &Span, trace_duration: Option<Duration>) -> SamplingDecision {
+        self.apply_rules_with_rates(span, trace_duration, &ServiceRates::default())
+    }
+
+    /// Like [`apply_rules`](Self::apply_rules), but spans no rule matches are
+    /// kept at their service's rate in `rates` when it has one.
+    pub fn apply_rules_with_rates(
+        &self,
+        span: &Span,
+        trace_duration: Option<Duration>,
+        rates: &ServiceRates,
+    ) -> SamplingDecision {
+        let duration = trace_duration.unwrap_or(span.duration);
+        let rule = self.rules.iter().find(|rule| {
+            rule.service_matches.matches(span.service_name.as_str())
+                && rule.operation_matches.matches(&span.operation_name)
+                && rule
+                    .min_duration_ms
+                    .map_or(true, |min| duration >= Duration::from_millis(min))
+                && rule
+                    .has_error
+                    .map_or(true, |error| span.is_error() == error)
+        });
+
+        let rate = match rule {
+            Some(rule) if rule.always_keep => return SamplingDecision::Keep,
+            Some(rule) if rule.always_drop => return SamplingDecision::Drop,
+            Some(rule) => rule.rate.unwrap_or(self.default_rate),
+            None => rates
+                .override_rate(span.service_name.as_str())
+                .unwrap_or(self.default_rate),
+        };
+        if keep_at_rate(&span.trace_id, rate) {
+            SamplingDecision::Keep
+        } else {
+            SamplingDecision::Drop
+        }
+    }
+}
+
+/// Per-service sampling rates from the `sampling.per_service` config section.
+///
+/// Keys are service names or globs such as `health*`. An exact name wins
+/// over globs, and among matching globs the longest pattern wins.
+#[derive(Debug, Clone)]
+pub struct ServiceRates {
+    /// Overrides, most specific first
+    overrides: Vec<(Glob, f64)>,
+    default_rate: f64,
+}
+
+impl Default for ServiceRates {
+    fn default() -> Self {
+        Self::new(std::iter::empty(), 1.0)
+    }
+}
+
+impl ServiceRates {
+    /// Rates for services matching each pattern, falling back to `default_rate`.
+    pub fn new(overrides: impl IntoIterator<Item = (String, f64)>, default_rate: f64) -> Self {
+        let mut overrides: Vec<(Glob, f64)> = overrides
+            .into_iter()
+            .map(|(pattern, rate)| (Glob::new(pattern), rate))
+            .collect();
+        overrides.sort_by(|(a, _), (b, _)| {
+            let is_glob = |glob: &Glob| glob.as_str().contains(['*', '?']);
+            is_glob(a)
+                .cmp(&is_glob(b))
+                .then_with(|| b.as_str().len().cmp(&a.as_str().len()))
+                .then_with(|| a.as_str().cmp(b.as_str()))
+        });
+        Self {
+            overrides,
+            default_rate,
+        }
+    }
+
+    /// Per-service rates and default rate of the `sampling` config section.
+    pub fn from_config(config: &SamplingConfig) -> Self {
+        Self::new(
+            config
+                .per_service
+                .iter()
+                .map(|(service, &rate)| (service.clone(), rate)),
+            config.default_rate,
+        )
+    }
+
+    /// Use `rate` for services without an override.
+    pub fn with_default_rate(mut self, rate: f64) -> Self {
+        self.default_rate = rate;
+        self
+    }
+
+    /// Rate used for services without an override.
+    pub fn default_rate(&self) -> f64 {
+        self.default_rate
+    }
+
+    /// The override for `service` and the pattern it came from, if any.
+    pub fn matching_override(&self, service: &str) -> Option<(&str, f64)> {
+        self.overrides
+            .iter()
+            .find(|(pattern, _)| pattern.matches(service))
+            .map(|(pattern, rate)| (pattern.as_str(), *rate))
+    }
+
+    /// The overriding rate for `service`, if any.
+    pub fn override_rate(&self, service: &str) -> Option<f64> {
+        self.matching_override(service).map(|(_, rate)| rate)
+    }
+
+    /// Effective sampling rate of `service`.
+    pub fn rate(&self, service: &str) -> f64 {
+        self.override_rate(service).unwrap_or(self.default_rate)
+    }
+
+    /// Decide whether to keep `span` by its service's override, or `None`
+    /// when its service has none.
+    pub fn decide(&self, span: &Span) -> Option<SamplingDecision> {
+        self.override_rate(span.service_name.as_str()).map(|rate| {
+            if keep_at_rate(&span.trace_id, rate) {
+                SamplingDecision::Keep
+            } else {
+                SamplingDecision::Drop
+            }
+        })
+    }
+}
+
+/// Keep a trace with probability `rate`, the same way for every span of it.
+fn keep_at_rate(trace_id: &TraceId, rate: f64) -> bool {
+    if rate >= 1.0 {
+        true
+    } else if rate <= 0.0 {
+        false
+    } else {
+        (fast_hash(trace_id) as f64) < rate * u64::MAX as f64
+    }
+}
+
 /// Fast non-cryptographic hash for sampling decisions
 #[inline(always)]
 fn fast_hash(trace_id: &TraceId) -> u64 {
@@ -199,4 +382,164 @@ mod tests {
         let decision = sampler.should_sample_tail(&characteristics).await;
         assert_eq!(decision, SamplingDecision::Keep);
     }
+
+    fn rules_from_yaml(yaml: &str) -> SamplingRules {
+        let config = crate::core::ConfigBuilder::new()
+            .from_yaml(yaml)
+            .unwrap()
+            .build()
+            .unwrap();
+        SamplingRules::from_config(&config.sampling)
+    }
+
+    fn span(trace: u64, service: &str, operation: &str, millis: u64, error: bool) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(crate::core::SpanId::new(format!("{:016x}", trace)).unwrap())
+            .service_name(crate::core::ServiceName::new(service.to_string()).unwrap())
+            .operation_name(operation)
+            .start_time(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .duration(Duration::from_millis(millis))
+            .status(if error {
+                crate::core::SpanStatus::Error("boom".to_string())
+            } else {
+                crate::core::SpanStatus::Ok
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_always_keep_rule_retains_all_payment_spans() {
+        let rules = rules_from_yaml(
+            r#"
+sampling:
+  default_rate: 0.0
+  per_service: {}
+  adaptive: false
+  rules:
+    - service_matches: "payment*"
+      always_keep: true
+"#,
+        );
+
+        for trace in 1..=1000 {
+            let payment = span(trace, "payment-service", "charge", 10, false);
+            assert_eq!(rules.apply_rules(&payment, None), SamplingDecision::Keep);
+            let checkout = span(trace, "checkout", "charge", 10, false);
+            assert_eq!(rules.apply_rules(&checkout, None), SamplingDecision::Drop);
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = rules_from_yaml(
+            r#"
+sampling:
+  default_rate: 1.0
+  per_service: {}
+  adaptive: false
+  rules:
+    - has_error: true
+      always_keep: true
+    - operation_matches: "GET /health*"
+      always_drop: true
+    - service_matches: "batch-*"
+      min_duration_ms: 500
+      rate: 0.0
+"#,
+        );
+
+        let errored_health = span(1, "api", "GET /healthz", 1, true);
+        assert_eq!(rules.apply_rules(&errored_health, None), SamplingDecision::Keep);
+        let health = span(2, "api", "GET /healthz", 1, false);
+        assert_eq!(rules.apply_rules(&health, None), SamplingDecision::Drop);
+
+        // Duration from the span, or from the trace when known
+        let short_job = span(3, "batch-export", "run", 100, false);
+        assert_eq!(rules.apply_rules(&short_job, None), SamplingDecision::Keep);
+        let trace_duration = Some(Duration::from_secs(2));
+        assert_eq!(rules.apply_rules(&short_job, trace_duration), SamplingDecision::Drop);
+
+        // No match falls back to the default rate
+        let other = span(4, "api", "GET /users", 1, false);
+        assert_eq!(rules.apply_rules(&other, None), SamplingDecision::Keep);
+    }
+
+    #[test]
+    fn test_rule_rate_is_consistent_per_trace() {
+        let rules = rules_from_yaml(
+            r#"
+sampling:
+  default_rate: 1.0
+  per_service: {}
+  adaptive: false
+  rules:
+    - service_matches: "*"
+      rate: 0.25
+"#,
+        );
+
+        let kept = (1..=4000)
+            .filter(|&trace| {
+                let decision = rules.apply_rules(&span(trace, "api", "GET", 1, false), None);
+                // Another span of the same trace gets the same decision
+                let sibling = span(trace, "db", "SELECT", 1, false);
+                assert_eq!(rules.apply_rules(&sibling, None), decision);
+                decision == SamplingDecision::Keep
+            })
+            .count();
+        assert!((800..1200).contains(&kept), "kept {} of 4000", kept);
+    }
+
+    #[test]
+    fn test_service_rates_prefer_exact_names_then_longest_glob() {
+        let rates = ServiceRates::new(
+            [
+                ("health*".to_string(), 0.01),
+                ("healthcheck-*".to_string(), 0.1),
+                ("healthcheck-eu".to_string(), 0.5),
+                ("checkout".to_string(), 1.0),
+            ],
+            0.2,
+        );
+
+        assert_eq!(rates.matching_override("healthcheck-eu"), Some(("healthcheck-eu", 0.5)));
+        assert_eq!(rates.matching_override("healthcheck-us"), Some(("healthcheck-*", 0.1)));
+        assert_eq!(rates.rate("healthz"), 0.01);
+        assert_eq!(rates.rate("checkout"), 1.0);
+        assert_eq!(rates.rate("payments"), 0.2);
+        assert_eq!(rates.decide(&span(1, "payments", "charge", 1, false)), None);
+
+        let kept = (1..=4000)
+            .filter(|&trace| {
+                rates.decide(&span(trace, "healthz", "GET", 1, false))
+                    == Some(SamplingDecision::Keep)
+            })
+            .count();
+        assert!(kept < 100, "kept {} of 4000", kept);
+    }
+
+    #[test]
+    fn test_unmatched_rules_fall_back_to_service_rates() {
+        let rules = rules_from_yaml(
+            r#"
+sampling:
+  default_rate: 1.0
+  per_service: {}
+  adaptive: false
+  rules:
+    - has_error: true
+      always_keep: true
+"#,
+        );
+        let rates = ServiceRates::new([("healthcheck".to_string(), 0.0)], 1.0);
+
+        let failed = span(1, "healthcheck", "GET", 1, true);
+        assert_eq!(rules.apply_rules_with_rates(&failed, None, &rates), SamplingDecision::Keep);
+        let ok = span(2, "healthcheck", "GET", 1, false);
+        assert_eq!(rules.apply_rules_with_rates(&ok, None, &rates), SamplingDecision::Drop);
+        let other = span(3, "checkout", "GET", 1, false);
+        assert_eq!(rules.apply_rules_with_rates(&other, None, &rates), SamplingDecision::Keep);
+    }
 }
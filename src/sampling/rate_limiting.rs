@@ -0,0 +1,137 @@
+//! Per-service span rate limiting
+//!
+//! Each service gets a token bucket refilled at the configured spans per
+//! second and holding at most one second of tokens, so a burst can briefly
+//! reach the limit but the sustained rate never exceeds it. Unlike the fair
+//! sampler this decides per span, not per trace: it is a hard cap applied
+//! after sampling.
+
+use super::SamplingDecision;
+use crate::core::{system_clock, SharedClock};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Token bucket refilled continuously, holding at most one second of tokens.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn try_take(&mut self, per_second: f64, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(per_second);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Caps the spans kept per service per second.
+pub struct RateLimitingSampler {
+    per_second: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    dropped: AtomicU64,
+    clock: SharedClock,
+}
+
+impl RateLimitingSampler {
+    /// Create a sampler keeping at most `spans_per_second` spans of each service.
+    pub fn new(spans_per_second: u32) -> Self {
+        Self::with_clock(spans_per_second, system_clock())
+    }
+
+    /// Create a sampler refilling its buckets by `clock`.
+    pub fn with_clock(spans_per_second: u32, clock: SharedClock) -> Self {
+        Self {
+            per_second: f64::from(spans_per_second),
+            buckets: Mutex::new(HashMap::new()),
+            dropped: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// Spans kept per service per second.
+    pub fn spans_per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Decide whether to keep a span of `service`.
+    pub fn should_sample(&self, service: &str) -> SamplingDecision {
+        let now = self.clock.instant();
+        let mut buckets = self.buckets.lock();
+        let bucket = match buckets.get_mut(service) {
+            Some(bucket) => bucket,
+            None => buckets.entry(service.to_string()).or_insert(TokenBucket {
+                tokens: self.per_second,
+                last_refill: now,
+            }),
+        };
+        if bucket.try_take(self.per_second, now) {
+            SamplingDecision::Keep
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            SamplingDecision::Drop
+        }
+    }
+
+    /// Spans dropped because their service was over the limit.
+    pub fn dropped_spans(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::MockClock;
+    use std::time::Duration;
+
+    fn kept(sampler: &RateLimitingSampler, service: &str, spans: usize) -> usize {
+        (0..spans)
+            .filter(|_| sampler.should_sample(service) == SamplingDecision::Keep)
+            .count()
+    }
+
+    #[test]
+    fn test_burst_is_capped_over_window() {
+        let clock = MockClock::default();
+        let sampler = RateLimitingSampler::with_clock(100, clock.shared());
+
+        // A burst gets at most one second of tokens
+        assert_eq!(kept(&sampler, "api", 1_000), 100);
+        assert_eq!(sampler.dropped_spans(), 900);
+
+        // Over ten more seconds of bursts, the cap holds at 100 per second
+        let mut total = 0;
+        for _ in 0..100 {
+            clock.advance(Duration::from_millis(100));
+            total += kept(&sampler, "api", 500);
+        }
+        assert_eq!(total, 1_000);
+        assert_eq!(sampler.dropped_spans(), 900 + 100 * 500 - 1_000);
+    }
+
+    #[test]
+    fn test_services_have_separate_buckets() {
+        let clock = MockClock::default();
+        let sampler = RateLimitingSampler::with_clock(5, clock.shared());
+
+        assert_eq!(kept(&sampler, "noisy", 50), 5);
+        assert_eq!(kept(&sampler, "quiet", 3), 3);
+        assert_eq!(sampler.should_sample("noisy"), SamplingDecision::Drop);
+
+        // Idle time refills only up to one second of tokens
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(kept(&sampler, "noisy", 50), 5);
+    }
+}
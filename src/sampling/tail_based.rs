@@ -3,7 +3,7 @@
 //! PERFORMANCE: Deferred decisions with bounded memory usage
 
 use super::SamplingDecision;
-use crate::core::TraceId;
+use crate::core::{system_clock, SharedClock, TraceId};
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
@@ -19,6 +19,8 @@ pub struct TailBasedSampler {
     total_kept: AtomicU64,
     /// Memory limit for pending traces
     max_pending: AtomicUsize,
+    /// Time source for pending trace ages
+    clock: SharedClock,
 }
 
 /// Trace pending tail-based decision
@@ -39,9 +41,16 @@ impl TailBasedSampler {
             total_evaluated: AtomicU64::new(0),
             total_kept: AtomicU64::new(0),
             max_pending: AtomicUsize::new(10_000),
+            clock: system_clock(),
         }
     }
 
+    /// Age pending traces by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Register a span for tail-based evaluation
     pub fn register_span(
         &self,
@@ -59,7 +68,7 @@ impl TailBasedSampler {
             .pending
             .entry(trace_id)
             .or_insert_with(|| PendingTrace {
-                start_time: Instant::now(),
+                start_time: self.clock.instant(),
                 span_count: AtomicUsize::new(0),
                 has_error: AtomicU64::new(0),
                 max_duration_ns: AtomicU64::new(0),
@@ -117,7 +126,7 @@ impl TailBasedSampler {
 
     /// Cleanup old pending traces
     fn cleanup_old_traces(&self) {
-        let now = Instant::now();
+        let now = self.clock.instant();
         let mut to_remove = Vec::new();
 
         for entry in self.pending.iter() {
@@ -181,6 +190,7 @@ fn fast_probability(trace_id: &TraceId, per_10000: u64) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::MockClock;
 
     #[test]
     fn test_error_traces_kept() {
@@ -249,4 +259,22 @@ mod tests {
         assert!(kept > 0 && kept < 50);
         assert!(dropped > 950);
     }
+
+    #[test]
+    fn test_stale_pending_traces_are_dropped() {
+        let clock = MockClock::default();
+        let sampler = TailBasedSampler::new().with_clock(clock.shared());
+        let stale = TraceId::new("stale_trace".to_string()).unwrap();
+        let fresh = TraceId::new("fresh_trace".to_string()).unwrap();
+
+        sampler.register_span(stale.clone(), false, 10_000_000, "service1".to_string());
+        clock.advance(Duration::from_secs(20));
+        sampler.register_span(fresh.clone(), false, 10_000_000, "service1".to_string());
+        clock.advance(Duration::from_secs(15));
+
+        // Only the trace pending longer than max_wait (30s) goes
+        sampler.cleanup_old_traces();
+        assert!(!sampler.pending.contains_key(&stale));
+        assert!(sampler.pending.contains_key(&fresh));
+    }
 }
@@ -3,8 +3,9 @@
 //! This module automatically analyzes traces to build service dependency graphs,
 //! showing how services call each other, with performance and error metrics.
 
-use crate::core::{Result, ServiceName, Span, TraceId};
-use crate::storage::StorageBackend;
+use crate::core::{Result, ServiceName, Span};
+use crate::storage::{StorageBackend, TraceInfo};
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -63,9 +64,14 @@ pub struct ServiceMap {
     pub time_window_seconds: u64,
 }
 
+/// Default number of traces fetched concurrently while building a map.
+pub const DEFAULT_BUILD_CONCURRENCY: usize = 16;
+
 /// Service map builder that analyzes traces.
 pub struct ServiceMapBuilder<'a> {
     storage: &'a dyn StorageBackend,
+    /// Maximum trace fetches in flight
+    concurrency: usize,
     /// Service -> (request_count, error_count, total_latency)
     service_metrics: HashMap<ServiceName, (u64, u64, u64)>,
     /// (from, to) -> edge data
@@ -88,13 +94,24 @@ impl<'a> ServiceMapBuilder<'a> {
     pub fn new(storage: &'a dyn StorageBackend) -> Self {
         Self {
             storage,
+            concurrency: DEFAULT_BUILD_CONCURRENCY,
             service_metrics: HashMap::new(),
             edges: HashMap::new(),
             services: HashSet::new(),
         }
     }
 
+    /// Set how many traces are fetched concurrently (at least 1).
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
     /// Build service map from recent traces.
+    ///
+    /// Trace spans are fetched with up to `concurrency` requests in flight;
+    /// each result is folded into the aggregates as it completes, so the map
+    /// does not depend on completion order.
     pub async fn build_from_recent_traces(
         &mut self,
         limit: usize,
@@ -102,7 +119,29 @@ impl<'a> ServiceMapBuilder<'a> {
     ) -> Result<ServiceMap> {
         // Get recent traces
         let traces = self.storage.list_traces(None, None, None, limit).await?;
+        self.build_from_traces(&traces, time_window_seconds).await
+    }
+
+    /// Build service map from up to `limit` of the traces that started in
+    /// the last `time_window_seconds`.
+    pub async fn build_from_window(
+        &mut self,
+        limit: usize,
+        time_window_seconds: u64,
+    ) -> Result<ServiceMap> {
+        let since = std::time::SystemTime::now()
+            .checked_sub(std::time::Duration::from_secs(time_window_seconds))
+            .and_then(|since| since.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos() as u64);
+        let traces = self.storage.list_traces(None, since, None, limit).await?;
+        self.build_from_traces(&traces, time_window_seconds).await
+    }
 
+    async fn build_from_traces(
+        &mut self,
+        traces: &[TraceInfo],
+        time_window_seconds: u64,
+    ) -> Result<ServiceMap> {
         if traces.is_empty() {
             return Ok(ServiceMap {
                 nodes: Vec::new(),
@@ -113,32 +152,31 @@ impl<'a> ServiceMapBuilder<'a> {
             });
         }
 
-        // Analyze each trace
-        for trace_info in &traces {
-            self.analyze_trace(&trace_info.trace_id).await?;
+        // Boxed fetch futures are lazy; buffer_unordered bounds how many run
+        let fetches: Vec<_> = traces
+            .iter()
+            .map(|trace_info| self.storage.get_trace_spans(&trace_info.trace_id))
+            .collect();
+        let mut fetches = stream::iter(fetches).buffer_unordered(self.concurrency);
+        while let Some(spans) = fetches.next().await {
+            self.analyze_trace(&spans?);
         }
 
         // Build the final map
         Ok(self.build_map(traces.len() as u64, time_window_seconds))
     }
 
-    /// Analyze a single trace to extract dependencies.
-    async fn analyze_trace(&mut self, trace_id: &TraceId) -> Result<()> {
-        let spans = self.storage.get_trace_spans(trace_id).await?;
-
-        if spans.is_empty() {
-            return Ok(());
-        }
-
+    /// Analyze the spans of a single trace to extract dependencies.
+    fn analyze_trace(&mut self, spans: &[Span]) {
         // Build span lookup map
         let mut span_map: HashMap<String, &Span> = HashMap::new();
-        for span in &spans {
+        for span in spans {
             span_map.insert(span.span_id.as_str().to_string(), span);
             self.services.insert(span.service_name.clone());
         }
 
         // Process each span to find service calls
-        for span in &spans {
+        for span in spans {
             // Update service metrics
             let metrics = self
                 .service_metrics
@@ -166,8 +204,6 @@ impl<'a> ServiceMapBuilder<'a> {
                 }
             }
         }
-
-        Ok(())
     }
 
     /// Record a service-to-service call.
@@ -388,7 +424,7 @@ pub mod api {
 mod tests {
     use super::*;
     use crate::{
-        core::{SpanBuilder, SpanId},
+        core::{SpanBuilder, SpanId, TraceId},
         storage::InMemoryStorage,
     };
 
@@ -457,4 +493,91 @@ mod tests {
             .iter()
             .any(|e| e.from.as_str() == "backend" && e.to.as_str() == "database"));
     }
+
+    /// Comparable view of a map: edges sorted, timestamps dropped.
+    fn summary(map: &ServiceMap) -> (Vec<String>, Vec<String>) {
+        let nodes = map
+            .nodes
+            .iter()
+            .map(|n| {
+                format!(
+                    "{} req={} err={:.3} avg={} root={} leaf={} tier={}",
+                    n.name.as_str(),
+                    n.request_count,
+                    n.error_rate,
+                    n.avg_latency_us,
+                    n.is_root,
+                    n.is_leaf,
+                    n.tier
+                )
+            })
+            .collect();
+        let mut edges: Vec<String> = map
+            .edges
+            .iter()
+            .map(|e| {
+                let mut ops: Vec<_> = e.operations.iter().collect();
+                ops.sort();
+                format!(
+                    "{}->{} calls={} err={} avg={} p99={} ops={:?}",
+                    e.from.as_str(),
+                    e.to.as_str(),
+                    e.call_count,
+                    e.error_count,
+                    e.avg_latency_us,
+                    e.p99_latency_us,
+                    ops
+                )
+            })
+            .collect();
+        edges.sort();
+        (nodes, edges)
+    }
+
+    #[tokio::test]
+    async fn test_parallel_build_matches_sequential() {
+        let storage = InMemoryStorage::new(100_000);
+        let services = ["gateway", "orders", "payments", "inventory", "postgres"];
+        let start = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        for trace in 0..200u64 {
+            let trace_id = TraceId::new(format!("{:032x}", trace + 1)).unwrap();
+            // A chain of varying depth, with every 7th call failing
+            let depth = 2 + (trace % 4) as usize;
+            for (i, service) in services.iter().take(depth).enumerate() {
+                let mut builder = SpanBuilder::default()
+                    .trace_id(trace_id.clone())
+                    .span_id(SpanId::new(format!("{:08x}{:08x}", trace + 1, i + 1)).unwrap())
+                    .service_name(ServiceName::new(service.to_string()).unwrap())
+                    .operation_name(format!("op-{}", trace % 3))
+                    .start_time(start + std::time::Duration::from_millis(trace))
+                    .duration(std::time::Duration::from_micros(100 * (trace + i as u64 + 1)));
+                if i > 0 {
+                    builder = builder.parent_span_id(
+                        SpanId::new(format!("{:08x}{:08x}", trace + 1, i)).unwrap(),
+                    );
+                }
+                if (trace + i as u64) % 7 == 0 {
+                    builder = builder.status(crate::core::SpanStatus::Error("boom".to_string()));
+                }
+                storage.store_span(builder.build().unwrap()).await.unwrap();
+            }
+        }
+
+        let sequential = ServiceMapBuilder::new(&storage)
+            .with_concurrency(1)
+            .build_from_recent_traces(1000, 3600)
+            .await
+            .unwrap();
+        let parallel = ServiceMapBuilder::new(&storage)
+            .with_concurrency(32)
+            .build_from_recent_traces(1000, 3600)
+            .await
+            .unwrap();
+
+        assert_eq!(sequential.trace_count, 200);
+        assert_eq!(parallel.trace_count, sequential.trace_count);
+        assert_eq!(sequential.edges.len(), 4);
+        assert_eq!(summary(&parallel), summary(&sequential));
+    }
 }
@@ -0,0 +1,602 @@
+//! Append-only disk archive for cold spans.
+//!
+//! Spans older than the archive age are moved out of memory into a single
+//! append-only file. Each block is a fixed header followed by a
+//! zstd-compressed bincode `Vec<Span>`:
+//!
+//! ```text
+//! magic "UARC" | min start (u64 ns) | max start (u64 ns) | span count (u32) | payload len (u32) | payload
+//! ```
+//!
+//! All integers are little-endian. A truncated trailing block (e.g. after a
+//! crash mid-write) is dropped when the archive is reopened.
+
+use crate::core::{Result, Span, TraceId, UrpoError};
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File name of the archive inside the archive directory.
+pub const ARCHIVE_FILE_NAME: &str = "spans.archive";
+
+const BLOCK_MAGIC: &[u8; 4] = b"UARC";
+const BLOCK_HEADER_LEN: u64 = 28;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Location and time range of one archived block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveBlock {
+    /// Byte offset of the block header in the archive file
+    pub offset: u64,
+    /// Earliest span start time in the block, in nanoseconds since the epoch
+    pub min_start_nanos: u64,
+    /// Latest span start time in the block, in nanoseconds since the epoch
+    pub max_start_nanos: u64,
+    /// Number of spans in the block
+    pub span_count: u32,
+    /// Compressed payload length in bytes
+    pub payload_len: u32,
+}
+
+impl ArchiveBlock {
+    fn encode_header(&self) -> [u8; BLOCK_HEADER_LEN as usize] {
+        let mut header = [0u8; BLOCK_HEADER_LEN as usize];
+        header[0..4].copy_from_slice(BLOCK_MAGIC);
+        header[4..12].copy_from_slice(&self.min_start_nanos.to_le_bytes());
+        header[12..20].copy_from_slice(&self.max_start_nanos.to_le_bytes());
+        header[20..24].copy_from_slice(&self.span_count.to_le_bytes());
+        header[24..28].copy_from_slice(&self.payload_len.to_le_bytes());
+        header
+    }
+
+    fn decode_header(offset: u64, header: &[u8; BLOCK_HEADER_LEN as usize]) -> Option<Self> {
+        if &header[0..4] != BLOCK_MAGIC {
+            return None;
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(header[i..i + 8].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+        Some(Self {
+            offset,
+            min_start_nanos: u64_at(4),
+            max_start_nanos: u64_at(12),
+            span_count: u32_at(20),
+            payload_len: u32_at(24),
+        })
+    }
+
+    /// Offset just past the end of this block.
+    fn end(&self) -> u64 {
+        self.offset + BLOCK_HEADER_LEN + u64::from(self.payload_len)
+    }
+}
+
+fn to_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .min(u128::from(u64::MAX)) as u64
+}
+
+fn encode_spans(spans: &[Span]) -> Result<Vec<u8>> {
+    let serialized = bincode::serialize(spans)
+        .map_err(|e| UrpoError::storage(format!("Archive serialization failed: {}", e)))?;
+    Ok(zstd::encode_all(serialized.as_slice(), ZSTD_LEVEL)?)
+}
+
+/// Encode `spans` as a block written at `offset`.
+fn encode_block(spans: &[Span], offset: u64) -> Result<(ArchiveBlock, Vec<u8>)> {
+    let payload = encode_spans(spans)?;
+    let payload_len = u32::try_from(payload.len())
+        .map_err(|_| UrpoError::storage("Archive block exceeds 4GiB"))?;
+    let starts = spans.iter().map(|s| to_nanos(s.start_time));
+    let block = ArchiveBlock {
+        offset,
+        min_start_nanos: starts.clone().min().unwrap_or(0),
+        max_start_nanos: starts.max().unwrap_or(0),
+        span_count: spans.len() as u32,
+        payload_len,
+    };
+    Ok((block, payload))
+}
+
+fn read_block(file: &mut File, block: &ArchiveBlock) -> Result<Vec<Span>> {
+    file.seek(SeekFrom::Start(block.offset + BLOCK_HEADER_LEN))?;
+    let mut payload = vec![0u8; block.payload_len as usize];
+    file.read_exact(&mut payload)?;
+    let serialized = zstd::decode_all(payload.as_slice())?;
+    bincode::deserialize(&serialized).map_err(|e| {
+        UrpoError::storage(format!("Corrupt archive block at {}: {}", block.offset, e))
+    })
+}
+
+/// Read every complete block header, stopping at the first incomplete block.
+fn scan_blocks(file: &mut File) -> Result<Vec<ArchiveBlock>> {
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(&mut *file);
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    let mut header = [0u8; BLOCK_HEADER_LEN as usize];
+    while offset + BLOCK_HEADER_LEN <= file_len {
+        reader.read_exact(&mut header)?;
+        let Some(block) = ArchiveBlock::decode_header(offset, &header) else {
+            break;
+        };
+        if block.end() > file_len {
+            break;
+        }
+        reader.seek_relative(i64::from(block.payload_len))?;
+        offset = block.end();
+        blocks.push(block);
+    }
+
+    if offset < file_len {
+        tracing::warn!("Ignoring {} trailing bytes of incomplete archive data", file_len - offset);
+    }
+    Ok(blocks)
+}
+
+/// Trace index of an archive, saved beside it so it can be reopened without
+/// decompressing every block.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchiveTraceIndex {
+    /// Blocks the index describes
+    pub blocks: Vec<ArchiveBlock>,
+    /// Trace ID to indices into `blocks`
+    pub trace_blocks: Vec<(TraceId, Vec<usize>)>,
+}
+
+/// Open (or create) the archive file at `path`, dropping an incomplete
+/// trailing block.
+fn open_blocks(path: &Path) -> Result<(File, Vec<ArchiveBlock>)> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    let blocks = scan_blocks(&mut file)?;
+    file.set_len(blocks.last().map_or(0, ArchiveBlock::end))?;
+    Ok((file, blocks))
+}
+
+/// Append-only span archive with a trace index.
+pub struct SpanArchive {
+    path: PathBuf,
+    writer: Mutex<File>,
+    blocks: RwLock<Vec<ArchiveBlock>>,
+    /// Trace ID to indices into `blocks`.
+    trace_blocks: DashMap<TraceId, Vec<usize>>,
+}
+
+impl SpanArchive {
+    /// Open (or create) the archive in `dir`, rebuilding the trace index.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        Self::open_file(dir.join(ARCHIVE_FILE_NAME))
+    }
+
+    /// Open (or create) the archive file at `path`, rebuilding the trace index.
+    pub fn open_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let (mut file, blocks) = open_blocks(&path)?;
+
+        let trace_blocks: DashMap<TraceId, Vec<usize>> = DashMap::new();
+        for (index, block) in blocks.iter().enumerate() {
+            for span in read_block(&mut file, block)? {
+                let mut entry = trace_blocks.entry(span.trace_id).or_default();
+                if entry.last() != Some(&index) {
+                    entry.push(index);
+                }
+            }
+        }
+
+        tracing::info!("Opened span archive {} with {} blocks", path.display(), blocks.len());
+
+        Ok(Self {
+            path,
+            writer: Mutex::new(file),
+            blocks: RwLock::new(blocks),
+            trace_blocks,
+        })
+    }
+
+    /// Open the archive file at `path` with an index from
+    /// [`trace_index`](Self::trace_index) instead of reading every block.
+    /// Returns `None` if the index does not describe the file's blocks.
+    pub fn open_file_indexed(
+        path: impl Into<PathBuf>,
+        index: ArchiveTraceIndex,
+    ) -> Result<Option<Self>> {
+        let path = path.into();
+        let (file, blocks) = open_blocks(&path)?;
+        let in_range = index
+            .trace_blocks
+            .iter()
+            .all(|(_, indices)| indices.iter().all(|&i| i < blocks.len()));
+        if blocks != index.blocks || !in_range {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            path,
+            writer: Mutex::new(file),
+            blocks: RwLock::new(blocks),
+            trace_blocks: index.trace_blocks.into_iter().collect(),
+        }))
+    }
+
+    /// Trace index to save for [`open_file_indexed`](Self::open_file_indexed).
+    pub fn trace_index(&self) -> ArchiveTraceIndex {
+        let blocks = self.blocks.read();
+        ArchiveTraceIndex {
+            blocks: blocks.clone(),
+            trace_blocks: self
+                .trace_blocks
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        }
+    }
+
+    /// Path of the archive file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append spans as one compressed block.
+    pub fn append(&self, spans: &[Span]) -> Result<()> {
+        if spans.is_empty() {
+            return Ok(());
+        }
+
+        let mut writer = self.writer.lock();
+        let offset = writer.seek(SeekFrom::End(0))?;
+        let (block, payload) = encode_block(spans, offset)?;
+        let written = writer
+            .write_all(&block.encode_header())
+            .and_then(|()| writer.write_all(&payload))
+            .and_then(|()| writer.flush());
+        if let Err(e) = written {
+            // Drop the partial block so later appends stay readable
+            let _ = writer.set_len(offset);
+            return Err(e.into());
+        }
+
+        let mut blocks = self.blocks.write();
+        let index = blocks.len();
+        blocks.push(block);
+        for span in spans {
+            let mut entry = self.trace_blocks.entry(span.trace_id.clone()).or_default();
+            if entry.last() != Some(&index) {
+                entry.push(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite the archive without the spans of `trace_ids`, returning how
+    /// many spans were removed. Blocks holding none of them are copied as is.
+    pub fn purge_traces(&self, trace_ids: &HashSet<TraceId>) -> Result<usize> {
+        let mut writer = self.writer.lock();
+        let affected: HashSet<usize> = trace_ids
+            .iter()
+            .filter_map(|id| self.trace_blocks.get(id).map(|e| e.clone()))
+            .flatten()
+            .collect();
+        if affected.is_empty() {
+            return Ok(0);
+        }
+
+        let mut blocks = self.blocks.write();
+        let purge_path = self.path.with_extension("purge");
+        let mut out = BufWriter::new(File::create(&purge_path)?);
+        let mut kept_blocks: Vec<ArchiveBlock> = Vec::with_capacity(blocks.len());
+        // Old block index to new block index, for blocks that survive
+        let mut new_index = vec![None; blocks.len()];
+        let mut removed = 0;
+        for (index, block) in blocks.iter().enumerate() {
+            let offset = kept_blocks.last().map_or(0, ArchiveBlock::end);
+            let (kept_block, payload) = if affected.contains(&index) {
+                let spans: Vec<Span> = read_block(&mut writer, block)?
+                    .into_iter()
+                    .filter(|span| !trace_ids.contains(&span.trace_id))
+                    .collect();
+                removed += block.span_count as usize - spans.len();
+                if spans.is_empty() {
+                    continue;
+                }
+                encode_block(&spans, offset)?
+            } else {
+                writer.seek(SeekFrom::Start(block.offset + BLOCK_HEADER_LEN))?;
+                let mut payload = vec![0u8; block.payload_len as usize];
+                writer.read_exact(&mut payload)?;
+                (ArchiveBlock { offset, ..*block }, payload)
+            };
+            out.write_all(&kept_block.encode_header())?;
+            out.write_all(&payload)?;
+            new_index[index] = Some(kept_blocks.len());
+            kept_blocks.push(kept_block);
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        std::fs::rename(&purge_path, &self.path)?;
+        *writer = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        *blocks = kept_blocks;
+        self.trace_blocks.retain(|trace_id, indices| {
+            if trace_ids.contains(trace_id) {
+                return false;
+            }
+            *indices = indices.iter().filter_map(|&i| new_index[i]).collect();
+            !indices.is_empty()
+        });
+        Ok(removed)
+    }
+
+    /// Whether any spans of the trace are archived.
+    pub fn contains_trace(&self, trace_id: &TraceId) -> bool {
+        self.trace_blocks.contains_key(trace_id)
+    }
+
+    /// Read all archived spans of a trace.
+    pub fn get_trace_spans(&self, trace_id: &TraceId) -> Result<Vec<Span>> {
+        let Some(indices) = self.trace_blocks.get(trace_id).map(|e| e.clone()) else {
+            return Ok(Vec::new());
+        };
+
+        let blocks: Vec<ArchiveBlock> = {
+            let all = self.blocks.read();
+            indices.iter().map(|&i| all[i]).collect()
+        };
+        let mut file = File::open(&self.path)?;
+        let mut spans = Vec::new();
+        for block in &blocks {
+            spans.extend(
+                read_block(&mut file, block)?
+                    .into_iter()
+                    .filter(|s| &s.trace_id == trace_id),
+            );
+        }
+        Ok(spans)
+    }
+
+    /// Number of blocks in the archive.
+    pub fn block_count(&self) -> usize {
+        self.blocks.read().len()
+    }
+
+    /// Total number of archived spans.
+    pub fn span_count(&self) -> usize {
+        self.blocks
+            .read()
+            .iter()
+            .map(|b| b.span_count as usize)
+            .sum()
+    }
+
+    /// Start time of the newest archived span.
+    pub fn newest_start(&self) -> Option<SystemTime> {
+        self.blocks
+            .read()
+            .iter()
+            .map(|b| b.max_start_nanos)
+            .max()
+            .map(|nanos| UNIX_EPOCH + Duration::from_nanos(nanos))
+    }
+
+    /// IDs of all traces with archived spans.
+    pub fn trace_ids(&self) -> Vec<TraceId> {
+        self.trace_blocks.iter().map(|e| e.key().clone()).collect()
+    }
+
+    /// Open a reader over the blocks written so far.
+    pub fn reader(&self) -> Result<ArchiveReader> {
+        ArchiveReader::open(&self.path)
+    }
+}
+
+/// Read-only view of an archive for time-range queries.
+///
+/// Blocks are sorted by their earliest start time, so the blocks overlapping
+/// a range are found by binary search instead of decompressing the archive.
+pub struct ArchiveReader {
+    file: File,
+    blocks: Vec<ArchiveBlock>,
+    /// Running maximum of `max_start_nanos` over `blocks`, for binary search.
+    max_start_prefix: Vec<u64>,
+}
+
+impl ArchiveReader {
+    /// Open the archive file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut blocks = scan_blocks(&mut file)?;
+        blocks.sort_by_key(|b| b.min_start_nanos);
+        let max_start_prefix = blocks
+            .iter()
+            .scan(0, |max, b| {
+                *max = (*max).max(b.max_start_nanos);
+                Some(*max)
+            })
+            .collect();
+        Ok(Self {
+            file,
+            blocks,
+            max_start_prefix,
+        })
+    }
+
+    /// Blocks sorted by earliest start time.
+    pub fn blocks(&self) -> &[ArchiveBlock] {
+        &self.blocks
+    }
+
+    /// Blocks that may contain spans started within `from..=to`.
+    pub fn blocks_between(&self, from: SystemTime, to: SystemTime) -> &[ArchiveBlock] {
+        let (from, to) = (to_nanos(from), to_nanos(to));
+        let first = self.max_start_prefix.partition_point(|&max| max < from);
+        let last = self.blocks.partition_point(|b| b.min_start_nanos <= to);
+        if first >= last {
+            return &[];
+        }
+        &self.blocks[first..last]
+    }
+
+    /// Every span, sorted by start time.
+    pub fn all_spans(&mut self) -> Result<Vec<Span>> {
+        let mut spans = Vec::new();
+        for block in &self.blocks {
+            spans.extend(read_block(&mut self.file, block)?);
+        }
+        spans.sort_by_key(|s| s.start_time);
+        Ok(spans)
+    }
+
+    /// Spans started within `from..=to`, sorted by start time.
+    pub fn spans_between(&mut self, from: SystemTime, to: SystemTime) -> Result<Vec<Span>> {
+        let blocks = self.blocks_between(from, to).to_vec();
+        let mut spans = Vec::new();
+        for block in &blocks {
+            if block.max_start_nanos < to_nanos(from) {
+                continue;
+            }
+            spans.extend(
+                read_block(&mut self.file, block)?
+                    .into_iter()
+                    .filter(|s| s.start_time >= from && s.start_time <= to),
+            );
+        }
+        spans.sort_by_key(|s| s.start_time);
+        Ok(spans)
+    }
+}
+
+/// Cutoff before which spans are archived at `now`.
+pub(crate) fn archive_cutoff(now: SystemTime, archive_after: Duration) -> SystemTime {
+    now.checked_sub(archive_after).unwrap_or(UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId};
+
+    fn span(trace: u64, id: u64, start_secs: u64) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("archive-test".to_string()).unwrap())
+            .operation_name("op")
+            .start_time(UNIX_EPOCH + Duration::from_secs(start_secs))
+            .duration(Duration::from_millis(5))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_append_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = SpanArchive::open(dir.path()).unwrap();
+        archive.append(&[span(1, 1, 10), span(2, 2, 11)]).unwrap();
+        archive.append(&[span(1, 3, 20)]).unwrap();
+
+        let trace = TraceId::new(format!("{:032x}", 1)).unwrap();
+        assert_eq!(archive.get_trace_spans(&trace).unwrap().len(), 2);
+        drop(archive);
+
+        // Simulate a crash mid-append
+        let path = dir.path().join(ARCHIVE_FILE_NAME);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"UARC\0\0")
+            .unwrap();
+
+        let reopened = SpanArchive::open(dir.path()).unwrap();
+        assert_eq!(reopened.block_count(), 2);
+        assert_eq!(reopened.span_count(), 3);
+        assert_eq!(reopened.get_trace_spans(&trace).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_reopen_with_saved_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ARCHIVE_FILE_NAME);
+        let archive = SpanArchive::open_file(&path).unwrap();
+        archive.append(&[span(1, 1, 10), span(2, 2, 11)]).unwrap();
+        let index = archive.trace_index();
+        archive.append(&[span(1, 3, 20)]).unwrap();
+        let current = archive.trace_index();
+        drop(archive);
+
+        // An index missing the last block is stale
+        assert!(SpanArchive::open_file_indexed(&path, index)
+            .unwrap()
+            .is_none());
+
+        let reopened = SpanArchive::open_file_indexed(&path, current)
+            .unwrap()
+            .unwrap();
+        let trace = TraceId::new(format!("{:032x}", 1)).unwrap();
+        assert_eq!(reopened.get_trace_spans(&trace).unwrap().len(), 2);
+        assert_eq!(reopened.trace_ids().len(), 2);
+        assert_eq!(reopened.reader().unwrap().all_spans().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_reader_time_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = SpanArchive::open(dir.path()).unwrap();
+        for block in 0..10u64 {
+            let spans: Vec<_> = (0..5)
+                .map(|i| span(block, block * 10 + i, block * 100 + i))
+                .collect();
+            archive.append(&spans).unwrap();
+        }
+
+        let mut reader = archive.reader().unwrap();
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+        assert_eq!(reader.blocks_between(at(302), at(501)).len(), 3);
+        let spans = reader.spans_between(at(302), at(501)).unwrap();
+        assert_eq!(spans.len(), 3 + 5 + 2);
+        assert_eq!(spans.first().unwrap().start_time, at(302));
+        assert_eq!(spans.last().unwrap().start_time, at(501));
+
+        assert!(reader.blocks_between(at(5_000), at(6_000)).is_empty());
+        assert!(reader.spans_between(at(50), at(60)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_traces_rewrites_affected_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = SpanArchive::open(dir.path()).unwrap();
+        archive.append(&[span(1, 1, 10), span(2, 2, 11)]).unwrap();
+        archive.append(&[span(1, 3, 20)]).unwrap();
+        archive.append(&[span(3, 4, 30)]).unwrap();
+        let trace = |id: u64| TraceId::new(format!("{:032x}", id)).unwrap();
+
+        let purged = HashSet::from([trace(1)]);
+        assert_eq!(archive.purge_traces(&purged).unwrap(), 2);
+        assert_eq!(archive.purge_traces(&purged).unwrap(), 0);
+        assert!(!archive.contains_trace(&trace(1)));
+        assert_eq!(archive.block_count(), 2);
+        assert_eq!(archive.get_trace_spans(&trace(2)).unwrap().len(), 1);
+        assert_eq!(archive.get_trace_spans(&trace(3)).unwrap().len(), 1);
+
+        // Appends keep working, and the purged spans are gone from the file
+        archive.append(&[span(4, 5, 40)]).unwrap();
+        drop(archive);
+        let reopened = SpanArchive::open(dir.path()).unwrap();
+        assert_eq!(reopened.span_count(), 3);
+        assert!(!reopened.contains_trace(&trace(1)));
+        assert_eq!(reopened.get_trace_spans(&trace(4)).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Inverted index from `(attribute key, value)` to span IDs.
+//!
+//! Maintained incrementally as spans are stored and removed, so exact
+//! attribute searches touch only the matching spans instead of scanning.
+
+use crate::core::{Span, SpanId};
+use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Interned attribute key and value.
+type AttributePair = (Arc<str>, Arc<str>);
+
+/// Attribute `(key, value)` to span IDs.
+#[derive(Default)]
+pub struct AttributeIndex {
+    entries: DashMap<AttributePair, HashSet<SpanId>>,
+}
+
+impl AttributeIndex {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index every attribute of `span`.
+    pub fn insert(&self, span: &Span) {
+        for (key, value) in &span.attributes.0 {
+            self.entries
+                .entry((Arc::clone(key), Arc::clone(value)))
+                .or_default()
+                .insert(span.span_id.clone());
+        }
+    }
+
+    /// Remove `span` from the index, dropping entries left empty.
+    pub fn remove(&self, span: &Span) {
+        for (key, value) in &span.attributes.0 {
+            let pair = (Arc::clone(key), Arc::clone(value));
+            let emptied = self.entries.get_mut(&pair).is_some_and(|mut ids| {
+                ids.remove(&span.span_id);
+                ids.is_empty()
+            });
+            if emptied {
+                self.entries.remove_if(&pair, |_, ids| ids.is_empty());
+            }
+        }
+    }
+
+    /// IDs of spans whose attribute `key` equals `value`.
+    pub fn lookup(&self, key: &str, value: &str) -> Vec<SpanId> {
+        self.entries
+            .get(&(Arc::from(key), Arc::from(value)))
+            .map(|ids| ids.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Number of distinct `(key, value)` pairs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every entry.
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, TraceId};
+    use std::time::SystemTime;
+
+    fn span(id: u64, route: &str) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET")
+            .start_time(SystemTime::now())
+            .attribute("http.route", route)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_insert_lookup_remove() {
+        let index = AttributeIndex::new();
+        let (a, b) = (span(1, "/users"), span(2, "/users"));
+        index.insert(&a);
+        index.insert(&b);
+        index.insert(&span(3, "/orders"));
+
+        assert_eq!(index.lookup("http.route", "/users").len(), 2);
+        assert!(index.lookup("http.route", "/USERS").is_empty());
+        assert_eq!(index.len(), 2);
+
+        index.remove(&a);
+        assert_eq!(index.lookup("http.route", "/users"), vec![b.span_id.clone()]);
+        index.remove(&b);
+        assert!(index.lookup("http.route", "/users").is_empty());
+        assert_eq!(index.len(), 1);
+    }
+}
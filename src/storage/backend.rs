@@ -1,8 +1,8 @@
 //! Storage backend trait and implementations.
 
-use super::{StorageHealth, StorageStats, TraceInfo};
-use crate::core::{Result, ServiceMetrics, ServiceName, Span, SpanId, TraceId};
-use std::collections::HashMap;
+use super::{EvictedTrace, LongTermStats, StorageHealth, StorageStats, TraceInfo};
+use crate::core::{Result, ServiceMetrics, ServiceName, Span, SpanId, TraceId, UrpoError};
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, SystemTime};
 
 /// Core storage backend trait for trace data persistence.
@@ -15,11 +15,53 @@ pub trait StorageBackend: Send + Sync {
     /// Store a span.
     async fn store_span(&self, span: Span) -> Result<()>;
 
+    /// Store a batch of spans, returning how many were stored.
+    ///
+    /// Backends can index the batch at once; the default stores the spans
+    /// one by one and stops at the first error. When storage is full the
+    /// error is `UrpoError::StorageFull` counting the spans not stored, and
+    /// any spans stored are the first ones of the batch.
+    async fn store_spans_bulk(&self, spans: Vec<Span>) -> Result<usize> {
+        let count = spans.len();
+        for (stored, span) in spans.into_iter().enumerate() {
+            if let Err(e) = self.store_span(span).await {
+                if e.is_storage_full() {
+                    return Err(UrpoError::StorageFull {
+                        rejected: count - stored,
+                    });
+                }
+                return Err(e);
+            }
+        }
+        Ok(count)
+    }
+
     /// Get a span by ID.
     async fn get_span(&self, span_id: &SpanId) -> Result<Option<Span>>;
 
-    /// Get all spans for a trace.
-    async fn get_trace_spans(&self, trace_id: &TraceId) -> Result<Vec<Span>>;
+    /// Get all spans for a trace, in start-time order.
+    ///
+    /// Backends configured for it correct clock skew between services.
+    async fn get_trace_spans(&self, trace_id: &TraceId) -> Result<Vec<Span>> {
+        let mut spans = Vec::new();
+        self.visit_trace_spans(trace_id, &mut |span| {
+            spans.push(span.clone());
+            true
+        })
+        .await?;
+        Ok(spans)
+    }
+
+    /// Call `visit` with each span of a trace in start-time order, without
+    /// collecting them or correcting clock skew. Stops early when `visit`
+    /// returns `false`.
+    ///
+    /// `visit` must not call back into the storage.
+    async fn visit_trace_spans(
+        &self,
+        trace_id: &TraceId,
+        visit: &mut (dyn for<'s> FnMut(&'s Span) -> bool + Send),
+    ) -> Result<()>;
 
     /// Get spans for a service within a time window.
     async fn get_service_spans(
@@ -46,6 +88,44 @@ pub trait StorageBackend: Send + Sync {
     /// Perform emergency cleanup.
     async fn emergency_cleanup(&self) -> Result<usize>;
 
+    /// Move aging spans to colder tiers, such as compressed memory or the
+    /// disk archive, returning how many were moved.
+    ///
+    /// Backends without tiers keep everything where it is.
+    async fn migrate_cold_spans(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Delete every span of `trace_ids` from all tiers and indices,
+    /// returning the number of spans removed.
+    ///
+    /// Backends that cannot delete return an error rather than silently
+    /// keep data the caller asked to remove.
+    async fn delete_traces(&self, _trace_ids: &[TraceId]) -> Result<usize> {
+        Err(UrpoError::storage("This storage backend does not support deleting traces"))
+    }
+
+    /// Count spans dropped because this backend reported it was full.
+    ///
+    /// Called by ingestion for every span of a batch it had to reject, so
+    /// the total shows up in [`StorageStats::rejected_spans`].
+    fn record_rejected_spans(&self, _count: usize) {}
+
+    /// When a trace no longer in storage was evicted, if still remembered.
+    fn trace_evicted_at(&self, _trace_id: &TraceId) -> Option<SystemTime> {
+        None
+    }
+
+    /// Up to `limit` recently evicted traces, most recent first.
+    fn recently_evicted_traces(&self, _limit: usize) -> Vec<EvictedTrace> {
+        Vec::new()
+    }
+
+    /// Hourly per-service statistics kept across restarts, if enabled.
+    fn longterm_stats(&self) -> Option<&LongTermStats> {
+        None
+    }
+
     /// Check storage health.
     fn get_health(&self) -> StorageHealth;
 
@@ -77,10 +157,36 @@ pub trait StorageBackend: Send + Sync {
         limit: usize,
     ) -> Result<Vec<TraceInfo>>;
 
+    /// Traces with a span whose attribute `key` equals `value`, newest
+    /// first, such as every trace of one `user.id` or `request.id`.
+    async fn get_traces_by_attribute(
+        &self,
+        key: &str,
+        value: &str,
+        limit: usize,
+    ) -> Result<Vec<TraceInfo>> {
+        let spans = self
+            .search_spans(value, None, Some(key), usize::MAX)
+            .await?;
+        let trace_ids: HashSet<TraceId> = spans.into_iter().map(|span| span.trace_id).collect();
+        let mut traces = Vec::with_capacity(trace_ids.len());
+        for trace_id in trace_ids {
+            let spans = self.get_trace_spans(&trace_id).await?;
+            traces.extend(crate::create_trace_info!(trace_id, spans));
+        }
+        traces.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        traces.truncate(limit);
+        Ok(traces)
+    }
+
     /// Get service metrics as a map.
     async fn get_service_metrics_map(&self) -> Result<HashMap<ServiceName, ServiceMetrics>>;
 
     /// Search spans by query with filters.
+    ///
+    /// With `attribute_key`, matches spans whose attribute equals `query`
+    /// exactly; otherwise `query` is a case-insensitive substring of the
+    /// operation name or of an attribute key or value.
     async fn search_spans(
         &self,
         query: &str,
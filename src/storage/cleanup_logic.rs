@@ -1,9 +1,10 @@
 //! Memory cleanup and management utilities for storage backends.
 
 use super::StorageHealth;
-use crate::core::{ServiceName, Span, SpanId, TraceId};
+use crate::core::config::StorageConfig;
+use crate::core::{Glob, ServiceName, Span, SpanEvent, SpanId, SpanLink, TraceId};
 use dashmap::DashMap;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 
@@ -20,10 +21,14 @@ pub struct CleanupConfig {
     pub emergency_threshold: f64,
     /// Span retention period.
     pub retention_period: Duration,
+    /// Retention of services matching a glob, instead of `retention_period`.
+    pub retention_overrides: HashMap<Glob, Duration>,
     /// Cleanup interval.
     pub cleanup_interval: Duration,
     /// Minimum spans to keep per service.
     pub min_spans_per_service: usize,
+    /// Rolling window the reported span processing rate covers.
+    pub rate_window: Duration,
 }
 
 impl Default for CleanupConfig {
@@ -34,12 +39,45 @@ impl Default for CleanupConfig {
             critical_threshold: 0.85,
             emergency_threshold: 0.95,
             retention_period: Duration::from_secs(3600), // 1 hour
+            retention_overrides: HashMap::new(),
             cleanup_interval: Duration::from_secs(30),
             min_spans_per_service: 100,
+            rate_window: Duration::from_secs(60),
         }
     }
 }
 
+impl CleanupConfig {
+    /// Cleanup settings derived from the storage configuration.
+    pub fn from_storage_config(storage: &StorageConfig) -> Self {
+        Self {
+            max_memory_bytes: storage.max_memory_mb * 1024 * 1024,
+            retention_period: storage.retention_duration,
+            retention_overrides: storage.retention_overrides.clone(),
+            cleanup_interval: storage.cleanup_interval,
+            ..Self::default()
+        }
+    }
+
+    /// Retention of `service`'s spans. When several overrides match, the
+    /// longest (most specific) pattern wins, then the longest retention.
+    pub fn retention_for(&self, service: &str) -> Duration {
+        self.retention_overrides
+            .iter()
+            .filter(|(pattern, _)| pattern.matches(service))
+            .max_by_key(|(pattern, retention)| (pattern.as_str().len(), **retention))
+            .map_or(self.retention_period, |(_, retention)| *retention)
+    }
+
+    /// Shortest retention of any service.
+    pub fn shortest_retention(&self) -> Duration {
+        self.retention_overrides
+            .values()
+            .copied()
+            .fold(self.retention_period, Duration::min)
+    }
+}
+
 /// Performance and monitoring counters.
 #[derive(Debug)]
 pub struct StorageCounters {
@@ -53,6 +91,14 @@ pub struct StorageCounters {
     pub memory_bytes: AtomicUsize,
     /// Spans evicted.
     pub spans_evicted: AtomicU64,
+    /// Spans rejected because storage was at capacity.
+    pub spans_rejected: AtomicU64,
+    /// Spans held compressed in the warm tier.
+    pub warm_spans: AtomicUsize,
+    /// Compressed size of the warm tier in bytes.
+    pub warm_bytes: AtomicUsize,
+    /// Estimated bytes the warm spans would take uncompressed.
+    pub warm_uncompressed_bytes: AtomicUsize,
     /// Start time for rate calculations.
     pub start_time: Instant,
 }
@@ -65,6 +111,10 @@ impl Default for StorageCounters {
             cleanup_operations: AtomicU64::new(0),
             memory_bytes: AtomicUsize::new(0),
             spans_evicted: AtomicU64::new(0),
+            spans_rejected: AtomicU64::new(0),
+            warm_spans: AtomicUsize::new(0),
+            warm_bytes: AtomicUsize::new(0),
+            warm_uncompressed_bytes: AtomicUsize::new(0),
             start_time: Instant::now(),
         }
     }
@@ -105,6 +155,25 @@ pub fn estimate_span_memory(span: &Span) -> usize {
         size += k.len() + v.len();
     }
 
+    // Events (exception stack traces can dominate a span's size)
+    for event in &span.events {
+        size += std::mem::size_of::<SpanEvent>() + event.name.len();
+        size += event.attributes.len() * std::mem::size_of::<(String, String)>();
+        for (k, v) in event.attributes.iter() {
+            size += k.len() + v.len();
+        }
+    }
+
+    // Links
+    for link in &span.links {
+        size += std::mem::size_of::<SpanLink>();
+        size += link.trace_id.as_str().len() + link.span_id.as_str().len();
+        size += link.attributes.len() * std::mem::size_of::<(String, String)>();
+        for (k, v) in link.attributes.iter() {
+            size += k.len() + v.len();
+        }
+    }
+
     size
 }
 
@@ -268,6 +337,7 @@ macro_rules! remove_span_indices {
             if trace_spans.is_empty() {
                 drop(trace_spans);
                 $self.traces.remove(&$span.trace_id);
+                $self.span_order.remove_trace(&$span.trace_id);
             }
         }
 
@@ -279,6 +349,10 @@ macro_rules! remove_span_indices {
                 $self.services.remove(&$span.service_name);
             }
         }
+
+        // Remove from attribute index
+        $self.attribute_index.remove($span);
+        $self.time_index.remove($span);
     }};
 }
 
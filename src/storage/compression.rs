@@ -6,7 +6,7 @@
 //! - Streaming compression for large datasets
 //! - Dictionary-based compression for repeated strings
 
-use crate::core::{Result, Span, UrpoError};
+use crate::core::{Result, Span, SpanLink, UrpoError};
 use bytes::Bytes;
 use lz4_flex::{compress_prepend_size, decompress_size_prepended};
 use parking_lot::RwLock;
@@ -74,6 +74,10 @@ pub struct ColumnarSpanBatch {
     pub attribute_values: Vec<u16>,
     /// Attribute spans (which span each attribute belongs to)
     pub attribute_spans: Vec<u32>,
+    /// Span links
+    pub links: Vec<SpanLink>,
+    /// Link spans (which span each link belongs to)
+    pub link_spans: Vec<u32>,
 }
 
 impl ColumnarSpanBatch {
@@ -91,6 +95,8 @@ impl ColumnarSpanBatch {
             attribute_keys: Vec::new(),
             attribute_values: Vec::new(),
             attribute_spans: Vec::new(),
+            links: Vec::new(),
+            link_spans: Vec::new(),
         };
 
         // Find base timestamp for delta encoding
@@ -136,6 +142,12 @@ impl ColumnarSpanBatch {
                 batch.attribute_values.push(string_pool.intern(value));
                 batch.attribute_spans.push(span_idx as u32);
             }
+
+            // Links
+            for link in &span.links {
+                batch.links.push(link.clone());
+                batch.link_spans.push(span_idx as u32);
+            }
         }
 
         batch
@@ -382,6 +394,8 @@ impl CompressionEngine {
 
     /// Decompress spans
     pub fn decompress_spans(&self, batch: &CompressedSpanBatch) -> Result<Vec<Span>> {
+        self.stats.write().decompression_operations += 1;
+
         match batch.compression_level {
             CompressionLevel::None => bincode::deserialize(&batch.data)
                 .map_err(|e| UrpoError::Storage(format!("Deserialization failed: {}", e))),
@@ -484,12 +498,17 @@ impl CompressionEngine {
                 builder = builder.attribute(key, value);
             }
 
-            spans.push(builder.build_default());
-        }
+            for (link, _) in columnar
+                .links
+                .iter()
+                .zip(&columnar.link_spans)
+                .filter(|(_, &span_idx)| span_idx == i as u32)
+            {
+                builder = builder.link(link.clone());
+            }
 
-        // Update decompression stats
-        let mut stats = self.stats.write();
-        stats.decompression_operations += 1;
+            spans.push(builder.build()?);
+        }
 
         Ok(spans)
     }
@@ -654,4 +673,41 @@ mod tests {
         // Should have decent compression estimate
         assert!(columnar.estimate_compression_ratio() < 1.0);
     }
+
+    #[test]
+    fn test_columnar_round_trip_keeps_links() {
+        use crate::core::SpanLink;
+
+        let engine = CompressionEngine::new();
+        let span = |id: &str, links: &[(&str, &str)]| {
+            let mut builder = SpanBuilder::default()
+                .trace_id(TraceId::new("trace-1".to_string()).unwrap())
+                .span_id(SpanId::new(id.to_string()).unwrap())
+                .service_name(ServiceName::new("consumer".to_string()).unwrap())
+                .operation_name("consume");
+            for &(trace, span) in links {
+                builder = builder.link(SpanLink::new(
+                    TraceId::new(trace.to_string()).unwrap(),
+                    SpanId::new(span.to_string()).unwrap(),
+                ));
+            }
+            builder.build().unwrap()
+        };
+        let spans = vec![
+            span("span-1", &[("trace-2", "span-a"), ("trace-3", "span-b")]),
+            span("span-2", &[]),
+        ];
+
+        for level in [CompressionLevel::Balanced, CompressionLevel::Maximum] {
+            let compressed = engine.compress_spans(&spans, level).unwrap();
+            let decompressed = engine.decompress_spans(&compressed).unwrap();
+            let linked: Vec<_> = decompressed[0]
+                .links
+                .iter()
+                .map(|link| (link.trace_id.as_str(), link.span_id.as_str()))
+                .collect();
+            assert_eq!(linked, vec![("trace-2", "span-a"), ("trace-3", "span-b")]);
+            assert!(decompressed[1].links.is_empty());
+        }
+    }
 }
@@ -0,0 +1,232 @@
+//! Span eviction order by trace priority, then age.
+//!
+//! Every stored span is queued in the band of its trace's
+//! [`SamplingPriority`]. Eviction drains the least important band first
+//! (`Minimal`, then `Low`, ...), so error and slow traces (`Critical`) go
+//! last. Within a band spans leave oldest first.
+//!
+//! A trace can become more important after some of its spans were queued
+//! (an error span arrives late). Those spans are moved to the right band
+//! when eviction reaches them, so bands only hand out spans whose trace is
+//! still at that priority.
+
+use crate::core::{Span, SpanId, TraceId};
+use crate::sampling::SamplingPriority;
+use crossbeam::queue::SegQueue;
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Queued span: its start time and ID.
+pub type QueuedSpan = (SystemTime, SpanId);
+
+/// Bands in eviction order, least important first.
+const EVICTION_BANDS: [SamplingPriority; 5] = [
+    SamplingPriority::Minimal,
+    SamplingPriority::Low,
+    SamplingPriority::Medium,
+    SamplingPriority::High,
+    SamplingPriority::Critical,
+];
+
+/// Default duration at or above which a span makes its trace `Critical`.
+pub const DEFAULT_SLOW_SPAN_THRESHOLD: Duration = Duration::from_secs(1);
+
+fn band_index(priority: SamplingPriority) -> usize {
+    priority as usize
+}
+
+/// Per-priority queues of stored spans.
+pub struct EvictionOrder {
+    bands: [SegQueue<QueuedSpan>; 5],
+    trace_priority: DashMap<TraceId, SamplingPriority>,
+    slow_span_threshold: Duration,
+}
+
+impl Default for EvictionOrder {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOW_SPAN_THRESHOLD)
+    }
+}
+
+impl EvictionOrder {
+    /// Create empty queues; spans lasting `slow_span_threshold` or longer
+    /// are `Critical`.
+    pub fn new(slow_span_threshold: Duration) -> Self {
+        Self {
+            bands: Default::default(),
+            trace_priority: DashMap::new(),
+            slow_span_threshold,
+        }
+    }
+
+    /// Priority a single span gives its trace.
+    pub fn span_priority(&self, span: &Span) -> SamplingPriority {
+        if span.status.is_error() || span.duration >= self.slow_span_threshold {
+            SamplingPriority::Critical
+        } else {
+            SamplingPriority::Low
+        }
+    }
+
+    /// Queue `span`, raising its trace's priority if the span warrants it.
+    pub fn push(&self, span: &Span) {
+        let span_priority = self.span_priority(span);
+        let priority = *self
+            .trace_priority
+            .entry(span.trace_id.clone())
+            .and_modify(|p| *p = (*p).min(span_priority))
+            .or_insert(span_priority);
+        self.bands[band_index(priority)].push((span.start_time, span.span_id.clone()));
+    }
+
+    /// Queue a batch of spans, updating each trace's priority once.
+    pub fn push_all(&self, spans: &[Span]) {
+        let mut batch_priority: HashMap<&TraceId, SamplingPriority> = HashMap::new();
+        for span in spans {
+            let span_priority = self.span_priority(span);
+            batch_priority
+                .entry(&span.trace_id)
+                .and_modify(|p| *p = (*p).min(span_priority))
+                .or_insert(span_priority);
+        }
+        for (trace_id, priority) in &mut batch_priority {
+            *priority = *self
+                .trace_priority
+                .entry((*trace_id).clone())
+                .and_modify(|p| *p = (*p).min(*priority))
+                .or_insert(*priority);
+        }
+        for span in spans {
+            let priority = batch_priority[&span.trace_id];
+            self.bands[band_index(priority)].push((span.start_time, span.span_id.clone()));
+        }
+    }
+
+    /// Current priority of `trace_id`, if it has queued spans.
+    pub fn trace_priority(&self, trace_id: &TraceId) -> Option<SamplingPriority> {
+        self.trace_priority.get(trace_id).map(|p| *p)
+    }
+
+    /// Forget the priority of a trace that is no longer stored.
+    pub fn remove_trace(&self, trace_id: &TraceId) {
+        self.trace_priority.remove(trace_id);
+    }
+
+    /// Pop the next span to evict: the oldest of the least important band.
+    ///
+    /// `trace_of` maps a queued span to its trace, or `None` if the span is
+    /// already gone, in which case its entry is dropped.
+    pub fn pop_next(
+        &self,
+        mut trace_of: impl FnMut(&SpanId) -> Option<TraceId>,
+    ) -> Option<QueuedSpan> {
+        for band in EVICTION_BANDS {
+            let queue = &self.bands[band_index(band)];
+            // Bounded so entries moved into this band are not revisited
+            for _ in 0..queue.len() {
+                let Some(item) = queue.pop() else {
+                    break;
+                };
+                let Some(trace_id) = trace_of(&item.1) else {
+                    continue;
+                };
+                match self.trace_priority(&trace_id) {
+                    Some(current) if current < band => {
+                        self.bands[band_index(current)].push(item);
+                    },
+                    _ => return Some(item),
+                }
+            }
+        }
+        None
+    }
+
+    /// The band queues, for consumers that walk spans by age regardless of
+    /// priority.
+    pub fn bands(&self) -> impl Iterator<Item = &SegQueue<QueuedSpan>> {
+        self.bands.iter()
+    }
+
+    /// Number of queued entries, including ones for spans already removed.
+    pub fn len(&self) -> usize {
+        self.bands.iter().map(SegQueue::len).sum()
+    }
+
+    /// Whether no entries are queued.
+    pub fn is_empty(&self) -> bool {
+        self.bands.iter().all(SegQueue::is_empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanStatus};
+
+    fn span(trace: u64, id: u64, millis: u64, error: bool) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET")
+            .start_time(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + id))
+            .duration(Duration::from_millis(millis))
+            .status(if error {
+                SpanStatus::Error("boom".to_string())
+            } else {
+                SpanStatus::Ok
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_low_priority_spans_leave_first() {
+        let order = EvictionOrder::default();
+        let spans = [
+            span(1, 1, 10, false),
+            // Trace 2 turns critical after its first span was queued
+            span(2, 2, 10, false),
+            span(3, 3, 5000, false),
+            span(2, 4, 10, true),
+            span(5, 5, 10, false),
+        ];
+        let traces: HashMap<SpanId, TraceId> = spans
+            .iter()
+            .map(|s| (s.span_id.clone(), s.trace_id.clone()))
+            .collect();
+        for s in &spans {
+            order.push(s);
+        }
+
+        let popped: Vec<u64> = std::iter::from_fn(|| order.pop_next(|id| traces.get(id).cloned()))
+            .map(|(_, id)| u64::from_str_radix(id.as_str(), 16).unwrap())
+            .collect();
+        // Low before critical; span 2 joins the critical band when eviction reaches it
+        assert_eq!(popped, vec![1, 5, 3, 4, 2]);
+        assert!(order.is_empty());
+    }
+
+    #[test]
+    fn test_push_all_queues_batch_at_trace_priority() {
+        let order = EvictionOrder::default();
+        order.push(&span(1, 1, 10, false));
+        let batch = [span(2, 2, 10, false), span(1, 3, 10, true), span(2, 4, 10, false)];
+        let traces: HashMap<SpanId, TraceId> = std::iter::once(span(1, 1, 10, false))
+            .chain(batch.iter().cloned())
+            .map(|s| (s.span_id, s.trace_id))
+            .collect();
+        order.push_all(&batch);
+
+        let trace = |n: u64| TraceId::new(format!("{:032x}", n)).unwrap();
+        assert_eq!(order.trace_priority(&trace(1)), Some(SamplingPriority::Critical));
+        assert_eq!(order.trace_priority(&trace(2)), Some(SamplingPriority::Low));
+
+        let popped: Vec<u64> = std::iter::from_fn(|| order.pop_next(|id| traces.get(id).cloned()))
+            .map(|(_, id)| u64::from_str_radix(id.as_str(), 16).unwrap())
+            .collect();
+        assert_eq!(popped, vec![2, 4, 3, 1]);
+        assert_eq!(order.len(), 0);
+    }
+}
@@ -0,0 +1,131 @@
+//! Breadcrumb log of recently evicted traces.
+//!
+//! Answers "did trace X exist?" after its spans were dropped: a bounded ring
+//! of trace IDs with the time their last span left storage.
+
+use crate::core::TraceId;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::SystemTime;
+
+/// Default number of evicted trace IDs remembered.
+pub const DEFAULT_EVICTION_LOG_CAPACITY: usize = 10_000;
+
+/// A trace that was evicted from storage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EvictedTrace {
+    /// Evicted trace
+    pub trace_id: TraceId,
+    /// When its last span was removed
+    pub evicted_at: SystemTime,
+}
+
+/// Bounded ring of recently evicted traces, oldest dropped first.
+#[derive(Debug)]
+pub struct EvictionLog {
+    capacity: usize,
+    inner: Mutex<EvictionRing>,
+}
+
+#[derive(Debug, Default)]
+struct EvictionRing {
+    order: VecDeque<EvictedTrace>,
+    index: HashMap<TraceId, SystemTime>,
+}
+
+impl EvictionLog {
+    /// Create a log remembering up to `capacity` traces.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(EvictionRing::default()),
+        }
+    }
+
+    /// Record that `trace_id` was evicted now.
+    pub fn record(&self, trace_id: &TraceId) {
+        self.record_at(trace_id, SystemTime::now());
+    }
+
+    /// Record that `trace_id` was evicted at `evicted_at`.
+    pub fn record_at(&self, trace_id: &TraceId, evicted_at: SystemTime) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut ring = self.inner.lock();
+        if ring.index.insert(trace_id.clone(), evicted_at).is_some() {
+            ring.order.retain(|entry| &entry.trace_id != trace_id);
+        }
+        ring.order.push_back(EvictedTrace {
+            trace_id: trace_id.clone(),
+            evicted_at,
+        });
+        while ring.order.len() > self.capacity {
+            if let Some(oldest) = ring.order.pop_front() {
+                ring.index.remove(&oldest.trace_id);
+            }
+        }
+    }
+
+    /// When `trace_id` was evicted, if it is still remembered.
+    pub fn evicted_at(&self, trace_id: &TraceId) -> Option<SystemTime> {
+        self.inner.lock().index.get(trace_id).copied()
+    }
+
+    /// Up to `limit` evictions, most recent first.
+    pub fn recent(&self, limit: usize) -> Vec<EvictedTrace> {
+        self.inner
+            .lock()
+            .order
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of remembered evictions.
+    pub fn len(&self) -> usize {
+        self.inner.lock().order.len()
+    }
+
+    /// True if nothing has been evicted.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for EvictionLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVICTION_LOG_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn trace(n: u32) -> TraceId {
+        TraceId::new(format!("{:032x}", n)).unwrap()
+    }
+
+    #[test]
+    fn test_eviction_log_is_bounded_and_deduplicated() {
+        let log = EvictionLog::new(3);
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        for n in 0..4 {
+            log.record_at(&trace(n), base + Duration::from_secs(n as u64));
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.evicted_at(&trace(0)), None);
+        assert_eq!(log.evicted_at(&trace(3)), Some(base + Duration::from_secs(3)));
+
+        // Re-evicting moves the trace to the front with the new time
+        log.record_at(&trace(1), base + Duration::from_secs(10));
+        let recent: Vec<_> = log.recent(10).into_iter().map(|e| e.trace_id).collect();
+        assert_eq!(recent, vec![trace(1), trace(3), trace(2)]);
+        assert_eq!(log.evicted_at(&trace(1)), Some(base + Duration::from_secs(10)));
+    }
+}
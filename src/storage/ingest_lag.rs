@@ -0,0 +1,114 @@
+//! Per-service ingest lag: how long after a span ended it reached storage.
+//!
+//! Lag is sampled when a span is stored rather than kept on the span, so a
+//! fixed number of recent samples per service bounds the memory used.
+//! Spans whose end time is in the future (clock skew) count as zero lag.
+
+use crate::core::{ServiceName, Span};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+/// Default number of recent lag samples kept per service.
+pub const DEFAULT_LAG_SAMPLES: usize = 1024;
+
+/// Ingest lag percentiles for one service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestLag {
+    /// Median lag
+    pub p50: Duration,
+    /// 95th percentile lag
+    pub p95: Duration,
+    /// 99th percentile lag
+    pub p99: Duration,
+}
+
+/// Recent ingest lag samples per service.
+pub struct IngestLagTracker {
+    samples: DashMap<ServiceName, VecDeque<Duration>>,
+    max_samples: usize,
+}
+
+impl Default for IngestLagTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_LAG_SAMPLES)
+    }
+}
+
+impl IngestLagTracker {
+    /// Create a tracker keeping up to `max_samples` samples per service.
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            samples: DashMap::new(),
+            max_samples: max_samples.max(1),
+        }
+    }
+
+    /// Record the lag of `span` ingested at `ingested_at`.
+    pub fn record(&self, span: &Span, ingested_at: SystemTime) {
+        let lag = ingested_at
+            .duration_since(span.end_time())
+            .unwrap_or_default();
+        let mut samples = self.samples.entry(span.service_name.clone()).or_default();
+        if samples.len() >= self.max_samples {
+            samples.pop_front();
+        }
+        samples.push_back(lag);
+    }
+
+    /// Lag percentiles of `service`, if any of its spans were recorded.
+    pub fn percentiles(&self, service: &ServiceName) -> Option<IngestLag> {
+        let mut lags: Vec<Duration> = self.samples.get(service)?.iter().copied().collect();
+        if lags.is_empty() {
+            return None;
+        }
+        lags.sort_unstable();
+        let percentile = |p: usize| lags[(lags.len() * p / 100).min(lags.len() - 1)];
+        Some(IngestLag {
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{SpanId, TraceId};
+
+    fn span(id: u64, end: SystemTime) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET")
+            .start_time(end - Duration::from_millis(10))
+            .duration(Duration::from_millis(10))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lag_percentiles_use_recent_samples() {
+        let tracker = IngestLagTracker::new(10);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let api = ServiceName::new("api".to_string()).unwrap();
+        assert!(tracker.percentiles(&api).is_none());
+
+        // Older samples are dropped once the window is full
+        for i in 0..10 {
+            tracker.record(&span(i, now - Duration::from_secs(3600)), now);
+        }
+        for i in 1..=10 {
+            tracker.record(&span(i, now - Duration::from_secs(i)), now);
+        }
+        let lag = tracker.percentiles(&api).unwrap();
+        assert_eq!(lag.p50, Duration::from_secs(6));
+        assert_eq!(lag.p99, Duration::from_secs(10));
+
+        // An end time ahead of the ingest clock is zero lag
+        tracker.record(&span(11, now + Duration::from_secs(5)), now);
+        assert_eq!(tracker.percentiles(&api).unwrap().p50, Duration::from_secs(6));
+    }
+}
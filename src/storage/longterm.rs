@@ -0,0 +1,449 @@
+//! Downsampled per-service statistics that survive restarts.
+//!
+//! Spans are folded into one bucket per service and hour (request count,
+//! error count, latency percentiles). Once an hour is over, its buckets are
+//! appended to a single file using the WAL record framing:
+//!
+//! ```text
+//! payload len (u32) | FNV-1a checksum of payload (u32) | bincode HourlyStats
+//! ```
+//!
+//! The file is loaded at startup. A torn or corrupt tail is truncated, and
+//! hours older than the retention period are compacted away. An hour written
+//! more than once (late spans, or a restart mid-hour) is merged on load.
+
+use super::wal::checksum;
+use crate::core::{Result, Span, UrpoError};
+use parking_lot::{Mutex, RwLock};
+use quantiles::ckms::CKMS;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RECORD_HEADER_LEN: u64 = 8;
+const HOUR_SECS: u64 = 3600;
+
+/// Start of the hour (Unix seconds) and service name.
+type HourKey = (u64, String);
+
+/// Default retention of hourly statistics.
+pub const DEFAULT_LONGTERM_RETENTION: Duration = Duration::from_secs(31 * 24 * HOUR_SECS);
+
+/// Statistics of one service over one hour.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourlyStats {
+    /// Service name
+    pub service: String,
+    /// Start of the hour, Unix seconds
+    pub hour: u64,
+    /// Spans started during the hour
+    pub request_count: u64,
+    /// Of which had error status
+    pub error_count: u64,
+    /// Median latency in microseconds
+    pub latency_p50_us: u64,
+    /// 95th percentile latency in microseconds
+    pub latency_p95_us: u64,
+    /// 99th percentile latency in microseconds
+    pub latency_p99_us: u64,
+}
+
+impl HourlyStats {
+    /// Fold a second record of the same service and hour into this one.
+    ///
+    /// Percentiles cannot be merged exactly, so they are weighted by the
+    /// request count of each record.
+    pub fn merge(&mut self, other: &HourlyStats) {
+        let total = self.request_count + other.request_count;
+        if total > 0 {
+            let weighted = |a: u64, b: u64| {
+                ((u128::from(a) * u128::from(self.request_count)
+                    + u128::from(b) * u128::from(other.request_count))
+                    / u128::from(total)) as u64
+            };
+            self.latency_p50_us = weighted(self.latency_p50_us, other.latency_p50_us);
+            self.latency_p95_us = weighted(self.latency_p95_us, other.latency_p95_us);
+            self.latency_p99_us = weighted(self.latency_p99_us, other.latency_p99_us);
+        }
+        self.request_count = total;
+        self.error_count += other.error_count;
+    }
+}
+
+/// Counters and latency estimator of an hour still being aggregated.
+struct OpenHour {
+    requests: u64,
+    errors: u64,
+    latencies: CKMS<f64>,
+}
+
+impl OpenHour {
+    fn new() -> Self {
+        Self {
+            requests: 0,
+            errors: 0,
+            latencies: CKMS::<f64>::new(0.001),
+        }
+    }
+
+    fn finish(&self, service: String, hour: u64) -> HourlyStats {
+        let percentile = |q: f64| {
+            self.latencies
+                .query(q)
+                .map_or(0, |(_, micros)| micros.max(0.0) as u64)
+        };
+        HourlyStats {
+            service,
+            hour,
+            request_count: self.requests,
+            error_count: self.errors,
+            latency_p50_us: percentile(0.5),
+            latency_p95_us: percentile(0.95),
+            latency_p99_us: percentile(0.99),
+        }
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn hour_of(time: SystemTime) -> u64 {
+    unix_secs(time) / HOUR_SECS * HOUR_SECS
+}
+
+fn encode_record(stats: &HourlyStats) -> Result<Vec<u8>> {
+    let payload = bincode::serialize(stats)
+        .map_err(|e| UrpoError::storage(format!("Stats serialization failed: {}", e)))?;
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&checksum(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    Ok(record)
+}
+
+/// Decode the complete records of the stats file.
+///
+/// Returns the records and the length of the valid prefix of the file.
+fn read_records(path: &Path) -> Result<(Vec<HourlyStats>, u64)> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut records = Vec::new();
+    let mut offset = 0;
+    let mut header = [0u8; RECORD_HEADER_LEN as usize];
+    while offset + RECORD_HEADER_LEN <= file_len {
+        reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let expected = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if offset + RECORD_HEADER_LEN + u64::from(len) > file_len {
+            break;
+        }
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload)?;
+        if checksum(&payload) != expected {
+            break;
+        }
+        let Ok(stats) = bincode::deserialize::<HourlyStats>(&payload) else {
+            break;
+        };
+        records.push(stats);
+        offset += RECORD_HEADER_LEN + u64::from(len);
+    }
+    Ok((records, offset))
+}
+
+/// Hourly per-service statistics persisted to a single append-only file.
+pub struct LongTermStats {
+    path: PathBuf,
+    retention: Duration,
+    file: Mutex<File>,
+    /// Written hours keyed by (hour, service)
+    history: RwLock<BTreeMap<HourKey, HourlyStats>>,
+    /// Hours still being aggregated, keyed by (hour, service)
+    open: Mutex<BTreeMap<HourKey, OpenHour>>,
+    /// Start of the newest hour that may still be open, Unix seconds
+    current_hour: AtomicU64,
+}
+
+impl LongTermStats {
+    /// Open (or create) the stats file, truncating a corrupt tail and
+    /// compacting away hours older than `retention`.
+    pub fn open(path: impl Into<PathBuf>, retention: Duration) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let (records, valid_len) = read_records(&path)?;
+        let file_len = file.metadata()?.len();
+        if valid_len < file_len {
+            tracing::warn!(
+                "Truncating {} bytes of corrupt long-term stats data in {}",
+                file_len - valid_len,
+                path.display()
+            );
+            file.set_len(valid_len)?;
+        }
+
+        let cutoff = hour_of(SystemTime::now()).saturating_sub(retention.as_secs());
+        let record_count = records.len();
+        let mut history: BTreeMap<HourKey, HourlyStats> = BTreeMap::new();
+        for stats in records.into_iter().filter(|stats| stats.hour >= cutoff) {
+            match history.get_mut(&(stats.hour, stats.service.clone())) {
+                Some(existing) => existing.merge(&stats),
+                None => {
+                    history.insert((stats.hour, stats.service.clone()), stats);
+                },
+            }
+        }
+
+        let stats = Self {
+            path,
+            retention,
+            file: Mutex::new(file),
+            history: RwLock::new(history),
+            open: Mutex::new(BTreeMap::new()),
+            current_hour: AtomicU64::new(hour_of(SystemTime::now())),
+        };
+        if stats.history.read().len() < record_count {
+            stats.compact()?;
+        }
+        tracing::info!(
+            "Loaded {} hours of long-term stats from {}",
+            stats.history.read().len(),
+            stats.path.display()
+        );
+        Ok(stats)
+    }
+
+    /// Path of the stats file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fold `span` into the bucket of its service and start hour.
+    pub fn record(&self, span: &Span) {
+        let mut open = self.open.lock();
+        let hour = open
+            .entry((hour_of(span.start_time), span.service_name.as_str().to_string()))
+            .or_insert_with(OpenHour::new);
+        hour.requests += 1;
+        if span.status.is_error() {
+            hour.errors += 1;
+        }
+        hour.latencies.insert(span.duration.as_micros() as f64);
+    }
+
+    /// Write the finished hours if the wall-clock hour has moved on since the
+    /// last call. Cheap enough to call for every stored span.
+    pub fn flush_if_due(&self, now: SystemTime) -> Result<usize> {
+        let hour = hour_of(now);
+        if self.current_hour.swap(hour, Ordering::AcqRel) >= hour {
+            return Ok(0);
+        }
+        self.flush_before(hour)
+    }
+
+    /// Write every bucket, including the current hour (e.g. at shutdown).
+    pub fn flush_all(&self) -> Result<usize> {
+        self.flush_before(u64::MAX)
+    }
+
+    /// Append the buckets of hours starting before `hour` and forget them.
+    fn flush_before(&self, hour: u64) -> Result<usize> {
+        let finished: Vec<HourlyStats> = {
+            let mut open = self.open.lock();
+            let still_open = open.split_off(&(hour, String::new()));
+            std::mem::replace(&mut *open, still_open)
+                .into_iter()
+                .map(|((hour, service), bucket)| bucket.finish(service, hour))
+                .collect()
+        };
+        if finished.is_empty() {
+            return Ok(0);
+        }
+
+        let mut bytes = Vec::new();
+        for stats in &finished {
+            bytes.extend(encode_record(stats)?);
+        }
+        {
+            let mut file = self.file.lock();
+            let len = file.metadata()?.len();
+            let written = file.write_all(&bytes);
+            if let Err(e) = written.and_then(|()| file.sync_data()) {
+                // Drop the partial write so later records stay readable
+                let _ = file.set_len(len);
+                return Err(e.into());
+            }
+        }
+
+        let mut history = self.history.write();
+        for stats in &finished {
+            match history.get_mut(&(stats.hour, stats.service.clone())) {
+                Some(existing) => existing.merge(stats),
+                None => {
+                    history.insert((stats.hour, stats.service.clone()), stats.clone());
+                },
+            }
+        }
+        let cutoff = hour_of(SystemTime::now()).saturating_sub(self.retention.as_secs());
+        history.retain(|(hour, _), _| *hour >= cutoff);
+        Ok(finished.len())
+    }
+
+    /// Rewrite the file with only the retained, merged hours.
+    fn compact(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        {
+            let mut out = File::create(&tmp)?;
+            for stats in self.history.read().values() {
+                out.write_all(&encode_record(stats)?)?;
+            }
+            out.sync_all()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        *self.file.lock() = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+
+    /// Written hours since `since`, oldest first, optionally for one service.
+    pub fn query(&self, service: Option<&str>, since: SystemTime) -> Vec<HourlyStats> {
+        self.history
+            .read()
+            .range((hour_of(since), String::new())..)
+            .map(|(_, stats)| stats)
+            .filter(|stats| service.map_or(true, |service| stats.service == service))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for LongTermStats {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush_all() {
+            tracing::warn!("Failed to write long-term stats: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, SpanStatus, TraceId};
+
+    fn span(id: u64, service: &str, start: SystemTime, millis: u64, error: bool) -> Span {
+        let status = if error {
+            SpanStatus::Error("boom".to_string())
+        } else {
+            SpanStatus::Ok
+        };
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name("op")
+            .start_time(start)
+            .duration(Duration::from_millis(millis))
+            .status(status)
+            .build()
+            .unwrap()
+    }
+
+    fn span_stats(requests: u64, errors: u64, p50: u64) -> HourlyStats {
+        HourlyStats {
+            service: "checkout".to_string(),
+            hour: 0,
+            request_count: requests,
+            error_count: errors,
+            latency_p50_us: p50,
+            latency_p95_us: p50,
+            latency_p99_us: p50,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("longterm.stats");
+        let hour = hour_of(SystemTime::now()) - 2 * HOUR_SECS;
+        let start = UNIX_EPOCH + Duration::from_secs(hour);
+
+        let stats = LongTermStats::open(&path, DEFAULT_LONGTERM_RETENTION).unwrap();
+        for i in 0..100 {
+            let error = i % 10 == 0;
+            stats.record(&span(i, "checkout", start + Duration::from_secs(i), i + 1, error));
+        }
+        stats.record(&span(200, "cart", start + Duration::from_secs(HOUR_SECS), 7, false));
+        assert_eq!(stats.flush_before(hour_of(SystemTime::now())).unwrap(), 2);
+        let written = stats.query(None, UNIX_EPOCH);
+        drop(stats);
+
+        let reopened = LongTermStats::open(&path, DEFAULT_LONGTERM_RETENTION).unwrap();
+        assert_eq!(reopened.query(None, UNIX_EPOCH), written);
+        let checkout = reopened.query(Some("checkout"), UNIX_EPOCH);
+        assert_eq!(checkout.len(), 1);
+        assert_eq!(checkout[0].hour, hour);
+        assert_eq!(checkout[0].request_count, 100);
+        assert_eq!(checkout[0].error_count, 10);
+        assert!((49_000..=52_000).contains(&checkout[0].latency_p50_us));
+        assert!((98_000..=100_000).contains(&checkout[0].latency_p99_us));
+        let since_second_hour = UNIX_EPOCH + Duration::from_secs(hour + HOUR_SECS);
+        assert_eq!(reopened.query(None, since_second_hour).len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_tail_is_truncated_and_old_hours_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("longterm.stats");
+        let now = hour_of(SystemTime::now());
+        let recent = UNIX_EPOCH + Duration::from_secs(now - HOUR_SECS);
+        let ancient = UNIX_EPOCH + Duration::from_secs(now - 90 * 24 * HOUR_SECS);
+
+        let stats = LongTermStats::open(&path, DEFAULT_LONGTERM_RETENTION).unwrap();
+        stats.record(&span(1, "checkout", recent, 5, false));
+        stats.record(&span(2, "checkout", ancient, 5, false));
+        stats.flush_before(now).unwrap();
+        drop(stats);
+
+        // A torn record after the valid ones
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&100u32.to_le_bytes()).unwrap();
+        file.write_all(&[0xde, 0xad, 0xbe, 0xef, 1, 2, 3]).unwrap();
+        drop(file);
+
+        let stats = LongTermStats::open(&path, DEFAULT_LONGTERM_RETENTION).unwrap();
+        let kept = stats.query(None, UNIX_EPOCH);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].hour, now - HOUR_SECS);
+
+        // New records land after the truncated tail and read back
+        stats.record(&span(3, "cart", recent, 5, false));
+        stats.flush_before(now).unwrap();
+        drop(stats);
+        let (records, valid_len) = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(valid_len, std::fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_repeated_hours_merge() {
+        let mut a = span_stats(10, 1, 100);
+        a.merge(&span_stats(30, 3, 200));
+        assert_eq!(a.request_count, 40);
+        assert_eq!(a.error_count, 4);
+        assert_eq!(a.latency_p50_us, 175);
+    }
+}
@@ -3,19 +3,46 @@
 //! Production-ready in-memory storage implementation with advanced memory management,
 //! bounded capacity, and efficient cleanup mechanisms.
 
+use super::archive::{archive_cutoff, SpanArchive};
+use super::attribute_index::AttributeIndex;
 use super::cleanup_logic::{estimate_span_memory, CleanupConfig, StorageCounters};
-use super::{StorageBackend, StorageHealth, StorageStats, TraceInfo};
-use crate::core::{Config, Result, ServiceMetrics, ServiceName, Span, SpanId, TraceId};
+use super::eviction_order::EvictionOrder;
+use super::evictions::{EvictedTrace, EvictionLog};
+use super::ingest_lag::IngestLagTracker;
+use super::longterm::LongTermStats;
+use super::snapshot::SpanSnapshot;
+use super::time_index::TimeBucketIndex;
+use super::wal::WriteAheadLog;
+use super::{
+    ServiceFootprint, SlidingWindowCounter, StorageBackend, StorageHealth, StorageStats,
+    TraceFootprint, TraceInfo,
+};
+use crate::core::{
+    system_clock, ClockSkewAdjuster, Config, Result, ServiceMetrics, ServiceName, SharedClock,
+    Span, SpanId, TraceId, WalConfig,
+};
 use crate::storage::simd_search::find_trace_id_simd; // SIMD acceleration
 use crate::storage::{CompressedSpanBatch, CompressionEngine, CompressionLevel}; // Compression for 5-10x memory savings
 use crate::{create_trace_info, impl_search, remove_span_indices, update_counter};
-use crossbeam::queue::SegQueue;
 use dashmap::DashMap;
-use std::collections::{HashMap, VecDeque};
-use std::sync::{atomic::Ordering, Arc};
+use lru::LruCache;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::{atomic::Ordering, Arc, Weak};
 use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Mutex;
 
+/// Default number of decompressed warm traces kept in the read cache.
+pub const DEFAULT_WARM_CACHE_TRACES: usize = 64;
+
+/// Maximum number of spans written to one archive block.
+const ARCHIVE_BLOCK_SPANS: usize = 1_000;
+
+/// Traces deleted between yields by [`InMemoryStorage::purge_traces`].
+const PURGE_BATCH_TRACES: usize = 100;
+
 /// Production-ready in-memory storage with advanced memory management.
 #[derive(Clone)]
 pub struct InMemoryStorage {
@@ -25,8 +52,8 @@ pub struct InMemoryStorage {
     traces: Arc<DashMap<TraceId, Vec<SpanId>>>,
     /// Service to span IDs mapping with timestamps for efficient querying.
     services: Arc<DashMap<ServiceName, VecDeque<(SystemTime, SpanId)>>>,
-    /// Lock-free queue of span IDs by insertion time for LRU eviction.
-    span_order: Arc<SegQueue<(SystemTime, SpanId)>>,
+    /// Lock-free queues of span IDs by trace priority, then insertion time, for eviction.
+    span_order: Arc<EvictionOrder>,
     /// Maximum number of spans to store.
     max_spans: usize,
     /// Maximum spans per service.
@@ -35,16 +62,46 @@ pub struct InMemoryStorage {
     cleanup_config: CleanupConfig,
     /// Performance counters.
     counters: Arc<StorageCounters>,
+    /// Recently processed spans, for the rolling processing rate.
+    recent_spans: Arc<SlidingWindowCounter>,
     /// Last cleanup operation time.
     last_cleanup: Arc<Mutex<Instant>>,
     /// Active service names for efficient listing (using DashMap for lock-free access).
     active_services: Arc<DashMap<ServiceName, SystemTime>>,
     /// Compression engine for 5-10x memory savings.
     compression_engine: Arc<CompressionEngine>,
-    /// Compressed span batches for cold storage.
+    /// Warm tier: compressed spans of each trace, decompressed on read.
     compressed_batches: Arc<DashMap<TraceId, CompressedSpanBatch>>,
-    /// Compression threshold - spans older than this get compressed.
+    /// Age and size of each warm batch, for retention and eviction.
+    warm_batches: Arc<DashMap<TraceId, WarmBatchInfo>>,
+    /// Spans started longer ago than this move to the warm tier.
     compression_threshold: Duration,
+    /// LRU cache of recently decompressed warm traces.
+    warm_cache: Option<Arc<parking_lot::Mutex<LruCache<TraceId, Vec<Span>>>>>,
+    /// Disk archive cold spans are moved to.
+    archive: Option<Arc<SpanArchive>>,
+    /// Spans started longer ago than this are archived.
+    archive_after: Duration,
+    /// Recently evicted traces, so lookups can tell "evicted" from "never seen".
+    evictions: Arc<EvictionLog>,
+    /// Write-ahead log stored spans are appended to before indexing.
+    wal: Option<Arc<WriteAheadLog>>,
+    /// Compacted copy of the stored spans, replayed before the WAL.
+    snapshot: Option<Arc<SpanSnapshot>>,
+    /// Attribute `(key, value)` to span IDs for exact attribute searches.
+    attribute_index: Arc<AttributeIndex>,
+    /// Start-time buckets to trace IDs for time-range and recent listings.
+    time_index: Arc<TimeBucketIndex>,
+    /// Recent per-service delay between a span ending and being stored.
+    ingest_lag: Arc<IngestLagTracker>,
+    /// Hourly per-service statistics kept across restarts.
+    longterm_stats: Option<Arc<LongTermStats>>,
+    /// Clock skew correction applied to traces as they are read.
+    clock_skew: Option<ClockSkewAdjuster>,
+    /// Time source for retention, compression and cleanup cutoffs.
+    clock: SharedClock,
+    /// Dropped with the last handle to the storage, stopping the tier migration task.
+    _tier_migration_stop: Option<Arc<tokio::sync::oneshot::Sender<()>>>,
 }
 
 impl InMemoryStorage {
@@ -54,47 +111,745 @@ impl InMemoryStorage {
             spans: Arc::new(DashMap::new()),
             traces: Arc::new(DashMap::new()),
             services: Arc::new(DashMap::new()),
-            span_order: Arc::new(SegQueue::new()),
+            span_order: Arc::new(EvictionOrder::default()),
             max_spans,
             max_spans_per_service: max_spans / 10, // Allow each service ~10% of total capacity
             cleanup_config: CleanupConfig::default(),
             counters: Arc::new(StorageCounters::default()),
+            recent_spans: Arc::new(SlidingWindowCounter::new(CleanupConfig::default().rate_window)),
             last_cleanup: Arc::new(Mutex::new(Instant::now())),
             active_services: Arc::new(DashMap::new()),
             compression_engine: Arc::new(CompressionEngine::new()),
             compressed_batches: Arc::new(DashMap::new()),
+            warm_batches: Arc::new(DashMap::new()),
             compression_threshold: Duration::from_secs(300), // Compress spans older than 5 minutes
+            warm_cache: None,
+            archive: None,
+            archive_after: Duration::from_secs(15 * 60),
+            evictions: Arc::new(EvictionLog::default()),
+            wal: None,
+            snapshot: None,
+            attribute_index: Arc::new(AttributeIndex::new()),
+            time_index: Arc::new(TimeBucketIndex::default()),
+            ingest_lag: Arc::new(IngestLagTracker::default()),
+            longterm_stats: None,
+            clock_skew: None,
+            clock: system_clock(),
+            _tier_migration_stop: None,
+        }
+        .with_warm_cache_capacity(DEFAULT_WARM_CACHE_TRACES)
+    }
+
+    /// Set how many decompressed warm traces are cached (0 disables the cache).
+    pub fn with_warm_cache_capacity(mut self, traces: usize) -> Self {
+        self.warm_cache = NonZeroUsize::new(traces)
+            .map(|capacity| Arc::new(parking_lot::Mutex::new(LruCache::new(capacity))));
+        self
+    }
+
+    /// Compress spans started more than `warm_after` ago into the warm tier.
+    pub fn with_warm_after(mut self, warm_after: Duration) -> Self {
+        self.compression_threshold = warm_after;
+        self
+    }
+
+    /// Move spans started more than `archive_after` ago to `archive`.
+    pub fn with_archive(mut self, archive: Arc<SpanArchive>, archive_after: Duration) -> Self {
+        self.archive = Some(archive);
+        self.archive_after = archive_after;
+        self
+    }
+
+    /// Append stored spans to `wal` before indexing them.
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    /// Snapshot the stored spans to `snapshot` every `interval` (never when
+    /// zero), deleting the WAL segments each snapshot covers. Set after the
+    /// WAL and cleanup config; snapshots wait while memory pressure is critical.
+    pub fn with_snapshots(mut self, snapshot: SpanSnapshot, interval: Duration) -> Self {
+        let snapshot = Arc::new(snapshot);
+        if !interval.is_zero() {
+            let source = SnapshotSource {
+                snapshot: Arc::clone(&snapshot),
+                spans: Arc::downgrade(&self.spans),
+                compressed_batches: Arc::downgrade(&self.compressed_batches),
+                compression_engine: Arc::clone(&self.compression_engine),
+                wal: self.wal.as_ref().map(Arc::downgrade),
+                counters: Arc::clone(&self.counters),
+                postpone_above_bytes: (self.cleanup_config.max_memory_bytes as f64
+                    * self.cleanup_config.critical_threshold) as usize,
+            };
+            if let Err(e) = spawn_snapshot_thread(source, interval) {
+                tracing::warn!("Periodic snapshots disabled: {}", e);
+            }
         }
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Keep a WAL and periodic snapshots in `dir` with the default WAL
+    /// settings, restoring the spans they already hold.
+    pub fn with_persistence(self, dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let config = WalConfig::default();
+        let wal = WriteAheadLog::open(dir, &config)?;
+        let storage = self
+            .with_wal(Arc::new(wal))
+            .with_snapshots(SpanSnapshot::new(dir), config.snapshot_interval);
+        let recovered = storage.recover()?;
+        tracing::info!("Recovered {} spans from {}", recovered, dir.display());
+        Ok(storage)
+    }
+
+    /// Read the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.last_cleanup = Arc::new(Mutex::new(clock.instant()));
+        self.clock = clock;
+        self.reset_rate_window();
+        self
+    }
+
+    /// Fold stored spans into `stats`, written out once each hour is over.
+    pub fn with_longterm_stats(mut self, stats: Arc<LongTermStats>) -> Self {
+        self.longterm_stats = Some(stats);
+        self
+    }
+
+    /// Correct clock skew of up to `max_skew` between services in the spans
+    /// returned by `get_trace_spans`.
+    pub fn with_clock_skew_correction(mut self, max_skew: Duration) -> Self {
+        self.clock_skew = Some(ClockSkewAdjuster::new(max_skew));
+        self
+    }
+
+    /// Treat spans lasting `threshold` or longer as slow, keeping their traces
+    /// through eviction like error traces. Set before storing spans.
+    pub fn with_slow_span_threshold(mut self, threshold: Duration) -> Self {
+        self.span_order = Arc::new(EvictionOrder::new(threshold));
+        self
+    }
+
+    /// Remember up to `capacity` evicted trace IDs (0 disables the log).
+    pub fn with_eviction_log_capacity(mut self, capacity: usize) -> Self {
+        self.evictions = Arc::new(EvictionLog::new(capacity));
+        self
     }
 
     /// Create storage with custom cleanup configuration.
     pub fn with_cleanup_config(max_spans: usize, cleanup_config: CleanupConfig) -> Self {
         let mut storage = Self::new(max_spans);
         storage.cleanup_config = cleanup_config;
+        storage.reset_rate_window();
         storage
     }
 
+    /// Restart the processing rate window from the clock and cleanup config.
+    fn reset_rate_window(&mut self) {
+        self.recent_spans = Arc::new(SlidingWindowCounter::starting_at(
+            self.clock.instant(),
+            self.cleanup_config.rate_window,
+        ));
+    }
+
     /// Create storage from application configuration.
     pub fn with_config(config: &Config) -> Self {
-        let cleanup_config = CleanupConfig {
-            max_memory_bytes: config.storage.max_memory_mb * 1024 * 1024,
-            warning_threshold: 0.7,
-            critical_threshold: 0.85,
-            emergency_threshold: 0.95,
-            retention_period: config.storage.retention_duration,
-            cleanup_interval: config.storage.cleanup_interval,
-            min_spans_per_service: 100,
-        };
+        let cleanup_config = CleanupConfig::from_storage_config(&config.storage);
 
-        let mut storage = Self::new(config.storage.max_spans);
+        let mut storage = Self::new(config.storage.max_spans)
+            .with_warm_cache_capacity(config.storage.warm_cache_traces)
+            .with_warm_after(config.storage.warm_after);
         storage.cleanup_config = cleanup_config;
+        storage.reset_rate_window();
         storage.max_spans_per_service = config.storage.max_spans / 10;
+        if let Some(max_skew) = config.storage.max_clock_skew {
+            storage = storage.with_clock_skew_correction(max_skew);
+        }
+
+        if config.storage.enable_archival {
+            let dir = config.storage.archive_dir();
+            match SpanArchive::open(&dir) {
+                Ok(archive) => {
+                    storage = storage.with_archive(Arc::new(archive), config.storage.archive_after);
+                },
+                Err(e) => {
+                    tracing::warn!("Span archive disabled, cannot open {}: {}", dir.display(), e);
+                },
+            }
+        }
+
+        if let (Some(wal_config), Some(dir)) = (&config.storage.wal, config.storage.wal_dir()) {
+            match WriteAheadLog::open(&dir, wal_config) {
+                Ok(wal) => {
+                    storage = storage
+                        .with_wal(Arc::new(wal))
+                        .with_snapshots(SpanSnapshot::new(&dir), wal_config.snapshot_interval);
+                    match storage.recover() {
+                        Ok(recovered) => {
+                            tracing::info!("Recovered {} spans from {}", recovered, dir.display());
+                        },
+                        Err(e) => tracing::warn!("WAL replay failed: {}", e),
+                    }
+                },
+                Err(e) => {
+                    tracing::warn!("WAL disabled, cannot open {}: {}", dir.display(), e);
+                },
+            }
+        }
+
+        if let (Some(stats_config), Some(path)) = (
+            &config.storage.longterm_stats,
+            config.storage.longterm_stats_path(),
+        ) {
+            match LongTermStats::open(&path, stats_config.retention) {
+                Ok(stats) => storage = storage.with_longterm_stats(Arc::new(stats)),
+                Err(e) => {
+                    tracing::warn!(
+                        "Long-term stats disabled, cannot open {}: {}",
+                        path.display(),
+                        e
+                    );
+                },
+            }
+        }
+
+        if config.storage.compression_enabled {
+            storage.spawn_tier_migration(config.storage.tier_migration_interval);
+        }
         storage
     }
 
-    /// Compress old spans to save 5-10x memory.
-    async fn compress_old_spans(&self) -> Result<()> {
-        let now = SystemTime::now();
+    /// Drop spans past their retention, hot and warm, and migrate spans
+    /// between tiers every `interval` (never when zero) until this storage
+    /// and its later clones are dropped. Needs a Tokio runtime;
+    /// without one nothing is spawned and `None` is returned.
+    pub fn spawn_tier_migration(
+        &mut self,
+        interval: Duration,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        if interval.is_zero() {
+            return None;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::debug!("Tier migration timer not started: no Tokio runtime");
+            return None;
+        };
+        // The task's own copy must not keep the stop sender alive
+        let (stop, mut stopped) = tokio::sync::oneshot::channel();
+        let mut storage = self.clone();
+        storage._tier_migration_stop = None;
+        self._tier_migration_stop = Some(Arc::new(stop));
+        Some(runtime.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {},
+                    _ = &mut stopped => break,
+                }
+                storage.cleanup_expired_spans(storage.clock.now()).await;
+                if let Err(e) = storage.migrate_cold_spans().await {
+                    tracing::warn!("Tier migration failed: {}", e);
+                }
+            }
+        }))
+    }
+
+    /// Rebuild the indices from the snapshot and then the write-ahead log.
+    ///
+    /// Only the newest `max_spans` spans are restored; spans already held in
+    /// memory or in the archive are skipped.
+    pub fn recover(&self) -> Result<usize> {
+        let mut spans = match self.snapshot {
+            Some(ref snapshot) => snapshot.load()?,
+            None => Vec::new(),
+        };
+        if let Some(ref wal) = self.wal {
+            spans.extend(wal.replay()?);
+        }
+        // The snapshot and the WAL segment before it can overlap
+        let mut seen = HashSet::new();
+        spans.retain(|span| seen.insert(span.span_id.clone()));
+        let skip = spans.len().saturating_sub(self.max_spans);
+        let spans: Vec<Span> = spans
+            .into_iter()
+            .skip(skip)
+            .filter(|span| {
+                !self.spans.contains_key(&span.span_id)
+                    && !self
+                        .archive
+                        .as_ref()
+                        .is_some_and(|archive| archive.contains_trace(&span.trace_id))
+            })
+            .collect();
+        let recovered = spans.len();
+        self.index_spans(spans);
+        Ok(recovered)
+    }
+
+    /// Snapshot the stored spans now, returning how many were written
+    /// (0 without [`with_snapshots`](Self::with_snapshots)).
+    pub fn write_snapshot(&self) -> Result<usize> {
+        let Some(ref snapshot) = self.snapshot else {
+            return Ok(0);
+        };
+        snapshot_spans(
+            snapshot,
+            &self.spans,
+            &self.compressed_batches,
+            &self.compression_engine,
+            self.wal.as_deref(),
+        )
+    }
+
+    /// Free memory and capacity for `incoming` new spans, or fail with
+    /// backpressure when the storage cannot take them.
+    async fn make_room(&self, incoming: usize) -> Result<()> {
+        // Check memory pressure and perform cleanup if needed
+        let memory_pressure = self.get_memory_pressure();
+        if memory_pressure >= self.cleanup_config.warning_threshold || self.should_cleanup().await {
+            if memory_pressure >= self.cleanup_config.emergency_threshold {
+                // Emergency: apply aggressive backpressure
+                self.counters
+                    .processing_errors
+                    .fetch_add(1, Ordering::Relaxed);
+
+                // Try one last emergency cleanup before rejecting
+                if let Ok(removed) = self.emergency_cleanup_internal().await {
+                    if removed == 0 {
+                        // No space could be freed, reject with backpressure error
+                        return Err(crate::core::UrpoError::MemoryLimitExceeded {
+                            current: (self.counters.memory_bytes.load(Ordering::Relaxed)
+                                / 1024
+                                / 1024) as usize,
+                            limit: (self.cleanup_config.max_memory_bytes / 1024 / 1024) as usize,
+                        });
+                    }
+                }
+
+                // After cleanup, allow span if there's now space
+                let new_pressure = self.get_memory_pressure();
+                if new_pressure >= self.cleanup_config.emergency_threshold {
+                    return Err(crate::core::UrpoError::MemoryLimitExceeded {
+                        current: (self.counters.memory_bytes.load(Ordering::Relaxed) / 1024 / 1024)
+                            as usize,
+                        limit: (self.cleanup_config.max_memory_bytes / 1024 / 1024) as usize,
+                    });
+                }
+            } else if memory_pressure >= self.cleanup_config.critical_threshold {
+                // Critical: aggressive cleanup
+                let _ = self.emergency_cleanup_internal().await;
+                *self.last_cleanup.lock().await = self.clock.instant();
+            } else {
+                // Warning or cleanup interval: regular cleanup with compression
+                let _ = self.compress_old_spans().await; // Try compression first for 5-10x memory savings
+                self.cleanup_expired_spans(self.clock.now()).await;
+                // Eviction takes whole warm batches, so only under pressure
+                if memory_pressure >= self.cleanup_config.warning_threshold {
+                    let to_evict = (self.max_spans / 20).max(10); // Evict 5% when at warning
+                    self.evict_oldest_spans(to_evict).await;
+                }
+                *self.last_cleanup.lock().await = self.clock.instant();
+            }
+        }
+
+        // Apply hard limit with backpressure
+        if self.memory_span_count() + incoming > self.max_spans {
+            // Try to evict spans first
+            let to_evict = (self.max_spans / 5).max(10).max(incoming); // Evict 20% when at capacity
+            let evicted = self.evict_oldest_spans(to_evict).await;
+
+            if evicted == 0 || self.memory_span_count() + incoming > self.max_spans {
+                // Unable to free space, apply backpressure
+                self.counters
+                    .processing_errors
+                    .fetch_add(1, Ordering::Relaxed);
+                return Err(crate::core::UrpoError::StorageFull { rejected: incoming });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Insert a span into the span, trace, service, attribute, time and eviction indices.
+    fn index_span(&self, span: Span, span_memory: usize) {
+        let span_id = span.span_id.clone();
+        let trace_id = span.trace_id.clone();
+        let service_name = span.service_name.clone();
+        let start_time = span.start_time;
+
+        // Store the span
+        self.attribute_index.insert(&span);
+        self.time_index.insert(&span);
+        self.span_order.push(&span);
+        self.spans.insert(span_id.clone(), span);
+
+        // Update memory tracking
+        self.counters
+            .memory_bytes
+            .fetch_add(span_memory, Ordering::Relaxed);
+
+        self.append_trace_spans(trace_id, [span_id.clone()]);
+        self.append_service_spans(service_name, [(start_time, span_id)]);
+    }
+
+    /// Insert a batch of spans into the indices, taking each trace and
+    /// service entry once for the whole batch.
+    fn index_spans(&self, spans: Vec<Span>) {
+        let mut span_memory = 0;
+        let mut trace_spans: HashMap<TraceId, Vec<SpanId>> = HashMap::new();
+        let mut service_spans: HashMap<ServiceName, Vec<(SystemTime, SpanId)>> = HashMap::new();
+        self.span_order.push_all(&spans);
+        for span in &spans {
+            span_memory += self.estimate_span_memory(span);
+            self.attribute_index.insert(span);
+            self.time_index.insert(span);
+            trace_spans
+                .entry(span.trace_id.clone())
+                .or_default()
+                .push(span.span_id.clone());
+            service_spans
+                .entry(span.service_name.clone())
+                .or_default()
+                .push((span.start_time, span.span_id.clone()));
+        }
+
+        spans.into_par_iter().for_each(|span| {
+            self.spans.insert(span.span_id.clone(), span);
+        });
+        self.counters
+            .memory_bytes
+            .fetch_add(span_memory, Ordering::Relaxed);
+
+        for (trace_id, span_ids) in trace_spans {
+            self.append_trace_spans(trace_id, span_ids);
+        }
+        for (service_name, entries) in service_spans {
+            self.append_service_spans(service_name, entries);
+        }
+    }
+
+    /// Add spans to a trace's index entry, evicting its oldest spans beyond
+    /// the per-trace limit.
+    fn append_trace_spans(&self, trace_id: TraceId, span_ids: impl IntoIterator<Item = SpanId>) {
+        // Update trace index with bounds checking
+        {
+            let mut trace_spans = self.traces.entry(trace_id).or_insert_with(Vec::new);
+            trace_spans.extend(span_ids);
+
+            // Enforce maximum spans per trace (prevent trace explosion)
+            const MAX_SPANS_PER_TRACE: usize = 10_000;
+            if trace_spans.len() > MAX_SPANS_PER_TRACE {
+                // Remove oldest spans from this trace
+                let to_remove = trace_spans.len() - MAX_SPANS_PER_TRACE;
+                for _ in 0..to_remove {
+                    if !trace_spans.is_empty() {
+                        let old_span_id = trace_spans.remove(0);
+                        // Remove from spans storage
+                        if let Some((_, span)) = self.spans.remove(&old_span_id) {
+                            self.attribute_index.remove(&span);
+                            self.time_index.remove(&span);
+                        }
+                        update_counter!(self.counters.spans_evicted, add 1);
+                    }
+                }
+                tracing::warn!(
+                    "Trace exceeded maximum spans ({}), evicted {} spans",
+                    MAX_SPANS_PER_TRACE,
+                    to_remove
+                );
+            }
+        }
+    }
+
+    /// Add `(start time, span)` entries to a service's index entry, evicting
+    /// its oldest spans beyond the per-service limit.
+    fn append_service_spans(
+        &self,
+        service_name: ServiceName,
+        entries: impl IntoIterator<Item = (SystemTime, SpanId)>,
+    ) {
+        let mut latest_start = None;
+        // Update service index with bounds and timestamp tracking
+        {
+            let mut service_spans = self
+                .services
+                .entry(service_name.clone())
+                .or_insert_with(VecDeque::new);
+            for (start_time, span_id) in entries {
+                latest_start = latest_start.max(Some(start_time));
+                service_spans.push_back((start_time, span_id));
+            }
+
+            // Enforce per-service span limits to prevent single service OOM
+            if service_spans.len() > self.max_spans_per_service {
+                // Remove oldest spans for this service
+                let to_remove = service_spans.len() - self.max_spans_per_service;
+                for _ in 0..to_remove {
+                    if let Some((_, old_span_id)) = service_spans.pop_front() {
+                        // Remove from spans storage
+                        if let Some((_, span)) = self.spans.remove(&old_span_id) {
+                            let freed_memory = self.estimate_span_memory(&span);
+                            self.counters
+                                .memory_bytes
+                                .fetch_sub(freed_memory, Ordering::Relaxed);
+                            self.attribute_index.remove(&span);
+                            self.time_index.remove(&span);
+                        }
+                        update_counter!(self.counters.spans_evicted, add 1);
+                    }
+                }
+            }
+        }
+
+        // Update active services tracking (lock-free with DashMap)
+        if let Some(start_time) = latest_start {
+            self.active_services.insert(service_name, start_time);
+        }
+    }
+
+    /// The `limit` newest traces with spans starting in `[start, end]` (Unix
+    /// nanos), as built by `trace_info` (`None` skips a trace).
+    ///
+    /// Walks the time index newest bucket first and stops once `limit` traces
+    /// start at or after the current bucket, since no unvisited trace can
+    /// start later than that.
+    fn newest_traces<F>(
+        &self,
+        start: Option<u64>,
+        end: Option<u64>,
+        limit: usize,
+        mut trace_info: F,
+    ) -> Vec<TraceInfo>
+    where
+        F: FnMut(&TraceId) -> Option<TraceInfo>,
+    {
+        let mut seen = HashSet::new();
+        let mut found: Vec<TraceInfo> = Vec::new();
+        if limit == 0 {
+            return found;
+        }
+
+        for bucket_start in self.time_index.buckets_newest_first(start, end) {
+            for trace_id in self.time_index.traces_in_bucket(bucket_start) {
+                if seen.insert(trace_id.clone()) {
+                    found.extend(trace_info(&trace_id));
+                }
+            }
+            if found.iter().filter(|t| t.start_time >= bucket_start).count() >= limit {
+                break;
+            }
+        }
+
+        found.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        found.truncate(limit);
+        found
+    }
+
+    /// Summary of a stored trace, if it involves `service_filter`.
+    fn recent_trace_info(
+        &self,
+        trace_id: &TraceId,
+        service_filter: Option<&ServiceName>,
+    ) -> Option<TraceInfo> {
+        let span_ids = self.traces.get(trace_id)?;
+
+        // Get all spans for this trace
+        let mut spans = Vec::new();
+        let mut services = HashSet::new();
+        let mut has_error = false;
+
+        for span_id in span_ids.iter() {
+            if let Some(span) = self.spans.get(span_id) {
+                services.insert(span.service_name.clone());
+                if span.status.is_error() {
+                    has_error = true;
+                }
+                spans.push(span.clone());
+            }
+        }
+        drop(span_ids);
+
+        // Find root span (no parent)
+        let root_span = spans
+            .iter()
+            .find(|s| s.parent_span_id.is_none())
+            .or_else(|| spans.first())?;
+
+        // Apply service filter if provided
+        if let Some(filter) = service_filter {
+            if !services.contains(filter) {
+                return None;
+            }
+        }
+
+        // Calculate total duration (from earliest start to latest end)
+        let min_start = spans.iter().map(|s| s.start_time).min()?;
+        let max_end = spans.iter().map(|s| s.start_time + s.duration).max()?;
+        let duration = max_end
+            .duration_since(min_start)
+            .unwrap_or_else(|_| Duration::ZERO);
+
+        Some(TraceInfo {
+            trace_id: trace_id.clone(),
+            root_service: root_span.service_name.clone(),
+            root_operation: root_span.operation_name.clone(),
+            span_count: spans.len(),
+            duration,
+            start_time: min_start,
+            has_error,
+            services: services.into_iter().collect(),
+        })
+    }
+
+    /// Spans whose attribute `key` equals `value`, looked up in the attribute index.
+    fn search_spans_indexed(
+        &self,
+        value: &str,
+        service: Option<&str>,
+        key: &str,
+        limit: usize,
+    ) -> Vec<Span> {
+        self.attribute_index
+            .lookup(key, value)
+            .iter()
+            .filter_map(|span_id| self.spans.get(span_id).map(|span| span.clone()))
+            .filter(|span| {
+                span.attributes.get(key) == Some(value)
+                    && service.map_or(true, |svc| span.service_name.as_str() == svc)
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Move spans started before the archive cutoff from memory to disk.
+    pub async fn migrate_to_archive(&self) -> Result<usize> {
+        let Some(ref archive) = self.archive else {
+            return Ok(0);
+        };
+        let cutoff = archive_cutoff(self.clock.now(), self.archive_after);
+        let mut migrated = 0;
+
+        // Hot spans
+        let cold_ids: Vec<SpanId> = self
+            .spans
+            .iter()
+            .filter(|entry| entry.start_time < cutoff)
+            .map(|entry| entry.key().clone())
+            .collect();
+        for chunk in cold_ids.chunks(ARCHIVE_BLOCK_SPANS) {
+            let spans: Vec<Span> = chunk
+                .iter()
+                .filter_map(|id| self.spans.get(id).map(|span| span.clone()))
+                .collect();
+            // Write before removing so a failed write loses nothing
+            archive.append(&spans)?;
+            for span in &spans {
+                if self.spans.remove(&span.span_id).is_some() {
+                    // Archived, not evicted: keep it out of the eviction log
+                    remove_span_indices!(self, span, &span.span_id);
+                }
+            }
+            migrated += spans.len();
+            tokio::task::yield_now().await;
+        }
+
+        // Warm traces whose spans are all cold
+        let cold_traces: Vec<TraceId> = self
+            .warm_batches
+            .iter()
+            .filter(|entry| entry.newest_start < cutoff)
+            .map(|entry| entry.key().clone())
+            .collect();
+        let mut cold_spans = Vec::new();
+        for trace_id in &cold_traces {
+            cold_spans.extend(self.warm_trace_spans(trace_id).unwrap_or_default());
+        }
+        if !cold_spans.is_empty() {
+            archive.append(&cold_spans)?;
+            for trace_id in &cold_traces {
+                self.remove_warm_batch(trace_id);
+            }
+            migrated += cold_spans.len();
+        }
+
+        if migrated > 0 {
+            tracing::info!(
+                "Archived {} cold spans to {}",
+                migrated,
+                archive.path().display()
+            );
+        }
+        Ok(migrated)
+    }
+
+    /// Delete every span of `trace_ids` from memory, the archive and the
+    /// WAL, returning the number of spans removed.
+    ///
+    /// Works through the traces in batches, yielding between them like
+    /// eviction. Deleted traces are not recorded as evicted.
+    pub async fn purge_traces(&self, trace_ids: &[TraceId]) -> Result<usize> {
+        let mut removed = 0;
+        for chunk in trace_ids.chunks(PURGE_BATCH_TRACES) {
+            for trace_id in chunk {
+                let span_ids = self
+                    .traces
+                    .get(trace_id)
+                    .map(|span_ids| span_ids.clone())
+                    .unwrap_or_default();
+                for span_id in &span_ids {
+                    if let Some((_, span)) = self.spans.remove(span_id) {
+                        remove_span_indices!(self, &span, span_id);
+                        removed += 1;
+                    }
+                }
+                self.traces.remove(trace_id);
+                self.span_order.remove_trace(trace_id);
+
+                if let Some(batch) = self.remove_warm_batch(trace_id) {
+                    removed += batch.span_count;
+                }
+                self.invalidate_warm_trace(trace_id);
+            }
+            tokio::task::yield_now().await;
+        }
+
+        let purged: HashSet<TraceId> = trace_ids.iter().cloned().collect();
+        if let Some(ref archive) = self.archive {
+            removed += archive.purge_traces(&purged)?;
+        }
+        if let Some(ref wal) = self.wal {
+            // Replayed spans were already counted in memory
+            wal.purge_traces(&purged)?;
+        }
+        if let Some(ref snapshot) = self.snapshot {
+            snapshot.purge_traces(&purged)?;
+        }
+        Ok(removed)
+    }
+
+    /// Compress every span started more than the warm threshold ago into
+    /// the warm tier, returning how many spans were moved.
+    pub async fn migrate_to_warm(&self) -> Result<usize> {
+        let mut migrated = 0;
+        loop {
+            let compressed = self.compress_old_spans().await?;
+            if compressed == 0 {
+                break;
+            }
+            migrated += compressed;
+            tokio::task::yield_now().await;
+        }
+        Ok(migrated)
+    }
+
+    /// Compress a batch of old spans to save 5-10x memory, returning how
+    /// many were compressed.
+    async fn compress_old_spans(&self) -> Result<usize> {
+        let now = self.clock.now();
         let mut spans_to_compress: HashMap<TraceId, Vec<Span>> = HashMap::new();
 
         // Collect spans older than compression threshold
@@ -103,64 +858,93 @@ impl InMemoryStorage {
             let mut temp_spans = Vec::new();
 
             // Collect from span_order
-            while let Some((timestamp, span_id)) = self.span_order.pop() {
-                let span_age = now.duration_since(timestamp).unwrap_or_default();
+            for queue in self.span_order.bands() {
+                while let Some((timestamp, span_id)) = queue.pop() {
+                    let span_age = now.duration_since(timestamp).unwrap_or_default();
 
-                if span_age < self.compression_threshold {
-                    // Put it back - we've reached recent spans
-                    temp_spans.push((timestamp, span_id));
-                    break;
-                }
+                    if span_age < self.compression_threshold {
+                        // Put it back - we've reached recent spans
+                        temp_spans.push((timestamp, span_id));
+                        break;
+                    }
 
-                if let Some((_, span)) = self.spans.remove(&span_id) {
-                    let trace_id = span.trace_id.clone();
-                    spans_to_compress.entry(trace_id).or_default().push(span);
-                    collected += 1;
+                    if let Some((_, span)) = self.spans.remove(&span_id) {
+                        // Compressed spans are no longer searchable
+                        self.attribute_index.remove(&span);
+                        self.time_index.remove(&span);
+                        let trace_id = span.trace_id.clone();
+                        spans_to_compress.entry(trace_id).or_default().push(span);
+                        collected += 1;
 
-                    // Batch compression - don't process too many at once
-                    if collected >= 500 {
-                        break;
+                        // Batch compression - don't process too many at once
+                        if collected >= 500 {
+                            break;
+                        }
+                    } else {
+                        // Span was already removed
+                        temp_spans.push((timestamp, span_id));
                     }
-                } else {
-                    // Span was already removed
-                    temp_spans.push((timestamp, span_id));
                 }
-            }
 
-            // Put back spans that weren't compressed
-            for item in temp_spans.into_iter().rev() {
-                self.span_order.push(item);
+                // Put back spans that weren't compressed
+                for item in temp_spans.drain(..).rev() {
+                    queue.push(item);
+                }
+                if collected >= 500 {
+                    break;
+                }
             }
         }
 
         if spans_to_compress.is_empty() {
-            return Ok(());
+            return Ok(0);
         }
 
         // Compress spans by trace
         let mut compressed_count = 0;
+        let mut failure = None;
         for (trace_id, spans) in spans_to_compress {
             if spans.is_empty() {
                 continue;
             }
 
+            // A trace compressed before keeps one batch, with its earlier warm spans
+            let mut batch_spans = match self.decompress_warm_trace(&trace_id) {
+                Ok(earlier) => earlier.unwrap_or_default(),
+                Err(e) => {
+                    // Keep the earlier batch rather than replace it with the new spans only
+                    self.restore_hot_spans(spans);
+                    failure.get_or_insert(e);
+                    continue;
+                },
+            };
+            batch_spans.extend(spans.iter().cloned());
+
             match self
                 .compression_engine
-                .compress_spans(&spans, CompressionLevel::Balanced)
+                .compress_spans(&batch_spans, CompressionLevel::Balanced)
             {
                 Ok(compressed_batch) => {
-                    self.compressed_batches
-                        .insert(trace_id.clone(), compressed_batch);
+                    self.remove_warm_batch(&trace_id);
+                    let hot_bytes = spans_memory(&spans);
+                    update_counter!(self.counters.memory_bytes, sub hot_bytes);
+                    self.insert_warm_batch(trace_id.clone(), compressed_batch, &batch_spans);
                     compressed_count += spans.len();
 
                     // Remove compressed spans from traces mapping
-                    if let Some(mut span_ids) = self.traces.get_mut(&trace_id) {
-                        for span in &spans {
-                            span_ids.retain(|id| id != &span.span_id);
-                        }
-                        if span_ids.is_empty() {
-                            self.traces.remove(&trace_id);
-                        }
+                    // Release the entry guard before removing, DashMap would deadlock otherwise
+                    let trace_emptied = match self.traces.get_mut(&trace_id) {
+                        Some(mut span_ids) => {
+                            for span in &spans {
+                                span_ids.retain(|id| id != &span.span_id);
+                            }
+                            span_ids.is_empty()
+                        },
+                        None => false,
+                    };
+                    if trace_emptied {
+                        self.traces.remove(&trace_id);
+                        self.span_order.remove_trace(&trace_id);
                     }
 
                     // Update service mappings
@@ -179,9 +963,7 @@ impl InMemoryStorage {
                 Err(e) => {
                     tracing::error!("Failed to compress spans for trace {}: {}", trace_id, e);
                     // Put spans back if compression fails
-                    for span in spans {
-                        self.spans.insert(span.span_id.clone(), span);
-                    }
+                    self.restore_hot_spans(spans);
                 },
             }
         }
@@ -193,23 +975,159 @@ impl InMemoryStorage {
             );
         }
 
-        Ok(())
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(compressed_count),
+        }
+    }
+
+    /// Put spans taken for compression back in the hot tier.
+    fn restore_hot_spans(&self, spans: Vec<Span>) {
+        self.span_order.push_all(&spans);
+        for span in spans {
+            self.attribute_index.insert(&span);
+            self.time_index.insert(&span);
+            self.spans.insert(span.span_id.clone(), span);
+        }
+    }
+
+    /// Add the warm batch of a trace, whose spans are `spans`.
+    fn insert_warm_batch(&self, trace_id: TraceId, batch: CompressedSpanBatch, spans: &[Span]) {
+        let info = WarmBatchInfo {
+            newest_start: spans
+                .iter()
+                .map(|span| span.start_time)
+                .max()
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+            expires_at: spans
+                .iter()
+                .map(|span| {
+                    span.start_time
+                        + self
+                            .cleanup_config
+                            .retention_for(span.service_name.as_str())
+                })
+                .max()
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+            uncompressed_bytes: spans_memory(spans),
+        };
+        update_counter!(self.counters.memory_bytes, add batch.compressed_size);
+        update_counter!(self.counters.warm_spans, add batch.span_count);
+        update_counter!(self.counters.warm_bytes, add batch.compressed_size);
+        update_counter!(self.counters.warm_uncompressed_bytes, add info.uncompressed_bytes);
+        self.compressed_batches.insert(trace_id.clone(), batch);
+        self.warm_batches.insert(trace_id.clone(), info);
+        self.invalidate_warm_trace(&trace_id);
+    }
+
+    /// Remove the warm batch of a trace.
+    fn remove_warm_batch(&self, trace_id: &TraceId) -> Option<CompressedSpanBatch> {
+        let (_, batch) = self.compressed_batches.remove(trace_id)?;
+        let uncompressed_bytes = self
+            .warm_batches
+            .remove(trace_id)
+            .map_or(0, |(_, info)| info.uncompressed_bytes);
+        self.invalidate_warm_trace(trace_id);
+        update_counter!(self.counters.memory_bytes, sub batch.compressed_size);
+        update_counter!(self.counters.warm_spans, sub batch.span_count);
+        update_counter!(self.counters.warm_bytes, sub batch.compressed_size);
+        update_counter!(self.counters.warm_uncompressed_bytes, sub uncompressed_bytes);
+        Some(batch)
+    }
+
+    /// Evict warm batches, oldest first, until at least `count` spans are
+    /// gone or the warm tier is empty. Returns the number of spans evicted.
+    fn evict_warm_batches(&self, count: usize) -> usize {
+        let mut oldest: Vec<(SystemTime, TraceId)> = self
+            .warm_batches
+            .iter()
+            .map(|entry| (entry.newest_start, entry.key().clone()))
+            .collect();
+        oldest.sort_unstable_by_key(|(newest_start, _)| *newest_start);
+
+        let mut evicted = 0;
+        for (_, trace_id) in oldest {
+            if evicted >= count {
+                break;
+            }
+            if let Some(batch) = self.remove_warm_batch(&trace_id) {
+                evicted += batch.span_count;
+                if !self.traces.contains_key(&trace_id) {
+                    self.evictions.record(&trace_id);
+                }
+            }
+        }
+        evicted
+    }
+
+    /// Spans held in memory, hot and warm.
+    fn memory_span_count(&self) -> usize {
+        self.spans.len() + update_counter!(self.counters.warm_spans, get)
+    }
+
+    /// Decompress a warm trace, serving repeated reads from the LRU cache.
+    fn warm_trace_spans(&self, trace_id: &TraceId) -> Option<Vec<Span>> {
+        self.decompress_warm_trace(trace_id).unwrap_or_else(|e| {
+            tracing::error!("Failed to decompress spans for trace {}: {}", trace_id, e);
+            None
+        })
+    }
+
+    /// Spans of a trace's warm batch, `None` if it has none.
+    fn decompress_warm_trace(&self, trace_id: &TraceId) -> Result<Option<Vec<Span>>> {
+        if let Some(ref cache) = self.warm_cache {
+            if let Some(spans) = cache.lock().get(trace_id) {
+                return Ok(Some(spans.clone()));
+            }
+        }
+
+        let Some(compressed_batch) = self.compressed_batches.get(trace_id) else {
+            return Ok(None);
+        };
+        let spans = self.compression_engine.decompress_spans(&compressed_batch)?;
+        if let Some(ref cache) = self.warm_cache {
+            cache.lock().put(trace_id.clone(), spans.clone());
+        }
+        Ok(Some(spans))
+    }
+
+    /// Drop a cached warm trace after its compressed batch changed.
+    fn invalidate_warm_trace(&self, trace_id: &TraceId) {
+        if let Some(ref cache) = self.warm_cache {
+            cache.lock().pop(trace_id);
+        }
+    }
+
+    /// Spans of a trace held in memory: decompressed warm spans, and the
+    /// IDs of hot spans still in the span map.
+    fn hot_trace_spans(&self, trace_id: &TraceId) -> (Vec<Span>, Vec<SpanId>) {
+        let warm = self.warm_trace_spans(trace_id).unwrap_or_default();
+
+        // SIMD-accelerated lookup for active spans, then the DashMap index
+        let hot = self
+            .find_trace_simd(trace_id)
+            .filter(|span_ids| !span_ids.is_empty())
+            .or_else(|| self.traces.get(trace_id).map(|span_ids| span_ids.clone()))
+            .unwrap_or_default();
+        (warm, hot)
     }
 
     /// Production-grade span eviction with memory tracking (async-runtime friendly).
     async fn evict_oldest_spans(&self, count: usize) -> usize {
         let batch_size = 100; // Process in batches to avoid blocking
-        let mut total_removed = 0;
+                              // Warm batches hold the oldest spans, so they go first
+        let mut total_removed = self.evict_warm_batches(count);
         let mut total_memory_freed = 0;
-        let mut remaining = count;
+        let mut remaining = count.saturating_sub(total_removed);
 
         while remaining > 0 {
             let batch_count = remaining.min(batch_size);
             let mut span_ids_to_remove = Vec::new();
 
-            // Batch 1: Collect span IDs from lock-free queue
+            // Batch 1: Collect span IDs, least important traces first
             for _ in 0..batch_count {
-                if let Some((_, span_id)) = self.span_order.pop() {
+                let trace_of = |id: &SpanId| self.spans.get(id).map(|s| s.trace_id.clone());
+                if let Some((_, span_id)) = self.span_order.pop_next(trace_of) {
                     span_ids_to_remove.push(span_id);
                 } else {
                     break;
@@ -228,6 +1146,8 @@ impl InMemoryStorage {
                 if let Some((_, span)) = self.spans.remove(&span_id) {
                     // Estimate memory freed
                     batch_memory_freed += self.estimate_span_memory(&span);
+                    self.attribute_index.remove(&span);
+                    self.time_index.remove(&span);
 
                     // Remove from trace index
                     if let Some(mut trace_spans) = self.traces.get_mut(&span.trace_id) {
@@ -235,6 +1155,8 @@ impl InMemoryStorage {
                         if trace_spans.is_empty() {
                             drop(trace_spans);
                             self.traces.remove(&span.trace_id);
+                            self.span_order.remove_trace(&span.trace_id);
+                            self.evictions.record(&span.trace_id);
                         }
                     }
 
@@ -314,7 +1236,7 @@ impl InMemoryStorage {
                         let latest_time = service_spans
                             .back()
                             .map(|(t, _)| *t)
-                            .unwrap_or(SystemTime::now());
+                            .unwrap_or_else(|| self.clock.now());
                         self.active_services
                             .insert(service_name.clone(), latest_time);
                     }
@@ -329,6 +1251,8 @@ impl InMemoryStorage {
                             self.counters
                                 .memory_bytes
                                 .fetch_sub(memory_freed, Ordering::Relaxed);
+                            self.attribute_index.remove(&span);
+                            self.time_index.remove(&span);
 
                             // Remove from trace index
                             if let Some(mut trace_spans) = self.traces.get_mut(&span.trace_id) {
@@ -336,6 +1260,8 @@ impl InMemoryStorage {
                                 if trace_spans.is_empty() {
                                     drop(trace_spans);
                                     self.traces.remove(&span.trace_id);
+                                    self.span_order.remove_trace(&span.trace_id);
+                                    self.evictions.record(&span.trace_id);
                                 }
                             }
 
@@ -357,14 +1283,18 @@ impl InMemoryStorage {
     async fn emergency_cleanup_internal(&self) -> Result<usize> {
         let mut removed = 0;
 
+        // 0. Spill cold spans to the disk archive, if one is configured
+        if let Err(e) = self.migrate_to_archive().await {
+            tracing::warn!("Archiving failed during emergency cleanup: {}", e);
+        }
+
         // 1. Compress old spans first (5-10x memory savings)
         if let Err(e) = self.compress_old_spans().await {
             tracing::warn!("Compression failed during emergency cleanup: {}", e);
         }
 
         // 2. Remove expired spans based on retention period
-        let cutoff_time = SystemTime::now() - self.cleanup_config.retention_period;
-        removed += self.cleanup_expired_spans(cutoff_time).await;
+        removed += self.cleanup_expired_spans(self.clock.now()).await;
 
         // 3. Remove incomplete traces (orphaned spans)
         removed += self.cleanup_incomplete_traces().await;
@@ -393,51 +1323,90 @@ impl InMemoryStorage {
         Ok(removed)
     }
 
-    /// Remove spans older than the retention period (async-runtime friendly).
-    async fn cleanup_expired_spans(&self, cutoff_time: SystemTime) -> usize {
+    /// Remove spans older than the retention period of their service
+    /// (async-runtime friendly).
+    async fn cleanup_expired_spans(&self, now: SystemTime) -> usize {
         let batch_size = 100;
         let mut total_removed = 0;
-
-        loop {
-            let mut expired_spans = Vec::new();
-
-            // Batch 1: Collect expired span IDs from lock-free queue
-            // Note: With SegQueue, we need to peek and conditionally pop
-            // Since we can't peek without popping, we'll collect all and re-add non-expired
-            let mut to_reinsert = Vec::new();
-            for _ in 0..batch_size {
-                if let Some((timestamp, span_id)) = self.span_order.pop() {
-                    if timestamp < cutoff_time {
+        let cutoff =
+            |retention: Duration| now.checked_sub(retention).unwrap_or(SystemTime::UNIX_EPOCH);
+        // Younger spans are within every service's retention
+        let scan_cutoff = cutoff(self.cleanup_config.shortest_retention());
+        // Spans kept by a longer retention go back in the queue, so with
+        // overrides the whole band is rotated to keep its order
+        let full_scan = !self.cleanup_config.retention_overrides.is_empty();
+
+        for queue in self.span_order.bands() {
+            // Each span is looked at no more than once
+            let mut remaining = queue.len();
+            let mut reached_recent = false;
+            while remaining > 0 && !reached_recent {
+                let mut expired_spans = Vec::new();
+
+                // Batch 1: Collect expired span IDs from lock-free queue
+                // Note: With SegQueue, we need to peek and conditionally pop
+                // Since we can't peek without popping, we'll collect all and re-add non-expired
+                let mut to_reinsert = Vec::new();
+                for _ in 0..batch_size.min(remaining) {
+                    let Some((timestamp, span_id)) = queue.pop() else {
+                        remaining = 0;
+                        break;
+                    };
+                    remaining -= 1;
+                    if timestamp >= scan_cutoff {
+                        to_reinsert.push((timestamp, span_id));
+                        if full_scan {
+                            continue;
+                        }
+                        reached_recent = true;
+                        break; // Spans are ordered by time
+                    }
+                    let retention = self.spans.get(&span_id).map_or(
+                        self.cleanup_config.retention_period,
+                        |span| {
+                            self.cleanup_config
+                                .retention_for(span.service_name.as_str())
+                        },
+                    );
+                    if timestamp < cutoff(retention) {
                         expired_spans.push(span_id);
                     } else {
-                        // Not expired, need to re-insert
                         to_reinsert.push((timestamp, span_id));
-                        break; // Spans are ordered by time
                     }
-                } else {
-                    break;
                 }
-            }
-            // Re-insert non-expired spans at the front
-            for item in to_reinsert.into_iter().rev() {
-                self.span_order.push(item);
-            }
+                // Re-insert non-expired spans at the back
+                for item in to_reinsert {
+                    queue.push(item);
+                }
+
+                // Batch 2: Process removals without holding span_order lock
+                for span_id in expired_spans {
+                    if let Some((_, span)) = self.spans.remove(&span_id) {
+                        // Remove from all indices (optimized to avoid repeated locks)
+                        self.remove_span_from_indices(&span, &span_id).await;
+                        total_removed += 1;
+                    }
+                }
 
-            if expired_spans.is_empty() {
-                break;
+                // Yield to async runtime after each batch
+                tokio::task::yield_now().await;
             }
+        }
 
-            // Batch 2: Process removals without holding span_order lock
-            for span_id in expired_spans {
-                if let Some((_, span)) = self.spans.remove(&span_id) {
-                    // Remove from all indices (optimized to avoid repeated locks)
-                    self.remove_span_from_indices(&span, &span_id).await;
-                    total_removed += 1;
+        // A warm batch goes once its last span is past its service's retention
+        let expired_traces: Vec<TraceId> = self
+            .warm_batches
+            .iter()
+            .filter(|entry| entry.expires_at < now)
+            .map(|entry| entry.key().clone())
+            .collect();
+        for trace_id in expired_traces {
+            if let Some(batch) = self.remove_warm_batch(&trace_id) {
+                total_removed += batch.span_count;
+                if !self.traces.contains_key(&trace_id) {
+                    self.evictions.record(&trace_id);
                 }
             }
-
-            // Yield to async runtime after each batch
-            tokio::task::yield_now().await;
         }
 
         total_removed
@@ -446,7 +1415,7 @@ impl InMemoryStorage {
     /// Remove incomplete traces (traces with only one span that's been around too long).
     async fn cleanup_incomplete_traces(&self) -> usize {
         let mut removed = 0;
-        let cutoff = SystemTime::now() - Duration::from_secs(300); // 5 minutes
+        let cutoff = self.clock.now() - Duration::from_secs(300); // 5 minutes
         let batch_size = 100;
 
         let traces_to_check: Vec<_> = self
@@ -479,7 +1448,7 @@ impl InMemoryStorage {
     /// Remove services that haven't seen activity recently (async-runtime friendly).
     async fn cleanup_inactive_services(&self) -> usize {
         let mut removed = 0;
-        let cutoff = SystemTime::now() - Duration::from_secs(900); // 15 minutes
+        let cutoff = self.clock.now() - Duration::from_secs(900); // 15 minutes
         let batch_size = 20; // Smaller batches for service cleanup
 
         let inactive_services: Vec<_> = self
@@ -527,6 +1496,9 @@ impl InMemoryStorage {
     /// Helper to remove span from all indices.
     async fn remove_span_from_indices(&self, span: &Span, span_id: &SpanId) {
         remove_span_indices!(self, span, span_id);
+        if !self.traces.contains_key(&span.trace_id) {
+            self.evictions.record(&span.trace_id);
+        }
     }
 
     /// Check if cleanup is needed based on memory pressure.
@@ -542,7 +1514,8 @@ impl InMemoryStorage {
         }
 
         // Regular cleanup interval
-        last_cleanup.elapsed() >= self.cleanup_config.cleanup_interval
+        self.clock.instant().saturating_duration_since(last_cleanup)
+            >= self.cleanup_config.cleanup_interval
     }
 
     /// Get current memory pressure level.
@@ -552,6 +1525,51 @@ impl InMemoryStorage {
         memory_usage as f64 / self.cleanup_config.max_memory_bytes as f64
     }
 
+    /// Traces with a span starting within `[start, end]`, newest first.
+    ///
+    /// Only the time-index buckets overlapping the range are visited, so the
+    /// cost grows with the traces in the range rather than all stored traces.
+    pub fn time_range_query(&self, start: SystemTime, end: SystemTime) -> Vec<TraceId> {
+        let nanos = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64
+        };
+        let in_range = |span_id: &SpanId| {
+            self.spans
+                .get(span_id)
+                .is_some_and(|span| span.start_time >= start && span.start_time <= end)
+        };
+
+        // Traces from the edge buckets may only have spans outside the range
+        self.time_index
+            .traces_between(nanos(start), nanos(end))
+            .into_iter()
+            .filter(|trace_id| {
+                self.traces
+                    .get(trace_id)
+                    .is_some_and(|span_ids| span_ids.iter().any(in_range))
+            })
+            .collect()
+    }
+
+    /// The `list_traces` result found by scanning every stored trace, the
+    /// path taken before the time index. Kept to check and benchmark the
+    /// indexed listing against.
+    pub fn scan_traces(
+        &self,
+        service: Option<&str>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<TraceInfo>> {
+        impl_search!(
+            self,
+            |spans: &Vec<Span>| trace_matches(spans, service, start_time, end_time),
+            limit
+        )
+    }
+
     /// SIMD-accelerated trace lookup for ultra-fast search (4x speedup)
     #[inline]
     pub fn find_trace_simd(&self, trace_id: &TraceId) -> Option<Vec<SpanId>> {
@@ -629,15 +1647,12 @@ impl InMemoryStorage {
         let memory_mb = memory_bytes as f64 / 1024.0 / 1024.0;
         let memory_pressure = self.get_memory_pressure();
 
-        // Calculate processing rate
-        let elapsed = self.counters.start_time.elapsed().as_secs_f64();
+        // Processing rate over the recent window, not the whole uptime
         let spans_processed = self.counters.spans_processed.load(Ordering::Relaxed);
         let processing_errors = self.counters.processing_errors.load(Ordering::Relaxed);
-        let processing_rate = if elapsed > 0.0 {
-            spans_processed as f64 / elapsed
-        } else {
-            0.0
-        };
+        let processing_rate = self
+            .recent_spans
+            .rate_per_second_at(self.clock.instant(), self.cleanup_config.rate_window);
         let error_rate = if spans_processed > 0 {
             processing_errors as f64 / spans_processed as f64
         } else {
@@ -647,7 +1662,11 @@ impl InMemoryStorage {
         // With SegQueue, we can't directly access front/back without popping
         // We'll track oldest/newest through other means or sample
         let oldest_span = None; // Will be tracked separately if needed
-        let newest_span = Some(SystemTime::now()); // Approximate with current time
+        let newest_span = Some(self.clock.now()); // Approximate with current time
+
+        let warm_memory_bytes = update_counter!(self.counters.warm_bytes, get);
+        let warm_memory_saved_bytes = update_counter!(self.counters.warm_uncompressed_bytes, get)
+            .saturating_sub(warm_memory_bytes);
 
         StorageStats {
             trace_count,
@@ -661,11 +1680,180 @@ impl InMemoryStorage {
             processing_rate,
             error_rate,
             cleanup_count: self.counters.cleanup_operations.load(Ordering::Relaxed),
-            last_cleanup: Some(SystemTime::now()), // Approximate
+            last_cleanup: Some(self.clock.now()), // Approximate
             health_status: self.get_health_status(),
             uptime_seconds: self.counters.start_time.elapsed().as_secs(),
+            rejected_spans: self.counters.spans_rejected.load(Ordering::Relaxed),
+            spans_processed,
+            spans_evicted: self.counters.spans_evicted.load(Ordering::Relaxed),
+            warm_span_count: update_counter!(self.counters.warm_spans, get),
+            warm_memory_bytes,
+            warm_memory_saved_bytes,
+        }
+    }
+
+    /// Per-service and largest-trace memory estimates for diagnostics.
+    ///
+    /// Walks the hot span map once, locking one shard at a time.
+    pub fn memory_footprint(
+        &self,
+        top_traces: usize,
+    ) -> (Vec<ServiceFootprint>, Vec<TraceFootprint>) {
+        let mut services: HashMap<ServiceName, (usize, usize)> = HashMap::new();
+        let mut traces: HashMap<TraceId, (usize, usize)> = HashMap::new();
+
+        for entry in self.spans.iter() {
+            let span = entry.value();
+            let bytes = self.estimate_span_memory(span);
+
+            let service = services.entry(span.service_name.clone()).or_default();
+            service.0 += 1;
+            service.1 += bytes;
+
+            let trace = traces.entry(span.trace_id.clone()).or_default();
+            trace.0 += 1;
+            trace.1 += bytes;
+        }
+
+        let mut services: Vec<ServiceFootprint> = services
+            .into_iter()
+            .map(|(service, (span_count, estimated_bytes))| ServiceFootprint {
+                service,
+                span_count,
+                estimated_bytes,
+            })
+            .collect();
+        services.sort_by(|a, b| b.estimated_bytes.cmp(&a.estimated_bytes));
+
+        let mut traces: Vec<TraceFootprint> = traces
+            .into_iter()
+            .map(|(trace_id, (span_count, estimated_bytes))| TraceFootprint {
+                trace_id,
+                span_count,
+                estimated_bytes,
+            })
+            .collect();
+        traces.sort_by(|a, b| b.estimated_bytes.cmp(&a.estimated_bytes));
+        traces.truncate(top_traces);
+
+        (services, traces)
+    }
+}
+
+/// What the snapshot thread reads, held weakly where it would otherwise
+/// keep a dropped storage's spans and WAL alive.
+struct SnapshotSource {
+    snapshot: Arc<SpanSnapshot>,
+    spans: Weak<DashMap<SpanId, Span>>,
+    compressed_batches: Weak<DashMap<TraceId, CompressedSpanBatch>>,
+    compression_engine: Arc<CompressionEngine>,
+    wal: Option<Weak<WriteAheadLog>>,
+    counters: Arc<StorageCounters>,
+    /// Memory use above which snapshots wait for cleanup.
+    postpone_above_bytes: usize,
+}
+
+/// What retention and eviction need to know of a warm batch without
+/// decompressing it.
+#[derive(Debug, Clone, Copy)]
+struct WarmBatchInfo {
+    /// Start time of the batch's newest span.
+    newest_start: SystemTime,
+    /// When every span of the batch is past its service's retention.
+    expires_at: SystemTime,
+    /// Estimated memory of the batch's spans when hot.
+    uncompressed_bytes: usize,
+}
+
+/// Whether every span of a trace is of `service` and starts within the
+/// bounds (Unix nanos).
+fn trace_matches(
+    spans: &[Span],
+    service: Option<&str>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+) -> bool {
+    spans.iter().all(|span| {
+        let nanos = span
+            .start_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        service.map_or(true, |svc| span.service_name.as_str() == svc)
+            && start_time.map_or(true, |start| nanos >= start)
+            && end_time.map_or(true, |end| nanos <= end)
+    })
+}
+
+/// Estimated memory of `spans` when hot.
+fn spans_memory(spans: &[Span]) -> usize {
+    spans.iter().map(estimate_span_memory).sum()
+}
+
+/// Write hot and warm spans to `snapshot`, then delete the WAL segments it
+/// covers. Returns how many spans were written.
+fn snapshot_spans(
+    snapshot: &SpanSnapshot,
+    spans: &DashMap<SpanId, Span>,
+    compressed_batches: &DashMap<TraceId, CompressedSpanBatch>,
+    compression_engine: &CompressionEngine,
+    wal: Option<&WriteAheadLog>,
+) -> Result<usize> {
+    // Spans logged from here on are in the checkpoint segment or later
+    let checkpoint = wal.map(WriteAheadLog::checkpoint).transpose()?;
+
+    let written = snapshot.write(|writer| {
+        for entry in spans.iter() {
+            writer.append(entry.value())?;
+        }
+        // One warm batch at a time, so the extra memory stays small
+        for entry in compressed_batches.iter() {
+            for span in compression_engine.decompress_spans(entry.value())? {
+                writer.append(&span)?;
+            }
         }
+        Ok(())
+    })?;
+
+    if let (Some(wal), Some(seq)) = (wal, checkpoint) {
+        // The segment before the checkpoint may hold spans logged but not yet indexed
+        wal.remove_segments_before(seq.saturating_sub(1))?;
     }
+    Ok(written)
+}
+
+/// Snapshot every `interval` until the storage is dropped.
+fn spawn_snapshot_thread(source: SnapshotSource, interval: Duration) -> Result<()> {
+    std::thread::Builder::new()
+        .name("urpo-snapshot".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+            let (Some(spans), Some(compressed_batches)) =
+                (source.spans.upgrade(), source.compressed_batches.upgrade())
+            else {
+                break;
+            };
+            if source.counters.memory_bytes.load(Ordering::Relaxed) >= source.postpone_above_bytes {
+                tracing::debug!("Snapshot postponed under memory pressure");
+                continue;
+            }
+            let wal = source.wal.as_ref().and_then(Weak::upgrade);
+            match snapshot_spans(
+                &source.snapshot,
+                &spans,
+                &compressed_batches,
+                &source.compression_engine,
+                wal.as_deref(),
+            ) {
+                Ok(written) => tracing::debug!(
+                    "Snapshotted {} spans to {}",
+                    written,
+                    source.snapshot.path().display()
+                ),
+                Err(e) => tracing::warn!("Span snapshot failed: {}", e),
+            }
+        })?;
+    Ok(())
 }
 
 #[async_trait::async_trait]
@@ -675,148 +1863,75 @@ impl StorageBackend for InMemoryStorage {
         self.counters
             .spans_processed
             .fetch_add(1, Ordering::Relaxed);
-
-        let span_id = span.span_id.clone();
-        let trace_id = span.trace_id.clone();
-        let service_name = span.service_name.clone();
-        let start_time = span.start_time;
+        self.recent_spans.record_at(self.clock.instant(), 1);
 
         // Estimate memory for this span
         let span_memory = self.estimate_span_memory(&span);
 
-        // Check memory pressure and perform cleanup if needed
-        let memory_pressure = self.get_memory_pressure();
-        if memory_pressure >= self.cleanup_config.warning_threshold || self.should_cleanup().await {
-            if memory_pressure >= self.cleanup_config.emergency_threshold {
-                // Emergency: apply aggressive backpressure
-                self.counters
-                    .processing_errors
-                    .fetch_add(1, Ordering::Relaxed);
-
-                // Try one last emergency cleanup before rejecting
-                if let Ok(removed) = self.emergency_cleanup_internal().await {
-                    if removed == 0 {
-                        // No space could be freed, reject with backpressure error
-                        return Err(crate::core::UrpoError::MemoryLimitExceeded {
-                            current: (self.counters.memory_bytes.load(Ordering::Relaxed)
-                                / 1024
-                                / 1024) as usize,
-                            limit: (self.cleanup_config.max_memory_bytes / 1024 / 1024) as usize,
-                        });
-                    }
-                }
+        self.make_room(1).await?;
 
-                // After cleanup, allow span if there's now space
-                let new_pressure = self.get_memory_pressure();
-                if new_pressure >= self.cleanup_config.emergency_threshold {
-                    return Err(crate::core::UrpoError::MemoryLimitExceeded {
-                        current: (self.counters.memory_bytes.load(Ordering::Relaxed) / 1024 / 1024)
-                            as usize,
-                        limit: (self.cleanup_config.max_memory_bytes / 1024 / 1024) as usize,
-                    });
-                }
-            } else if memory_pressure >= self.cleanup_config.critical_threshold {
-                // Critical: aggressive cleanup
-                let _ = self.emergency_cleanup_internal().await;
-                *self.last_cleanup.lock().await = Instant::now();
-            } else {
-                // Warning: regular cleanup with compression
-                let _ = self.compress_old_spans().await; // Try compression first for 5-10x memory savings
-                let to_evict = (self.max_spans / 20).max(10); // Evict 5% when at warning
-                self.evict_oldest_spans(to_evict).await;
-                *self.last_cleanup.lock().await = Instant::now();
-            }
+        // Log before indexing so a crash cannot lose an acknowledged span
+        if let Some(ref wal) = self.wal {
+            wal.append(&span)?;
         }
-
-        // Apply hard limit with backpressure
-        if self.spans.len() >= self.max_spans {
-            // Try to evict spans first
-            let to_evict = (self.max_spans / 5).max(10); // Evict 20% when at capacity
-            let evicted = self.evict_oldest_spans(to_evict).await;
-
-            if evicted == 0 || self.spans.len() >= self.max_spans {
-                // Unable to free space, apply backpressure
-                self.counters
-                    .processing_errors
-                    .fetch_add(1, Ordering::Relaxed);
-                return Err(crate::core::UrpoError::Storage(format!(
-                    "Storage at capacity limit: {} spans",
-                    self.max_spans
-                )));
+        let now = self.clock.now();
+        self.ingest_lag.record(&span, now);
+        if let Some(ref stats) = self.longterm_stats {
+            stats.record(&span);
+            if let Err(e) = stats.flush_if_due(now) {
+                tracing::warn!("Failed to write long-term stats: {}", e);
             }
         }
+        self.index_span(span, span_memory);
 
-        // Store the span
-        self.spans.insert(span_id.clone(), span);
+        // Enforce per-service limits
+        self.enforce_service_limits().await;
 
-        // Update memory tracking
-        self.counters
-            .memory_bytes
-            .fetch_add(span_memory, Ordering::Relaxed);
+        Ok(())
+    }
 
-        // Update trace index with bounds checking
-        {
-            let mut trace_spans = self.traces.entry(trace_id).or_insert_with(Vec::new);
-            trace_spans.push(span_id.clone());
+    async fn store_spans_bulk(&self, spans: Vec<Span>) -> Result<usize> {
+        if spans.is_empty() {
+            return Ok(0);
+        }
+        let count = spans.len();
+        self.counters
+            .spans_processed
+            .fetch_add(count as u64, Ordering::Relaxed);
+        self.recent_spans
+            .record_at(self.clock.instant(), count as u64);
+
+        // The whole batch is refused, so every span counts as rejected
+        self.make_room(count).await.map_err(|e| {
+            if e.is_storage_full() {
+                crate::core::UrpoError::StorageFull { rejected: count }
+            } else {
+                e
+            }
+        })?;
 
-            // Enforce maximum spans per trace (prevent trace explosion)
-            const MAX_SPANS_PER_TRACE: usize = 10_000;
-            if trace_spans.len() > MAX_SPANS_PER_TRACE {
-                // Remove oldest spans from this trace
-                let to_remove = trace_spans.len() - MAX_SPANS_PER_TRACE;
-                for _ in 0..to_remove {
-                    if !trace_spans.is_empty() {
-                        let old_span_id = trace_spans.remove(0);
-                        // Remove from spans storage
-                        self.spans.remove(&old_span_id);
-                        update_counter!(self.counters.spans_evicted, add 1);
-                    }
-                }
-                tracing::warn!(
-                    "Trace exceeded maximum spans ({}), evicted {} spans",
-                    MAX_SPANS_PER_TRACE,
-                    to_remove
-                );
+        if let Some(ref wal) = self.wal {
+            for span in &spans {
+                wal.append(span)?;
             }
         }
-
-        // Update service index with bounds and timestamp tracking
-        {
-            let mut service_spans = self
-                .services
-                .entry(service_name.clone())
-                .or_insert_with(VecDeque::new);
-            service_spans.push_back((start_time, span_id.clone()));
-
-            // Enforce per-service span limits to prevent single service OOM
-            if service_spans.len() > self.max_spans_per_service {
-                // Remove oldest spans for this service
-                let to_remove = service_spans.len() - self.max_spans_per_service;
-                for _ in 0..to_remove {
-                    if let Some((_, old_span_id)) = service_spans.pop_front() {
-                        // Remove from spans storage
-                        if let Some((_, span)) = self.spans.remove(&old_span_id) {
-                            let freed_memory = self.estimate_span_memory(&span);
-                            self.counters
-                                .memory_bytes
-                                .fetch_sub(freed_memory, Ordering::Relaxed);
-                        }
-                        update_counter!(self.counters.spans_evicted, add 1);
-                    }
-                }
+        let now = self.clock.now();
+        for span in &spans {
+            self.ingest_lag.record(span, now);
+        }
+        if let Some(ref stats) = self.longterm_stats {
+            for span in &spans {
+                stats.record(span);
+            }
+            if let Err(e) = stats.flush_if_due(now) {
+                tracing::warn!("Failed to write long-term stats: {}", e);
             }
         }
+        self.index_spans(spans);
 
-        // Add to lock-free span order queue for LRU eviction
-        self.span_order.push((start_time, span_id));
-
-        // Update active services tracking (lock-free with DashMap)
-        self.active_services.insert(service_name, start_time);
-
-        // Enforce per-service limits
         self.enforce_service_limits().await;
 
-        Ok(())
+        Ok(count)
     }
 
     #[inline]
@@ -826,48 +1941,63 @@ impl StorageBackend for InMemoryStorage {
 
     async fn get_trace_spans(&self, trace_id: &TraceId) -> Result<Vec<Span>> {
         let mut spans = Vec::new();
-
-        // First check compressed batches for 5-10x memory efficiency
-        if let Some(compressed_batch) = self.compressed_batches.get(trace_id) {
-            match self.compression_engine.decompress_spans(&compressed_batch) {
-                Ok(decompressed_spans) => {
-                    spans.extend(decompressed_spans);
-                },
-                Err(e) => {
-                    tracing::error!("Failed to decompress spans for trace {}: {}", trace_id, e);
-                },
-            }
+        self.visit_trace_spans(trace_id, &mut |span| {
+            spans.push(span.clone());
+            true
+        })
+        .await?;
+        if let Some(ref adjuster) = self.clock_skew {
+            adjuster.adjust(&mut spans);
         }
+        Ok(spans)
+    }
 
-        // Then try SIMD-accelerated lookup for active spans (4x speedup)
-        if let Some(span_ids) = self.find_trace_simd(trace_id) {
-            for span_id in span_ids.iter() {
-                if let Some(span) = self.spans.get(span_id) {
-                    spans.push(span.clone());
-                }
+    async fn visit_trace_spans(
+        &self,
+        trace_id: &TraceId,
+        visit: &mut (dyn for<'s> FnMut(&'s Span) -> bool + Send),
+    ) -> Result<()> {
+        let (mut decoded, hot_ids) = self.hot_trace_spans(trace_id);
+
+        // Fall back to the disk archive for migrated spans
+        if let Some(ref archive) = self.archive {
+            if archive.contains_trace(trace_id) {
+                decoded.extend(archive.get_trace_spans(trace_id)?);
             }
         }
+        decoded.sort_by_key(|s| s.start_time);
 
-        if !spans.is_empty() {
-            // Sort by start time
-            spans.sort_by_key(|s| s.start_time);
-            return Ok(spans);
-        }
+        // Hot spans are visited in place; only their IDs are sorted
+        let mut hot: Vec<(SystemTime, SpanId)> = hot_ids
+            .into_iter()
+            .filter_map(|span_id| Some((self.spans.get(&span_id)?.start_time, span_id)))
+            .collect();
+        hot.sort_by_key(|(start_time, _)| *start_time);
 
-        // Fallback to regular DashMap lookup
-        if let Some(span_ids) = self.traces.get(trace_id) {
-            let mut spans = Vec::with_capacity(span_ids.len());
-            for span_id in span_ids.iter() {
-                if let Some(span) = self.spans.get(span_id) {
-                    spans.push(span.clone());
-                }
+        let mut decoded = decoded.into_iter().peekable();
+        let mut hot = hot.into_iter().peekable();
+        loop {
+            let take_hot = match (decoded.peek(), hot.peek()) {
+                (None, None) => break,
+                (Some(span), Some((start_time, _))) => *start_time < span.start_time,
+                (None, Some(_)) => true,
+                (Some(_), None) => false,
+            };
+            let keep_going = if take_hot {
+                let (_, span_id) = hot.next().expect("peeked a hot span");
+                // Evicted since its ID was collected
+                let Some(span) = self.spans.get(&span_id) else {
+                    continue;
+                };
+                visit(&span)
+            } else {
+                visit(&decoded.next().expect("peeked a decoded span"))
+            };
+            if !keep_going {
+                break;
             }
-            // Sort by start time
-            spans.sort_by_key(|s| s.start_time);
-            Ok(spans)
-        } else {
-            Ok(Vec::new())
         }
+        Ok(())
     }
 
     async fn get_service_spans(
@@ -933,6 +2063,8 @@ impl StorageBackend for InMemoryStorage {
             let min_duration = durations.first().copied().unwrap_or_default();
             let max_duration = durations.last().copied().unwrap_or_default();
 
+            let ingest_lag = self.ingest_lag.percentiles(&service_name).unwrap_or_default();
+
             // Calculate error rate
             let error_rate = if span_count > 0 {
                 error_count as f64 / span_count as f64
@@ -953,6 +2085,9 @@ impl StorageBackend for InMemoryStorage {
                 avg_duration,
                 max_duration,
                 min_duration,
+                ingest_lag_p50: ingest_lag.p50,
+                ingest_lag_p95: ingest_lag.p95,
+                ingest_lag_p99: ingest_lag.p99,
             });
         }
         Ok(metrics)
@@ -964,7 +2099,7 @@ impl StorageBackend for InMemoryStorage {
     }
 
     async fn enforce_limits(&self) -> Result<usize> {
-        let current_count = self.spans.len();
+        let current_count = self.memory_span_count();
         if current_count > self.max_spans {
             let to_remove = current_count - self.max_spans;
             Ok(self.evict_oldest_spans(to_remove).await)
@@ -986,101 +2121,53 @@ impl StorageBackend for InMemoryStorage {
         self.emergency_cleanup_internal().await
     }
 
+    async fn migrate_cold_spans(&self) -> Result<usize> {
+        // Spans past the archive cutoff go straight to disk
+        let archived = self.migrate_to_archive().await?;
+        Ok(archived + self.migrate_to_warm().await?)
+    }
+
+    async fn delete_traces(&self, trace_ids: &[TraceId]) -> Result<usize> {
+        self.purge_traces(trace_ids).await
+    }
+
     #[inline(always)]
     fn get_health(&self) -> StorageHealth {
         self.get_health_status()
     }
 
-    #[inline(always)]
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
+    fn longterm_stats(&self) -> Option<&LongTermStats> {
+        self.longterm_stats.as_deref()
     }
 
-    async fn list_recent_traces(
-        &self,
-        limit: usize,
-        service_filter: Option<&ServiceName>,
-    ) -> Result<Vec<TraceInfo>> {
-        let mut trace_infos = Vec::new();
-
-        // Collect trace information
-        for entry in self.traces.iter() {
-            let trace_id = entry.key().clone();
-            let span_ids = entry.value();
-
-            if span_ids.is_empty() {
-                continue;
-            }
-
-            // Get all spans for this trace
-            let mut spans = Vec::new();
-            let mut services = std::collections::HashSet::new();
-            let mut has_error = false;
-
-            for span_id in span_ids.iter() {
-                if let Some(span) = self.spans.get(span_id) {
-                    services.insert(span.service_name.clone());
-                    if span.status.is_error() {
-                        has_error = true;
-                    }
-                    spans.push(span.clone());
-                }
-            }
-
-            if spans.is_empty() {
-                continue;
-            }
-
-            // Find root span (no parent)
-            // SAFE: Already checked spans.is_empty() above
-            let root_span = spans
-                .iter()
-                .find(|s| s.parent_span_id.is_none())
-                .or_else(|| spans.first())
-                .expect("spans not empty");
-
-            // Apply service filter if provided
-            if let Some(filter) = service_filter {
-                if !services.contains(filter) {
-                    continue;
-                }
-            }
-
-            // Calculate total duration (from earliest start to latest end)
-            // SAFE: Already checked spans.is_empty() above
-            let min_start = spans
-                .iter()
-                .map(|s| s.start_time)
-                .min()
-                .expect("spans not empty");
-            let max_end = spans
-                .iter()
-                .map(|s| s.start_time + s.duration)
-                .max()
-                .expect("spans not empty");
-            let duration = max_end
-                .duration_since(min_start)
-                .unwrap_or_else(|_| Duration::ZERO);
+    fn record_rejected_spans(&self, count: usize) {
+        update_counter!(self.counters.spans_rejected, add count as u64);
+    }
 
-            trace_infos.push(TraceInfo {
-                trace_id,
-                root_service: root_span.service_name.clone(),
-                root_operation: root_span.operation_name.clone(),
-                span_count: spans.len(),
-                duration,
-                start_time: min_start,
-                has_error,
-                services: services.into_iter().collect(),
-            });
+    fn trace_evicted_at(&self, trace_id: &TraceId) -> Option<SystemTime> {
+        if self.traces.contains_key(trace_id) || self.compressed_batches.contains_key(trace_id) {
+            return None;
         }
+        self.evictions.evicted_at(trace_id)
+    }
 
-        // Sort by start time (most recent first)
-        trace_infos.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+    fn recently_evicted_traces(&self, limit: usize) -> Vec<EvictedTrace> {
+        self.evictions.recent(limit)
+    }
 
-        // Limit results
-        trace_infos.truncate(limit);
+    #[inline(always)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 
-        Ok(trace_infos)
+    async fn list_recent_traces(
+        &self,
+        limit: usize,
+        service_filter: Option<&ServiceName>,
+    ) -> Result<Vec<TraceInfo>> {
+        Ok(self.newest_traces(None, None, limit, |trace_id| {
+            self.recent_trace_info(trace_id, service_filter)
+        }))
     }
 
     async fn search_traces(&self, query: &str, limit: usize) -> Result<Vec<TraceInfo>> {
@@ -1135,41 +2222,53 @@ impl StorageBackend for InMemoryStorage {
         end_time: Option<u64>,
         limit: usize,
     ) -> Result<Vec<TraceInfo>> {
-        impl_search!(
-            self,
-            |spans: &Vec<Span>| {
-                spans.iter().all(|span| {
-                    // Apply filters
-                    if let Some(svc) = service {
-                        if span.service_name.as_str() != svc {
-                            return false;
-                        }
-                    }
-                    if let Some(start) = start_time {
-                        let span_time = span
-                            .start_time
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_nanos() as u64;
-                        if span_time < start {
-                            return false;
-                        }
-                    }
-                    if let Some(end) = end_time {
-                        let span_time = span
-                            .start_time
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_nanos() as u64;
-                        if span_time > end {
-                            return false;
-                        }
-                    }
-                    true
-                })
-            },
-            limit
-        )
+        let trace_info = |trace_id: &TraceId| {
+            let spans: Vec<Span> = self
+                .traces
+                .get(trace_id)?
+                .iter()
+                .filter_map(|id| self.spans.get(id).map(|s| s.clone()))
+                .collect();
+            if spans.is_empty() || !trace_matches(&spans, service, start_time, end_time) {
+                return None;
+            }
+            create_trace_info!(trace_id, spans)
+        };
+
+        // A closed range only visits the index buckets inside it
+        if let (Some(start), Some(end)) = (start_time, end_time) {
+            let at = |nanos| SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos);
+            let mut traces: Vec<TraceInfo> = self
+                .time_range_query(at(start), at(end))
+                .iter()
+                .filter_map(trace_info)
+                .collect();
+            traces.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+            traces.truncate(limit);
+            return Ok(traces);
+        }
+        Ok(self.newest_traces(start_time, end_time, limit, trace_info))
+    }
+
+    async fn get_traces_by_attribute(
+        &self,
+        key: &str,
+        value: &str,
+        limit: usize,
+    ) -> Result<Vec<TraceInfo>> {
+        let trace_ids: HashSet<TraceId> = self
+            .attribute_index
+            .lookup(key, value)
+            .iter()
+            .filter_map(|span_id| self.spans.get(span_id).map(|span| span.trace_id.clone()))
+            .collect();
+        let mut traces: Vec<TraceInfo> = trace_ids
+            .iter()
+            .filter_map(|trace_id| self.recent_trace_info(trace_id, None))
+            .collect();
+        traces.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        traces.truncate(limit);
+        Ok(traces)
     }
 
     async fn get_service_metrics_map(&self) -> Result<HashMap<ServiceName, ServiceMetrics>> {
@@ -1188,6 +2287,10 @@ impl StorageBackend for InMemoryStorage {
         attribute_key: Option<&str>,
         limit: usize,
     ) -> Result<Vec<Span>> {
+        if let Some(attr_key) = attribute_key {
+            return Ok(self.search_spans_indexed(query, service, attr_key, limit));
+        }
+
         let mut matching_spans = Vec::new();
         let query_lower = query.to_lowercase();
 
@@ -1210,13 +2313,6 @@ impl StorageBackend for InMemoryStorage {
             // Search in attributes
             if !match_found {
                 for (key, value) in &span.attributes {
-                    // Apply attribute key filter
-                    if let Some(attr_key) = attribute_key {
-                        if key != attr_key {
-                            continue;
-                        }
-                    }
-
                     if key.to_lowercase().contains(&query_lower)
                         || value.to_lowercase().contains(&query_lower)
                     {
@@ -1245,6 +2341,8 @@ impl StorageBackend for InMemoryStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::{Clock, Glob, MockClock};
+    use std::ops::RangeInclusive;
     use std::time::Duration;
 
     async fn create_test_span(trace_num: u32, span_num: u32, service: &str) -> Span {
@@ -1273,6 +2371,108 @@ mod tests {
         assert_eq!(retrieved.unwrap().span_id, span_id);
     }
 
+    #[tokio::test]
+    async fn test_time_range_query() {
+        let storage = InMemoryStorage::new(1000);
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        // Trace n starts n * 500ms after base; trace 3 also has a late span
+        for n in 0..6 {
+            let mut span = create_test_span(n, n, "api").await;
+            span.start_time = base + Duration::from_millis(u64::from(n) * 500);
+            storage.store_span(span).await.unwrap();
+        }
+        let mut late = create_test_span(3, 10, "api").await;
+        late.start_time = base + Duration::from_secs(60);
+        storage.store_span(late).await.unwrap();
+
+        let ids = |traces: Vec<TraceId>| -> Vec<String> {
+            traces.iter().map(|t| t.as_str().to_string()).collect()
+        };
+        let range = storage.time_range_query(
+            base + Duration::from_millis(700),
+            base + Duration::from_millis(1600),
+        );
+        let mut found = ids(range);
+        found.sort();
+        assert_eq!(found, vec!["trace_0002", "trace_0003"]);
+
+        // Any span in the range is enough
+        let late_range = storage
+            .time_range_query(base + Duration::from_secs(59), base + Duration::from_secs(61));
+        assert_eq!(ids(late_range), vec!["trace_0003"]);
+        assert!(storage
+            .time_range_query(base + Duration::from_secs(10), base + Duration::from_secs(20))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_traces_in_range_matches_scan() {
+        let storage = InMemoryStorage::new(1000);
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        // Two spans per trace, 400ms apart, alternating services
+        for trace in 0..30 {
+            for n in 0..2 {
+                let service = if trace % 2 == 0 { "api" } else { "db" };
+                let mut span = create_test_span(trace, trace * 2 + n, service).await;
+                span.start_time = base + Duration::from_millis(u64::from(trace * 300 + n * 400));
+                storage.store_span(span).await.unwrap();
+            }
+        }
+
+        let nanos = |ms: u64| {
+            (base + Duration::from_millis(ms))
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as u64
+        };
+        let ids = |traces: Vec<TraceInfo>| -> Vec<TraceId> {
+            traces.into_iter().map(|t| t.trace_id).collect()
+        };
+        for (start, end, service, limit) in [
+            (0, 9_000, None, 100),
+            (1_000, 4_100, None, 100),
+            (1_000, 4_100, Some("api"), 100),
+            (2_500, 6_000, None, 3),
+            (20_000, 30_000, None, 100),
+        ] {
+            let (start, end) = (Some(nanos(start)), Some(nanos(end)));
+            let listed = storage.list_traces(service, start, end, limit).await.unwrap();
+            let scanned = storage.scan_traces(service, start, end, limit).unwrap();
+            assert_eq!(ids(listed), ids(scanned));
+        }
+        // Only traces with both spans inside the range are listed
+        let listed = storage
+            .list_traces(None, Some(nanos(1_000)), Some(nanos(4_100)), 100)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_eviction_keeps_error_and_slow_traces() {
+        let storage = InMemoryStorage::new(1000);
+        // Oldest first; every fourth trace ends in an error, trace 1 is slow
+        for trace in 0..40 {
+            for n in 0..2 {
+                let mut span = create_test_span(trace, trace * 2 + n, "checkout").await;
+                if n == 1 && trace % 4 == 0 {
+                    span.status = crate::core::SpanStatus::Error("timeout".to_string());
+                }
+                if trace == 1 {
+                    span.duration = Duration::from_secs(5);
+                }
+                storage.store_span(span).await.unwrap();
+            }
+        }
+
+        assert_eq!(storage.evict_oldest_spans(58).await, 58);
+        assert_eq!(storage.spans.len(), 22);
+        for trace in (0..40).filter(|t| t % 4 == 0 || *t == 1) {
+            let trace_id = TraceId::new(format!("trace_{:04}", trace)).unwrap();
+            assert_eq!(storage.get_trace_spans(&trace_id).await.unwrap().len(), 2);
+        }
+    }
+
     #[tokio::test]
     async fn test_get_trace_spans() {
         let storage = InMemoryStorage::new(100);
@@ -1288,6 +2488,116 @@ mod tests {
         assert_eq!(spans.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_bulk_store_matches_single_store() {
+        let mut spans = Vec::new();
+        for i in 1..=30 {
+            let service = if i % 2 == 0 { "frontend" } else { "backend" };
+            let mut span = create_test_span(i, i, service).await;
+            span.trace_id = TraceId::new(format!("{:032x}", i % 3)).unwrap();
+            spans.push(span);
+        }
+        let single = InMemoryStorage::new(1000);
+        for span in spans.clone() {
+            single.store_span(span).await.unwrap();
+        }
+        let bulk = InMemoryStorage::new(1000);
+        assert_eq!(bulk.store_spans_bulk(spans).await.unwrap(), 30);
+
+        assert_eq!(bulk.get_span_count().await.unwrap(), 30);
+        assert_eq!(
+            bulk.counters.memory_bytes.load(Ordering::Relaxed),
+            single.counters.memory_bytes.load(Ordering::Relaxed)
+        );
+        for trace in 0..3 {
+            let trace_id = TraceId::new(format!("{:032x}", trace)).unwrap();
+            let ids = |spans: Vec<Span>| spans.into_iter().map(|s| s.span_id).collect::<Vec<_>>();
+            assert_eq!(
+                ids(bulk.get_trace_spans(&trace_id).await.unwrap()),
+                ids(single.get_trace_spans(&trace_id).await.unwrap())
+            );
+        }
+        let since = SystemTime::now() - Duration::from_secs(60);
+        let frontend = ServiceName::new("frontend".to_string()).unwrap();
+        let frontend_spans = bulk.get_service_spans(&frontend, since).await.unwrap();
+        assert_eq!(frontend_spans.len(), 15);
+        assert_eq!(bulk.list_services().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_store_rejects_batch_over_capacity() {
+        let storage = InMemoryStorage::new(5);
+        let mut spans = Vec::new();
+        for i in 1..=10 {
+            spans.push(create_test_span(i, i, "test-service").await);
+        }
+
+        let result = storage.store_spans_bulk(spans).await;
+        assert!(matches!(result, Err(crate::core::UrpoError::StorageFull { rejected: 10 })));
+        assert_eq!(storage.get_span_count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_traces_by_attribute() {
+        let storage = InMemoryStorage::new(100);
+        // (trace, span, request.id); trace 1 matches on its second span only
+        for (trace, span, request_id) in
+            [(1, 1, "r-1"), (1, 2, "r-9"), (2, 3, "r-2"), (3, 4, "r-9")]
+        {
+            let mut span = create_test_span(trace, span, "gateway").await;
+            span.attributes
+                .push(Arc::from("request.id"), Arc::from(request_id));
+            storage.store_span(span).await.unwrap();
+        }
+
+        let mut traces: Vec<String> = storage
+            .get_traces_by_attribute("request.id", "r-9", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|trace| trace.trace_id.as_str().to_string())
+            .collect();
+        traces.sort();
+        assert_eq!(traces, vec!["trace_0001", "trace_0003"]);
+        let newest = storage.get_traces_by_attribute("request.id", "r-9", 1).await;
+        assert_eq!(newest.unwrap().len(), 1);
+        assert!(storage
+            .get_traces_by_attribute("request.id", "r-404", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_trace_spans_corrects_clock_skew() {
+        let storage =
+            InMemoryStorage::new(100).with_clock_skew_correction(Duration::from_millis(500));
+        let parent = create_test_span(1, 1, "frontend").await;
+        let mut child = create_test_span(1, 2, "backend").await;
+        child.parent_span_id = Some(parent.span_id.clone());
+        // Backend clock 200ms behind, so the child starts before its parent
+        child.start_time = parent.start_time - Duration::from_millis(200);
+        child.duration = Duration::from_millis(50);
+        storage.store_span(parent.clone()).await.unwrap();
+        storage.store_span(child).await.unwrap();
+
+        let spans = storage.get_trace_spans(&parent.trace_id).await.unwrap();
+        assert_eq!(spans[0].span_id, parent.span_id);
+        assert_eq!(spans[1].start_time, parent.start_time + Duration::from_millis(25));
+        assert_eq!(spans[1].attributes.get(crate::core::CLOCK_SKEW_ATTRIBUTE), Some("225"));
+
+        // Stored spans keep their recorded timing
+        let mut stored = Vec::new();
+        storage
+            .visit_trace_spans(&parent.trace_id, &mut |span| {
+                stored.push(span.start_time);
+                true
+            })
+            .await
+            .unwrap();
+        assert_eq!(stored[0], parent.start_time - Duration::from_millis(200));
+    }
+
     #[tokio::test]
     async fn test_storage_limits() {
         let storage = InMemoryStorage::new(5); // Max 5 spans
@@ -1301,6 +2611,133 @@ mod tests {
         assert!(storage.spans.len() <= 5);
     }
 
+    #[tokio::test]
+    async fn test_attribute_search_matches_naive_scan() {
+        // 100 spans per service, so the later spans evict the earlier ones
+        let storage = InMemoryStorage::new(1000);
+        let services = ["cart", "checkout", "payments"];
+        for i in 1..=600u32 {
+            let mut span = create_test_span(i / 3, i, services[i as usize % 3]).await;
+            span.attributes
+                .push("customer.id".into(), format!("c{}", i % 7).into());
+            span.attributes
+                .push("region".into(), if i % 2 == 0 { "eu" } else { "us" }.into());
+            storage.store_span(span).await.unwrap();
+        }
+        assert!(storage.spans.len() < 600);
+
+        let naive = |key: &str, value: &str, service: Option<&str>| {
+            let mut ids: Vec<SpanId> = storage
+                .spans
+                .iter()
+                .filter(|s| s.attributes.get(key) == Some(value))
+                .filter(|s| service.map_or(true, |svc| s.service_name.as_str() == svc))
+                .map(|s| s.span_id.clone())
+                .collect();
+            ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            ids
+        };
+        for (key, value) in [("customer.id", "c3"), ("region", "eu"), ("region", "ap")] {
+            for service in [None, Some("checkout")] {
+                let mut found: Vec<SpanId> = storage
+                    .search_spans(value, service, Some(key), usize::MAX)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|s| s.span_id)
+                    .collect();
+                found.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                assert_eq!(found, naive(key, value, service), "{}={} in {:?}", key, value, service);
+            }
+        }
+
+        let limited = storage
+            .search_spans("eu", None, Some("region"), 5)
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 5);
+
+        // Evicted spans are gone from the index: 7 customer IDs + 2 regions
+        assert_eq!(storage.attribute_index.len(), 9);
+        storage.evict_oldest_spans(600).await;
+        assert!(storage.spans.is_empty());
+        assert!(storage.attribute_index.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_time_bucketed_listing_matches_naive_scan() {
+        // 100 spans per service, so the later spans evict the earlier ones
+        let storage = InMemoryStorage::new(1000);
+        let services = ["cart", "checkout", "payments"];
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for i in 1..=600u32 {
+            let mut span = create_test_span(i / 3, i, services[i as usize % 3]).await;
+            span.start_time = base + Duration::from_millis(u64::from(i) * 250);
+            storage.store_span(span).await.unwrap();
+        }
+        assert!(storage.spans.len() < 600);
+
+        let nanos = |secs: u64| (1_700_000_000 + secs) * 1_000_000_000;
+        let naive = |service: Option<&str>, start: Option<u64>, end: Option<u64>, limit| {
+            let mut traces: Vec<TraceInfo> = storage
+                .traces
+                .iter()
+                .filter_map(|entry| {
+                    let spans: Vec<Span> = entry
+                        .value()
+                        .iter()
+                        .filter_map(|id| storage.spans.get(id).map(|s| s.clone()))
+                        .collect();
+                    let in_range = spans.iter().all(|s| {
+                        let t = s.start_time.duration_since(SystemTime::UNIX_EPOCH).unwrap();
+                        let t = t.as_nanos() as u64;
+                        service.map_or(true, |svc| s.service_name.as_str() == svc)
+                            && start.map_or(true, |start| t >= start)
+                            && end.map_or(true, |end| t <= end)
+                    });
+                    if !in_range {
+                        return None;
+                    }
+                    create_trace_info!(entry.key(), spans)
+                })
+                .collect();
+            traces.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+            traces.truncate(limit);
+            traces.into_iter().map(|t| t.trace_id).collect::<Vec<_>>()
+        };
+
+        let cases = [
+            (None, None, None, 10),
+            (None, None, None, usize::MAX),
+            (None, Some(nanos(100)), Some(nanos(120)), 1000),
+            (None, Some(nanos(100)), Some(nanos(120)), 5),
+            (Some("cart"), Some(nanos(60)), None, 1000),
+            (None, Some(nanos(500)), None, 10),
+            (None, None, Some(nanos(10)), 10),
+        ];
+        for (service, start, end, limit) in cases {
+            let found: Vec<TraceId> = storage
+                .list_traces(service, start, end, limit)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|t| t.trace_id)
+                .collect();
+            let expected = naive(service, start, end, limit);
+            assert_eq!(found, expected, "{:?} {:?}..{:?} limit {}", service, start, end, limit);
+        }
+        assert_eq!(storage.list_traces(None, None, None, 10).await.unwrap().len(), 10);
+
+        let recent = storage.list_recent_traces(10, None).await.unwrap();
+        let recent: Vec<TraceId> = recent.into_iter().map(|t| t.trace_id).collect();
+        assert_eq!(recent, naive(None, None, None, 10));
+
+        // Evicted spans are gone from the time index
+        storage.evict_oldest_spans(600).await;
+        assert!(storage.time_index.is_empty());
+        assert!(storage.list_traces(None, None, None, 10).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_service_spans_time_filter() {
         let storage = InMemoryStorage::new(100);
@@ -1328,4 +2765,400 @@ mod tests {
             .unwrap();
         assert_eq!(spans.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_warm_trace_read_cache() {
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(100).with_clock(clock.shared());
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+
+        for i in 1..=3 {
+            let mut span = create_test_span(1, i, "test-service").await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+        // Too recent to compress until the clock moves past the threshold
+        storage.compress_old_spans().await.unwrap();
+        assert!(!storage.compressed_batches.contains_key(&trace_id));
+        clock.advance(Duration::from_secs(600));
+        storage.compress_old_spans().await.unwrap();
+        assert!(storage.compressed_batches.contains_key(&trace_id));
+
+        let decompressions = || {
+            storage
+                .compression_engine
+                .get_stats()
+                .decompression_operations
+        };
+
+        let first = storage.get_trace_spans(&trace_id).await.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(decompressions(), 1);
+
+        let second = storage.get_trace_spans(&trace_id).await.unwrap();
+        assert_eq!(decompressions(), 1, "second read should be served from the cache");
+        assert_eq!(
+            first.iter().map(|s| &s.span_id).collect::<Vec<_>>(),
+            second.iter().map(|s| &s.span_id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            first.iter().map(|s| &s.operation_name).collect::<Vec<_>>(),
+            second.iter().map(|s| &s.operation_name).collect::<Vec<_>>()
+        );
+
+        // Without a cache every read decompresses again
+        let uncached = storage.clone().with_warm_cache_capacity(0);
+        uncached.get_trace_spans(&trace_id).await.unwrap();
+        assert_eq!(decompressions(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_trace_fully_retrievable_after_warm_migration() {
+        async fn store(storage: &InMemoryStorage, clock: &MockClock, ids: RangeInclusive<u32>) {
+            for i in ids {
+                let mut span = create_test_span(1, i, "test-service").await;
+                span.start_time = clock.now();
+                storage.store_span(span).await.unwrap();
+            }
+        }
+
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(1000)
+            .with_clock(clock.shared())
+            .with_warm_after(Duration::from_secs(60));
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+
+        store(&storage, &clock, 1..=50).await;
+        let hot_memory = storage.counters.memory_bytes.load(Ordering::Relaxed);
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 50);
+        assert!(storage.spans.is_empty());
+
+        let stats = storage.get_detailed_stats().await;
+        assert_eq!((stats.span_count, stats.warm_span_count), (0, 50));
+        assert!(stats.warm_memory_saved_bytes > 0);
+        assert_eq!(stats.memory_bytes, stats.warm_memory_bytes);
+        assert!(stats.memory_bytes < hot_memory);
+
+        // Later spans of the trace join its warm batch instead of replacing it
+        store(&storage, &clock, 51..=60).await;
+        assert_eq!(storage.get_trace_spans(&trace_id).await.unwrap().len(), 60);
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(storage.migrate_to_warm().await.unwrap(), 10);
+        assert_eq!(storage.compressed_batches.len(), 1);
+        assert_eq!(storage.get_detailed_stats().await.warm_span_count, 60);
+
+        let spans = storage.get_trace_spans(&trace_id).await.unwrap();
+        let span_ids: HashSet<_> = spans.iter().map(|s| s.span_id.clone()).collect();
+        assert_eq!(span_ids.len(), 60);
+
+        assert_eq!(storage.delete_traces(&[trace_id]).await.unwrap(), 60);
+        let stats = storage.get_detailed_stats().await;
+        assert_eq!((stats.warm_span_count, stats.warm_memory_bytes), (0, 0));
+        assert_eq!(stats.memory_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_tier_migration_runs_on_timer() {
+        let clock = MockClock::default();
+        let mut storage = InMemoryStorage::new(100)
+            .with_clock(clock.shared())
+            .with_warm_after(Duration::from_secs(60));
+        for i in 1..=3 {
+            let mut span = create_test_span(1, i, "test-service").await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+        clock.advance(Duration::from_secs(120));
+
+        let handle = storage
+            .spawn_tier_migration(Duration::from_millis(10))
+            .unwrap();
+        for _ in 0..200 {
+            if storage.spans.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(storage.get_detailed_stats().await.warm_span_count, 3);
+
+        // The timer stops once the storage is dropped, even if its maps are shared
+        let spans = Arc::clone(&storage.spans);
+        drop(storage);
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Arc::strong_count(&spans), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_batch_kept_when_merge_fails() {
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(100)
+            .with_clock(clock.shared())
+            .with_warm_after(Duration::from_secs(60));
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+        let mut span = create_test_span(1, 1, "test-service").await;
+        span.start_time = clock.now();
+        storage.store_span(span).await.unwrap();
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(storage.migrate_to_warm().await.unwrap(), 1);
+
+        // Corrupt the earlier batch, then age a new span of the same trace
+        storage.invalidate_warm_trace(&trace_id);
+        storage.compressed_batches.get_mut(&trace_id).unwrap().data =
+            bytes::Bytes::from_static(&[4, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        let mut late = create_test_span(1, 2, "test-service").await;
+        late.start_time = clock.now();
+        storage.store_span(late).await.unwrap();
+        clock.advance(Duration::from_secs(120));
+
+        assert!(storage.migrate_to_warm().await.is_err());
+        let batch = storage.compressed_batches.get(&trace_id).unwrap();
+        assert_eq!((batch.span_count, batch.data.len()), (1, 8));
+        drop(batch);
+        let hot = SpanId::new("span_0002".to_string()).unwrap();
+        assert!(storage.get_span(&hot).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_warm_tier_counts_toward_limits_and_expires() {
+        let clock = MockClock::default();
+        let mut config = CleanupConfig::default();
+        config.retention_period = Duration::from_secs(3600);
+        config.retention_overrides =
+            HashMap::from([(Glob::new("health-*"), Duration::from_secs(300))]);
+        // Only the calls below clean up, not storing
+        config.cleanup_interval = Duration::from_secs(24 * 3600);
+        let storage = InMemoryStorage::with_cleanup_config(100, config).with_clock(clock.shared());
+        let trace = |i: u32| TraceId::new(format!("trace_{:04}", i)).unwrap();
+
+        for (i, service) in [(1, "orders"), (2, "health-check"), (3, "orders")] {
+            for j in 0..2 {
+                let mut span = create_test_span(i, i * 10 + j, service).await;
+                span.start_time = clock.now();
+                storage.store_span(span).await.unwrap();
+            }
+            clock.advance(Duration::from_secs(10));
+        }
+        clock.advance(Duration::from_secs(600));
+        assert_eq!(storage.migrate_to_warm().await.unwrap(), 6);
+        for j in 0..2 {
+            let mut span = create_test_span(4, 40 + j, "orders").await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+        assert_eq!(storage.memory_span_count(), 8);
+        assert_eq!(storage.enforce_limits().await.unwrap(), 0);
+
+        // The oldest warm batch goes before any hot span
+        assert_eq!(storage.evict_oldest_spans(2).await, 2);
+        assert!(!storage.compressed_batches.contains_key(&trace(1)));
+        assert!(storage.trace_evicted_at(&trace(1)).is_some());
+        assert_eq!(storage.spans.len(), 2);
+
+        // Warm batches follow the retention of their services
+        assert_eq!(storage.cleanup_expired_spans(clock.now()).await, 2);
+        assert!(!storage.compressed_batches.contains_key(&trace(2)));
+        assert!(storage.compressed_batches.contains_key(&trace(3)));
+
+        clock.advance(Duration::from_secs(3601));
+        assert_eq!(storage.cleanup_expired_spans(clock.now()).await, 4);
+        assert!(storage.compressed_batches.is_empty());
+        assert_eq!(storage.memory_span_count(), 0);
+        let stats = storage.get_detailed_stats().await;
+        assert_eq!((stats.warm_span_count, stats.warm_memory_bytes), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_migrated_spans_served_from_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Arc::new(SpanArchive::open(dir.path()).unwrap());
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(100)
+            .with_clock(clock.shared())
+            .with_archive(archive.clone(), Duration::from_secs(60));
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+
+        // Two spans that will turn cold, then one ten minutes later
+        for i in 1..=3 {
+            if i == 3 {
+                clock.advance(Duration::from_secs(600));
+            }
+            let mut span = create_test_span(1, i, "test-service").await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 2);
+        assert_eq!(storage.spans.len(), 1);
+        assert_eq!(archive.span_count(), 2);
+
+        let spans = storage.get_trace_spans(&trace_id).await.unwrap();
+        assert_eq!(spans.len(), 3);
+        assert!(spans.windows(2).all(|w| w[0].start_time <= w[1].start_time));
+
+        // Nothing left to migrate until the last span ages too
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 0);
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 1);
+        assert!(storage.spans.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purge_traces_from_memory_and_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Arc::new(SpanArchive::open(dir.path()).unwrap());
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(100)
+            .with_clock(clock.shared())
+            .with_archive(Arc::clone(&archive), Duration::from_secs(60));
+
+        // Trace 1 gets an archived span and a hot one, trace 2 stays untouched
+        for (trace, span_id) in [(1, 1), (2, 2)] {
+            let mut span = create_test_span(trace, span_id, "checkout").await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+        clock.advance(Duration::from_secs(600));
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 2);
+        let archived_memory = storage.counters.memory_bytes.load(Ordering::Relaxed);
+        let mut span = create_test_span(1, 3, "payments").await;
+        span.start_time = clock.now();
+        storage.store_span(span).await.unwrap();
+
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+        assert_eq!(storage.delete_traces(&[trace_id.clone()]).await.unwrap(), 2);
+
+        assert!(storage.get_trace_spans(&trace_id).await.unwrap().is_empty());
+        assert!(!archive.contains_trace(&trace_id));
+        assert_eq!(archive.span_count(), 1);
+        let payments = ServiceName::new("payments".to_string()).unwrap();
+        assert!(!storage.services.contains_key(&payments));
+        assert_eq!(storage.counters.memory_bytes.load(Ordering::Relaxed), archived_memory);
+        assert_eq!(storage.trace_evicted_at(&trace_id), None);
+
+        let kept = TraceId::new("trace_0002".to_string()).unwrap();
+        assert_eq!(storage.get_trace_spans(&kept).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_interval_follows_clock() {
+        let clock = MockClock::default();
+        let storage = InMemoryStorage::new(100).with_clock(clock.shared());
+        let interval = storage.cleanup_config.cleanup_interval;
+
+        assert!(!storage.should_cleanup().await);
+        clock.advance(interval - Duration::from_secs(1));
+        assert!(!storage.should_cleanup().await);
+        clock.advance(Duration::from_secs(1));
+        assert!(storage.should_cleanup().await);
+    }
+
+    #[tokio::test]
+    async fn test_processing_rate_covers_recent_window() {
+        let clock = MockClock::default();
+        let mut config = CleanupConfig::default();
+        config.rate_window = Duration::from_secs(10);
+        let storage = InMemoryStorage::with_cleanup_config(1000, config).with_clock(clock.shared());
+
+        for i in 0..50 {
+            storage
+                .store_span(create_test_span(i, i, "api").await)
+                .await
+                .unwrap();
+            clock.advance(Duration::from_millis(20));
+        }
+        let rate = storage.get_detailed_stats().await.processing_rate;
+        assert!((rate - 50.0).abs() < 1.0, "rate {}", rate);
+
+        // A long idle period no longer averages in the early burst
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(storage.get_detailed_stats().await.processing_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_applies_per_service_retention() {
+        let clock = MockClock::default();
+        let mut config = CleanupConfig::default();
+        config.retention_period = Duration::from_secs(3600);
+        config.retention_overrides = HashMap::from([
+            (Glob::new("payment-*"), Duration::from_secs(7 * 24 * 3600)),
+            (Glob::new("health-*"), Duration::from_secs(300)),
+        ]);
+        let storage = InMemoryStorage::with_cleanup_config(1000, config).with_clock(clock.shared());
+
+        for (i, service) in [(1, "health-check"), (2, "payment-api"), (3, "orders")] {
+            let mut span = create_test_span(i, i, service).await;
+            span.start_time = clock.now();
+            storage.store_span(span).await.unwrap();
+        }
+        let stored = |i: u32| {
+            let id = SpanId::new(format!("span_{:04}", i)).unwrap();
+            storage.spans.contains_key(&id)
+        };
+
+        clock.advance(Duration::from_secs(301));
+        assert_eq!(storage.cleanup_expired_spans(clock.now()).await, 1);
+        assert!(!stored(1) && stored(2) && stored(3));
+
+        // Kept spans are looked at again on the next run
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(storage.cleanup_expired_spans(clock.now()).await, 1);
+        assert!(stored(2) && !stored(3));
+
+        clock.advance(Duration::from_secs(7 * 24 * 3600));
+        assert_eq!(storage.cleanup_expired_spans(clock.now()).await, 1);
+        assert!(storage.spans.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_visit_trace_spans_in_start_time_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Arc::new(SpanArchive::open(dir.path()).unwrap());
+        let storage = InMemoryStorage::new(100).with_archive(archive, Duration::from_secs(60));
+        let trace_id = TraceId::new("trace_0001".to_string()).unwrap();
+        let now = SystemTime::now();
+
+        // Stored newest first; spans 1 and 2 go to the archive
+        for (i, age) in [(4, 5), (3, 30), (2, 300), (1, 600)] {
+            let mut span = create_test_span(1, i, "test-service").await;
+            span.start_time = now - Duration::from_secs(age);
+            storage.store_span(span).await.unwrap();
+        }
+        assert_eq!(storage.migrate_cold_spans().await.unwrap(), 2);
+
+        let mut visited = Vec::new();
+        storage
+            .visit_trace_spans(&trace_id, &mut |span| {
+                visited.push(span.span_id.clone());
+                true
+            })
+            .await
+            .unwrap();
+        let expected: Vec<SpanId> = storage
+            .get_trace_spans(&trace_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.span_id)
+            .collect();
+        assert_eq!(visited.len(), 4);
+        assert_eq!(visited, expected);
+        let mut sorted = visited.clone();
+        sorted.sort_by_key(|id| id.as_str().to_string());
+        assert_eq!(visited, sorted);
+
+        // Returning false stops the walk
+        let mut seen = 0;
+        storage
+            .visit_trace_spans(&trace_id, &mut |_| {
+                seen += 1;
+                seen < 3
+            })
+            .await
+            .unwrap();
+        assert_eq!(seen, 3);
+    }
 }
@@ -5,20 +5,35 @@
 //!
 //! We keep only the high-performance components:
 //! - memory.rs: Main in-memory storage implementation
+//! - persistent.rs: Disk-backed storage that survives restarts
+//! - archive.rs: Append-only disk archive for cold spans
+//! - evictions.rs: Breadcrumbs for recently evicted traces
+//! - ingest_lag.rs: Per-service delay between span end and storage
+//! - longterm.rs: Hourly per-service statistics kept across restarts
 //! - compression.rs: 5-10x memory savings
 //! - simd_search.rs: 4x search speedup with SIMD
 //! - zero_alloc_pool.rs: 6.3x performance boost with object pooling
 //! - span_pool.rs: Integrated span pooling
 
-use crate::core::Config;
+use crate::core::{Config, StorageBackendKind};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 // Core modules
+pub mod archive;
+pub mod attribute_index;
 pub mod backend;
 pub mod cleanup_logic;
+pub mod eviction_order;
+pub mod evictions;
+pub mod ingest_lag;
+pub mod longterm;
 pub mod memory;
+pub mod persistent;
+pub mod snapshot;
+pub mod time_index;
 pub mod types;
+pub mod wal;
 
 // Performance modules
 pub mod compression;
@@ -27,14 +42,39 @@ pub mod span_pool;
 pub mod zero_alloc_pool;
 
 // Re-export commonly used types
+pub use archive::{ArchiveBlock, ArchiveReader, SpanArchive};
+pub use attribute_index::AttributeIndex;
 pub use backend::StorageBackend;
 pub use cleanup_logic::CleanupConfig;
 pub use compression::{CompressedSpanBatch, CompressionEngine, CompressionLevel, CompressionStats};
+pub use eviction_order::EvictionOrder;
+pub use evictions::{EvictedTrace, EvictionLog};
+pub use ingest_lag::{IngestLag, IngestLagTracker};
+pub use longterm::{HourlyStats, LongTermStats};
 pub use memory::InMemoryStorage;
+pub use persistent::PersistentStorage;
+pub use snapshot::SpanSnapshot;
 pub use span_pool::{PooledSpan, SpanPool, GLOBAL_SPAN_POOL};
-pub use types::{StorageHealth, StorageStats, TraceInfo};
+pub use time_index::TimeBucketIndex;
+pub use types::{
+    ServiceFootprint, SlidingWindowCounter, StorageHealth, StorageStats, TraceFootprint, TraceInfo,
+};
+pub use wal::WriteAheadLog;
 pub use zero_alloc_pool::{PoolStats, ZeroAllocSpanPool};
 
+/// Shared handle to a storage backend.
+pub type SharedBackend = Arc<RwLock<dyn StorageBackend>>;
+
+/// Build the backend selected by `storage.backend`.
+pub fn backend_from_config(config: &Config) -> crate::core::Result<SharedBackend> {
+    Ok(match config.storage.backend_kind() {
+        StorageBackendKind::Memory => Arc::new(RwLock::new(InMemoryStorage::with_config(config))),
+        StorageBackendKind::Persistent => {
+            Arc::new(RwLock::new(PersistentStorage::with_config(config)?))
+        },
+    })
+}
+
 /// Unified storage interface that wraps the actual implementation
 pub struct UnifiedStorage {
     inner: Arc<RwLock<dyn StorageBackend>>,
@@ -49,12 +89,22 @@ impl UnifiedStorage {
         }
     }
 
-    /// Create a new unified storage from configuration
+    /// Create a new unified storage with the configured backend
+    pub fn open(config: &Config) -> crate::core::Result<Self> {
+        Ok(Self {
+            inner: backend_from_config(config)?,
+        })
+    }
+
+    /// Create a new unified storage from configuration, falling back to
+    /// in-memory storage if the configured backend cannot be opened
     pub fn from_config(config: &Config) -> Self {
-        let storage = InMemoryStorage::with_config(config);
-        Self {
-            inner: Arc::new(RwLock::new(storage)),
-        }
+        Self::open(config).unwrap_or_else(|e| {
+            tracing::error!("Falling back to in-memory storage: {}", e);
+            Self {
+                inner: Arc::new(RwLock::new(InMemoryStorage::with_config(config))),
+            }
+        })
     }
 
     /// Get the inner storage backend
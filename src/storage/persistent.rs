@@ -0,0 +1,1102 @@
+//! Disk-backed storage backend that survives restarts.
+//!
+//! Spans are buffered in memory and flushed as compressed blocks (the
+//! [archive](super::archive) block format) into numbered segment files:
+//!
+//! ```text
+//! <dir>/segment-0000000000000001.uarc
+//! <dir>/segment-0000000000000002.uarc
+//! ```
+//!
+//! The newest segment is appended to until it holds `segment_spans` spans.
+//! Retention deletes whole segments once their newest span is past the
+//! retention period. Each segment has a trace, span and service index beside
+//! it (`segment-<id>.uidx`), saved when the segment is sealed and when the
+//! storage is dropped. Reopening loads the indexes; only a segment whose
+//! index is missing or stale (e.g. after a crash) is decompressed to rebuild
+//! it. Writing and deleting segments runs on the blocking thread pool.
+
+use super::archive::{ArchiveTraceIndex, SpanArchive};
+use super::cleanup_logic::{estimate_span_memory, CleanupConfig, StorageCounters};
+use super::evictions::{EvictedTrace, EvictionLog};
+use super::ingest_lag::{IngestLag, IngestLagTracker};
+use super::{StorageBackend, StorageHealth, StorageStats, TraceInfo};
+use crate::core::{
+    system_clock, ClockSkewAdjuster, Config, Result, ServiceMetrics, ServiceName, SharedClock,
+    Span, SpanId, TraceId, UrpoError,
+};
+use crate::update_counter;
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Spans buffered in memory before they are written to disk.
+pub const DEFAULT_FLUSH_SPANS: usize = 512;
+
+/// Spans written to one segment file before a new one is started.
+pub const DEFAULT_SEGMENT_SPANS: usize = 50_000;
+
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_EXTENSION: &str = "uarc";
+const SEGMENT_INDEX_EXTENSION: &str = "uidx";
+
+/// One segment file.
+struct Segment {
+    id: u64,
+    archive: SpanArchive,
+}
+
+impl Segment {
+    fn path(dir: &Path, id: u64) -> PathBuf {
+        dir.join(format!("{}{:016}.{}", SEGMENT_PREFIX, id, SEGMENT_EXTENSION))
+    }
+
+    fn parse_id(path: &Path) -> Option<u64> {
+        if path.extension()? != SEGMENT_EXTENSION {
+            return None;
+        }
+        path.file_stem()?
+            .to_str()?
+            .strip_prefix(SEGMENT_PREFIX)?
+            .parse()
+            .ok()
+    }
+
+    /// Open segment `id` with its saved index, rebuilding and saving the
+    /// index from the segment's spans if it is missing or stale.
+    fn open(dir: &Path, id: u64) -> Result<(Self, SegmentIndex)> {
+        let path = Self::path(dir, id);
+        let index_path = SegmentIndex::path(dir, id);
+        if let Some(mut index) = SegmentIndex::load(&index_path) {
+            let saved = std::mem::take(&mut index.archive);
+            if let Some(archive) = SpanArchive::open_file_indexed(&path, saved)? {
+                return Ok((Self { id, archive }, index));
+            }
+        }
+
+        let segment = Self {
+            id,
+            archive: SpanArchive::open_file(&path)?,
+        };
+        let mut index = SegmentIndex::default();
+        for span in segment.spans()? {
+            index.add(&span);
+        }
+        index.save(&index_path, &segment.archive)?;
+        tracing::info!("Rebuilt index of segment {}", path.display());
+        Ok((segment, index))
+    }
+
+    /// All spans of the segment, oldest first.
+    fn spans(&self) -> Result<Vec<Span>> {
+        self.archive.reader()?.all_spans()
+    }
+}
+
+/// Traces, spans and services of one segment, saved beside it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SegmentIndex {
+    /// Trace index of the segment file, filled in when saved
+    archive: ArchiveTraceIndex,
+    traces: HashMap<TraceId, TraceSummary>,
+    spans: HashMap<SpanId, TraceId>,
+    /// Service to the start time of its newest span
+    services: HashMap<ServiceName, SystemTime>,
+}
+
+impl SegmentIndex {
+    fn path(dir: &Path, id: u64) -> PathBuf {
+        dir.join(format!("{}{:016}.{}", SEGMENT_PREFIX, id, SEGMENT_INDEX_EXTENSION))
+    }
+
+    /// Saved index at `path`, or `None` if it is missing or unreadable.
+    fn load(path: &Path) -> Option<Self> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!("Cannot read segment index {}: {}", path.display(), e);
+                return None;
+            },
+        };
+        match bincode::deserialize(&bytes) {
+            Ok(index) => Some(index),
+            Err(e) => {
+                tracing::warn!("Ignoring corrupt segment index {}: {}", path.display(), e);
+                None
+            },
+        }
+    }
+
+    /// Save the index of `archive` to `path`, replacing it atomically.
+    fn save(&mut self, path: &Path, archive: &SpanArchive) -> Result<()> {
+        self.archive = archive.trace_index();
+        let bytes = bincode::serialize(self).map_err(|e| {
+            UrpoError::storage(format!("Segment index serialization failed: {}", e))
+        })?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn add(&mut self, span: &Span) {
+        self.traces
+            .entry(span.trace_id.clone())
+            .and_modify(|summary| summary.add(span))
+            .or_insert_with(|| TraceSummary::new(span));
+        self.spans
+            .insert(span.span_id.clone(), span.trace_id.clone());
+        self.services
+            .entry(span.service_name.clone())
+            .and_modify(|last| *last = (*last).max(span.start_time))
+            .or_insert(span.start_time);
+    }
+}
+
+/// In-memory summary of a stored trace, enough to list it without disk reads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TraceSummary {
+    root_service: ServiceName,
+    root_operation: String,
+    has_root: bool,
+    start_time: SystemTime,
+    end_time: SystemTime,
+    span_count: usize,
+    has_error: bool,
+    services: Vec<ServiceName>,
+}
+
+impl TraceSummary {
+    fn new(span: &Span) -> Self {
+        Self {
+            root_service: span.service_name.clone(),
+            root_operation: span.operation_name.clone(),
+            has_root: span.parent_span_id.is_none(),
+            start_time: span.start_time,
+            end_time: span.start_time + span.duration,
+            span_count: 1,
+            has_error: span.is_error(),
+            services: vec![span.service_name.clone()],
+        }
+    }
+
+    fn add(&mut self, span: &Span) {
+        if !self.has_root && span.parent_span_id.is_none() {
+            self.root_service = span.service_name.clone();
+            self.root_operation = span.operation_name.clone();
+            self.has_root = true;
+        }
+        self.start_time = self.start_time.min(span.start_time);
+        self.end_time = self.end_time.max(span.start_time + span.duration);
+        self.span_count += 1;
+        self.has_error |= span.is_error();
+        if !self.services.contains(&span.service_name) {
+            self.services.push(span.service_name.clone());
+        }
+    }
+
+    /// Fold in the summary of the same trace from another segment.
+    fn merge(&mut self, other: &TraceSummary) {
+        if !self.has_root && other.has_root {
+            self.root_service = other.root_service.clone();
+            self.root_operation = other.root_operation.clone();
+            self.has_root = true;
+        }
+        self.start_time = self.start_time.min(other.start_time);
+        self.end_time = self.end_time.max(other.end_time);
+        self.span_count += other.span_count;
+        self.has_error |= other.has_error;
+        for service in &other.services {
+            if !self.services.contains(service) {
+                self.services.push(service.clone());
+            }
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        self.end_time
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+    }
+
+    fn to_info(&self, trace_id: &TraceId) -> TraceInfo {
+        TraceInfo {
+            trace_id: trace_id.clone(),
+            root_service: self.root_service.clone(),
+            root_operation: self.root_operation.clone(),
+            span_count: self.span_count,
+            duration: self.duration(),
+            start_time: self.start_time,
+            has_error: self.has_error,
+            services: self.services.clone(),
+        }
+    }
+}
+
+/// Storage backend persisting spans to segment files on disk.
+///
+/// State is shared behind `Arc`s so disk work can run on the blocking
+/// thread pool through a [`handle`](Self::handle) to the same storage.
+pub struct PersistentStorage {
+    dir: PathBuf,
+    /// Segments, oldest first. The last one is appended to.
+    segments: Arc<RwLock<Vec<Segment>>>,
+    /// Index of the newest segment, saved when it is sealed or dropped.
+    active_index: Arc<Mutex<SegmentIndex>>,
+    /// Spans not yet flushed to a segment.
+    pending: Arc<Mutex<Vec<Span>>>,
+    traces: Arc<DashMap<TraceId, TraceSummary>>,
+    span_traces: Arc<DashMap<SpanId, TraceId>>,
+    /// Service to the start time of its newest span.
+    services: Arc<DashMap<ServiceName, SystemTime>>,
+    cleanup_config: CleanupConfig,
+    max_spans: usize,
+    flush_spans: usize,
+    segment_spans: usize,
+    counters: Arc<StorageCounters>,
+    last_cleanup: Arc<Mutex<Option<SystemTime>>>,
+    evictions: Arc<EvictionLog>,
+    /// Ingest lag of spans stored since the storage was opened.
+    ingest_lag: Arc<IngestLagTracker>,
+    /// Clock skew correction applied to traces as they are read.
+    clock_skew: Option<ClockSkewAdjuster>,
+    clock: SharedClock,
+    /// When the storage was opened, by `clock`.
+    opened_at: SystemTime,
+    /// False for handles, which leave the final flush to the storage.
+    owner: bool,
+}
+
+impl PersistentStorage {
+    /// Open (or create) the storage in `dir`, loading the segment indexes.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            if let Some(id) = Segment::parse_id(&entry?.path()) {
+                ids.push(id);
+            }
+        }
+        ids.sort_unstable();
+
+        let clock = system_clock();
+        let storage = Self {
+            dir,
+            segments: Arc::new(RwLock::new(Vec::new())),
+            active_index: Arc::new(Mutex::new(SegmentIndex::default())),
+            pending: Arc::new(Mutex::new(Vec::new())),
+            traces: Arc::new(DashMap::new()),
+            span_traces: Arc::new(DashMap::new()),
+            services: Arc::new(DashMap::new()),
+            cleanup_config: CleanupConfig::default(),
+            max_spans: usize::MAX,
+            flush_spans: DEFAULT_FLUSH_SPANS,
+            segment_spans: DEFAULT_SEGMENT_SPANS,
+            counters: Arc::new(StorageCounters::default()),
+            last_cleanup: Arc::new(Mutex::new(None)),
+            evictions: Arc::new(EvictionLog::default()),
+            ingest_lag: Arc::new(IngestLagTracker::default()),
+            clock_skew: None,
+            opened_at: clock.now(),
+            clock,
+            owner: true,
+        };
+
+        let mut segments = Vec::with_capacity(ids.len());
+        let mut active_index = SegmentIndex::default();
+        for id in ids {
+            let (segment, index) = Segment::open(&storage.dir, id)?;
+            storage.merge_index(&index);
+            segments.push(segment);
+            active_index = index;
+        }
+        *storage.active_index.lock() = active_index;
+        tracing::info!(
+            "Opened persistent storage {} with {} segments, {} traces",
+            storage.dir.display(),
+            segments.len(),
+            storage.traces.len()
+        );
+        *storage.segments.write() = segments;
+        Ok(storage)
+    }
+
+    /// Open the storage under `<data_dir>/spans` with the configured limits.
+    pub fn with_config(config: &Config) -> Result<Self> {
+        let mut storage = Self::open(config.storage.persistent_dir())?
+            .with_cleanup_config(CleanupConfig::from_storage_config(&config.storage))
+            .with_max_spans(config.storage.max_spans);
+        if let Some(max_skew) = config.storage.max_clock_skew {
+            storage = storage.with_clock_skew_correction(max_skew);
+        }
+        Ok(storage)
+    }
+
+    /// Read the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.opened_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Set the retention settings.
+    pub fn with_cleanup_config(mut self, cleanup_config: CleanupConfig) -> Self {
+        self.cleanup_config = cleanup_config;
+        self
+    }
+
+    /// Delete the oldest segments once more than `max_spans` are stored.
+    pub fn with_max_spans(mut self, max_spans: usize) -> Self {
+        self.max_spans = max_spans;
+        self
+    }
+
+    /// Write buffered spans to disk once `spans` have accumulated.
+    pub fn with_flush_spans(mut self, spans: usize) -> Self {
+        self.flush_spans = spans.max(1);
+        self
+    }
+
+    /// Correct clock skew of up to `max_skew` between services in the spans
+    /// returned by `get_trace_spans`.
+    pub fn with_clock_skew_correction(mut self, max_skew: Duration) -> Self {
+        self.clock_skew = Some(ClockSkewAdjuster::new(max_skew));
+        self
+    }
+
+    /// Start a new segment file after `spans` spans.
+    pub fn with_segment_spans(mut self, spans: usize) -> Self {
+        self.segment_spans = spans.max(1);
+        self
+    }
+
+    /// Directory holding the segment files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of segment files.
+    pub fn segment_count(&self) -> usize {
+        self.segments.read().len()
+    }
+
+    /// Another handle to the same storage, for disk work on the blocking
+    /// thread pool.
+    fn handle(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            segments: Arc::clone(&self.segments),
+            active_index: Arc::clone(&self.active_index),
+            pending: Arc::clone(&self.pending),
+            traces: Arc::clone(&self.traces),
+            span_traces: Arc::clone(&self.span_traces),
+            services: Arc::clone(&self.services),
+            cleanup_config: self.cleanup_config.clone(),
+            max_spans: self.max_spans,
+            flush_spans: self.flush_spans,
+            segment_spans: self.segment_spans,
+            counters: Arc::clone(&self.counters),
+            last_cleanup: Arc::clone(&self.last_cleanup),
+            evictions: Arc::clone(&self.evictions),
+            ingest_lag: Arc::clone(&self.ingest_lag),
+            clock_skew: self.clock_skew,
+            clock: Arc::clone(&self.clock),
+            opened_at: self.opened_at,
+            owner: false,
+        }
+    }
+
+    /// Run `work` on the blocking thread pool: compression, file writes and
+    /// deletes must not stall the async executor.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        work: impl FnOnce(&Self) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let storage = self.handle();
+        tokio::task::spawn_blocking(move || work(&storage))
+            .await
+            .map_err(|e| UrpoError::storage(format!("Persistent storage task failed: {}", e)))?
+    }
+
+    /// Save the index of `segment`. A failure is only logged: the index is
+    /// rebuilt from the segment when the storage is reopened.
+    fn save_index(&self, segment: &Segment, index: &mut SegmentIndex) {
+        let path = SegmentIndex::path(&self.dir, segment.id);
+        if let Err(e) = index.save(&path, &segment.archive) {
+            tracing::warn!("Failed to save segment index {}: {}", path.display(), e);
+        }
+    }
+
+    /// Write buffered spans to the newest segment.
+    pub fn flush(&self) -> Result<()> {
+        let spans = std::mem::take(&mut *self.pending.lock());
+        if spans.is_empty() {
+            return Ok(());
+        }
+
+        let mut segments = self.segments.write();
+        let needs_segment = segments
+            .last()
+            .map_or(true, |s| s.archive.span_count() >= self.segment_spans);
+        let written = if needs_segment {
+            // The current segment is sealed: save its index for reopening
+            if let Some(sealed) = segments.last() {
+                self.save_index(sealed, &mut self.active_index.lock());
+            }
+            let id = segments.last().map_or(1, |s| s.id + 1);
+            SpanArchive::open_file(Segment::path(&self.dir, id)).and_then(|archive| {
+                segments.push(Segment { id, archive });
+                *self.active_index.lock() = SegmentIndex::default();
+                segments
+                    .last()
+                    .expect("segment just pushed")
+                    .archive
+                    .append(&spans)
+            })
+        } else {
+            segments
+                .last()
+                .expect("segment exists")
+                .archive
+                .append(&spans)
+        };
+
+        if let Err(e) = written {
+            // Keep the spans so the next flush retries them
+            let mut pending = self.pending.lock();
+            let newer = std::mem::replace(&mut *pending, spans);
+            pending.extend(newer);
+            return Err(e);
+        }
+        let mut index = self.active_index.lock();
+        for span in &spans {
+            index.add(span);
+        }
+        Ok(())
+    }
+
+    /// Add the traces, spans and services of a saved segment index.
+    fn merge_index(&self, index: &SegmentIndex) {
+        for (trace_id, summary) in &index.traces {
+            self.traces
+                .entry(trace_id.clone())
+                .and_modify(|stored| stored.merge(summary))
+                .or_insert_with(|| summary.clone());
+        }
+        for (span_id, trace_id) in &index.spans {
+            self.span_traces.insert(span_id.clone(), trace_id.clone());
+        }
+        for (service, newest) in &index.services {
+            self.services
+                .entry(service.clone())
+                .and_modify(|last| *last = (*last).max(*newest))
+                .or_insert(*newest);
+        }
+    }
+
+    fn index_span(&self, span: &Span) {
+        self.traces
+            .entry(span.trace_id.clone())
+            .and_modify(|summary| summary.add(span))
+            .or_insert_with(|| TraceSummary::new(span));
+        self.span_traces
+            .insert(span.span_id.clone(), span.trace_id.clone());
+        self.services
+            .entry(span.service_name.clone())
+            .and_modify(|last| *last = (*last).max(span.start_time))
+            .or_insert(span.start_time);
+    }
+
+    /// Visit stored spans newest segment first until `visit` returns false.
+    fn scan_spans(&self, mut visit: impl FnMut(&Span) -> bool) -> Result<()> {
+        for span in self.pending.lock().iter().rev() {
+            if !visit(span) {
+                return Ok(());
+            }
+        }
+        let segments = self.segments.read();
+        for segment in segments.iter().rev() {
+            for span in segment.spans()?.iter().rev() {
+                if !visit(span) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn stored_span_count(&self) -> usize {
+        let on_disk: usize = self
+            .segments
+            .read()
+            .iter()
+            .map(|s| s.archive.span_count())
+            .sum();
+        on_disk + self.pending.lock().len()
+    }
+
+    /// Delete segments past retention, then the oldest ones over `max_spans`.
+    fn delete_old_segments(&self) -> Result<usize> {
+        let now = self.clock.now();
+        let cutoff = now
+            .checked_sub(self.cleanup_config.retention_period)
+            .unwrap_or(UNIX_EPOCH);
+        let mut total = self.stored_span_count();
+        *self.last_cleanup.lock() = Some(now);
+
+        let removed: Vec<Segment> = {
+            let mut segments = self.segments.write();
+            let mut expired = 0;
+            for segment in segments.iter() {
+                let past_retention = segment
+                    .archive
+                    .newest_start()
+                    .map_or(true, |newest| newest < cutoff);
+                let over_limit = total > self.max_spans && expired + 1 < segments.len();
+                if !(past_retention || over_limit) {
+                    break;
+                }
+                total -= segment.archive.span_count();
+                expired += 1;
+            }
+            if expired == segments.len() {
+                *self.active_index.lock() = SegmentIndex::default();
+            }
+            segments.drain(..expired).collect()
+        };
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let mut removed_spans = 0;
+        let mut affected = HashSet::new();
+        for segment in removed {
+            removed_spans += segment.archive.span_count();
+            affected.extend(segment.archive.trace_ids());
+            let path = segment.archive.path().to_path_buf();
+            let index_path = SegmentIndex::path(&self.dir, segment.id);
+            drop(segment);
+            std::fs::remove_file(&path)?;
+            match std::fs::remove_file(&index_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {},
+            }
+        }
+
+        // Forget traces no longer stored anywhere
+        {
+            let segments = self.segments.read();
+            let pending = self.pending.lock();
+            for trace_id in &affected {
+                let still_stored = segments.iter().any(|s| s.archive.contains_trace(trace_id))
+                    || pending.iter().any(|s| &s.trace_id == trace_id);
+                if !still_stored {
+                    self.traces.remove(trace_id);
+                    self.evictions.record(trace_id);
+                }
+            }
+        }
+        self.span_traces
+            .retain(|_, trace_id| self.traces.contains_key(trace_id));
+        let live_services: HashSet<ServiceName> = self
+            .traces
+            .iter()
+            .flat_map(|entry| entry.services.clone())
+            .collect();
+        self.services
+            .retain(|service, _| live_services.contains(service));
+
+        update_counter!(self.counters.spans_evicted, add removed_spans as u64);
+        update_counter!(self.counters.cleanup_operations, add 1);
+        Ok(removed_spans)
+    }
+
+    fn cleanup_due(&self) -> bool {
+        let since = self.last_cleanup.lock().unwrap_or(self.opened_at);
+        self.clock.now().duration_since(since).unwrap_or_default()
+            >= self.cleanup_config.cleanup_interval
+    }
+
+    fn sorted_infos(
+        &self,
+        mut keep: impl FnMut(&TraceSummary) -> bool,
+        limit: usize,
+    ) -> Vec<TraceInfo> {
+        let mut infos: Vec<TraceInfo> = self
+            .traces
+            .iter()
+            .filter(|entry| keep(entry.value()))
+            .map(|entry| entry.value().to_info(entry.key()))
+            .collect();
+        infos.sort_by_key(|info| Reverse(info.start_time));
+        infos.truncate(limit);
+        infos
+    }
+}
+
+impl Drop for PersistentStorage {
+    fn drop(&mut self) {
+        if !self.owner {
+            return;
+        }
+        if let Err(e) = self.flush() {
+            tracing::error!("Failed to flush persistent storage on shutdown: {}", e);
+        }
+        let segments = self.segments.read();
+        if let Some(active) = segments.last() {
+            self.save_index(active, &mut self.active_index.lock());
+        }
+    }
+}
+
+fn matches_query(span: &Span, query_lower: &str) -> bool {
+    span.operation_name.to_lowercase().contains(query_lower)
+        || span.attributes.iter().any(|(k, v)| {
+            k.to_lowercase().contains(query_lower) || v.to_lowercase().contains(query_lower)
+        })
+}
+
+fn service_metrics(
+    name: ServiceName,
+    mut durations: Vec<Duration>,
+    error_count: u64,
+    last_seen: SystemTime,
+    ingest_lag: IngestLag,
+) -> ServiceMetrics {
+    durations.sort();
+    let span_count = durations.len() as u64;
+    let percentile = |p: usize| {
+        durations
+            .get(durations.len() * p / 100)
+            .copied()
+            .unwrap_or_default()
+    };
+    let total: Duration = durations.iter().sum();
+    ServiceMetrics {
+        name,
+        request_rate: span_count as f64 / 60.0,
+        error_rate: error_count as f64 / span_count.max(1) as f64,
+        latency_p50: percentile(50),
+        latency_p95: percentile(95),
+        latency_p99: percentile(99),
+        last_seen,
+        span_count,
+        error_count,
+        avg_duration: total / (span_count.max(1) as u32),
+        max_duration: durations.last().copied().unwrap_or_default(),
+        min_duration: durations.first().copied().unwrap_or_default(),
+        ingest_lag_p50: ingest_lag.p50,
+        ingest_lag_p95: ingest_lag.p95,
+        ingest_lag_p99: ingest_lag.p99,
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for PersistentStorage {
+    async fn store_span(&self, span: Span) -> Result<()> {
+        self.ingest_lag.record(&span, self.clock.now());
+        self.index_span(&span);
+        let should_flush = {
+            let mut pending = self.pending.lock();
+            pending.push(span);
+            pending.len() >= self.flush_spans
+        };
+        update_counter!(self.counters.spans_processed, add 1);
+        if should_flush {
+            self.blocking(|storage| {
+                storage.flush()?;
+                if storage.cleanup_due() {
+                    storage.delete_old_segments()?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn get_span(&self, span_id: &SpanId) -> Result<Option<Span>> {
+        let Some(trace_id) = self.span_traces.get(span_id).map(|e| e.clone()) else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_trace_spans(&trace_id)
+            .await?
+            .into_iter()
+            .find(|s| &s.span_id == span_id))
+    }
+
+    async fn get_trace_spans(&self, trace_id: &TraceId) -> Result<Vec<Span>> {
+        let mut spans = Vec::new();
+        self.visit_trace_spans(trace_id, &mut |span| {
+            spans.push(span.clone());
+            true
+        })
+        .await?;
+        if let Some(ref adjuster) = self.clock_skew {
+            adjuster.adjust(&mut spans);
+        }
+        Ok(spans)
+    }
+
+    async fn visit_trace_spans(
+        &self,
+        trace_id: &TraceId,
+        visit: &mut (dyn for<'s> FnMut(&'s Span) -> bool + Send),
+    ) -> Result<()> {
+        if !self.traces.contains_key(trace_id) {
+            return Ok(());
+        }
+        // Segment spans are decoded from disk either way; only the pending
+        // buffer is cloned, so the lock is not held while visiting
+        let mut spans = Vec::new();
+        for segment in self.segments.read().iter() {
+            if segment.archive.contains_trace(trace_id) {
+                spans.extend(segment.archive.get_trace_spans(trace_id)?);
+            }
+        }
+        spans.extend(
+            self.pending
+                .lock()
+                .iter()
+                .filter(|s| &s.trace_id == trace_id)
+                .cloned(),
+        );
+        spans.sort_by_key(|s| s.start_time);
+        for span in &spans {
+            if !visit(span) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_service_spans(
+        &self,
+        service: &ServiceName,
+        since: SystemTime,
+    ) -> Result<Vec<Span>> {
+        let mut spans = Vec::new();
+        self.scan_spans(|span| {
+            if &span.service_name == service && span.start_time >= since {
+                spans.push(span.clone());
+            }
+            true
+        })?;
+        spans.sort_by_key(|s| s.start_time);
+        Ok(spans)
+    }
+
+    async fn get_service_metrics(&self) -> Result<Vec<ServiceMetrics>> {
+        let mut per_service: HashMap<ServiceName, (Vec<Duration>, u64, SystemTime)> =
+            HashMap::new();
+        self.scan_spans(|span| {
+            let (durations, errors, last_seen) = per_service
+                .entry(span.service_name.clone())
+                .or_insert_with(|| (Vec::new(), 0, UNIX_EPOCH));
+            durations.push(span.duration);
+            if span.status.is_error() {
+                *errors += 1;
+            }
+            *last_seen = (*last_seen).max(span.start_time);
+            true
+        })?;
+        Ok(per_service
+            .into_iter()
+            .map(|(name, (durations, errors, last_seen))| {
+                let lag = self.ingest_lag.percentiles(&name).unwrap_or_default();
+                service_metrics(name, durations, errors, last_seen, lag)
+            })
+            .collect())
+    }
+
+    async fn get_span_count(&self) -> Result<usize> {
+        Ok(self.stored_span_count())
+    }
+
+    async fn enforce_limits(&self) -> Result<usize> {
+        self.blocking(Self::delete_old_segments).await
+    }
+
+    async fn list_services(&self) -> Result<Vec<ServiceName>> {
+        Ok(self.services.iter().map(|e| e.key().clone()).collect())
+    }
+
+    async fn get_storage_stats(&self) -> Result<StorageStats> {
+        let memory_bytes: usize = self.pending.lock().iter().map(estimate_span_memory).sum();
+        let uptime = self.counters.start_time.elapsed();
+        let processed = update_counter!(self.counters.spans_processed, get);
+        Ok(StorageStats {
+            trace_count: self.traces.len(),
+            span_count: self.stored_span_count(),
+            service_count: self.services.len(),
+            memory_bytes,
+            memory_mb: memory_bytes as f64 / 1024.0 / 1024.0,
+            memory_pressure: 0.0,
+            oldest_span: self.traces.iter().map(|e| e.start_time).min(),
+            newest_span: self.services.iter().map(|e| *e.value()).max(),
+            processing_rate: processed as f64 / uptime.as_secs_f64().max(1.0),
+            error_rate: 0.0,
+            cleanup_count: update_counter!(self.counters.cleanup_operations, get),
+            last_cleanup: *self.last_cleanup.lock(),
+            health_status: self.get_health(),
+            uptime_seconds: uptime.as_secs(),
+            rejected_spans: update_counter!(self.counters.spans_rejected, get),
+            spans_processed: processed,
+            spans_evicted: update_counter!(self.counters.spans_evicted, get),
+            warm_span_count: 0,
+            warm_memory_bytes: 0,
+            warm_memory_saved_bytes: 0,
+        })
+    }
+
+    async fn emergency_cleanup(&self) -> Result<usize> {
+        self.blocking(|storage| {
+            storage.flush()?;
+            storage.delete_old_segments()
+        })
+        .await
+    }
+
+    fn record_rejected_spans(&self, count: usize) {
+        update_counter!(self.counters.spans_rejected, add count as u64);
+    }
+
+    fn trace_evicted_at(&self, trace_id: &TraceId) -> Option<SystemTime> {
+        if self.traces.contains_key(trace_id) {
+            return None;
+        }
+        self.evictions.evicted_at(trace_id)
+    }
+
+    fn recently_evicted_traces(&self, limit: usize) -> Vec<EvictedTrace> {
+        self.evictions.recent(limit)
+    }
+
+    fn get_health(&self) -> StorageHealth {
+        StorageHealth::Healthy
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn list_recent_traces(
+        &self,
+        limit: usize,
+        service_filter: Option<&ServiceName>,
+    ) -> Result<Vec<TraceInfo>> {
+        Ok(self.sorted_infos(
+            |summary| service_filter.map_or(true, |s| summary.services.contains(s)),
+            limit,
+        ))
+    }
+
+    async fn search_traces(&self, query: &str, limit: usize) -> Result<Vec<TraceInfo>> {
+        let query_lower = query.to_lowercase();
+        let mut matched = HashSet::new();
+        self.scan_spans(|span| {
+            if matches_query(span, &query_lower) {
+                matched.insert(span.trace_id.clone());
+            }
+            matched.len() < limit
+        })?;
+        let mut infos: Vec<TraceInfo> = matched
+            .iter()
+            .filter_map(|id| self.traces.get(id).map(|summary| summary.to_info(id)))
+            .collect();
+        infos.sort_by_key(|info| Reverse(info.start_time));
+        Ok(infos)
+    }
+
+    async fn get_error_traces(&self, limit: usize) -> Result<Vec<TraceInfo>> {
+        Ok(self.sorted_infos(|summary| summary.has_error, limit))
+    }
+
+    async fn get_slow_traces(&self, threshold: Duration, limit: usize) -> Result<Vec<TraceInfo>> {
+        let mut traces = self.sorted_infos(|summary| summary.duration() >= threshold, usize::MAX);
+        traces.sort_by_key(|info| Reverse(info.duration));
+        traces.truncate(limit);
+        Ok(traces)
+    }
+
+    async fn list_traces(
+        &self,
+        service: Option<&str>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<TraceInfo>> {
+        let to_nanos = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64
+        };
+        Ok(self.sorted_infos(
+            |summary| {
+                service.map_or(true, |svc| summary.services.iter().any(|s| s.as_str() == svc))
+                    && start_time.map_or(true, |start| to_nanos(summary.start_time) >= start)
+                    && end_time.map_or(true, |end| to_nanos(summary.start_time) <= end)
+            },
+            limit,
+        ))
+    }
+
+    async fn get_service_metrics_map(&self) -> Result<HashMap<ServiceName, ServiceMetrics>> {
+        let metrics = self.get_service_metrics().await?;
+        Ok(metrics
+            .into_iter()
+            .map(|metric| (metric.name.clone(), metric))
+            .collect())
+    }
+
+    async fn search_spans(
+        &self,
+        query: &str,
+        service: Option<&str>,
+        attribute_key: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Span>> {
+        let query_lower = query.to_lowercase();
+        let mut spans = Vec::new();
+        if limit == 0 {
+            return Ok(spans);
+        }
+        self.scan_spans(|span| {
+            let matched = match attribute_key {
+                Some(key) => span.attributes.get(key) == Some(query),
+                None => matches_query(span, &query_lower),
+            };
+            if matched && service.map_or(true, |svc| span.service_name.as_str() == svc) {
+                spans.push(span.clone());
+            }
+            spans.len() < limit
+        })?;
+        Ok(spans)
+    }
+
+    async fn get_stats(&self) -> Result<StorageStats> {
+        self.get_storage_stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(trace: u64, id: u64, service: &str, start: SystemTime) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name("op")
+            .start_time(start)
+            .duration(Duration::from_millis(5))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_segments_rotate_and_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = PersistentStorage::open(dir.path())
+            .unwrap()
+            .with_flush_spans(2)
+            .with_segment_spans(2);
+
+        let old = SystemTime::now() - Duration::from_secs(7200);
+        storage.store_span(span(1, 1, "old", old)).await.unwrap();
+        storage.store_span(span(1, 2, "old", old)).await.unwrap();
+        storage
+            .store_span(span(2, 3, "new", SystemTime::now()))
+            .await
+            .unwrap();
+        storage
+            .store_span(span(2, 4, "new", SystemTime::now()))
+            .await
+            .unwrap();
+        assert_eq!(storage.segment_count(), 2);
+
+        // Default retention is one hour: only the first segment expires
+        assert_eq!(storage.enforce_limits().await.unwrap(), 2);
+        assert_eq!(storage.segment_count(), 1);
+        let expired = TraceId::new(format!("{:032x}", 1)).unwrap();
+        assert!(storage.get_trace_spans(&expired).await.unwrap().is_empty());
+        assert!(storage.trace_evicted_at(&expired).is_some());
+        assert_eq!(storage.list_services().await.unwrap().len(), 1);
+        assert_eq!(storage.get_span_count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retention_follows_injected_clock() {
+        use crate::core::{Clock, MockClock};
+
+        let dir = tempfile::tempdir().unwrap();
+        let clock = MockClock::default();
+        let storage = PersistentStorage::open(dir.path())
+            .unwrap()
+            .with_clock(clock.shared())
+            .with_flush_spans(2)
+            .with_segment_spans(2);
+
+        let start = clock.now();
+        storage.store_span(span(1, 1, "svc", start)).await.unwrap();
+        storage.store_span(span(1, 2, "svc", start)).await.unwrap();
+        assert_eq!(storage.enforce_limits().await.unwrap(), 0);
+
+        clock.advance(Duration::from_secs(7200));
+        assert_eq!(storage.enforce_limits().await.unwrap(), 2);
+        assert_eq!(storage.segment_count(), 0);
+        assert!(!SegmentIndex::path(dir.path(), 1).exists());
+    }
+
+    #[tokio::test]
+    async fn test_reopen_loads_segment_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        // Far in the future, e.g. from a client with a skewed clock
+        let future = SystemTime::now() + Duration::from_secs(2 * 365 * 24 * 3600);
+        {
+            let storage = PersistentStorage::open(dir.path())
+                .unwrap()
+                .with_flush_spans(2)
+                .with_segment_spans(2);
+            for id in 1..=3 {
+                storage
+                    .store_span(span(1, id, "svc", future))
+                    .await
+                    .unwrap();
+            }
+        }
+        assert!(SegmentIndex::path(dir.path(), 1).exists());
+        assert!(SegmentIndex::path(dir.path(), 2).exists());
+
+        let trace = TraceId::new(format!("{:032x}", 1)).unwrap();
+        let check = |storage: &PersistentStorage| {
+            let summary = storage.traces.get(&trace).unwrap();
+            assert_eq!(summary.span_count, 3);
+            assert_eq!(storage.span_traces.len(), 3);
+        };
+
+        let storage = PersistentStorage::open(dir.path()).unwrap();
+        check(&storage);
+        assert_eq!(storage.get_trace_spans(&trace).await.unwrap().len(), 3);
+        drop(storage);
+
+        // A missing or corrupt index is rebuilt from its segment
+        std::fs::remove_file(SegmentIndex::path(dir.path(), 1)).unwrap();
+        std::fs::write(SegmentIndex::path(dir.path(), 2), b"garbage").unwrap();
+        let storage = PersistentStorage::open(dir.path()).unwrap();
+        check(&storage);
+        assert_eq!(storage.get_trace_spans(&trace).await.unwrap().len(), 3);
+        assert!(SegmentIndex::load(&SegmentIndex::path(dir.path(), 1)).is_some());
+        assert!(SegmentIndex::load(&SegmentIndex::path(dir.path(), 2)).is_some());
+    }
+}
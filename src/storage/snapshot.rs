@@ -0,0 +1,170 @@
+//! Compacted snapshots of the in-memory span store.
+//!
+//! A snapshot holds every span in memory when it was taken, in the
+//! write-ahead log's record format. Once it is written the WAL segments it
+//! covers are deleted, so recovery reads the snapshot and then the few newer
+//! segments instead of the whole log. Snapshots are written to a temporary
+//! file and renamed into place, so a crash mid-write keeps the previous one.
+
+use super::wal::{encode_record, read_segment};
+use crate::core::{Result, Span, TraceId, UrpoError};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File name of the snapshot inside its directory.
+pub const SNAPSHOT_FILE: &str = "snapshot.bin";
+
+/// The snapshot file of one storage directory.
+#[derive(Debug, Clone)]
+pub struct SpanSnapshot {
+    path: PathBuf,
+    /// Held while a snapshot is written, as writers share the temporary file.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl SpanSnapshot {
+    /// The snapshot kept in `dir`.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            path: dir.as_ref().join(SNAPSHOT_FILE),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Path of the snapshot file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Spans of the last snapshot, none if there is no snapshot yet.
+    ///
+    /// A corrupt tail is skipped with a warning.
+    pub fn load(&self) -> Result<Vec<Span>> {
+        match read_segment(&self.path) {
+            Ok((spans, _)) => Ok(spans),
+            Err(UrpoError::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace the snapshot with the spans `fill` appends, returning how
+    /// many there are. The old snapshot stays in place if `fill` fails.
+    pub fn write(&self, fill: impl FnOnce(&mut SnapshotWriter) -> Result<()>) -> Result<usize> {
+        let _guard = self.write_lock.lock();
+        let temp_path = self.path.with_extension("tmp");
+        let mut writer = SnapshotWriter {
+            out: BufWriter::new(File::create(&temp_path)?),
+            spans: 0,
+        };
+        fill(&mut writer)?;
+
+        writer
+            .out
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(writer.spans)
+    }
+
+    /// Rewrite the snapshot without the spans of `trace_ids`, returning how
+    /// many were removed.
+    pub fn purge_traces(&self, trace_ids: &HashSet<TraceId>) -> Result<usize> {
+        let mut removed = 0;
+        self.write(|writer| {
+            for span in self.load()? {
+                if trace_ids.contains(&span.trace_id) {
+                    removed += 1;
+                } else {
+                    writer.append(&span)?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+}
+
+/// A snapshot being written by [`SpanSnapshot::write`].
+pub struct SnapshotWriter {
+    out: BufWriter<File>,
+    spans: usize,
+}
+
+impl SnapshotWriter {
+    /// Add `span` to the snapshot.
+    pub fn append(&mut self, span: &Span) -> Result<()> {
+        self.out.write_all(&encode_record(span)?)?;
+        self.spans += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn span(id: u64) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id / 10)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("snapshot-test".to_string()).unwrap())
+            .operation_name("op")
+            .start_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000 + id))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_and_purge() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = SpanSnapshot::new(dir.path());
+        assert!(snapshot.load().unwrap().is_empty());
+
+        let written = snapshot.write(|writer| {
+            for id in 0..20 {
+                writer.append(&span(id))?;
+            }
+            Ok(())
+        });
+        assert_eq!(written.unwrap(), 20);
+        assert_eq!(snapshot.load().unwrap().len(), 20);
+
+        // A failed write keeps the previous snapshot
+        let failed = snapshot.write(|writer| {
+            writer.append(&span(99))?;
+            Err(UrpoError::storage("interrupted"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(snapshot.load().unwrap().len(), 20);
+
+        let purged = HashSet::from([TraceId::new(format!("{:032x}", 1)).unwrap()]);
+        assert_eq!(snapshot.purge_traces(&purged).unwrap(), 10);
+        let spans = snapshot.load().unwrap();
+        assert_eq!(spans.len(), 10);
+        assert!(spans.iter().all(|span| !purged.contains(&span.trace_id)));
+    }
+
+    #[test]
+    fn test_corrupt_tail_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = SpanSnapshot::new(dir.path());
+        snapshot
+            .write(|writer| (0..3).try_for_each(|id| writer.append(&span(id))))
+            .unwrap();
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(snapshot.path())
+            .unwrap();
+        file.write_all(&[7, 0, 0, 0, 1, 2]).unwrap();
+        drop(file);
+        assert_eq!(snapshot.load().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,197 @@
+//! Coarse time-bucket index from span start time to trace IDs.
+//!
+//! Each bucket covers a fixed slice of time and counts, per trace, how many
+//! stored spans start inside it. Time-range and "most recent" trace listings
+//! walk only the relevant buckets instead of every stored trace. Counts are
+//! decremented as spans are evicted, so empty buckets and traces are dropped.
+
+use crate::core::{Span, TraceId};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default width of one bucket.
+pub const DEFAULT_BUCKET_WIDTH: Duration = Duration::from_secs(1);
+
+/// Bucket start (Unix nanos) to per-trace span counts.
+type Buckets = BTreeMap<u64, HashMap<TraceId, u32>>;
+
+/// Bucket start (Unix nanos) to the traces with spans starting in it.
+pub struct TimeBucketIndex {
+    bucket_nanos: u64,
+    buckets: RwLock<Buckets>,
+}
+
+impl Default for TimeBucketIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUCKET_WIDTH)
+    }
+}
+
+impl TimeBucketIndex {
+    /// Create an empty index with buckets of `bucket_width` (at least 1ns).
+    pub fn new(bucket_width: Duration) -> Self {
+        Self {
+            bucket_nanos: (bucket_width.as_nanos() as u64).max(1),
+            buckets: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    fn bucket_of(&self, nanos: u64) -> u64 {
+        nanos - nanos % self.bucket_nanos
+    }
+
+    fn span_bucket(&self, span: &Span) -> u64 {
+        let nanos = span
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        self.bucket_of(nanos)
+    }
+
+    /// Record `span` under the bucket of its start time.
+    pub fn insert(&self, span: &Span) {
+        let bucket = self.span_bucket(span);
+        *self
+            .buckets
+            .write()
+            .entry(bucket)
+            .or_default()
+            .entry(span.trace_id.clone())
+            .or_insert(0) += 1;
+    }
+
+    /// Forget `span`, dropping its trace and bucket once they are empty.
+    pub fn remove(&self, span: &Span) {
+        let bucket = self.span_bucket(span);
+        let mut buckets = self.buckets.write();
+        let Some(traces) = buckets.get_mut(&bucket) else {
+            return;
+        };
+        if let Some(count) = traces.get_mut(&span.trace_id) {
+            *count -= 1;
+            if *count == 0 {
+                traces.remove(&span.trace_id);
+            }
+        }
+        if traces.is_empty() {
+            buckets.remove(&bucket);
+        }
+    }
+
+    /// Start times of the buckets overlapping `[start, end]` (Unix nanos),
+    /// newest first.
+    pub fn buckets_newest_first(&self, start: Option<u64>, end: Option<u64>) -> Vec<SystemTime> {
+        let low = start.map_or(0, |start| self.bucket_of(start));
+        let high = end.unwrap_or(u64::MAX);
+        if low > high {
+            return Vec::new();
+        }
+        self.buckets
+            .read()
+            .range(low..=high)
+            .rev()
+            .map(|(&bucket, _)| UNIX_EPOCH + Duration::from_nanos(bucket))
+            .collect()
+    }
+
+    /// Traces with at least one span starting in the bucket at `bucket_start`.
+    pub fn traces_in_bucket(&self, bucket_start: SystemTime) -> Vec<TraceId> {
+        let bucket = bucket_start
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        self.buckets
+            .read()
+            .get(&bucket)
+            .map(|traces| traces.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Traces with spans in the buckets overlapping `[start, end]` (Unix
+    /// nanos), newest bucket first and each trace once. Traces in the edge
+    /// buckets may have no span inside the range itself.
+    pub fn traces_between(&self, start: u64, end: u64) -> Vec<TraceId> {
+        let low = self.bucket_of(start);
+        if low > end {
+            return Vec::new();
+        }
+        let mut seen = HashSet::new();
+        self.buckets
+            .read()
+            .range(low..=end)
+            .rev()
+            .flat_map(|(_, traces)| traces.keys())
+            .filter(|trace_id| seen.insert(*trace_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of non-empty buckets.
+    pub fn len(&self) -> usize {
+        self.buckets.read().len()
+    }
+
+    /// Whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.read().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId};
+
+    fn span(trace: u64, id: u64, secs: u64) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("api".to_string()).unwrap())
+            .operation_name("GET")
+            .start_time(UNIX_EPOCH + Duration::from_millis(secs * 1000 + id))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_buckets_track_inserts_and_removals() {
+        let index = TimeBucketIndex::default();
+        let (a, b, c) = (span(1, 1, 100), span(1, 2, 100), span(2, 3, 105));
+        for s in [&a, &b, &c] {
+            index.insert(s);
+        }
+        assert_eq!(index.len(), 2);
+
+        let secs = |s: u64| s * 1_000_000_000;
+        let all = index.buckets_newest_first(None, None);
+        assert_eq!(
+            all,
+            vec![UNIX_EPOCH + Duration::from_secs(105), UNIX_EPOCH + Duration::from_secs(100)]
+        );
+        // A start inside a bucket still includes that bucket
+        assert_eq!(
+            index
+                .buckets_newest_first(Some(secs(100) + 500), Some(secs(104)))
+                .len(),
+            1
+        );
+        assert!(index.buckets_newest_first(Some(secs(106)), None).is_empty());
+        assert_eq!(
+            index.traces_between(secs(100), secs(110)),
+            vec![c.trace_id.clone(), a.trace_id.clone()]
+        );
+        assert_eq!(index.traces_between(secs(104), secs(105)), vec![c.trace_id.clone()]);
+        assert!(index.traces_between(secs(110), secs(100)).is_empty());
+
+        // The trace stays until its last span in the bucket is removed
+        index.remove(&a);
+        assert_eq!(index.traces_in_bucket(all[1]), vec![a.trace_id.clone()]);
+        index.remove(&b);
+        assert!(index.traces_in_bucket(all[1]).is_empty());
+        assert_eq!(index.len(), 1);
+        index.remove(&c);
+        assert!(index.is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 //! Storage data types and structures.
 
 use crate::core::{ServiceName, TraceId};
-use std::time::{Duration, SystemTime};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Observations closer together than this share one entry.
+const RATE_RESOLUTION: Duration = Duration::from_millis(100);
 
 /// Information about a trace for listing purposes.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -55,6 +60,24 @@ pub struct StorageStats {
     pub health_status: StorageHealth,
     /// Uptime in seconds.
     pub uptime_seconds: u64,
+    /// Spans rejected because storage was at capacity.
+    #[serde(default)]
+    pub rejected_spans: u64,
+    /// Spans received since start, stored or not.
+    #[serde(default)]
+    pub spans_processed: u64,
+    /// Spans evicted to stay within limits.
+    #[serde(default)]
+    pub spans_evicted: u64,
+    /// Spans compressed into the warm tier, not counted in `span_count`.
+    #[serde(default)]
+    pub warm_span_count: usize,
+    /// Compressed size of the warm tier in bytes, included in `memory_bytes`.
+    #[serde(default)]
+    pub warm_memory_bytes: usize,
+    /// Estimated bytes saved by compressing the warm tier.
+    #[serde(default)]
+    pub warm_memory_saved_bytes: usize,
 }
 
 /// Health status of the storage system.
@@ -69,3 +92,138 @@ pub enum StorageHealth {
     /// Storage is offline or unavailable.
     Offline,
 }
+
+/// Estimated memory held by one service's spans.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ServiceFootprint {
+    /// Service name.
+    pub service: ServiceName,
+    /// Number of hot spans stored for the service.
+    pub span_count: usize,
+    /// Estimated memory usage in bytes.
+    pub estimated_bytes: usize,
+}
+
+/// Estimated memory held by one trace's spans.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TraceFootprint {
+    /// Trace identifier.
+    pub trace_id: TraceId,
+    /// Number of hot spans stored for the trace.
+    pub span_count: usize,
+    /// Estimated memory usage in bytes.
+    pub estimated_bytes: usize,
+}
+
+/// Event counts over a rolling time window, for rates that stay meaningful
+/// however long the process has been running.
+///
+/// Observations within [`RATE_RESOLUTION`] of each other are merged and
+/// anything older than the retention is dropped, so memory stays bounded
+/// under any ingest rate.
+#[derive(Debug)]
+pub struct SlidingWindowCounter {
+    observations: Mutex<VecDeque<(Instant, u64)>>,
+    retention: Duration,
+    started: Instant,
+}
+
+impl SlidingWindowCounter {
+    /// A counter keeping observations for `retention`, starting now.
+    pub fn new(retention: Duration) -> Self {
+        Self::starting_at(Instant::now(), retention)
+    }
+
+    /// A counter keeping observations for `retention`, starting at `started`.
+    pub fn starting_at(started: Instant, retention: Duration) -> Self {
+        Self {
+            observations: Mutex::new(VecDeque::new()),
+            retention,
+            started,
+        }
+    }
+
+    /// Record `count` events now.
+    pub fn record(&self, count: u64) {
+        self.record_at(Instant::now(), count);
+    }
+
+    /// Record `count` events at `at`.
+    pub fn record_at(&self, at: Instant, count: u64) {
+        let mut observations = self.observations.lock();
+        match observations.back_mut() {
+            Some((last, total)) if at.saturating_duration_since(*last) < RATE_RESOLUTION => {
+                *total += count;
+            },
+            _ => observations.push_back((at, count)),
+        }
+        while observations
+            .front()
+            .is_some_and(|(first, _)| at.saturating_duration_since(*first) > self.retention)
+        {
+            observations.pop_front();
+        }
+    }
+
+    /// Events per second over the last `window`, up to the retention.
+    pub fn rate_per_second(&self, window: Duration) -> f64 {
+        self.rate_per_second_at(Instant::now(), window)
+    }
+
+    /// Events per second over the `window` before `now`, up to the retention.
+    ///
+    /// A counter younger than the window is averaged over its lifetime.
+    pub fn rate_per_second_at(&self, now: Instant, window: Duration) -> f64 {
+        let window = window
+            .min(self.retention)
+            .min(now.saturating_duration_since(self.started));
+        if window.is_zero() {
+            return 0.0;
+        }
+        let total: u64 = self
+            .observations
+            .lock()
+            .iter()
+            .rev()
+            .take_while(|(at, _)| now.saturating_duration_since(*at) <= window)
+            .map(|(_, count)| count)
+            .sum();
+        total as f64 / window.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_window_rate() {
+        let start = Instant::now();
+        let counter = SlidingWindowCounter::starting_at(start, Duration::from_secs(60));
+        for i in 0..100 {
+            counter.record_at(start + Duration::from_millis(i * 10), 1);
+        }
+
+        let now = start + Duration::from_secs(1);
+        let rate = counter.rate_per_second_at(now, Duration::from_secs(5));
+        assert!((rate - 100.0).abs() < 1.0, "rate {}", rate);
+
+        // Idle time inside the window lowers the rate, then it drops out
+        let rate =
+            counter.rate_per_second_at(start + Duration::from_secs(5), Duration::from_secs(5));
+        assert!((rate - 20.0).abs() < 1.0, "rate {}", rate);
+        let later = start + Duration::from_secs(30);
+        assert_eq!(counter.rate_per_second_at(later, Duration::from_secs(5)), 0.0);
+    }
+
+    #[test]
+    fn test_sliding_window_is_bounded() {
+        let start = Instant::now();
+        let counter = SlidingWindowCounter::starting_at(start, Duration::from_secs(1));
+        for i in 0..10_000 {
+            counter.record_at(start + Duration::from_millis(i), 1);
+        }
+        // 100ms resolution over a 1s retention
+        assert!(counter.observations.lock().len() <= 12);
+    }
+}
@@ -0,0 +1,479 @@
+//! Write-ahead log for crash recovery of in-memory spans.
+//!
+//! Every stored span is appended as one record before it is indexed:
+//!
+//! ```text
+//! payload len (u32) | FNV-1a checksum of payload (u32) | bincode Span
+//! ```
+//!
+//! Records go to numbered segment files (`wal-00000001.log`, ...). A segment
+//! is rotated once it reaches `max_segment_bytes` and the oldest segments
+//! beyond `max_segments` are deleted, so the log stays bounded. Writes reach
+//! the OS immediately; a background thread fsyncs them every `sync_interval`.
+//!
+//! A torn or corrupt trailing record (e.g. after a crash mid-write) ends the
+//! replay of its segment and is truncated when the log is reopened.
+
+use crate::core::config::WalConfig;
+use crate::core::{Result, Span, TraceId, UrpoError};
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+const RECORD_HEADER_LEN: u64 = 8;
+const SEGMENT_PREFIX: &str = "wal-";
+const SEGMENT_SUFFIX: &str = ".log";
+
+/// FNV-1a 32-bit checksum, enough to detect torn writes.
+pub(super) fn checksum(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0x811c_9dc5, |hash, &b| (hash ^ u32::from(b)).wrapping_mul(0x0100_0193))
+}
+
+fn segment_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{}{:08}{}", SEGMENT_PREFIX, seq, SEGMENT_SUFFIX))
+}
+
+/// Sequence numbers of the segments in `dir`, oldest first.
+fn list_segments(dir: &Path) -> Result<VecDeque<u64>> {
+    let mut seqs: Vec<u64> = std::fs::read_dir(dir)?
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_prefix(SEGMENT_PREFIX)?
+                .strip_suffix(SEGMENT_SUFFIX)?
+                .parse()
+                .ok()
+        })
+        .collect();
+    seqs.sort_unstable();
+    Ok(seqs.into())
+}
+
+/// Decode the complete records of a segment.
+///
+/// Returns the spans and the length of the valid prefix of the file.
+pub(super) fn read_segment(path: &Path) -> Result<(Vec<Span>, u64)> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    let mut header = [0u8; RECORD_HEADER_LEN as usize];
+    while offset + RECORD_HEADER_LEN <= file_len {
+        reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let expected = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if offset + RECORD_HEADER_LEN + u64::from(len) > file_len {
+            break;
+        }
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload)?;
+        if checksum(&payload) != expected {
+            break;
+        }
+        let Ok(span) = bincode::deserialize::<Span>(&payload) else {
+            break;
+        };
+        spans.push(span);
+        offset += RECORD_HEADER_LEN + u64::from(len);
+    }
+
+    if offset < file_len {
+        tracing::warn!(
+            "Ignoring {} trailing bytes of incomplete span records in {}",
+            file_len - offset,
+            path.display()
+        );
+    }
+    Ok((spans, offset))
+}
+
+/// Encode `span` as one length-prefixed, checksummed record.
+pub(super) fn encode_record(span: &Span) -> Result<Vec<u8>> {
+    let payload = bincode::serialize(span)
+        .map_err(|e| UrpoError::storage(format!("WAL serialization failed: {}", e)))?;
+    let len =
+        u32::try_from(payload.len()).map_err(|_| UrpoError::storage("WAL record exceeds 4GiB"))?;
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+    record.extend_from_slice(&len.to_le_bytes());
+    record.extend_from_slice(&checksum(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    Ok(record)
+}
+
+/// Segment currently being appended to.
+struct SegmentWriter {
+    file: File,
+    seq: u64,
+    len: u64,
+    /// All segments on disk, oldest first (including the current one).
+    segments: VecDeque<u64>,
+}
+
+struct WalShared {
+    dir: PathBuf,
+    max_segment_bytes: u64,
+    max_segments: usize,
+    sync_each_write: bool,
+    writer: Mutex<SegmentWriter>,
+    dirty: AtomicBool,
+}
+
+impl WalShared {
+    fn sync_if_dirty(&self) {
+        if self.dirty.swap(false, Ordering::AcqRel) {
+            if let Err(e) = self.writer.lock().file.sync_data() {
+                tracing::warn!("WAL fsync failed: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for WalShared {
+    fn drop(&mut self) {
+        self.sync_if_dirty();
+    }
+}
+
+/// Append-only, size-bounded log of stored spans.
+pub struct WriteAheadLog {
+    shared: Arc<WalShared>,
+}
+
+impl WriteAheadLog {
+    /// Open (or create) the log in `dir`, truncating a torn trailing record.
+    pub fn open(dir: impl AsRef<Path>, config: &WalConfig) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        if config.max_segments == 0 {
+            return Err(UrpoError::config("storage.wal.max_segments must be at least 1"));
+        }
+
+        let mut segments = list_segments(&dir)?;
+        let seq = match segments.back() {
+            Some(&seq) => seq,
+            None => {
+                segments.push_back(1);
+                1
+            },
+        };
+        let path = segment_path(&dir, seq);
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let (_, len) = read_segment(&path)?;
+        file.set_len(len)?;
+
+        let shared = Arc::new(WalShared {
+            dir,
+            max_segment_bytes: config.max_segment_bytes.max(1),
+            max_segments: config.max_segments,
+            sync_each_write: config.sync_interval.is_zero(),
+            writer: Mutex::new(SegmentWriter {
+                file,
+                seq,
+                len,
+                segments,
+            }),
+            dirty: AtomicBool::new(false),
+        });
+        if !shared.sync_each_write {
+            spawn_sync_thread(Arc::downgrade(&shared), config.sync_interval)?;
+        }
+
+        tracing::info!("Opened WAL {} at segment {}", shared.dir.display(), seq);
+        Ok(Self { shared })
+    }
+
+    /// Directory the segments are written to.
+    pub fn dir(&self) -> &Path {
+        &self.shared.dir
+    }
+
+    /// Number of segment files on disk.
+    pub fn segment_count(&self) -> usize {
+        self.shared.writer.lock().segments.len()
+    }
+
+    /// Append one span record, rotating the segment if it is full.
+    pub fn append(&self, span: &Span) -> Result<()> {
+        let record = encode_record(span)?;
+        let shared = &self.shared;
+        let mut writer = shared.writer.lock();
+        if writer.len > 0 && writer.len + record.len() as u64 > shared.max_segment_bytes {
+            self.rotate(&mut writer)?;
+        }
+
+        if let Err(e) = writer.file.write_all(&record) {
+            // Drop the partial record so later appends stay readable
+            let _ = writer.file.set_len(writer.len);
+            return Err(e.into());
+        }
+        writer.len += record.len() as u64;
+
+        if shared.sync_each_write {
+            writer.file.sync_data()?;
+        } else {
+            shared.dirty.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    /// Start a new segment and delete the oldest ones beyond the limit.
+    fn rotate(&self, writer: &mut SegmentWriter) -> Result<()> {
+        let shared = &self.shared;
+        writer.file.sync_data()?;
+
+        let seq = writer.seq + 1;
+        writer.file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(segment_path(&shared.dir, seq))?;
+        writer.seq = seq;
+        writer.len = 0;
+        writer.segments.push_back(seq);
+
+        while writer.segments.len() > shared.max_segments {
+            if let Some(old) = writer.segments.pop_front() {
+                match std::fs::remove_file(segment_path(&shared.dir, old)) {
+                    Ok(()) => {},
+                    Err(e) if e.kind() == ErrorKind::NotFound => {},
+                    Err(e) => tracing::warn!("Failed to delete WAL segment {}: {}", old, e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a new segment unless the current one is empty, returning its
+    /// sequence number. Later appends go to this segment or newer ones.
+    pub fn checkpoint(&self) -> Result<u64> {
+        let mut writer = self.shared.writer.lock();
+        if writer.len > 0 {
+            self.rotate(&mut writer)?;
+        }
+        Ok(writer.seq)
+    }
+
+    /// Delete the segments older than `seq`, returning how many were deleted.
+    pub fn remove_segments_before(&self, seq: u64) -> Result<usize> {
+        let mut writer = self.shared.writer.lock();
+        let mut removed = 0;
+        while writer.segments.front().is_some_and(|&old| old < seq) {
+            let old = writer.segments.pop_front().unwrap_or_default();
+            match std::fs::remove_file(segment_path(&self.shared.dir, old)) {
+                Ok(()) => removed += 1,
+                Err(e) if e.kind() == ErrorKind::NotFound => {},
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Rewrite the segments holding spans of `trace_ids` without them,
+    /// returning how many records were removed.
+    pub fn purge_traces(&self, trace_ids: &HashSet<TraceId>) -> Result<usize> {
+        let shared = &self.shared;
+        let mut writer = shared.writer.lock();
+        let mut removed = 0;
+        for seq in writer.segments.clone() {
+            let path = segment_path(&shared.dir, seq);
+            if !path.exists() {
+                continue;
+            }
+            let (spans, _) = read_segment(&path)?;
+            let before = spans.len();
+            let kept: Vec<Span> = spans
+                .into_iter()
+                .filter(|span| !trace_ids.contains(&span.trace_id))
+                .collect();
+            if kept.len() == before {
+                continue;
+            }
+            removed += before - kept.len();
+
+            let purge_path = path.with_extension("purge");
+            let mut out = std::io::BufWriter::new(File::create(&purge_path)?);
+            let mut len = 0;
+            for span in &kept {
+                let record = encode_record(span)?;
+                out.write_all(&record)?;
+                len += record.len() as u64;
+            }
+            out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            std::fs::rename(&purge_path, &path)?;
+            if seq == writer.seq {
+                writer.file = OpenOptions::new().read(true).append(true).open(&path)?;
+                writer.len = len;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Read back every complete record, oldest first.
+    pub fn replay(&self) -> Result<Vec<Span>> {
+        let segments = self.shared.writer.lock().segments.clone();
+        let mut spans = Vec::new();
+        for seq in segments {
+            let path = segment_path(&self.shared.dir, seq);
+            if path.exists() {
+                spans.extend(read_segment(&path)?.0);
+            }
+        }
+        Ok(spans)
+    }
+
+    /// Flush written records to disk now.
+    pub fn sync(&self) -> Result<()> {
+        self.shared.dirty.store(false, Ordering::Release);
+        self.shared.writer.lock().file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Fsync dirty segments every `interval` until the log is dropped.
+fn spawn_sync_thread(shared: Weak<WalShared>, interval: Duration) -> Result<()> {
+    std::thread::Builder::new()
+        .name("urpo-wal-sync".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+            match shared.upgrade() {
+                Some(shared) => shared.sync_if_dirty(),
+                None => break,
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ServiceName, SpanId, TraceId};
+    use std::time::UNIX_EPOCH;
+
+    fn span(id: u64) -> Span {
+        Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", id / 10)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+            .service_name(ServiceName::new("wal-test".to_string()).unwrap())
+            .operation_name("op")
+            .start_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000 + id))
+            .duration(Duration::from_millis(5))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_append_replay_and_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WalConfig::default();
+        let wal = WriteAheadLog::open(dir.path(), &config).unwrap();
+        for id in 1..=3 {
+            wal.append(&span(id)).unwrap();
+        }
+        drop(wal);
+
+        // Header promising more payload than was written
+        let path = segment_path(dir.path(), 1);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&100u32.to_le_bytes()).unwrap();
+        file.write_all(&[0xde, 0xad, 0xbe, 0xef, 1, 2, 3]).unwrap();
+        drop(file);
+
+        let wal = WriteAheadLog::open(dir.path(), &config).unwrap();
+        wal.append(&span(4)).unwrap();
+        let ids: Vec<_> = wal
+            .replay()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.span_id.as_str().to_string())
+            .collect();
+        assert_eq!(ids, (1..=4).map(|id| format!("{:016x}", id)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_rotation_bounds_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WalConfig {
+            max_segment_bytes: 1,
+            max_segments: 3,
+            sync_interval: Duration::ZERO,
+            ..WalConfig::default()
+        };
+        let wal = WriteAheadLog::open(dir.path(), &config).unwrap();
+        for id in 1..=10 {
+            wal.append(&span(id)).unwrap();
+        }
+
+        // One record per segment, only the newest three kept
+        assert_eq!(wal.segment_count(), 3);
+        assert_eq!(list_segments(dir.path()).unwrap(), vec![8, 9, 10]);
+        let spans = wal.replay().unwrap();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].span_id.as_str(), format!("{:016x}", 8));
+    }
+
+    #[test]
+    fn test_purge_traces_across_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WalConfig {
+            max_segment_bytes: 600,
+            max_segments: 100,
+            sync_interval: Duration::ZERO,
+            ..WalConfig::default()
+        };
+        let wal = WriteAheadLog::open(dir.path(), &config).unwrap();
+        // Traces 0, 1 and 2, spread over several segments
+        for id in 5..25 {
+            wal.append(&span(id)).unwrap();
+        }
+        assert!(wal.segment_count() > 1);
+
+        let purged = HashSet::from([TraceId::new(format!("{:032x}", 1)).unwrap()]);
+        assert_eq!(wal.purge_traces(&purged).unwrap(), 10);
+        wal.append(&span(25)).unwrap();
+
+        let ids: Vec<u64> = wal
+            .replay()
+            .unwrap()
+            .iter()
+            .map(|s| u64::from_str_radix(s.span_id.as_str(), 16).unwrap())
+            .collect();
+        assert_eq!(ids, (5..10).chain(20..26).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_checkpoint_and_remove_old_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WalConfig {
+            sync_interval: Duration::ZERO,
+            ..WalConfig::default()
+        };
+        let wal = WriteAheadLog::open(dir.path(), &config).unwrap();
+        wal.append(&span(1)).unwrap();
+        wal.append(&span(2)).unwrap();
+
+        assert_eq!(wal.checkpoint().unwrap(), 2);
+        // An empty segment is reused
+        assert_eq!(wal.checkpoint().unwrap(), 2);
+        wal.append(&span(3)).unwrap();
+
+        assert_eq!(wal.remove_segments_before(2).unwrap(), 1);
+        assert_eq!(wal.segment_count(), 1);
+        let spans = wal.replay().unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].span_id.as_str(), format!("{:016x}", 3));
+    }
+}
@@ -0,0 +1,73 @@
+//! Alert webhook integration tests.
+//! Run with: cargo test --features alerting --test alerting_test
+
+#![cfg(feature = "alerting")]
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use urpo_lib::core::{ServiceName, Span, SpanId, SpanStatus, TraceId};
+use urpo_lib::monitoring::alerting::{AlertCondition, AlertEngine, AlertPayload, AlertRule};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test_error_rate_alert_is_posted_to_webhook() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks/urpo"))
+        .and(header("content-type", "application/json"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // checkout fails half its requests, inventory none
+    let storage = InMemoryStorage::new(1000);
+    for (i, (service, error)) in [
+        ("checkout", true),
+        ("checkout", false),
+        ("checkout", true),
+        ("checkout", false),
+        ("inventory", false),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let span = Span::builder()
+            .trace_id(TraceId::new(format!("{:032x}", i + 1)).unwrap())
+            .span_id(SpanId::new(format!("{:016x}", i + 1)).unwrap())
+            .service_name(ServiceName::new(service.to_string()).unwrap())
+            .operation_name("POST /pay")
+            .start_time(SystemTime::now())
+            .duration(Duration::from_millis(20))
+            .status(if error {
+                SpanStatus::Error("declined".to_string())
+            } else {
+                SpanStatus::Ok
+            })
+            .build()
+            .unwrap();
+        storage.store_span(span).await.unwrap();
+    }
+    let storage: Arc<RwLock<dyn StorageBackend>> = Arc::new(RwLock::new(storage));
+
+    let rule = AlertRule {
+        condition: AlertCondition::ErrorRate { threshold: 10.0 },
+        webhook_url: format!("{}/hooks/urpo", server.uri()),
+        cooldown: Duration::from_secs(300),
+    };
+    let mut engine = AlertEngine::new(vec![rule], storage).unwrap();
+    assert_eq!(engine.evaluate_once().await.unwrap().len(), 1);
+    // Still breaching, but within the cooldown
+    assert!(engine.evaluate_once().await.unwrap().is_empty());
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let payload: AlertPayload = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(payload.service, "checkout");
+    assert!((payload.error_rate_percent - 50.0).abs() < 0.01);
+    assert_eq!(payload.condition, "error rate > 10%");
+    assert!(payload.text.contains("checkout"));
+}
@@ -0,0 +1,196 @@
+//! Browser ingestion tests: CORS preflight and gRPC-Web trace exports.
+//! Run with: cargo test --test browser_ingest_test
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use base64::Engine;
+use http_body_util::BodyExt;
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use prost::Message;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::grpc_web::{decode_data_frame, encode_frame, GRPC_WEB_TRACES_PATH};
+use urpo_lib::receiver::{http::create_http_router, OtelReceiver};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+fn new_receiver() -> (OtelReceiver, Arc<RwLock<dyn StorageBackend>>) {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let receiver = OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new()));
+    (receiver, storage)
+}
+
+fn request(count: u8) -> ExportTraceServiceRequest {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: (1..=count)
+                    .map(|i| Span {
+                        trace_id: vec![0x42; 16],
+                        span_id: vec![i; 8],
+                        name: format!("fetch-{}", i),
+                        start_time_unix_nano: now,
+                        end_time_unix_nano: now + 2_000_000,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn preflight(path: &str, origin: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri(path)
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,x-grpc-web")
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn grpc_web_call(router: Router, content_type: &str, body: Vec<u8>) -> (String, Vec<u8>) {
+    let response = router
+        .oneshot(
+            Request::post(GRPC_WEB_TRACES_PATH)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::ORIGIN, "http://localhost:3000")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (content_type, body.to_vec())
+}
+
+/// Split a gRPC-Web response into its data message and trailer text.
+fn split_response(body: &[u8]) -> (Option<ExportTraceServiceResponse>, String) {
+    let (message, rest) = if body[0] & 0x80 == 0 {
+        let message = decode_data_frame(body).unwrap();
+        (
+            Some(ExportTraceServiceResponse::decode(message).unwrap()),
+            &body[5 + message.len()..],
+        )
+    } else {
+        (None, body)
+    };
+    assert_eq!(rest[0], 0x80, "expected a trailer frame");
+    (message, String::from_utf8(rest[5..].to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_preflight_allows_any_origin_by_default() {
+    let (receiver, _) = new_receiver();
+    let response = create_http_router(Arc::new(receiver))
+        .oneshot(preflight("/v1/traces", "http://localhost:3000"))
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    assert!(headers.contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+    assert!(headers.contains_key(header::ACCESS_CONTROL_ALLOW_HEADERS));
+}
+
+#[tokio::test]
+async fn test_preflight_respects_allowed_origins() {
+    let (receiver, _) = new_receiver();
+    let router = create_http_router(Arc::new(
+        receiver.with_cors_allowed_origins(vec!["https://shop.example.com".to_string()]),
+    ));
+
+    let allowed = router
+        .clone()
+        .oneshot(preflight(GRPC_WEB_TRACES_PATH, "https://shop.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(
+        allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://shop.example.com"
+    );
+
+    let denied = router
+        .oneshot(preflight("/v1/traces", "https://evil.example.com"))
+        .await
+        .unwrap();
+    assert!(!denied
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[tokio::test]
+async fn test_grpc_web_export_stores_spans() {
+    let (receiver, storage) = new_receiver();
+    let router = create_http_router(Arc::new(receiver));
+
+    let mut body = Vec::new();
+    encode_frame(&mut body, 0, &request(3).encode_to_vec());
+    let (content_type, response) =
+        grpc_web_call(router.clone(), "application/grpc-web+proto", body).await;
+    assert_eq!(content_type, "application/grpc-web+proto");
+    let (message, trailers) = split_response(&response);
+    assert!(message.unwrap().partial_success.is_none());
+    assert!(trailers.contains("grpc-status:0\r\n"), "{}", trailers);
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 3);
+
+    // grpc-web-text: the same frames, base64 encoded
+    let mut frame = Vec::new();
+    encode_frame(&mut frame, 0, &request(5).encode_to_vec());
+    let body = base64::engine::general_purpose::STANDARD.encode(frame);
+    let (content_type, response) =
+        grpc_web_call(router, "application/grpc-web-text", body.into_bytes()).await;
+    assert_eq!(content_type, "application/grpc-web-text+proto");
+    let response = base64::engine::general_purpose::STANDARD
+        .decode(response)
+        .unwrap();
+    let (_, trailers) = split_response(&response);
+    assert!(trailers.contains("grpc-status:0\r\n"), "{}", trailers);
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_grpc_web_errors_are_reported_in_trailers() {
+    let (receiver, storage) = new_receiver();
+    let router = create_http_router(Arc::new(receiver));
+
+    let mut body = Vec::new();
+    encode_frame(&mut body, 0, b"\xff\xff not protobuf");
+    let (_, response) = grpc_web_call(router, "application/grpc-web+proto", body).await;
+    let (message, trailers) = split_response(&response);
+    assert!(message.is_none());
+    assert!(trailers.contains("grpc-status:3\r\n"), "{}", trailers);
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 0);
+
+    let (receiver, _) = new_receiver();
+    let response = create_http_router(Arc::new(receiver.with_grpc_web(false)))
+        .oneshot(
+            Request::post(GRPC_WEB_TRACES_PATH)
+                .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
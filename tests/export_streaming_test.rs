@@ -0,0 +1,124 @@
+//! Streaming export memory tests.
+//! Run with: cargo test --test export_streaming_test
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use urpo_lib::core::{ServiceName, Span, SpanId, SpanStatus, TraceId};
+use urpo_lib::export::{ExportFormat, ExportOptions, TraceExporter};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+/// System allocator that tracks live and peak allocated bytes.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Sink that only counts the bytes written to it.
+#[derive(Default)]
+struct CountingSink {
+    bytes: usize,
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `future`, returning its output and the most bytes it had allocated
+/// at once.
+async fn peak_during<F: Future>(future: F) -> (F::Output, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let output = future.await;
+    (output, PEAK.load(Ordering::Relaxed).saturating_sub(baseline))
+}
+
+const TRACES: usize = 2_000;
+const SPANS_PER_TRACE: usize = 4;
+
+async fn populated_storage() -> InMemoryStorage {
+    // Each service may hold a tenth of the capacity, one span per trace
+    let storage = InMemoryStorage::new(TRACES * 10);
+    let start = SystemTime::now();
+    for trace in 0..TRACES {
+        let trace_id = TraceId::new(format!("{:032x}", trace + 1)).unwrap();
+        for span in 0..SPANS_PER_TRACE {
+            let mut builder = Span::builder()
+                .trace_id(trace_id.clone())
+                .span_id(
+                    SpanId::new(format!("{:016x}", trace * SPANS_PER_TRACE + span + 1)).unwrap(),
+                )
+                .service_name(ServiceName::new(format!("service-{}", span)).unwrap())
+                .operation_name(format!("operation-{}", span))
+                .start_time(start + Duration::from_millis(span as u64))
+                .duration(Duration::from_millis(10))
+                .status(SpanStatus::Ok)
+                .attribute("http.route", "/api/orders/{id}");
+            if span > 0 {
+                builder = builder.parent_span_id(
+                    SpanId::new(format!("{:016x}", trace * SPANS_PER_TRACE + 1)).unwrap(),
+                );
+            }
+            storage.store_span(builder.build().unwrap()).await.unwrap();
+        }
+    }
+    storage
+}
+
+#[tokio::test]
+async fn test_streamed_export_peak_memory_is_bounded() {
+    let storage = populated_storage().await;
+    let exporter = TraceExporter::new(&storage);
+
+    // The exporter holds the selected trace list while writing
+    let (traces, selection_peak) = peak_during(storage.list_traces(None, None, None, TRACES)).await;
+    assert_eq!(traces.unwrap().len(), TRACES);
+
+    for format in [ExportFormat::Json, ExportFormat::Ndjson, ExportFormat::Csv] {
+        let options = ExportOptions {
+            format,
+            limit: Some(TRACES),
+            ..Default::default()
+        };
+        let mut sink = CountingSink::default();
+        let (exported, peak) = peak_during(exporter.write_traces(&options, &mut sink)).await;
+
+        assert_eq!(exported.unwrap(), TRACES, "{} export", format.name());
+        // Buffering the output would need at least as many bytes as were written
+        assert!(
+            peak < selection_peak + sink.bytes / 4,
+            "{} export peaked at {} bytes for {} bytes of output",
+            format.name(),
+            peak,
+            sink.bytes
+        );
+    }
+}
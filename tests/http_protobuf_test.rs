@@ -0,0 +1,238 @@
+//! OTLP/HTTP binary protobuf ingestion tests.
+//! Run with: cargo test --test http_protobuf_test
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use prost::Message;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use urpo_lib::core::{ServiceName, TraceId};
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::{http::create_http_router, OtelReceiver};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const SPAN_ID: &str = "00f067aa0ba902b7";
+
+fn setup() -> (axum::Router, Arc<RwLock<dyn StorageBackend>>) {
+    setup_with_traceparent(false)
+}
+
+fn setup_with_traceparent(apply: bool) -> (axum::Router, Arc<RwLock<dyn StorageBackend>>) {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let receiver = OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new()))
+        .with_apply_traceparent(apply);
+    (create_http_router(Arc::new(receiver)), storage)
+}
+
+fn export_request() -> ExportTraceServiceRequest {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(any_value::Value::StringValue("checkout".to_string())),
+                    }),
+                }],
+                dropped_attributes_count: 0,
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans: vec![Span {
+                    trace_id: hex::decode(TRACE_ID).unwrap(),
+                    span_id: hex::decode(SPAN_ID).unwrap(),
+                    name: "POST /pay".to_string(),
+                    kind: 2,
+                    start_time_unix_nano: now,
+                    end_time_unix_nano: now + 5_000_000,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_protobuf_body_is_decoded_and_stored() {
+    let (app, storage) = setup();
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .body(Body::from(export_request().encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-protobuf");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let decoded = ExportTraceServiceResponse::decode(body).unwrap();
+    assert!(decoded.partial_success.is_none());
+
+    let storage = storage.read().await;
+    let spans = storage
+        .get_trace_spans(&TraceId::new(TRACE_ID.to_string()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].span_id.as_str(), SPAN_ID);
+    assert_eq!(spans[0].operation_name, "POST /pay");
+    assert_eq!(spans[0].service_name, ServiceName::new("checkout".to_string()).unwrap());
+}
+
+#[tokio::test]
+async fn test_accept_header_selects_response_encoding() {
+    let (app, _storage) = setup();
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .header("accept", "application/json")
+                .body(Body::from(export_request().encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("application/json"));
+}
+
+#[tokio::test]
+async fn test_invalid_protobuf_body_is_rejected() {
+    let (app, _storage) = setup();
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .body(Body::from(vec![0xff, 0xff, 0xff]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_traceparent_header_sets_trace_and_parent() {
+    let (app, storage) = setup_with_traceparent(true);
+    let caller_trace = "0af7651916cd43dd8448eb211c80319c";
+    let caller_span = "b7ad6b7169203331";
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .header("traceparent", format!("00-{}-{}-01", caller_trace, caller_span))
+                .header("tracestate", "congo=t61rcWkgMzE")
+                .body(Body::from(export_request().encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let storage = storage.read().await;
+    let spans = storage
+        .get_trace_spans(&TraceId::new(caller_trace.to_string()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].span_id.as_str(), SPAN_ID);
+    assert_eq!(spans[0].parent_span_id.as_ref().unwrap().as_str(), caller_span);
+}
+
+#[tokio::test]
+async fn test_traceparent_header_keeps_batch_traces_by_default() {
+    let (app, storage) = setup();
+    let traces = ["4bf92f3577b34da6a3ce929d0e0e4736", "5ce03f4688c45eb7b4dfa30e1f1f5847"];
+    let mut request = export_request();
+    let template = request.resource_spans[0].scope_spans[0].spans[0].clone();
+    request.resource_spans[0].scope_spans[0].spans = traces
+        .iter()
+        .zip(["00f067aa0ba902b7", "11a178bb1cb013c8"])
+        .map(|(trace, span)| Span {
+            trace_id: hex::decode(trace).unwrap(),
+            span_id: hex::decode(span).unwrap(),
+            ..template.clone()
+        })
+        .collect();
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .header("traceparent", "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+                .body(Body::from(request.encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // An instrumented exporter's traceparent must not merge the batch's traces
+    let storage = storage.read().await;
+    for trace in traces {
+        let spans = storage
+            .get_trace_spans(&TraceId::new(trace.to_string()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(spans.len(), 1);
+        assert!(spans[0].parent_span_id.is_none());
+    }
+    let caller_trace = TraceId::new("0af7651916cd43dd8448eb211c80319c".to_string()).unwrap();
+    assert!(storage
+        .get_trace_spans(&caller_trace)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_malformed_traceparent_is_ignored() {
+    use axum::http::HeaderMap;
+    use urpo_lib::receiver::http::parse_w3c_trace_context;
+
+    let parse = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("traceparent", value.parse().unwrap());
+        parse_w3c_trace_context(&headers)
+    };
+    let (trace_id, span_id) =
+        parse("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01").unwrap();
+    assert_eq!(trace_id.as_str(), "0af7651916cd43dd8448eb211c80319c");
+    assert_eq!(span_id.as_str(), "b7ad6b7169203331");
+
+    assert!(parse("00-00000000000000000000000000000000-b7ad6b7169203331-01").is_none());
+    assert!(parse("00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01").is_none());
+    assert!(parse("ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01").is_none());
+    assert!(parse("00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01").is_none());
+    assert!(parse("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra").is_none());
+    assert!(parse("0af7651916cd43dd8448eb211c80319c").is_none());
+    assert!(parse_w3c_trace_context(&HeaderMap::new()).is_none());
+}
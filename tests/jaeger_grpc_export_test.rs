@@ -0,0 +1,247 @@
+//! Jaeger gRPC remote storage export tests against a mock SpanWriterPlugin.
+//! Run with: cargo test --test jaeger_grpc_export_test
+
+use axum::body::Body;
+use axum::http::Request;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, ResourceSpans, ScopeSpans};
+use prost::Message;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tonic::body::BoxBody;
+use tonic::codegen::http;
+use tower::ServiceExt;
+use urpo_lib::core::{ServiceName, Span, SpanId, SpanStatus, TraceId};
+use urpo_lib::export::jaeger_grpc::{proto, JaegerGrpcExporter};
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::{http::create_http_router, OtelReceiver};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+/// Minimal `jaeger.storage.v1.SpanWriterPlugin` recording written spans.
+#[derive(Clone, Default)]
+struct MockSpanWriter {
+    spans: Arc<Mutex<Vec<proto::Span>>>,
+}
+
+impl tonic::server::NamedService for MockSpanWriter {
+    const NAME: &'static str = "jaeger.storage.v1.SpanWriterPlugin";
+}
+
+impl tower::Service<http::Request<BoxBody>> for MockSpanWriter {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let spans = Arc::clone(&self.spans);
+        Box::pin(async move {
+            assert_eq!(req.uri().path(), "/jaeger.storage.v1.SpanWriterPlugin/WriteSpan");
+            let write_span =
+                tower::service_fn(move |req: tonic::Request<proto::WriteSpanRequest>| {
+                    let spans = Arc::clone(&spans);
+                    async move {
+                        spans.lock().unwrap().extend(req.into_inner().span);
+                        Ok::<_, tonic::Status>(tonic::Response::new(proto::WriteSpanResponse {}))
+                    }
+                });
+            let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::<
+                proto::WriteSpanResponse,
+                proto::WriteSpanRequest,
+            >::default());
+            Ok(grpc.unary(write_span, req).await)
+        })
+    }
+}
+
+async fn start_mock() -> (String, Arc<Mutex<Vec<proto::Span>>>) {
+    let writer = MockSpanWriter::default();
+    let spans = Arc::clone(&writer.spans);
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    tokio::spawn(async move {
+        let _ = tonic::transport::Server::builder()
+            .add_service(writer)
+            .serve(addr)
+            .await;
+    });
+
+    for _ in 0..50 {
+        if tokio::net::TcpStream::connect(addr).await.is_ok() {
+            return (addr.to_string(), spans);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("mock Jaeger did not start");
+}
+
+async fn wait_for_spans(spans: &Mutex<Vec<proto::Span>>, count: usize) -> Vec<proto::Span> {
+    for _ in 0..100 {
+        let written = spans.lock().unwrap().clone();
+        if written.len() >= count {
+            return written;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("mock Jaeger received {} spans, expected {}", spans.lock().unwrap().len(), count);
+}
+
+fn tag<'a>(tags: &'a [proto::KeyValue], key: &str) -> &'a proto::KeyValue {
+    tags.iter()
+        .find(|t| t.key == key)
+        .unwrap_or_else(|| panic!("missing tag {}", key))
+}
+
+#[tokio::test]
+async fn test_write_span_sends_jaeger_model() {
+    let (endpoint, spans) = start_mock().await;
+    let exporter = JaegerGrpcExporter::connect_lazy(&endpoint).unwrap();
+    assert_eq!(exporter.endpoint(), format!("http://{}", endpoint));
+
+    let start = UNIX_EPOCH + Duration::new(1_700_000_000, 250);
+    let span = Span::builder()
+        .trace_id(TraceId::new("0af7651916cd43dd8448eb211c80319c".to_string()).unwrap())
+        .span_id(SpanId::new("b7ad6b7169203331".to_string()).unwrap())
+        .parent_span_id(SpanId::new("00f067aa0ba902b7".to_string()).unwrap())
+        .service_name(ServiceName::new("payments".to_string()).unwrap())
+        .operation_name("POST /charge")
+        .start_time(start)
+        .duration(Duration::from_millis(1_250))
+        .status(SpanStatus::Error("card declined".to_string()))
+        .attribute("http.status_code", "402")
+        .resource_attribute("host.name", "pay-1")
+        .build()
+        .unwrap();
+
+    exporter.write_span(&span).await.unwrap();
+
+    let written = wait_for_spans(&spans, 1).await;
+    let jaeger = &written[0];
+    assert_eq!(hex::encode(&jaeger.trace_id), "0af7651916cd43dd8448eb211c80319c");
+    assert_eq!(hex::encode(&jaeger.span_id), "b7ad6b7169203331");
+    assert_eq!(jaeger.references.len(), 1);
+    assert_eq!(hex::encode(&jaeger.references[0].span_id), "00f067aa0ba902b7");
+    assert_eq!(jaeger.references[0].trace_id, jaeger.trace_id);
+    assert_eq!(jaeger.references[0].ref_type, proto::SpanRefType::ChildOf as i32);
+    assert_eq!(jaeger.operation_name, "POST /charge");
+    assert_eq!(
+        jaeger.start_time,
+        Some(proto::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 250
+        })
+    );
+    assert_eq!(
+        jaeger.duration,
+        Some(proto::Duration {
+            seconds: 1,
+            nanos: 250_000_000
+        })
+    );
+
+    assert_eq!(tag(&jaeger.tags, "http.status_code").v_str, "402");
+    let error = tag(&jaeger.tags, "error");
+    assert_eq!(error.v_type, proto::ValueType::Bool as i32);
+    assert!(error.v_bool);
+    assert_eq!(tag(&jaeger.tags, "otel.status_description").v_str, "card declined");
+
+    let process = jaeger.process.as_ref().unwrap();
+    assert_eq!(process.service_name, "payments");
+    assert_eq!(tag(&process.tags, "host.name").v_str, "pay-1");
+}
+
+#[tokio::test]
+async fn test_write_span_fails_when_endpoint_is_down() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let exporter = JaegerGrpcExporter::connect_lazy(&addr.to_string()).unwrap();
+    let span = Span::builder()
+        .trace_id(TraceId::new(format!("{:032x}", 1)).unwrap())
+        .span_id(SpanId::new(format!("{:016x}", 1)).unwrap())
+        .service_name(ServiceName::new("svc".to_string()).unwrap())
+        .operation_name("op")
+        .start_time(SystemTime::now())
+        .build()
+        .unwrap();
+    assert!(exporter.write_span(&span).await.is_err());
+    assert!(JaegerGrpcExporter::connect_lazy("http://bad host").is_err());
+}
+
+#[tokio::test]
+async fn test_receiver_streams_stored_spans_to_jaeger() {
+    let (endpoint, spans) = start_mock().await;
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let receiver = Arc::new(
+        OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new()))
+            .with_jaeger_export(&endpoint)
+            .unwrap(),
+    );
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let request = ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(any_value::Value::StringValue("frontend".to_string())),
+                    }),
+                }],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans: (1..=2u8)
+                    .map(|i| opentelemetry_proto::tonic::trace::v1::Span {
+                        trace_id: vec![0xab; 16],
+                        span_id: vec![i; 8],
+                        name: format!("op-{}", i),
+                        kind: SpanKind::Server as i32,
+                        start_time_unix_nano: now,
+                        end_time_unix_nano: now + 1_000_000,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let response = create_http_router(receiver)
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .body(Body::from(request.encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 2);
+
+    let mut written = wait_for_spans(&spans, 2).await;
+    written.sort_by(|a, b| a.operation_name.cmp(&b.operation_name));
+    assert_eq!(written[0].operation_name, "op-1");
+    assert_eq!(written[0].trace_id, vec![0xab; 16]);
+    assert_eq!(written[1].span_id, vec![2; 8]);
+    assert_eq!(written[0].process.as_ref().unwrap().service_name, "frontend");
+    assert_eq!(tag(&written[0].tags, "span.kind").v_str, "server");
+}
@@ -0,0 +1,228 @@
+//! OTLP/HTTP log and metric ingestion tests.
+//! Run with: cargo test --test otlp_http_signals_test
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    ExportLogsServiceRequest, ExportLogsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric::Data, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use urpo_lib::core::TraceId;
+use urpo_lib::logs::LogSeverity;
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::{http::create_http_router, OtelReceiver};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const SPAN_ID: &str = "00f067aa0ba902b7";
+
+fn receiver() -> OtelReceiver {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    OtelReceiver::new(0, 0, storage, Arc::new(Monitor::new())).with_metrics(1024, 100)
+}
+
+fn resource(service: &str) -> Option<Resource> {
+    Some(Resource {
+        attributes: vec![KeyValue {
+            key: "service.name".to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(service.to_string())),
+            }),
+        }],
+        dropped_attributes_count: 0,
+    })
+}
+
+async fn post(
+    app: axum::Router,
+    path: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .oneshot(
+            Request::post(path)
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_json_logs_are_stored() {
+    let receiver = Arc::new(receiver().with_logs(1000));
+    let logs = Arc::clone(receiver.logs_storage().unwrap());
+    let app = create_http_router(receiver);
+
+    let body = serde_json::json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [{"key": "service.name", "value": {"stringValue": "checkout"}}]
+            },
+            "scopeLogs": [{
+                "logRecords": [{
+                    "timeUnixNano": "1700000000000000000",
+                    "severityNumber": 17,
+                    "severityText": "ERROR",
+                    "body": {"stringValue": "card declined"},
+                    "traceId": TRACE_ID,
+                    "spanId": SPAN_ID,
+                    "attributes": [{"key": "payment.provider", "value": {"stringValue": "acme"}}]
+                }]
+            }]
+        }]
+    });
+    let (status, body) =
+        post(app, "/v1/logs", "application/json", serde_json::to_vec(&body).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(response["partialSuccess"].is_null());
+
+    let logs = logs.lock().await;
+    let trace_id = TraceId::new(TRACE_ID.to_string()).unwrap();
+    let found = logs.get_logs_by_trace(&trace_id).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].severity, LogSeverity::Error);
+    assert_eq!(found[0].body, "card declined");
+    assert_eq!(found[0].timestamp, 1_700_000_000_000_000_000);
+    assert_eq!(found[0].span_id.as_ref().map(|s| s.as_str()), Some(SPAN_ID));
+    let attributes = found[0].attributes.as_ref().unwrap();
+    assert_eq!(attributes.get("payment.provider").map(String::as_str), Some("acme"));
+    assert_eq!(logs.search_logs("declined", 10).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_protobuf_logs_get_protobuf_response() {
+    let receiver = Arc::new(receiver().with_logs(1000));
+    let logs = Arc::clone(receiver.logs_storage().unwrap());
+    let app = create_http_router(receiver);
+
+    let request = ExportLogsServiceRequest {
+        resource_logs: vec![ResourceLogs {
+            resource: resource("inventory"),
+            scope_logs: vec![ScopeLogs {
+                log_records: vec![LogRecord {
+                    time_unix_nano: 1_700_000_000_000_000_000,
+                    severity_number: SeverityNumber::Warn as i32,
+                    body: Some(AnyValue {
+                        value: Some(any_value::Value::StringValue("stock low".to_string())),
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+    let (status, body) =
+        post(app, "/v1/logs", "application/x-protobuf", request.encode_to_vec()).await;
+    assert_eq!(status, StatusCode::OK);
+    let response = ExportLogsServiceResponse::decode(body.as_slice()).unwrap();
+    assert!(response.partial_success.is_none());
+
+    let logs = logs.lock().await;
+    let warnings = logs.filter_by_severity(LogSeverity::Warn, 10);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].body, "stock low");
+}
+
+#[tokio::test]
+async fn test_metrics_are_stored_from_json_and_protobuf() {
+    let receiver = Arc::new(receiver());
+    let metrics = Arc::clone(receiver.metrics_storage().unwrap());
+    let app = create_http_router(receiver);
+
+    let body = serde_json::json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [{"key": "service.name", "value": {"stringValue": "checkout"}}]
+            },
+            "scopeMetrics": [{
+                "metrics": [{
+                    "name": "http.server.duration",
+                    "gauge": {"dataPoints": [{"timeUnixNano": "1700000000000000000", "asDouble": 12.5}]}
+                }]
+            }]
+        }]
+    });
+    let (status, body) = post(
+        app.clone(),
+        "/v1/metrics",
+        "application/json",
+        serde_json::to_vec(&body).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(response["partialSuccess"].is_null());
+
+    let request = ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: resource("inventory"),
+            scope_metrics: vec![ScopeMetrics {
+                metrics: vec![Metric {
+                    name: "queue.depth".to_string(),
+                    data: Some(Data::Gauge(Gauge {
+                        data_points: vec![NumberDataPoint {
+                            value: Some(number_data_point::Value::AsInt(7)),
+                            ..Default::default()
+                        }],
+                    })),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+    let (status, body) =
+        post(app, "/v1/metrics", "application/x-protobuf", request.encode_to_vec()).await;
+    assert_eq!(status, StatusCode::OK);
+    let response = ExportMetricsServiceResponse::decode(body.as_slice()).unwrap();
+    assert!(response.partial_success.is_none());
+
+    let metrics = metrics.lock().await;
+    let mut services: Vec<String> = metrics
+        .list_services()
+        .into_iter()
+        .filter_map(|id| metrics.get_service_health(id))
+        .map(|health| health.service_name)
+        .collect();
+    services.sort();
+    assert_eq!(services, vec!["checkout", "inventory"]);
+}
+
+#[tokio::test]
+async fn test_logs_not_enabled_is_not_found() {
+    let app = create_http_router(Arc::new(receiver()));
+    let (status, _) = post(app, "/v1/logs", "application/json", b"{}".to_vec()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_malformed_logs_are_rejected() {
+    let app = create_http_router(Arc::new(receiver().with_logs(1000)));
+    let (status, _) =
+        post(app.clone(), "/v1/logs", "application/json", b"{\"resourceLogs\": 1}".to_vec()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(app, "/v1/logs", "application/x-protobuf", vec![0xff; 8]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,106 @@
+//! OTLP log ingestion over the gRPC receiver.
+//! Run with: cargo test --test otlp_logs_test
+
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    logs_service_client::LogsServiceClient, ExportLogsServiceRequest,
+};
+use opentelemetry_proto::tonic::common::v1::{any_value::Value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use urpo_lib::core::{SpanId, TraceId};
+use urpo_lib::logs::LogSeverity;
+use urpo_lib::metrics::string_pool::StringId;
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::OtelReceiver;
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn string_value(s: &str) -> Option<AnyValue> {
+    Some(AnyValue {
+        value: Some(Value::StringValue(s.to_string())),
+    })
+}
+
+#[tokio::test]
+async fn test_error_log_is_stored() {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let grpc_port = free_port();
+    let receiver = Arc::new(
+        OtelReceiver::new(grpc_port, free_port(), storage, Arc::new(Monitor::new()))
+            .with_logs(1000),
+    );
+    let logs = Arc::clone(receiver.logs_storage().unwrap());
+    let handle = Arc::clone(&receiver).spawn();
+
+    let mut client = None;
+    for _ in 0..50 {
+        if let Ok(c) = LogsServiceClient::connect(format!("http://127.0.0.1:{}", grpc_port)).await {
+            client = Some(c);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let mut client = client.expect("gRPC receiver did not start");
+
+    let request = ExportLogsServiceRequest {
+        resource_logs: vec![ResourceLogs {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: string_value("checkout"),
+                }],
+                dropped_attributes_count: 0,
+            }),
+            scope_logs: vec![ScopeLogs {
+                log_records: vec![LogRecord {
+                    time_unix_nano: 1_700_000_000_000_000_000,
+                    severity_number: SeverityNumber::Error as i32,
+                    severity_text: "ERROR".to_string(),
+                    body: string_value("payment declined"),
+                    trace_id: vec![0xab; 16],
+                    span_id: vec![0x01; 8],
+                    attributes: vec![KeyValue {
+                        key: "payment.provider".to_string(),
+                        value: string_value("acme"),
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+    let response = client.export(request).await.unwrap().into_inner();
+    assert!(response.partial_success.is_none());
+
+    {
+        let logs = logs.lock().await;
+        let errors = logs.filter_by_severity(LogSeverity::Error, 10);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].body, "payment declined");
+        assert_eq!(errors[0].timestamp, 1_700_000_000_000_000_000);
+        let service = logs.string_pool().get(StringId(errors[0].service_id));
+        assert_eq!(service.as_deref(), Some("checkout"));
+
+        let trace_id = TraceId::new("ab".repeat(16)).unwrap();
+        let correlated = logs.get_logs_by_trace(&trace_id).unwrap();
+        assert_eq!(correlated.len(), 1);
+        assert_eq!(correlated[0].span_id, Some(SpanId::new("01".repeat(8)).unwrap()));
+        let attributes = correlated[0].attributes.as_ref().unwrap();
+        assert_eq!(attributes.get("payment.provider").map(String::as_str), Some("acme"));
+
+        let found = logs.search_logs("declined", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].trace_id, Some(trace_id));
+    }
+
+    handle.shutdown().await.unwrap();
+}
@@ -0,0 +1,120 @@
+//! Persistent storage backend tests.
+//! Run with: cargo test --test persistent_storage_test
+
+use std::time::{Duration, SystemTime};
+use urpo_lib::core::{
+    ConfigBuilder, ServiceName, Span, SpanId, SpanStatus, StorageBackendKind, TraceId,
+};
+use urpo_lib::storage::{backend_from_config, PersistentStorage, StorageBackend};
+
+fn span(trace: u64, id: u64, parent: Option<u64>, service: &str, error: bool) -> Span {
+    let mut builder = Span::builder()
+        .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+        .span_id(SpanId::new(format!("{:016x}", id)).unwrap())
+        .service_name(ServiceName::new(service.to_string()).unwrap())
+        .operation_name(format!("op-{}", id))
+        .start_time(SystemTime::now())
+        .duration(Duration::from_millis(10 * id))
+        .status(if error {
+            SpanStatus::Error("boom".to_string())
+        } else {
+            SpanStatus::Ok
+        });
+    if let Some(parent) = parent {
+        builder = builder.parent_span_id(SpanId::new(format!("{:016x}", parent)).unwrap());
+    }
+    builder.build().unwrap()
+}
+
+#[tokio::test]
+async fn test_traces_survive_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+
+    {
+        let storage = PersistentStorage::open(dir.path()).unwrap();
+        storage
+            .store_span(span(1, 1, None, "frontend", false))
+            .await
+            .unwrap();
+        storage
+            .store_span(span(1, 2, Some(1), "checkout", false))
+            .await
+            .unwrap();
+        storage
+            .store_span(span(2, 3, None, "frontend", false))
+            .await
+            .unwrap();
+        storage
+            .store_span(span(2, 4, Some(3), "payments", true))
+            .await
+            .unwrap();
+        // Dropping flushes buffered spans
+    }
+
+    let storage = PersistentStorage::open(dir.path()).unwrap();
+    assert_eq!(storage.get_span_count().await.unwrap(), 4);
+
+    let trace_1 = TraceId::new(format!("{:032x}", 1)).unwrap();
+    let spans = storage.get_trace_spans(&trace_1).await.unwrap();
+    assert_eq!(spans.len(), 2);
+    assert!(spans.iter().any(|s| s.service_name.as_str() == "checkout"));
+
+    let recent = storage.list_recent_traces(10, None).await.unwrap();
+    assert_eq!(recent.len(), 2);
+    assert!(recent.iter().all(|t| t.root_service.as_str() == "frontend"));
+
+    let errors = storage.get_error_traces(10).await.unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].trace_id.as_str(), format!("{:032x}", 2));
+
+    let payments = ServiceName::new("payments".to_string()).unwrap();
+    let by_service = storage
+        .list_recent_traces(10, Some(&payments))
+        .await
+        .unwrap();
+    assert_eq!(by_service.len(), 1);
+
+    let mut services = storage.list_services().await.unwrap();
+    services.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(services.len(), 3);
+
+    let span_id = SpanId::new(format!("{:016x}", 4)).unwrap();
+    assert!(storage
+        .get_span(&span_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .is_error());
+}
+
+#[tokio::test]
+async fn test_persistent_backend_selected_by_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = ConfigBuilder::new()
+        .storage_backend(StorageBackendKind::Persistent)
+        .data_dir(dir.path().to_path_buf())
+        .build()
+        .unwrap();
+
+    let storage = backend_from_config(&config).unwrap();
+    storage
+        .read()
+        .await
+        .store_span(span(7, 1, None, "frontend", false))
+        .await
+        .unwrap();
+    let persistent = storage.read().await;
+    persistent
+        .as_any()
+        .downcast_ref::<PersistentStorage>()
+        .expect("persistent backend")
+        .flush()
+        .unwrap();
+    assert!(dir
+        .path()
+        .join("spans")
+        .read_dir()
+        .unwrap()
+        .next()
+        .is_some());
+}
@@ -0,0 +1,478 @@
+//! OTLP receiver request limit and rejection tests.
+//! Run with: cargo test --test receiver_limits_test
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    trace_service_client::TraceServiceClient, ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use prost::Message;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use urpo_lib::core::{
+    RateLimit, RateLimitConfig, RateLimitKey, RateLimits, Result, ServiceMetrics, ServiceName,
+    Span as UrpoSpan, SpanId, TraceId, UrpoError,
+};
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::{
+    http::create_http_router, rate_limit::RateLimiter, OtelReceiver, ReceiverConfig,
+    DEFAULT_MAX_REQUEST_BYTES, STORAGE_FULL_RETRY_DELAY,
+};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend, StorageHealth, StorageStats, TraceInfo};
+
+fn receiver(max_request_bytes: usize) -> Arc<OtelReceiver> {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    Arc::new(
+        OtelReceiver::new(0, 0, storage, Arc::new(Monitor::new()))
+            .with_max_request_bytes(max_request_bytes),
+    )
+}
+
+async fn connect(receiver: Arc<OtelReceiver>) -> TraceServiceClient<tonic::transport::Channel> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    tokio::spawn(async move {
+        let _ = receiver.start_grpc(addr).await;
+    });
+
+    for _ in 0..50 {
+        if let Ok(client) = TraceServiceClient::connect(format!("http://{}", addr)).await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("gRPC receiver did not start");
+}
+
+fn oversized_request(bytes: usize) -> ExportTraceServiceRequest {
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: vec![Span {
+                    name: "x".repeat(bytes),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_http_rejects_oversized_body_with_413() {
+    let app = create_http_router(receiver(1024));
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/json")
+                .body(Body::from(vec![b' '; 4096]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_grpc_rejects_oversized_message_with_resource_exhausted() {
+    let mut client = connect(receiver(1024)).await;
+
+    let status = client
+        .export(oversized_request(8 * 1024))
+        .await
+        .expect_err("oversized request must be rejected");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn test_grpc_accepts_message_above_default_limit_when_raised() {
+    let mut client = connect(receiver(2 * DEFAULT_MAX_REQUEST_BYTES)).await;
+
+    client
+        .export(oversized_request(DEFAULT_MAX_REQUEST_BYTES + 1024 * 1024))
+        .await
+        .expect("request under the configured limit must be accepted");
+}
+
+#[tokio::test]
+async fn test_grpc_ten_megabyte_batch_depends_on_configured_limit() {
+    const BATCH_BYTES: usize = 10 * 1024 * 1024;
+
+    let mut client = connect(receiver(DEFAULT_MAX_REQUEST_BYTES)).await;
+    let status = client
+        .export(oversized_request(BATCH_BYTES))
+        .await
+        .expect_err("10MB batch must exceed the default limit");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let config = ReceiverConfig {
+        max_request_bytes: 16 * 1024 * 1024,
+        max_concurrent_streams: Some(8),
+        request_timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let raised =
+        Arc::new(OtelReceiver::with_config(0, 0, storage, Arc::new(Monitor::new()), config));
+    let mut client = connect(raised).await;
+    client
+        .export(oversized_request(BATCH_BYTES))
+        .await
+        .expect("10MB batch must be accepted under a 16MB limit");
+}
+
+/// Storage that accepts a fixed number of spans and then reports itself full.
+struct FullStorage {
+    inner: InMemoryStorage,
+    capacity: usize,
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for FullStorage {
+    async fn store_span(&self, span: UrpoSpan) -> Result<()> {
+        if self.inner.get_span_count().await? >= self.capacity {
+            return Err(UrpoError::StorageFull { rejected: 1 });
+        }
+        self.inner.store_span(span).await
+    }
+
+    async fn get_span(&self, span_id: &SpanId) -> Result<Option<UrpoSpan>> {
+        self.inner.get_span(span_id).await
+    }
+
+    async fn visit_trace_spans(
+        &self,
+        trace_id: &TraceId,
+        visit: &mut (dyn for<'s> FnMut(&'s UrpoSpan) -> bool + Send),
+    ) -> Result<()> {
+        self.inner.visit_trace_spans(trace_id, visit).await
+    }
+
+    async fn get_service_spans(
+        &self,
+        service: &ServiceName,
+        since: SystemTime,
+    ) -> Result<Vec<UrpoSpan>> {
+        self.inner.get_service_spans(service, since).await
+    }
+
+    async fn get_service_metrics(&self) -> Result<Vec<ServiceMetrics>> {
+        self.inner.get_service_metrics().await
+    }
+
+    async fn get_span_count(&self) -> Result<usize> {
+        self.inner.get_span_count().await
+    }
+
+    async fn enforce_limits(&self) -> Result<usize> {
+        self.inner.enforce_limits().await
+    }
+
+    async fn list_services(&self) -> Result<Vec<ServiceName>> {
+        self.inner.list_services().await
+    }
+
+    async fn get_storage_stats(&self) -> Result<StorageStats> {
+        self.inner.get_storage_stats().await
+    }
+
+    async fn emergency_cleanup(&self) -> Result<usize> {
+        self.inner.emergency_cleanup().await
+    }
+
+    fn record_rejected_spans(&self, count: usize) {
+        self.inner.record_rejected_spans(count);
+    }
+
+    fn get_health(&self) -> StorageHealth {
+        self.inner.get_health()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn list_recent_traces(
+        &self,
+        limit: usize,
+        service_filter: Option<&ServiceName>,
+    ) -> Result<Vec<TraceInfo>> {
+        self.inner.list_recent_traces(limit, service_filter).await
+    }
+
+    async fn search_traces(&self, query: &str, limit: usize) -> Result<Vec<TraceInfo>> {
+        self.inner.search_traces(query, limit).await
+    }
+
+    async fn get_error_traces(&self, limit: usize) -> Result<Vec<TraceInfo>> {
+        self.inner.get_error_traces(limit).await
+    }
+
+    async fn get_slow_traces(&self, threshold: Duration, limit: usize) -> Result<Vec<TraceInfo>> {
+        self.inner.get_slow_traces(threshold, limit).await
+    }
+
+    async fn list_traces(
+        &self,
+        service: Option<&str>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<TraceInfo>> {
+        self.inner
+            .list_traces(service, start_time, end_time, limit)
+            .await
+    }
+
+    async fn get_service_metrics_map(&self) -> Result<HashMap<ServiceName, ServiceMetrics>> {
+        self.inner.get_service_metrics_map().await
+    }
+
+    async fn search_spans(
+        &self,
+        query: &str,
+        service: Option<&str>,
+        attribute_key: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<UrpoSpan>> {
+        self.inner
+            .search_spans(query, service, attribute_key, limit)
+            .await
+    }
+
+    async fn get_stats(&self) -> Result<StorageStats> {
+        self.inner.get_stats().await
+    }
+}
+
+fn batch_request(count: u8) -> ExportTraceServiceRequest {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: (1..=count)
+                    .map(|i| Span {
+                        trace_id: vec![0xab; 16],
+                        span_id: vec![i; 8],
+                        name: format!("op-{}", i),
+                        start_time_unix_nano: now,
+                        end_time_unix_nano: now + 1_000_000,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_full_storage_returns_resource_exhausted_with_partial_success() {
+    let storage: Arc<RwLock<dyn StorageBackend>> = Arc::new(RwLock::new(FullStorage {
+        inner: InMemoryStorage::new(1000),
+        capacity: 3,
+    }));
+    let receiver =
+        Arc::new(OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new())));
+    let mut client = connect(receiver).await;
+
+    let status = client
+        .export(batch_request(5))
+        .await
+        .expect_err("export into full storage must fail");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    let response = ExportTraceServiceResponse::decode(status.details()).unwrap();
+    let partial_success = response
+        .partial_success
+        .expect("partial_success must be set");
+    assert_eq!(partial_success.rejected_spans, 2);
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 3);
+
+    let retry_ms = status
+        .metadata()
+        .get("grpc-retry-pushback-ms")
+        .expect("retry delay hint must be set");
+    assert_eq!(retry_ms.to_str().unwrap(), STORAGE_FULL_RETRY_DELAY.as_millis().to_string());
+    let stats = storage.read().await.get_storage_stats().await.unwrap();
+    assert_eq!(stats.rejected_spans, 2);
+}
+
+#[tokio::test]
+async fn test_http_full_storage_returns_429_with_retry_after() {
+    let storage: Arc<RwLock<dyn StorageBackend>> = Arc::new(RwLock::new(FullStorage {
+        inner: InMemoryStorage::new(1000),
+        capacity: 1,
+    }));
+    let receiver =
+        Arc::new(OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new())));
+    let app = create_http_router(receiver);
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .body(Body::from(batch_request(4).encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        response.headers()["retry-after"],
+        STORAGE_FULL_RETRY_DELAY.as_secs().to_string()
+    );
+
+    let stats = storage.read().await.get_storage_stats().await.unwrap();
+    assert_eq!(stats.rejected_spans, 3);
+}
+
+fn batch_with_invalid_span() -> ExportTraceServiceRequest {
+    let mut request = batch_request(3);
+    request.resource_spans[0].scope_spans[0].spans[1].trace_id = vec![0; 16];
+    request
+}
+
+#[tokio::test]
+async fn test_grpc_reports_rejected_spans_in_partial_success() {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let receiver =
+        Arc::new(OtelReceiver::new(0, 0, Arc::clone(&storage), Arc::new(Monitor::new())));
+    let mut client = connect(receiver).await;
+
+    let response = client
+        .export(batch_with_invalid_span())
+        .await
+        .expect("export with one invalid span must succeed")
+        .into_inner();
+
+    let partial_success = response
+        .partial_success
+        .expect("partial_success must be set");
+    assert_eq!(partial_success.rejected_spans, 1);
+    assert!(!partial_success.error_message.is_empty());
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_http_reports_rejected_spans_in_partial_success() {
+    let app = create_http_router(receiver(4 * 1024 * 1024));
+
+    let response = app
+        .oneshot(
+            Request::post("/v1/traces")
+                .header("content-type", "application/x-protobuf")
+                .body(Body::from(batch_with_invalid_span().encode_to_vec()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let response = ExportTraceServiceResponse::decode(body).unwrap();
+    assert_eq!(response.partial_success.unwrap().rejected_spans, 1);
+}
+
+fn rate_limited_receiver(key: RateLimitKey, limit: RateLimit) -> Arc<OtelReceiver> {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        key,
+        limits: RateLimits {
+            default: Some(limit),
+            ..Default::default()
+        },
+    });
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    Arc::new(
+        OtelReceiver::new(0, 0, storage, Arc::new(Monitor::new()))
+            .with_rate_limiter(Arc::new(limiter)),
+    )
+}
+
+#[tokio::test]
+async fn test_grpc_client_over_rate_limit_gets_resource_exhausted() {
+    let receiver = rate_limited_receiver(
+        RateLimitKey::PeerIp,
+        RateLimit {
+            spans_per_second: 1.0,
+            burst: Some(5.0),
+        },
+    );
+    let mut client = connect(Arc::clone(&receiver)).await;
+
+    client
+        .export(batch_request(5))
+        .await
+        .expect("the first batch fits the burst");
+    let status = client
+        .export(batch_request(5))
+        .await
+        .expect_err("the bucket is empty");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    let response = ExportTraceServiceResponse::decode(status.details()).unwrap();
+    assert_eq!(response.partial_success.unwrap().rejected_spans, 5);
+
+    // About five seconds until five tokens are back
+    let retry_ms: u64 = status
+        .metadata()
+        .get("grpc-retry-pushback-ms")
+        .expect("retry delay hint must be set")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((4000..=5000).contains(&retry_ms), "retry after {}ms", retry_ms);
+
+    assert_eq!(receiver.stats().rate_limited_spans, 5);
+    let stats = receiver.rate_limiter().unwrap().stats();
+    assert_eq!(stats.clients[0].client, "127.0.0.1");
+    assert_eq!((stats.clients[0].admitted, stats.clients[0].dropped), (5, 5));
+}
+
+#[tokio::test]
+async fn test_http_client_over_rate_limit_gets_429_with_retry_after() {
+    let receiver = rate_limited_receiver(
+        RateLimitKey::ResourceAttribute("service.name".to_string()),
+        RateLimit {
+            spans_per_second: 0.5,
+            burst: Some(2.0),
+        },
+    );
+    let app = create_http_router(Arc::clone(&receiver));
+    let export = || {
+        Request::post("/v1/traces")
+            .header("content-type", "application/x-protobuf")
+            .body(Body::from(batch_request(4).encode_to_vec()))
+            .unwrap()
+    };
+
+    // Larger than the burst, admitted from the full bucket
+    let response = app.clone().oneshot(export()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Four tokens in debt, so eight seconds until two are back
+    let response = app.oneshot(export()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "8");
+    assert_eq!(receiver.stats().rate_limited_spans, 4);
+}
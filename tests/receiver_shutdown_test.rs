@@ -0,0 +1,79 @@
+//! Graceful receiver shutdown tests.
+//! Run with: cargo test --test receiver_shutdown_test
+
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    trace_service_client::TraceServiceClient, ExportTraceServiceRequest,
+};
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::OtelReceiver;
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn request(count: u8) -> ExportTraceServiceRequest {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: (1..=count)
+                    .map(|i| Span {
+                        trace_id: vec![0xcd; 16],
+                        span_id: vec![i; 8],
+                        name: format!("op-{}", i),
+                        start_time_unix_nano: now,
+                        end_time_unix_nano: now + 1_000_000,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_spans_sent_before_shutdown_are_persisted() {
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let grpc_port = free_port();
+    let receiver = Arc::new(
+        OtelReceiver::new(grpc_port, free_port(), Arc::clone(&storage), Arc::new(Monitor::new()))
+            // Large batches only flush on the interval tick or on shutdown
+            .with_batch_processing(10_000),
+    );
+    let handle = receiver.spawn();
+
+    let mut client = None;
+    for _ in 0..50 {
+        if let Ok(c) = TraceServiceClient::connect(format!("http://127.0.0.1:{}", grpc_port)).await
+        {
+            client = Some(c);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let mut client = client.expect("gRPC receiver did not start");
+
+    client.export(request(5)).await.unwrap();
+    handle.shutdown().await.unwrap();
+
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 5);
+    assert!(
+        TraceServiceClient::connect(format!("http://127.0.0.1:{}", grpc_port))
+            .await
+            .is_err(),
+        "receiver must stop accepting connections after shutdown"
+    );
+}
@@ -0,0 +1,189 @@
+//! Prometheus remote-write integration tests.
+//! Run with: cargo test --features remote-write --test remote_write_test
+
+#![cfg(feature = "remote-write")]
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use urpo_lib::core::{ServiceName, Span, SpanId, SpanStatus, TraceId};
+use urpo_lib::export::remote_write::{
+    decode_request, RemoteWriteAuth, RemoteWriteClient, RemoteWriteConfig, STALE_NAN_BITS,
+};
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn make_span(trace: u32, service: &str, operation: &str, error: bool) -> Span {
+    Span::builder()
+        .trace_id(TraceId::new(format!("{:032x}", trace)).unwrap())
+        .span_id(SpanId::new(format!("{:016x}", trace)).unwrap())
+        .service_name(ServiceName::new(service.to_string()).unwrap())
+        .operation_name(operation)
+        .start_time(SystemTime::now())
+        .duration(Duration::from_millis(u64::from(trace) * 10))
+        .status(if error {
+            SpanStatus::Error("failed".to_string())
+        } else {
+            SpanStatus::Ok
+        })
+        .build()
+        .unwrap()
+}
+
+async fn storage_with_spans() -> Arc<RwLock<dyn StorageBackend>> {
+    let storage = InMemoryStorage::new(1000);
+    storage
+        .store_span(make_span(1, "checkout", "POST /pay", false))
+        .await
+        .unwrap();
+    storage
+        .store_span(make_span(2, "checkout", "POST /pay", true))
+        .await
+        .unwrap();
+    storage
+        .store_span(make_span(3, "inventory", "GET /stock", false))
+        .await
+        .unwrap();
+    Arc::new(RwLock::new(storage))
+}
+
+fn label<'a>(
+    ts: &'a urpo_lib::export::remote_write::proto::TimeSeries,
+    name: &str,
+) -> Option<&'a str> {
+    ts.labels
+        .iter()
+        .find(|l| l.name == name)
+        .map(|l| l.value.as_str())
+}
+
+#[tokio::test]
+async fn test_remote_write_payload_is_valid_protobuf() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/write"))
+        .and(header("content-encoding", "snappy"))
+        .and(header("content-type", "application/x-protobuf"))
+        .and(header("x-prometheus-remote-write-version", "0.1.0"))
+        .and(header("authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = RemoteWriteConfig {
+        endpoint: format!("{}/api/v1/write", server.uri()),
+        auth: Some(RemoteWriteAuth::Bearer {
+            token: "secret".to_string(),
+        }),
+        interval: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let mut client = RemoteWriteClient::new(config, storage_with_spans().await).unwrap();
+    client.push_once().await.unwrap();
+
+    let stats = client.stats();
+    assert_eq!(stats.sent, 1);
+    assert_eq!(stats.queued, 0);
+
+    let requests = server.received_requests().await.unwrap();
+    let write = decode_request(&requests[0].body).unwrap();
+
+    let checkout_errors = write
+        .timeseries
+        .iter()
+        .find(|ts| {
+            label(ts, "__name__") == Some("urpo_service_error_rate")
+                && label(ts, "service") == Some("checkout")
+        })
+        .expect("checkout error rate series");
+    assert!((checkout_errors.samples[0].value - 0.5).abs() < f64::EPSILON);
+
+    let op_p99 = write
+        .timeseries
+        .iter()
+        .find(|ts| {
+            label(ts, "__name__") == Some("urpo_operation_latency_seconds")
+                && label(ts, "operation") == Some("GET /stock")
+                && label(ts, "quantile") == Some("0.99")
+        })
+        .expect("inventory p99 series");
+    assert!((op_p99.samples[0].value - 0.03).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_remote_write_retries_on_server_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let config = RemoteWriteConfig {
+        endpoint: server.uri(),
+        interval: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let mut client = RemoteWriteClient::new(config, storage_with_spans().await).unwrap();
+
+    client.push_once().await.unwrap();
+    assert_eq!(client.stats().queued, 1);
+    assert_eq!(client.stats().sent, 0);
+
+    // Next push delivers the queued payload and the new one
+    client.push_once().await.unwrap();
+    assert_eq!(client.stats().queued, 0);
+    assert_eq!(client.stats().sent, 2);
+}
+
+#[tokio::test]
+async fn test_remote_write_marks_vanished_series_stale() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let config = RemoteWriteConfig {
+        endpoint: server.uri(),
+        interval: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let storage = storage_with_spans().await;
+    let mut client = RemoteWriteClient::new(config, Arc::clone(&storage)).unwrap();
+    client.push_once().await.unwrap();
+
+    // inventory stops reporting before the next push
+    let inventory = TraceId::new(format!("{:032x}", 3)).unwrap();
+    storage
+        .read()
+        .await
+        .delete_traces(&[inventory])
+        .await
+        .unwrap();
+    client.push_once().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let second = decode_request(&requests[1].body).unwrap();
+    let (stale, live): (Vec<_>, Vec<_>) = second
+        .timeseries
+        .iter()
+        .partition(|ts| label(ts, "service") == Some("inventory"));
+
+    // Every inventory series is closed with the exact staleness NaN, not a value
+    assert!(!stale.is_empty());
+    for ts in &stale {
+        assert_eq!(ts.samples.len(), 1);
+        assert_eq!(ts.samples[0].value.to_bits(), STALE_NAN_BITS);
+    }
+    assert!(live
+        .iter()
+        .all(|ts| label(ts, "service") == Some("checkout") && !ts.samples[0].value.is_nan()));
+}
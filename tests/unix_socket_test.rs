@@ -0,0 +1,122 @@
+//! OTLP ingestion over Unix domain sockets.
+//! Run with: cargo test --test unix_socket_test
+#![cfg(unix)]
+
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    trace_service_client::TraceServiceClient, ExportTraceServiceRequest,
+};
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use prost::Message;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UnixStream;
+use tokio::sync::RwLock;
+use urpo_lib::monitoring::Monitor;
+use urpo_lib::receiver::OtelReceiver;
+use urpo_lib::storage::{InMemoryStorage, StorageBackend};
+
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn request(span_id: u8) -> ExportTraceServiceRequest {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: vec![Span {
+                    trace_id: vec![0xab; 16],
+                    span_id: vec![span_id; 8],
+                    name: "local-op".to_string(),
+                    start_time_unix_nano: now,
+                    end_time_unix_nano: now + 1_000_000,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+async fn wait_for_socket(path: &Path) {
+    for _ in 0..50 {
+        if path.exists() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{} was not created", path.display());
+}
+
+async fn export_grpc(path: PathBuf) {
+    // The URI is required but unused; the connector dials the socket
+    let channel = tonic::transport::Endpoint::try_from("http://localhost")
+        .unwrap()
+        .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+            let path = path.clone();
+            async move { Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?)) }
+        }))
+        .await
+        .unwrap();
+    TraceServiceClient::new(channel)
+        .export(request(1))
+        .await
+        .unwrap();
+}
+
+async fn export_http(path: &Path) -> hyper::StatusCode {
+    let stream = UnixStream::connect(path).await.unwrap();
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .unwrap();
+    tokio::spawn(connection);
+
+    let request = hyper::Request::post("/v1/traces")
+        .header("host", "localhost")
+        .header("content-type", "application/x-protobuf")
+        .body(Full::new(bytes::Bytes::from(request(2).encode_to_vec())))
+        .unwrap();
+    let response = sender.send_request(request).await.unwrap();
+    let status = response.status();
+    response.into_body().collect().await.unwrap();
+    status
+}
+
+#[tokio::test]
+async fn test_export_over_unix_sockets() {
+    let dir = tempfile::tempdir().unwrap();
+    let grpc_path = dir.path().join("otlp-grpc.sock");
+    let http_path = dir.path().join("otlp-http.sock");
+    let storage: Arc<RwLock<dyn StorageBackend>> =
+        Arc::new(RwLock::new(InMemoryStorage::new(1000)));
+    let receiver = Arc::new(
+        OtelReceiver::new(free_port(), free_port(), Arc::clone(&storage), Arc::new(Monitor::new()))
+            .with_grpc_uds_path(Some(grpc_path.clone()))
+            .with_http_uds_path(Some(http_path.clone()))
+            .with_uds_mode(0o660),
+    );
+    let handle = receiver.spawn();
+    wait_for_socket(&grpc_path).await;
+    wait_for_socket(&http_path).await;
+
+    let mode = std::fs::metadata(&grpc_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o660);
+
+    export_grpc(grpc_path.clone()).await;
+    assert_eq!(export_http(&http_path).await, hyper::StatusCode::OK);
+    handle.shutdown().await.unwrap();
+
+    assert_eq!(storage.read().await.get_span_count().await.unwrap(), 2);
+    assert!(!grpc_path.exists(), "gRPC socket file must be removed on shutdown");
+    assert!(!http_path.exists(), "HTTP socket file must be removed on shutdown");
+}